@@ -1,8 +1,12 @@
 //! Export command for sessions and database
 
 use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use colored::Colorize;
-use llm_memory_graph_types::SessionId;
+use llm_memory_graph::export::{GraphExporter, GraphFormat};
+use llm_memory_graph::query::TraversalOptions;
+use llm_memory_graph_types::{AttachmentBody, Node, NodeId, SessionId};
 use std::path::PathBuf;
 use uuid::Uuid;
 
@@ -47,10 +51,50 @@ pub async fn handle_export_session(
     let nodes = ctx.graph.get_session_nodes(&session_id).await
         .context("Failed to get session nodes")?;
 
+    // Include the custom kinds in use so the import can register them
+    let mut kind_names: Vec<&str> = nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Custom(custom) => Some(custom.kind.as_str()),
+            _ => None,
+        })
+        .collect();
+    kind_names.sort_unstable();
+    kind_names.dedup();
+    let mut node_kinds = Vec::with_capacity(kind_names.len());
+    for name in kind_names {
+        if let Some(kind) = ctx.graph.node_kind(name).await? {
+            node_kinds.push(kind);
+        }
+    }
+
+    // Include the blobs attachments refer to, base64-encoded by key
+    let mut blobs = serde_json::Map::new();
+    for node in &nodes {
+        let attachments = match node {
+            Node::Prompt(prompt) => &prompt.attachments,
+            Node::Response(response) => &response.attachments,
+            _ => continue,
+        };
+        for attachment in attachments {
+            let Some(AttachmentBody::Blob(key)) = &attachment.body else {
+                continue;
+            };
+            if blobs.contains_key(key) {
+                continue;
+            }
+            if let Some(bytes) = ctx.graph.blob(key).await? {
+                blobs.insert(key.clone(), BASE64.encode(bytes).into());
+            }
+        }
+    }
+
     // Create export data structure
     let export_data = serde_json::json!({
         "session": session,
         "nodes": nodes,
+        "node_kinds": node_kinds,
+        "blobs": blobs,
         "node_count": nodes.len(),
         "exported_at": chrono::Utc::now(),
     });
@@ -155,3 +199,90 @@ pub async fn handle_export_database(
 
     Ok(())
 }
+
+/// Graph export options
+pub struct GraphExportOptions {
+    pub session_id: Option<String>,
+    pub node_id: Option<String>,
+    pub depth: usize,
+    pub format: GraphFormat,
+    pub output: Option<PathBuf>,
+    pub edge_properties: Vec<String>,
+    pub title: Option<String>,
+}
+
+/// Handle graph export command
+pub async fn handle_export_graph(
+    ctx: &CommandContext<'_>,
+    options: GraphExportOptions,
+) -> Result<()> {
+    let traversal = ctx.graph.traversal();
+
+    let (subgraph, default_title) = match (&options.session_id, &options.node_id) {
+        (Some(session_id_str), _) => {
+            let session_id = SessionId::from(Uuid::parse_str(session_id_str)?);
+            let subgraph = traversal
+                .session_subgraph(&session_id)
+                .await
+                .context("Failed to load session graph")?;
+            (subgraph, format!("Session {}", session_id))
+        }
+        (None, Some(node_id_str)) => {
+            let node_id = NodeId::from(Uuid::parse_str(node_id_str)?);
+            let subgraph = traversal
+                .neighborhood(node_id, options.depth, &TraversalOptions::new())
+                .await
+                .context("Failed to load node neighbourhood")?;
+            (
+                subgraph,
+                format!("{} hops around {}", options.depth, node_id),
+            )
+        }
+        (None, None) => anyhow::bail!("Either --session or --node is required"),
+    };
+
+    let mut exporter = GraphExporter::new().title(options.title.unwrap_or(default_title));
+    for key in options.edge_properties {
+        exporter = exporter.edge_property(key);
+    }
+    let rendered = exporter.export(&subgraph, options.format);
+
+    let Some(output) = options.output else {
+        print!("{}", rendered);
+        return Ok(());
+    };
+    std::fs::write(&output, rendered).context("Failed to write export file")?;
+
+    match ctx.format {
+        OutputFormat::Text | OutputFormat::Table => {
+            println!(
+                "{} Graph exported to: {}",
+                "✓".green().bold(),
+                output.display().to_string().cyan()
+            );
+            println!(
+                "  {} nodes, {} edges ({})",
+                subgraph.nodes.len(),
+                subgraph.edges.len(),
+                options.format
+            );
+        }
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let result = serde_json::json!({
+                "status": "success",
+                "message": "Graph exported",
+                "output_file": output.display().to_string(),
+                "format": options.format.to_string(),
+                "node_count": subgraph.nodes.len(),
+                "edge_count": subgraph.edges.len(),
+            });
+            if matches!(ctx.format, OutputFormat::Json) {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                println!("{}", serde_yaml::to_string(&result)?);
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,13 @@
 //! Import command for data restoration
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use colored::Colorize;
+use llm_memory_graph::attachments;
+use llm_memory_graph::custom::NodeKind;
+use llm_memory_graph_types::{AttachmentBody, ConversationSession, Node};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::output::OutputFormat;
@@ -66,6 +72,12 @@ pub async fn handle_import(
                     println!("Type:       Session export");
                     println!("Nodes:      {}", nodes.len());
                 }
+                if let Some(kinds) = import_data.get("node_kinds").and_then(|k| k.as_array()) {
+                    println!("Node kinds: {}", kinds.len());
+                }
+                if let Some(blobs) = import_data.get("blobs").and_then(|b| b.as_object()) {
+                    println!("Blobs:      {}", blobs.len());
+                }
             } else if is_db_export {
                 println!("Type:       Database export");
                 if let Some(version) = import_data.get("version").and_then(|v| v.as_str()) {
@@ -97,7 +109,15 @@ pub async fn handle_import(
         }
     }
 
-    if !dry_run {
+    if is_session_export {
+        let imported = import_session(ctx, import_data, dry_run).await?;
+        if dry_run {
+            ctx.format
+                .success("Dry run completed. File format is valid.");
+        } else {
+            ctx.format.success(&format!("Imported {} nodes", imported));
+        }
+    } else if !dry_run {
         ctx.format.warning("Import functionality not yet fully implemented.");
         ctx.format.warning("Use --dry-run to validate import files.");
     } else {
@@ -106,3 +126,107 @@ pub async fn handle_import(
 
     Ok(())
 }
+
+/// Import a session export, registering the custom node kinds it uses
+///
+/// Kinds already registered under the same name are kept as they are. Custom
+/// payloads are checked against their kind, and attachment blob references
+/// against the blobs in the file or graph, before anything is written, so a
+/// dry run reports the same validation errors a real import would.
+async fn import_session(
+    ctx: &CommandContext<'_>,
+    import_data: serde_json::Value,
+    dry_run: bool,
+) -> Result<usize> {
+    let session: ConversationSession = serde_json::from_value(import_data["session"].clone())
+        .context("Invalid session in import file")?;
+    let nodes: Vec<Node> = serde_json::from_value(import_data["nodes"].clone())
+        .context("Invalid nodes in import file")?;
+    let file_kinds: Vec<NodeKind> = match import_data.get("node_kinds") {
+        Some(kinds) => {
+            serde_json::from_value(kinds.clone()).context("Invalid node kinds in import file")?
+        }
+        None => Vec::new(),
+    };
+    let file_blobs: HashMap<String, String> = match import_data.get("blobs") {
+        Some(blobs) => {
+            serde_json::from_value(blobs.clone()).context("Invalid blobs in import file")?
+        }
+        None => HashMap::new(),
+    };
+    let mut blobs = Vec::with_capacity(file_blobs.len());
+    for (key, data) in file_blobs {
+        let bytes = BASE64
+            .decode(data)
+            .with_context(|| format!("Blob '{}' is not valid base64", key))?;
+        if attachments::sha256_hex(&bytes) != key {
+            bail!("Blob '{}' does not match its content", key);
+        }
+        blobs.push((key, bytes));
+    }
+
+    let mut missing_kinds = Vec::new();
+    for kind in file_kinds {
+        if ctx.graph.node_kind(&kind.name).await?.is_none() {
+            kind.validate()?;
+            missing_kinds.push(kind);
+        }
+    }
+
+    for node in &nodes {
+        let Node::Custom(custom) = node else {
+            continue;
+        };
+        let kind = match missing_kinds.iter().find(|k| k.name == custom.kind) {
+            Some(kind) => kind.clone(),
+            None => match ctx.graph.node_kind(&custom.kind).await? {
+                Some(kind) => kind,
+                None => bail!(
+                    "Node {} has kind '{}', which is neither registered nor in the import file",
+                    custom.id,
+                    custom.kind
+                ),
+            },
+        };
+        kind.check_payload(&custom.payload)?;
+    }
+
+    for node in &nodes {
+        let attachments = match node {
+            Node::Prompt(prompt) => &prompt.attachments,
+            Node::Response(response) => &response.attachments,
+            _ => continue,
+        };
+        for attachment in attachments {
+            let Some(AttachmentBody::Blob(key)) = &attachment.body else {
+                continue;
+            };
+            if !blobs.iter().any(|(k, _)| k == key) && ctx.graph.blob(key).await?.is_none() {
+                bail!(
+                    "Node {} refers to blob '{}', which is neither stored nor in the import file",
+                    node.id(),
+                    key
+                );
+            }
+        }
+    }
+
+    if dry_run {
+        return Ok(0);
+    }
+
+    for (_, bytes) in blobs {
+        ctx.graph.store_blob(&bytes).await?;
+    }
+
+    for kind in missing_kinds {
+        ctx.graph.register_node_kind(kind).await?;
+    }
+
+    let mut batch = nodes;
+    if !batch.iter().any(|node| matches!(node, Node::Session(_))) {
+        batch.insert(0, Node::Session(session));
+    }
+    let imported = ctx.graph.store_nodes_batch(batch).await?.len();
+    Ok(imported)
+}
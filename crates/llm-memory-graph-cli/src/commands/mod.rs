@@ -11,10 +11,12 @@ pub mod import;
 pub mod memory_retrieval;
 pub mod pattern;
 pub mod query;
+pub mod search;
 pub mod server;
 pub mod session;
 pub mod stats;
 pub mod template;
+pub mod view;
 
 use llm_memory_graph::engine::AsyncMemoryGraph;
 
@@ -3,6 +3,7 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use colored::Colorize;
+use llm_memory_graph::query::aggregate::{Aggregate, AggregateRow, Aggregation, GroupBy};
 use llm_memory_graph_types::{Node, NodeType, SessionId};
 use uuid::Uuid;
 
@@ -18,6 +19,7 @@ fn get_node_created_at(node: &Node) -> DateTime<Utc> {
         Node::ToolInvocation(t) => t.timestamp,
         Node::Agent(a) => a.created_at,
         Node::Template(t) => t.created_at,
+        Node::Custom(c) => c.created_at,
     }
 }
 
@@ -30,6 +32,7 @@ fn get_node_session_id(node: &Node) -> Option<SessionId> {
         Node::ToolInvocation(_) => None, // ToolInvocation doesn't have session_id
         Node::Agent(_) => None,
         Node::Template(_) => None,
+        Node::Custom(c) => c.session_id,
     }
 }
 
@@ -53,22 +56,10 @@ pub async fn handle_query(ctx: &CommandContext<'_>, filters: QueryFilters) -> Re
     };
 
     // Parse node type if provided
-    let node_type_filter = if let Some(ref type_str) = filters.node_type {
-        Some(match type_str.to_lowercase().as_str() {
-            "prompt" => NodeType::Prompt,
-            "response" => NodeType::Response,
-            "agent" => NodeType::Agent,
-            "template" => NodeType::Template,
-            "tool" | "toolinvocation" => NodeType::ToolInvocation,
-            "session" => NodeType::Session,
-            _ => {
-                ctx.format.error(&format!("Invalid node type: {}. Use: prompt, response, agent, template, tool, session", type_str));
-                std::process::exit(1);
-            }
-        })
-    } else {
-        None
-    };
+    let node_type_filter = filters
+        .node_type
+        .as_deref()
+        .map(|type_str| parse_node_type(ctx, type_str));
 
     // Parse time filters if provided
     let after_filter: Option<DateTime<Utc>> = if let Some(ref after_str) = filters.after {
@@ -123,6 +114,253 @@ pub async fn handle_query(ctx: &CommandContext<'_>, filters: QueryFilters) -> Re
     Ok(())
 }
 
+/// Parse a node type name, exiting with an error message if it is unknown
+pub fn parse_node_type(ctx: &CommandContext<'_>, type_str: &str) -> NodeType {
+    match type_str.to_lowercase().as_str() {
+        "prompt" => NodeType::Prompt,
+        "response" => NodeType::Response,
+        "agent" => NodeType::Agent,
+        "template" => NodeType::Template,
+        "tool" | "toolinvocation" => NodeType::ToolInvocation,
+        "session" => NodeType::Session,
+        "custom" => NodeType::Custom,
+        _ => {
+            ctx.format.error(&format!("Invalid node type: {}. Use: prompt, response, agent, template, tool, session, custom", type_str));
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Handle `query --aggregate`, grouping matching nodes and computing aggregates
+pub async fn handle_aggregate_query(
+    ctx: &CommandContext<'_>,
+    filters: &QueryFilters,
+    group_by: &[String],
+    aggregates: &[String],
+) -> Result<()> {
+    let mut aggregation = Aggregation::new();
+    for dimension in group_by {
+        aggregation = aggregation.group_by(dimension.parse::<GroupBy>()?);
+    }
+    for aggregate in aggregates {
+        aggregation = aggregation.aggregate(aggregate.parse::<Aggregate>()?);
+    }
+
+    let mut builder = ctx.graph.query();
+    if let Some(ref session_str) = filters.session_id {
+        builder = builder.session(SessionId::from(Uuid::parse_str(session_str)?));
+    }
+    if let Some(ref type_str) = filters.node_type {
+        builder = builder.node_type(parse_node_type(ctx, type_str));
+    }
+    if filters.after.is_some() || filters.before.is_some() {
+        let after = match filters.after {
+            Some(ref after_str) => DateTime::parse_from_rfc3339(after_str)?.into(),
+            None => DateTime::<Utc>::MIN_UTC,
+        };
+        let before = match filters.before {
+            Some(ref before_str) => DateTime::parse_from_rfc3339(before_str)?.into(),
+            None => DateTime::<Utc>::MAX_UTC,
+        };
+        builder = builder.time_range(after, before);
+    }
+
+    let rows = builder.aggregate(&aggregation).await?;
+    print_aggregate_rows(ctx, &aggregation, &rows)
+}
+
+/// Print aggregate rows in the selected output format
+pub fn print_aggregate_rows(
+    ctx: &CommandContext<'_>,
+    aggregation: &Aggregation,
+    rows: &[AggregateRow],
+) -> Result<()> {
+    let group_columns: Vec<String> = aggregation
+        .group_by
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+    let value_columns: Vec<String> = aggregation
+        .aggregates
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    match ctx.format {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let rows: Vec<serde_json::Value> = rows
+                .iter()
+                .map(|row| {
+                    let key: serde_json::Map<String, serde_json::Value> = group_columns
+                        .iter()
+                        .zip(&row.key)
+                        .map(|(column, value)| (column.clone(), value.to_string().into()))
+                        .collect();
+                    let values: serde_json::Map<String, serde_json::Value> = value_columns
+                        .iter()
+                        .zip(&row.values)
+                        .map(|(column, value)| (column.clone(), serde_json::json!(value)))
+                        .collect();
+                    serde_json::json!({
+                        "key": key,
+                        "count": row.count,
+                        "values": values,
+                    })
+                })
+                .collect();
+            let output = serde_json::json!({
+                "group_by": group_columns,
+                "aggregates": value_columns,
+                "rows": rows,
+            });
+            if matches!(ctx.format, OutputFormat::Json) {
+                println!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                println!("{}", serde_yaml::to_string(&output)?);
+            }
+        }
+        OutputFormat::Table => {
+            if rows.iter().all(|row| row.count == 0) {
+                println!("{}", "No nodes matched the query".yellow());
+                return Ok(());
+            }
+
+            let header: Vec<&str> = group_columns
+                .iter()
+                .chain(&value_columns)
+                .map(String::as_str)
+                .collect();
+            let mut builder = TableBuilder::new().header(header);
+
+            for row in rows {
+                let cells = row
+                    .key
+                    .iter()
+                    .map(ToString::to_string)
+                    .chain(row.values.iter().map(|value| display_number(*value)))
+                    .collect();
+                builder = builder.row(cells);
+            }
+
+            builder.display();
+            println!("\n{} groups", rows.len().to_string().cyan().bold());
+        }
+        OutputFormat::Text => {
+            if rows.iter().all(|row| row.count == 0) {
+                println!("{}", "No nodes matched the query".yellow());
+                return Ok(());
+            }
+
+            println!(
+                "{}",
+                format!("Aggregates: {} groups", rows.len()).bold().green()
+            );
+            println!("{}", "=".repeat(50).green());
+
+            for row in rows {
+                let key: Vec<String> = row.key.iter().map(ToString::to_string).collect();
+                let title = if key.is_empty() {
+                    "all".to_string()
+                } else {
+                    key.join(" / ")
+                };
+                println!("\n{} ({} nodes)", title.bold(), row.count);
+                for (column, value) in value_columns.iter().zip(&row.values) {
+                    println!("  {}: {}", column.cyan(), display_number(*value));
+                }
+            }
+
+            println!("\n{}", "=".repeat(50).green());
+        }
+    }
+
+    Ok(())
+}
+
+/// Render an aggregate value, dropping the fraction from whole numbers
+fn display_number(value: Option<f64>) -> String {
+    match value {
+        Some(v) if v.fract() == 0.0 => format!("{v:.0}"),
+        Some(v) => format!("{v:.2}"),
+        None => "-".to_string(),
+    }
+}
+
+/// Handle `query --expr`, running a graph query expression
+pub async fn handle_expression_query(ctx: &CommandContext<'_>, expression: &str) -> Result<()> {
+    let result = ctx.graph.execute_query(expression).await?;
+
+    match ctx.format {
+        OutputFormat::Json => {
+            let output = serde_json::json!({
+                "count": result.len(),
+                "columns": result.columns,
+                "rows": result.rows,
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Yaml => {
+            let output = serde_json::json!({
+                "count": result.len(),
+                "columns": result.columns,
+                "rows": result.rows,
+            });
+            println!("{}", serde_yaml::to_string(&output)?);
+        }
+        OutputFormat::Table => {
+            if result.is_empty() {
+                println!("{}", "No rows matched the query".yellow());
+                return Ok(());
+            }
+
+            let mut builder =
+                TableBuilder::new().header(result.columns.iter().map(String::as_str).collect());
+
+            for row in &result.rows {
+                builder = builder.row(row.iter().map(display_value).collect());
+            }
+
+            builder.display();
+            println!("\n{} rows", result.len().to_string().cyan().bold());
+        }
+        OutputFormat::Text => {
+            if result.is_empty() {
+                println!("{}", "No rows matched the query".yellow());
+                return Ok(());
+            }
+
+            println!(
+                "{}",
+                format!("Query Results: {} rows", result.len())
+                    .bold()
+                    .green()
+            );
+            println!("{}", "=".repeat(50).green());
+
+            for (i, row) in result.rows.iter().enumerate() {
+                println!("\n{}", format!("[{}]", i + 1).bold());
+                for (column, value) in result.columns.iter().zip(row) {
+                    println!("  {}: {}", column.cyan(), display_value(value));
+                }
+            }
+
+            println!("\n{}", "=".repeat(50).green());
+            println!("Total: {} rows", result.len().to_string().cyan().bold());
+        }
+    }
+
+    Ok(())
+}
+
+/// Render a result value for table and text output
+fn display_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => "null".to_string(),
+        other => other.to_string(),
+    }
+}
+
 /// Print query results in the appropriate format
 fn print_node_results(format: &OutputFormat, nodes: &[Node]) -> Result<()> {
     match format {
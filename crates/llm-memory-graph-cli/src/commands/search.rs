@@ -0,0 +1,154 @@
+//! Full-text search command
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use llm_memory_graph::search::{SearchHit, TextQuery};
+use llm_memory_graph_types::{NodeType, SessionId};
+use uuid::Uuid;
+
+use super::CommandContext;
+use crate::output::{OutputFormat, TableBuilder};
+
+/// Search options
+pub struct SearchOptions {
+    pub query: String,
+    pub session_id: Option<String>,
+    pub node_types: Vec<String>,
+    pub after: Option<String>,
+    pub before: Option<String>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+/// Handle the search command
+pub async fn handle_search(ctx: &CommandContext<'_>, options: SearchOptions) -> Result<()> {
+    let mut query = TextQuery::new(options.query)
+        .limit(options.limit)
+        .offset(options.offset);
+
+    if let Some(ref session_str) = options.session_id {
+        query = query.session(SessionId::from(Uuid::parse_str(session_str)?));
+    }
+
+    for type_str in &options.node_types {
+        query = query.node_type(match type_str.to_lowercase().as_str() {
+            "prompt" => NodeType::Prompt,
+            "response" => NodeType::Response,
+            "template" => NodeType::Template,
+            "tool" | "toolinvocation" => NodeType::ToolInvocation,
+            "custom" => NodeType::Custom,
+            _ => {
+                ctx.format.error(&format!(
+                    "Invalid node type: {}. Use: prompt, response, template, tool, custom",
+                    type_str
+                ));
+                std::process::exit(1);
+            }
+        });
+    }
+
+    if let Some(ref after_str) = options.after {
+        let after: DateTime<Utc> = DateTime::parse_from_rfc3339(after_str)?.into();
+        query = query.after(after);
+    }
+    if let Some(ref before_str) = options.before {
+        let before: DateTime<Utc> = DateTime::parse_from_rfc3339(before_str)?.into();
+        query = query.before(before);
+    }
+
+    let hits = ctx.graph.search_text(&query).await?;
+    print_search_results(ctx.format, &hits)
+}
+
+/// Print search hits in the appropriate format
+fn print_search_results(format: &OutputFormat, hits: &[SearchHit]) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            let result = serde_json::json!({
+                "count": hits.len(),
+                "hits": hits
+            });
+            println!("{}", serde_json::to_string_pretty(&result)?);
+        }
+        OutputFormat::Yaml => {
+            let result = serde_json::json!({
+                "count": hits.len(),
+                "hits": hits
+            });
+            println!("{}", serde_yaml::to_string(&result)?);
+        }
+        OutputFormat::Table => {
+            if hits.is_empty() {
+                println!("{}", "No matches found".yellow());
+                return Ok(());
+            }
+
+            let mut builder =
+                TableBuilder::new().header(vec!["Score", "ID", "Type", "Session", "Snippet"]);
+
+            for hit in hits {
+                builder = builder.row(vec![
+                    format!("{:.3}", hit.score),
+                    hit.node_id.to_string(),
+                    format!("{:?}", hit.node_type),
+                    hit.session_id
+                        .map(|s| s.to_string())
+                        .unwrap_or_else(|| "N/A".to_string()),
+                    hit.snippet.render("[", "]"),
+                ]);
+            }
+
+            builder.display();
+            println!("\n{} matches", hits.len().to_string().cyan().bold());
+        }
+        OutputFormat::Text => {
+            if hits.is_empty() {
+                println!("{}", "No matches found".yellow());
+                return Ok(());
+            }
+
+            println!(
+                "{}",
+                format!("Search Results: {} matches", hits.len())
+                    .bold()
+                    .green()
+            );
+            println!("{}", "=".repeat(50).green());
+
+            for (i, hit) in hits.iter().enumerate() {
+                println!(
+                    "\n{} {} {}",
+                    format!("[{}]", i + 1).bold(),
+                    hit.node_id.to_string().cyan(),
+                    format!("({:.3})", hit.score).dimmed()
+                );
+                println!("  Type:    {:?}", hit.node_type);
+                println!("  Created: {}", hit.timestamp.format("%Y-%m-%d %H:%M:%S"));
+                if let Some(session_id) = hit.session_id {
+                    println!("  Session: {}", session_id);
+                }
+                println!("  {}", highlight(hit));
+            }
+
+            println!("\n{}", "=".repeat(50).green());
+            println!("Total: {} matches", hits.len().to_string().cyan().bold());
+        }
+    }
+
+    Ok(())
+}
+
+/// Snippet text with matched words highlighted
+fn highlight(hit: &SearchHit) -> String {
+    let text = &hit.snippet.text;
+    let mut out = String::new();
+    let mut last = 0;
+    for range in &hit.snippet.highlights {
+        out.push_str(&text[last..range.start]);
+        out.push_str(&text[range.clone()].yellow().bold().to_string());
+        last = range.end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
@@ -1,7 +1,11 @@
 //! Session management commands
 
 use anyhow::Result;
-use colored::Colorize;
+use colored::{ColoredString, Colorize};
+use llm_memory_graph::diff::{
+    DiffOptions, DiffSummary, LineChange, SessionDiff, TextDiff, ToolCallDiff, ToolChange,
+    TurnDiff, TurnStatus,
+};
 use llm_memory_graph_types::SessionId;
 use uuid::Uuid;
 
@@ -138,33 +142,59 @@ pub async fn handle_verify(ctx: &CommandContext<'_>) -> Result<()> {
     }
 
     let stats = ctx.graph.stats().await?;
+    let report = ctx.graph.verify_integrity().await?;
+    let status = if report.is_ok() {
+        "✓".green().bold()
+    } else {
+        "✗".red().bold()
+    };
 
     match ctx.format {
-        OutputFormat::Json => {
-            let result = serde_json::json!({
-                "status": "verified",
-                "nodes": stats.node_count,
-                "edges": stats.edge_count,
-                "sessions": stats.session_count
-            });
-            println!("{}", serde_json::to_string_pretty(&result)?);
-        }
-        OutputFormat::Yaml => {
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let violations: Vec<_> = report
+                .violations
+                .iter()
+                .map(|v| {
+                    serde_json::json!({
+                        "kind": format!("{:?}", v.kind),
+                        "node_id": v.node_id.to_string(),
+                        "edge_id": v.edge_id.map(|id| id.to_string()),
+                        "message": v.message,
+                    })
+                })
+                .collect();
             let result = serde_json::json!({
-                "status": "verified",
+                "status": if report.is_ok() { "verified" } else { "violations" },
                 "nodes": stats.node_count,
                 "edges": stats.edge_count,
-                "sessions": stats.session_count
+                "sessions": stats.session_count,
+                "violations": violations
             });
-            println!("{}", serde_yaml::to_string(&result)?);
+            if matches!(ctx.format, OutputFormat::Json) {
+                println!("{}", serde_json::to_string_pretty(&result)?);
+            } else {
+                println!("{}", serde_yaml::to_string(&result)?);
+            }
         }
         OutputFormat::Table => {
-            TableBuilder::new()
+            let mut table = TableBuilder::new()
                 .header(vec!["Component", "Count", "Status"])
                 .row(vec!["Nodes".to_string(), stats.node_count.to_string(), "✓".to_string()])
                 .row(vec!["Edges".to_string(), stats.edge_count.to_string(), "✓".to_string()])
                 .row(vec!["Sessions".to_string(), stats.session_count.to_string(), "✓".to_string()])
-                .display();
+                .row(vec![
+                    "Violations".to_string(),
+                    report.violations.len().to_string(),
+                    status.to_string(),
+                ]);
+            for violation in &report.violations {
+                table = table.row(vec![
+                    format!("{:?}", violation.kind),
+                    String::new(),
+                    violation.message.clone(),
+                ]);
+            }
+            table.display();
         }
         OutputFormat::Text => {
             println!("{} Verified {} nodes", "✓".green().bold(), stats.node_count);
@@ -174,9 +204,402 @@ pub async fn handle_verify(ctx: &CommandContext<'_>) -> Result<()> {
                 "✓".green().bold(),
                 stats.session_count
             );
-            println!("\n{} Database verification complete", "✓".green().bold());
+            for violation in &report.violations {
+                println!(
+                    "{} {:?}: {}",
+                    "✗".red().bold(),
+                    violation.kind,
+                    violation.message
+                );
+            }
+            if report.is_ok() {
+                println!("\n{} Database verification complete", status);
+            } else {
+                println!(
+                    "\n{} Database verification found {} violation(s)",
+                    status,
+                    report.violations.len()
+                );
+            }
         }
     }
 
+    if !report.is_ok() {
+        anyhow::bail!("{} integrity violation(s) found", report.violations.len());
+    }
+
     Ok(())
 }
+
+/// Layout for rendering a session diff as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffView {
+    Unified,
+    SideBySide,
+}
+
+impl std::str::FromStr for DiffView {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unified" => Ok(DiffView::Unified),
+            "side-by-side" | "sidebyside" | "split" => Ok(DiffView::SideBySide),
+            _ => Err(format!(
+                "Invalid diff view: '{}'. Use 'unified' or 'side-by-side'",
+                s
+            )),
+        }
+    }
+}
+
+/// Handle the session diff command
+pub async fn handle_session_diff(
+    ctx: &CommandContext<'_>,
+    session_a: &str,
+    session_b: &str,
+    options: &DiffOptions,
+    view: DiffView,
+    width: usize,
+) -> Result<()> {
+    let a = SessionId::from(Uuid::parse_str(session_a)?);
+    let b = SessionId::from(Uuid::parse_str(session_b)?);
+    let diff = ctx.graph.diff_sessions(a, b, options).await?;
+
+    match ctx.format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&diff)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&diff)?),
+        OutputFormat::Text | OutputFormat::Table => {
+            print_diff_header(&diff);
+            for turn in &diff.turns {
+                match view {
+                    DiffView::Unified => print_unified_turn(turn),
+                    DiffView::SideBySide => print_side_by_side_turn(turn, width.max(20)),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_diff_header(diff: &SessionDiff) {
+    let summary = &diff.summary;
+    println!("{}", format!("--- a {}", diff.session_a).red().bold());
+    println!("{}", format!("+++ b {}", diff.session_b).green().bold());
+    println!(
+        "Aligned by {}: {} vs {} turns, {} unchanged, {} changed, {} only in a, {} only in b",
+        diff.alignment,
+        summary.turns_a,
+        summary.turns_b,
+        summary.unchanged,
+        summary.changed,
+        summary.only_in_a,
+        summary.only_in_b
+    );
+    println!("{}", totals(summary));
+}
+
+fn totals(summary: &DiffSummary) -> String {
+    format!(
+        "Tokens {} -> {} ({}), latency {}ms -> {}ms ({})",
+        summary.tokens_a,
+        summary.tokens_b,
+        signed(summary.token_delta, ""),
+        summary.latency_a_ms,
+        summary.latency_b_ms,
+        signed(summary.latency_delta_ms, "ms")
+    )
+}
+
+fn signed(value: i64, unit: &str) -> ColoredString {
+    let text = format!("{:+}{}", value, unit);
+    match value.signum() {
+        1 => text.yellow(),
+        -1 => text.cyan(),
+        _ => text.normal(),
+    }
+}
+
+fn turn_title(turn: &TurnDiff) -> String {
+    let number = |index: Option<usize>| index.map_or("-".to_string(), |i| (i + 1).to_string());
+    let status = match turn.status {
+        TurnStatus::Unchanged => "unchanged",
+        TurnStatus::Changed => "changed",
+        TurnStatus::OnlyInA => "only in a",
+        TurnStatus::OnlyInB => "only in b",
+    };
+    format!(
+        "@@ turn {} <-> {} {} @@",
+        number(turn.a.as_ref().map(|t| t.index)),
+        number(turn.b.as_ref().map(|t| t.index)),
+        status
+    )
+}
+
+fn print_unified_turn(turn: &TurnDiff) {
+    println!("\n{}", turn_title(turn).cyan());
+    if turn.status == TurnStatus::Unchanged {
+        return;
+    }
+
+    match (&turn.a, &turn.b, &turn.prompt, &turn.response) {
+        (_, _, Some(prompt), Some(response)) => {
+            print_unified_text("prompt", prompt);
+            print_unified_text("response", response);
+        }
+        (Some(side), None, _, _) => {
+            println!("{}", "prompt:".bold());
+            print_lines(&side.prompt, "-");
+            println!("{}", "response:".bold());
+            print_lines(&side.response, "-");
+        }
+        (None, Some(side), _, _) => {
+            println!("{}", "prompt:".bold());
+            print_lines(&side.prompt, "+");
+            println!("{}", "response:".bold());
+            print_lines(&side.response, "+");
+        }
+        _ => {}
+    }
+
+    if !turn.tools.is_empty() {
+        println!("{}", "tools:".bold());
+        for tool in &turn.tools {
+            println!("{}", tool_line(tool));
+        }
+    }
+    if let Some(agents) = &turn.agents {
+        println!("{}", "agents:".bold());
+        if agents.handled_by_a != agents.handled_by_b {
+            println!(
+                "  handled by {} -> {}",
+                agents.handled_by_a.as_deref().unwrap_or("-"),
+                agents.handled_by_b.as_deref().unwrap_or("-")
+            );
+        }
+        if agents.handoffs_a != agents.handoffs_b {
+            println!(
+                "  handoffs [{}] -> [{}]",
+                agents.handoffs_a.join(", "),
+                agents.handoffs_b.join(", ")
+            );
+        }
+    }
+    println!(
+        "tokens {}, latency {}",
+        signed(turn.token_delta, ""),
+        signed(turn.latency_delta_ms, "ms")
+    );
+}
+
+fn print_unified_text(label: &str, diff: &TextDiff) {
+    if diff.is_equal() {
+        println!("{}", format!("{}: unchanged", label).bold());
+        return;
+    }
+    println!(
+        "{}",
+        format!("{} ({:.0}% similar):", label, diff.similarity * 100.0).bold()
+    );
+    for line in &diff.lines {
+        match line.change {
+            LineChange::Equal => println!(" {}", line.text),
+            LineChange::Delete => println!("{}", format!("-{}", line.text).red()),
+            LineChange::Insert => println!("{}", format!("+{}", line.text).green()),
+        }
+    }
+}
+
+fn print_lines(text: &str, marker: &str) {
+    for line in text.lines() {
+        let line = format!("{}{}", marker, line);
+        if marker == "-" {
+            println!("{}", line.red());
+        } else {
+            println!("{}", line.green());
+        }
+    }
+}
+
+fn tool_line(tool: &ToolCallDiff) -> ColoredString {
+    let params = |value: &Option<serde_json::Value>| {
+        value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+    };
+    match tool.change {
+        ToolChange::Unchanged => format!("  {} {}", tool.name, params(&tool.parameters_a)).normal(),
+        ToolChange::Removed => format!("- {} {}", tool.name, params(&tool.parameters_a)).red(),
+        ToolChange::Added => format!("+ {} {}", tool.name, params(&tool.parameters_b)).green(),
+        ToolChange::Changed if tool.changed_parameters.is_empty() => format!(
+            "~ {} {} -> {}",
+            tool.name,
+            params(&tool.parameters_a),
+            params(&tool.parameters_b)
+        )
+        .yellow(),
+        ToolChange::Changed => format!(
+            "~ {} changed {}: {} -> {}",
+            tool.name,
+            tool.changed_parameters.join(", "),
+            params(&tool.parameters_a),
+            params(&tool.parameters_b)
+        )
+        .yellow(),
+    }
+}
+
+/// One row of a side-by-side diff: left text, right text and the gutter mark
+type SideRow = (String, String, char);
+
+fn print_side_by_side_turn(turn: &TurnDiff, width: usize) {
+    println!("\n{}", turn_title(turn).cyan());
+
+    let mut rows: Vec<SideRow> = Vec::new();
+    match (&turn.a, &turn.b, &turn.prompt, &turn.response) {
+        (_, _, Some(prompt), Some(response)) => {
+            rows.push(("[prompt]".to_string(), "[prompt]".to_string(), ' '));
+            rows.extend(side_rows(prompt));
+            rows.push(("[response]".to_string(), "[response]".to_string(), ' '));
+            rows.extend(side_rows(response));
+        }
+        (Some(side), None, _, _) => {
+            rows.push(("[prompt]".to_string(), String::new(), '<'));
+            rows.extend(
+                side.prompt
+                    .lines()
+                    .map(|l| (l.to_string(), String::new(), '<')),
+            );
+            rows.push(("[response]".to_string(), String::new(), '<'));
+            rows.extend(
+                side.response
+                    .lines()
+                    .map(|l| (l.to_string(), String::new(), '<')),
+            );
+        }
+        (None, Some(side), _, _) => {
+            rows.push((String::new(), "[prompt]".to_string(), '>'));
+            rows.extend(
+                side.prompt
+                    .lines()
+                    .map(|l| (String::new(), l.to_string(), '>')),
+            );
+            rows.push((String::new(), "[response]".to_string(), '>'));
+            rows.extend(
+                side.response
+                    .lines()
+                    .map(|l| (String::new(), l.to_string(), '>')),
+            );
+        }
+        _ => {}
+    }
+
+    if !turn.tools.is_empty() {
+        rows.push(("[tools]".to_string(), "[tools]".to_string(), ' '));
+        for tool in &turn.tools {
+            let call = |value: &Option<serde_json::Value>| {
+                value
+                    .as_ref()
+                    .map(|v| format!("{} {}", tool.name, v))
+                    .unwrap_or_default()
+            };
+            let mark = match tool.change {
+                ToolChange::Unchanged => ' ',
+                ToolChange::Changed => '|',
+                ToolChange::Removed => '<',
+                ToolChange::Added => '>',
+            };
+            rows.push((call(&tool.parameters_a), call(&tool.parameters_b), mark));
+        }
+    }
+
+    if let Some(agents) = &turn.agents {
+        rows.push(("[agents]".to_string(), "[agents]".to_string(), ' '));
+        let describe = |handled: &Option<String>, handoffs: &[String]| {
+            let mut text = format!("handled by {}", handled.as_deref().unwrap_or("-"));
+            if !handoffs.is_empty() {
+                text.push_str(&format!(", to {}", handoffs.join(", ")));
+            }
+            text
+        };
+        rows.push((
+            describe(&agents.handled_by_a, &agents.handoffs_a),
+            describe(&agents.handled_by_b, &agents.handoffs_b),
+            '|',
+        ));
+    }
+
+    if let (Some(a), Some(b)) = (&turn.a, &turn.b) {
+        rows.push((
+            format!("{} tokens, {}ms", a.tokens, a.latency_ms),
+            format!("{} tokens, {}ms", b.tokens, b.latency_ms),
+            if a.tokens == b.tokens && a.latency_ms == b.latency_ms {
+                ' '
+            } else {
+                '|'
+            },
+        ));
+    }
+
+    for (left, right, mark) in rows {
+        let left = fit(&left, width);
+        let right = fit(&right, width);
+        let (left, right) = match mark {
+            '<' => (left.red(), right.normal()),
+            '>' => (left.normal(), right.green()),
+            '|' => (left.red(), right.green()),
+            _ => (left.normal(), right.normal()),
+        };
+        println!("{} {} {}", left, mark, right);
+    }
+}
+
+/// Pair deleted and inserted lines into side-by-side rows
+fn side_rows(diff: &TextDiff) -> Vec<SideRow> {
+    let mut rows = Vec::new();
+    let mut deleted: Vec<&str> = Vec::new();
+    let mut inserted: Vec<&str> = Vec::new();
+
+    let flush = |rows: &mut Vec<SideRow>, deleted: &mut Vec<&str>, inserted: &mut Vec<&str>| {
+        for i in 0..deleted.len().max(inserted.len()) {
+            let left = deleted.get(i).copied();
+            let right = inserted.get(i).copied();
+            let mark = match (left, right) {
+                (Some(_), Some(_)) => '|',
+                (Some(_), None) => '<',
+                _ => '>',
+            };
+            rows.push((
+                left.unwrap_or_default().to_string(),
+                right.unwrap_or_default().to_string(),
+                mark,
+            ));
+        }
+        deleted.clear();
+        inserted.clear();
+    };
+
+    for line in &diff.lines {
+        match line.change {
+            LineChange::Delete => deleted.push(&line.text),
+            LineChange::Insert => inserted.push(&line.text),
+            LineChange::Equal => {
+                flush(&mut rows, &mut deleted, &mut inserted);
+                rows.push((line.text.clone(), line.text.clone(), ' '));
+            }
+        }
+    }
+    flush(&mut rows, &mut deleted, &mut inserted);
+    rows
+}
+
+/// Pad or truncate text to exactly `width` characters
+fn fit(text: &str, width: usize) -> String {
+    let count = text.chars().count();
+    if count <= width {
+        format!("{}{}", text, " ".repeat(width - count))
+    } else {
+        let mut cut: String = text.chars().take(width - 1).collect();
+        cut.push('…');
+        cut
+    }
+}
@@ -0,0 +1,174 @@
+//! Materialized view commands
+
+use anyhow::{bail, Result};
+use colored::Colorize;
+use llm_memory_graph::query::aggregate::{Aggregate, Aggregation, GroupBy};
+use llm_memory_graph::query::predicate::{Field, Predicate};
+use llm_memory_graph::views::ViewDefinition;
+use llm_memory_graph_types::SessionId;
+use uuid::Uuid;
+
+use super::query::{parse_node_type, print_aggregate_rows};
+use super::CommandContext;
+use crate::output::{OutputFormat, TableBuilder};
+
+/// Options for `view create`
+pub struct ViewCreateOptions {
+    pub name: String,
+    pub node_type: Option<String>,
+    pub aggregates: Vec<String>,
+    pub group_by: Vec<String>,
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    pub metadata: Vec<String>,
+}
+
+/// Handle view create command
+pub async fn handle_view_create(
+    ctx: &CommandContext<'_>,
+    options: ViewCreateOptions,
+) -> Result<()> {
+    let mut aggregation = Aggregation::new();
+    for dimension in &options.group_by {
+        aggregation = aggregation.group_by(dimension.parse::<GroupBy>()?);
+    }
+    for aggregate in &options.aggregates {
+        aggregation = aggregation.aggregate(aggregate.parse::<Aggregate>()?);
+    }
+    if aggregation.aggregates.is_empty() {
+        aggregation = aggregation.count();
+    }
+
+    let mut definition = ViewDefinition::new(options.name.clone(), aggregation);
+    if let Some(ref type_str) = options.node_type {
+        definition = definition.node_type(parse_node_type(ctx, type_str));
+    }
+    if let Some(ref session_str) = options.session_id {
+        definition = definition.filter(Predicate::session(SessionId::from(Uuid::parse_str(
+            session_str,
+        )?)));
+    }
+    if let Some(model) = options.model {
+        definition = definition.filter(Field::Model.eq(model));
+    }
+    for pair in &options.metadata {
+        let Some((key, value)) = pair.split_once('=') else {
+            bail!("Invalid metadata filter '{pair}', expected key=value");
+        };
+        definition = definition.filter(Predicate::metadata(key, value));
+    }
+
+    ctx.graph.create_view(definition).await?;
+    ctx.format
+        .success(&format!("View '{}' created", options.name));
+    Ok(())
+}
+
+/// Handle view list command
+pub async fn handle_view_list(ctx: &CommandContext<'_>) -> Result<()> {
+    let views = ctx.graph.list_views();
+
+    let describe = |view: &ViewDefinition| {
+        let aggregates: Vec<String> = view
+            .aggregation
+            .aggregates
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let group_by: Vec<String> = view
+            .aggregation
+            .group_by
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        serde_json::json!({
+            "name": view.name,
+            "node_type": view.node_type.as_ref().map(|t| format!("{t:?}")),
+            "filter": view.filter.as_ref().map(ToString::to_string),
+            "aggregates": aggregates,
+            "group_by": group_by,
+            "created_at": view.created_at.to_rfc3339(),
+        })
+    };
+
+    match ctx.format {
+        OutputFormat::Json => {
+            let output: Vec<serde_json::Value> = views.iter().map(describe).collect();
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        OutputFormat::Yaml => {
+            let output: Vec<serde_json::Value> = views.iter().map(describe).collect();
+            println!("{}", serde_yaml::to_string(&output)?);
+        }
+        OutputFormat::Table | OutputFormat::Text => {
+            if views.is_empty() {
+                println!("{}", "No views defined".yellow());
+                return Ok(());
+            }
+
+            let mut builder = TableBuilder::new().header(vec![
+                "Name",
+                "Node Type",
+                "Filter",
+                "Aggregates",
+                "Group By",
+            ]);
+            for view in &views {
+                let aggregates: Vec<String> = view
+                    .aggregation
+                    .aggregates
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                let group_by: Vec<String> = view
+                    .aggregation
+                    .group_by
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                builder = builder.row(vec![
+                    view.name.clone(),
+                    view.node_type
+                        .as_ref()
+                        .map_or("-".to_string(), |t| format!("{t:?}")),
+                    view.filter
+                        .as_ref()
+                        .map_or("-".to_string(), ToString::to_string),
+                    aggregates.join(", "),
+                    if group_by.is_empty() {
+                        "-".to_string()
+                    } else {
+                        group_by.join(", ")
+                    },
+                ]);
+            }
+            builder.display();
+            println!("\n{} views", views.len().to_string().cyan().bold());
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle view show command, printing the view's current rows
+pub async fn handle_view_show(ctx: &CommandContext<'_>, name: &str) -> Result<()> {
+    let rows = ctx.graph.read_view(name).await?;
+    let Some(view) = ctx.graph.list_views().into_iter().find(|v| v.name == name) else {
+        bail!("No view named '{name}'");
+    };
+    print_aggregate_rows(ctx, &view.aggregation, &rows)
+}
+
+/// Handle view refresh command
+pub async fn handle_view_refresh(ctx: &CommandContext<'_>, name: &str) -> Result<()> {
+    ctx.graph.refresh_view(name).await?;
+    ctx.format.success(&format!("View '{name}' rebuilt"));
+    Ok(())
+}
+
+/// Handle view drop command
+pub async fn handle_view_drop(ctx: &CommandContext<'_>, name: &str) -> Result<()> {
+    ctx.graph.drop_view(name).await?;
+    ctx.format.success(&format!("View '{name}' dropped"));
+    Ok(())
+}
@@ -16,7 +16,12 @@ mod output;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use llm_memory_graph::{engine::AsyncMemoryGraph, Config};
+use llm_memory_graph::{
+    diff::{DiffOptions, TurnAlignment},
+    engine::AsyncMemoryGraph,
+    export::GraphFormat,
+    Config, Error,
+};
 use std::path::PathBuf;
 
 use commands::CommandContext;
@@ -73,6 +78,48 @@ enum Commands {
         /// Limit number of results
         #[arg(short, long)]
         limit: Option<usize>,
+
+        /// Graph query expression, e.g. "MATCH (p:Prompt) WHERE p.content CONTAINS 'rust' RETURN p"
+        #[arg(short, long, conflicts_with_all = ["session", "node_type", "after", "before", "limit"])]
+        expr: Option<String>,
+
+        /// Aggregates to compute instead of listing nodes, e.g. "count,sum(total_tokens),p95(latency_ms)"
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["expr", "limit"])]
+        aggregate: Vec<String>,
+
+        /// Group aggregates by model, session, agent, template, tool, hour, day or week
+        #[arg(short, long, value_delimiter = ',', requires = "aggregate")]
+        group_by: Vec<String>,
+    },
+
+    /// Full-text search over prompt, response, template and tool text
+    Search {
+        /// Search terms; use "quotes" for phrases and a trailing * for prefixes
+        query: String,
+
+        /// Filter by session ID (UUID format)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Filter by node type (prompt, response, template, tool); repeatable
+        #[arg(short = 't', long)]
+        node_type: Vec<String>,
+
+        /// Only match nodes created after this timestamp (RFC3339 format)
+        #[arg(short, long)]
+        after: Option<String>,
+
+        /// Only match nodes created before this timestamp (RFC3339 format)
+        #[arg(short, long)]
+        before: Option<String>,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+
+        /// Number of results to skip
+        #[arg(long, default_value_t = 0)]
+        offset: usize,
     },
 
     /// Export operations
@@ -98,6 +145,10 @@ enum Commands {
     #[command(subcommand)]
     Template(TemplateCommands),
 
+    /// Materialized view management
+    #[command(subcommand)]
+    View(ViewCommands),
+
     /// Agent management
     #[command(subcommand)]
     Agent(AgentCommands),
@@ -121,6 +172,37 @@ enum Commands {
     Verify,
 }
 
+impl Commands {
+    /// Whether the command only reads from the database
+    ///
+    /// Inspection commands can run against a read-only snapshot when the
+    /// database is locked by another process.
+    fn is_inspection(&self) -> bool {
+        match self {
+            Commands::Stats
+            | Commands::Session(_)
+            | Commands::Node(_)
+            | Commands::Query { .. }
+            | Commands::Search { .. }
+            | Commands::Export(_)
+            | Commands::Verify => true,
+            Commands::Template(cmd) => {
+                matches!(
+                    cmd,
+                    TemplateCommands::Get { .. } | TemplateCommands::List { .. }
+                )
+            }
+            Commands::Agent(cmd) => matches!(cmd, AgentCommands::Get { .. } | AgentCommands::List),
+            Commands::View(cmd) => matches!(cmd, ViewCommands::List | ViewCommands::Show { .. }),
+            Commands::Import { dry_run, .. } => *dry_run,
+            Commands::Server(_)
+            | Commands::Benchmark(_)
+            | Commands::Pattern(_)
+            | Commands::Flush => false,
+        }
+    }
+}
+
 #[derive(Subcommand)]
 enum SessionCommands {
     /// Get session details
@@ -128,6 +210,31 @@ enum SessionCommands {
         /// Session ID (UUID format)
         session_id: String,
     },
+
+    /// Compare two sessions turn by turn
+    Diff {
+        /// First session ID (UUID format)
+        session_a: String,
+
+        /// Second session ID (UUID format)
+        session_b: String,
+
+        /// How to pair up turns (order, template, similarity)
+        #[arg(long, default_value = "order")]
+        align: TurnAlignment,
+
+        /// Minimum prompt similarity (0-1) for --align similarity
+        #[arg(long, default_value_t = 0.5)]
+        threshold: f32,
+
+        /// Text layout (unified, side-by-side)
+        #[arg(long, default_value = "unified")]
+        view: commands::session::DiffView,
+
+        /// Column width for the side-by-side layout
+        #[arg(long, default_value_t = 60)]
+        width: usize,
+    },
 }
 
 #[derive(Subcommand)]
@@ -165,6 +272,91 @@ enum ExportCommands {
         #[arg(long, default_value = "json")]
         export_format: commands::export::ExportFormat,
     },
+
+    /// Export a session or node neighbourhood as a graph diagram
+    Graph {
+        /// Session to export (UUID format)
+        #[arg(short, long, conflicts_with = "node", required_unless_present = "node")]
+        session: Option<String>,
+
+        /// Export the neighbourhood of this node instead (UUID format)
+        #[arg(short, long)]
+        node: Option<String>,
+
+        /// Number of hops around --node to include
+        #[arg(long, default_value = "2")]
+        depth: usize,
+
+        /// Graph format (dot, graphml, mermaid, json)
+        #[arg(long, default_value = "dot")]
+        format: GraphFormat,
+
+        /// Output file path (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Edge property to show in edge labels (repeatable)
+        #[arg(long = "edge-property")]
+        edge_properties: Vec<String>,
+
+        /// Graph title
+        #[arg(long)]
+        title: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ViewCommands {
+    /// Define a view and build it from the current graph
+    Create {
+        /// View name (letters, digits, '_', '-' and '.')
+        name: String,
+
+        /// Only aggregate nodes of this type (prompt, response, agent, template, tool)
+        #[arg(short = 't', long)]
+        node_type: Option<String>,
+
+        /// Aggregates to maintain, e.g. "count,sum(total_tokens),p95(latency_ms)" (default: count)
+        #[arg(long, value_delimiter = ',')]
+        aggregate: Vec<String>,
+
+        /// Group by model, session, agent, template, tool, hour, day or week
+        #[arg(short, long, value_delimiter = ',')]
+        group_by: Vec<String>,
+
+        /// Only aggregate nodes from this session (UUID format)
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Only aggregate prompts and responses for this model
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Only aggregate nodes with this metadata, in key=value format; repeatable
+        #[arg(long)]
+        metadata: Vec<String>,
+    },
+
+    /// List defined views
+    List,
+
+    /// Show the current rows of a view
+    Show {
+        /// View name
+        name: String,
+    },
+
+    /// Rebuild a view from the current graph
+    Refresh {
+        /// View name
+        name: String,
+    },
+
+    /// Delete a view
+    Drop {
+        /// View name
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -381,9 +573,17 @@ enum PatternCommands {
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    // Open database
+    // Open database, falling back to a read-only snapshot for inspection
+    // commands when another process (e.g. the server) holds the lock
     let config = Config::new(cli.db_path.to_str().unwrap());
-    let graph = AsyncMemoryGraph::open(config).await?;
+    let graph = match AsyncMemoryGraph::open(config.clone()).await {
+        Ok(graph) => graph,
+        Err(Error::DatabaseLocked(_)) if cli.command.is_inspection() => {
+            eprintln!("Database is locked by another process; reading from a snapshot");
+            AsyncMemoryGraph::open_read_only(config).await?
+        }
+        Err(e) => return Err(e.into()),
+    };
 
     // Create command context
     let ctx = CommandContext::new(&graph, &cli.format);
@@ -395,6 +595,22 @@ async fn main() -> Result<()> {
             SessionCommands::Get { session_id } => {
                 commands::session::handle_session_get(&ctx, &session_id).await?
             }
+            SessionCommands::Diff {
+                session_a,
+                session_b,
+                align,
+                threshold,
+                view,
+                width,
+            } => {
+                let options = DiffOptions::new()
+                    .alignment(align)
+                    .similarity_threshold(threshold);
+                commands::session::handle_session_diff(
+                    &ctx, &session_a, &session_b, &options, view, width,
+                )
+                .await?
+            }
         },
 
         Commands::Node(node_cmd) => match node_cmd {
@@ -409,15 +625,48 @@ async fn main() -> Result<()> {
             after,
             before,
             limit,
+            expr,
+            aggregate,
+            group_by,
         } => {
-            let filters = commands::query::QueryFilters {
+            if let Some(expr) = expr {
+                commands::query::handle_expression_query(&ctx, &expr).await?;
+            } else {
+                let filters = commands::query::QueryFilters {
+                    session_id: session,
+                    node_type,
+                    after,
+                    before,
+                    limit,
+                };
+                if aggregate.is_empty() {
+                    commands::query::handle_query(&ctx, filters).await?;
+                } else {
+                    commands::query::handle_aggregate_query(&ctx, &filters, &group_by, &aggregate)
+                        .await?;
+                }
+            }
+        }
+
+        Commands::Search {
+            query,
+            session,
+            node_type,
+            after,
+            before,
+            limit,
+            offset,
+        } => {
+            let options = commands::search::SearchOptions {
+                query,
                 session_id: session,
-                node_type,
+                node_types: node_type,
                 after,
                 before,
                 limit,
+                offset,
             };
-            commands::query::handle_query(&ctx, filters).await?;
+            commands::search::handle_search(&ctx, options).await?;
         }
 
         Commands::Export(export_cmd) => match export_cmd {
@@ -433,6 +682,26 @@ async fn main() -> Result<()> {
                 output,
                 export_format,
             } => commands::export::handle_export_database(&ctx, &output, export_format).await?,
+            ExportCommands::Graph {
+                session,
+                node,
+                depth,
+                format,
+                output,
+                edge_properties,
+                title,
+            } => {
+                let options = commands::export::GraphExportOptions {
+                    session_id: session,
+                    node_id: node,
+                    depth,
+                    format,
+                    output,
+                    edge_properties,
+                    title,
+                };
+                commands::export::handle_export_graph(&ctx, options).await?
+            }
         },
 
         Commands::Import {
@@ -441,6 +710,35 @@ async fn main() -> Result<()> {
             dry_run,
         } => commands::import::handle_import(&ctx, &input, import_format, dry_run).await?,
 
+        Commands::View(view_cmd) => match view_cmd {
+            ViewCommands::Create {
+                name,
+                node_type,
+                aggregate,
+                group_by,
+                session,
+                model,
+                metadata,
+            } => {
+                let options = commands::view::ViewCreateOptions {
+                    name,
+                    node_type,
+                    aggregates: aggregate,
+                    group_by,
+                    session_id: session,
+                    model,
+                    metadata,
+                };
+                commands::view::handle_view_create(&ctx, options).await?
+            }
+            ViewCommands::List => commands::view::handle_view_list(&ctx).await?,
+            ViewCommands::Show { name } => commands::view::handle_view_show(&ctx, &name).await?,
+            ViewCommands::Refresh { name } => {
+                commands::view::handle_view_refresh(&ctx, &name).await?
+            }
+            ViewCommands::Drop { name } => commands::view::handle_view_drop(&ctx, &name).await?,
+        },
+
         Commands::Template(template_cmd) => match template_cmd {
             TemplateCommands::Create {
                 name,
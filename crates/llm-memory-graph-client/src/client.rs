@@ -59,22 +59,59 @@ impl MemoryGraphClient {
 
     /// List sessions
     pub async fn list_sessions(&self, limit: i32, offset: i32) -> Result<Vec<proto::Session>> {
-        let request = proto::ListSessionsRequest { limit, offset };
+        let request = proto::ListSessionsRequest {
+            limit,
+            offset,
+            page_token: String::new(),
+        };
         let response = self.client.clone().list_sessions(request).await?;
         Ok(response.into_inner().sessions)
     }
 
+    /// List one page of sessions, starting after `page_token`
+    ///
+    /// Pass an empty token for the first page and the response's
+    /// `next_page_token` for the following ones.
+    pub async fn list_sessions_page(
+        &self,
+        limit: i32,
+        page_token: String,
+    ) -> Result<proto::ListSessionsResponse> {
+        let request = proto::ListSessionsRequest {
+            limit,
+            offset: 0,
+            page_token,
+        };
+        let response = self.client.clone().list_sessions(request).await?;
+        Ok(response.into_inner())
+    }
+
     /// Add a prompt
     pub async fn add_prompt(
         &self,
         session_id: String,
         content: String,
         metadata: Option<proto::PromptMetadata>,
+    ) -> Result<proto::PromptNode> {
+        self.add_prompt_with_attachments(session_id, content, metadata, Vec::new())
+            .await
+    }
+
+    /// Add a prompt with images, documents or other files attached
+    ///
+    /// Each attachment's size and SHA-256 must match its inline data.
+    pub async fn add_prompt_with_attachments(
+        &self,
+        session_id: String,
+        content: String,
+        metadata: Option<proto::PromptMetadata>,
+        attachments: Vec<proto::Attachment>,
     ) -> Result<proto::PromptNode> {
         let request = proto::AddPromptRequest {
             session_id,
             content,
             metadata,
+            attachments,
         };
         let response = self.client.clone().add_prompt(request).await?;
         Ok(response.into_inner())
@@ -87,17 +124,84 @@ impl MemoryGraphClient {
         content: String,
         token_usage: Option<proto::TokenUsage>,
         metadata: Option<proto::ResponseMetadata>,
+    ) -> Result<proto::ResponseNode> {
+        self.add_response_with_attachments(prompt_id, content, token_usage, metadata, Vec::new())
+            .await
+    }
+
+    /// Add a response with images, documents or other files attached
+    ///
+    /// Each attachment's size and SHA-256 must match its inline data.
+    pub async fn add_response_with_attachments(
+        &self,
+        prompt_id: String,
+        content: String,
+        token_usage: Option<proto::TokenUsage>,
+        metadata: Option<proto::ResponseMetadata>,
+        attachments: Vec<proto::Attachment>,
     ) -> Result<proto::ResponseNode> {
         let request = proto::AddResponseRequest {
             prompt_id,
             content,
             token_usage,
             metadata,
+            attachments,
         };
         let response = self.client.clone().add_response(request).await?;
         Ok(response.into_inner())
     }
 
+    /// Create a node of a registered custom kind
+    ///
+    /// `payload` is a JSON document and must satisfy the kind's schema.
+    pub async fn create_custom_node(
+        &self,
+        kind: String,
+        payload: String,
+        session_id: Option<String>,
+        metadata: HashMap<String, String>,
+    ) -> Result<proto::Node> {
+        let request = proto::CreateNodeRequest {
+            node: Some(proto::Node {
+                r#type: proto::NodeType::Custom as i32,
+                node_data: Some(proto::node::NodeData::Custom(proto::CustomNode {
+                    kind,
+                    payload,
+                    session_id,
+                    metadata,
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }),
+        };
+        let response = self.client.clone().create_node(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Create an edge of a registered custom type
+    ///
+    /// The endpoints and properties must fit the type's definition.
+    pub async fn create_custom_edge(
+        &self,
+        from_node_id: String,
+        to_node_id: String,
+        custom_type: String,
+        properties: HashMap<String, String>,
+    ) -> Result<proto::Edge> {
+        let request = proto::CreateEdgeRequest {
+            edge: Some(proto::Edge {
+                from_node_id,
+                to_node_id,
+                r#type: proto::EdgeType::Custom as i32,
+                properties,
+                custom_type,
+                ..Default::default()
+            }),
+        };
+        let response = self.client.clone().create_edge(request).await?;
+        Ok(response.into_inner())
+    }
+
     /// Query nodes
     pub async fn query(
         &self,
@@ -114,11 +218,112 @@ impl MemoryGraphClient {
             filters: HashMap::new(),
             after: None,
             before: None,
+            page_token: String::new(),
         };
         let response = self.client.clone().query(request).await?;
         Ok(response.into_inner())
     }
 
+    /// Query one page of nodes, starting after `page_token`
+    ///
+    /// Pass an empty token for the first page and the response's
+    /// `next_page_token` for the following ones.
+    pub async fn query_page(
+        &self,
+        session_id: Option<String>,
+        node_type: Option<i32>,
+        limit: i32,
+        page_token: String,
+    ) -> Result<proto::QueryResponse> {
+        let request = proto::QueryRequest {
+            session_id,
+            node_type,
+            limit,
+            offset: 0,
+            filters: HashMap::new(),
+            after: None,
+            before: None,
+            page_token,
+        };
+        let response = self.client.clone().query(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Execute a graph query expression (`MATCH ... RETURN ...`)
+    ///
+    /// Each row value in the response is JSON-encoded.
+    pub async fn execute_query(
+        &self,
+        expression: impl Into<String>,
+    ) -> Result<proto::ExpressionQueryResponse> {
+        let request = proto::ExpressionQueryRequest {
+            expression: expression.into(),
+        };
+        let response = self.client.clone().execute_query(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Full-text search over node content
+    pub async fn search(
+        &self,
+        query: impl Into<String>,
+        session_id: Option<String>,
+        limit: i32,
+    ) -> Result<proto::SearchResponse> {
+        let request = proto::SearchRequest {
+            query: query.into(),
+            session_id,
+            node_types: Vec::new(),
+            after: None,
+            before: None,
+            limit,
+            offset: 0,
+        };
+        let response = self.client.clone().search(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Aggregate token usage, latency and tool durations
+    ///
+    /// `group_by` takes dimensions such as `model` or `day`, and `aggregates`
+    /// expressions such as `sum(total_tokens)` or `p95(latency_ms)`.
+    pub async fn aggregate(
+        &self,
+        session_id: Option<String>,
+        group_by: Vec<String>,
+        aggregates: Vec<String>,
+    ) -> Result<proto::AggregateResponse> {
+        let request = proto::AggregateRequest {
+            query: Some(proto::QueryRequest {
+                session_id,
+                ..Default::default()
+            }),
+            group_by,
+            aggregates,
+        };
+        let response = self.client.clone().aggregate(request).await?;
+        Ok(response.into_inner())
+    }
+
+    /// Page through the nodes listed under a reverse lookup, newest first
+    ///
+    /// Pass the previous response's `next_page_token` to continue; an empty
+    /// token starts from the newest node.
+    pub async fn lookup(
+        &self,
+        key: proto::lookup_request::Key,
+        limit: i32,
+        page_token: String,
+    ) -> Result<proto::LookupResponse> {
+        let request = proto::LookupRequest {
+            key: Some(key),
+            limit,
+            page_token,
+        };
+        let response = self.client.clone().lookup(request).await?;
+        Ok(response.into_inner())
+    }
+
     /// Get service health
     pub async fn health(&self) -> Result<proto::HealthResponse> {
         let request = tonic::Request::new(());
@@ -2,6 +2,22 @@
 
 use std::path::PathBuf;
 
+/// How the engines treat edges that break the built-in edge constraints
+///
+/// The constraints cover which node types each built-in edge type may
+/// connect, how many such edges a node may have, and whether typed edge
+/// properties parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConstraintMode {
+    /// Do not check edges
+    Off,
+    /// Log a warning and store the edge anyway
+    #[default]
+    Warn,
+    /// Reject the edge with a validation error
+    Strict,
+}
+
 /// Configuration for `MemoryGraph`
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -15,6 +31,10 @@ pub struct Config {
     pub compression_level: u8,
     /// Flush interval in milliseconds (0 = sync every write)
     pub flush_interval_ms: u64,
+    /// Query result pages to cache (0 = no query cache)
+    pub query_cache_size: usize,
+    /// How edges breaking the built-in edge constraints are handled
+    pub edge_constraints: ConstraintMode,
 }
 
 impl Config {
@@ -27,6 +47,8 @@ impl Config {
             enable_wal: true,
             compression_level: 3,
             flush_interval_ms: 1000,
+            query_cache_size: 0,
+            edge_constraints: ConstraintMode::Warn,
         }
     }
 
@@ -57,6 +79,20 @@ impl Config {
         self.flush_interval_ms = interval_ms;
         self
     }
+
+    /// Cache up to `entries` query result pages (0 disables the cache)
+    #[must_use]
+    pub const fn with_query_cache(mut self, entries: usize) -> Self {
+        self.query_cache_size = entries;
+        self
+    }
+
+    /// Set how edges breaking the built-in edge constraints are handled
+    #[must_use]
+    pub const fn with_edge_constraints(mut self, mode: ConstraintMode) -> Self {
+        self.edge_constraints = mode;
+        self
+    }
 }
 
 impl Default for Config {
@@ -67,6 +103,8 @@ impl Default for Config {
             enable_wal: true,
             compression_level: 3,
             flush_interval_ms: 1000,
+            query_cache_size: 0,
+            edge_constraints: ConstraintMode::Warn,
         }
     }
 }
@@ -88,12 +126,16 @@ mod tests {
             .with_cache_size(200)
             .with_wal(false)
             .with_compression(5)
-            .with_flush_interval(2000);
+            .with_flush_interval(2000)
+            .with_query_cache(500)
+            .with_edge_constraints(ConstraintMode::Strict);
 
         assert_eq!(config.cache_size_mb, 200);
         assert!(!config.enable_wal);
         assert_eq!(config.compression_level, 5);
         assert_eq!(config.flush_interval_ms, 2000);
+        assert_eq!(config.query_cache_size, 500);
+        assert_eq!(config.edge_constraints, ConstraintMode::Strict);
     }
 
     #[test]
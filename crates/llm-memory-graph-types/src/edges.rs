@@ -3,7 +3,7 @@
 //! This module provides strongly-typed edge definitions with property validation
 //! for enterprise-grade graph operations.
 
-use super::{EdgeId, NodeId};
+use super::{EdgeId, NodeId, SessionId};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -29,6 +29,10 @@ pub enum EdgeType {
     Inherits,
     /// Links a prompt to external context sources (Prompt → ExternalContext)
     References,
+    /// Links a forked session to the prompt or response it branched from (Session → Prompt)
+    BranchesFrom,
+    /// A user-defined relationship, registered by name
+    Custom(String),
 }
 
 // ===== Edge Property Structs =====
@@ -432,6 +436,44 @@ impl ReferencesProperties {
     }
 }
 
+/// Properties for `BRANCHES_FROM` edge (Session → Prompt)
+///
+/// Records which session a fork was taken from, so the shared history can be
+/// found without loading the node it points at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchesFromProperties {
+    /// Session the fork point belongs to
+    pub parent_session: SessionId,
+}
+
+impl BranchesFromProperties {
+    /// Create new branch properties
+    pub fn new(parent_session: SessionId) -> Self {
+        Self { parent_session }
+    }
+
+    /// Convert to property map for storage
+    pub fn to_properties(&self) -> HashMap<String, String> {
+        let mut props = HashMap::new();
+        props.insert(
+            "parent_session".to_string(),
+            self.parent_session.to_string(),
+        );
+        props
+    }
+
+    /// Parse from property map
+    pub fn from_properties(props: &HashMap<String, String>) -> Result<Self, String> {
+        let parent_session = props
+            .get("parent_session")
+            .and_then(|s| uuid::Uuid::parse_str(s).ok())
+            .map(SessionId::from)
+            .ok_or("Missing or invalid parent_session")?;
+
+        Ok(Self { parent_session })
+    }
+}
+
 /// An edge connecting two nodes in the graph
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Edge {
@@ -609,6 +651,14 @@ impl Edge {
         Self::with_properties(from, to, EdgeType::References, properties.to_properties())
     }
 
+    /// Create a `BRANCHES_FROM` edge with properties
+    ///
+    /// Links a forked session node to the last node it shares with its parent.
+    #[must_use]
+    pub fn branches_from(from: NodeId, to: NodeId, properties: &BranchesFromProperties) -> Self {
+        Self::with_properties(from, to, EdgeType::BranchesFrom, properties.to_properties())
+    }
+
     // ===== Property Extraction Methods =====
 
     /// Extract INSTANTIATES properties from edge
@@ -665,6 +715,17 @@ impl Edge {
         }
         ReferencesProperties::from_properties(&self.properties).ok()
     }
+
+    /// Extract `BRANCHES_FROM` properties from edge
+    ///
+    /// Returns None if edge is not of type `BranchesFrom` or properties are invalid.
+    #[must_use]
+    pub fn get_branches_from_properties(&self) -> Option<BranchesFromProperties> {
+        if self.edge_type != EdgeType::BranchesFrom {
+            return None;
+        }
+        BranchesFromProperties::from_properties(&self.properties).ok()
+    }
 }
 
 #[cfg(test)]
@@ -1032,6 +1093,24 @@ mod tests {
         assert_eq!(extracted.chunk_id, Some("section_5".to_string()));
     }
 
+    #[test]
+    fn test_branches_from_edge_builder() {
+        let session_node = NodeId::new();
+        let fork_point = NodeId::new();
+        let parent = SessionId::new();
+
+        let edge = Edge::branches_from(
+            session_node,
+            fork_point,
+            &BranchesFromProperties::new(parent),
+        );
+
+        assert_eq!(edge.edge_type, EdgeType::BranchesFrom);
+        let extracted = edge.get_branches_from_properties().unwrap();
+        assert_eq!(extracted.parent_session, parent);
+        assert!(BranchesFromProperties::from_properties(&HashMap::new()).is_err());
+    }
+
     // ===== Property Extraction Tests =====
 
     #[test]
@@ -1046,6 +1125,7 @@ mod tests {
         assert!(edge.get_invokes_properties().is_none());
         assert!(edge.get_transfers_to_properties().is_none());
         assert!(edge.get_references_properties().is_none());
+        assert!(edge.get_branches_from_properties().is_none());
     }
 
     #[test]
@@ -1063,5 +1143,6 @@ mod tests {
         let _instantiates = Edge::new(from, to, EdgeType::Instantiates);
         let _inherits = Edge::new(from, to, EdgeType::Inherits);
         let _references = Edge::new(from, to, EdgeType::References);
+        let _branches_from = Edge::new(from, to, EdgeType::BranchesFrom);
     }
 }
@@ -105,6 +105,18 @@ pub enum Error {
     #[error("Prometheus error: {0}")]
     PrometheusError(String),
 
+    /// Attempted to mutate a graph opened in read-only mode
+    #[error("Read-only graph: {0}")]
+    ReadOnly(String),
+
+    /// Database is locked by another process
+    #[error("Database locked: {0}")]
+    DatabaseLocked(String),
+
+    /// Operation not supported by the storage backend
+    #[error("Unsupported operation: {0}")]
+    Unsupported(String),
+
     /// Other error
     #[error("Other error: {0}")]
     Other(String),
@@ -42,16 +42,18 @@ pub mod nodes;
 pub mod utils;
 
 // Re-export main types
-pub use config::Config;
+pub use config::{Config, ConstraintMode};
 pub use edges::{
-    ContextType, Edge, EdgeType, InheritsProperties, InstantiatesProperties, InvokesProperties,
-    Priority, ReferencesProperties, TransfersToProperties,
+    BranchesFromProperties, ContextType, Edge, EdgeType, InheritsProperties,
+    InstantiatesProperties, InvokesProperties, Priority, ReferencesProperties,
+    TransfersToProperties,
 };
 pub use error::{Error, Result};
 pub use ids::{AgentId, EdgeId, NodeId, SessionId, TemplateId};
 pub use nodes::{
-    AgentConfig, AgentMetrics, AgentNode, AgentStatus, ConversationSession, Node, NodeType,
-    PromptMetadata, PromptNode, PromptTemplate, ResponseMetadata, ResponseNode, TokenUsage,
-    ToolInvocation, VariableSpec, Version, VersionLevel,
+    AgentConfig, AgentMetrics, AgentNode, AgentStatus, Attachment, AttachmentBody,
+    ConversationSession, CustomNode, Modality, Node, NodeType, PromptMetadata, PromptNode,
+    PromptTemplate, ResponseMetadata, ResponseNode, TokenUsage, ToolInvocation, VariableSpec,
+    Version, VersionLevel,
 };
 pub use utils::*;
@@ -21,6 +21,8 @@ pub enum NodeType {
     Agent,
     /// A versioned prompt template
     Template,
+    /// A user-defined node of a registered kind
+    Custom,
 }
 
 /// Generic node wrapper that contains any node type
@@ -38,6 +40,8 @@ pub enum Node {
     Agent(AgentNode),
     /// Template node
     Template(PromptTemplate),
+    /// User-defined node
+    Custom(CustomNode),
 }
 
 impl Node {
@@ -51,6 +55,7 @@ impl Node {
             Node::ToolInvocation(t) => t.id,
             Node::Agent(a) => a.node_id,
             Node::Template(t) => t.node_id,
+            Node::Custom(c) => c.id,
         }
     }
 
@@ -64,6 +69,7 @@ impl Node {
             Node::ToolInvocation(_) => NodeType::ToolInvocation,
             Node::Agent(_) => NodeType::Agent,
             Node::Template(_) => NodeType::Template,
+            Node::Custom(_) => NodeType::Custom,
         }
     }
 }
@@ -154,6 +160,106 @@ impl Default for PromptMetadata {
     }
 }
 
+/// Broad category of an attachment, derived from its media type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modality {
+    /// `image/*`
+    Image,
+    /// `audio/*`
+    Audio,
+    /// `video/*`
+    Video,
+    /// PDFs and other documents
+    Document,
+    /// `text/*`
+    Text,
+    /// Anything else
+    Other,
+}
+
+/// Where an attachment's bytes are kept
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttachmentBody {
+    /// The bytes, stored with the node
+    Inline(Vec<u8>),
+    /// Key of the bytes in the graph's blob store
+    Blob(String),
+}
+
+/// An image, document, audio clip or other file attached to a prompt or response
+///
+/// The bytes are optional: an attachment may only describe content kept
+/// elsewhere, identified by its source URI and hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// Media type, such as `image/png` or `application/pdf`
+    pub media_type: String,
+    /// Size of the content in bytes
+    pub size: u64,
+    /// Lowercase hex SHA-256 of the content
+    pub sha256: String,
+    /// The content, inline or by blob reference, if the graph holds it
+    pub body: Option<AttachmentBody>,
+    /// Where the content came from
+    pub source_uri: Option<String>,
+    /// File name, if known
+    pub name: Option<String>,
+}
+
+impl Attachment {
+    /// Describe content the graph does not hold
+    #[must_use]
+    pub fn external(media_type: impl Into<String>, size: u64, sha256: impl Into<String>) -> Self {
+        Self {
+            media_type: media_type.into(),
+            size,
+            sha256: sha256.into(),
+            body: None,
+            source_uri: None,
+            name: None,
+        }
+    }
+
+    /// Set the source URI
+    #[must_use]
+    pub fn with_source_uri(mut self, uri: impl Into<String>) -> Self {
+        self.source_uri = Some(uri.into());
+        self
+    }
+
+    /// Set the file name
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The category of the media type
+    pub fn modality(&self) -> Modality {
+        let media_type = self.media_type.to_ascii_lowercase();
+        match media_type.split('/').next().unwrap_or_default() {
+            "image" => Modality::Image,
+            "audio" => Modality::Audio,
+            "video" => Modality::Video,
+            "text" => Modality::Text,
+            _ if media_type == "application/pdf" || media_type.contains("document") => {
+                Modality::Document
+            }
+            _ => Modality::Other,
+        }
+    }
+
+    /// The inline bytes, if the content is stored with the node
+    pub fn inline_data(&self) -> Option<&[u8]> {
+        match &self.body {
+            Some(AttachmentBody::Inline(bytes)) => Some(bytes),
+            _ => None,
+        }
+    }
+}
+
 /// A prompt node representing input to an LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptNode {
@@ -171,6 +277,9 @@ pub struct PromptNode {
     pub variables: HashMap<String, String>,
     /// Metadata about the prompt
     pub metadata: PromptMetadata,
+    /// Images, documents and other files sent with the prompt
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
 impl PromptNode {
@@ -185,6 +294,7 @@ impl PromptNode {
             content,
             variables: HashMap::new(),
             metadata: PromptMetadata::default(),
+            attachments: Vec::new(),
         }
     }
 
@@ -199,6 +309,7 @@ impl PromptNode {
             content,
             variables: HashMap::new(),
             metadata,
+            attachments: Vec::new(),
         }
     }
 
@@ -218,6 +329,7 @@ impl PromptNode {
             content,
             variables,
             metadata: PromptMetadata::default(),
+            attachments: Vec::new(),
         }
     }
 }
@@ -284,6 +396,9 @@ pub struct ResponseNode {
     pub usage: TokenUsage,
     /// Metadata about the response
     pub metadata: ResponseMetadata,
+    /// Images, documents and other files the response produced
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
 }
 
 impl ResponseNode {
@@ -297,6 +412,7 @@ impl ResponseNode {
             content,
             usage,
             metadata: ResponseMetadata::default(),
+            attachments: Vec::new(),
         }
     }
 
@@ -315,6 +431,7 @@ impl ResponseNode {
             content,
             usage,
             metadata,
+            attachments: Vec::new(),
         }
     }
 }
@@ -996,6 +1113,71 @@ impl PromptTemplate {
     }
 }
 
+/// A node of a user-defined kind, such as a document, ticket or evaluation result
+///
+/// The payload is free-form JSON; registering the kind with a schema lets the
+/// graph check payloads before they are stored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomNode {
+    /// Unique node identifier
+    pub id: NodeId,
+    /// Name of the registered kind
+    pub kind: String,
+    /// Kind-specific content
+    pub payload: serde_json::Value,
+    /// Session the node belongs to, if any
+    pub session_id: Option<SessionId>,
+    /// When the node was created
+    pub created_at: DateTime<Utc>,
+    /// When the node was last updated
+    pub updated_at: DateTime<Utc>,
+    /// Additional metadata
+    pub metadata: HashMap<String, String>,
+}
+
+impl CustomNode {
+    /// Create a node of `kind` outside any session
+    #[must_use]
+    pub fn new(kind: String, payload: serde_json::Value) -> Self {
+        let now = Utc::now();
+        Self {
+            id: NodeId::new(),
+            kind,
+            payload,
+            session_id: None,
+            created_at: now,
+            updated_at: now,
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Create a node of `kind` belonging to a session
+    #[must_use]
+    pub fn in_session(session_id: SessionId, kind: String, payload: serde_json::Value) -> Self {
+        let mut node = Self::new(kind, payload);
+        node.session_id = Some(session_id);
+        node
+    }
+
+    /// Look up a payload value by a dot-separated path such as `"author.name"`
+    ///
+    /// Array elements are addressed by index, as in `"labels.0"`.
+    pub fn field(&self, path: &str) -> Option<&serde_json::Value> {
+        path.split('.')
+            .try_fold(&self.payload, |value, segment| match value {
+                serde_json::Value::Object(map) => map.get(segment),
+                serde_json::Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+                _ => None,
+            })
+    }
+
+    /// Add metadata to the node
+    pub fn add_metadata(&mut self, key: String, value: String) {
+        self.metadata.insert(key, value);
+        self.updated_at = Utc::now();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1032,6 +1214,28 @@ mod tests {
         assert_eq!(response.usage.total_tokens, 30);
     }
 
+    #[test]
+    fn test_attachment_modality_and_defaults() {
+        let image = Attachment::external("image/PNG", 3, "abc").with_name("cat.png");
+        assert_eq!(image.modality(), Modality::Image);
+        assert!(image.inline_data().is_none());
+        assert_eq!(
+            Attachment::external("application/pdf", 1, "abc").modality(),
+            Modality::Document
+        );
+        assert_eq!(
+            Attachment::external("application/zip", 1, "abc").modality(),
+            Modality::Other
+        );
+
+        // Prompts stored before attachments existed still load
+        let mut value =
+            serde_json::to_value(PromptNode::new(SessionId::new(), "Hi".to_string())).unwrap();
+        value.as_object_mut().unwrap().remove("attachments");
+        let prompt: PromptNode = serde_json::from_value(value).unwrap();
+        assert!(prompt.attachments.is_empty());
+    }
+
     #[test]
     fn test_token_usage() {
         let usage = TokenUsage::new(100, 50);
@@ -1709,4 +1913,28 @@ mod tests {
         );
         assert_eq!(template.metadata.get("priority"), Some(&"high".to_string()));
     }
+
+    #[test]
+    fn test_custom_node_fields() {
+        let session_id = SessionId::new();
+        let node = CustomNode::in_session(
+            session_id,
+            "ticket".to_string(),
+            serde_json::json!({"title": "Login fails", "labels": ["auth", "p1"], "owner": {"team": "identity"}}),
+        );
+
+        assert_eq!(node.session_id, Some(session_id));
+        assert_eq!(node.field("title"), Some(&serde_json::json!("Login fails")));
+        assert_eq!(node.field("labels.1"), Some(&serde_json::json!("p1")));
+        assert_eq!(
+            node.field("owner.team"),
+            Some(&serde_json::json!("identity"))
+        );
+        assert_eq!(node.field("owner.name"), None);
+        assert_eq!(node.field("title.0"), None);
+
+        let wrapped = Node::Custom(node.clone());
+        assert_eq!(wrapped.id(), node.id);
+        assert_eq!(wrapped.node_type(), NodeType::Custom);
+    }
 }
@@ -3,6 +3,10 @@
 //! This script uses tonic-build to generate Rust code from .proto files.
 //! The generated code is placed in the OUT_DIR and included via the
 //! `tonic::include_proto!` macro in the grpc module.
+//!
+//! `proto/memory_graph.proto` is a copy of the workspace-level proto, kept
+//! inside the crate so that it is packaged with it. Run
+//! `scripts/check_proto_sync.sh --fix` after changing the workspace proto.
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Compile the protobuf definitions
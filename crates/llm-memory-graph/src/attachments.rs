@@ -0,0 +1,292 @@
+//! Multi-modal attachments on prompts and responses
+//!
+//! Images, documents and audio sent with a prompt or produced by a response
+//! are recorded as [`Attachment`]s on the node. An attachment always carries
+//! its media type, size and SHA-256 hash; the bytes themselves are optional.
+//! Small content is kept inline with the node, while content larger than
+//! [`INLINE_LIMIT`] is moved into the graph's blob store, where it is keyed
+//! by its hash so identical files are stored once, and the attachment keeps
+//! a blob reference instead.
+//!
+//! Blobs are not removed when the nodes referencing them are deleted.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::attachments;
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::Config;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let session = graph.create_session().await?;
+//! let prompt = graph.add_prompt(session.id, "What is in this picture?".to_string(), None).await?;
+//!
+//! let image = attachments::from_bytes("image/png", std::fs::read("cat.png")?)
+//!     .with_source_uri("file:///tmp/cat.png");
+//! let stored = graph.add_attachment(prompt, image).await?;
+//! let bytes = graph.attachment_data(&stored).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::storage::{AsyncStorageBackend, StorageBackend};
+use crate::{Attachment, AttachmentBody, Error, Node, Result};
+use ring::digest;
+use std::fmt::Write;
+
+/// Largest content, in bytes, kept inline with its node
+pub const INLINE_LIMIT: usize = 64 * 1024;
+
+const BLOB_PREFIX: &str = "blob/";
+
+/// Lowercase hex SHA-256 of `bytes`
+#[must_use]
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    let hash = digest::digest(&digest::SHA256, bytes);
+    hash.as_ref()
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        })
+}
+
+/// An attachment holding `bytes` inline, with its size and hash filled in
+#[must_use]
+pub fn from_bytes(media_type: impl Into<String>, bytes: Vec<u8>) -> Attachment {
+    let mut attachment = Attachment::external(media_type, bytes.len() as u64, sha256_hex(&bytes));
+    attachment.body = Some(AttachmentBody::Inline(bytes));
+    attachment
+}
+
+fn blob_key(sha256: &str) -> Vec<u8> {
+    format!("{BLOB_PREFIX}{sha256}").into_bytes()
+}
+
+/// Check the fields an attachment carries about itself
+fn validate(attachment: &Attachment) -> Result<()> {
+    let (kind, subtype) = attachment.media_type.split_once('/').unwrap_or_default();
+    if kind.is_empty() || subtype.is_empty() {
+        return Err(Error::ValidationError(format!(
+            "Invalid media type '{}'",
+            attachment.media_type
+        )));
+    }
+    if attachment.sha256.len() != 64
+        || !attachment
+            .sha256
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    {
+        return Err(Error::ValidationError(format!(
+            "Attachment hash '{}' is not a lowercase hex SHA-256",
+            attachment.sha256
+        )));
+    }
+    if let Some(bytes) = attachment.inline_data() {
+        if bytes.len() as u64 != attachment.size {
+            return Err(Error::ValidationError(format!(
+                "Attachment declares {} bytes but holds {}",
+                attachment.size,
+                bytes.len()
+            )));
+        }
+        if sha256_hex(bytes) != attachment.sha256 {
+            return Err(Error::ValidationError(
+                "Attachment content does not match its hash".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Store `bytes` in the blob store, returning their key
+pub(crate) async fn put_blob(storage: &dyn AsyncStorageBackend, bytes: &[u8]) -> Result<String> {
+    let sha256 = sha256_hex(bytes);
+    let key = blob_key(&sha256);
+    if storage.get_catalog_entry(&key).await?.is_none() {
+        storage.write_catalog(&[(key, bytes.to_vec())], &[]).await?;
+    }
+    Ok(sha256)
+}
+
+/// The blob stored under `sha256`, if any
+pub(crate) async fn get_blob(
+    storage: &dyn AsyncStorageBackend,
+    sha256: &str,
+) -> Result<Option<Vec<u8>>> {
+    storage.get_catalog_entry(&blob_key(sha256)).await
+}
+
+/// Check an attachment and move large inline content into the blob store
+pub(crate) async fn prepare(
+    storage: &dyn AsyncStorageBackend,
+    mut attachment: Attachment,
+) -> Result<Attachment> {
+    validate(&attachment)?;
+    match &attachment.body {
+        Some(AttachmentBody::Inline(bytes)) if bytes.len() > INLINE_LIMIT => {
+            let key = put_blob(storage, bytes).await?;
+            attachment.body = Some(AttachmentBody::Blob(key));
+        }
+        Some(AttachmentBody::Blob(key)) if get_blob(storage, key).await?.is_none() => {
+            return Err(Error::ValidationError(format!(
+                "Blob '{key}' does not exist"
+            )));
+        }
+        _ => {}
+    }
+    Ok(attachment)
+}
+
+/// [`prepare`] every attachment of a prompt or response
+pub(crate) async fn prepare_node(storage: &dyn AsyncStorageBackend, node: &mut Node) -> Result<()> {
+    let attachments = match node {
+        Node::Prompt(prompt) => &mut prompt.attachments,
+        Node::Response(response) => &mut response.attachments,
+        _ => return Ok(()),
+    };
+    for attachment in attachments.iter_mut() {
+        *attachment = prepare(storage, attachment.clone()).await?;
+    }
+    Ok(())
+}
+
+/// The content of an attachment, if the graph holds it
+pub(crate) async fn load(
+    storage: &dyn AsyncStorageBackend,
+    attachment: &Attachment,
+) -> Result<Option<Vec<u8>>> {
+    match &attachment.body {
+        Some(AttachmentBody::Inline(bytes)) => Ok(Some(bytes.clone())),
+        Some(AttachmentBody::Blob(key)) => get_blob(storage, key).await,
+        None => Ok(None),
+    }
+}
+
+/// [`prepare`] through a synchronous backend
+pub(crate) fn prepare_sync(
+    storage: &dyn StorageBackend,
+    mut attachment: Attachment,
+) -> Result<Attachment> {
+    validate(&attachment)?;
+    match &attachment.body {
+        Some(AttachmentBody::Inline(bytes)) if bytes.len() > INLINE_LIMIT => {
+            let sha256 = sha256_hex(bytes);
+            let key = blob_key(&sha256);
+            if storage.get_catalog_entry(&key)?.is_none() {
+                storage.write_catalog(&[(key, bytes.clone())], &[])?;
+            }
+            attachment.body = Some(AttachmentBody::Blob(sha256));
+        }
+        Some(AttachmentBody::Blob(key)) if storage.get_catalog_entry(&blob_key(key))?.is_none() => {
+            return Err(Error::ValidationError(format!(
+                "Blob '{key}' does not exist"
+            )));
+        }
+        _ => {}
+    }
+    Ok(attachment)
+}
+
+/// [`load`] through a synchronous backend
+pub(crate) fn load_sync(
+    storage: &dyn StorageBackend,
+    attachment: &Attachment,
+) -> Result<Option<Vec<u8>>> {
+    match &attachment.body {
+        Some(AttachmentBody::Inline(bytes)) => Ok(Some(bytes.clone())),
+        Some(AttachmentBody::Blob(key)) => storage.get_catalog_entry(&blob_key(key)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::AsyncMemoryGraph;
+    use crate::{Config, MemoryGraph, TokenUsage};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_sha256_hex() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_attachments_inline_and_blob() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Describe these".to_string(), None)
+            .await
+            .unwrap();
+
+        let small = graph
+            .add_attachment(prompt, from_bytes("image/png", vec![1, 2, 3]))
+            .await
+            .unwrap();
+        assert_eq!(small.inline_data(), Some(&[1, 2, 3][..]));
+
+        let pdf = vec![7u8; INLINE_LIMIT + 1];
+        let large = graph
+            .add_attachment(
+                prompt,
+                from_bytes("application/pdf", pdf.clone()).with_name("report.pdf"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(large.body, Some(AttachmentBody::Blob(large.sha256.clone())));
+        assert_eq!(graph.attachment_data(&large).await.unwrap(), Some(pdf));
+
+        let Some(Node::Prompt(stored)) = graph.get_node(&prompt).await.unwrap() else {
+            panic!("prompt missing");
+        };
+        assert_eq!(stored.attachments, vec![small, large]);
+
+        // Responses take attachments too, but other nodes and bad hashes don't
+        let response = graph
+            .add_response(prompt, "A cat".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        let mut forged = from_bytes("image/png", vec![1]);
+        forged.size = 2;
+        assert!(graph.add_attachment(response, forged).await.is_err());
+        assert!(graph
+            .add_attachment(response, Attachment::external("image", 1, sha256_hex(b"x")))
+            .await
+            .is_err());
+        let session_node = graph.get_session_nodes(&session.id).await.unwrap();
+        let session_node = session_node
+            .iter()
+            .find(|n| matches!(n, Node::Session(_)))
+            .unwrap();
+        assert!(graph
+            .add_attachment(session_node.id(), from_bytes("image/png", vec![1]))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn test_sync_attachments() {
+        let dir = tempdir().unwrap();
+        let graph = MemoryGraph::open(Config::new(dir.path())).unwrap();
+        let session = graph.create_session().unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Listen".to_string(), None)
+            .unwrap();
+        let audio = vec![3u8; INLINE_LIMIT * 2];
+        let stored = graph
+            .add_attachment(prompt, from_bytes("audio/wav", audio.clone()))
+            .unwrap();
+        assert!(stored.inline_data().is_none());
+        assert_eq!(graph.attachment_data(&stored).unwrap(), Some(audio));
+    }
+}
@@ -32,6 +32,8 @@
 //! cargo run --bin server
 //! ```
 
+use llm_memory_graph::grpc::proto::memory_graph_service_server::MemoryGraphServiceServer;
+use llm_memory_graph::grpc::{MemoryGraphServiceImpl, ServiceConfig, MAX_MESSAGE_SIZE};
 use llm_memory_graph::{engine::AsyncMemoryGraph, observatory::PrometheusMetrics, Config};
 use prometheus::Registry;
 use std::sync::Arc;
@@ -169,7 +171,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let graph = Arc::new(
         AsyncMemoryGraph::open(graph_config)
             .await
-            .map_err(|e| format!("Failed to open memory graph: {}", e))?,
+            .map_err(|e| format!("Failed to open memory graph: {}", e))?
+            .with_query_metrics(Arc::clone(&_metrics)),
     );
     info!("Memory graph database opened successfully");
 
@@ -221,9 +224,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.metrics_port
     );
 
-    // Note: gRPC service implementation would go here
-    // For now, we create a placeholder that demonstrates the structure
-    info!("gRPC service not yet fully implemented");
+    // Spawn gRPC server
+    let grpc_addr = config
+        .grpc_address()
+        .parse()
+        .map_err(|e| format!("Invalid gRPC address {}: {}", config.grpc_address(), e))?;
+    let service = MemoryGraphServiceImpl::new(
+        Arc::clone(&graph),
+        Some(Arc::clone(&_metrics)),
+        ServiceConfig {
+            host: config.grpc_host.clone(),
+            port: config.grpc_port,
+            start_time: config.start_time,
+            ..ServiceConfig::default()
+        },
+    );
+    let grpc_handle = tokio::spawn(async move {
+        let server = MemoryGraphServiceServer::new(service)
+            .max_decoding_message_size(MAX_MESSAGE_SIZE)
+            .max_encoding_message_size(MAX_MESSAGE_SIZE);
+        if let Err(e) = tonic::transport::Server::builder()
+            .add_service(server)
+            .serve(grpc_addr)
+            .await
+        {
+            error!("gRPC server error: {}", e);
+        }
+    });
+
+    info!("gRPC server started on {}", config.grpc_address());
     info!("Server initialization complete");
 
     // Wait for shutdown signal
@@ -241,7 +270,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Graceful shutdown
     info!("Starting graceful shutdown...");
 
-    // Abort metrics server
+    // Stop accepting requests before flushing
+    grpc_handle.abort();
     _metrics_handle.abort();
 
     // Flush database
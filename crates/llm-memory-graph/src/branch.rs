@@ -0,0 +1,441 @@
+//! Conversation branching
+//!
+//! Editing a message and regenerating should keep both versions of a
+//! conversation. [`AsyncMemoryGraph::fork_session`] starts a child session
+//! that shares its parent's history up to a prompt or response. Nothing is
+//! copied: the child session node gets a [`EdgeType::BranchesFrom`] edge to
+//! the fork point, and new turns go into the child session as usual.
+//!
+//! Each branch stays an ordinary session, so session queries only see the
+//! turns added to that branch. [`AsyncMemoryGraph::branch_thread`] follows the
+//! fork edges back to the root and returns the whole linear conversation. The
+//! history shared with a parent is found by walking back from the fork point
+//! from each response to its prompt and each prompt to the one before it, so a
+//! response that finished after the fork still belongs to it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::{Config, TokenUsage};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let session = graph.create_session().await?;
+//! let first = graph.add_prompt(session.id, "Plan a trip".to_string(), None).await?;
+//! let reply = graph
+//!     .add_response(first, "Where to?".to_string(), TokenUsage::new(3, 3), None)
+//!     .await?;
+//! graph.add_prompt(session.id, "Somewhere warm".to_string(), None).await?;
+//!
+//! // The user edits their second message instead
+//! let branch = graph.fork_session(reply).await?;
+//! graph.add_prompt(branch.id, "Somewhere cold".to_string(), None).await?;
+//!
+//! let thread = graph.branch_thread(branch.id).await?;
+//! assert_eq!(thread.len(), 3);
+//! assert_eq!(graph.list_branches(session.id).await?.len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::engine::AsyncMemoryGraph;
+use crate::search::node_timestamp;
+use crate::{
+    BranchesFromProperties, ConversationSession, Edge, EdgeType, Error, Node, NodeId, PromptNode,
+    Result, SessionId,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+
+/// A session forked from another session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Branch {
+    /// The forked session
+    pub session_id: SessionId,
+    /// Session the branch was forked from
+    pub parent_session: SessionId,
+    /// Last prompt or response shared with the parent
+    pub fork_point: NodeId,
+    /// When the fork was made
+    pub created_at: DateTime<Utc>,
+}
+
+impl Branch {
+    fn from_edge(session_id: SessionId, edge: &Edge) -> Result<Self> {
+        let properties = edge.get_branches_from_properties().ok_or_else(|| {
+            Error::TraversalError(format!(
+                "Branch edge {} is missing its parent session",
+                edge.id
+            ))
+        })?;
+        Ok(Self {
+            session_id,
+            parent_session: properties.parent_session,
+            fork_point: edge.to,
+            created_at: edge.created_at,
+        })
+    }
+}
+
+impl AsyncMemoryGraph {
+    /// Start a new session that shares history up to `at_node`
+    ///
+    /// `at_node` is the last prompt or response the branch keeps; anything
+    /// added to the parent after it is not part of the branch. To replace a
+    /// prompt, fork at the response before it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node does not exist, is not a prompt or
+    /// response, or storage fails.
+    pub async fn fork_session(&self, at_node: NodeId) -> Result<ConversationSession> {
+        let node = self
+            .get_node(&at_node)
+            .await?
+            .ok_or_else(|| Error::NodeNotFound(at_node.to_string()))?;
+        let parent = match &node {
+            Node::Prompt(prompt) => prompt.session_id,
+            Node::Response(response) => match self.get_node(&response.prompt_id).await? {
+                Some(Node::Prompt(prompt)) => prompt.session_id,
+                _ => {
+                    return Err(Error::TraversalError(format!(
+                        "Response {at_node} does not point to a prompt"
+                    )))
+                }
+            },
+            other => {
+                return Err(Error::ValidationError(format!(
+                    "Can only fork at a prompt or response, not a {:?} node",
+                    other.node_type()
+                )))
+            }
+        };
+        self.get_session(parent).await?;
+
+        let session = self.create_session().await?;
+        let edge = Edge::branches_from(
+            session.node_id,
+            at_node,
+            &BranchesFromProperties::new(parent),
+        );
+        self.store_edges_batch(vec![edge]).await?;
+
+        Ok(session)
+    }
+
+    /// The fork a session was created from, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session does not exist or storage fails.
+    pub async fn parent_branch(&self, session_id: SessionId) -> Result<Option<Branch>> {
+        let session = self.get_session(session_id).await?;
+        self.get_outgoing_edges(&session.node_id)
+            .await?
+            .iter()
+            .find(|edge| edge.edge_type == EdgeType::BranchesFrom)
+            .map(|edge| Branch::from_edge(session_id, edge))
+            .transpose()
+    }
+
+    /// Sessions forked directly from a session, oldest first
+    ///
+    /// Forks of those branches are not included; call this again on each
+    /// branch to walk the whole tree.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session does not exist or storage fails.
+    pub async fn list_branches(&self, session_id: SessionId) -> Result<Vec<Branch>> {
+        self.get_session(session_id).await?;
+
+        let mut branches = Vec::new();
+        for node in self.get_session_nodes(&session_id).await? {
+            if !matches!(node, Node::Prompt(_) | Node::Response(_)) {
+                continue;
+            }
+            for edge in self.get_incoming_edges(&node.id()).await? {
+                if edge.edge_type != EdgeType::BranchesFrom {
+                    continue;
+                }
+                if let Some(Node::Session(child)) = self.get_node(&edge.from).await? {
+                    branches.push(Branch::from_edge(child.id, &edge)?);
+                }
+            }
+        }
+        branches.sort_by_key(|b| b.created_at);
+
+        Ok(branches)
+    }
+
+    /// The full conversation of a branch in chronological order
+    ///
+    /// Starts with the history shared with each ancestor, up to its fork
+    /// point, followed by the prompts and responses of the session itself.
+    /// For a session that was never forked this is just its own turns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session or a fork point does not exist, or
+    /// storage fails.
+    pub async fn branch_thread(&self, session_id: SessionId) -> Result<Vec<Node>> {
+        let mut nodes = self.get_session_nodes(&session_id).await?;
+        nodes.retain(|n| matches!(n, Node::Prompt(_) | Node::Response(_)));
+        nodes.sort_by_key(node_timestamp);
+        let mut segments = vec![nodes];
+
+        let mut visited = HashSet::from([session_id]);
+        let mut current = session_id;
+        while let Some(branch) = self.parent_branch(current).await? {
+            if !visited.insert(branch.parent_session) {
+                break;
+            }
+            segments.push(self.thread_up_to(branch.fork_point).await?);
+            current = branch.parent_session;
+        }
+
+        Ok(segments.into_iter().rev().flatten().collect())
+    }
+
+    /// Prompts and responses of a session up to and including `fork_point`
+    ///
+    /// Walks back from the fork point: a response leads to its prompt, and a
+    /// prompt to the one it follows. Of the fork point's own prompt only the
+    /// fork point is kept; earlier prompts keep all their responses. The async
+    /// engine does not link prompts with [`EdgeType::Follows`] edges, so a
+    /// prompt without one follows the latest earlier prompt of its session.
+    async fn thread_up_to(&self, fork_point: NodeId) -> Result<Vec<Node>> {
+        let fork = self
+            .get_node(&fork_point)
+            .await?
+            .ok_or_else(|| Error::NodeNotFound(fork_point.to_string()))?;
+
+        // Collected newest first and reversed at the end
+        let mut thread = Vec::new();
+        let mut prompt = match fork {
+            Node::Prompt(prompt) => Some(prompt),
+            Node::Response(response) => {
+                let prompt = self.responded_prompt(&response.id).await?;
+                thread.push(Node::Response(response));
+                prompt
+            }
+            other => {
+                return Err(Error::TraversalError(format!(
+                    "Fork point {fork_point} is a {:?} node",
+                    other.node_type()
+                )))
+            }
+        };
+        let Some(first) = &prompt else {
+            return Ok(thread);
+        };
+        let session_prompts: Vec<PromptNode> = self
+            .get_session_nodes(&first.session_id)
+            .await?
+            .into_iter()
+            .filter_map(|n| match n {
+                Node::Prompt(p) => Some(p),
+                _ => None,
+            })
+            .collect();
+
+        let mut visited = HashSet::new();
+        let mut own_prompt = true;
+        while let Some(current) = prompt.take() {
+            if !visited.insert(current.id) {
+                break;
+            }
+            if !own_prompt {
+                let mut responses = Vec::new();
+                for edge in self.get_incoming_edges(&current.id).await? {
+                    if edge.edge_type != EdgeType::RespondsTo {
+                        continue;
+                    }
+                    if let Some(response @ Node::Response(_)) = self.get_node(&edge.from).await? {
+                        responses.push(response);
+                    }
+                }
+                responses.sort_by_key(|n| std::cmp::Reverse(node_timestamp(n)));
+                thread.extend(responses);
+            }
+            own_prompt = false;
+
+            prompt = self.previous_prompt(&current, &session_prompts).await?;
+            thread.push(Node::Prompt(current));
+        }
+
+        thread.reverse();
+        Ok(thread)
+    }
+
+    /// The prompt a response answers, following its `RespondsTo` edge
+    async fn responded_prompt(&self, response_id: &NodeId) -> Result<Option<PromptNode>> {
+        for edge in self.get_outgoing_edges(response_id).await? {
+            if edge.edge_type != EdgeType::RespondsTo {
+                continue;
+            }
+            if let Some(Node::Prompt(prompt)) = self.get_node(&edge.to).await? {
+                return Ok(Some(prompt));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The prompt before `prompt` in its session
+    async fn previous_prompt(
+        &self,
+        prompt: &PromptNode,
+        session_prompts: &[PromptNode],
+    ) -> Result<Option<PromptNode>> {
+        for edge in self.get_outgoing_edges(&prompt.id).await? {
+            if edge.edge_type != EdgeType::Follows {
+                continue;
+            }
+            if let Some(Node::Prompt(previous)) = self.get_node(&edge.to).await? {
+                if previous.session_id == prompt.session_id {
+                    return Ok(Some(previous));
+                }
+            }
+        }
+        Ok(session_prompts
+            .iter()
+            .filter(|p| p.timestamp < prompt.timestamp)
+            .max_by_key(|p| p.timestamp)
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, TokenUsage};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_fork_and_thread() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+
+        let p1 = graph
+            .add_prompt(session.id, "first".to_string(), None)
+            .await
+            .unwrap();
+        let r1 = graph
+            .add_response(p1, "reply".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        let p2 = graph
+            .add_prompt(session.id, "second".to_string(), None)
+            .await
+            .unwrap();
+
+        let branch = graph.fork_session(r1).await.unwrap();
+        let edited = graph
+            .add_prompt(branch.id, "second, edited".to_string(), None)
+            .await
+            .unwrap();
+        let nested = graph.fork_session(edited).await.unwrap();
+        let deeper = graph
+            .add_prompt(nested.id, "third".to_string(), None)
+            .await
+            .unwrap();
+
+        // Branches are independent sessions
+        let own: Vec<NodeId> = graph
+            .get_session_nodes(&branch.id)
+            .await
+            .unwrap()
+            .iter()
+            .filter(|n| matches!(n, Node::Prompt(_)))
+            .map(Node::id)
+            .collect();
+        assert_eq!(own, vec![edited]);
+
+        let ids = |nodes: Vec<Node>| nodes.iter().map(Node::id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(graph.branch_thread(session.id).await.unwrap()),
+            vec![p1, r1, p2]
+        );
+        assert_eq!(
+            ids(graph.branch_thread(branch.id).await.unwrap()),
+            vec![p1, r1, edited]
+        );
+        assert_eq!(
+            ids(graph.branch_thread(nested.id).await.unwrap()),
+            vec![p1, r1, edited, deeper]
+        );
+
+        let branches = graph.list_branches(session.id).await.unwrap();
+        assert_eq!(branches.len(), 1);
+        assert_eq!(branches[0].session_id, branch.id);
+        assert_eq!(branches[0].parent_session, session.id);
+        assert_eq!(branches[0].fork_point, r1);
+        assert_eq!(
+            graph
+                .parent_branch(nested.id)
+                .await
+                .unwrap()
+                .unwrap()
+                .fork_point,
+            edited
+        );
+        assert!(graph.parent_branch(session.id).await.unwrap().is_none());
+
+        assert!(graph.fork_session(session.node_id).await.is_err());
+        assert!(graph.fork_session(NodeId::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_thread_keeps_responses_finished_after_fork() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+
+        let p1 = graph
+            .add_prompt(session.id, "first".to_string(), None)
+            .await
+            .unwrap();
+        let p2 = graph
+            .add_prompt(session.id, "second".to_string(), None)
+            .await
+            .unwrap();
+        // The reply to the first prompt completes after the second was sent
+        let r1 = graph
+            .add_response(p1, "late reply".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        let r2 = graph
+            .add_response(p2, "reply".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        graph
+            .add_response(p2, "regenerated".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+
+        let ids = |nodes: Vec<Node>| nodes.iter().map(Node::id).collect::<Vec<_>>();
+
+        let at_prompt = graph.fork_session(p2).await.unwrap();
+        assert_eq!(
+            ids(graph.branch_thread(at_prompt.id).await.unwrap()),
+            vec![p1, r1, p2]
+        );
+
+        // Forking at a response keeps only that response of its prompt
+        let at_response = graph.fork_session(r2).await.unwrap();
+        let edited = graph
+            .add_prompt(at_response.id, "third".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            ids(graph.branch_thread(at_response.id).await.unwrap()),
+            vec![p1, r1, p2, r2, edited]
+        );
+    }
+}
@@ -0,0 +1,732 @@
+//! Built-in edge constraints and graph integrity checks
+//!
+//! Each built-in [`EdgeType`] has an [`EdgeRule`]: the `(NodeType, NodeType)`
+//! pairs it may connect, and how many edges of the type each source and
+//! target node may have. A response, for example, has exactly one
+//! `RespondsTo` edge, and a tool invocation is invoked by exactly one
+//! response. Edges of the types with typed property structs, such as
+//! [`InvokesProperties`] and [`TransfersToProperties`], must carry
+//! properties that parse into them; edges without properties are accepted,
+//! since the engines create most built-in edges bare.
+//!
+//! Both engines check the edges written through their generic edge methods
+//! (`add_edge`, `add_edge_with_properties` and `store_edges_batch`) according
+//! to [`Config::edge_constraints`](crate::Config::edge_constraints): in
+//! [`ConstraintMode::Strict`] a violation rejects the write, in
+//! [`ConstraintMode::Warn`] it is logged and the edge stored. Only upper
+//! bounds can be checked while writing; lower bounds, such as a response
+//! without a `RespondsTo` edge, are found by [`verify_graph`], which checks a
+//! whole graph against the same rules and is what
+//! [`AsyncMemoryGraph::verify_integrity`] runs.
+//!
+//! Custom edge types are always checked against their registered
+//! [`EdgeKind`], whatever the mode.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::{Config, ConstraintMode, EdgeType};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let config = Config::new("./data/graph.db").with_edge_constraints(ConstraintMode::Strict);
+//! let graph = AsyncMemoryGraph::open(config).await?;
+//! let session = graph.create_session().await?;
+//! let first = graph.add_prompt(session.id, "Hi".to_string(), None).await?;
+//! let second = graph.add_prompt(session.id, "Still there?".to_string(), None).await?;
+//!
+//! // A prompt cannot respond to another prompt
+//! assert!(graph.add_edge(first, second, EdgeType::RespondsTo).await.is_err());
+//!
+//! for violation in graph.verify_integrity().await?.violations {
+//!     println!("{violation}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::custom::EdgeKind;
+use crate::engine::AsyncMemoryGraph;
+use crate::storage::{AsyncStorageBackend, StorageBackend};
+use crate::{
+    BranchesFromProperties, ConstraintMode, Edge, EdgeId, EdgeType, Error, InheritsProperties,
+    InstantiatesProperties, InvokesProperties, Node, NodeId, NodeType, ReferencesProperties,
+    Result, TransfersToProperties,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How many edges of a type a node may have
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cardinality {
+    /// Fewest edges a node must have
+    pub min: usize,
+    /// Most edges a node may have, if bounded
+    pub max: Option<usize>,
+}
+
+impl Cardinality {
+    /// Any number of edges
+    pub const ANY: Self = Self { min: 0, max: None };
+    /// No more than one edge
+    pub const AT_MOST_ONE: Self = Self {
+        min: 0,
+        max: Some(1),
+    };
+    /// Exactly one edge
+    pub const EXACTLY_ONE: Self = Self {
+        min: 1,
+        max: Some(1),
+    };
+
+    fn admits(self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl fmt::Display for Cardinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "exactly {max}"),
+            Some(max) if self.min == 0 => write!(f, "at most {max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+/// The constraints on a built-in edge type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EdgeRule {
+    /// The constrained edge type
+    pub edge_type: EdgeType,
+    /// Legal `(source, target)` node types
+    pub endpoints: &'static [(NodeType, NodeType)],
+    /// Edges of this type each node of a source type has
+    pub per_source: Cardinality,
+    /// Edges of this type each node of a target type receives
+    pub per_target: Cardinality,
+}
+
+impl EdgeRule {
+    /// Whether an edge of this type may run from `from` to `to`
+    #[must_use]
+    pub fn allows(&self, from: &NodeType, to: &NodeType) -> bool {
+        self.endpoints
+            .iter()
+            .any(|(source, target)| source == from && target == to)
+    }
+
+    fn is_source(&self, node_type: &NodeType) -> bool {
+        self.endpoints.iter().any(|(source, _)| source == node_type)
+    }
+
+    fn is_target(&self, node_type: &NodeType) -> bool {
+        self.endpoints.iter().any(|(_, target)| target == node_type)
+    }
+}
+
+const REFERENCE_ENDPOINTS: &[(NodeType, NodeType)] = &[
+    (NodeType::Prompt, NodeType::Prompt),
+    (NodeType::Prompt, NodeType::Response),
+    (NodeType::Prompt, NodeType::ToolInvocation),
+    (NodeType::Prompt, NodeType::Template),
+    (NodeType::Prompt, NodeType::Custom),
+    (NodeType::Response, NodeType::Prompt),
+    (NodeType::Response, NodeType::Response),
+    (NodeType::Response, NodeType::ToolInvocation),
+    (NodeType::Response, NodeType::Template),
+    (NodeType::Response, NodeType::Custom),
+];
+
+/// The rules of every built-in edge type
+pub static EDGE_RULES: [EdgeRule; 10] = [
+    EdgeRule {
+        edge_type: EdgeType::Follows,
+        endpoints: &[(NodeType::Prompt, NodeType::Prompt)],
+        per_source: Cardinality::AT_MOST_ONE,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::RespondsTo,
+        endpoints: &[(NodeType::Response, NodeType::Prompt)],
+        per_source: Cardinality::EXACTLY_ONE,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::HandledBy,
+        endpoints: &[(NodeType::Prompt, NodeType::Agent)],
+        per_source: Cardinality::ANY,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::PartOf,
+        endpoints: &[
+            (NodeType::Prompt, NodeType::Session),
+            (NodeType::Custom, NodeType::Session),
+        ],
+        per_source: Cardinality::AT_MOST_ONE,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::Invokes,
+        endpoints: &[(NodeType::Response, NodeType::ToolInvocation)],
+        per_source: Cardinality::ANY,
+        per_target: Cardinality::EXACTLY_ONE,
+    },
+    EdgeRule {
+        edge_type: EdgeType::TransfersTo,
+        endpoints: &[(NodeType::Response, NodeType::Agent)],
+        per_source: Cardinality::ANY,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::Instantiates,
+        endpoints: &[(NodeType::Prompt, NodeType::Template)],
+        per_source: Cardinality::AT_MOST_ONE,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::Inherits,
+        endpoints: &[(NodeType::Template, NodeType::Template)],
+        per_source: Cardinality::AT_MOST_ONE,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::References,
+        endpoints: REFERENCE_ENDPOINTS,
+        per_source: Cardinality::ANY,
+        per_target: Cardinality::ANY,
+    },
+    EdgeRule {
+        edge_type: EdgeType::BranchesFrom,
+        endpoints: &[
+            (NodeType::Session, NodeType::Prompt),
+            (NodeType::Session, NodeType::Response),
+        ],
+        per_source: Cardinality::AT_MOST_ONE,
+        per_target: Cardinality::ANY,
+    },
+];
+
+/// The rule of a built-in edge type; custom types have none
+#[must_use]
+pub fn rule_for(edge_type: &EdgeType) -> Option<&'static EdgeRule> {
+    EDGE_RULES.iter().find(|rule| rule.edge_type == *edge_type)
+}
+
+/// What a [`Violation`] breaks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// The edge connects node types its type does not allow
+    Endpoints,
+    /// A node has too many or too few edges of a type
+    Cardinality,
+    /// The edge's properties do not parse into its typed property struct
+    Properties,
+    /// The edge points at a node that does not exist
+    DanglingEdge,
+    /// A custom edge is unregistered or does not fit its [`EdgeKind`]
+    CustomEdge,
+}
+
+/// A broken edge constraint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// What is broken
+    pub kind: ViolationKind,
+    /// The node the violation is reported against
+    pub node_id: NodeId,
+    /// The offending edge, if a single edge is at fault
+    pub edge_id: Option<EdgeId>,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl Violation {
+    fn for_edge(kind: ViolationKind, edge: &Edge, message: String) -> Self {
+        Self {
+            kind,
+            node_id: edge.from,
+            edge_id: Some(edge.id),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Check that typed edge properties parse into their property struct
+///
+/// Edges without properties, and edge types without a property struct,
+/// always pass.
+#[must_use]
+pub fn check_properties(edge: &Edge) -> Option<Violation> {
+    if edge.properties.is_empty() {
+        return None;
+    }
+    let props = &edge.properties;
+    let (name, parsed) = match edge.edge_type {
+        EdgeType::Instantiates => (
+            "InstantiatesProperties",
+            InstantiatesProperties::from_properties(props).map(drop),
+        ),
+        EdgeType::Inherits => (
+            "InheritsProperties",
+            InheritsProperties::from_properties(props).map(drop),
+        ),
+        EdgeType::Invokes => (
+            "InvokesProperties",
+            InvokesProperties::from_properties(props).map(drop),
+        ),
+        EdgeType::TransfersTo => (
+            "TransfersToProperties",
+            TransfersToProperties::from_properties(props).map(drop),
+        ),
+        EdgeType::References => (
+            "ReferencesProperties",
+            ReferencesProperties::from_properties(props).map(drop),
+        ),
+        EdgeType::BranchesFrom => (
+            "BranchesFromProperties",
+            BranchesFromProperties::from_properties(props).map(drop),
+        ),
+        _ => return None,
+    };
+    parsed.err().map(|e| {
+        Violation::for_edge(
+            ViolationKind::Properties,
+            edge,
+            format!(
+                "{:?} edge {} has properties that do not parse as {name}: {e}",
+                edge.edge_type, edge.id
+            ),
+        )
+    })
+}
+
+/// Check one edge about to be written against the built-in rules
+///
+/// `outgoing` are the edges already leaving `from` and `incoming` those
+/// already entering `to`; only upper bounds are checked, since the node may
+/// still gain edges. Custom edges are not covered here.
+#[must_use]
+pub fn check_edge(
+    edge: &Edge,
+    from: &Node,
+    to: &Node,
+    outgoing: &[Edge],
+    incoming: &[Edge],
+) -> Vec<Violation> {
+    let Some(rule) = rule_for(&edge.edge_type) else {
+        return Vec::new();
+    };
+    let mut violations = Vec::new();
+
+    let (from_type, to_type) = (from.node_type(), to.node_type());
+    if !rule.allows(&from_type, &to_type) {
+        violations.push(Violation::for_edge(
+            ViolationKind::Endpoints,
+            edge,
+            format!(
+                "{:?} edge {} cannot connect a {from_type:?} node to a {to_type:?} node",
+                edge.edge_type, edge.id
+            ),
+        ));
+    }
+
+    let count = |edges: &[Edge]| {
+        edges
+            .iter()
+            .filter(|e| e.edge_type == edge.edge_type && e.id != edge.id)
+            .count()
+            + 1
+    };
+    if let Some(max) = rule.per_source.max {
+        if count(outgoing) > max {
+            violations.push(Violation::for_edge(
+                ViolationKind::Cardinality,
+                edge,
+                format!(
+                    "{from_type:?} node {} may have {} outgoing {:?} edge(s)",
+                    edge.from, rule.per_source, edge.edge_type
+                ),
+            ));
+        }
+    }
+    if let Some(max) = rule.per_target.max {
+        if count(incoming) > max {
+            violations.push(Violation {
+                node_id: edge.to,
+                ..Violation::for_edge(
+                    ViolationKind::Cardinality,
+                    edge,
+                    format!(
+                        "{to_type:?} node {} may have {} incoming {:?} edge(s)",
+                        edge.to, rule.per_target, edge.edge_type
+                    ),
+                )
+            });
+        }
+    }
+
+    violations.extend(check_properties(edge));
+    violations
+}
+
+/// Check a whole graph against the built-in rules and registered edge kinds
+///
+/// Every edge is expected once in `edges`. Unlike [`check_edge`], this also
+/// reports nodes with fewer edges than their rules require.
+#[must_use]
+pub fn verify_graph(nodes: &[Node], edges: &[Edge], edge_kinds: &[EdgeKind]) -> IntegrityReport {
+    let edge_kinds: HashMap<&str, &EdgeKind> =
+        edge_kinds.iter().map(|k| (k.name.as_str(), k)).collect();
+    let by_id: HashMap<NodeId, &Node> = nodes.iter().map(|n| (n.id(), n)).collect();
+    let mut violations = Vec::new();
+    let mut outgoing: HashMap<(NodeId, &EdgeType), usize> = HashMap::new();
+    let mut incoming: HashMap<(NodeId, &EdgeType), usize> = HashMap::new();
+
+    for edge in edges {
+        *outgoing.entry((edge.from, &edge.edge_type)).or_default() += 1;
+        *incoming.entry((edge.to, &edge.edge_type)).or_default() += 1;
+
+        let (Some(from), Some(to)) = (by_id.get(&edge.from), by_id.get(&edge.to)) else {
+            let missing = if by_id.contains_key(&edge.from) {
+                edge.to
+            } else {
+                edge.from
+            };
+            violations.push(Violation::for_edge(
+                ViolationKind::DanglingEdge,
+                edge,
+                format!(
+                    "{:?} edge {} points at missing node {missing}",
+                    edge.edge_type, edge.id
+                ),
+            ));
+            continue;
+        };
+
+        if let EdgeType::Custom(name) = &edge.edge_type {
+            let checked = match edge_kinds.get(name.as_str()) {
+                Some(kind) => kind.check(edge, from, to),
+                None => Err(Error::ValidationError(format!(
+                    "Edge kind '{name}' of edge {} is not registered",
+                    edge.id
+                ))),
+            };
+            if let Err(e) = checked {
+                violations.push(Violation::for_edge(
+                    ViolationKind::CustomEdge,
+                    edge,
+                    e.to_string(),
+                ));
+            }
+            continue;
+        }
+
+        // Cardinalities are checked per node below, with the full counts
+        violations.extend(
+            check_edge(edge, from, to, &[], &[])
+                .into_iter()
+                .filter(|v| v.kind != ViolationKind::Cardinality),
+        );
+    }
+
+    for node in nodes {
+        let node_type = node.node_type();
+        for rule in &EDGE_RULES {
+            let sides = [
+                (
+                    rule.is_source(&node_type),
+                    &outgoing,
+                    rule.per_source,
+                    "outgoing",
+                ),
+                (
+                    rule.is_target(&node_type),
+                    &incoming,
+                    rule.per_target,
+                    "incoming",
+                ),
+            ];
+            for (applies, counts, cardinality, direction) in sides {
+                let count = counts
+                    .get(&(node.id(), &rule.edge_type))
+                    .copied()
+                    .unwrap_or(0);
+                if applies && !cardinality.admits(count) {
+                    violations.push(Violation {
+                        kind: ViolationKind::Cardinality,
+                        node_id: node.id(),
+                        edge_id: None,
+                        message: format!(
+                            "{node_type:?} node {} has {count} {direction} {:?} edge(s), expected {cardinality}",
+                            node.id(),
+                            rule.edge_type
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    IntegrityReport {
+        nodes_checked: nodes.len(),
+        edges_checked: edges.len(),
+        violations,
+    }
+}
+
+/// Result of checking a graph with [`verify_graph`]
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    /// Nodes examined
+    pub nodes_checked: usize,
+    /// Edges examined
+    pub edges_checked: usize,
+    /// Every broken constraint found
+    pub violations: Vec<Violation>,
+}
+
+impl IntegrityReport {
+    /// Whether no constraint is broken
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Apply `mode` to the violations of an edge write
+pub(crate) fn enforce(mode: ConstraintMode, violations: Vec<Violation>) -> Result<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    match mode {
+        ConstraintMode::Off => Ok(()),
+        ConstraintMode::Warn => {
+            for violation in &violations {
+                tracing::warn!("Edge constraint violated: {}", violation);
+            }
+            Ok(())
+        }
+        ConstraintMode::Strict => {
+            let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+            Err(Error::ValidationError(messages.join("; ")))
+        }
+    }
+}
+
+/// The built-in rule violations of writing `edges`, in order
+///
+/// Earlier edges of the batch count towards the cardinalities of later ones.
+pub(crate) async fn check_edges(
+    storage: &dyn AsyncStorageBackend,
+    edges: &[Edge],
+) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+    for (i, edge) in edges.iter().enumerate() {
+        let Some(rule) = rule_for(&edge.edge_type) else {
+            continue;
+        };
+        let (Some(from), Some(to)) = (
+            storage.get_node(&edge.from).await?,
+            storage.get_node(&edge.to).await?,
+        ) else {
+            violations.push(dangling(edge));
+            continue;
+        };
+        let mut outgoing = if rule.per_source.max.is_some() {
+            storage.get_outgoing_edges(&edge.from).await?
+        } else {
+            Vec::new()
+        };
+        let mut incoming = if rule.per_target.max.is_some() {
+            storage.get_incoming_edges(&edge.to).await?
+        } else {
+            Vec::new()
+        };
+        add_pending(&edges[..i], edge, &mut outgoing, &mut incoming);
+        violations.extend(check_edge(edge, &from, &to, &outgoing, &incoming));
+    }
+    Ok(violations)
+}
+
+/// [`check_edges`] through a synchronous backend
+pub(crate) fn check_edges_sync(
+    storage: &dyn StorageBackend,
+    edges: &[Edge],
+) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+    for (i, edge) in edges.iter().enumerate() {
+        let Some(rule) = rule_for(&edge.edge_type) else {
+            continue;
+        };
+        let (Some(from), Some(to)) = (storage.get_node(&edge.from)?, storage.get_node(&edge.to)?)
+        else {
+            violations.push(dangling(edge));
+            continue;
+        };
+        let mut outgoing = if rule.per_source.max.is_some() {
+            storage.get_outgoing_edges(&edge.from)?
+        } else {
+            Vec::new()
+        };
+        let mut incoming = if rule.per_target.max.is_some() {
+            storage.get_incoming_edges(&edge.to)?
+        } else {
+            Vec::new()
+        };
+        add_pending(&edges[..i], edge, &mut outgoing, &mut incoming);
+        violations.extend(check_edge(edge, &from, &to, &outgoing, &incoming));
+    }
+    Ok(violations)
+}
+
+fn add_pending(pending: &[Edge], edge: &Edge, outgoing: &mut Vec<Edge>, incoming: &mut Vec<Edge>) {
+    outgoing.extend(pending.iter().filter(|e| e.from == edge.from).cloned());
+    incoming.extend(pending.iter().filter(|e| e.to == edge.to).cloned());
+}
+
+fn dangling(edge: &Edge) -> Violation {
+    Violation::for_edge(
+        ViolationKind::DanglingEdge,
+        edge,
+        format!(
+            "{:?} edge {} connects {} to {}, which do not both exist",
+            edge.edge_type, edge.id, edge.from, edge.to
+        ),
+    )
+}
+
+impl AsyncMemoryGraph {
+    /// Check the whole graph against the built-in edge rules and the
+    /// registered edge kinds
+    ///
+    /// This is a full scan of nodes and edges.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn verify_integrity(&self) -> Result<IntegrityReport> {
+        let storage = self.storage();
+        let nodes = storage.scan_nodes().await?;
+        let mut edges = Vec::new();
+        for node in &nodes {
+            edges.extend(storage.get_outgoing_edges(&node.id()).await?);
+        }
+        let edge_kinds = self.list_edge_kinds().await?;
+        Ok(verify_graph(&nodes, &edges, &edge_kinds))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentNode, Config, TokenUsage, ToolInvocation};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_strict_mode_rejects_illegal_edges() {
+        let dir = tempdir().unwrap();
+        let config = Config::new(dir.path()).with_edge_constraints(ConstraintMode::Strict);
+        let graph = AsyncMemoryGraph::open(config).await.unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Weather?".to_string(), None)
+            .await
+            .unwrap();
+        let other = graph
+            .add_prompt(session.id, "Anyone?".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(prompt, "Sunny".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        let agent = AgentNode::new("a".to_string(), "r".to_string(), vec![]);
+        let agent_node = agent.node_id;
+        graph.add_agent(agent).await.unwrap();
+
+        // Wrong endpoints, and a second RespondsTo from the same response
+        let err = graph
+            .add_edge(agent_node, agent_node, EdgeType::RespondsTo)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("cannot connect a Agent node"),
+            "{err}"
+        );
+        assert!(graph
+            .add_edge(response, other, EdgeType::RespondsTo)
+            .await
+            .is_err());
+        // Typed properties must parse
+        let bad = HashMap::from([("invocation_order".to_string(), "first".to_string())]);
+        let tool = ToolInvocation::new(response, "search".to_string(), serde_json::json!({}));
+        let tool_id = graph.add_tool_invocation(tool).await.unwrap();
+        assert!(graph
+            .add_edge_with_properties(response, tool_id, EdgeType::Invokes, bad)
+            .await
+            .is_err());
+        // Within a batch, earlier edges count
+        let duplicate_follows = vec![
+            Edge::new(other, prompt, EdgeType::Follows),
+            Edge::new(other, prompt, EdgeType::Follows),
+        ];
+        assert!(graph.store_edges_batch(duplicate_follows).await.is_err());
+
+        graph
+            .add_edge(prompt, agent_node, EdgeType::HandledBy)
+            .await
+            .unwrap();
+        let report = graph.verify_integrity().await.unwrap();
+        assert!(report.is_ok(), "{:?}", report.violations);
+    }
+
+    #[tokio::test]
+    async fn test_warn_mode_stores_and_verifier_reports() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Hello".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(prompt, "Hi".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+
+        // Stored despite breaking the rules
+        graph
+            .add_edge(prompt, response, EdgeType::Invokes)
+            .await
+            .unwrap();
+        graph
+            .store_nodes_batch(vec![Node::Response(crate::ResponseNode::new(
+                prompt,
+                "Orphan".to_string(),
+                TokenUsage::new(1, 1),
+            ))])
+            .await
+            .unwrap();
+
+        let report = graph.verify_integrity().await.unwrap();
+        let mut kinds: Vec<ViolationKind> = report.violations.iter().map(|v| v.kind).collect();
+        kinds.sort_by_key(|k| format!("{k:?}"));
+        // The Invokes edge has a prompt source and a response target, and the
+        // stored response has no RespondsTo edge
+        assert_eq!(
+            kinds,
+            vec![ViolationKind::Cardinality, ViolationKind::Endpoints]
+        );
+    }
+}
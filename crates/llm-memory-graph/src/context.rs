@@ -0,0 +1,664 @@
+//! Token-budgeted context windows
+//!
+//! [`AsyncMemoryGraph::build_context`] picks the prior turns of a session that
+//! fit a token budget, for feeding back to a model. The history is split into
+//! blocks: each prompt on its own, and each response together with the tool
+//! invocations it made, so a tool call is never kept without its result or
+//! the other way round. A [`ContextStrategy`] decides which blocks survive;
+//! the result is in conversation order and lists every dropped node.
+//!
+//! Token counts come from a [`Tokenizer`], which defaults to a rough
+//! four-characters-per-token estimate. Responses can instead use the
+//! completion token count stored in their [`TokenUsage`](crate::TokenUsage).
+//! Stored prompt token counts are never used, since they cover the whole
+//! request a prompt was sent with rather than the prompt text alone.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::context::{ContextRequest, ContextStrategy};
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::{Config, SessionId};
+//!
+//! # async fn example(session_id: SessionId) -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let request = ContextRequest::for_session(session_id, 8_000)
+//!     .strategy(ContextStrategy::FirstAndRecent { first: 1 })
+//!     .summarize_dropped();
+//! let window = graph.build_context(&request).await?;
+//! println!(
+//!     "{} of {} tokens used, {} nodes dropped",
+//!     window.used_tokens,
+//!     window.budget,
+//!     window.dropped.len()
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::engine::AsyncMemoryGraph;
+use crate::search::node_timestamp;
+use crate::{EdgeType, Error, Node, NodeId, NodeType, Result, SessionId};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Default weight of embedding similarity against recency
+const DEFAULT_RELEVANCE_WEIGHT: f32 = 0.7;
+
+/// Number of words of the first prompt quoted in a default summary
+const SUMMARY_WORDS: usize = 12;
+
+/// Counts tokens in text
+pub trait Tokenizer: Send + Sync {
+    /// Number of tokens `text` encodes to
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Estimates one token per four characters
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApproximateTokenizer;
+
+impl Tokenizer for ApproximateTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Condenses a span of dropped turns into a short text
+pub trait Summarizer: Send + Sync {
+    /// Summarize `nodes`, which are in conversation order
+    fn summarize(&self, nodes: &[Node]) -> String;
+}
+
+/// Notes how many turns were left out and quotes the first prompt
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExtractiveSummarizer;
+
+impl Summarizer for ExtractiveSummarizer {
+    fn summarize(&self, nodes: &[Node]) -> String {
+        let opening = nodes.iter().find_map(|node| match node {
+            Node::Prompt(p) => Some(p.content.split_whitespace().collect::<Vec<_>>()),
+            _ => None,
+        });
+        let omitted = match nodes.len() {
+            1 => "[1 earlier message omitted".to_string(),
+            n => format!("[{n} earlier messages omitted"),
+        };
+        match opening {
+            Some(words) if words.len() > SUMMARY_WORDS => format!(
+                "{omitted}, starting with: \"{} …\"]",
+                words[..SUMMARY_WORDS].join(" ")
+            ),
+            Some(words) => format!("{omitted}, starting with: \"{}\"]", words.join(" ")),
+            None => format!("{omitted}]"),
+        }
+    }
+}
+
+/// How to choose which turns fit the budget
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ContextStrategy {
+    /// Keep the newest turns
+    #[default]
+    MostRecent,
+    /// Keep the first `first` blocks, then fill the rest with the newest turns
+    FirstAndRecent {
+        /// Number of leading blocks to keep
+        first: usize,
+    },
+    /// Keep the turns most similar to a query embedding, favouring recent ones
+    ///
+    /// The query is [`ContextRequest::query_embedding`] if set, otherwise the
+    /// embedding of the anchor node or of the latest prompt that has one.
+    /// Turns without embeddings are scored on recency alone, so without any
+    /// embeddings this behaves like a recency ranking that may skip over
+    /// blocks too large to fit.
+    Relevance {
+        /// Share of the score given to similarity, between 0 and 1
+        weight: f32,
+    },
+}
+
+impl ContextStrategy {
+    /// Relevance ranking with the default similarity weight
+    #[must_use]
+    pub const fn relevance() -> Self {
+        Self::Relevance {
+            weight: DEFAULT_RELEVANCE_WEIGHT,
+        }
+    }
+}
+
+/// Where the history of a context window comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextAnchor {
+    /// The whole session, including history shared with the session it was
+    /// forked from
+    Session(SessionId),
+    /// The conversation up to and including this node
+    Node(NodeId),
+}
+
+/// Parameters for [`AsyncMemoryGraph::build_context`]
+#[derive(Clone)]
+pub struct ContextRequest {
+    anchor: ContextAnchor,
+    budget: usize,
+    strategy: ContextStrategy,
+    tokenizer: Arc<dyn Tokenizer>,
+    summarizer: Option<Arc<dyn Summarizer>>,
+    query_embedding: Option<Vec<f32>>,
+    stored_usage: bool,
+    include_tools: bool,
+}
+
+impl ContextRequest {
+    fn new(anchor: ContextAnchor, budget: usize) -> Self {
+        Self {
+            anchor,
+            budget,
+            strategy: ContextStrategy::default(),
+            tokenizer: Arc::new(ApproximateTokenizer),
+            summarizer: None,
+            query_embedding: None,
+            stored_usage: true,
+            include_tools: true,
+        }
+    }
+
+    /// Build context from a whole session within `budget` tokens
+    #[must_use]
+    pub fn for_session(session_id: SessionId, budget: usize) -> Self {
+        Self::new(ContextAnchor::Session(session_id), budget)
+    }
+
+    /// Build context from the conversation leading up to a node
+    #[must_use]
+    pub fn for_node(node_id: NodeId, budget: usize) -> Self {
+        Self::new(ContextAnchor::Node(node_id), budget)
+    }
+
+    /// Set the selection strategy
+    #[must_use]
+    pub fn strategy(mut self, strategy: ContextStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Count tokens with `tokenizer`
+    #[must_use]
+    pub fn tokenizer(mut self, tokenizer: Arc<dyn Tokenizer>) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Replace dropped spans with summaries from the default summarizer
+    #[must_use]
+    pub fn summarize_dropped(self) -> Self {
+        self.summarizer(Arc::new(ExtractiveSummarizer))
+    }
+
+    /// Replace dropped spans with summaries from `summarizer`
+    #[must_use]
+    pub fn summarizer(mut self, summarizer: Arc<dyn Summarizer>) -> Self {
+        self.summarizer = Some(summarizer);
+        self
+    }
+
+    /// Rank turns against this embedding under [`ContextStrategy::Relevance`]
+    #[must_use]
+    pub fn query_embedding(mut self, embedding: Vec<f32>) -> Self {
+        self.query_embedding = Some(embedding);
+        self
+    }
+
+    /// Whether response sizes come from their stored token usage (default)
+    #[must_use]
+    pub fn stored_usage(mut self, enabled: bool) -> Self {
+        self.stored_usage = enabled;
+        self
+    }
+
+    /// Whether tool invocations are included with their responses (default)
+    #[must_use]
+    pub fn tools(mut self, include: bool) -> Self {
+        self.include_tools = include;
+        self
+    }
+}
+
+/// A node kept in a context window
+#[derive(Debug, Clone)]
+pub struct ContextEntry {
+    /// The prompt, response or tool invocation
+    pub node: Node,
+    /// Tokens the node was counted as
+    pub tokens: usize,
+}
+
+/// A summary standing in for a dropped span
+#[derive(Debug, Clone)]
+pub struct SpanSummary {
+    /// Summary text
+    pub text: String,
+    /// Tokens the summary was counted as
+    pub tokens: usize,
+    /// Index in [`ContextWindow::entries`] the summary goes before
+    pub position: usize,
+    /// Nodes the summary covers
+    pub node_ids: Vec<NodeId>,
+}
+
+/// A node left out of a context window
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DroppedNode {
+    /// Node ID
+    pub node_id: NodeId,
+    /// Node type
+    pub node_type: NodeType,
+    /// Tokens the node was counted as
+    pub tokens: usize,
+}
+
+/// The turns chosen to fit a token budget
+#[derive(Debug, Clone)]
+pub struct ContextWindow {
+    /// Kept nodes in conversation order
+    pub entries: Vec<ContextEntry>,
+    /// Summaries of dropped spans, ordered by position
+    pub summaries: Vec<SpanSummary>,
+    /// Nodes left out, in conversation order
+    pub dropped: Vec<DroppedNode>,
+    /// Tokens used by entries and summaries
+    pub used_tokens: usize,
+    /// Requested budget
+    pub budget: usize,
+}
+
+impl ContextWindow {
+    /// Tokens of the dropped nodes
+    #[must_use]
+    pub fn dropped_tokens(&self) -> usize {
+        self.dropped.iter().map(|d| d.tokens).sum()
+    }
+}
+
+/// A prompt, or a response with its tool invocations
+struct Block {
+    entries: Vec<ContextEntry>,
+    tokens: usize,
+    embedding: Option<Vec<f32>>,
+}
+
+impl AsyncMemoryGraph {
+    /// Choose the prior turns of a session that fit a token budget
+    ///
+    /// See [`crate::context`] for how turns are counted and chosen.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session or anchor node does not exist, the
+    /// anchor node is not part of a conversation, or storage fails.
+    pub async fn build_context(&self, request: &ContextRequest) -> Result<ContextWindow> {
+        let history = match request.anchor {
+            ContextAnchor::Session(session_id) => self.branch_thread(session_id).await?,
+            ContextAnchor::Node(node_id) => {
+                let mut nodes = self.traversal().get_conversation_thread(node_id).await?;
+                let anchor = self
+                    .get_node(&node_id)
+                    .await?
+                    .ok_or_else(|| Error::NodeNotFound(node_id.to_string()))?;
+                let cutoff = node_timestamp(&anchor);
+                nodes.retain(|n| node_timestamp(n) <= cutoff);
+                nodes
+            }
+        };
+
+        let blocks = self.blocks(history, request).await?;
+        let keep = self.select(&blocks, request).await?;
+
+        Ok(assemble(blocks, &keep, request))
+    }
+
+    async fn blocks(&self, history: Vec<Node>, request: &ContextRequest) -> Result<Vec<Block>> {
+        let tokenizer = request.tokenizer.as_ref();
+        let ranked = matches!(request.strategy, ContextStrategy::Relevance { .. });
+
+        let mut blocks = Vec::with_capacity(history.len());
+        for node in history {
+            let embedding = if ranked {
+                self.get_embedding(&node.id()).await?
+            } else {
+                None
+            };
+
+            let mut entries = vec![ContextEntry {
+                tokens: node_tokens(&node, tokenizer, request.stored_usage),
+                node,
+            }];
+            if request.include_tools && matches!(entries[0].node, Node::Response(_)) {
+                let mut tools = Vec::new();
+                for edge in self.get_outgoing_edges(&entries[0].node.id()).await? {
+                    if edge.edge_type == EdgeType::Invokes {
+                        if let Some(tool @ Node::ToolInvocation(_)) =
+                            self.get_node(&edge.to).await?
+                        {
+                            tools.push(tool);
+                        }
+                    }
+                }
+                tools.sort_by_key(node_timestamp);
+                entries.extend(tools.into_iter().map(|tool| ContextEntry {
+                    tokens: node_tokens(&tool, tokenizer, request.stored_usage),
+                    node: tool,
+                }));
+            }
+
+            blocks.push(Block {
+                tokens: entries.iter().map(|e| e.tokens).sum(),
+                entries,
+                embedding,
+            });
+        }
+        Ok(blocks)
+    }
+
+    /// Indices of the blocks to keep
+    async fn select(&self, blocks: &[Block], request: &ContextRequest) -> Result<HashSet<usize>> {
+        let mut keep = HashSet::new();
+        let mut used = 0;
+
+        match &request.strategy {
+            ContextStrategy::MostRecent => {
+                fill_recent(blocks, &mut keep, &mut used, request.budget);
+            }
+            ContextStrategy::FirstAndRecent { first } => {
+                for (i, block) in blocks.iter().enumerate().take(*first) {
+                    if used + block.tokens > request.budget {
+                        break;
+                    }
+                    used += block.tokens;
+                    keep.insert(i);
+                }
+                fill_recent(blocks, &mut keep, &mut used, request.budget);
+            }
+            ContextStrategy::Relevance { weight } => {
+                let query = match (&request.query_embedding, request.anchor) {
+                    (Some(query), _) => Some(query.clone()),
+                    (None, ContextAnchor::Node(node_id)) => self.get_embedding(&node_id).await?,
+                    (None, ContextAnchor::Session(_)) => None,
+                };
+                let query = query.or_else(|| {
+                    blocks
+                        .iter()
+                        .rev()
+                        .filter(|b| matches!(b.entries[0].node, Node::Prompt(_)))
+                        .find_map(|b| b.embedding.clone())
+                });
+
+                let weight = weight.clamp(0.0, 1.0);
+                let last = blocks.len().saturating_sub(1).max(1) as f32;
+                let mut ranked: Vec<(f32, usize)> = blocks
+                    .iter()
+                    .enumerate()
+                    .map(|(i, block)| {
+                        let recency = i as f32 / last;
+                        let similarity = match (&query, &block.embedding) {
+                            (Some(q), Some(e)) => Some(cosine(q, e)),
+                            _ => None,
+                        };
+                        let score =
+                            similarity.map_or(recency, |s| weight * s + (1.0 - weight) * recency);
+                        (score, i)
+                    })
+                    .collect();
+                ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+
+                for (_, i) in ranked {
+                    if used + blocks[i].tokens <= request.budget {
+                        used += blocks[i].tokens;
+                        keep.insert(i);
+                    }
+                }
+            }
+        }
+
+        Ok(keep)
+    }
+}
+
+/// Add blocks newest first until one no longer fits
+fn fill_recent(blocks: &[Block], keep: &mut HashSet<usize>, used: &mut usize, budget: usize) {
+    for (i, block) in blocks.iter().enumerate().rev() {
+        if keep.contains(&i) {
+            continue;
+        }
+        if *used + block.tokens > budget {
+            break;
+        }
+        *used += block.tokens;
+        keep.insert(i);
+    }
+}
+
+/// Lay out kept blocks, dropped nodes and span summaries
+fn assemble(blocks: Vec<Block>, keep: &HashSet<usize>, request: &ContextRequest) -> ContextWindow {
+    let mut entries = Vec::new();
+    let mut dropped = Vec::new();
+    let mut spans: Vec<(usize, Vec<Node>)> = Vec::new();
+    let mut used_tokens = 0;
+
+    for (i, block) in blocks.into_iter().enumerate() {
+        if keep.contains(&i) {
+            used_tokens += block.tokens;
+            entries.extend(block.entries);
+            continue;
+        }
+
+        let continues_span = i > 0 && !keep.contains(&(i - 1));
+        if !continues_span {
+            spans.push((entries.len(), Vec::new()));
+        }
+        let (_, nodes) = spans.last_mut().expect("span opened above");
+        for entry in block.entries {
+            dropped.push(DroppedNode {
+                node_id: entry.node.id(),
+                node_type: entry.node.node_type(),
+                tokens: entry.tokens,
+            });
+            nodes.push(entry.node);
+        }
+    }
+
+    // Summaries take whatever budget is left, latest spans first
+    let mut summaries = Vec::new();
+    if let Some(summarizer) = &request.summarizer {
+        for (position, nodes) in spans.into_iter().rev() {
+            let text = summarizer.summarize(&nodes);
+            let tokens = request.tokenizer.count_tokens(&text);
+            if used_tokens + tokens > request.budget {
+                continue;
+            }
+            used_tokens += tokens;
+            summaries.push(SpanSummary {
+                text,
+                tokens,
+                position,
+                node_ids: nodes.iter().map(Node::id).collect(),
+            });
+        }
+        summaries.reverse();
+    }
+
+    ContextWindow {
+        entries,
+        summaries,
+        dropped,
+        used_tokens,
+        budget: request.budget,
+    }
+}
+
+/// Tokens a node counts as
+fn node_tokens(node: &Node, tokenizer: &dyn Tokenizer, stored_usage: bool) -> usize {
+    match node {
+        Node::Prompt(p) => tokenizer.count_tokens(&p.content),
+        Node::Response(r) if stored_usage && r.usage.completion_tokens > 0 => {
+            r.usage.completion_tokens as usize
+        }
+        Node::Response(r) => tokenizer.count_tokens(&r.content),
+        Node::ToolInvocation(t) => {
+            let mut tokens = tokenizer.count_tokens(&t.tool_name)
+                + tokenizer.count_tokens(&t.parameters.to_string());
+            if let Some(result) = &t.result {
+                tokens += tokenizer.count_tokens(&result.to_string());
+            }
+            if let Some(error) = &t.error {
+                tokens += tokenizer.count_tokens(error);
+            }
+            tokens
+        }
+        Node::Custom(c) => tokenizer.count_tokens(&c.payload.to_string()),
+        Node::Session(_) | Node::Agent(_) | Node::Template(_) => 0,
+    }
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denominator = norm(a) * norm(b);
+    if denominator == 0.0 {
+        0.0
+    } else {
+        dot / denominator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, TokenUsage, ToolInvocation};
+    use tempfile::tempdir;
+
+    /// Counts one token per word
+    struct Words;
+
+    impl Tokenizer for Words {
+        fn count_tokens(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    /// Summarizes any span as one word
+    struct Brief;
+
+    impl Summarizer for Brief {
+        fn summarize(&self, _nodes: &[Node]) -> String {
+            "earlier".to_string()
+        }
+    }
+
+    /// Four turns of five-word prompts and responses; the second response
+    /// calls a tool
+    async fn conversation(graph: &AsyncMemoryGraph) -> (SessionId, Vec<NodeId>) {
+        let session = graph.create_session().await.unwrap();
+        let mut ids = Vec::new();
+        for i in 0..4 {
+            let prompt = graph
+                .add_prompt(session.id, format!("question {i} about the topic"), None)
+                .await
+                .unwrap();
+            let response = graph
+                .add_response(
+                    prompt,
+                    format!("answer {i} on the topic"),
+                    TokenUsage::new(0, 0),
+                    None,
+                )
+                .await
+                .unwrap();
+            ids.push(prompt);
+            ids.push(response);
+            if i == 1 {
+                let mut tool =
+                    ToolInvocation::new(response, "lookup".to_string(), serde_json::json!("x"));
+                tool.result = Some(serde_json::json!("y"));
+                ids.push(graph.add_tool_invocation(tool).await.unwrap());
+            }
+        }
+        (session.id, ids)
+    }
+
+    #[tokio::test]
+    async fn test_most_recent_and_first() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let (session_id, ids) = conversation(&graph).await;
+        let words = Arc::new(Words);
+
+        let request = ContextRequest::for_session(session_id, 20).tokenizer(words.clone());
+        let window = graph.build_context(&request).await.unwrap();
+        let kept: Vec<NodeId> = window.entries.iter().map(|e| e.node.id()).collect();
+        assert_eq!(kept, ids[5..]);
+        assert_eq!(window.used_tokens, 20);
+        assert_eq!(window.dropped.len(), 5);
+        assert!(window.summaries.is_empty());
+
+        let request = ContextRequest::for_session(session_id, 23)
+            .tokenizer(words.clone())
+            .strategy(ContextStrategy::FirstAndRecent { first: 1 });
+        let window = graph.build_context(&request).await.unwrap();
+        let kept: Vec<NodeId> = window.entries.iter().map(|e| e.node.id()).collect();
+        assert_eq!(kept, [&ids[..1], &ids[6..]].concat());
+
+        // The second response is dropped together with its tool call
+        let request = ContextRequest::for_session(session_id, 30)
+            .tokenizer(words)
+            .strategy(ContextStrategy::FirstAndRecent { first: 1 })
+            .summarizer(Arc::new(Brief));
+        let window = graph.build_context(&request).await.unwrap();
+        assert_eq!(window.entries.len(), 5);
+        assert_eq!(window.summaries.len(), 1);
+        assert_eq!(window.summaries[0].position, 1);
+        assert_eq!(window.summaries[0].node_ids, ids[1..5]);
+        assert_eq!(window.used_tokens, 26);
+        assert_eq!(window.dropped_tokens(), 18);
+    }
+
+    #[tokio::test]
+    async fn test_anchor_and_relevance() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let (session_id, ids) = conversation(&graph).await;
+        let words = Arc::new(Words);
+
+        // Anchored on the second prompt: only what came before it
+        let request = ContextRequest::for_node(ids[2], 1_000).tokenizer(words.clone());
+        let window = graph.build_context(&request).await.unwrap();
+        assert_eq!(window.entries.len(), 3);
+        assert!(window.dropped.is_empty());
+
+        // The first turn matches the query best and wins over recency
+        graph.set_embedding(&ids[0], vec![1.0, 0.0]).await.unwrap();
+        graph.set_embedding(&ids[7], vec![0.0, 1.0]).await.unwrap();
+        let request = ContextRequest::for_session(session_id, 10)
+            .tokenizer(words)
+            .strategy(ContextStrategy::Relevance { weight: 0.9 })
+            .query_embedding(vec![1.0, 0.0]);
+        let window = graph.build_context(&request).await.unwrap();
+        let kept: Vec<NodeId> = window.entries.iter().map(|e| e.node.id()).collect();
+        assert_eq!(kept, vec![ids[0], ids[8]]);
+
+        assert!(graph
+            .build_context(&ContextRequest::for_session(SessionId::new(), 10))
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,604 @@
+//! User-defined edge types
+//!
+//! An [`EdgeKind`] defines the relationship named by
+//! [`EdgeType::Custom`](crate::EdgeType::Custom): which nodes it may connect
+//! and which typed properties it carries. Edges of an unregistered custom
+//! type are rejected, as are edges whose endpoints or properties do not fit
+//! the definition.
+//!
+//! Edge properties are stored as strings like those of the built-in types;
+//! a [`PropertySpec`] declares how each one must parse, and
+//! [`EdgeKind::parse_properties`] returns them as typed JSON values.
+
+use crate::storage::{AsyncStorageBackend, StorageBackend};
+use crate::views::validate_name;
+use crate::{Edge, EdgeType, Error, Node, NodeType, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Catalog key prefix of registered edge kinds: `edge_kind/<name>`
+const EDGE_KIND_PREFIX: &str = "edge_kind/";
+
+/// Nodes an edge kind may start or end at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EndpointKind {
+    /// Any node of a built-in type, or any custom node for [`NodeType::Custom`]
+    Type(NodeType),
+    /// Custom nodes of the named kind
+    Custom(String),
+}
+
+impl EndpointKind {
+    /// Whether `node` is of this kind
+    #[must_use]
+    pub fn matches(&self, node: &Node) -> bool {
+        match (self, node) {
+            (Self::Custom(kind), Node::Custom(custom)) => custom.kind == *kind,
+            (Self::Custom(_), _) => false,
+            (Self::Type(node_type), node) => node.node_type() == *node_type,
+        }
+    }
+}
+
+impl fmt::Display for EndpointKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Type(node_type) => write!(f, "{}", format!("{node_type:?}").to_lowercase()),
+            Self::Custom(kind) => write!(f, "custom:{kind}"),
+        }
+    }
+}
+
+impl FromStr for EndpointKind {
+    type Err = Error;
+
+    /// Parse a node label such as `prompt`, or `custom:<kind>`
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some(kind) = s.strip_prefix("custom:") {
+            validate_name(kind)?;
+            return Ok(Self::Custom(kind.to_string()));
+        }
+        crate::query::language::parse_node_type(s)
+            .map(Self::Type)
+            .ok_or_else(|| Error::ValidationError(format!("Unknown node kind '{s}'")))
+    }
+}
+
+/// How an edge property value must parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PropertyType {
+    /// Any string
+    String,
+    /// A signed 64-bit integer
+    Integer,
+    /// A floating point number
+    Float,
+    /// `true` or `false`
+    Boolean,
+    /// An RFC 3339 timestamp
+    Timestamp,
+    /// A JSON document
+    Json,
+}
+
+impl PropertyType {
+    /// Parse `value` as this type
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the expected type if `value` does not parse.
+    pub fn parse(self, value: &str) -> std::result::Result<Value, String> {
+        let parsed = match self {
+            Self::String => Some(Value::String(value.to_string())),
+            Self::Integer => value.parse::<i64>().ok().map(Value::from),
+            Self::Float => value
+                .parse::<f64>()
+                .ok()
+                .and_then(serde_json::Number::from_f64)
+                .map(Value::Number),
+            Self::Boolean => value.parse::<bool>().ok().map(Value::Bool),
+            Self::Timestamp => DateTime::parse_from_rfc3339(value)
+                .ok()
+                .map(|ts| Value::String(ts.with_timezone(&Utc).to_rfc3339())),
+            Self::Json => serde_json::from_str(value).ok(),
+        };
+        parsed.ok_or_else(|| format!("must be {}, found '{value}'", self.describe()))
+    }
+
+    const fn describe(self) -> &'static str {
+        match self {
+            Self::String => "a string",
+            Self::Integer => "an integer",
+            Self::Float => "a number",
+            Self::Boolean => "true or false",
+            Self::Timestamp => "an RFC 3339 timestamp",
+            Self::Json => "a JSON document",
+        }
+    }
+}
+
+/// A property declared by an edge kind
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropertySpec {
+    /// Property key
+    pub name: String,
+    /// How the value must parse
+    pub property_type: PropertyType,
+    /// Whether every edge must set the property
+    pub required: bool,
+    /// What the property records
+    pub description: Option<String>,
+}
+
+impl PropertySpec {
+    /// A property every edge of the kind must set
+    pub fn required(name: impl Into<String>, property_type: PropertyType) -> Self {
+        Self {
+            name: name.into(),
+            property_type,
+            required: true,
+            description: None,
+        }
+    }
+
+    /// A property edges of the kind may set
+    pub fn optional(name: impl Into<String>, property_type: PropertyType) -> Self {
+        Self {
+            required: false,
+            ..Self::required(name, property_type)
+        }
+    }
+
+    /// Describe what the property records
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+}
+
+/// A registered custom edge type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EdgeKind {
+    /// Name edges of this kind carry in [`EdgeType::Custom`]
+    pub name: String,
+    /// What the relationship means
+    pub description: Option<String>,
+    /// Nodes the edge may start at; empty allows any node
+    pub sources: Vec<EndpointKind>,
+    /// Nodes the edge may end at; empty allows any node
+    pub targets: Vec<EndpointKind>,
+    /// Declared properties
+    pub properties: Vec<PropertySpec>,
+    /// Whether properties that are not declared are accepted
+    pub allow_undeclared: bool,
+    /// When the kind was registered
+    pub created_at: DateTime<Utc>,
+}
+
+impl EdgeKind {
+    /// A kind connecting any two nodes, without properties
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            sources: Vec::new(),
+            targets: Vec::new(),
+            properties: Vec::new(),
+            allow_undeclared: false,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Describe what the relationship means
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Allow edges to start at nodes of `kind`
+    #[must_use]
+    pub fn source(mut self, kind: EndpointKind) -> Self {
+        self.sources.push(kind);
+        self
+    }
+
+    /// Allow edges to end at nodes of `kind`
+    #[must_use]
+    pub fn target(mut self, kind: EndpointKind) -> Self {
+        self.targets.push(kind);
+        self
+    }
+
+    /// Declare a property
+    #[must_use]
+    pub fn property(mut self, spec: PropertySpec) -> Self {
+        self.properties.push(spec);
+        self
+    }
+
+    /// Accept properties that are not declared, as strings
+    #[must_use]
+    pub const fn allow_undeclared(mut self) -> Self {
+        self.allow_undeclared = true;
+        self
+    }
+
+    /// The edge type of edges of this kind
+    #[must_use]
+    pub fn edge_type(&self) -> EdgeType {
+        EdgeType::Custom(self.name.clone())
+    }
+
+    /// Check the names used by the definition
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error describing the problem.
+    pub fn validate(&self) -> Result<()> {
+        validate_name(&self.name)?;
+        for endpoint in self.sources.iter().chain(&self.targets) {
+            if let EndpointKind::Custom(kind) = endpoint {
+                validate_name(kind)?;
+            }
+        }
+        let mut seen = HashSet::new();
+        for spec in &self.properties {
+            if spec.name.is_empty() {
+                return Err(Error::ValidationError(format!(
+                    "Edge kind '{}' declares a property without a name",
+                    self.name
+                )));
+            }
+            if !seen.insert(spec.name.as_str()) {
+                return Err(Error::ValidationError(format!(
+                    "Edge kind '{}' declares property '{}' twice",
+                    self.name, spec.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that an edge of this kind may connect `from` to `to`
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error naming the endpoint that does not fit.
+    pub fn check_endpoints(&self, from: &Node, to: &Node) -> Result<()> {
+        let allowed = |kinds: &[EndpointKind], node: &Node| {
+            kinds.is_empty() || kinds.iter().any(|kind| kind.matches(node))
+        };
+        let describe = |kinds: &[EndpointKind]| {
+            kinds
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        if !allowed(&self.sources, from) {
+            return Err(Error::ValidationError(format!(
+                "Invalid '{}' edge: source {} is not one of {}",
+                self.name,
+                from.id(),
+                describe(&self.sources)
+            )));
+        }
+        if !allowed(&self.targets, to) {
+            return Err(Error::ValidationError(format!(
+                "Invalid '{}' edge: target {} is not one of {}",
+                self.name,
+                to.id(),
+                describe(&self.targets)
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check and parse edge properties against the declared specs
+    ///
+    /// Undeclared properties, when allowed, are returned as strings.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error naming the first property that is missing,
+    /// undeclared or does not parse.
+    pub fn parse_properties(
+        &self,
+        properties: &HashMap<String, String>,
+    ) -> Result<Map<String, Value>> {
+        let invalid = |problem: String| {
+            Error::ValidationError(format!("Invalid '{}' edge: {problem}", self.name))
+        };
+
+        let mut typed = Map::new();
+        for spec in &self.properties {
+            match properties.get(&spec.name) {
+                Some(value) => {
+                    let parsed = spec
+                        .property_type
+                        .parse(value)
+                        .map_err(|e| invalid(format!("property '{}' {e}", spec.name)))?;
+                    typed.insert(spec.name.clone(), parsed);
+                }
+                None if spec.required => {
+                    return Err(invalid(format!(
+                        "missing required property '{}'",
+                        spec.name
+                    )));
+                }
+                None => {}
+            }
+        }
+
+        let mut undeclared: Vec<&String> = properties
+            .keys()
+            .filter(|key| !self.properties.iter().any(|spec| spec.name == **key))
+            .collect();
+        undeclared.sort();
+        if let Some(key) = undeclared.first() {
+            if !self.allow_undeclared {
+                return Err(invalid(format!("property '{key}' is not declared")));
+            }
+        }
+        for key in undeclared {
+            typed.insert(key.clone(), Value::String(properties[key].clone()));
+        }
+        Ok(typed)
+    }
+
+    /// Check an edge of this kind between the given nodes
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the endpoints or properties do not fit.
+    pub fn check(&self, edge: &Edge, from: &Node, to: &Node) -> Result<()> {
+        self.check_endpoints(from, to)?;
+        self.parse_properties(&edge.properties)?;
+        Ok(())
+    }
+}
+
+fn kind_key(name: &str) -> Vec<u8> {
+    format!("{EDGE_KIND_PREFIX}{name}").into_bytes()
+}
+
+/// Register `kind`, failing if the name is taken
+///
+/// The name is claimed atomically, so of two concurrent registrations of the
+/// same name exactly one succeeds.
+pub(crate) async fn register(storage: &dyn AsyncStorageBackend, kind: &EdgeKind) -> Result<()> {
+    kind.validate()?;
+    let inserted = storage
+        .insert_catalog_entry(&kind_key(&kind.name), &rmp_serde::to_vec(kind)?)
+        .await?;
+    if !inserted {
+        return Err(already_registered(&kind.name));
+    }
+    Ok(())
+}
+
+/// The edge kind registered as `name`, if any
+pub(crate) async fn get(storage: &dyn AsyncStorageBackend, name: &str) -> Result<Option<EdgeKind>> {
+    match storage.get_catalog_entry(&kind_key(name)).await? {
+        Some(bytes) => Ok(Some(rmp_serde::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Every registered edge kind, by name
+pub(crate) async fn list(storage: &dyn AsyncStorageBackend) -> Result<Vec<EdgeKind>> {
+    storage
+        .scan_catalog(EDGE_KIND_PREFIX.as_bytes())
+        .await?
+        .into_iter()
+        .map(|(_, bytes)| Ok(rmp_serde::from_slice(&bytes)?))
+        .collect()
+}
+
+/// Remove the edge kind registered as `name`, returning whether it existed
+pub(crate) async fn unregister(storage: &dyn AsyncStorageBackend, name: &str) -> Result<bool> {
+    if get(storage, name).await?.is_none() {
+        return Ok(false);
+    }
+    storage.write_catalog(&[], &[kind_key(name)]).await?;
+    Ok(true)
+}
+
+/// Check a custom edge against its registered kind; built-in edges pass
+pub(crate) async fn check_edge(storage: &dyn AsyncStorageBackend, edge: &Edge) -> Result<()> {
+    let EdgeType::Custom(name) = &edge.edge_type else {
+        return Ok(());
+    };
+    let kind = get(storage, name)
+        .await?
+        .ok_or_else(|| not_registered(name))?;
+    let from = endpoint(storage.get_node(&edge.from).await?, edge.from)?;
+    let to = endpoint(storage.get_node(&edge.to).await?, edge.to)?;
+    kind.check(edge, &from, &to)
+}
+
+/// Register `kind` through a synchronous backend
+pub(crate) fn register_sync(storage: &dyn StorageBackend, kind: &EdgeKind) -> Result<()> {
+    kind.validate()?;
+    if !storage.insert_catalog_entry(&kind_key(&kind.name), &rmp_serde::to_vec(kind)?)? {
+        return Err(already_registered(&kind.name));
+    }
+    Ok(())
+}
+
+/// The edge kind registered as `name`, read through a synchronous backend
+pub(crate) fn get_sync(storage: &dyn StorageBackend, name: &str) -> Result<Option<EdgeKind>> {
+    match storage.get_catalog_entry(&kind_key(name))? {
+        Some(bytes) => Ok(Some(rmp_serde::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// [`check_edge`] through a synchronous backend
+pub(crate) fn check_edge_sync(storage: &dyn StorageBackend, edge: &Edge) -> Result<()> {
+    let EdgeType::Custom(name) = &edge.edge_type else {
+        return Ok(());
+    };
+    let kind = get_sync(storage, name)?.ok_or_else(|| not_registered(name))?;
+    let from = endpoint(storage.get_node(&edge.from)?, edge.from)?;
+    let to = endpoint(storage.get_node(&edge.to)?, edge.to)?;
+    kind.check(edge, &from, &to)
+}
+
+fn endpoint(node: Option<Node>, id: crate::NodeId) -> Result<Node> {
+    node.ok_or_else(|| Error::NodeNotFound(id.to_string()))
+}
+
+fn already_registered(name: &str) -> Error {
+    Error::ValidationError(format!("Edge kind '{name}' is already registered"))
+}
+
+fn not_registered(name: &str) -> Error {
+    Error::ValidationError(format!("Edge kind '{name}' is not registered"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::custom::NodeKind;
+    use crate::engine::AsyncMemoryGraph;
+    use crate::query::TraversalOptions;
+    use crate::{Config, CustomNode};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_property_parsing() {
+        let cites = EdgeKind::new("cites")
+            .property(PropertySpec::required("page", PropertyType::Integer))
+            .property(PropertySpec::optional("quoted_at", PropertyType::Timestamp));
+
+        let props = HashMap::from([("page".to_string(), "12".to_string())]);
+        assert_eq!(cites.parse_properties(&props).unwrap()["page"], json!(12));
+
+        let props = HashMap::from([("page".to_string(), "twelve".to_string())]);
+        let err = cites.parse_properties(&props).unwrap_err().to_string();
+        assert!(err.contains("property 'page' must be an integer"), "{err}");
+
+        assert!(cites.parse_properties(&HashMap::new()).is_err());
+        let props = HashMap::from([
+            ("page".to_string(), "1".to_string()),
+            ("note".to_string(), "x".to_string()),
+        ]);
+        assert!(cites.parse_properties(&props).is_err());
+        assert_eq!(
+            cites
+                .clone()
+                .allow_undeclared()
+                .parse_properties(&props)
+                .unwrap()["note"],
+            json!("x")
+        );
+
+        assert_eq!(
+            "custom:document".parse::<EndpointKind>().unwrap(),
+            EndpointKind::Custom("document".to_string())
+        );
+        assert_eq!(
+            "tool_invocation".parse::<EndpointKind>().unwrap(),
+            EndpointKind::Type(NodeType::ToolInvocation)
+        );
+        assert!(EdgeKind::new("dup")
+            .property(PropertySpec::optional("a", PropertyType::String))
+            .property(PropertySpec::optional("a", PropertyType::Json))
+            .validate()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_custom_edges_are_validated() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        graph
+            .register_node_kind(NodeKind::new("document"))
+            .await
+            .unwrap();
+        graph
+            .register_edge_kind(
+                EdgeKind::new("cites")
+                    .source(EndpointKind::Type(NodeType::Response))
+                    .target(EndpointKind::Custom("document".to_string()))
+                    .property(PropertySpec::required("page", PropertyType::Integer)),
+            )
+            .await
+            .unwrap();
+        assert!(graph
+            .register_edge_kind(EdgeKind::new("cites"))
+            .await
+            .is_err());
+
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Summarise".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(
+                prompt,
+                "Summary".to_string(),
+                crate::TokenUsage::new(1, 1),
+                None,
+            )
+            .await
+            .unwrap();
+        let document = graph
+            .add_custom_node(CustomNode::new("document".to_string(), json!({})))
+            .await
+            .unwrap();
+
+        let cites = EdgeType::Custom("cites".to_string());
+        let page = |p: &str| HashMap::from([("page".to_string(), p.to_string())]);
+        graph
+            .add_edge_with_properties(response, document, cites.clone(), page("3"))
+            .await
+            .unwrap();
+
+        // Wrong source, bad property, missing property, unknown type
+        assert!(graph
+            .add_edge_with_properties(prompt, document, cites.clone(), page("3"))
+            .await
+            .is_err());
+        assert!(graph
+            .add_edge_with_properties(response, document, cites.clone(), page("x"))
+            .await
+            .is_err());
+        assert!(graph
+            .add_edge(response, document, cites.clone())
+            .await
+            .is_err());
+        assert!(graph
+            .add_edge(
+                response,
+                document,
+                EdgeType::Custom("supersedes".to_string())
+            )
+            .await
+            .is_err());
+
+        // Stored in the adjacency indexes and usable as a traversal filter
+        let incoming = graph.get_incoming_edges(&document).await.unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].edge_type, cites);
+        let reached = graph
+            .traversal()
+            .neighborhood(response, 1, &TraversalOptions::new().edge_type(cites))
+            .await
+            .unwrap();
+        let ids: Vec<_> = reached.nodes.iter().map(Node::id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&document));
+    }
+}
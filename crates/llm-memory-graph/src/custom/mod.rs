@@ -0,0 +1,401 @@
+//! User-defined node kinds
+//!
+//! A [`CustomNode`] stores a domain entity, such as a document, ticket or
+//! evaluation result, as a JSON payload in the same graph as the
+//! conversations it relates to. Each node names its kind, and kinds are
+//! registered up front as a [`NodeKind`], optionally with a JSON Schema that
+//! every payload of the kind is validated against before it is written. See
+//! [`schema`] for the supported schema keywords.
+//!
+//! Kinds are stored in the graph's catalog, so they survive restarts and are
+//! shared by every process opening the database.
+//!
+//! Custom nodes that belong to a session are listed in the session index and
+//! linked to the session with a `PartOf` edge. Every custom node is listed in
+//! the reverse lookup for its kind, and queries can filter on the kind
+//! ([`Field::Kind`](crate::query::Field::Kind)) and payload values
+//! ([`Predicate::payload`](crate::query::Predicate::payload)), or address
+//! `kind` and `payload.<field>` properties in the query language.
+//!
+//! Relationships between them are registered the same way, as an
+//! [`EdgeKind`] naming an [`EdgeType::Custom`](crate::EdgeType::Custom); see
+//! [`edge`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::custom::NodeKind;
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::query::predicate::{Comparison, Predicate};
+//! use llm_memory_graph::{Config, CustomNode};
+//! use serde_json::json;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! graph
+//!     .register_node_kind(NodeKind::new("ticket").schema(json!({
+//!         "type": "object",
+//!         "required": ["title", "priority"],
+//!         "properties": {
+//!             "title": {"type": "string"},
+//!             "priority": {"type": "integer", "minimum": 1, "maximum": 4}
+//!         }
+//!     })))
+//!     .await?;
+//!
+//! let session = graph.create_session().await?;
+//! let ticket = CustomNode::in_session(
+//!     session.id,
+//!     "ticket".to_string(),
+//!     json!({"title": "Login fails", "priority": 1}),
+//! );
+//! graph.add_custom_node(ticket).await?;
+//!
+//! let urgent = graph
+//!     .query()
+//!     .filter(Predicate::kind("ticket").and(Predicate::payload("priority", Comparison::Le, 2)))
+//!     .execute()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod edge;
+pub mod schema;
+
+pub use edge::{EdgeKind, EndpointKind, PropertySpec, PropertyType};
+
+use crate::storage::{AsyncStorageBackend, Lookup};
+use crate::views::validate_name;
+use crate::{CustomNode, Error, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Catalog key prefix of registered kinds: `kind/<name>`
+const KIND_PREFIX: &str = "kind/";
+
+/// A registered kind of custom node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeKind {
+    /// Name nodes of this kind carry in [`CustomNode::kind`]
+    pub name: String,
+    /// What nodes of this kind represent
+    pub description: Option<String>,
+    /// JSON Schema every payload of this kind must satisfy
+    pub schema: Option<serde_json::Value>,
+    /// When the kind was registered
+    pub created_at: DateTime<Utc>,
+}
+
+impl NodeKind {
+    /// A kind accepting any payload
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            schema: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Describe what nodes of this kind represent
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Require payloads to satisfy a JSON Schema
+    #[must_use]
+    pub fn schema(mut self, schema: serde_json::Value) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    /// Check the name and that the schema only uses supported keywords
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error describing the problem.
+    pub fn validate(&self) -> Result<()> {
+        validate_name(&self.name)?;
+        match &self.schema {
+            Some(schema) => schema::check_schema(schema),
+            None => Ok(()),
+        }
+    }
+
+    /// Check a payload against this kind's schema
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error naming the first violation.
+    pub fn check_payload(&self, payload: &serde_json::Value) -> Result<()> {
+        let Some(schema) = &self.schema else {
+            return Ok(());
+        };
+        schema::validate(schema, payload, "payload").map_err(|violation| {
+            Error::ValidationError(format!("Invalid '{}' payload: {violation}", self.name))
+        })
+    }
+}
+
+fn kind_key(name: &str) -> Vec<u8> {
+    format!("{KIND_PREFIX}{name}").into_bytes()
+}
+
+/// Register `kind`, failing if the name is taken
+///
+/// The name is claimed atomically, so of two concurrent registrations of the
+/// same name exactly one succeeds.
+pub(crate) async fn register(storage: &dyn AsyncStorageBackend, kind: &NodeKind) -> Result<()> {
+    kind.validate()?;
+    let inserted = storage
+        .insert_catalog_entry(&kind_key(&kind.name), &rmp_serde::to_vec(kind)?)
+        .await?;
+    if !inserted {
+        return Err(Error::ValidationError(format!(
+            "Node kind '{}' is already registered",
+            kind.name
+        )));
+    }
+    Ok(())
+}
+
+/// The kind registered as `name`, if any
+pub(crate) async fn get(storage: &dyn AsyncStorageBackend, name: &str) -> Result<Option<NodeKind>> {
+    match storage.get_catalog_entry(&kind_key(name)).await? {
+        Some(bytes) => Ok(Some(rmp_serde::from_slice(&bytes)?)),
+        None => Ok(None),
+    }
+}
+
+/// Every registered kind, by name
+pub(crate) async fn list(storage: &dyn AsyncStorageBackend) -> Result<Vec<NodeKind>> {
+    storage
+        .scan_catalog(KIND_PREFIX.as_bytes())
+        .await?
+        .into_iter()
+        .map(|(_, bytes)| Ok(rmp_serde::from_slice(&bytes)?))
+        .collect()
+}
+
+/// Remove the kind registered as `name`, returning whether it existed
+///
+/// Kinds that still have nodes cannot be removed.
+pub(crate) async fn unregister(storage: &dyn AsyncStorageBackend, name: &str) -> Result<bool> {
+    if get(storage, name).await?.is_none() {
+        return Ok(false);
+    }
+    let in_use = storage
+        .lookup_nodes(&Lookup::Kind(name.to_string()), None, 1)
+        .await?;
+    if !in_use.items.is_empty() {
+        return Err(Error::ValidationError(format!(
+            "Node kind '{name}' still has nodes"
+        )));
+    }
+    storage.write_catalog(&[], &[kind_key(name)]).await?;
+    Ok(true)
+}
+
+/// Check that a node's kind is registered and its payload fits the schema
+pub(crate) async fn check_node(storage: &dyn AsyncStorageBackend, node: &CustomNode) -> Result<()> {
+    let kind = get(storage, &node.kind).await?.ok_or_else(|| {
+        Error::ValidationError(format!("Node kind '{}' is not registered", node.kind))
+    })?;
+    kind.check_payload(&node.payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::AsyncMemoryGraph;
+    use crate::query::predicate::{Comparison, Predicate};
+    use crate::{Config, EdgeType, Node};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_registered_kinds_validate_writes() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let ticket = NodeKind::new("ticket")
+            .description("Support ticket")
+            .schema(json!({
+                "type": "object",
+                "required": ["title"],
+                "properties": {"title": {"type": "string"}, "priority": {"type": "integer"}}
+            }));
+        graph.register_node_kind(ticket.clone()).await.unwrap();
+        graph
+            .register_node_kind(NodeKind::new("document"))
+            .await
+            .unwrap();
+
+        assert!(graph.register_node_kind(ticket).await.is_err());
+        assert!(graph
+            .register_node_kind(NodeKind::new("bad").schema(json!({"$ref": "#/x"})))
+            .await
+            .is_err());
+        let names: Vec<String> = graph
+            .list_node_kinds()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|k| k.name)
+            .collect();
+        assert_eq!(names, vec!["document", "ticket"]);
+
+        let session = graph.create_session().await.unwrap();
+        let valid = CustomNode::in_session(
+            session.id,
+            "ticket".to_string(),
+            json!({"title": "Login fails", "priority": 1}),
+        );
+        let id = graph.add_custom_node(valid).await.unwrap();
+
+        let invalid = CustomNode::new("ticket".to_string(), json!({"priority": "high"}));
+        let err = graph.add_custom_node(invalid).await.unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("missing required property 'title'"));
+        let unknown = CustomNode::new("user".to_string(), json!({}));
+        assert!(graph.add_custom_node(unknown.clone()).await.is_err());
+        assert!(graph
+            .store_nodes_batch(vec![Node::Custom(unknown)])
+            .await
+            .is_err());
+
+        // Indexed by session, linked to it, and listed under its kind
+        let session_nodes = graph.get_session_nodes(&session.id).await.unwrap();
+        assert!(session_nodes.iter().any(|n| n.id() == id));
+        let edges = graph.get_outgoing_edges(&id).await.unwrap();
+        assert!(edges.iter().any(|e| e.edge_type == EdgeType::PartOf));
+
+        let document = CustomNode::new("document".to_string(), json!({"title": "Runbook"}));
+        graph.add_custom_node(document).await.unwrap();
+        let tickets = graph
+            .custom_nodes_by_kind("ticket", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(tickets.items.len(), 1);
+        assert_eq!(tickets.items[0].id, id);
+
+        let urgent = graph
+            .query()
+            .filter(Predicate::kind("ticket").and(Predicate::payload(
+                "priority",
+                Comparison::Le,
+                2,
+            )))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(urgent.len(), 1);
+
+        let result = graph
+            .execute_query(
+                "MATCH (t:Custom) WHERE t.kind = 'document' RETURN t.payload.title AS title",
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0][0], json!("Runbook"));
+
+        assert!(graph.drop_node_kind("ticket").await.is_err());
+        assert!(!graph.drop_node_kind("user").await.unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrent_registration() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+
+        let attempts = (0..8).map(|i| {
+            graph.register_node_kind(NodeKind::new("ticket").description(format!("Attempt {i}")))
+        });
+        let results = futures::future::join_all(attempts).await;
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+        assert_eq!(graph.list_node_kinds().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_custom_node() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        graph
+            .register_node_kind(NodeKind::new("ticket").schema(json!({
+                "type": "object",
+                "required": ["title"]
+            })))
+            .await
+            .unwrap();
+        graph
+            .register_node_kind(NodeKind::new("document"))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let other = graph.create_session().await.unwrap();
+        let node = CustomNode::in_session(
+            session.id,
+            "ticket".to_string(),
+            json!({"title": "Login fails"}),
+        );
+        let id = graph.add_custom_node(node.clone()).await.unwrap();
+
+        let mut update = node.clone();
+        update.payload = json!({"title": "Login fails on Safari"});
+        update
+            .metadata
+            .insert("assignee".to_string(), "ops".to_string());
+        graph.update_custom_node(update).await.unwrap();
+        let Some(Node::Custom(stored)) = graph.get_node(&id).await.unwrap() else {
+            panic!("custom node missing");
+        };
+        assert_eq!(stored.payload["title"], "Login fails on Safari");
+        assert_eq!(stored.metadata["assignee"], "ops");
+        assert_eq!(stored.created_at, node.created_at);
+
+        // The stored kind's schema still applies
+        let mut invalid = node.clone();
+        invalid.payload = json!({});
+        assert!(graph.update_custom_node(invalid).await.is_err());
+
+        let missing = CustomNode::new("ticket".to_string(), json!({"title": "New"}));
+        let missing_id = missing.id;
+        let err = graph.update_custom_node(missing).await.unwrap_err();
+        assert!(matches!(err, Error::NodeNotFound(_)));
+        assert!(graph.get_node(&missing_id).await.unwrap().is_none());
+
+        let mut rekinded = node.clone();
+        rekinded.kind = "document".to_string();
+        assert!(matches!(
+            graph.update_custom_node(rekinded).await,
+            Err(Error::ValidationError(_))
+        ));
+        let mut moved = node.clone();
+        moved.session_id = Some(other.id);
+        assert!(matches!(
+            graph.update_custom_node(moved).await,
+            Err(Error::ValidationError(_))
+        ));
+        let other_nodes = graph.get_session_nodes(&other.id).await.unwrap();
+        assert!(other_nodes.iter().all(|n| n.id() != id));
+
+        let not_custom = CustomNode {
+            id: session.node_id,
+            ..node
+        };
+        assert!(matches!(
+            graph.update_custom_node(not_custom).await,
+            Err(Error::ValidationError(_))
+        ));
+    }
+}
@@ -0,0 +1,485 @@
+//! Validation of custom node payloads against JSON Schema
+//!
+//! Implements the structural core of JSON Schema (draft 2020-12):
+//!
+//! - `type`, `enum`, `const`
+//! - `properties`, `required`, `additionalProperties`, `minProperties`,
+//!   `maxProperties`
+//! - `items`, `minItems`, `maxItems`, `uniqueItems`
+//! - `minLength`, `maxLength`, `pattern`
+//! - `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+//!   `multipleOf`
+//! - `allOf`, `anyOf`, `oneOf`, `not`
+//!
+//! Annotations such as `title`, `description`, `default` and `format` are
+//! accepted and ignored. Any other keyword, including `$ref`, is rejected when
+//! the schema is registered rather than silently skipped during validation.
+
+use crate::{Error, Result};
+use regex::Regex;
+use serde_json::{Map, Value};
+
+/// Keywords that carry no validation meaning
+const ANNOTATIONS: &[&str] = &[
+    "$schema",
+    "$id",
+    "$comment",
+    "title",
+    "description",
+    "default",
+    "examples",
+    "format",
+    "deprecated",
+    "readOnly",
+    "writeOnly",
+];
+
+const TYPES: &[&str] = &[
+    "null", "boolean", "object", "array", "number", "integer", "string",
+];
+
+/// Check that `schema` only uses supported keywords with well-formed values
+pub(crate) fn check_schema(schema: &Value) -> Result<()> {
+    check_at(schema, "#")
+}
+
+fn check_at(schema: &Value, at: &str) -> Result<()> {
+    let keywords = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(keywords) => keywords,
+        _ => return Err(invalid(at, "a schema must be an object or a boolean")),
+    };
+
+    for (keyword, value) in keywords {
+        let here = format!("{at}/{keyword}");
+        match keyword.as_str() {
+            "type" => {
+                let names: Vec<&Value> = match value {
+                    Value::Array(names) => names.iter().collect(),
+                    other => vec![other],
+                };
+                for name in names {
+                    if !name.as_str().is_some_and(|n| TYPES.contains(&n)) {
+                        return Err(invalid(&here, &format!("unknown type {name}")));
+                    }
+                }
+            }
+            "enum" => {
+                if !value.is_array() {
+                    return Err(invalid(&here, "expected an array"));
+                }
+            }
+            "properties" => {
+                let Value::Object(properties) = value else {
+                    return Err(invalid(&here, "expected an object"));
+                };
+                for (name, property) in properties {
+                    check_at(property, &format!("{here}/{name}"))?;
+                }
+            }
+            "required" => {
+                let all_strings = value
+                    .as_array()
+                    .is_some_and(|names| names.iter().all(Value::is_string));
+                if !all_strings {
+                    return Err(invalid(&here, "expected an array of property names"));
+                }
+            }
+            "additionalProperties" | "items" | "not" => check_at(value, &here)?,
+            "allOf" | "anyOf" | "oneOf" => {
+                let Some(schemas) = value.as_array().filter(|s| !s.is_empty()) else {
+                    return Err(invalid(&here, "expected a non-empty array of schemas"));
+                };
+                for (i, schema) in schemas.iter().enumerate() {
+                    check_at(schema, &format!("{here}/{i}"))?;
+                }
+            }
+            "minProperties" | "maxProperties" | "minItems" | "maxItems" | "minLength"
+            | "maxLength" => {
+                if value.as_u64().is_none() {
+                    return Err(invalid(&here, "expected a non-negative integer"));
+                }
+            }
+            "minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum" => {
+                if !value.is_number() {
+                    return Err(invalid(&here, "expected a number"));
+                }
+            }
+            "multipleOf" => {
+                if !value.as_f64().is_some_and(|m| m > 0.0) {
+                    return Err(invalid(&here, "expected a positive number"));
+                }
+            }
+            "uniqueItems" => {
+                if !value.is_boolean() {
+                    return Err(invalid(&here, "expected a boolean"));
+                }
+            }
+            "pattern" => {
+                let Some(pattern) = value.as_str() else {
+                    return Err(invalid(&here, "expected a string"));
+                };
+                Regex::new(pattern).map_err(|e| invalid(&here, &e.to_string()))?;
+            }
+            "const" => {}
+            other if ANNOTATIONS.contains(&other) => {}
+            other => return Err(invalid(at, &format!("unsupported keyword '{other}'"))),
+        }
+    }
+    Ok(())
+}
+
+fn invalid(at: &str, message: &str) -> Error {
+    Error::ValidationError(format!("Invalid schema at {at}: {message}"))
+}
+
+/// Validate `value` against a schema accepted by [`check_schema`]
+///
+/// Returns a description of the first violation, naming its location as a
+/// dot-separated path below `path`.
+pub(crate) fn validate(
+    schema: &Value,
+    value: &Value,
+    path: &str,
+) -> std::result::Result<(), String> {
+    let keywords = match schema {
+        Value::Object(keywords) => keywords,
+        Value::Bool(false) => return Err(format!("{path}: no value is allowed here")),
+        // `true`, and anything `check_schema` would have rejected
+        _ => return Ok(()),
+    };
+
+    if let Some(types) = keywords.get("type") {
+        let allowed: Vec<&str> = match types {
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            other => other.as_str().into_iter().collect(),
+        };
+        if !allowed.iter().any(|t| has_type(value, t)) {
+            return Err(format!(
+                "{path}: expected {}, found {}",
+                allowed.join(" or "),
+                type_name(value)
+            ));
+        }
+    }
+    if let Some(options) = keywords.get("enum").and_then(Value::as_array) {
+        if !options.iter().any(|option| equal(option, value)) {
+            return Err(format!("{path}: {value} is not one of the allowed values"));
+        }
+    }
+    if let Some(expected) = keywords.get("const") {
+        if !equal(expected, value) {
+            return Err(format!("{path}: expected {expected}"));
+        }
+    }
+
+    match value {
+        Value::Object(object) => validate_object(keywords, object, path)?,
+        Value::Array(items) => validate_array(keywords, items, path)?,
+        Value::String(s) => validate_string(keywords, s, path)?,
+        Value::Number(_) => validate_number(keywords, value, path)?,
+        _ => {}
+    }
+
+    if let Some(schemas) = keywords.get("allOf").and_then(Value::as_array) {
+        for schema in schemas {
+            validate(schema, value, path)?;
+        }
+    }
+    if let Some(schemas) = keywords.get("anyOf").and_then(Value::as_array) {
+        if !schemas.iter().any(|s| validate(s, value, path).is_ok()) {
+            return Err(format!("{path}: does not match any of the allowed schemas"));
+        }
+    }
+    if let Some(schemas) = keywords.get("oneOf").and_then(Value::as_array) {
+        let matching = schemas
+            .iter()
+            .filter(|s| validate(s, value, path).is_ok())
+            .count();
+        if matching != 1 {
+            return Err(format!(
+                "{path}: matches {matching} of the oneOf schemas instead of exactly one"
+            ));
+        }
+    }
+    if let Some(schema) = keywords.get("not") {
+        if validate(schema, value, path).is_ok() {
+            return Err(format!("{path}: matches a schema it must not match"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_object(
+    keywords: &Map<String, Value>,
+    object: &Map<String, Value>,
+    path: &str,
+) -> std::result::Result<(), String> {
+    if let Some(required) = keywords.get("required").and_then(Value::as_array) {
+        for name in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(name) {
+                return Err(format!("{path}: missing required property '{name}'"));
+            }
+        }
+    }
+    check_count(
+        keywords,
+        "minProperties",
+        "maxProperties",
+        object.len(),
+        "properties",
+        path,
+    )?;
+
+    let properties = keywords.get("properties").and_then(Value::as_object);
+    for (name, item) in object {
+        let item_path = format!("{path}.{name}");
+        match properties.and_then(|p| p.get(name)) {
+            Some(schema) => validate(schema, item, &item_path)?,
+            None => match keywords.get("additionalProperties") {
+                Some(Value::Bool(false)) => {
+                    return Err(format!("{path}: unexpected property '{name}'"))
+                }
+                Some(schema) => validate(schema, item, &item_path)?,
+                None => {}
+            },
+        }
+    }
+    Ok(())
+}
+
+fn validate_array(
+    keywords: &Map<String, Value>,
+    items: &[Value],
+    path: &str,
+) -> std::result::Result<(), String> {
+    check_count(keywords, "minItems", "maxItems", items.len(), "items", path)?;
+    if keywords.get("uniqueItems") == Some(&Value::Bool(true)) {
+        for (i, item) in items.iter().enumerate() {
+            if items[..i].iter().any(|earlier| equal(earlier, item)) {
+                return Err(format!("{path}: item {i} repeats an earlier item"));
+            }
+        }
+    }
+    if let Some(schema) = keywords.get("items") {
+        for (i, item) in items.iter().enumerate() {
+            validate(schema, item, &format!("{path}.{i}"))?;
+        }
+    }
+    Ok(())
+}
+
+fn validate_string(
+    keywords: &Map<String, Value>,
+    s: &str,
+    path: &str,
+) -> std::result::Result<(), String> {
+    check_count(
+        keywords,
+        "minLength",
+        "maxLength",
+        s.chars().count(),
+        "characters",
+        path,
+    )?;
+    if let Some(pattern) = keywords.get("pattern").and_then(Value::as_str) {
+        // Patterns were compiled when the schema was registered
+        if Regex::new(pattern).is_ok_and(|re| !re.is_match(s)) {
+            return Err(format!("{path}: does not match pattern '{pattern}'"));
+        }
+    }
+    Ok(())
+}
+
+fn validate_number(
+    keywords: &Map<String, Value>,
+    value: &Value,
+    path: &str,
+) -> std::result::Result<(), String> {
+    let Some(n) = value.as_f64() else {
+        return Ok(());
+    };
+    let bound = |keyword: &str| keywords.get(keyword).and_then(Value::as_f64);
+    if let Some(min) = bound("minimum").filter(|min| n < *min) {
+        return Err(format!("{path}: {value} is less than the minimum {min}"));
+    }
+    if let Some(max) = bound("maximum").filter(|max| n > *max) {
+        return Err(format!("{path}: {value} is greater than the maximum {max}"));
+    }
+    if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+        return Err(format!("{path}: {value} must be greater than {min}"));
+    }
+    if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+        return Err(format!("{path}: {value} must be less than {max}"));
+    }
+    if let Some(step) = bound("multipleOf") {
+        let quotient = n / step;
+        if (quotient - quotient.round()).abs() > 1e-9 {
+            return Err(format!("{path}: {value} is not a multiple of {step}"));
+        }
+    }
+    Ok(())
+}
+
+fn check_count(
+    keywords: &Map<String, Value>,
+    min_keyword: &str,
+    max_keyword: &str,
+    count: usize,
+    unit: &str,
+    path: &str,
+) -> std::result::Result<(), String> {
+    let limit = |keyword: &str| keywords.get(keyword).and_then(Value::as_u64);
+    let count = count as u64;
+    if let Some(min) = limit(min_keyword).filter(|min| count < *min) {
+        return Err(format!("{path}: has {count} {unit}, fewer than {min}"));
+    }
+    if let Some(max) = limit(max_keyword).filter(|max| count > *max) {
+        return Err(format!("{path}: has {count} {unit}, more than {max}"));
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// JSON equality, treating numbers equal by value (`1` equals `1.0`)
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => x.as_f64() == y.as_f64(),
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| equal(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(k, v)| y.get(k).is_some_and(|w| equal(v, w)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ticket_schema() -> Value {
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "Ticket",
+            "type": "object",
+            "required": ["title", "priority"],
+            "additionalProperties": false,
+            "properties": {
+                "title": {"type": "string", "minLength": 3},
+                "priority": {"type": "integer", "minimum": 1, "maximum": 4},
+                "status": {"enum": ["open", "closed"]},
+                "labels": {"type": "array", "items": {"type": "string", "pattern": "^[a-z-]+$"}, "uniqueItems": true},
+                "estimate": {"anyOf": [{"type": "null"}, {"type": "number", "exclusiveMinimum": 0}]}
+            }
+        })
+    }
+
+    #[test]
+    fn test_accepts_and_rejects_payloads() {
+        let schema = ticket_schema();
+        check_schema(&schema).unwrap();
+
+        let valid = json!({"title": "Login fails", "priority": 2, "labels": ["auth", "sso"], "estimate": null});
+        assert!(validate(&schema, &valid, "payload").is_ok());
+        assert!(validate(
+            &schema,
+            &json!({"title": "Fix", "priority": 3.0}),
+            "payload"
+        )
+        .is_ok());
+
+        let cases = [
+            (
+                json!({"title": "Login fails"}),
+                "payload: missing required property 'priority'",
+            ),
+            (
+                json!({"title": "Login", "priority": "high"}),
+                "payload.priority: expected integer, found string",
+            ),
+            (
+                json!({"title": "Login", "priority": 9}),
+                "payload.priority: 9 is greater than the maximum 4",
+            ),
+            (
+                json!({"title": "Login", "priority": 1, "owner": "sam"}),
+                "payload: unexpected property 'owner'",
+            ),
+            (
+                json!({"title": "Login", "priority": 1, "labels": ["Auth"]}),
+                "payload.labels.0: does not match pattern '^[a-z-]+$'",
+            ),
+            (
+                json!({"title": "Login", "priority": 1, "labels": ["a", "a"]}),
+                "payload.labels: item 1 repeats an earlier item",
+            ),
+            (
+                json!({"title": "Login", "priority": 1, "status": "stale"}),
+                "payload.status: \"stale\" is not one of the allowed values",
+            ),
+            (
+                json!({"title": "Login", "priority": 1, "estimate": 0}),
+                "payload.estimate: does not match any of the allowed schemas",
+            ),
+            (json!([1, 2]), "payload: expected object, found array"),
+        ];
+        for (payload, expected) in cases {
+            assert_eq!(
+                validate(&schema, &payload, "payload").unwrap_err(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsupported_schemas() {
+        for (schema, expected) in [
+            (
+                json!({"$ref": "#/$defs/ticket"}),
+                "unsupported keyword '$ref'",
+            ),
+            (json!({"type": "text"}), "unknown type \"text\""),
+            (
+                json!({"properties": {"a": {"minLength": -1}}}),
+                "#/properties/a/minLength",
+            ),
+            (json!({"pattern": "("}), "#/pattern"),
+            (json!("object"), "must be an object or a boolean"),
+        ] {
+            let err = check_schema(&schema).unwrap_err().to_string();
+            assert!(err.contains(expected), "{err}");
+        }
+        check_schema(&json!(true)).unwrap();
+    }
+}
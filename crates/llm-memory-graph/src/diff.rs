@@ -0,0 +1,879 @@
+//! Session comparison
+//!
+//! [`AsyncMemoryGraph::diff_sessions`] lines up the turns of two sessions, for
+//! example two runs of an A/B test between prompt templates, and reports how
+//! each pair differs: line diffs of prompt and response text, tool calls that
+//! were added, removed or called with other parameters, token and latency
+//! deltas, and which agents handled the turn.
+//!
+//! A turn is a prompt with its responses, the tools those responses invoked
+//! and the agents involved. Turns are aligned according to a
+//! [`TurnAlignment`]; turns without a counterpart are reported as only being
+//! in one of the sessions.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::diff::{DiffOptions, TurnAlignment};
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::{Config, SessionId};
+//!
+//! # async fn example(a: SessionId, b: SessionId) -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let options = DiffOptions::new().alignment(TurnAlignment::Similarity);
+//! let diff = graph.diff_sessions(a, b, &options).await?;
+//! println!(
+//!     "{} changed turns, {:+} tokens",
+//!     diff.summary.changed, diff.summary.token_delta
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::engine::AsyncMemoryGraph;
+use crate::{
+    EdgeType, Error, Node, NodeId, PromptNode, ResponseNode, Result, SessionId, TemplateId,
+};
+use serde::Serialize;
+use serde_json::Value;
+use similar::ChangeTag;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+/// Default minimum prompt similarity for [`TurnAlignment::Similarity`]
+const DEFAULT_SIMILARITY_THRESHOLD: f32 = 0.5;
+
+/// How turns of the two sessions are paired up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnAlignment {
+    /// The n-th turn of one session against the n-th turn of the other
+    #[default]
+    Order,
+    /// Turns instantiated from the same template, keeping conversation order
+    ///
+    /// Turns left between two matches are paired by position, so sessions
+    /// that use different templates still line up turn by turn.
+    Template,
+    /// Turns whose prompts are most alike, keeping conversation order
+    ///
+    /// Prompts less similar than the threshold are never paired.
+    Similarity,
+}
+
+impl fmt::Display for TurnAlignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Order => "order",
+            Self::Template => "template",
+            Self::Similarity => "similarity",
+        })
+    }
+}
+
+impl FromStr for TurnAlignment {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "order" | "position" => Ok(Self::Order),
+            "template" => Ok(Self::Template),
+            "similarity" | "content" => Ok(Self::Similarity),
+            _ => Err(Error::ValidationError(format!(
+                "Unknown turn alignment '{s}'. Use order, template or similarity"
+            ))),
+        }
+    }
+}
+
+/// Options for [`AsyncMemoryGraph::diff_sessions`]
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    alignment: TurnAlignment,
+    similarity_threshold: f32,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiffOptions {
+    /// Align by order with the default similarity threshold
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            alignment: TurnAlignment::Order,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+        }
+    }
+
+    /// Set how turns are paired up
+    #[must_use]
+    pub const fn alignment(mut self, alignment: TurnAlignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
+
+    /// Minimum prompt similarity, between 0 and 1, for
+    /// [`TurnAlignment::Similarity`] to pair two turns
+    #[must_use]
+    pub fn similarity_threshold(mut self, threshold: f32) -> Self {
+        self.similarity_threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+}
+
+/// Kind of a line in a [`TextDiff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineChange {
+    /// In both texts
+    Equal,
+    /// Only in the first text
+    Delete,
+    /// Only in the second text
+    Insert,
+}
+
+/// A line of a [`TextDiff`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiffLine {
+    /// Whether the line was kept, removed or added
+    pub change: LineChange,
+    /// Line text without its line break
+    pub text: String,
+}
+
+/// Line diff between two texts
+#[derive(Debug, Clone, Serialize)]
+pub struct TextDiff {
+    /// Word-level similarity between 0 and 1
+    pub similarity: f32,
+    /// Lines of both texts in diff order
+    pub lines: Vec<DiffLine>,
+}
+
+impl TextDiff {
+    /// Diff `a` against `b`
+    #[must_use]
+    pub fn new(a: &str, b: &str) -> Self {
+        let lines = similar::TextDiff::from_lines(a, b)
+            .iter_all_changes()
+            .map(|change| DiffLine {
+                change: match change.tag() {
+                    ChangeTag::Equal => LineChange::Equal,
+                    ChangeTag::Delete => LineChange::Delete,
+                    ChangeTag::Insert => LineChange::Insert,
+                },
+                text: change.value().trim_end_matches(['\r', '\n']).to_string(),
+            })
+            .collect();
+        Self {
+            similarity: similarity(a, b),
+            lines,
+        }
+    }
+
+    /// Whether the texts are identical
+    #[must_use]
+    pub fn is_equal(&self) -> bool {
+        self.lines.iter().all(|l| l.change == LineChange::Equal)
+    }
+}
+
+/// A tool invoked during a turn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCall {
+    /// Tool name
+    pub name: String,
+    /// Parameters the tool was called with
+    pub parameters: Value,
+    /// Whether the call succeeded
+    pub success: bool,
+}
+
+/// One side of an aligned turn
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnSide {
+    /// Position of the turn in its session, from 0
+    pub index: usize,
+    /// Prompt node
+    pub prompt_id: NodeId,
+    /// Prompt text
+    pub prompt: String,
+    /// Response texts joined by blank lines
+    pub response: String,
+    /// Template the prompt was instantiated from
+    pub template: Option<TemplateId>,
+    /// Total tokens of the responses
+    pub tokens: u64,
+    /// Total latency of the responses in milliseconds
+    pub latency_ms: u64,
+    /// Agent the prompt was handled by
+    pub handled_by: Option<String>,
+    /// Agents the responses handed off to
+    pub handoffs: Vec<String>,
+    /// Tools the responses invoked, in call order
+    pub tools: Vec<ToolCall>,
+}
+
+/// How a tool call differs between two turns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChange {
+    /// Same parameters and outcome
+    Unchanged,
+    /// Called in both turns with other parameters or outcome
+    Changed,
+    /// Only called in the first turn
+    Removed,
+    /// Only called in the second turn
+    Added,
+}
+
+/// A tool call compared between two turns
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolCallDiff {
+    /// Tool name
+    pub name: String,
+    /// Kind of difference
+    pub change: ToolChange,
+    /// Parameters in the first turn
+    pub parameters_a: Option<Value>,
+    /// Parameters in the second turn
+    pub parameters_b: Option<Value>,
+    /// Top-level parameters whose values differ
+    pub changed_parameters: Vec<String>,
+}
+
+/// Agents involved in two turns, reported when they differ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AgentDiff {
+    /// Agent that handled the first prompt
+    pub handled_by_a: Option<String>,
+    /// Agent that handled the second prompt
+    pub handled_by_b: Option<String>,
+    /// Handoffs in the first turn
+    pub handoffs_a: Vec<String>,
+    /// Handoffs in the second turn
+    pub handoffs_b: Vec<String>,
+}
+
+/// How an aligned turn differs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TurnStatus {
+    /// Same text, tools and agents
+    Unchanged,
+    /// Present in both sessions with differences
+    Changed,
+    /// Only in the first session
+    OnlyInA,
+    /// Only in the second session
+    OnlyInB,
+}
+
+/// Comparison of one pair of aligned turns
+#[derive(Debug, Clone, Serialize)]
+pub struct TurnDiff {
+    /// Kind of difference
+    pub status: TurnStatus,
+    /// Turn in the first session
+    pub a: Option<TurnSide>,
+    /// Turn in the second session
+    pub b: Option<TurnSide>,
+    /// Prompt text diff, when both turns exist
+    pub prompt: Option<TextDiff>,
+    /// Response text diff, when both turns exist
+    pub response: Option<TextDiff>,
+    /// Tool calls compared by name in call order; a turn only in one
+    /// session lists its calls as added or removed
+    pub tools: Vec<ToolCallDiff>,
+    /// Tokens of the second turn minus the first
+    pub token_delta: i64,
+    /// Latency of the second turn minus the first, in milliseconds
+    pub latency_delta_ms: i64,
+    /// Agent differences, if any
+    pub agents: Option<AgentDiff>,
+}
+
+/// Totals over a whole [`SessionDiff`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DiffSummary {
+    /// Turns in the first session
+    pub turns_a: usize,
+    /// Turns in the second session
+    pub turns_b: usize,
+    /// Aligned turns without differences
+    pub unchanged: usize,
+    /// Aligned turns with differences
+    pub changed: usize,
+    /// Turns only in the first session
+    pub only_in_a: usize,
+    /// Turns only in the second session
+    pub only_in_b: usize,
+    /// Total tokens of the first session
+    pub tokens_a: u64,
+    /// Total tokens of the second session
+    pub tokens_b: u64,
+    /// Tokens of the second session minus the first
+    pub token_delta: i64,
+    /// Total response latency of the first session in milliseconds
+    pub latency_a_ms: u64,
+    /// Total response latency of the second session in milliseconds
+    pub latency_b_ms: u64,
+    /// Latency of the second session minus the first, in milliseconds
+    pub latency_delta_ms: i64,
+}
+
+/// Turn-by-turn comparison of two sessions
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionDiff {
+    /// First session
+    pub session_a: SessionId,
+    /// Second session
+    pub session_b: SessionId,
+    /// How turns were aligned
+    pub alignment: TurnAlignment,
+    /// Aligned turns in conversation order
+    pub turns: Vec<TurnDiff>,
+    /// Totals
+    pub summary: DiffSummary,
+}
+
+impl SessionDiff {
+    /// Whether the sessions have no differences at all
+    #[must_use]
+    pub fn is_identical(&self) -> bool {
+        self.turns.iter().all(|t| t.status == TurnStatus::Unchanged)
+    }
+}
+
+impl AsyncMemoryGraph {
+    /// Compare the turns of two sessions
+    ///
+    /// Forked sessions are compared including the history they share with
+    /// their parent, see [`AsyncMemoryGraph::branch_thread`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either session does not exist or storage fails.
+    pub async fn diff_sessions(
+        &self,
+        a: SessionId,
+        b: SessionId,
+        options: &DiffOptions,
+    ) -> Result<SessionDiff> {
+        let turns_a = self.turn_sides(a).await?;
+        let turns_b = self.turn_sides(b).await?;
+
+        let pairs = align(&turns_a, &turns_b, options);
+        let mut summary = DiffSummary {
+            turns_a: turns_a.len(),
+            turns_b: turns_b.len(),
+            tokens_a: turns_a.iter().map(|t| t.tokens).sum(),
+            tokens_b: turns_b.iter().map(|t| t.tokens).sum(),
+            latency_a_ms: turns_a.iter().map(|t| t.latency_ms).sum(),
+            latency_b_ms: turns_b.iter().map(|t| t.latency_ms).sum(),
+            ..DiffSummary::default()
+        };
+        summary.token_delta = delta(summary.tokens_a, summary.tokens_b);
+        summary.latency_delta_ms = delta(summary.latency_a_ms, summary.latency_b_ms);
+
+        let mut side_a: Vec<Option<TurnSide>> = turns_a.into_iter().map(Some).collect();
+        let mut side_b: Vec<Option<TurnSide>> = turns_b.into_iter().map(Some).collect();
+        let turns: Vec<TurnDiff> = pairs
+            .into_iter()
+            .map(|(i, j)| {
+                diff_turn(
+                    i.and_then(|i| side_a[i].take()),
+                    j.and_then(|j| side_b[j].take()),
+                )
+            })
+            .collect();
+
+        for turn in &turns {
+            match turn.status {
+                TurnStatus::Unchanged => summary.unchanged += 1,
+                TurnStatus::Changed => summary.changed += 1,
+                TurnStatus::OnlyInA => summary.only_in_a += 1,
+                TurnStatus::OnlyInB => summary.only_in_b += 1,
+            }
+        }
+
+        Ok(SessionDiff {
+            session_a: a,
+            session_b: b,
+            alignment: options.alignment,
+            turns,
+            summary,
+        })
+    }
+
+    /// Collect the turns of a session
+    async fn turn_sides(&self, session_id: SessionId) -> Result<Vec<TurnSide>> {
+        let mut prompts: Vec<PromptNode> = Vec::new();
+        let mut responses: HashMap<NodeId, Vec<ResponseNode>> = HashMap::new();
+        for node in self.branch_thread(session_id).await? {
+            match node {
+                Node::Prompt(prompt) => prompts.push(prompt),
+                Node::Response(response) => {
+                    responses
+                        .entry(response.prompt_id)
+                        .or_default()
+                        .push(response);
+                }
+                _ => {}
+            }
+        }
+
+        let mut turns = Vec::with_capacity(prompts.len());
+        for (index, prompt) in prompts.into_iter().enumerate() {
+            let replies = responses.remove(&prompt.id).unwrap_or_default();
+
+            let mut template = prompt.template_id;
+            let mut handled_by = None;
+            for edge in self.get_outgoing_edges(&prompt.id).await? {
+                match (edge.edge_type, self.get_node(&edge.to).await?) {
+                    (EdgeType::HandledBy, Some(Node::Agent(agent))) => {
+                        handled_by = Some(agent.name);
+                    }
+                    (EdgeType::Instantiates, Some(Node::Template(t))) if template.is_none() => {
+                        template = Some(t.id);
+                    }
+                    _ => {}
+                }
+            }
+
+            let mut handoffs = Vec::new();
+            let mut tools = Vec::new();
+            for response in &replies {
+                for edge in self.get_outgoing_edges(&response.id).await? {
+                    match (edge.edge_type, self.get_node(&edge.to).await?) {
+                        (EdgeType::TransfersTo, Some(Node::Agent(agent))) => {
+                            handoffs.push(agent.name);
+                        }
+                        (EdgeType::Invokes, Some(Node::ToolInvocation(tool))) => tools.push(tool),
+                        _ => {}
+                    }
+                }
+            }
+            tools.sort_by_key(|t| t.timestamp);
+
+            turns.push(TurnSide {
+                index,
+                prompt_id: prompt.id,
+                prompt: prompt.content,
+                response: replies
+                    .iter()
+                    .map(|r| r.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n\n"),
+                template,
+                tokens: replies
+                    .iter()
+                    .map(|r| u64::from(r.usage.total_tokens))
+                    .sum(),
+                latency_ms: replies.iter().map(|r| r.metadata.latency_ms).sum(),
+                handled_by,
+                handoffs,
+                tools: tools
+                    .into_iter()
+                    .map(|t| ToolCall {
+                        name: t.tool_name,
+                        parameters: t.parameters,
+                        success: t.success,
+                    })
+                    .collect(),
+            });
+        }
+        Ok(turns)
+    }
+}
+
+/// Pair up turn indices in conversation order
+fn align(
+    a: &[TurnSide],
+    b: &[TurnSide],
+    options: &DiffOptions,
+) -> Vec<(Option<usize>, Option<usize>)> {
+    let matches = match options.alignment {
+        TurnAlignment::Order => (0..a.len().min(b.len())).map(|i| (i, i)).collect(),
+        TurnAlignment::Template => best_matches(a, b, |x, y| match (x.template, y.template) {
+            (Some(tx), Some(ty)) if tx == ty => Some(1.0),
+            _ => None,
+        }),
+        TurnAlignment::Similarity => best_matches(a, b, |x, y| {
+            let score = similarity(&x.prompt, &y.prompt);
+            (score >= options.similarity_threshold).then_some(score)
+        }),
+    };
+    let pair_gaps = options.alignment == TurnAlignment::Template;
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (mi, mj) in matches
+        .into_iter()
+        .chain(std::iter::once((a.len(), b.len())))
+    {
+        while i < mi && j < mj && pair_gaps {
+            pairs.push((Some(i), Some(j)));
+            i += 1;
+            j += 1;
+        }
+        pairs.extend((i..mi).map(|i| (Some(i), None)));
+        pairs.extend((j..mj).map(|j| (None, Some(j))));
+        if mi < a.len() && mj < b.len() {
+            pairs.push((Some(mi), Some(mj)));
+        }
+        i = mi + 1;
+        j = mj + 1;
+    }
+    pairs
+}
+
+/// Order-preserving pairs with the highest total score
+fn best_matches<F>(a: &[TurnSide], b: &[TurnSide], score: F) -> Vec<(usize, usize)>
+where
+    F: Fn(&TurnSide, &TurnSide) -> Option<f32>,
+{
+    /// Step taken from a cell of the table
+    #[derive(Clone, Copy)]
+    enum Step {
+        Pair,
+        SkipA,
+        SkipB,
+    }
+
+    let (rows, cols) = (a.len(), b.len());
+    let mut best = vec![vec![0.0_f32; cols + 1]; rows + 1];
+    let mut steps = vec![vec![Step::SkipA; cols]; rows];
+    for i in (0..rows).rev() {
+        for j in (0..cols).rev() {
+            let (mut total, mut step) = if best[i + 1][j] >= best[i][j + 1] {
+                (best[i + 1][j], Step::SkipA)
+            } else {
+                (best[i][j + 1], Step::SkipB)
+            };
+            if let Some(pair) = score(&a[i], &b[j]) {
+                if best[i + 1][j + 1] + pair > total {
+                    total = best[i + 1][j + 1] + pair;
+                    step = Step::Pair;
+                }
+            }
+            best[i][j] = total;
+            steps[i][j] = step;
+        }
+    }
+
+    let mut matches = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < rows && j < cols {
+        match steps[i][j] {
+            Step::Pair => {
+                matches.push((i, j));
+                i += 1;
+                j += 1;
+            }
+            Step::SkipA => i += 1,
+            Step::SkipB => j += 1,
+        }
+    }
+    matches
+}
+
+fn diff_turn(first: Option<TurnSide>, second: Option<TurnSide>) -> TurnDiff {
+    let (Some(left), Some(right)) = (&first, &second) else {
+        let (status, tools, tokens, latency) = match (&first, &second) {
+            (Some(left), _) => (
+                TurnStatus::OnlyInA,
+                diff_tools(&left.tools, &[]),
+                delta(left.tokens, 0),
+                delta(left.latency_ms, 0),
+            ),
+            (_, Some(right)) => (
+                TurnStatus::OnlyInB,
+                diff_tools(&[], &right.tools),
+                delta(0, right.tokens),
+                delta(0, right.latency_ms),
+            ),
+            (None, None) => unreachable!("alignment never yields an empty pair"),
+        };
+        return TurnDiff {
+            status,
+            a: first,
+            b: second,
+            prompt: None,
+            response: None,
+            tools,
+            token_delta: tokens,
+            latency_delta_ms: latency,
+            agents: None,
+        };
+    };
+
+    let prompt = TextDiff::new(&left.prompt, &right.prompt);
+    let response = TextDiff::new(&left.response, &right.response);
+    let tools = diff_tools(&left.tools, &right.tools);
+    let agents =
+        (left.handled_by != right.handled_by || left.handoffs != right.handoffs).then(|| {
+            AgentDiff {
+                handled_by_a: left.handled_by.clone(),
+                handled_by_b: right.handled_by.clone(),
+                handoffs_a: left.handoffs.clone(),
+                handoffs_b: right.handoffs.clone(),
+            }
+        });
+
+    let unchanged = prompt.is_equal()
+        && response.is_equal()
+        && tools.iter().all(|t| t.change == ToolChange::Unchanged)
+        && agents.is_none();
+
+    TurnDiff {
+        status: if unchanged {
+            TurnStatus::Unchanged
+        } else {
+            TurnStatus::Changed
+        },
+        token_delta: delta(left.tokens, right.tokens),
+        latency_delta_ms: delta(left.latency_ms, right.latency_ms),
+        prompt: Some(prompt),
+        response: Some(response),
+        tools,
+        agents,
+        a: first,
+        b: second,
+    }
+}
+
+/// Match tool calls by name in call order
+fn diff_tools(a: &[ToolCall], b: &[ToolCall]) -> Vec<ToolCallDiff> {
+    let mut used = vec![false; b.len()];
+    let mut diffs = Vec::new();
+
+    for call in a {
+        let counterpart = b
+            .iter()
+            .enumerate()
+            .position(|(j, other)| !used[j] && other.name == call.name);
+        let Some(j) = counterpart else {
+            diffs.push(ToolCallDiff {
+                name: call.name.clone(),
+                change: ToolChange::Removed,
+                parameters_a: Some(call.parameters.clone()),
+                parameters_b: None,
+                changed_parameters: Vec::new(),
+            });
+            continue;
+        };
+        used[j] = true;
+
+        let other = &b[j];
+        let changed_parameters = changed_keys(&call.parameters, &other.parameters);
+        let same = call.parameters == other.parameters && call.success == other.success;
+        diffs.push(ToolCallDiff {
+            name: call.name.clone(),
+            change: if same {
+                ToolChange::Unchanged
+            } else {
+                ToolChange::Changed
+            },
+            parameters_a: Some(call.parameters.clone()),
+            parameters_b: Some(other.parameters.clone()),
+            changed_parameters,
+        });
+    }
+
+    for (call, _) in b.iter().zip(used).filter(|(_, used)| !used) {
+        diffs.push(ToolCallDiff {
+            name: call.name.clone(),
+            change: ToolChange::Added,
+            parameters_a: None,
+            parameters_b: Some(call.parameters.clone()),
+            changed_parameters: Vec::new(),
+        });
+    }
+    diffs
+}
+
+/// Top-level object keys whose values differ
+fn changed_keys(a: &Value, b: &Value) -> Vec<String> {
+    match (a, b) {
+        (Value::Object(x), Value::Object(y)) => x
+            .keys()
+            .chain(y.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|key| x.get(*key) != y.get(*key))
+            .cloned()
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Word-level similarity of two texts between 0 and 1
+fn similarity(a: &str, b: &str) -> f32 {
+    similar::TextDiff::from_words(a, b).ratio()
+}
+
+fn delta(a: u64, b: u64) -> i64 {
+    i64::try_from(b).unwrap_or(i64::MAX) - i64::try_from(a).unwrap_or(i64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentNode, Config, ResponseMetadata, TokenUsage, ToolInvocation};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    async fn turn(
+        graph: &AsyncMemoryGraph,
+        session: SessionId,
+        prompt: &str,
+        response: &str,
+        tokens: u32,
+    ) -> NodeId {
+        let prompt = graph
+            .add_prompt(session, prompt.to_string(), None)
+            .await
+            .unwrap();
+        let metadata = ResponseMetadata {
+            latency_ms: u64::from(tokens) * 10,
+            ..ResponseMetadata::default()
+        };
+        graph
+            .add_response(
+                prompt,
+                response.to_string(),
+                TokenUsage::new(0, tokens),
+                Some(metadata),
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_diff_sessions() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let a = graph.create_session().await.unwrap().id;
+        let b = graph.create_session().await.unwrap().id;
+
+        turn(&graph, a, "hello there", "hi", 10).await;
+        let ra = turn(&graph, a, "find flights to Oslo", "searching", 20).await;
+        turn(&graph, a, "thanks a lot", "bye", 5).await;
+
+        turn(&graph, b, "hello there", "hi", 10).await;
+        let rb = turn(&graph, b, "please find flights to Oslo", "searching", 30).await;
+        turn(&graph, b, "thanks a lot", "goodbye", 5).await;
+
+        let mut call_a = ToolInvocation::new(ra, "flights".to_string(), json!({"to": "OSL"}));
+        call_a.success = true;
+        graph.add_tool_invocation(call_a).await.unwrap();
+        let mut call_b = ToolInvocation::new(
+            rb,
+            "flights".to_string(),
+            json!({"to": "OSL", "nonstop": true}),
+        );
+        call_b.success = true;
+        graph.add_tool_invocation(call_b).await.unwrap();
+        graph
+            .add_tool_invocation(ToolInvocation::new(rb, "hotels".to_string(), json!({})))
+            .await
+            .unwrap();
+
+        let agent = AgentNode::new("travel".to_string(), "planner".to_string(), vec![]);
+        let agent_node = agent.node_id;
+        graph.add_agent(agent).await.unwrap();
+        graph.transfer_to_agent(rb, agent_node).await.unwrap();
+
+        let diff = graph
+            .diff_sessions(a, b, &DiffOptions::new())
+            .await
+            .unwrap();
+        let statuses: Vec<TurnStatus> = diff.turns.iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                TurnStatus::Unchanged,
+                TurnStatus::Changed,
+                TurnStatus::Changed
+            ]
+        );
+
+        let middle = &diff.turns[1];
+        assert_eq!(middle.token_delta, 10);
+        assert_eq!(middle.latency_delta_ms, 100);
+        assert_eq!(middle.tools.len(), 2);
+        assert_eq!(middle.tools[0].change, ToolChange::Changed);
+        assert_eq!(middle.tools[0].changed_parameters, vec!["nonstop"]);
+        assert_eq!(middle.tools[1].change, ToolChange::Added);
+        assert_eq!(
+            middle.agents.as_ref().unwrap().handoffs_b,
+            vec!["travel".to_string()]
+        );
+        let prompt = middle.prompt.as_ref().unwrap();
+        assert_eq!(prompt.lines.len(), 2);
+        assert_eq!(prompt.lines[0].change, LineChange::Delete);
+
+        assert_eq!(diff.summary.changed, 2);
+        assert_eq!(diff.summary.token_delta, 10);
+        assert!(!diff.is_identical());
+        assert!(graph
+            .diff_sessions(a, a, &DiffOptions::new())
+            .await
+            .unwrap()
+            .is_identical());
+    }
+
+    #[tokio::test]
+    async fn test_similarity_alignment() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let a = graph.create_session().await.unwrap().id;
+        let b = graph.create_session().await.unwrap().id;
+
+        turn(&graph, a, "summarise the quarterly report", "ok", 1).await;
+        turn(&graph, a, "list the open risks", "ok", 1).await;
+
+        turn(&graph, b, "what is the weather like", "sunny", 1).await;
+        turn(&graph, b, "summarise the quarterly report", "ok", 1).await;
+        turn(&graph, b, "list all open risks", "ok", 1).await;
+
+        let options = DiffOptions::new().alignment(TurnAlignment::Similarity);
+        let diff = graph.diff_sessions(a, b, &options).await.unwrap();
+        let statuses: Vec<TurnStatus> = diff.turns.iter().map(|t| t.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                TurnStatus::OnlyInB,
+                TurnStatus::Unchanged,
+                TurnStatus::Changed
+            ]
+        );
+        assert_eq!(diff.summary.only_in_b, 1);
+
+        // By order every turn lines up with an unrelated one
+        let diff = graph
+            .diff_sessions(a, b, &DiffOptions::new())
+            .await
+            .unwrap();
+        assert_eq!(diff.summary.changed, 2);
+        assert_eq!(diff.summary.only_in_b, 1);
+
+        assert_eq!(
+            "template".parse::<TurnAlignment>().unwrap(),
+            TurnAlignment::Template
+        );
+        assert!("fuzzy".parse::<TurnAlignment>().is_err());
+    }
+}
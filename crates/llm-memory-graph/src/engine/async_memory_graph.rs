@@ -4,14 +4,22 @@
 //! high-performance concurrent operations and non-blocking I/O.
 
 use crate::{Error, Result};
+use crate::attachments;
+use crate::constraints;
+use crate::custom::{self, EdgeKind, NodeKind};
 use crate::observatory::{
     EventPublisher, MemoryGraphEvent, MemoryGraphMetrics, NoOpPublisher, ObservatoryConfig,
+    PrometheusMetrics,
 };
-use crate::storage::{AsyncSledBackend, AsyncStorageBackend, StorageCache};
+use crate::query::{Cursor, Page, QueryCache};
+use crate::storage::{
+    AsyncReadOnlyBackend, AsyncSledBackend, AsyncStorageBackend, CacheStats, Lookup, StorageCache,
+};
+use crate::views::ViewCatalog;
 use crate::{
-    AgentId, AgentNode, Config, ConversationSession, Edge, EdgeType, Node, NodeId, PromptMetadata,
-    PromptNode, PromptTemplate, ResponseMetadata, ResponseNode, SessionId, TemplateId, TokenUsage,
-    ToolInvocation,
+    AgentId, AgentNode, Attachment, Config, ConstraintMode, ConversationSession, CustomNode, Edge,
+    EdgeId, EdgeType, Node, NodeId, PromptMetadata, PromptNode, PromptTemplate, ResponseMetadata,
+    ResponseNode, SessionId, TemplateId, TokenUsage, ToolInvocation,
 };
 use chrono::Utc;
 use std::collections::HashMap;
@@ -51,6 +59,23 @@ pub struct AsyncMemoryGraph {
     observatory: Option<Arc<dyn EventPublisher>>,
     metrics: Option<Arc<MemoryGraphMetrics>>,
     cache: StorageCache,
+    query_cache: Option<Arc<QueryCache>>,
+    query_metrics: Option<Arc<PrometheusMetrics>>,
+    views: Arc<ViewCatalog>,
+    read_only: bool,
+    edge_constraints: ConstraintMode,
+}
+
+/// Wrap `backend` for query result caching if the config asks for it
+fn with_query_cache(
+    backend: Arc<dyn AsyncStorageBackend>,
+    config: &Config,
+) -> (Arc<dyn AsyncStorageBackend>, Option<Arc<QueryCache>>) {
+    if config.query_cache_size == 0 {
+        return (backend, None);
+    }
+    let cache = Arc::new(QueryCache::new(config.query_cache_size));
+    (cache.watch(backend), Some(cache))
 }
 
 impl AsyncMemoryGraph {
@@ -88,16 +113,101 @@ impl AsyncMemoryGraph {
         let edge_capacity = node_capacity * 5; // Edges are smaller, cache more
 
         let cache = StorageCache::with_capacity(node_capacity, edge_capacity);
+        let (backend, views) = ViewCatalog::attach(Arc::new(backend)).await?;
+        let (backend, query_cache) = with_query_cache(backend, &config);
 
-        Ok(Self {
-            backend: Arc::new(backend),
+        let graph = Self {
+            backend,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             observatory: None,
             metrics: None,
             cache,
+            query_cache,
+            query_metrics: None,
+            views,
+            read_only: false,
+            edge_constraints: config.edge_constraints,
+        };
+        graph.recover_interrupted_responses().await?;
+        Ok(graph)
+    }
+
+    /// Open a read-only view of the graph at `config.path`
+    ///
+    /// The database directory (or a checkpoint copy of it) is snapshotted into a
+    /// temporary location, so this works even while another process, such as the
+    /// gRPC server, holds the database open. The snapshot may be stale, and torn
+    /// if the owning process writes while it is copied. All mutating methods
+    /// return [`Error::ReadOnly`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be copied or the snapshot fails to open.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use llm_memory_graph::engine::AsyncMemoryGraph;
+    /// use llm_memory_graph::Config;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let graph = AsyncMemoryGraph::open_read_only(Config::new("./data/graph.db")).await?;
+    ///     let stats = graph.stats().await?;
+    ///     println!("{} nodes", stats.node_count);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn open_read_only(config: Config) -> Result<Self> {
+        let backend = AsyncReadOnlyBackend::open(&config.path).await?;
+
+        let node_capacity = (config.cache_size_mb as u64) * 1000;
+        let edge_capacity = node_capacity * 5;
+
+        let (backend, views) = ViewCatalog::attach(Arc::new(backend)).await?;
+        let (backend, query_cache) = with_query_cache(backend, &config);
+
+        Ok(Self {
+            backend,
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            observatory: None,
+            metrics: None,
+            cache: StorageCache::with_capacity(node_capacity, edge_capacity),
+            query_cache,
+            query_metrics: None,
+            views,
+            read_only: true,
+            edge_constraints: config.edge_constraints,
         })
     }
 
+    /// Views defined on this graph
+    pub(crate) fn view_catalog(&self) -> &ViewCatalog {
+        &self.views
+    }
+
+    /// The storage backend, for crate modules that extend the graph
+    pub(crate) fn storage(&self) -> &dyn AsyncStorageBackend {
+        self.backend.as_ref()
+    }
+
+    /// Check `edges` against the custom edge kinds and built-in edge rules
+    pub(crate) async fn check_edges(&self, edges: &[Edge]) -> Result<()> {
+        for edge in edges {
+            custom::edge::check_edge(self.backend.as_ref(), edge).await?;
+        }
+        if self.edge_constraints == ConstraintMode::Off {
+            return Ok(());
+        }
+        let violations = constraints::check_edges(self.backend.as_ref(), edges).await?;
+        constraints::enforce(self.edge_constraints, violations)
+    }
+
+    /// Whether this graph was opened with [`AsyncMemoryGraph::open_read_only`]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     /// Open graph with Observatory integration
     ///
     /// # Examples
@@ -148,13 +258,34 @@ impl AsyncMemoryGraph {
             None
         };
 
-        Ok(Self {
-            backend: Arc::new(backend),
+        let (backend, views) = ViewCatalog::attach(Arc::new(backend)).await?;
+        let (backend, query_cache) = with_query_cache(backend, &config);
+
+        let graph = Self {
+            backend,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             observatory,
             metrics,
             cache,
-        })
+            query_cache,
+            query_metrics: None,
+            views,
+            read_only: false,
+            edge_constraints: config.edge_constraints,
+        };
+        graph.recover_interrupted_responses().await?;
+        Ok(graph)
+    }
+
+    /// Record the duration of every query run through this graph
+    ///
+    /// Queries built with [`query`](Self::query) and graph queries run with
+    /// [`execute_query`](Self::execute_query) report to the query duration
+    /// histogram in `metrics`, labelled with their plan shape.
+    #[must_use]
+    pub fn with_query_metrics(mut self, metrics: Arc<PrometheusMetrics>) -> Self {
+        self.query_metrics = Some(metrics);
+        self
     }
 
     /// Get metrics snapshot
@@ -174,6 +305,23 @@ impl AsyncMemoryGraph {
         }
     }
 
+    /// Account for a response written outside [`Self::add_response`]
+    pub(crate) async fn response_stored(&self, response: &ResponseNode) {
+        self.cache.invalidate_node(&response.id).await;
+        if let Some(metrics) = &self.metrics {
+            metrics.record_node_created();
+            metrics.record_response_generated();
+        }
+        self.publish_event(MemoryGraphEvent::ResponseGenerated {
+            response_id: response.id,
+            prompt_id: response.prompt_id,
+            content_length: response.content.len(),
+            tokens_used: response.usage,
+            latency_ms: response.metadata.latency_ms,
+            timestamp: Utc::now(),
+        });
+    }
+
     // ===== Session Management =====
 
     /// Create a new conversation session asynchronously
@@ -341,6 +489,7 @@ impl AsyncMemoryGraph {
             timestamp: chrono::Utc::now(),
             template_id: None,
             variables: HashMap::new(),
+            attachments: Vec::new(),
         };
 
         let prompt_id = prompt.id;
@@ -452,6 +601,7 @@ impl AsyncMemoryGraph {
             content: content.clone(),
             usage: token_usage,
             metadata: metadata.unwrap_or_default(),
+            attachments: Vec::new(),
         };
 
         let response_id = response.id;
@@ -489,6 +639,61 @@ impl AsyncMemoryGraph {
         Ok(response_id)
     }
 
+    // ===== Attachment Operations =====
+
+    /// Attach an image, document or other file to a prompt or response
+    ///
+    /// Content larger than [`attachments::INLINE_LIMIT`] is moved into the
+    /// blob store. Returns the attachment as stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the node is not a prompt or response,
+    /// or the attachment's size or hash do not match its content.
+    pub async fn add_attachment(
+        &self,
+        node_id: NodeId,
+        attachment: Attachment,
+    ) -> Result<Attachment> {
+        let mut node = self
+            .backend
+            .get_node(&node_id)
+            .await?
+            .ok_or_else(|| Error::NodeNotFound(node_id.to_string()))?;
+        let attachments = match &mut node {
+            Node::Prompt(prompt) => &mut prompt.attachments,
+            Node::Response(response) => &mut response.attachments,
+            _ => {
+                return Err(Error::ValidationError(format!(
+                    "Node {node_id} is not a prompt or response"
+                )))
+            }
+        };
+        let attachment = attachments::prepare(self.backend.as_ref(), attachment).await?;
+        attachments.push(attachment.clone());
+        self.backend.store_node(&node).await?;
+        self.cache.invalidate_node(&node_id).await;
+        Ok(attachment)
+    }
+
+    /// The content of an attachment, if the graph holds it
+    pub async fn attachment_data(&self, attachment: &Attachment) -> Result<Option<Vec<u8>>> {
+        attachments::load(self.backend.as_ref(), attachment).await
+    }
+
+    /// Store `bytes` in the blob store, returning the key to reference them by
+    ///
+    /// The key is the content's SHA-256, so storing the same bytes twice
+    /// keeps one copy.
+    pub async fn store_blob(&self, bytes: &[u8]) -> Result<String> {
+        attachments::put_blob(self.backend.as_ref(), bytes).await
+    }
+
+    /// The blob stored under `key`, if any
+    pub async fn blob(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        attachments::get_blob(self.backend.as_ref(), key).await
+    }
+
     // ===== Agent Operations =====
 
     /// Add an agent node asynchronously
@@ -715,6 +920,155 @@ impl AsyncMemoryGraph {
         Ok(())
     }
 
+    // ===== Custom Node Operations =====
+
+    /// Register a kind of custom node
+    ///
+    /// Nodes of a kind can only be written once it is registered. See
+    /// [`crate::custom`] for the supported JSON Schema keywords.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the name is invalid or taken or the
+    /// schema is unsupported, or an error if the graph is read-only or
+    /// storage fails.
+    pub async fn register_node_kind(&self, kind: NodeKind) -> Result<()> {
+        custom::register(self.backend.as_ref(), &kind).await
+    }
+
+    /// The kind registered as `name`, if any
+    pub async fn node_kind(&self, name: &str) -> Result<Option<NodeKind>> {
+        custom::get(self.backend.as_ref(), name).await
+    }
+
+    /// Every registered kind, by name
+    pub async fn list_node_kinds(&self) -> Result<Vec<NodeKind>> {
+        custom::list(self.backend.as_ref()).await
+    }
+
+    /// Remove a kind that has no nodes, returning whether it was registered
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if nodes of the kind still exist, or an
+    /// error if the graph is read-only or storage fails.
+    pub async fn drop_node_kind(&self, name: &str) -> Result<bool> {
+        custom::unregister(self.backend.as_ref(), name).await
+    }
+
+    /// Add a custom node, validating its payload against its kind's schema
+    ///
+    /// A node with a session is linked to the session with a `PartOf` edge.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the kind is not registered or the payload
+    /// does not satisfy its schema, or an error if the session does not exist
+    /// or storage fails.
+    pub async fn add_custom_node(&self, node: CustomNode) -> Result<NodeId> {
+        custom::check_node(self.backend.as_ref(), &node).await?;
+        if let Some(session_id) = node.session_id {
+            self.get_session(session_id).await?;
+        }
+
+        let node_id = node.id;
+        let session_id = node.session_id;
+        let node = Node::Custom(node);
+        self.backend.store_node(&node).await?;
+        self.cache.insert_node(node_id, node).await;
+
+        if let Some(session_id) = session_id {
+            let session_nodes = self.backend.get_session_nodes(&session_id).await?;
+            if let Some(session_node) = session_nodes.iter().find(|n| matches!(n, Node::Session(_)))
+            {
+                let edge = Edge::new(node_id, session_node.id(), EdgeType::PartOf);
+                self.backend.store_edge(&edge).await?;
+                self.cache.insert_edge(edge.id, edge).await;
+            }
+        }
+
+        if let Some(metrics) = &self.metrics {
+            metrics.record_node_created();
+        }
+
+        Ok(node_id)
+    }
+
+    /// Replace a custom node's payload and metadata, validating the payload
+    ///
+    /// Only `payload` and `metadata` are taken from `node`. Its kind and
+    /// session must match the stored node, and the creation time is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns `NodeNotFound` if no node has `node.id`, a validation error if
+    /// the stored node is not a custom node, `node` changes its kind or
+    /// session, or the payload does not satisfy the kind's schema, or an error
+    /// if storage fails.
+    pub async fn update_custom_node(&self, node: CustomNode) -> Result<()> {
+        let node_id = node.id;
+        let mut stored = match self.backend.get_node(&node_id).await? {
+            Some(Node::Custom(stored)) => stored,
+            Some(other) => {
+                return Err(Error::ValidationError(format!(
+                    "Node {node_id} is a {:?} node, not a custom node",
+                    other.node_type()
+                )))
+            }
+            None => return Err(Error::NodeNotFound(node_id.to_string())),
+        };
+        if node.kind != stored.kind {
+            return Err(Error::ValidationError(format!(
+                "Cannot change the kind of node {node_id} from '{}' to '{}'",
+                stored.kind, node.kind
+            )));
+        }
+        if node.session_id != stored.session_id {
+            return Err(Error::ValidationError(format!(
+                "Cannot move node {node_id} to another session"
+            )));
+        }
+
+        stored.payload = node.payload;
+        stored.metadata = node.metadata;
+        stored.updated_at = Utc::now();
+        custom::check_node(self.backend.as_ref(), &stored).await?;
+        self.backend.store_node(&Node::Custom(stored)).await?;
+        self.cache.invalidate_node(&node_id).await;
+        Ok(())
+    }
+
+    /// Register a custom edge type
+    ///
+    /// Edges of type [`EdgeType::Custom`] with this name can only be added
+    /// once it is registered, and are checked against its endpoints and
+    /// property specs.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the name is invalid or taken, or an
+    /// error if the graph is read-only or storage fails.
+    pub async fn register_edge_kind(&self, kind: EdgeKind) -> Result<()> {
+        custom::edge::register(self.backend.as_ref(), &kind).await
+    }
+
+    /// The edge kind registered as `name`, if any
+    pub async fn edge_kind(&self, name: &str) -> Result<Option<EdgeKind>> {
+        custom::edge::get(self.backend.as_ref(), name).await
+    }
+
+    /// Every registered edge kind, by name
+    pub async fn list_edge_kinds(&self) -> Result<Vec<EdgeKind>> {
+        custom::edge::list(self.backend.as_ref()).await
+    }
+
+    /// Remove an edge kind, returning whether it was registered
+    ///
+    /// Existing edges of the kind are kept, but no new ones can be added.
+    pub async fn drop_edge_kind(&self, name: &str) -> Result<bool> {
+        custom::edge::unregister(self.backend.as_ref(), name).await
+    }
+
     // ===== Edge and Traversal Operations =====
 
     /// Get a node by ID asynchronously (cache-aware)
@@ -811,12 +1165,33 @@ impl AsyncMemoryGraph {
         Ok(None)
     }
 
-    /// Add a custom edge asynchronously
+    /// Add an edge asynchronously
+    ///
+    /// Edges of a custom type are checked against its registered
+    /// [`EdgeKind`], and built-in edges against their
+    /// [constraints](crate::constraints) as configured.
     pub async fn add_edge(&self, from: NodeId, to: NodeId, edge_type: EdgeType) -> Result<()> {
         let edge = Edge::new(from, to, edge_type);
+        self.check_edges(std::slice::from_ref(&edge)).await?;
         self.backend.store_edge(&edge).await
     }
 
+    /// Add an edge with properties asynchronously, returning its ID
+    ///
+    /// The edge is checked like one added with [`Self::add_edge`].
+    pub async fn add_edge_with_properties(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        edge_type: EdgeType,
+        properties: HashMap<String, String>,
+    ) -> Result<EdgeId> {
+        let edge = Edge::with_properties(from, to, edge_type, properties);
+        self.check_edges(std::slice::from_ref(&edge)).await?;
+        self.backend.store_edge(&edge).await?;
+        Ok(edge.id)
+    }
+
     /// Get all outgoing edges from a node asynchronously
     pub async fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
         self.backend.get_outgoing_edges(node_id).await
@@ -837,12 +1212,25 @@ impl AsyncMemoryGraph {
     /// Store multiple nodes concurrently asynchronously
     ///
     /// This method leverages async concurrency to store multiple nodes in parallel.
-    pub async fn store_nodes_batch(&self, nodes: Vec<Node>) -> Result<Vec<NodeId>> {
+    /// Custom nodes are validated against their kinds, and the attachments of
+    /// prompts and responses checked, before anything is stored.
+    pub async fn store_nodes_batch(&self, mut nodes: Vec<Node>) -> Result<Vec<NodeId>> {
+        for node in &mut nodes {
+            if let Node::Custom(custom) = node {
+                custom::check_node(self.backend.as_ref(), custom).await?;
+            }
+            attachments::prepare_node(self.backend.as_ref(), node).await?;
+        }
         self.backend.store_nodes_batch(&nodes).await
     }
 
     /// Store multiple edges concurrently asynchronously
+    ///
+    /// Edges are checked like those added with [`Self::add_edge`] before
+    /// anything is stored; earlier edges of the batch count towards the
+    /// cardinality limits of later ones.
     pub async fn store_edges_batch(&self, edges: Vec<Edge>) -> Result<()> {
+        self.check_edges(&edges).await?;
         self.backend.store_edges_batch(&edges).await?;
         Ok(())
     }
@@ -1064,7 +1452,360 @@ impl AsyncMemoryGraph {
     /// }
     /// ```
     pub fn query(&self) -> crate::query::AsyncQueryBuilder {
-        crate::query::AsyncQueryBuilder::new(Arc::clone(&self.backend))
+        let mut builder = crate::query::AsyncQueryBuilder::new(Arc::clone(&self.backend));
+        if let Some(cache) = &self.query_cache {
+            builder = builder.cache(Arc::clone(cache));
+        }
+        if let Some(metrics) = &self.query_metrics {
+            builder = builder.metrics(Arc::clone(metrics));
+        }
+        builder
+    }
+
+    /// Node, edge and query result cache statistics
+    ///
+    /// The query cache counters stay at zero unless
+    /// [`Config::query_cache_size`] is set.
+    pub async fn cache_stats(&self) -> CacheStats {
+        let mut stats = self.cache.stats().await;
+        if let Some(query_cache) = &self.query_cache {
+            stats.query_cache_size = query_cache.len() as u64;
+            stats.query_cache_hits = query_cache.hits();
+            stats.query_cache_misses = query_cache.misses();
+        }
+        stats
+    }
+
+    /// Create an async traversal helper over this graph
+    ///
+    /// See [`crate::query::traversal`] for the available limits and filters.
+    pub fn traversal(&self) -> crate::query::AsyncGraphTraversal {
+        crate::query::AsyncGraphTraversal::new(Arc::clone(&self.backend))
+    }
+
+    /// Rebuild a session as a chat transcript in a provider message format
+    ///
+    /// See [`crate::transcript`] for how nodes map to messages.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use llm_memory_graph::engine::AsyncMemoryGraph;
+    /// use llm_memory_graph::transcript::{TranscriptFormat, TranscriptOptions};
+    /// use llm_memory_graph::{Config, SessionId};
+    ///
+    /// # async fn example(session_id: SessionId) -> Result<(), Box<dyn std::error::Error>> {
+    /// let graph = AsyncMemoryGraph::open(Config::default()).await?;
+    /// let options = TranscriptOptions::new().agents(true);
+    /// let body = graph
+    ///     .session_transcript(&session_id, TranscriptFormat::OpenAi, &options)
+    ///     .await?;
+    /// println!("{}", body["messages"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn session_transcript(
+        &self,
+        session_id: &SessionId,
+        format: crate::transcript::TranscriptFormat,
+        options: &crate::transcript::TranscriptOptions,
+    ) -> Result<serde_json::Value> {
+        let transcript = crate::transcript::Transcript::load(self, session_id, options).await?;
+        Ok(transcript.render(format))
+    }
+
+    /// Run a declarative graph query
+    ///
+    /// See [`crate::query::language`] for the query syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use llm_memory_graph::engine::AsyncMemoryGraph;
+    /// use llm_memory_graph::Config;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let graph = AsyncMemoryGraph::open(Config::default()).await?;
+    ///
+    ///     let result = graph
+    ///         .execute_query(
+    ///             "MATCH (r:Response)-[:RESPONDS_TO]->(p:Prompt) \
+    ///              WHERE r.usage.total_tokens > 1000 \
+    ///              RETURN p.content, r.usage.total_tokens AS tokens ORDER BY tokens DESC",
+    ///         )
+    ///         .await?;
+    ///
+    ///     for row in &result.rows {
+    ///         println!("{row:?}");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn execute_query(&self, expression: &str) -> Result<crate::query::QueryResult> {
+        let query = crate::query::language::parse_query(expression)?;
+        let plan = crate::query::language::plan_query(&query)?;
+        self.query_executor().execute(&plan).await
+    }
+
+    /// Parse and plan a graph query without running it
+    ///
+    /// The returned plan prints as one operator per line, showing the access
+    /// path chosen for each pattern and where each WHERE conjunct runs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query cannot be parsed or planned.
+    pub fn explain_query(&self, expression: &str) -> Result<crate::query::language::QueryPlan> {
+        let query = crate::query::language::parse_query(expression)?;
+        crate::query::language::plan_query(&query)
+    }
+
+    /// Execute a graph query and report rows and timings for each step
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// [`execute_query`](Self::execute_query).
+    pub async fn execute_query_with_stats(
+        &self,
+        expression: &str,
+    ) -> Result<(crate::query::QueryResult, crate::query::QueryStats)> {
+        let plan = self.explain_query(expression)?;
+        self.query_executor().execute_with_stats(&plan).await
+    }
+
+    fn query_executor(&self) -> crate::query::QueryExecutor {
+        let executor = crate::query::QueryExecutor::new(Arc::clone(&self.backend));
+        match &self.query_metrics {
+            Some(metrics) => executor.with_metrics(Arc::clone(metrics)),
+            None => executor,
+        }
+    }
+
+    /// Find every subgraph matching a structural pattern
+    ///
+    /// Matches are sorted by the creation time of the pattern's first node.
+    /// See [`crate::query::pattern`] for how patterns and scopes are built.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern refers to undeclared variables or a
+    /// storage lookup fails.
+    pub async fn match_pattern(
+        &self,
+        pattern: &crate::query::GraphPattern,
+        scope: &crate::query::PatternScope,
+    ) -> Result<Vec<crate::query::PatternMatch>> {
+        let mut matches =
+            crate::query::pattern::match_pattern(self.backend.as_ref(), pattern, scope).await?;
+        if let Some(first) = pattern.nodes.first() {
+            matches.sort_by_cached_key(|m| {
+                m.node(&first.variable)
+                    .map(|n| (crate::search::node_timestamp(n), *n.id().as_uuid()))
+            });
+        }
+        Ok(matches)
+    }
+
+    /// Full-text search over prompt, response, template and tool text
+    ///
+    /// Results are ranked with BM25 and carry a highlighted snippet. See
+    /// [`crate::search`] for the query syntax.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use llm_memory_graph::engine::AsyncMemoryGraph;
+    /// use llm_memory_graph::search::TextQuery;
+    /// use llm_memory_graph::Config;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let graph = AsyncMemoryGraph::open(Config::default()).await?;
+    ///
+    ///     let hits = graph.search_text(&TextQuery::new("invoice reconcil*").limit(5)).await?;
+    ///     for hit in hits {
+    ///         println!("{} {}", hit.node_id, hit.snippet.render("[", "]"));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn search_text(
+        &self,
+        query: &crate::search::TextQuery,
+    ) -> Result<Vec<crate::search::SearchHit>> {
+        self.backend.search_text(query).await
+    }
+
+    /// Attach an embedding vector to a prompt, response or template node
+    ///
+    /// Replaces any embedding the node already has. Every vector in the graph
+    /// must have the same dimension.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::NodeNotFound`] if the node does not exist, and
+    /// [`Error::ValidationError`] for other node types, empty or non-finite
+    /// vectors, or a dimension mismatch.
+    pub async fn set_embedding(&self, node_id: &NodeId, vector: Vec<f32>) -> Result<()> {
+        self.backend.store_embedding(node_id, &vector).await
+    }
+
+    /// Get the embedding attached to a node, if any
+    pub async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.backend.get_embedding(node_id).await
+    }
+
+    /// Remove the embedding attached to a node, if any
+    pub async fn remove_embedding(&self, node_id: &NodeId) -> Result<()> {
+        self.backend.delete_embedding(node_id).await
+    }
+
+    /// Find the `k` nodes whose embeddings are closest to `vector`
+    ///
+    /// Uses the approximate nearest-neighbour index, so very rarely a true
+    /// neighbour may be missed. See [`crate::vector`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use llm_memory_graph::engine::AsyncMemoryGraph;
+    /// use llm_memory_graph::vector::{SimilarityMetric, VectorFilter};
+    /// use llm_memory_graph::Config;
+    ///
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    ///     let graph = AsyncMemoryGraph::open(Config::default()).await?;
+    ///
+    ///     let filter = VectorFilter::new().metric(SimilarityMetric::DotProduct);
+    ///     for hit in graph.similar_nodes(&[0.2, 0.7, 0.1], 10, &filter).await? {
+    ///         println!("{} {:.3}", hit.node_id, hit.score);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &crate::vector::VectorFilter,
+    ) -> Result<Vec<crate::vector::SimilarNode>> {
+        self.backend.similar_nodes(vector, k, filter).await
+    }
+
+    // ===== Reverse Lookups =====
+
+    /// Page through the nodes listed under any reverse lookup, newest first
+    ///
+    /// The typed helpers below cover each kind of [`Lookup`]; this form suits
+    /// callers that receive the lookup as data, such as the gRPC service.
+    pub async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.backend.lookup_nodes(lookup, after, limit).await
+    }
+
+    /// Page through the prompts instantiated from a template, newest first
+    ///
+    /// Served from a maintained index, so the cost is proportional to the
+    /// page size rather than the size of the graph.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::engine::AsyncMemoryGraph;
+    /// # use llm_memory_graph::{Config, TemplateId};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = AsyncMemoryGraph::open(Config::default()).await?;
+    /// # let template_id = TemplateId::new();
+    /// let mut page = graph.prompts_for_template(template_id, None, 100).await?;
+    /// loop {
+    ///     for prompt in &page.items {
+    ///         println!("{}", prompt.content);
+    ///     }
+    ///     let Some(cursor) = page.next_cursor else { break };
+    ///     page = graph.prompts_for_template(template_id, Some(&cursor), 100).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn prompts_for_template(
+        &self,
+        template_id: TemplateId,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page<PromptNode>> {
+        let page = self
+            .backend
+            .lookup_nodes(&Lookup::Template(template_id), after, limit)
+            .await?;
+        Ok(page.filter_map(|node| match node {
+            Node::Prompt(prompt) => Some(prompt),
+            _ => None,
+        }))
+    }
+
+    /// Page through the nodes assigned to an agent via `HandledBy` edges
+    pub async fn nodes_handled_by_agent(
+        &self,
+        agent_node_id: NodeId,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.backend
+            .lookup_nodes(&Lookup::Agent(agent_node_id), after, limit)
+            .await
+    }
+
+    /// Page through the invocations of a tool, newest first
+    pub async fn tool_invocations_by_name(
+        &self,
+        tool_name: &str,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page<ToolInvocation>> {
+        let page = self
+            .backend
+            .lookup_nodes(&Lookup::ToolName(tool_name.to_string()), after, limit)
+            .await?;
+        Ok(page.filter_map(|node| match node {
+            Node::ToolInvocation(tool) => Some(tool),
+            _ => None,
+        }))
+    }
+
+    /// Page through the prompts and responses recorded against a model
+    pub async fn nodes_by_model(
+        &self,
+        model: &str,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.backend
+            .lookup_nodes(&Lookup::Model(model.to_string()), after, limit)
+            .await
+    }
+
+    /// Page through the custom nodes of a kind, newest first
+    pub async fn custom_nodes_by_kind(
+        &self,
+        kind: &str,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page<CustomNode>> {
+        let page = self
+            .backend
+            .lookup_nodes(&Lookup::Kind(kind.to_string()), after, limit)
+            .await?;
+        Ok(page.filter_map(|node| match node {
+            Node::Custom(custom) => Some(custom),
+            _ => None,
+        }))
     }
 }
 
@@ -1083,6 +1824,281 @@ mod tests {
         assert_eq!(stats.node_count, 0);
     }
 
+    #[tokio::test]
+    async fn test_async_open_read_only() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt_id = graph
+            .add_prompt(session.id, "Hello".to_string(), None)
+            .await
+            .unwrap();
+
+        let reader = AsyncMemoryGraph::open_read_only(Config::new(dir.path()))
+            .await
+            .unwrap();
+        assert!(reader.is_read_only());
+        assert!(reader.get_node(&prompt_id).await.unwrap().is_some());
+        assert_eq!(reader.stats().await.unwrap().node_count, 2);
+
+        assert!(matches!(
+            reader
+                .add_prompt(session.id, "Again".to_string(), None)
+                .await,
+            Err(Error::ReadOnly(_))
+        ));
+        assert!(matches!(
+            reader.create_session().await,
+            Err(Error::ReadOnly(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_reverse_lookups() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+
+        let metadata = PromptMetadata {
+            model: "gpt-4".to_string(),
+            ..PromptMetadata::default()
+        };
+        let mut prompt_ids = Vec::new();
+        for i in 0..3 {
+            let id = graph
+                .add_prompt(session.id, format!("q{i}"), Some(metadata.clone()))
+                .await
+                .unwrap();
+            prompt_ids.push(id);
+        }
+        let response_id = graph
+            .add_response(prompt_ids[0], "a".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        graph
+            .add_tool_invocation(ToolInvocation::new(
+                response_id,
+                "search".to_string(),
+                serde_json::json!({"q": "rust"}),
+            ))
+            .await
+            .unwrap();
+
+        let agent = AgentNode::new("Router".to_string(), "router".to_string(), vec![]);
+        let agent_node_id = agent.node_id;
+        graph.add_agent(agent).await.unwrap();
+        graph
+            .assign_agent_to_prompt(prompt_ids[2], agent_node_id)
+            .await
+            .unwrap();
+
+        let page = graph.nodes_by_model("gpt-4", None, 2).await.unwrap();
+        assert_eq!(page.items.len(), 2);
+        let rest = graph
+            .nodes_by_model("gpt-4", page.next_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(rest.items.len(), 1);
+        assert!(rest.next_cursor.is_none());
+
+        let tools = graph
+            .tool_invocations_by_name("search", None, 10)
+            .await
+            .unwrap();
+        assert_eq!(tools.items[0].response_id, response_id);
+
+        let handled = graph
+            .nodes_handled_by_agent(agent_node_id, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(handled.items.len(), 1);
+        assert_eq!(handled.items[0].id(), prompt_ids[2]);
+    }
+
+    #[tokio::test]
+    async fn test_async_execute_query() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt_id = graph
+            .add_prompt(session.id, "What is Rust?".to_string(), None)
+            .await
+            .unwrap();
+        graph
+            .add_response(
+                prompt_id,
+                "A systems language".to_string(),
+                TokenUsage::new(5, 50),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let result = graph
+            .execute_query(
+                "MATCH (r:Response)-[:RESPONDS_TO]->(p:Prompt) \
+                 WHERE r.usage.total_tokens > 10 RETURN p.content, r.content AS answer",
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.columns, vec!["p.content", "answer"]);
+        assert_eq!(
+            result.rows,
+            vec![vec![
+                serde_json::json!("What is Rust?"),
+                serde_json::json!("A systems language")
+            ]]
+        );
+
+        assert!(matches!(
+            graph.execute_query("MATCH (p:Nope) RETURN p").await,
+            Err(Error::QueryError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_search_text() {
+        use crate::search::TextQuery;
+        use crate::NodeType;
+
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let other = graph.create_session().await.unwrap();
+
+        let prompt_id = graph
+            .add_prompt(
+                session.id,
+                "Help with invoice reconciliation".to_string(),
+                None,
+            )
+            .await
+            .unwrap();
+        let response_id = graph
+            .add_response(
+                prompt_id,
+                "Match each invoice against the bank statement".to_string(),
+                TokenUsage::new(5, 10),
+                None,
+            )
+            .await
+            .unwrap();
+        graph
+            .add_prompt(other.id, "Write a haiku about invoices".to_string(), None)
+            .await
+            .unwrap();
+
+        let hits = graph
+            .search_text(&TextQuery::new("invoice").session(session.id))
+            .await
+            .unwrap();
+        let ids: Vec<NodeId> = hits.iter().map(|h| h.node_id).collect();
+        assert_eq!(hits.len(), 2);
+        assert!(ids.contains(&prompt_id) && ids.contains(&response_id));
+        // Responses inherit the session of their prompt
+        assert!(hits.iter().all(|h| h.session_id == Some(session.id)));
+
+        let hits = graph
+            .search_text(&TextQuery::new("\"bank statement\"").node_type(NodeType::Response))
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(
+            hits[0].snippet.render("[", "]"),
+            "Match each invoice against the [bank] [statement]"
+        );
+
+        assert_eq!(
+            graph
+                .search_text(&TextQuery::new("invoice*"))
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_async_similar_nodes() {
+        use crate::vector::VectorFilter;
+
+        let dir = tempdir().unwrap();
+        let (session_id, prompt_id, response_id) = {
+            let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+                .await
+                .unwrap();
+            let session = graph.create_session().await.unwrap();
+            let prompt_id = graph
+                .add_prompt(session.id, "Summarize the report".to_string(), None)
+                .await
+                .unwrap();
+            let response_id = graph
+                .add_response(
+                    prompt_id,
+                    "The report covers Q3".to_string(),
+                    TokenUsage::new(4, 5),
+                    None,
+                )
+                .await
+                .unwrap();
+
+            graph
+                .set_embedding(&prompt_id, vec![1.0, 0.0, 0.0])
+                .await
+                .unwrap();
+            graph
+                .set_embedding(&response_id, vec![0.8, 0.6, 0.0])
+                .await
+                .unwrap();
+            // Sessions cannot carry embeddings
+            assert!(matches!(
+                graph
+                    .set_embedding(&session.node_id, vec![1.0, 0.0, 0.0])
+                    .await,
+                Err(Error::ValidationError(_))
+            ));
+            graph.flush().await.unwrap();
+            (session.id, prompt_id, response_id)
+        };
+
+        // The index is persisted with the data
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        assert_eq!(
+            graph.get_embedding(&response_id).await.unwrap(),
+            Some(vec![0.8, 0.6, 0.0])
+        );
+
+        let hits = graph
+            .similar_nodes(
+                &[0.0, 1.0, 0.0],
+                1,
+                &VectorFilter::new().session(session_id),
+            )
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].node_id, response_id);
+        assert!((hits[0].score - 0.6).abs() < 1e-6);
+
+        graph.remove_embedding(&response_id).await.unwrap();
+        let hits = graph
+            .similar_nodes(&[0.0, 1.0, 0.0], 5, &VectorFilter::new())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].node_id, prompt_id);
+    }
+
     #[tokio::test]
     async fn test_async_session_management() {
         let dir = tempdir().unwrap();
@@ -5,11 +5,15 @@ mod async_memory_graph;
 pub use async_memory_graph::AsyncMemoryGraph;
 
 use crate::{Error, Result};
-use crate::storage::{SledBackend, StorageBackend};
+use crate::attachments;
+use crate::constraints;
+use crate::custom::{self, EdgeKind};
+use crate::query::{Cursor, Page};
+use crate::storage::{Lookup, ReadOnlyBackend, SledBackend, StorageBackend};
 use crate::{
-    AgentNode, Config, ConversationSession, Edge, EdgeType, Node, NodeId, PromptMetadata,
-    PromptNode, PromptTemplate, ResponseMetadata, ResponseNode, SessionId, TemplateId, TokenUsage,
-    ToolInvocation,
+    AgentNode, Attachment, Config, ConstraintMode, ConversationSession, Edge, EdgeId, EdgeType,
+    Node, NodeId, PromptMetadata, PromptNode, PromptTemplate, ResponseMetadata, ResponseNode,
+    SessionId, TemplateId, TokenUsage, ToolInvocation,
 };
 use parking_lot::RwLock;
 use std::collections::HashMap;
@@ -37,6 +41,8 @@ use std::sync::Arc;
 pub struct MemoryGraph {
     backend: Arc<dyn StorageBackend>,
     sessions: Arc<RwLock<HashMap<SessionId, ConversationSession>>>,
+    read_only: bool,
+    edge_constraints: ConstraintMode,
 }
 
 impl MemoryGraph {
@@ -69,9 +75,50 @@ impl MemoryGraph {
         Ok(Self {
             backend: Arc::new(backend),
             sessions: Arc::new(RwLock::new(HashMap::new())),
+            read_only: false,
+            edge_constraints: config.edge_constraints,
         })
     }
 
+    /// Open a read-only view of the graph at `config.path`
+    ///
+    /// The database directory (or a checkpoint copy of it) is snapshotted into a
+    /// temporary location, so this works even while another process holds the
+    /// database open. The snapshot may be stale, and torn if the owning process
+    /// writes while it is copied. All mutating methods return [`Error::ReadOnly`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be copied or the snapshot fails to open.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use llm_memory_graph::{MemoryGraph, Config};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let graph = MemoryGraph::open_read_only(Config::new("./data/graph.db"))?;
+    /// println!("{} nodes", graph.stats()?.node_count);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_read_only(config: Config) -> Result<Self> {
+        let backend = ReadOnlyBackend::open(&config.path)?;
+
+        Ok(Self {
+            backend: Arc::new(backend),
+            sessions: Arc::new(RwLock::new(HashMap::new())),
+            read_only: true,
+            edge_constraints: config.edge_constraints,
+        })
+    }
+
+    /// Whether this graph was opened with [`MemoryGraph::open_read_only`]
+    #[must_use]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
     /// Create a new conversation session
     ///
     /// Sessions are used to group related prompts and responses together.
@@ -310,6 +357,41 @@ impl MemoryGraph {
         Ok(response_id)
     }
 
+    /// Attach an image, document or other file to a prompt or response
+    ///
+    /// Content larger than [`attachments::INLINE_LIMIT`] is moved into the
+    /// blob store. Returns the attachment as stored.
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the node is not a prompt or response,
+    /// or the attachment's size or hash do not match its content.
+    pub fn add_attachment(&self, node_id: NodeId, attachment: Attachment) -> Result<Attachment> {
+        let mut node = self.get_node(node_id)?;
+        let attachments = match &mut node {
+            Node::Prompt(prompt) => &mut prompt.attachments,
+            Node::Response(response) => &mut response.attachments,
+            _ => {
+                return Err(Error::ValidationError(format!(
+                    "Node {node_id} is not a prompt or response"
+                )))
+            }
+        };
+        let attachment = attachments::prepare_sync(self.backend.as_ref(), attachment)?;
+        attachments.push(attachment.clone());
+        self.backend.store_node(&node)?;
+        Ok(attachment)
+    }
+
+    /// The content of an attachment, if the graph holds it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage retrieval fails.
+    pub fn attachment_data(&self, attachment: &Attachment) -> Result<Option<Vec<u8>>> {
+        attachments::load_sync(self.backend.as_ref(), attachment)
+    }
+
     /// Add a tool invocation node to the graph
     ///
     /// This creates a tool invocation record and automatically creates an INVOKES edge
@@ -664,11 +746,17 @@ impl MemoryGraph {
             .ok_or_else(|| Error::NodeNotFound(node_id.to_string()))
     }
 
-    /// Add a custom edge between two nodes
+    /// Add an edge between two nodes
+    ///
+    /// Edges of a custom type are checked against its registered
+    /// [`EdgeKind`], and built-in edges against their
+    /// [constraints](crate::constraints) as configured.
     ///
     /// # Errors
     ///
-    /// Returns an error if storage operations fail.
+    /// Returns a validation error if a custom edge does not fit its kind or,
+    /// in strict mode, a built-in edge breaks a constraint, or an error if
+    /// storage operations fail.
     ///
     /// # Examples
     ///
@@ -685,10 +773,62 @@ impl MemoryGraph {
     /// ```
     pub fn add_edge(&self, from: NodeId, to: NodeId, edge_type: EdgeType) -> Result<()> {
         let edge = Edge::new(from, to, edge_type);
+        self.check_edge(&edge)?;
         self.backend.store_edge(&edge)?;
         Ok(())
     }
 
+    /// Add an edge with properties between two nodes, returning its ID
+    ///
+    /// The edge is checked like one added with [`Self::add_edge`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the edge is rejected, or an error if
+    /// storage operations fail.
+    pub fn add_edge_with_properties(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        edge_type: EdgeType,
+        properties: HashMap<String, String>,
+    ) -> Result<EdgeId> {
+        let edge = Edge::with_properties(from, to, edge_type, properties);
+        self.check_edge(&edge)?;
+        self.backend.store_edge(&edge)?;
+        Ok(edge.id)
+    }
+
+    /// Check `edge` against its custom edge kind or built-in edge rule
+    fn check_edge(&self, edge: &Edge) -> Result<()> {
+        custom::edge::check_edge_sync(self.backend.as_ref(), edge)?;
+        if self.edge_constraints == ConstraintMode::Off {
+            return Ok(());
+        }
+        let violations =
+            constraints::check_edges_sync(self.backend.as_ref(), std::slice::from_ref(edge))?;
+        constraints::enforce(self.edge_constraints, violations)
+    }
+
+    /// Register a custom edge type
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the name is invalid or taken, or an
+    /// error if the graph is read-only or storage fails.
+    pub fn register_edge_kind(&self, kind: EdgeKind) -> Result<()> {
+        custom::edge::register_sync(self.backend.as_ref(), &kind)
+    }
+
+    /// The edge kind registered as `name`, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage retrieval fails.
+    pub fn edge_kind(&self, name: &str) -> Result<Option<EdgeKind>> {
+        custom::edge::get_sync(self.backend.as_ref(), name)
+    }
+
     /// Get all edges originating from a node
     ///
     /// # Errors
@@ -754,6 +894,11 @@ impl MemoryGraph {
         self.backend.get_session_nodes(&session_id)
     }
 
+    /// Load every node in the graph (full scan, used when no index applies)
+    pub(crate) fn scan_nodes(&self) -> Result<Vec<Node>> {
+        self.backend.scan_nodes()
+    }
+
     /// Flush all pending writes to disk
     ///
     /// # Errors
@@ -797,6 +942,109 @@ impl MemoryGraph {
         self.backend.stats()
     }
 
+    // ===== Reverse Lookups =====
+
+    /// Page through the nodes listed under any reverse lookup, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be read.
+    pub fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.backend.lookup_nodes(lookup, after, limit)
+    }
+
+    /// Page through the prompts instantiated from a template, newest first
+    ///
+    /// Pass the previous page's `next_cursor` as `after` to continue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::{MemoryGraph, Config, TemplateId};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = MemoryGraph::open(Config::default())?;
+    /// # let template_id = TemplateId::new();
+    /// let page = graph.prompts_for_template(template_id, None, 50)?;
+    /// if let Some(cursor) = page.next_cursor {
+    ///     let next = graph.prompts_for_template(template_id, Some(&cursor), 50)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prompts_for_template(
+        &self,
+        template_id: TemplateId,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page<PromptNode>> {
+        let page = self
+            .backend
+            .lookup_nodes(&Lookup::Template(template_id), after, limit)?;
+        Ok(page.filter_map(|node| match node {
+            Node::Prompt(prompt) => Some(prompt),
+            _ => None,
+        }))
+    }
+
+    /// Page through the nodes assigned to an agent via `HandledBy` edges
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be read.
+    pub fn nodes_handled_by_agent(
+        &self,
+        agent_node_id: NodeId,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.backend
+            .lookup_nodes(&Lookup::Agent(agent_node_id), after, limit)
+    }
+
+    /// Page through the invocations of a tool, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be read.
+    pub fn tool_invocations_by_name(
+        &self,
+        tool_name: &str,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page<ToolInvocation>> {
+        let page =
+            self.backend
+                .lookup_nodes(&Lookup::ToolName(tool_name.to_string()), after, limit)?;
+        Ok(page.filter_map(|node| match node {
+            Node::ToolInvocation(tool) => Some(tool),
+            _ => None,
+        }))
+    }
+
+    /// Page through the prompts and responses recorded against a model
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be read.
+    pub fn nodes_by_model(
+        &self,
+        model: &str,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.backend
+            .lookup_nodes(&Lookup::Model(model.to_string()), after, limit)
+    }
+
     // ===== Template Management Methods =====
 
     /// Create and store a new prompt template
@@ -1050,6 +1298,34 @@ mod tests {
         assert_eq!(session.id, retrieved.id);
     }
 
+    #[test]
+    fn test_open_read_only_while_locked() {
+        let dir = tempdir().unwrap();
+        let graph = MemoryGraph::open(Config::new(dir.path())).unwrap();
+        let session = graph.create_session().unwrap();
+        let prompt_id = graph
+            .add_prompt(session.id, "Hello".to_string(), None)
+            .unwrap();
+
+        // The writer keeps the database locked
+        assert!(matches!(
+            MemoryGraph::open(Config::new(dir.path())),
+            Err(Error::DatabaseLocked(_))
+        ));
+
+        let reader = MemoryGraph::open_read_only(Config::new(dir.path())).unwrap();
+        assert!(reader.is_read_only());
+        assert!(!graph.is_read_only());
+        assert_eq!(reader.get_node(prompt_id).unwrap().id(), prompt_id);
+        assert_eq!(reader.get_session(session.id).unwrap().id, session.id);
+
+        assert!(matches!(reader.create_session(), Err(Error::ReadOnly(_))));
+        assert!(matches!(
+            reader.add_prompt(session.id, "Again".to_string(), None),
+            Err(Error::ReadOnly(_))
+        ));
+    }
+
     #[test]
     fn test_add_prompt() {
         let dir = tempdir().unwrap();
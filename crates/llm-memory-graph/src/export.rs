@@ -0,0 +1,600 @@
+//! Graph exporters for visualisation tools
+//!
+//! A [`GraphExporter`] renders a [`Subgraph`] (a session, a traversal result or
+//! a k-hop neighbourhood) as Graphviz DOT, GraphML, a Mermaid flowchart or
+//! [JSON Graph Format](https://jsongraphformat.info/). Nodes are labelled and
+//! styled by [`NodeType`]; edges are labelled with their [`EdgeType`] and any
+//! edge properties selected with [`GraphExporter::edge_property`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::export::{GraphExporter, GraphFormat};
+//! use llm_memory_graph::{Config, SessionId};
+//!
+//! # async fn example(session_id: SessionId) -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let subgraph = graph.traversal().session_subgraph(&session_id).await?;
+//! let dot = GraphExporter::new()
+//!     .title("Support session")
+//!     .edge_property("priority")
+//!     .export(&subgraph, GraphFormat::Dot);
+//! std::fs::write("session.dot", dot)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::query::Subgraph;
+use crate::{Edge, EdgeType, Error, Node, NodeId, NodeType};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Default maximum length of a node label, in characters
+const DEFAULT_LABEL_LENGTH: usize = 40;
+
+/// Output format for graph exports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    Dot,
+    /// GraphML (XML)
+    GraphMl,
+    /// Mermaid flowchart
+    Mermaid,
+    /// JSON Graph Format, version 2
+    JsonGraph,
+}
+
+impl GraphFormat {
+    /// Conventional file extension for the format
+    #[must_use]
+    pub const fn extension(&self) -> &'static str {
+        match self {
+            Self::Dot => "dot",
+            Self::GraphMl => "graphml",
+            Self::Mermaid => "mmd",
+            Self::JsonGraph => "json",
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Dot => "dot",
+            Self::GraphMl => "graphml",
+            Self::Mermaid => "mermaid",
+            Self::JsonGraph => "json",
+        })
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dot" | "graphviz" => Ok(Self::Dot),
+            "graphml" => Ok(Self::GraphMl),
+            "mermaid" | "mmd" => Ok(Self::Mermaid),
+            "json" | "jgf" | "json-graph" => Ok(Self::JsonGraph),
+            _ => Err(Error::ValidationError(format!(
+                "Unknown graph format '{s}'. Use dot, graphml, mermaid or json"
+            ))),
+        }
+    }
+}
+
+/// Visual style for one node type
+struct NodeStyle {
+    class: &'static str,
+    shape: &'static str,
+    fill: &'static str,
+    stroke: &'static str,
+}
+
+const fn node_style(node_type: &NodeType) -> NodeStyle {
+    match node_type {
+        NodeType::Prompt => NodeStyle {
+            class: "prompt",
+            shape: "box",
+            fill: "#dbeafe",
+            stroke: "#2563eb",
+        },
+        NodeType::Response => NodeStyle {
+            class: "response",
+            shape: "box",
+            fill: "#dcfce7",
+            stroke: "#16a34a",
+        },
+        NodeType::Session => NodeStyle {
+            class: "session",
+            shape: "folder",
+            fill: "#f3f4f6",
+            stroke: "#4b5563",
+        },
+        NodeType::ToolInvocation => NodeStyle {
+            class: "tool",
+            shape: "hexagon",
+            fill: "#ffedd5",
+            stroke: "#ea580c",
+        },
+        NodeType::Agent => NodeStyle {
+            class: "agent",
+            shape: "doubleoctagon",
+            fill: "#ede9fe",
+            stroke: "#7c3aed",
+        },
+        NodeType::Template => NodeStyle {
+            class: "template",
+            shape: "note",
+            fill: "#fef9c3",
+            stroke: "#ca8a04",
+        },
+        NodeType::Custom => NodeStyle {
+            class: "custom",
+            shape: "component",
+            fill: "#fce7f3",
+            stroke: "#db2777",
+        },
+    }
+}
+
+const ALL_NODE_TYPES: [NodeType; 7] = [
+    NodeType::Prompt,
+    NodeType::Response,
+    NodeType::Session,
+    NodeType::ToolInvocation,
+    NodeType::Agent,
+    NodeType::Template,
+    NodeType::Custom,
+];
+
+/// Name of a node type as shown in exports
+const fn node_type_name(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::Prompt => "Prompt",
+        NodeType::Response => "Response",
+        NodeType::Session => "Session",
+        NodeType::ToolInvocation => "ToolInvocation",
+        NodeType::Agent => "Agent",
+        NodeType::Template => "Template",
+        NodeType::Custom => "Custom",
+    }
+}
+
+/// Name of an edge type as shown in exports
+fn edge_type_name(edge_type: &EdgeType) -> &str {
+    match edge_type {
+        EdgeType::Follows => "Follows",
+        EdgeType::RespondsTo => "RespondsTo",
+        EdgeType::HandledBy => "HandledBy",
+        EdgeType::PartOf => "PartOf",
+        EdgeType::Invokes => "Invokes",
+        EdgeType::TransfersTo => "TransfersTo",
+        EdgeType::Instantiates => "Instantiates",
+        EdgeType::Inherits => "Inherits",
+        EdgeType::References => "References",
+        EdgeType::BranchesFrom => "BranchesFrom",
+        EdgeType::Custom(name) => name,
+    }
+}
+
+/// Renders subgraphs in graph visualisation formats
+#[derive(Debug, Clone)]
+pub struct GraphExporter {
+    title: Option<String>,
+    edge_properties: Vec<String>,
+    label_length: usize,
+}
+
+impl Default for GraphExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GraphExporter {
+    /// Create an exporter with default settings
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            title: None,
+            edge_properties: Vec::new(),
+            label_length: DEFAULT_LABEL_LENGTH,
+        }
+    }
+
+    /// Set the graph title
+    #[must_use]
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Also show the edge property `key` in edge labels
+    #[must_use]
+    pub fn edge_property(mut self, key: impl Into<String>) -> Self {
+        self.edge_properties.push(key.into());
+        self
+    }
+
+    /// Truncate node labels to `length` characters
+    #[must_use]
+    pub const fn label_length(mut self, length: usize) -> Self {
+        self.label_length = length;
+        self
+    }
+
+    /// Render `graph` in the given format
+    #[must_use]
+    pub fn export(&self, graph: &Subgraph, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(graph),
+            GraphFormat::GraphMl => self.to_graphml(graph),
+            GraphFormat::Mermaid => self.to_mermaid(graph),
+            GraphFormat::JsonGraph => {
+                serde_json::to_string_pretty(&self.to_json_graph(graph)).unwrap_or_default()
+            }
+        }
+    }
+
+    /// Render `graph` as Graphviz DOT
+    #[must_use]
+    pub fn to_dot(&self, graph: &Subgraph) -> String {
+        let mut out = String::from("digraph memory_graph {\n");
+        if let Some(title) = &self.title {
+            let _ = writeln!(out, "  label=\"{}\";", escape_dot(title));
+            out.push_str("  labelloc=t;\n");
+        }
+        out.push_str("  rankdir=LR;\n");
+        out.push_str("  node [style=filled, fontname=\"Helvetica\"];\n");
+        out.push_str("  edge [fontname=\"Helvetica\", fontsize=10];\n");
+
+        for node in &graph.nodes {
+            let style = node_style(&node.node_type());
+            let _ = writeln!(
+                out,
+                "  \"{}\" [label=\"{}\", shape={}, fillcolor=\"{}\", color=\"{}\"];",
+                node.id(),
+                escape_dot(&self.node_label(node)),
+                style.shape,
+                style.fill,
+                style.stroke,
+            );
+        }
+        for edge in self.edges(graph) {
+            let _ = writeln!(
+                out,
+                "  \"{}\" -> \"{}\" [label=\"{}\"];",
+                edge.from,
+                edge.to,
+                escape_dot(&self.edge_label(edge)),
+            );
+        }
+
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render `graph` as GraphML
+    #[must_use]
+    pub fn to_graphml(&self, graph: &Subgraph) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        for (id, target, name) in [
+            ("n_type", "node", "type"),
+            ("n_label", "node", "label"),
+            ("n_color", "node", "color"),
+            ("e_type", "edge", "type"),
+            ("e_label", "edge", "label"),
+        ] {
+            let _ = writeln!(
+                out,
+                "  <key id=\"{id}\" for=\"{target}\" attr.name=\"{name}\" attr.type=\"string\"/>"
+            );
+        }
+
+        let _ = write!(out, "  <graph id=\"memory_graph\" edgedefault=\"directed\"");
+        match &self.title {
+            Some(title) => {
+                let _ = writeln!(out, ">\n    <desc>{}</desc>", escape_xml(title));
+            }
+            None => out.push_str(">\n"),
+        }
+
+        for node in &graph.nodes {
+            let node_type = node.node_type();
+            let _ = writeln!(out, "    <node id=\"{}\">", node.id());
+            let _ = writeln!(
+                out,
+                "      <data key=\"n_type\">{}</data>",
+                node_type_name(&node_type)
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"n_label\">{}</data>",
+                escape_xml(&self.node_label(node))
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"n_color\">{}</data>",
+                node_style(&node_type).fill
+            );
+            out.push_str("    </node>\n");
+        }
+        for edge in self.edges(graph) {
+            let _ = writeln!(
+                out,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\">",
+                edge.id, edge.from, edge.to
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"e_type\">{}</data>",
+                escape_xml(edge_type_name(&edge.edge_type))
+            );
+            let _ = writeln!(
+                out,
+                "      <data key=\"e_label\">{}</data>",
+                escape_xml(&self.edge_label(edge))
+            );
+            out.push_str("    </edge>\n");
+        }
+
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+
+    /// Render `graph` as a Mermaid flowchart
+    #[must_use]
+    pub fn to_mermaid(&self, graph: &Subgraph) -> String {
+        let mut out = String::new();
+        if let Some(title) = &self.title {
+            let _ = writeln!(out, "---\ntitle: {}\n---", title.replace('\n', " "));
+        }
+        out.push_str("flowchart LR\n");
+
+        // Mermaid IDs must be plain identifiers, so number the nodes
+        let ids: HashMap<NodeId, String> = graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| (node.id(), format!("n{i}")))
+            .collect();
+
+        for node in &graph.nodes {
+            let label = escape_mermaid(&self.node_label(node));
+            let id = &ids[&node.id()];
+            let node_type = node.node_type();
+            let shape = match node_type {
+                NodeType::Response => format!("{id}(\"{label}\")"),
+                NodeType::Session => format!("{id}[(\"{label}\")]"),
+                NodeType::ToolInvocation => format!("{id}{{{{\"{label}\"}}}}"),
+                NodeType::Agent => format!("{id}[[\"{label}\"]]"),
+                NodeType::Template => format!("{id}>\"{label}\"]"),
+                NodeType::Prompt => format!("{id}[\"{label}\"]"),
+                NodeType::Custom => format!("{id}[/\"{label}\"/]"),
+            };
+            let _ = writeln!(out, "  {shape}:::{}", node_style(&node_type).class);
+        }
+        for edge in self.edges(graph) {
+            let _ = writeln!(
+                out,
+                "  {} -->|\"{}\"| {}",
+                ids[&edge.from],
+                escape_mermaid(&self.edge_label(edge)),
+                ids[&edge.to],
+            );
+        }
+
+        for node_type in &ALL_NODE_TYPES {
+            let style = node_style(node_type);
+            let _ = writeln!(
+                out,
+                "  classDef {} fill:{},stroke:{}",
+                style.class, style.fill, style.stroke
+            );
+        }
+        out
+    }
+
+    /// Render `graph` as a JSON Graph Format document
+    #[must_use]
+    pub fn to_json_graph(&self, graph: &Subgraph) -> Value {
+        let nodes: Map<String, Value> = graph
+            .nodes
+            .iter()
+            .map(|node| {
+                let node_type = node.node_type();
+                let entry = json!({
+                    "label": self.node_label(node),
+                    "metadata": {
+                        "type": node_type_name(&node_type),
+                        "color": node_style(&node_type).fill,
+                        "node": node,
+                    },
+                });
+                (node.id().to_string(), entry)
+            })
+            .collect();
+
+        let edges: Vec<Value> = self
+            .edges(graph)
+            .map(|edge| {
+                json!({
+                    "id": edge.id.to_string(),
+                    "source": edge.from.to_string(),
+                    "target": edge.to.to_string(),
+                    "relation": edge_type_name(&edge.edge_type),
+                    "directed": true,
+                    "label": self.edge_label(edge),
+                    "metadata": {
+                        "created_at": edge.created_at,
+                        "properties": edge.properties,
+                    },
+                })
+            })
+            .collect();
+
+        let mut body = json!({
+            "directed": true,
+            "type": "llm-memory-graph",
+            "nodes": nodes,
+            "edges": edges,
+        });
+        if let Some(title) = &self.title {
+            body["label"] = Value::String(title.clone());
+        }
+        json!({ "graph": body })
+    }
+
+    /// Edges whose endpoints are both in the graph
+    fn edges<'a>(&self, graph: &'a Subgraph) -> impl Iterator<Item = &'a Edge> {
+        let members: std::collections::HashSet<NodeId> = graph.nodes.iter().map(Node::id).collect();
+        graph
+            .edges
+            .iter()
+            .filter(move |edge| members.contains(&edge.from) && members.contains(&edge.to))
+    }
+
+    fn node_label(&self, node: &Node) -> String {
+        let node_type = node.node_type();
+        let summary = match node {
+            Node::Prompt(p) => p.content.clone(),
+            Node::Response(r) => r.content.clone(),
+            Node::Session(s) => s.id.to_string(),
+            Node::ToolInvocation(t) => t.tool_name.clone(),
+            Node::Agent(a) => a.name.clone(),
+            Node::Template(t) => format!("{} v{}", t.name, t.version),
+            Node::Custom(c) => c.kind.clone(),
+        };
+        format!(
+            "{}: {}",
+            node_type_name(&node_type),
+            truncate(&summary, self.label_length)
+        )
+    }
+
+    fn edge_label(&self, edge: &Edge) -> String {
+        let mut label = edge_type_name(&edge.edge_type).to_string();
+        for key in &self.edge_properties {
+            if let Some(value) = edge.properties.get(key) {
+                let _ = write!(label, "\n{key}={value}");
+            }
+        }
+        label
+    }
+}
+
+/// Collapse whitespace and cut `text` to `max` characters
+fn truncate(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max {
+        text
+    } else {
+        let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    }
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_mermaid(text: &str) -> String {
+    text.replace('"', "#quot;").replace('\n', "<br/>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgentNode, PromptNode, ResponseNode, SessionId, TokenUsage};
+
+    fn sample() -> Subgraph {
+        let prompt = PromptNode::new(SessionId::new(), "Say \"hi\" <now>".to_string());
+        let response = ResponseNode::new(prompt.id, "Hi!".to_string(), TokenUsage::new(3, 1));
+        let agent = AgentNode::new("router".to_string(), "dispatcher".to_string(), Vec::new());
+
+        let responds = Edge::new(response.id, prompt.id, EdgeType::RespondsTo);
+        let mut transfer = Edge::new(response.id, agent.node_id, EdgeType::TransfersTo);
+        transfer
+            .properties
+            .insert("priority".to_string(), "high".to_string());
+        // Dangling edge to a node outside the subgraph is dropped
+        let dangling = Edge::new(prompt.id, NodeId::new(), EdgeType::References);
+
+        Subgraph {
+            nodes: vec![
+                Node::Prompt(prompt),
+                Node::Response(response),
+                Node::Agent(agent),
+            ],
+            edges: vec![responds, transfer, dangling],
+        }
+    }
+
+    #[test]
+    fn test_format_parsing() {
+        assert_eq!("DOT".parse::<GraphFormat>().unwrap(), GraphFormat::Dot);
+        assert_eq!("mmd".parse::<GraphFormat>().unwrap(), GraphFormat::Mermaid);
+        assert_eq!(
+            "jgf".parse::<GraphFormat>().unwrap(),
+            GraphFormat::JsonGraph
+        );
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+
+    #[test]
+    fn test_exports() {
+        let graph = sample();
+        let exporter = GraphExporter::new().title("Demo").edge_property("priority");
+
+        let dot = exporter.to_dot(&graph);
+        assert!(dot.starts_with("digraph memory_graph {"));
+        assert!(dot.contains("Prompt: Say \\\"hi\\\" <now>"));
+        assert!(dot.contains("shape=doubleoctagon"));
+        assert!(dot.contains("label=\"TransfersTo\\npriority=high\""));
+        assert_eq!(dot.matches(" -> ").count(), 2);
+
+        let graphml = exporter.to_graphml(&graph);
+        assert!(graphml.contains("Say &quot;hi&quot; &lt;now&gt;"));
+        assert_eq!(graphml.matches("<node ").count(), 3);
+        assert_eq!(graphml.matches("<edge ").count(), 2);
+
+        let mermaid = exporter.to_mermaid(&graph);
+        assert!(mermaid.contains("flowchart LR"));
+        assert!(mermaid.contains("n1 -->|\"RespondsTo\"| n0"));
+        assert!(mermaid.contains("n1 -->|\"TransfersTo<br/>priority=high\"| n2"));
+        assert!(mermaid.contains(":::agent"));
+
+        let jgf = exporter.to_json_graph(&graph);
+        assert_eq!(jgf["graph"]["label"], "Demo");
+        assert_eq!(jgf["graph"]["nodes"].as_object().unwrap().len(), 3);
+        assert_eq!(jgf["graph"]["edges"][1]["relation"], "TransfersTo");
+        assert_eq!(
+            jgf["graph"]["edges"][1]["metadata"]["properties"]["priority"],
+            "high"
+        );
+    }
+
+    #[test]
+    fn test_label_truncation() {
+        assert_eq!(truncate("a  b\nc", 10), "a b c");
+        assert_eq!(truncate("abcdefgh", 4), "abc…");
+    }
+}
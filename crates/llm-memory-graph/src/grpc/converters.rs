@@ -3,12 +3,13 @@
 //! This module provides bidirectional conversion between protocol buffer
 //! message types and internal Rust types used by the memory graph.
 
-use crate::{Error, Result};
 use crate::grpc::proto;
 use crate::{
-    ConversationSession, EdgeType, Node, NodeType, PromptMetadata, PromptNode, ResponseMetadata,
-    ResponseNode, SessionId, TokenUsage, ToolInvocation, AgentNode, PromptTemplate, VariableSpec,
+    AgentNode, AgentStatus, Attachment, AttachmentBody, ConversationSession, CustomNode, EdgeType,
+    Node, NodeType, PromptMetadata, PromptNode, PromptTemplate, ResponseMetadata, ResponseNode,
+    SessionId, TokenUsage, ToolInvocation, VariableSpec,
 };
+use crate::{Error, Result};
 use chrono::{DateTime, Utc};
 use prost_types::Timestamp;
 use std::collections::HashMap;
@@ -21,15 +22,23 @@ use tonic::Status;
 /// Convert internal Error to gRPC Status
 pub fn error_to_status(err: Error) -> Status {
     match err {
-        Error::NotFound(msg) => Status::not_found(msg),
-        Error::InvalidInput(msg) => Status::invalid_argument(msg),
-        Error::Timeout(msg) => Status::deadline_exceeded(msg),
-        Error::Storage(msg) => Status::internal(format!("Storage error: {}", msg)),
-        Error::SerializationError(msg) => Status::internal(format!("Serialization error: {}", msg)),
-        Error::SessionNotFound(msg) => Status::not_found(format!("Session not found: {}", msg)),
-        Error::NodeNotFound(msg) => Status::not_found(format!("Node not found: {}", msg)),
-        Error::IO(err) => Status::internal(format!("IO error: {}", err)),
-        Error::Other(msg) => Status::internal(msg),
+        Error::NodeNotFound(_)
+        | Error::EdgeNotFound(_)
+        | Error::SessionNotFound(_)
+        | Error::TemplateNotFound(_)
+        | Error::AgentNotFound(_) => Status::not_found(err.to_string()),
+        Error::NodeAlreadyExists(_) | Error::EdgeAlreadyExists(_) => {
+            Status::already_exists(err.to_string())
+        }
+        Error::InvalidNodeType(_)
+        | Error::InvalidEdgeType(_)
+        | Error::ValidationError(_)
+        | Error::QueryError(_) => Status::invalid_argument(err.to_string()),
+        Error::Timeout(_) => Status::deadline_exceeded(err.to_string()),
+        Error::ReadOnly(_) => Status::failed_precondition(err.to_string()),
+        Error::DatabaseLocked(_) => Status::unavailable(err.to_string()),
+        Error::Unsupported(_) => Status::unimplemented(err.to_string()),
+        _ => Status::internal(err.to_string()),
     }
 }
 
@@ -46,21 +55,23 @@ pub fn result_to_status<T>(result: Result<T>) -> std::result::Result<T, Status>
 pub fn datetime_to_proto(dt: DateTime<Utc>) -> Timestamp {
     Timestamp {
         seconds: dt.timestamp(),
-        nanos: dt.timestamp_subsec_nanos() as i32,
+        nanos: i32::try_from(dt.timestamp_subsec_nanos()).unwrap_or(i32::MAX),
     }
 }
 
 /// Convert protobuf Timestamp to chrono DateTime
 pub fn proto_to_datetime(ts: Timestamp) -> Result<DateTime<Utc>> {
-    DateTime::from_timestamp(ts.seconds, ts.nanos as u32)
-        .ok_or_else(|| Error::InvalidInput("Invalid timestamp".to_string()))
+    let nanos = u32::try_from(ts.nanos)
+        .map_err(|_| Error::ValidationError("Invalid timestamp".to_string()))?;
+    DateTime::from_timestamp(ts.seconds, nanos)
+        .ok_or_else(|| Error::ValidationError("Invalid timestamp".to_string()))
 }
 
 /// Convert optional protobuf Timestamp to chrono DateTime
 pub fn optional_proto_to_datetime(ts: Option<Timestamp>) -> Result<DateTime<Utc>> {
     match ts {
         Some(timestamp) => proto_to_datetime(timestamp),
-        None => Err(Error::InvalidInput("Missing timestamp".to_string())),
+        None => Err(Error::ValidationError("Missing timestamp".to_string())),
     }
 }
 
@@ -75,7 +86,8 @@ pub fn session_to_proto(session: ConversationSession) -> proto::Session {
         created_at: Some(datetime_to_proto(session.created_at)),
         updated_at: Some(datetime_to_proto(session.updated_at)),
         metadata: session.metadata,
-        is_active: session.is_active,
+        // Sessions are never closed, so every stored session is active
+        is_active: true,
     }
 }
 
@@ -86,25 +98,30 @@ pub fn session_to_proto(session: ConversationSession) -> proto::Session {
 /// Convert protobuf NodeType to internal NodeType
 pub fn proto_to_node_type(node_type: i32) -> Result<NodeType> {
     match proto::NodeType::try_from(node_type) {
-        Ok(proto::NodeType::NodeTypeSession) => Ok(NodeType::Session),
-        Ok(proto::NodeType::NodeTypePrompt) => Ok(NodeType::Prompt),
-        Ok(proto::NodeType::NodeTypeResponse) => Ok(NodeType::Response),
-        Ok(proto::NodeType::NodeTypeToolInvocation) => Ok(NodeType::ToolInvocation),
-        Ok(proto::NodeType::NodeTypeAgent) => Ok(NodeType::Agent),
-        Ok(proto::NodeType::NodeTypeTemplate) => Ok(NodeType::Template),
-        _ => Err(Error::InvalidInput(format!("Invalid node type: {}", node_type))),
+        Ok(proto::NodeType::Session) => Ok(NodeType::Session),
+        Ok(proto::NodeType::Prompt) => Ok(NodeType::Prompt),
+        Ok(proto::NodeType::Response) => Ok(NodeType::Response),
+        Ok(proto::NodeType::ToolInvocation) => Ok(NodeType::ToolInvocation),
+        Ok(proto::NodeType::Agent) => Ok(NodeType::Agent),
+        Ok(proto::NodeType::Template) => Ok(NodeType::Template),
+        Ok(proto::NodeType::Custom) => Ok(NodeType::Custom),
+        _ => Err(Error::ValidationError(format!(
+            "Invalid node type: {}",
+            node_type
+        ))),
     }
 }
 
 /// Convert internal NodeType to protobuf NodeType
 pub fn node_type_to_proto(node_type: NodeType) -> i32 {
     match node_type {
-        NodeType::Session => proto::NodeType::NodeTypeSession as i32,
-        NodeType::Prompt => proto::NodeType::NodeTypePrompt as i32,
-        NodeType::Response => proto::NodeType::NodeTypeResponse as i32,
-        NodeType::ToolInvocation => proto::NodeType::NodeTypeToolInvocation as i32,
-        NodeType::Agent => proto::NodeType::NodeTypeAgent as i32,
-        NodeType::Template => proto::NodeType::NodeTypeTemplate as i32,
+        NodeType::Session => proto::NodeType::Session as i32,
+        NodeType::Prompt => proto::NodeType::Prompt as i32,
+        NodeType::Response => proto::NodeType::Response as i32,
+        NodeType::ToolInvocation => proto::NodeType::ToolInvocation as i32,
+        NodeType::Agent => proto::NodeType::Agent as i32,
+        NodeType::Template => proto::NodeType::Template as i32,
+        NodeType::Custom => proto::NodeType::Custom as i32,
     }
 }
 
@@ -113,33 +130,48 @@ pub fn node_type_to_proto(node_type: NodeType) -> i32 {
 // ============================================================================
 
 /// Convert protobuf EdgeType to internal EdgeType
-pub fn proto_to_edge_type(edge_type: i32) -> Result<EdgeType> {
+///
+/// `custom_type` names the registered type of `EDGE_TYPE_CUSTOM` edges and is
+/// ignored for the built-in types.
+pub fn proto_to_edge_type(edge_type: i32, custom_type: &str) -> Result<EdgeType> {
     match proto::EdgeType::try_from(edge_type) {
-        Ok(proto::EdgeType::EdgeTypeBelongsTo) => Ok(EdgeType::PartOf),
-        Ok(proto::EdgeType::EdgeTypeRespondsTo) => Ok(EdgeType::RespondsTo),
-        Ok(proto::EdgeType::EdgeTypeFollows) => Ok(EdgeType::Follows),
-        Ok(proto::EdgeType::EdgeTypeInvokes) => Ok(EdgeType::Invokes),
-        Ok(proto::EdgeType::EdgeTypeHandledBy) => Ok(EdgeType::HandledBy),
-        Ok(proto::EdgeType::EdgeTypeInstantiates) => Ok(EdgeType::Instantiates),
-        Ok(proto::EdgeType::EdgeTypeInherits) => Ok(EdgeType::Inherits),
-        Ok(proto::EdgeType::EdgeTypeTransfersTo) => Ok(EdgeType::TransfersTo),
-        Ok(proto::EdgeType::EdgeTypeReferences) => Ok(EdgeType::References),
-        _ => Err(Error::InvalidInput(format!("Invalid edge type: {}", edge_type))),
+        Ok(proto::EdgeType::BelongsTo) => Ok(EdgeType::PartOf),
+        Ok(proto::EdgeType::RespondsTo) => Ok(EdgeType::RespondsTo),
+        Ok(proto::EdgeType::Follows) => Ok(EdgeType::Follows),
+        Ok(proto::EdgeType::Invokes) => Ok(EdgeType::Invokes),
+        Ok(proto::EdgeType::HandledBy) => Ok(EdgeType::HandledBy),
+        Ok(proto::EdgeType::Instantiates) => Ok(EdgeType::Instantiates),
+        Ok(proto::EdgeType::Inherits) => Ok(EdgeType::Inherits),
+        Ok(proto::EdgeType::TransfersTo) => Ok(EdgeType::TransfersTo),
+        Ok(proto::EdgeType::References) => Ok(EdgeType::References),
+        Ok(proto::EdgeType::BranchesFrom) => Ok(EdgeType::BranchesFrom),
+        Ok(proto::EdgeType::Custom) if !custom_type.is_empty() => {
+            Ok(EdgeType::Custom(custom_type.to_string()))
+        }
+        Ok(proto::EdgeType::Custom) => Err(Error::ValidationError(
+            "Custom edge type requires custom_type".to_string(),
+        )),
+        _ => Err(Error::ValidationError(format!(
+            "Invalid edge type: {}",
+            edge_type
+        ))),
     }
 }
 
 /// Convert internal EdgeType to protobuf EdgeType
 pub fn edge_type_to_proto(edge_type: EdgeType) -> i32 {
     match edge_type {
-        EdgeType::PartOf => proto::EdgeType::EdgeTypeBelongsTo as i32,
-        EdgeType::RespondsTo => proto::EdgeType::EdgeTypeRespondsTo as i32,
-        EdgeType::Follows => proto::EdgeType::EdgeTypeFollows as i32,
-        EdgeType::Invokes => proto::EdgeType::EdgeTypeInvokes as i32,
-        EdgeType::HandledBy => proto::EdgeType::EdgeTypeHandledBy as i32,
-        EdgeType::Instantiates => proto::EdgeType::EdgeTypeInstantiates as i32,
-        EdgeType::Inherits => proto::EdgeType::EdgeTypeInherits as i32,
-        EdgeType::TransfersTo => proto::EdgeType::EdgeTypeTransfersTo as i32,
-        EdgeType::References => proto::EdgeType::EdgeTypeReferences as i32,
+        EdgeType::PartOf => proto::EdgeType::BelongsTo as i32,
+        EdgeType::RespondsTo => proto::EdgeType::RespondsTo as i32,
+        EdgeType::Follows => proto::EdgeType::Follows as i32,
+        EdgeType::Invokes => proto::EdgeType::Invokes as i32,
+        EdgeType::HandledBy => proto::EdgeType::HandledBy as i32,
+        EdgeType::Instantiates => proto::EdgeType::Instantiates as i32,
+        EdgeType::Inherits => proto::EdgeType::Inherits as i32,
+        EdgeType::TransfersTo => proto::EdgeType::TransfersTo as i32,
+        EdgeType::References => proto::EdgeType::References as i32,
+        EdgeType::BranchesFrom => proto::EdgeType::BranchesFrom as i32,
+        EdgeType::Custom(_) => proto::EdgeType::Custom as i32,
     }
 }
 
@@ -151,8 +183,8 @@ pub fn edge_type_to_proto(edge_type: EdgeType) -> i32 {
 pub fn proto_to_prompt_metadata(metadata: proto::PromptMetadata) -> PromptMetadata {
     PromptMetadata {
         model: metadata.model,
-        temperature: metadata.temperature,
-        max_tokens: metadata.max_tokens.map(|t| t as usize),
+        temperature: metadata.temperature as f32,
+        max_tokens: metadata.max_tokens.map(|t| t.max(0) as usize),
         tools_available: metadata.tools_available,
         custom: metadata.custom,
     }
@@ -162,8 +194,10 @@ pub fn proto_to_prompt_metadata(metadata: proto::PromptMetadata) -> PromptMetada
 pub fn prompt_metadata_to_proto(metadata: PromptMetadata) -> proto::PromptMetadata {
     proto::PromptMetadata {
         model: metadata.model,
-        temperature: metadata.temperature,
-        max_tokens: metadata.max_tokens.map(|t| t as i32),
+        temperature: f64::from(metadata.temperature),
+        max_tokens: metadata
+            .max_tokens
+            .map(|t| i32::try_from(t).unwrap_or(i32::MAX)),
         tools_available: metadata.tools_available,
         custom: metadata.custom,
     }
@@ -174,7 +208,7 @@ pub fn proto_to_response_metadata(metadata: proto::ResponseMetadata) -> Response
     ResponseMetadata {
         model: metadata.model,
         finish_reason: metadata.finish_reason,
-        latency_ms: metadata.latency_ms as u64,
+        latency_ms: metadata.latency_ms.max(0) as u64,
         custom: metadata.custom,
     }
 }
@@ -184,26 +218,65 @@ pub fn response_metadata_to_proto(metadata: ResponseMetadata) -> proto::Response
     proto::ResponseMetadata {
         model: metadata.model,
         finish_reason: metadata.finish_reason,
-        latency_ms: metadata.latency_ms as i64,
+        latency_ms: i64::try_from(metadata.latency_ms).unwrap_or(i64::MAX),
         custom: metadata.custom,
     }
 }
 
 /// Convert protobuf TokenUsage to internal TokenUsage
-pub fn proto_to_token_usage(usage: proto::TokenUsage) -> TokenUsage {
-    TokenUsage {
-        prompt_tokens: usage.prompt_tokens as usize,
-        completion_tokens: usage.completion_tokens as usize,
-        total_tokens: usage.total_tokens as usize,
-    }
+///
+/// Counts that are negative or do not fit in a `u32` are rejected.
+pub fn proto_to_token_usage(usage: proto::TokenUsage) -> Result<TokenUsage> {
+    let count = |value: i64, field: &str| {
+        u32::try_from(value)
+            .map_err(|_| Error::ValidationError(format!("Invalid {}: {}", field, value)))
+    };
+    Ok(TokenUsage {
+        prompt_tokens: count(usage.prompt_tokens, "prompt_tokens")?,
+        completion_tokens: count(usage.completion_tokens, "completion_tokens")?,
+        total_tokens: count(usage.total_tokens, "total_tokens")?,
+    })
 }
 
 /// Convert internal TokenUsage to protobuf TokenUsage
 pub fn token_usage_to_proto(usage: TokenUsage) -> proto::TokenUsage {
     proto::TokenUsage {
-        prompt_tokens: usage.prompt_tokens as i64,
-        completion_tokens: usage.completion_tokens as i64,
-        total_tokens: usage.total_tokens as i64,
+        prompt_tokens: i64::from(usage.prompt_tokens),
+        completion_tokens: i64::from(usage.completion_tokens),
+        total_tokens: i64::from(usage.total_tokens),
+    }
+}
+
+/// Convert internal Attachment to protobuf Attachment
+pub fn attachment_to_proto(attachment: Attachment) -> proto::Attachment {
+    proto::Attachment {
+        media_type: attachment.media_type,
+        size: attachment.size,
+        sha256: attachment.sha256,
+        source_uri: attachment.source_uri,
+        name: attachment.name,
+        body: attachment.body.map(|body| match body {
+            AttachmentBody::Inline(bytes) => proto::attachment::Body::InlineData(bytes),
+            AttachmentBody::Blob(key) => proto::attachment::Body::BlobRef(key),
+        }),
+    }
+}
+
+/// Convert protobuf Attachment to internal Attachment
+///
+/// The content is checked against the size and hash when the attachment is
+/// stored, not here.
+pub fn proto_to_attachment(attachment: proto::Attachment) -> Attachment {
+    Attachment {
+        media_type: attachment.media_type,
+        size: attachment.size,
+        sha256: attachment.sha256,
+        source_uri: attachment.source_uri,
+        name: attachment.name,
+        body: attachment.body.map(|body| match body {
+            proto::attachment::Body::InlineData(bytes) => AttachmentBody::Inline(bytes),
+            proto::attachment::Body::BlobRef(key) => AttachmentBody::Blob(key),
+        }),
     }
 }
 
@@ -219,6 +292,11 @@ pub fn prompt_node_to_proto(prompt: PromptNode) -> proto::PromptNode {
         content: prompt.content,
         timestamp: Some(datetime_to_proto(prompt.timestamp)),
         metadata: Some(prompt_metadata_to_proto(prompt.metadata)),
+        attachments: prompt
+            .attachments
+            .into_iter()
+            .map(attachment_to_proto)
+            .collect(),
     }
 }
 
@@ -231,21 +309,27 @@ pub fn response_node_to_proto(response: ResponseNode) -> proto::ResponseNode {
         timestamp: Some(datetime_to_proto(response.timestamp)),
         token_usage: Some(token_usage_to_proto(response.usage)),
         metadata: Some(response_metadata_to_proto(response.metadata)),
+        attachments: response
+            .attachments
+            .into_iter()
+            .map(attachment_to_proto)
+            .collect(),
     }
 }
 
 /// Convert internal ToolInvocation to protobuf ToolInvocationNode
 pub fn tool_invocation_to_proto(tool: ToolInvocation) -> proto::ToolInvocationNode {
+    let status = tool.status().to_string();
     proto::ToolInvocationNode {
         id: tool.id.to_string(),
         response_id: tool.response_id.to_string(),
         tool_name: tool.tool_name,
         parameters: tool.parameters.to_string(),
-        status: tool.status,
+        status,
         result: tool.result.map(|r| r.to_string()),
         error: tool.error,
-        duration_ms: tool.duration_ms.map(|d| d as i64).unwrap_or(0),
-        retry_count: tool.retry_count.unwrap_or(0) as i32,
+        duration_ms: i64::try_from(tool.duration_ms).unwrap_or(i64::MAX),
+        retry_count: i32::try_from(tool.retry_count).unwrap_or(i32::MAX),
         timestamp: Some(datetime_to_proto(tool.timestamp)),
         metadata: tool.metadata,
     }
@@ -258,9 +342,20 @@ pub fn agent_node_to_proto(agent: AgentNode) -> proto::AgentNode {
         name: agent.name,
         role: agent.role,
         capabilities: agent.capabilities,
-        status: agent.status.to_string(),
+        status: agent_status_name(&agent.status).to_string(),
         created_at: Some(datetime_to_proto(agent.created_at)),
-        metadata: agent.metadata,
+        metadata: HashMap::new(),
+    }
+}
+
+/// Lowercase name of an agent status
+fn agent_status_name(status: &AgentStatus) -> &'static str {
+    match status {
+        AgentStatus::Active => "active",
+        AgentStatus::Idle => "idle",
+        AgentStatus::Busy => "busy",
+        AgentStatus::Paused => "paused",
+        AgentStatus::Terminated => "terminated",
     }
 }
 
@@ -269,26 +364,62 @@ pub fn template_to_proto(template: PromptTemplate) -> proto::TemplateNode {
     proto::TemplateNode {
         id: template.id.to_string(),
         name: template.name,
-        template_text: template.template_text,
+        template_text: template.template,
         variables: template
             .variables
             .into_iter()
             .map(variable_spec_to_proto)
             .collect(),
         version: template.version.to_string(),
-        usage_count: template.usage_count as i64,
+        usage_count: i64::try_from(template.usage_count).unwrap_or(i64::MAX),
         created_at: Some(datetime_to_proto(template.created_at)),
         metadata: template.metadata,
     }
 }
 
+/// Convert internal CustomNode to protobuf CustomNode
+pub fn custom_node_to_proto(custom: CustomNode) -> proto::CustomNode {
+    proto::CustomNode {
+        id: custom.id.to_string(),
+        kind: custom.kind,
+        payload: custom.payload.to_string(),
+        session_id: custom.session_id.map(|id| id.to_string()),
+        created_at: Some(datetime_to_proto(custom.created_at)),
+        updated_at: Some(datetime_to_proto(custom.updated_at)),
+        metadata: custom.metadata,
+    }
+}
+
+/// Convert protobuf CustomNode to internal CustomNode
+///
+/// A missing ID or timestamp is filled in, as for a newly created node.
+pub fn proto_to_custom_node(custom: proto::CustomNode) -> Result<CustomNode> {
+    let payload = serde_json::from_str(&custom.payload)
+        .map_err(|e| Error::ValidationError(format!("Invalid custom node payload: {}", e)))?;
+    let mut node = CustomNode::new(custom.kind, payload);
+    if !custom.id.is_empty() {
+        node.id = parse_node_id(&custom.id)?;
+    }
+    if let Some(session_id) = custom.session_id {
+        node.session_id = Some(parse_session_id(&session_id)?);
+    }
+    if let Some(created_at) = custom.created_at {
+        node.created_at = proto_to_datetime(created_at)?;
+    }
+    if let Some(updated_at) = custom.updated_at {
+        node.updated_at = proto_to_datetime(updated_at)?;
+    }
+    node.metadata = custom.metadata;
+    Ok(node)
+}
+
 /// Convert internal VariableSpec to protobuf VariableSpec
 pub fn variable_spec_to_proto(spec: VariableSpec) -> proto::VariableSpec {
     proto::VariableSpec {
         name: spec.name,
         type_hint: spec.type_hint,
         required: spec.required,
-        default_value: spec.default_value,
+        default_value: spec.default,
         validation_pattern: spec.validation_pattern,
         description: spec.description,
     }
@@ -297,7 +428,7 @@ pub fn variable_spec_to_proto(spec: VariableSpec) -> proto::VariableSpec {
 /// Convert internal Node to protobuf Node
 pub fn node_to_proto(node: Node) -> proto::Node {
     let id = node.id().to_string();
-    let created_at = Some(datetime_to_proto(Utc::now())); // TODO: Get actual created_at from node
+    let created_at = Some(datetime_to_proto(crate::search::node_timestamp(&node)));
 
     match node {
         Node::Prompt(prompt) => {
@@ -345,7 +476,16 @@ pub fn node_to_proto(node: Node) -> proto::Node {
                 node_data: Some(proto::node::NodeData::Template(template_to_proto(template))),
             }
         }
-        Node::Session(session) => {
+        Node::Custom(custom) => {
+            let node_type = node_type_to_proto(NodeType::Custom);
+            proto::Node {
+                id,
+                r#type: node_type,
+                created_at,
+                node_data: Some(proto::node::NodeData::Custom(custom_node_to_proto(custom))),
+            }
+        }
+        Node::Session(_) => {
             let node_type = node_type_to_proto(NodeType::Session);
             proto::Node {
                 id,
@@ -363,13 +503,218 @@ pub fn node_to_proto(node: Node) -> proto::Node {
 
 /// Convert internal Edge to protobuf Edge
 pub fn edge_to_proto(edge: crate::Edge) -> proto::Edge {
+    let custom_type = match &edge.edge_type {
+        EdgeType::Custom(name) => name.clone(),
+        _ => String::new(),
+    };
     proto::Edge {
         id: edge.id.to_string(),
         from_node_id: edge.from.to_string(),
         to_node_id: edge.to.to_string(),
         r#type: edge_type_to_proto(edge.edge_type),
         created_at: Some(datetime_to_proto(edge.created_at)),
-        properties: edge.properties.unwrap_or_default(),
+        properties: edge.properties,
+        custom_type,
+    }
+}
+
+/// Convert protobuf Edge to internal Edge
+///
+/// A missing ID or timestamp is filled in, as for a newly created edge.
+pub fn proto_to_edge(edge: proto::Edge) -> Result<crate::Edge> {
+    let edge_type = proto_to_edge_type(edge.r#type, &edge.custom_type)?;
+    let mut converted = crate::Edge::with_properties(
+        parse_node_id(&edge.from_node_id)?,
+        parse_node_id(&edge.to_node_id)?,
+        edge_type,
+        edge.properties,
+    );
+    if !edge.id.is_empty() {
+        converted.id = uuid::Uuid::parse_str(&edge.id)
+            .map(|uuid| crate::EdgeId::from_bytes(*uuid.as_bytes()))
+            .map_err(|_| Error::ValidationError(format!("Invalid edge ID: {}", edge.id)))?;
+    }
+    if let Some(created_at) = edge.created_at {
+        converted.created_at = proto_to_datetime(created_at)?;
+    }
+    Ok(converted)
+}
+
+// ============================================================================
+// Query Conversion
+// ============================================================================
+
+/// Apply the filters of a protobuf query request to a query builder
+///
+/// Paging fields are left to the caller, so the same filters can be used to
+/// count the full result.
+pub fn apply_query_filters(
+    mut builder: crate::query::AsyncQueryBuilder,
+    req: &proto::QueryRequest,
+) -> Result<crate::query::AsyncQueryBuilder> {
+    if let Some(session_id) = &req.session_id {
+        builder = builder.session(parse_session_id(session_id)?);
+    }
+    if let Some(node_type) = req.node_type {
+        builder = builder.node_type(proto_to_node_type(node_type)?);
+    }
+    if req.after.is_some() || req.before.is_some() {
+        let after = req.after.clone().map(proto_to_datetime).transpose()?;
+        let before = req.before.clone().map(proto_to_datetime).transpose()?;
+        builder = builder.time_range(
+            after.unwrap_or(DateTime::<Utc>::MIN_UTC),
+            before.unwrap_or(DateTime::<Utc>::MAX_UTC),
+        );
+    }
+    for (key, value) in &req.filters {
+        builder = builder.filter(crate::query::Predicate::metadata(key, value));
+    }
+    Ok(builder)
+}
+
+/// Parse a page token, treating an empty token as the first page
+pub fn page_token_to_cursor(token: &str) -> Result<Option<crate::query::Cursor>> {
+    if token.is_empty() {
+        Ok(None)
+    } else {
+        crate::query::Cursor::decode(token).map(Some)
+    }
+}
+
+/// Encode the next page cursor as a page token, empty on the last page
+pub fn cursor_to_page_token(cursor: Option<crate::query::Cursor>) -> String {
+    cursor.map(|cursor| cursor.encode()).unwrap_or_default()
+}
+
+// ============================================================================
+// Aggregation Conversion
+// ============================================================================
+
+/// Parse the grouping dimensions and aggregates of a protobuf request
+pub fn proto_to_aggregation(
+    req: &proto::AggregateRequest,
+) -> Result<crate::query::aggregate::Aggregation> {
+    let mut aggregation = crate::query::aggregate::Aggregation::new();
+    for group_by in &req.group_by {
+        aggregation = aggregation.group_by(group_by.parse()?);
+    }
+    for aggregate in &req.aggregates {
+        aggregation = aggregation.aggregate(aggregate.parse()?);
+    }
+    Ok(aggregation)
+}
+
+/// Convert aggregate rows to protobuf
+pub fn aggregate_rows_to_proto(
+    aggregation: &crate::query::aggregate::Aggregation,
+    rows: Vec<crate::query::AggregateRow>,
+) -> proto::AggregateResponse {
+    proto::AggregateResponse {
+        columns: aggregation
+            .aggregates
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        rows: rows
+            .into_iter()
+            .map(|row| proto::AggregateRow {
+                key: row.key.iter().map(ToString::to_string).collect(),
+                count: row.count as u64,
+                values: row
+                    .values
+                    .into_iter()
+                    .map(|value| proto::AggregateValue { value })
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+// ============================================================================
+// Query Result Conversion
+// ============================================================================
+
+/// Convert a graph query result to protobuf, encoding each value as JSON
+pub fn query_result_to_proto(result: crate::query::QueryResult) -> proto::ExpressionQueryResponse {
+    proto::ExpressionQueryResponse {
+        columns: result.columns,
+        rows: result
+            .rows
+            .into_iter()
+            .map(|row| proto::QueryRow {
+                values: row.iter().map(ToString::to_string).collect(),
+            })
+            .collect(),
+    }
+}
+
+// ============================================================================
+// Search Conversion
+// ============================================================================
+
+/// Convert a protobuf search request to a text query
+pub fn proto_to_text_query(req: proto::SearchRequest) -> Result<crate::search::TextQuery> {
+    let mut query = crate::search::TextQuery::new(req.query);
+    if let Some(session_id) = req.session_id {
+        query = query.session(parse_session_id(&session_id)?);
+    }
+    for node_type in req.node_types {
+        query = query.node_type(proto_to_node_type(node_type)?);
+    }
+    if let Some(after) = req.after {
+        query = query.after(proto_to_datetime(after)?);
+    }
+    if let Some(before) = req.before {
+        query = query.before(proto_to_datetime(before)?);
+    }
+    if req.limit > 0 {
+        query = query.limit(req.limit as usize);
+    }
+    if req.offset > 0 {
+        query = query.offset(req.offset as usize);
+    }
+    Ok(query)
+}
+
+/// Convert a search hit to protobuf
+pub fn search_hit_to_proto(hit: crate::search::SearchHit) -> proto::SearchHit {
+    proto::SearchHit {
+        node_id: hit.node_id.to_string(),
+        node_type: node_type_to_proto(hit.node_type),
+        session_id: hit.session_id.map(|s| s.to_string()),
+        timestamp: Some(datetime_to_proto(hit.timestamp)),
+        score: hit.score,
+        snippet: hit.snippet.text,
+        highlights: hit
+            .snippet
+            .highlights
+            .into_iter()
+            .map(|range| proto::HighlightRange {
+                start: range.start as u32,
+                end: range.end as u32,
+            })
+            .collect(),
+    }
+}
+
+// ============================================================================
+// Lookup Conversion
+// ============================================================================
+
+/// Convert the key of a protobuf lookup request to a reverse lookup
+pub fn proto_to_lookup(key: Option<proto::lookup_request::Key>) -> Result<crate::storage::Lookup> {
+    use crate::storage::Lookup;
+    use proto::lookup_request::Key;
+
+    match key {
+        Some(Key::TemplateId(id)) => uuid::Uuid::parse_str(&id)
+            .map(|uuid| Lookup::Template(crate::TemplateId::from_uuid(uuid)))
+            .map_err(|_| Error::ValidationError(format!("Invalid template ID: {}", id))),
+        Some(Key::AgentNodeId(id)) => parse_node_id(&id).map(Lookup::Agent),
+        Some(Key::ToolName(name)) => Ok(Lookup::ToolName(name)),
+        Some(Key::Model(model)) => Ok(Lookup::Model(model)),
+        Some(Key::Kind(kind)) => Ok(Lookup::Kind(kind)),
+        None => Err(Error::ValidationError("Lookup key is required".to_string())),
     }
 }
 
@@ -379,14 +724,16 @@ pub fn edge_to_proto(edge: crate::Edge) -> proto::Edge {
 
 /// Parse SessionId from string
 pub fn parse_session_id(id: &str) -> Result<SessionId> {
-    SessionId::parse_str(id)
-        .map_err(|_| Error::InvalidInput(format!("Invalid session ID: {}", id)))
+    uuid::Uuid::parse_str(id)
+        .map(SessionId::from_uuid)
+        .map_err(|_| Error::ValidationError(format!("Invalid session ID: {}", id)))
 }
 
 /// Parse NodeId from string
 pub fn parse_node_id(id: &str) -> Result<crate::NodeId> {
-    crate::NodeId::parse_str(id)
-        .map_err(|_| Error::InvalidInput(format!("Invalid node ID: {}", id)))
+    uuid::Uuid::parse_str(id)
+        .map(crate::NodeId::from_uuid)
+        .map_err(|_| Error::ValidationError(format!("Invalid node ID: {}", id)))
 }
 
 // ============================================================================
@@ -409,9 +756,12 @@ mod tests {
 
     #[test]
     fn test_node_type_conversion() {
-        assert_eq!(node_type_to_proto(NodeType::Prompt), proto::NodeType::NodeTypePrompt as i32);
         assert_eq!(
-            proto_to_node_type(proto::NodeType::NodeTypePrompt as i32).unwrap(),
+            node_type_to_proto(NodeType::Prompt),
+            proto::NodeType::Prompt as i32
+        );
+        assert_eq!(
+            proto_to_node_type(proto::NodeType::Prompt as i32).unwrap(),
             NodeType::Prompt
         );
     }
@@ -420,12 +770,17 @@ mod tests {
     fn test_edge_type_conversion() {
         assert_eq!(
             edge_type_to_proto(EdgeType::RespondsTo),
-            proto::EdgeType::EdgeTypeRespondsTo as i32
+            proto::EdgeType::RespondsTo as i32
         );
         assert_eq!(
-            proto_to_edge_type(proto::EdgeType::EdgeTypeRespondsTo as i32).unwrap(),
+            proto_to_edge_type(proto::EdgeType::RespondsTo as i32, "").unwrap(),
             EdgeType::RespondsTo
         );
+        assert_eq!(
+            proto_to_edge_type(proto::EdgeType::Custom as i32, "cites").unwrap(),
+            EdgeType::Custom("cites".to_string())
+        );
+        assert!(proto_to_edge_type(proto::EdgeType::Custom as i32, "").is_err());
     }
 
     #[test]
@@ -437,10 +792,23 @@ mod tests {
         };
 
         let proto_usage = token_usage_to_proto(usage);
-        let converted = proto_to_token_usage(proto_usage);
+        let converted = proto_to_token_usage(proto_usage).unwrap();
 
         assert_eq!(converted.prompt_tokens, 10);
         assert_eq!(converted.completion_tokens, 50);
         assert_eq!(converted.total_tokens, 60);
     }
+
+    #[test]
+    fn test_attachment_conversion() {
+        let mut attachment = Attachment::external("image/png", 3, "ab").with_name("a.png");
+        attachment.body = Some(AttachmentBody::Blob("ab".to_string()));
+
+        let proto_attachment = attachment_to_proto(attachment.clone());
+        assert_eq!(
+            proto_attachment.body,
+            Some(proto::attachment::Body::BlobRef("ab".to_string()))
+        );
+        assert_eq!(proto_to_attachment(proto_attachment), attachment);
+    }
 }
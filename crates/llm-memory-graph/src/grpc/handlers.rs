@@ -3,7 +3,6 @@
 //! This module contains helper functions for handling specific types of
 //! gRPC requests, including validation, transformation, and error handling.
 
-use crate::Result;
 use crate::grpc::proto;
 use tonic::Status;
 
@@ -47,12 +46,8 @@ pub fn validate_add_response_request(request: &proto::AddResponseRequest) -> Res
 
 /// Validate a query request
 pub fn validate_query_request(request: &proto::QueryRequest) -> Result<(), Status> {
-    if let Some(limit) = request.limit.into() {
-        if limit > 10000 {
-            return Err(Status::invalid_argument(
-                "limit cannot exceed 10000",
-            ));
-        }
+    if request.limit > 10000 {
+        return Err(Status::invalid_argument("limit cannot exceed 10000"));
     }
     Ok(())
 }
@@ -67,20 +62,23 @@ mod tests {
             session_id: "test-session".to_string(),
             content: "Test prompt".to_string(),
             metadata: None,
+            attachments: Vec::new(),
         };
         assert!(validate_add_prompt_request(&valid_req).is_ok());
 
         let empty_session = proto::AddPromptRequest {
-            session_id: "".to_string(),
+            session_id: String::new(),
             content: "Test".to_string(),
             metadata: None,
+            attachments: Vec::new(),
         };
         assert!(validate_add_prompt_request(&empty_session).is_err());
 
         let empty_content = proto::AddPromptRequest {
             session_id: "test".to_string(),
-            content: "".to_string(),
+            content: String::new(),
             metadata: None,
+            attachments: Vec::new(),
         };
         assert!(validate_add_prompt_request(&empty_content).is_err());
     }
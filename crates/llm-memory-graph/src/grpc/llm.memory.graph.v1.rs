@@ -1,2852 +0,0 @@
-// This file is @generated by prost-build.
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct Session {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(message, optional, tag = "2")]
-    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(message, optional, tag = "3")]
-    pub updated_at: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(map = "string, string", tag = "4")]
-    pub metadata: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-    #[prost(bool, tag = "5")]
-    pub is_active: bool,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct Node {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(enumeration = "NodeType", tag = "2")]
-    pub r#type: i32,
-    #[prost(message, optional, tag = "3")]
-    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(oneof = "node::NodeData", tags = "10, 11, 12, 13, 14")]
-    pub node_data: ::core::option::Option<node::NodeData>,
-}
-/// Nested message and enum types in `Node`.
-pub mod node {
-    #[allow(clippy::derive_partial_eq_without_eq)]
-    #[derive(Clone, PartialEq, ::prost::Oneof)]
-    pub enum NodeData {
-        #[prost(message, tag = "10")]
-        Prompt(super::PromptNode),
-        #[prost(message, tag = "11")]
-        Response(super::ResponseNode),
-        #[prost(message, tag = "12")]
-        ToolInvocation(super::ToolInvocationNode),
-        #[prost(message, tag = "13")]
-        Agent(super::AgentNode),
-        #[prost(message, tag = "14")]
-        Template(super::TemplateNode),
-    }
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct PromptNode {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub session_id: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub content: ::prost::alloc::string::String,
-    #[prost(message, optional, tag = "4")]
-    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(message, optional, tag = "5")]
-    pub metadata: ::core::option::Option<PromptMetadata>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ResponseNode {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub prompt_id: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub content: ::prost::alloc::string::String,
-    #[prost(message, optional, tag = "4")]
-    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(message, optional, tag = "5")]
-    pub token_usage: ::core::option::Option<TokenUsage>,
-    #[prost(message, optional, tag = "6")]
-    pub metadata: ::core::option::Option<ResponseMetadata>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ToolInvocationNode {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub response_id: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub tool_name: ::prost::alloc::string::String,
-    /// JSON
-    #[prost(string, tag = "4")]
-    pub parameters: ::prost::alloc::string::String,
-    #[prost(string, tag = "5")]
-    pub status: ::prost::alloc::string::String,
-    /// JSON
-    #[prost(string, optional, tag = "6")]
-    pub result: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(string, optional, tag = "7")]
-    pub error: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(int64, tag = "8")]
-    pub duration_ms: i64,
-    #[prost(int32, tag = "9")]
-    pub retry_count: i32,
-    #[prost(message, optional, tag = "10")]
-    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(map = "string, string", tag = "11")]
-    pub metadata: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct AgentNode {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub name: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub role: ::prost::alloc::string::String,
-    #[prost(string, repeated, tag = "4")]
-    pub capabilities: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-    #[prost(string, tag = "5")]
-    pub status: ::prost::alloc::string::String,
-    #[prost(message, optional, tag = "6")]
-    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(map = "string, string", tag = "7")]
-    pub metadata: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct TemplateNode {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub name: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub template_text: ::prost::alloc::string::String,
-    #[prost(message, repeated, tag = "4")]
-    pub variables: ::prost::alloc::vec::Vec<VariableSpec>,
-    #[prost(string, tag = "5")]
-    pub version: ::prost::alloc::string::String,
-    #[prost(int64, tag = "6")]
-    pub usage_count: i64,
-    #[prost(message, optional, tag = "7")]
-    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(map = "string, string", tag = "8")]
-    pub metadata: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct Edge {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub from_node_id: ::prost::alloc::string::String,
-    #[prost(string, tag = "3")]
-    pub to_node_id: ::prost::alloc::string::String,
-    #[prost(enumeration = "EdgeType", tag = "4")]
-    pub r#type: i32,
-    #[prost(message, optional, tag = "5")]
-    pub created_at: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(map = "string, string", tag = "6")]
-    pub properties: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct TokenUsage {
-    #[prost(int64, tag = "1")]
-    pub prompt_tokens: i64,
-    #[prost(int64, tag = "2")]
-    pub completion_tokens: i64,
-    #[prost(int64, tag = "3")]
-    pub total_tokens: i64,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct PromptMetadata {
-    #[prost(string, tag = "1")]
-    pub model: ::prost::alloc::string::String,
-    #[prost(double, tag = "2")]
-    pub temperature: f64,
-    #[prost(int32, optional, tag = "3")]
-    pub max_tokens: ::core::option::Option<i32>,
-    #[prost(string, repeated, tag = "4")]
-    pub tools_available: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-    #[prost(map = "string, string", tag = "5")]
-    pub custom: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ResponseMetadata {
-    #[prost(string, tag = "1")]
-    pub model: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub finish_reason: ::prost::alloc::string::String,
-    #[prost(int64, tag = "3")]
-    pub latency_ms: i64,
-    #[prost(map = "string, string", tag = "4")]
-    pub custom: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct VariableSpec {
-    #[prost(string, tag = "1")]
-    pub name: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub type_hint: ::prost::alloc::string::String,
-    #[prost(bool, tag = "3")]
-    pub required: bool,
-    #[prost(string, optional, tag = "4")]
-    pub default_value: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(string, optional, tag = "5")]
-    pub validation_pattern: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(string, tag = "6")]
-    pub description: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct CreateSessionRequest {
-    #[prost(map = "string, string", tag = "1")]
-    pub metadata: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GetSessionRequest {
-    #[prost(string, tag = "1")]
-    pub session_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct DeleteSessionRequest {
-    #[prost(string, tag = "1")]
-    pub session_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ListSessionsRequest {
-    #[prost(int32, tag = "1")]
-    pub limit: i32,
-    #[prost(int32, tag = "2")]
-    pub offset: i32,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct ListSessionsResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub sessions: ::prost::alloc::vec::Vec<Session>,
-    #[prost(int64, tag = "2")]
-    pub total_count: i64,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct CreateNodeRequest {
-    #[prost(message, optional, tag = "1")]
-    pub node: ::core::option::Option<Node>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GetNodeRequest {
-    #[prost(string, tag = "1")]
-    pub node_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct UpdateNodeRequest {
-    #[prost(message, optional, tag = "1")]
-    pub node: ::core::option::Option<Node>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct DeleteNodeRequest {
-    #[prost(string, tag = "1")]
-    pub node_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct BatchCreateNodesRequest {
-    #[prost(message, repeated, tag = "1")]
-    pub nodes: ::prost::alloc::vec::Vec<Node>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct BatchCreateNodesResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub nodes: ::prost::alloc::vec::Vec<Node>,
-    #[prost(int32, tag = "2")]
-    pub created_count: i32,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct BatchGetNodesRequest {
-    #[prost(string, repeated, tag = "1")]
-    pub node_ids: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct BatchGetNodesResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub nodes: ::prost::alloc::vec::Vec<Node>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct CreateEdgeRequest {
-    #[prost(message, optional, tag = "1")]
-    pub edge: ::core::option::Option<Edge>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GetEdgesRequest {
-    #[prost(string, tag = "1")]
-    pub node_id: ::prost::alloc::string::String,
-    #[prost(enumeration = "EdgeDirection", optional, tag = "2")]
-    pub direction: ::core::option::Option<i32>,
-    #[prost(enumeration = "EdgeType", optional, tag = "3")]
-    pub r#type: ::core::option::Option<i32>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct GetEdgesResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub edges: ::prost::alloc::vec::Vec<Edge>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct DeleteEdgeRequest {
-    #[prost(string, tag = "1")]
-    pub edge_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct QueryRequest {
-    #[prost(string, optional, tag = "1")]
-    pub session_id: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(enumeration = "NodeType", optional, tag = "2")]
-    pub node_type: ::core::option::Option<i32>,
-    #[prost(message, optional, tag = "3")]
-    pub after: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(message, optional, tag = "4")]
-    pub before: ::core::option::Option<::prost_types::Timestamp>,
-    #[prost(int32, tag = "5")]
-    pub limit: i32,
-    #[prost(int32, tag = "6")]
-    pub offset: i32,
-    #[prost(map = "string, string", tag = "7")]
-    pub filters: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct QueryResponse {
-    #[prost(message, repeated, tag = "1")]
-    pub nodes: ::prost::alloc::vec::Vec<Node>,
-    #[prost(int64, tag = "2")]
-    pub total_count: i64,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct AddPromptRequest {
-    #[prost(string, tag = "1")]
-    pub session_id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub content: ::prost::alloc::string::String,
-    #[prost(message, optional, tag = "3")]
-    pub metadata: ::core::option::Option<PromptMetadata>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct AddResponseRequest {
-    #[prost(string, tag = "1")]
-    pub prompt_id: ::prost::alloc::string::String,
-    #[prost(string, tag = "2")]
-    pub content: ::prost::alloc::string::String,
-    #[prost(message, optional, tag = "3")]
-    pub token_usage: ::core::option::Option<TokenUsage>,
-    #[prost(message, optional, tag = "4")]
-    pub metadata: ::core::option::Option<ResponseMetadata>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct AddToolInvocationRequest {
-    #[prost(message, optional, tag = "1")]
-    pub tool_invocation: ::core::option::Option<ToolInvocationNode>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct CreateTemplateRequest {
-    #[prost(message, optional, tag = "1")]
-    pub template: ::core::option::Option<TemplateNode>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct InstantiateTemplateRequest {
-    #[prost(string, tag = "1")]
-    pub template_id: ::prost::alloc::string::String,
-    #[prost(map = "string, string", tag = "2")]
-    pub variable_values: ::std::collections::HashMap<
-        ::prost::alloc::string::String,
-        ::prost::alloc::string::String,
-    >,
-    #[prost(string, tag = "3")]
-    pub session_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct StreamEventsRequest {
-    #[prost(string, optional, tag = "1")]
-    pub session_id: ::core::option::Option<::prost::alloc::string::String>,
-    #[prost(enumeration = "EventType", repeated, tag = "2")]
-    pub event_types: ::prost::alloc::vec::Vec<i32>,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct Event {
-    #[prost(string, tag = "1")]
-    pub id: ::prost::alloc::string::String,
-    #[prost(enumeration = "EventType", tag = "2")]
-    pub r#type: i32,
-    #[prost(message, optional, tag = "3")]
-    pub timestamp: ::core::option::Option<::prost_types::Timestamp>,
-    /// JSON
-    #[prost(string, tag = "4")]
-    pub payload: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct SubscribeRequest {
-    #[prost(string, tag = "1")]
-    pub session_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct SessionEvent {
-    #[prost(message, optional, tag = "1")]
-    pub event: ::core::option::Option<Event>,
-    #[prost(string, tag = "2")]
-    pub session_id: ::prost::alloc::string::String,
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct HealthResponse {
-    #[prost(enumeration = "health_response::ServingStatus", tag = "1")]
-    pub status: i32,
-    #[prost(string, tag = "2")]
-    pub version: ::prost::alloc::string::String,
-    #[prost(int64, tag = "3")]
-    pub uptime_seconds: i64,
-}
-/// Nested message and enum types in `HealthResponse`.
-pub mod health_response {
-    #[derive(
-        Clone,
-        Copy,
-        Debug,
-        PartialEq,
-        Eq,
-        Hash,
-        PartialOrd,
-        Ord,
-        ::prost::Enumeration
-    )]
-    #[repr(i32)]
-    pub enum ServingStatus {
-        Unknown = 0,
-        Serving = 1,
-        NotServing = 2,
-    }
-    impl ServingStatus {
-        /// String value of the enum field names used in the ProtoBuf definition.
-        ///
-        /// The values are not transformed in any way and thus are considered stable
-        /// (if the ProtoBuf definition does not change) and safe for programmatic use.
-        pub fn as_str_name(&self) -> &'static str {
-            match self {
-                ServingStatus::Unknown => "UNKNOWN",
-                ServingStatus::Serving => "SERVING",
-                ServingStatus::NotServing => "NOT_SERVING",
-            }
-        }
-        /// Creates an enum from field names used in the ProtoBuf definition.
-        pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
-            match value {
-                "UNKNOWN" => Some(Self::Unknown),
-                "SERVING" => Some(Self::Serving),
-                "NOT_SERVING" => Some(Self::NotServing),
-                _ => None,
-            }
-        }
-    }
-}
-#[allow(clippy::derive_partial_eq_without_eq)]
-#[derive(Clone, PartialEq, ::prost::Message)]
-pub struct MetricsResponse {
-    #[prost(int64, tag = "1")]
-    pub total_nodes: i64,
-    #[prost(int64, tag = "2")]
-    pub total_edges: i64,
-    #[prost(int64, tag = "3")]
-    pub total_sessions: i64,
-    #[prost(int64, tag = "4")]
-    pub active_sessions: i64,
-    #[prost(double, tag = "5")]
-    pub avg_write_latency_ms: f64,
-    #[prost(double, tag = "6")]
-    pub avg_read_latency_ms: f64,
-    #[prost(int64, tag = "7")]
-    pub requests_per_second: i64,
-}
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-#[repr(i32)]
-pub enum NodeType {
-    Unspecified = 0,
-    Session = 1,
-    Prompt = 2,
-    Response = 3,
-    ToolInvocation = 4,
-    Agent = 5,
-    Template = 6,
-}
-impl NodeType {
-    /// String value of the enum field names used in the ProtoBuf definition.
-    ///
-    /// The values are not transformed in any way and thus are considered stable
-    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
-    pub fn as_str_name(&self) -> &'static str {
-        match self {
-            NodeType::Unspecified => "NODE_TYPE_UNSPECIFIED",
-            NodeType::Session => "NODE_TYPE_SESSION",
-            NodeType::Prompt => "NODE_TYPE_PROMPT",
-            NodeType::Response => "NODE_TYPE_RESPONSE",
-            NodeType::ToolInvocation => "NODE_TYPE_TOOL_INVOCATION",
-            NodeType::Agent => "NODE_TYPE_AGENT",
-            NodeType::Template => "NODE_TYPE_TEMPLATE",
-        }
-    }
-    /// Creates an enum from field names used in the ProtoBuf definition.
-    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
-        match value {
-            "NODE_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
-            "NODE_TYPE_SESSION" => Some(Self::Session),
-            "NODE_TYPE_PROMPT" => Some(Self::Prompt),
-            "NODE_TYPE_RESPONSE" => Some(Self::Response),
-            "NODE_TYPE_TOOL_INVOCATION" => Some(Self::ToolInvocation),
-            "NODE_TYPE_AGENT" => Some(Self::Agent),
-            "NODE_TYPE_TEMPLATE" => Some(Self::Template),
-            _ => None,
-        }
-    }
-}
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-#[repr(i32)]
-pub enum EdgeType {
-    Unspecified = 0,
-    BelongsTo = 1,
-    RespondsTo = 2,
-    Follows = 3,
-    Invokes = 4,
-    HandledBy = 5,
-    Instantiates = 6,
-    Inherits = 7,
-    TransfersTo = 8,
-    References = 9,
-}
-impl EdgeType {
-    /// String value of the enum field names used in the ProtoBuf definition.
-    ///
-    /// The values are not transformed in any way and thus are considered stable
-    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
-    pub fn as_str_name(&self) -> &'static str {
-        match self {
-            EdgeType::Unspecified => "EDGE_TYPE_UNSPECIFIED",
-            EdgeType::BelongsTo => "EDGE_TYPE_BELONGS_TO",
-            EdgeType::RespondsTo => "EDGE_TYPE_RESPONDS_TO",
-            EdgeType::Follows => "EDGE_TYPE_FOLLOWS",
-            EdgeType::Invokes => "EDGE_TYPE_INVOKES",
-            EdgeType::HandledBy => "EDGE_TYPE_HANDLED_BY",
-            EdgeType::Instantiates => "EDGE_TYPE_INSTANTIATES",
-            EdgeType::Inherits => "EDGE_TYPE_INHERITS",
-            EdgeType::TransfersTo => "EDGE_TYPE_TRANSFERS_TO",
-            EdgeType::References => "EDGE_TYPE_REFERENCES",
-        }
-    }
-    /// Creates an enum from field names used in the ProtoBuf definition.
-    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
-        match value {
-            "EDGE_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
-            "EDGE_TYPE_BELONGS_TO" => Some(Self::BelongsTo),
-            "EDGE_TYPE_RESPONDS_TO" => Some(Self::RespondsTo),
-            "EDGE_TYPE_FOLLOWS" => Some(Self::Follows),
-            "EDGE_TYPE_INVOKES" => Some(Self::Invokes),
-            "EDGE_TYPE_HANDLED_BY" => Some(Self::HandledBy),
-            "EDGE_TYPE_INSTANTIATES" => Some(Self::Instantiates),
-            "EDGE_TYPE_INHERITS" => Some(Self::Inherits),
-            "EDGE_TYPE_TRANSFERS_TO" => Some(Self::TransfersTo),
-            "EDGE_TYPE_REFERENCES" => Some(Self::References),
-            _ => None,
-        }
-    }
-}
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-#[repr(i32)]
-pub enum EdgeDirection {
-    Unspecified = 0,
-    Outgoing = 1,
-    Incoming = 2,
-    Both = 3,
-}
-impl EdgeDirection {
-    /// String value of the enum field names used in the ProtoBuf definition.
-    ///
-    /// The values are not transformed in any way and thus are considered stable
-    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
-    pub fn as_str_name(&self) -> &'static str {
-        match self {
-            EdgeDirection::Unspecified => "EDGE_DIRECTION_UNSPECIFIED",
-            EdgeDirection::Outgoing => "EDGE_DIRECTION_OUTGOING",
-            EdgeDirection::Incoming => "EDGE_DIRECTION_INCOMING",
-            EdgeDirection::Both => "EDGE_DIRECTION_BOTH",
-        }
-    }
-    /// Creates an enum from field names used in the ProtoBuf definition.
-    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
-        match value {
-            "EDGE_DIRECTION_UNSPECIFIED" => Some(Self::Unspecified),
-            "EDGE_DIRECTION_OUTGOING" => Some(Self::Outgoing),
-            "EDGE_DIRECTION_INCOMING" => Some(Self::Incoming),
-            "EDGE_DIRECTION_BOTH" => Some(Self::Both),
-            _ => None,
-        }
-    }
-}
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
-#[repr(i32)]
-pub enum EventType {
-    Unspecified = 0,
-    NodeCreated = 1,
-    NodeUpdated = 2,
-    NodeDeleted = 3,
-    EdgeCreated = 4,
-    EdgeDeleted = 5,
-    SessionCreated = 6,
-    SessionClosed = 7,
-}
-impl EventType {
-    /// String value of the enum field names used in the ProtoBuf definition.
-    ///
-    /// The values are not transformed in any way and thus are considered stable
-    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
-    pub fn as_str_name(&self) -> &'static str {
-        match self {
-            EventType::Unspecified => "EVENT_TYPE_UNSPECIFIED",
-            EventType::NodeCreated => "EVENT_TYPE_NODE_CREATED",
-            EventType::NodeUpdated => "EVENT_TYPE_NODE_UPDATED",
-            EventType::NodeDeleted => "EVENT_TYPE_NODE_DELETED",
-            EventType::EdgeCreated => "EVENT_TYPE_EDGE_CREATED",
-            EventType::EdgeDeleted => "EVENT_TYPE_EDGE_DELETED",
-            EventType::SessionCreated => "EVENT_TYPE_SESSION_CREATED",
-            EventType::SessionClosed => "EVENT_TYPE_SESSION_CLOSED",
-        }
-    }
-    /// Creates an enum from field names used in the ProtoBuf definition.
-    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
-        match value {
-            "EVENT_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
-            "EVENT_TYPE_NODE_CREATED" => Some(Self::NodeCreated),
-            "EVENT_TYPE_NODE_UPDATED" => Some(Self::NodeUpdated),
-            "EVENT_TYPE_NODE_DELETED" => Some(Self::NodeDeleted),
-            "EVENT_TYPE_EDGE_CREATED" => Some(Self::EdgeCreated),
-            "EVENT_TYPE_EDGE_DELETED" => Some(Self::EdgeDeleted),
-            "EVENT_TYPE_SESSION_CREATED" => Some(Self::SessionCreated),
-            "EVENT_TYPE_SESSION_CLOSED" => Some(Self::SessionClosed),
-            _ => None,
-        }
-    }
-}
-/// Generated client implementations.
-pub mod memory_graph_service_client {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    use tonic::codegen::http::Uri;
-    #[derive(Debug, Clone)]
-    pub struct MemoryGraphServiceClient<T> {
-        inner: tonic::client::Grpc<T>,
-    }
-    impl MemoryGraphServiceClient<tonic::transport::Channel> {
-        /// Attempt to create a new client by connecting to a given endpoint.
-        pub async fn connect<D>(dst: D) -> Result<Self, tonic::transport::Error>
-        where
-            D: TryInto<tonic::transport::Endpoint>,
-            D::Error: Into<StdError>,
-        {
-            let conn = tonic::transport::Endpoint::new(dst)?.connect().await?;
-            Ok(Self::new(conn))
-        }
-    }
-    impl<T> MemoryGraphServiceClient<T>
-    where
-        T: tonic::client::GrpcService<tonic::body::BoxBody>,
-        T::Error: Into<StdError>,
-        T::ResponseBody: Body<Data = Bytes> + Send + 'static,
-        <T::ResponseBody as Body>::Error: Into<StdError> + Send,
-    {
-        pub fn new(inner: T) -> Self {
-            let inner = tonic::client::Grpc::new(inner);
-            Self { inner }
-        }
-        pub fn with_origin(inner: T, origin: Uri) -> Self {
-            let inner = tonic::client::Grpc::with_origin(inner, origin);
-            Self { inner }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> MemoryGraphServiceClient<InterceptedService<T, F>>
-        where
-            F: tonic::service::Interceptor,
-            T::ResponseBody: Default,
-            T: tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-                Response = http::Response<
-                    <T as tonic::client::GrpcService<tonic::body::BoxBody>>::ResponseBody,
-                >,
-            >,
-            <T as tonic::codegen::Service<
-                http::Request<tonic::body::BoxBody>,
-            >>::Error: Into<StdError> + Send + Sync,
-        {
-            MemoryGraphServiceClient::new(InterceptedService::new(inner, interceptor))
-        }
-        /// Compress requests with the given encoding.
-        ///
-        /// This requires the server to support it otherwise it might respond with an
-        /// error.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.send_compressed(encoding);
-            self
-        }
-        /// Enable decompressing responses.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.inner = self.inner.accept_compressed(encoding);
-            self
-        }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.inner = self.inner.max_decoding_message_size(limit);
-            self
-        }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.inner = self.inner.max_encoding_message_size(limit);
-            self
-        }
-        /// Session Management
-        pub async fn create_session(
-            &mut self,
-            request: impl tonic::IntoRequest<super::CreateSessionRequest>,
-        ) -> std::result::Result<tonic::Response<super::Session>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/CreateSession",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "CreateSession",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn get_session(
-            &mut self,
-            request: impl tonic::IntoRequest<super::GetSessionRequest>,
-        ) -> std::result::Result<tonic::Response<super::Session>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/GetSession",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "GetSession",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn delete_session(
-            &mut self,
-            request: impl tonic::IntoRequest<super::DeleteSessionRequest>,
-        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/DeleteSession",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "DeleteSession",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn list_sessions(
-            &mut self,
-            request: impl tonic::IntoRequest<super::ListSessionsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::ListSessionsResponse>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/ListSessions",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "ListSessions",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        /// Node Operations
-        pub async fn create_node(
-            &mut self,
-            request: impl tonic::IntoRequest<super::CreateNodeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Node>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/CreateNode",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "CreateNode",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn get_node(
-            &mut self,
-            request: impl tonic::IntoRequest<super::GetNodeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Node>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/GetNode",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("llm.memory.graph.v1.MemoryGraphService", "GetNode"),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn update_node(
-            &mut self,
-            request: impl tonic::IntoRequest<super::UpdateNodeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Node>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/UpdateNode",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "UpdateNode",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn delete_node(
-            &mut self,
-            request: impl tonic::IntoRequest<super::DeleteNodeRequest>,
-        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/DeleteNode",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "DeleteNode",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn batch_create_nodes(
-            &mut self,
-            request: impl tonic::IntoRequest<super::BatchCreateNodesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::BatchCreateNodesResponse>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/BatchCreateNodes",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "BatchCreateNodes",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn batch_get_nodes(
-            &mut self,
-            request: impl tonic::IntoRequest<super::BatchGetNodesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::BatchGetNodesResponse>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/BatchGetNodes",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "BatchGetNodes",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        /// Edge Operations
-        pub async fn create_edge(
-            &mut self,
-            request: impl tonic::IntoRequest<super::CreateEdgeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Edge>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/CreateEdge",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "CreateEdge",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn get_edges(
-            &mut self,
-            request: impl tonic::IntoRequest<super::GetEdgesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetEdgesResponse>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/GetEdges",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("llm.memory.graph.v1.MemoryGraphService", "GetEdges"),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn delete_edge(
-            &mut self,
-            request: impl tonic::IntoRequest<super::DeleteEdgeRequest>,
-        ) -> std::result::Result<tonic::Response<()>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/DeleteEdge",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "DeleteEdge",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        /// Query Operations
-        pub async fn query(
-            &mut self,
-            request: impl tonic::IntoRequest<super::QueryRequest>,
-        ) -> std::result::Result<tonic::Response<super::QueryResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/Query",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("llm.memory.graph.v1.MemoryGraphService", "Query"),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn stream_query(
-            &mut self,
-            request: impl tonic::IntoRequest<super::QueryRequest>,
-        ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::Node>>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/StreamQuery",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "StreamQuery",
-                    ),
-                );
-            self.inner.server_streaming(req, path, codec).await
-        }
-        /// Prompt & Response Operations
-        pub async fn add_prompt(
-            &mut self,
-            request: impl tonic::IntoRequest<super::AddPromptRequest>,
-        ) -> std::result::Result<tonic::Response<super::PromptNode>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/AddPrompt",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "AddPrompt",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn add_response(
-            &mut self,
-            request: impl tonic::IntoRequest<super::AddResponseRequest>,
-        ) -> std::result::Result<tonic::Response<super::ResponseNode>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/AddResponse",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "AddResponse",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn add_tool_invocation(
-            &mut self,
-            request: impl tonic::IntoRequest<super::AddToolInvocationRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::ToolInvocationNode>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/AddToolInvocation",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "AddToolInvocation",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        /// Template Operations
-        pub async fn create_template(
-            &mut self,
-            request: impl tonic::IntoRequest<super::CreateTemplateRequest>,
-        ) -> std::result::Result<tonic::Response<super::TemplateNode>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/CreateTemplate",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "CreateTemplate",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn instantiate_template(
-            &mut self,
-            request: impl tonic::IntoRequest<super::InstantiateTemplateRequest>,
-        ) -> std::result::Result<tonic::Response<super::PromptNode>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/InstantiateTemplate",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "InstantiateTemplate",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        /// Streaming Operations
-        pub async fn stream_events(
-            &mut self,
-            request: impl tonic::IntoRequest<super::StreamEventsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::Event>>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/StreamEvents",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "StreamEvents",
-                    ),
-                );
-            self.inner.server_streaming(req, path, codec).await
-        }
-        pub async fn subscribe_to_session(
-            &mut self,
-            request: impl tonic::IntoRequest<super::SubscribeRequest>,
-        ) -> std::result::Result<
-            tonic::Response<tonic::codec::Streaming<super::SessionEvent>>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/SubscribeToSession",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "SubscribeToSession",
-                    ),
-                );
-            self.inner.server_streaming(req, path, codec).await
-        }
-        /// Health & Metrics
-        pub async fn health(
-            &mut self,
-            request: impl tonic::IntoRequest<()>,
-        ) -> std::result::Result<tonic::Response<super::HealthResponse>, tonic::Status> {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/Health",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new("llm.memory.graph.v1.MemoryGraphService", "Health"),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-        pub async fn get_metrics(
-            &mut self,
-            request: impl tonic::IntoRequest<()>,
-        ) -> std::result::Result<
-            tonic::Response<super::MetricsResponse>,
-            tonic::Status,
-        > {
-            self.inner
-                .ready()
-                .await
-                .map_err(|e| {
-                    tonic::Status::new(
-                        tonic::Code::Unknown,
-                        format!("Service was not ready: {}", e.into()),
-                    )
-                })?;
-            let codec = tonic::codec::ProstCodec::default();
-            let path = http::uri::PathAndQuery::from_static(
-                "/llm.memory.graph.v1.MemoryGraphService/GetMetrics",
-            );
-            let mut req = request.into_request();
-            req.extensions_mut()
-                .insert(
-                    GrpcMethod::new(
-                        "llm.memory.graph.v1.MemoryGraphService",
-                        "GetMetrics",
-                    ),
-                );
-            self.inner.unary(req, path, codec).await
-        }
-    }
-}
-/// Generated server implementations.
-pub mod memory_graph_service_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with MemoryGraphServiceServer.
-    #[async_trait]
-    pub trait MemoryGraphService: Send + Sync + 'static {
-        /// Session Management
-        async fn create_session(
-            &self,
-            request: tonic::Request<super::CreateSessionRequest>,
-        ) -> std::result::Result<tonic::Response<super::Session>, tonic::Status>;
-        async fn get_session(
-            &self,
-            request: tonic::Request<super::GetSessionRequest>,
-        ) -> std::result::Result<tonic::Response<super::Session>, tonic::Status>;
-        async fn delete_session(
-            &self,
-            request: tonic::Request<super::DeleteSessionRequest>,
-        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
-        async fn list_sessions(
-            &self,
-            request: tonic::Request<super::ListSessionsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::ListSessionsResponse>,
-            tonic::Status,
-        >;
-        /// Node Operations
-        async fn create_node(
-            &self,
-            request: tonic::Request<super::CreateNodeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Node>, tonic::Status>;
-        async fn get_node(
-            &self,
-            request: tonic::Request<super::GetNodeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Node>, tonic::Status>;
-        async fn update_node(
-            &self,
-            request: tonic::Request<super::UpdateNodeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Node>, tonic::Status>;
-        async fn delete_node(
-            &self,
-            request: tonic::Request<super::DeleteNodeRequest>,
-        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
-        async fn batch_create_nodes(
-            &self,
-            request: tonic::Request<super::BatchCreateNodesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::BatchCreateNodesResponse>,
-            tonic::Status,
-        >;
-        async fn batch_get_nodes(
-            &self,
-            request: tonic::Request<super::BatchGetNodesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::BatchGetNodesResponse>,
-            tonic::Status,
-        >;
-        /// Edge Operations
-        async fn create_edge(
-            &self,
-            request: tonic::Request<super::CreateEdgeRequest>,
-        ) -> std::result::Result<tonic::Response<super::Edge>, tonic::Status>;
-        async fn get_edges(
-            &self,
-            request: tonic::Request<super::GetEdgesRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::GetEdgesResponse>,
-            tonic::Status,
-        >;
-        async fn delete_edge(
-            &self,
-            request: tonic::Request<super::DeleteEdgeRequest>,
-        ) -> std::result::Result<tonic::Response<()>, tonic::Status>;
-        /// Query Operations
-        async fn query(
-            &self,
-            request: tonic::Request<super::QueryRequest>,
-        ) -> std::result::Result<tonic::Response<super::QueryResponse>, tonic::Status>;
-        /// Server streaming response type for the StreamQuery method.
-        type StreamQueryStream: tonic::codegen::tokio_stream::Stream<
-                Item = std::result::Result<super::Node, tonic::Status>,
-            >
-            + Send
-            + 'static;
-        async fn stream_query(
-            &self,
-            request: tonic::Request<super::QueryRequest>,
-        ) -> std::result::Result<
-            tonic::Response<Self::StreamQueryStream>,
-            tonic::Status,
-        >;
-        /// Prompt & Response Operations
-        async fn add_prompt(
-            &self,
-            request: tonic::Request<super::AddPromptRequest>,
-        ) -> std::result::Result<tonic::Response<super::PromptNode>, tonic::Status>;
-        async fn add_response(
-            &self,
-            request: tonic::Request<super::AddResponseRequest>,
-        ) -> std::result::Result<tonic::Response<super::ResponseNode>, tonic::Status>;
-        async fn add_tool_invocation(
-            &self,
-            request: tonic::Request<super::AddToolInvocationRequest>,
-        ) -> std::result::Result<
-            tonic::Response<super::ToolInvocationNode>,
-            tonic::Status,
-        >;
-        /// Template Operations
-        async fn create_template(
-            &self,
-            request: tonic::Request<super::CreateTemplateRequest>,
-        ) -> std::result::Result<tonic::Response<super::TemplateNode>, tonic::Status>;
-        async fn instantiate_template(
-            &self,
-            request: tonic::Request<super::InstantiateTemplateRequest>,
-        ) -> std::result::Result<tonic::Response<super::PromptNode>, tonic::Status>;
-        /// Server streaming response type for the StreamEvents method.
-        type StreamEventsStream: tonic::codegen::tokio_stream::Stream<
-                Item = std::result::Result<super::Event, tonic::Status>,
-            >
-            + Send
-            + 'static;
-        /// Streaming Operations
-        async fn stream_events(
-            &self,
-            request: tonic::Request<super::StreamEventsRequest>,
-        ) -> std::result::Result<
-            tonic::Response<Self::StreamEventsStream>,
-            tonic::Status,
-        >;
-        /// Server streaming response type for the SubscribeToSession method.
-        type SubscribeToSessionStream: tonic::codegen::tokio_stream::Stream<
-                Item = std::result::Result<super::SessionEvent, tonic::Status>,
-            >
-            + Send
-            + 'static;
-        async fn subscribe_to_session(
-            &self,
-            request: tonic::Request<super::SubscribeRequest>,
-        ) -> std::result::Result<
-            tonic::Response<Self::SubscribeToSessionStream>,
-            tonic::Status,
-        >;
-        /// Health & Metrics
-        async fn health(
-            &self,
-            request: tonic::Request<()>,
-        ) -> std::result::Result<tonic::Response<super::HealthResponse>, tonic::Status>;
-        async fn get_metrics(
-            &self,
-            request: tonic::Request<()>,
-        ) -> std::result::Result<tonic::Response<super::MetricsResponse>, tonic::Status>;
-    }
-    #[derive(Debug)]
-    pub struct MemoryGraphServiceServer<T: MemoryGraphService> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    struct _Inner<T>(Arc<T>);
-    impl<T: MemoryGraphService> MemoryGraphServiceServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
-        }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
-        }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
-        }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
-        }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
-        }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
-        }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
-        }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for MemoryGraphServiceServer<T>
-    where
-        T: MemoryGraphService,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
-            &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
-        }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/llm.memory.graph.v1.MemoryGraphService/CreateSession" => {
-                    #[allow(non_camel_case_types)]
-                    struct CreateSessionSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::CreateSessionRequest>
-                    for CreateSessionSvc<T> {
-                        type Response = super::Session;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::CreateSessionRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::create_session(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = CreateSessionSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/GetSession" => {
-                    #[allow(non_camel_case_types)]
-                    struct GetSessionSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::GetSessionRequest>
-                    for GetSessionSvc<T> {
-                        type Response = super::Session;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::GetSessionRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::get_session(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = GetSessionSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/DeleteSession" => {
-                    #[allow(non_camel_case_types)]
-                    struct DeleteSessionSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::DeleteSessionRequest>
-                    for DeleteSessionSvc<T> {
-                        type Response = ();
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::DeleteSessionRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::delete_session(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = DeleteSessionSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/ListSessions" => {
-                    #[allow(non_camel_case_types)]
-                    struct ListSessionsSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::ListSessionsRequest>
-                    for ListSessionsSvc<T> {
-                        type Response = super::ListSessionsResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::ListSessionsRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::list_sessions(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = ListSessionsSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/CreateNode" => {
-                    #[allow(non_camel_case_types)]
-                    struct CreateNodeSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::CreateNodeRequest>
-                    for CreateNodeSvc<T> {
-                        type Response = super::Node;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::CreateNodeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::create_node(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = CreateNodeSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/GetNode" => {
-                    #[allow(non_camel_case_types)]
-                    struct GetNodeSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::GetNodeRequest>
-                    for GetNodeSvc<T> {
-                        type Response = super::Node;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::GetNodeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::get_node(&inner, request).await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = GetNodeSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/UpdateNode" => {
-                    #[allow(non_camel_case_types)]
-                    struct UpdateNodeSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::UpdateNodeRequest>
-                    for UpdateNodeSvc<T> {
-                        type Response = super::Node;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::UpdateNodeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::update_node(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = UpdateNodeSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/DeleteNode" => {
-                    #[allow(non_camel_case_types)]
-                    struct DeleteNodeSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::DeleteNodeRequest>
-                    for DeleteNodeSvc<T> {
-                        type Response = ();
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::DeleteNodeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::delete_node(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = DeleteNodeSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/BatchCreateNodes" => {
-                    #[allow(non_camel_case_types)]
-                    struct BatchCreateNodesSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::BatchCreateNodesRequest>
-                    for BatchCreateNodesSvc<T> {
-                        type Response = super::BatchCreateNodesResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::BatchCreateNodesRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::batch_create_nodes(
-                                        &inner,
-                                        request,
-                                    )
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = BatchCreateNodesSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/BatchGetNodes" => {
-                    #[allow(non_camel_case_types)]
-                    struct BatchGetNodesSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::BatchGetNodesRequest>
-                    for BatchGetNodesSvc<T> {
-                        type Response = super::BatchGetNodesResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::BatchGetNodesRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::batch_get_nodes(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = BatchGetNodesSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/CreateEdge" => {
-                    #[allow(non_camel_case_types)]
-                    struct CreateEdgeSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::CreateEdgeRequest>
-                    for CreateEdgeSvc<T> {
-                        type Response = super::Edge;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::CreateEdgeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::create_edge(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = CreateEdgeSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/GetEdges" => {
-                    #[allow(non_camel_case_types)]
-                    struct GetEdgesSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::GetEdgesRequest>
-                    for GetEdgesSvc<T> {
-                        type Response = super::GetEdgesResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::GetEdgesRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::get_edges(&inner, request).await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = GetEdgesSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/DeleteEdge" => {
-                    #[allow(non_camel_case_types)]
-                    struct DeleteEdgeSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::DeleteEdgeRequest>
-                    for DeleteEdgeSvc<T> {
-                        type Response = ();
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::DeleteEdgeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::delete_edge(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = DeleteEdgeSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/Query" => {
-                    #[allow(non_camel_case_types)]
-                    struct QuerySvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::QueryRequest> for QuerySvc<T> {
-                        type Response = super::QueryResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::QueryRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::query(&inner, request).await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = QuerySvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/StreamQuery" => {
-                    #[allow(non_camel_case_types)]
-                    struct StreamQuerySvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::ServerStreamingService<super::QueryRequest>
-                    for StreamQuerySvc<T> {
-                        type Response = super::Node;
-                        type ResponseStream = T::StreamQueryStream;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::ResponseStream>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::QueryRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::stream_query(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = StreamQuerySvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.server_streaming(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/AddPrompt" => {
-                    #[allow(non_camel_case_types)]
-                    struct AddPromptSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::AddPromptRequest>
-                    for AddPromptSvc<T> {
-                        type Response = super::PromptNode;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::AddPromptRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::add_prompt(&inner, request).await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = AddPromptSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/AddResponse" => {
-                    #[allow(non_camel_case_types)]
-                    struct AddResponseSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::AddResponseRequest>
-                    for AddResponseSvc<T> {
-                        type Response = super::ResponseNode;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::AddResponseRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::add_response(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = AddResponseSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/AddToolInvocation" => {
-                    #[allow(non_camel_case_types)]
-                    struct AddToolInvocationSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::AddToolInvocationRequest>
-                    for AddToolInvocationSvc<T> {
-                        type Response = super::ToolInvocationNode;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::AddToolInvocationRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::add_tool_invocation(
-                                        &inner,
-                                        request,
-                                    )
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = AddToolInvocationSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/CreateTemplate" => {
-                    #[allow(non_camel_case_types)]
-                    struct CreateTemplateSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::CreateTemplateRequest>
-                    for CreateTemplateSvc<T> {
-                        type Response = super::TemplateNode;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::CreateTemplateRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::create_template(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = CreateTemplateSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/InstantiateTemplate" => {
-                    #[allow(non_camel_case_types)]
-                    struct InstantiateTemplateSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::UnaryService<super::InstantiateTemplateRequest>
-                    for InstantiateTemplateSvc<T> {
-                        type Response = super::PromptNode;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::InstantiateTemplateRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::instantiate_template(
-                                        &inner,
-                                        request,
-                                    )
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = InstantiateTemplateSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/StreamEvents" => {
-                    #[allow(non_camel_case_types)]
-                    struct StreamEventsSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::ServerStreamingService<super::StreamEventsRequest>
-                    for StreamEventsSvc<T> {
-                        type Response = super::Event;
-                        type ResponseStream = T::StreamEventsStream;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::ResponseStream>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::StreamEventsRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::stream_events(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = StreamEventsSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.server_streaming(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/SubscribeToSession" => {
-                    #[allow(non_camel_case_types)]
-                    struct SubscribeToSessionSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<
-                        T: MemoryGraphService,
-                    > tonic::server::ServerStreamingService<super::SubscribeRequest>
-                    for SubscribeToSessionSvc<T> {
-                        type Response = super::SessionEvent;
-                        type ResponseStream = T::SubscribeToSessionStream;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::ResponseStream>,
-                            tonic::Status,
-                        >;
-                        fn call(
-                            &mut self,
-                            request: tonic::Request<super::SubscribeRequest>,
-                        ) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::subscribe_to_session(
-                                        &inner,
-                                        request,
-                                    )
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = SubscribeToSessionSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.server_streaming(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/Health" => {
-                    #[allow(non_camel_case_types)]
-                    struct HealthSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<T: MemoryGraphService> tonic::server::UnaryService<()>
-                    for HealthSvc<T> {
-                        type Response = super::HealthResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::health(&inner, request).await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = HealthSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                "/llm.memory.graph.v1.MemoryGraphService/GetMetrics" => {
-                    #[allow(non_camel_case_types)]
-                    struct GetMetricsSvc<T: MemoryGraphService>(pub Arc<T>);
-                    impl<T: MemoryGraphService> tonic::server::UnaryService<()>
-                    for GetMetricsSvc<T> {
-                        type Response = super::MetricsResponse;
-                        type Future = BoxFuture<
-                            tonic::Response<Self::Response>,
-                            tonic::Status,
-                        >;
-                        fn call(&mut self, request: tonic::Request<()>) -> Self::Future {
-                            let inner = Arc::clone(&self.0);
-                            let fut = async move {
-                                <T as MemoryGraphService>::get_metrics(&inner, request)
-                                    .await
-                            };
-                            Box::pin(fut)
-                        }
-                    }
-                    let accept_compression_encodings = self.accept_compression_encodings;
-                    let send_compression_encodings = self.send_compression_encodings;
-                    let max_decoding_message_size = self.max_decoding_message_size;
-                    let max_encoding_message_size = self.max_encoding_message_size;
-                    let inner = self.inner.clone();
-                    let fut = async move {
-                        let inner = inner.0;
-                        let method = GetMetricsSvc(inner);
-                        let codec = tonic::codec::ProstCodec::default();
-                        let mut grpc = tonic::server::Grpc::new(codec)
-                            .apply_compression_config(
-                                accept_compression_encodings,
-                                send_compression_encodings,
-                            )
-                            .apply_max_message_size_config(
-                                max_decoding_message_size,
-                                max_encoding_message_size,
-                            );
-                        let res = grpc.unary(method, req).await;
-                        Ok(res)
-                    };
-                    Box::pin(fut)
-                }
-                _ => {
-                    Box::pin(async move {
-                        Ok(
-                            http::Response::builder()
-                                .status(200)
-                                .header("grpc-status", "12")
-                                .header("content-type", "application/grpc")
-                                .body(empty_body())
-                                .unwrap(),
-                        )
-                    })
-                }
-            }
-        }
-    }
-    impl<T: MemoryGraphService> Clone for MemoryGraphServiceServer<T> {
-        fn clone(&self) -> Self {
-            let inner = self.inner.clone();
-            Self {
-                inner,
-                accept_compression_encodings: self.accept_compression_encodings,
-                send_compression_encodings: self.send_compression_encodings,
-                max_decoding_message_size: self.max_decoding_message_size,
-                max_encoding_message_size: self.max_encoding_message_size,
-            }
-        }
-    }
-    impl<T: MemoryGraphService> Clone for _Inner<T> {
-        fn clone(&self) -> Self {
-            Self(Arc::clone(&self.0))
-        }
-    }
-    impl<T: std::fmt::Debug> std::fmt::Debug for _Inner<T> {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "{:?}", self.0)
-        }
-    }
-    impl<T: MemoryGraphService> tonic::server::NamedService
-    for MemoryGraphServiceServer<T> {
-        const NAME: &'static str = "llm.memory.graph.v1.MemoryGraphService";
-    }
-}
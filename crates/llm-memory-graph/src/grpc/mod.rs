@@ -43,11 +43,15 @@
 //! }
 //! ```
 
+// Every RPC must return `tonic::Status` as its error type
+#![allow(clippy::result_large_err)]
+
 // Include generated protobuf code
-#[allow(clippy::all)]
+#[allow(clippy::all, clippy::pedantic)]
 #[allow(missing_docs)]
-#[path = "llm.memory.graph.v1.rs"]
-pub mod proto;
+pub mod proto {
+    tonic::include_proto!("llm.memory.graph.v1");
+}
 
 pub mod converters;
 pub mod handlers;
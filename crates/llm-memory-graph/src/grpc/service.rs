@@ -4,15 +4,16 @@
 //! It provides all CRUD operations, query interfaces, and streaming endpoints.
 
 use crate::engine::AsyncMemoryGraph;
+#[allow(clippy::wildcard_imports)]
 use crate::grpc::converters::*;
 use crate::grpc::proto::memory_graph_service_server::MemoryGraphService;
+#[allow(clippy::wildcard_imports)]
 use crate::grpc::proto::*;
 use crate::observatory::prometheus::PrometheusMetrics;
 use std::sync::Arc;
 use std::time::Instant as StdInstant;
-use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
-use tracing::{error, info, instrument, warn};
+use tracing::{info, instrument, warn};
 
 /// Service configuration
 #[derive(Debug, Clone)]
@@ -74,11 +75,8 @@ impl MemoryGraphServiceImpl {
     /// Record gRPC request metrics
     fn record_request(&self, method: &str, latency_secs: f64, success: bool) {
         if let Some(metrics) = &self.metrics {
-            // Record in existing Prometheus metrics
-            if success {
-                metrics.record_query_duration(latency_secs);
-            }
-            // Additional gRPC-specific metrics would go here
+            metrics.record_grpc_request(method, if success { "success" } else { "error" });
+            metrics.record_grpc_request_duration(method, latency_secs);
             tracing::debug!(
                 method = method,
                 latency_ms = latency_secs * 1000.0,
@@ -91,6 +89,10 @@ impl MemoryGraphServiceImpl {
 
 #[tonic::async_trait]
 impl MemoryGraphService for MemoryGraphServiceImpl {
+    type StreamQueryStream = crate::grpc::streaming::StreamQueryStream;
+    type StreamEventsStream = crate::grpc::streaming::StreamEventsStream;
+    type SubscribeToSessionStream = crate::grpc::streaming::SubscribeToSessionStream;
+
     // ========================================================================
     // Session Management
     // ========================================================================
@@ -126,7 +128,7 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         let start = StdInstant::now();
         let req = request.into_inner();
 
-        let session_id = parse_session_id(&req.session_id)?;
+        let session_id = parse_session_id(&req.session_id).map_err(error_to_status)?;
         let session = self.graph.get_session(session_id).await.map_err(error_to_status)?;
 
         let proto_session = session_to_proto(session);
@@ -153,12 +155,39 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         &self,
         request: Request<ListSessionsRequest>,
     ) -> Result<Response<ListSessionsResponse>, Status> {
-        let _start = StdInstant::now();
-        let _req = request.into_inner();
+        let start = StdInstant::now();
+        let req = request.into_inner();
 
-        // TODO: Implement session listing with pagination
-        warn!("list_sessions not yet implemented");
-        Err(Status::unimplemented("Session listing not yet implemented"))
+        let sessions = || {
+            self.graph
+                .query()
+                .filter(crate::query::Predicate::node_type(crate::NodeType::Session))
+        };
+        let total_count = sessions().count().await.map_err(error_to_status)?;
+
+        let mut builder = sessions().offset(req.offset.max(0) as usize);
+        if req.limit > 0 {
+            builder = builder.limit(req.limit as usize);
+        }
+        if let Some(cursor) = page_token_to_cursor(&req.page_token).map_err(error_to_status)? {
+            builder = builder.cursor(cursor);
+        }
+        let page = builder.execute_page().await.map_err(error_to_status)?;
+
+        self.record_request("list_sessions", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(ListSessionsResponse {
+            sessions: page
+                .items
+                .into_iter()
+                .filter_map(|node| match node {
+                    crate::Node::Session(session) => Some(session_to_proto(session)),
+                    _ => None,
+                })
+                .collect(),
+            total_count: i64::try_from(total_count).unwrap_or(i64::MAX),
+            next_page_token: cursor_to_page_token(page.next_cursor),
+        }))
     }
 
     // ========================================================================
@@ -170,12 +199,36 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         &self,
         request: Request<CreateNodeRequest>,
     ) -> Result<Response<Node>, Status> {
-        let _start = StdInstant::now();
-        let _req = request.into_inner();
+        let start = StdInstant::now();
+        let req = request.into_inner();
 
-        // TODO: Implement generic node creation
-        warn!("create_node not yet implemented");
-        Err(Status::unimplemented("Generic node creation not yet implemented"))
+        // Built-in node types have dedicated RPCs; this path creates custom nodes
+        let custom = match req.node.and_then(|node| node.node_data) {
+            Some(node::NodeData::Custom(custom)) => {
+                proto_to_custom_node(custom).map_err(error_to_status)?
+            }
+            Some(_) => {
+                return Err(Status::invalid_argument(
+                    "CreateNode only accepts custom nodes; use the type-specific RPCs for built-in types",
+                ))
+            }
+            None => return Err(Status::invalid_argument("Node data is required")),
+        };
+
+        let node_id = self
+            .graph
+            .add_custom_node(custom)
+            .await
+            .map_err(error_to_status)?;
+        let node = self.graph
+            .get_node(&node_id)
+            .await
+            .map_err(error_to_status)?
+            .ok_or_else(|| Status::internal(format!("Node {} missing after creation", node_id)))?;
+
+        self.record_request("create_node", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(node_to_proto(node)))
     }
 
     #[instrument(skip(self))]
@@ -186,7 +239,7 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         let start = StdInstant::now();
         let req = request.into_inner();
 
-        let node_id = parse_node_id(&req.node_id)?;
+        let node_id = parse_node_id(&req.node_id).map_err(error_to_status)?;
         let node = self.graph
             .get_node(&node_id)
             .await
@@ -251,7 +304,7 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
             .iter()
             .map(|id| parse_node_id(id))
             .collect();
-        let node_ids = node_ids?;
+        let node_ids = node_ids.map_err(error_to_status)?;
 
         let nodes = self.graph
             .get_nodes_batch(node_ids)
@@ -279,12 +332,23 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         &self,
         request: Request<CreateEdgeRequest>,
     ) -> Result<Response<Edge>, Status> {
-        let _start = StdInstant::now();
-        let _req = request.into_inner();
+        let start = StdInstant::now();
+        let req = request.into_inner();
 
-        // TODO: Implement edge creation
-        warn!("create_edge not yet implemented");
-        Err(Status::unimplemented("Edge creation not yet implemented"))
+        let edge = req
+            .edge
+            .ok_or_else(|| Status::invalid_argument("Edge is required"))?;
+        let edge = proto_to_edge(edge).map_err(error_to_status)?;
+
+        // Custom edges are checked against their registered kind
+        self.graph
+            .store_edges_batch(vec![edge.clone()])
+            .await
+            .map_err(error_to_status)?;
+
+        self.record_request("create_edge", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(edge_to_proto(edge)))
     }
 
     #[instrument(skip(self))]
@@ -295,17 +359,17 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         let start = StdInstant::now();
         let req = request.into_inner();
 
-        let node_id = parse_node_id(&req.node_id)?;
+        let node_id = parse_node_id(&req.node_id).map_err(error_to_status)?;
 
         // Get outgoing edges by default, or as specified
         let edges = match req.direction {
-            Some(dir) if dir == EdgeDirection::EdgeDirectionIncoming as i32 => {
+            Some(dir) if dir == EdgeDirection::Incoming as i32 => {
                 self.graph.get_incoming_edges(&node_id).await
             }
-            Some(dir) if dir == EdgeDirection::EdgeDirectionOutgoing as i32 => {
+            Some(dir) if dir == EdgeDirection::Outgoing as i32 => {
                 self.graph.get_outgoing_edges(&node_id).await
             }
-            Some(dir) if dir == EdgeDirection::EdgeDirectionBoth as i32 => {
+            Some(dir) if dir == EdgeDirection::Both as i32 => {
                 // Get both incoming and outgoing
                 let mut outgoing = self.graph.get_outgoing_edges(&node_id).await.map_err(error_to_status)?;
                 let mut incoming = self.graph.get_incoming_edges(&node_id).await.map_err(error_to_status)?;
@@ -316,7 +380,16 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         }
         .map_err(error_to_status)?;
 
-        let proto_edges: Vec<Edge> = edges.into_iter().map(edge_to_proto).collect();
+        let edge_type = req
+            .r#type
+            .map(|t| proto_to_edge_type(t, req.custom_type.as_deref().unwrap_or_default()))
+            .transpose()
+            .map_err(error_to_status)?;
+        let proto_edges: Vec<Edge> = edges
+            .into_iter()
+            .filter(|edge| edge_type.as_ref().is_none_or(|t| edge.edge_type == *t))
+            .map(edge_to_proto)
+            .collect();
         self.record_request("get_edges", start.elapsed().as_secs_f64(), true);
 
         Ok(Response::new(GetEdgesResponse { edges: proto_edges }))
@@ -344,12 +417,33 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         &self,
         request: Request<QueryRequest>,
     ) -> Result<Response<QueryResponse>, Status> {
-        let _start = StdInstant::now();
-        let _req = request.into_inner();
+        let start = StdInstant::now();
+        let req = request.into_inner();
 
-        // TODO: Implement query operation
-        warn!("query not yet implemented");
-        Err(Status::unimplemented("Query operation not yet implemented"))
+        let total_count = apply_query_filters(self.graph.query(), &req)
+            .map_err(error_to_status)?
+            .count()
+            .await
+            .map_err(error_to_status)?;
+
+        let mut builder = apply_query_filters(self.graph.query(), &req)
+            .map_err(error_to_status)?
+            .offset(req.offset.max(0) as usize);
+        if req.limit > 0 {
+            builder = builder.limit(req.limit as usize);
+        }
+        if let Some(cursor) = page_token_to_cursor(&req.page_token).map_err(error_to_status)? {
+            builder = builder.cursor(cursor);
+        }
+        let page = builder.execute_page().await.map_err(error_to_status)?;
+
+        self.record_request("query", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(QueryResponse {
+            nodes: page.items.into_iter().map(node_to_proto).collect(),
+            total_count: i64::try_from(total_count).unwrap_or(i64::MAX),
+            next_page_token: cursor_to_page_token(page.next_cursor),
+        }))
     }
 
     #[instrument(skip(self))]
@@ -365,6 +459,97 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         Err(Status::unimplemented("Stream query not yet implemented"))
     }
 
+    #[instrument(skip(self))]
+    async fn execute_query(
+        &self,
+        request: Request<ExpressionQueryRequest>,
+    ) -> Result<Response<ExpressionQueryResponse>, Status> {
+        let start = StdInstant::now();
+        let req = request.into_inner();
+
+        let result = self
+            .graph
+            .execute_query(&req.expression)
+            .await
+            .map_err(error_to_status)?;
+
+        self.record_request("execute_query", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(query_result_to_proto(result)))
+    }
+
+    #[instrument(skip(self))]
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> Result<Response<SearchResponse>, Status> {
+        let start = StdInstant::now();
+        let req = request.into_inner();
+
+        let query = proto_to_text_query(req).map_err(error_to_status)?;
+        let hits = self
+            .graph
+            .search_text(&query)
+            .await
+            .map_err(error_to_status)?;
+
+        self.record_request("search", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(SearchResponse {
+            hits: hits.into_iter().map(search_hit_to_proto).collect(),
+        }))
+    }
+
+    #[instrument(skip(self))]
+    async fn aggregate(
+        &self,
+        request: Request<AggregateRequest>,
+    ) -> Result<Response<AggregateResponse>, Status> {
+        let start = StdInstant::now();
+        let req = request.into_inner();
+
+        let aggregation = proto_to_aggregation(&req).map_err(error_to_status)?;
+        let query = req.query.unwrap_or_default();
+        let rows = apply_query_filters(self.graph.query(), &query)
+            .map_err(error_to_status)?
+            .aggregate(&aggregation)
+            .await
+            .map_err(error_to_status)?;
+
+        self.record_request("aggregate", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(aggregate_rows_to_proto(&aggregation, rows)))
+    }
+
+    #[instrument(skip(self))]
+    async fn lookup(
+        &self,
+        request: Request<LookupRequest>,
+    ) -> Result<Response<LookupResponse>, Status> {
+        let start = StdInstant::now();
+        let req = request.into_inner();
+
+        let lookup = proto_to_lookup(req.key).map_err(error_to_status)?;
+        let after = page_token_to_cursor(&req.page_token).map_err(error_to_status)?;
+        let limit = if req.limit > 0 {
+            req.limit as usize
+        } else {
+            100
+        };
+        let page = self
+            .graph
+            .lookup_nodes(&lookup, after.as_ref(), limit)
+            .await
+            .map_err(error_to_status)?;
+
+        self.record_request("lookup", start.elapsed().as_secs_f64(), true);
+
+        Ok(Response::new(LookupResponse {
+            nodes: page.items.into_iter().map(node_to_proto).collect(),
+            next_page_token: cursor_to_page_token(page.next_cursor),
+        }))
+    }
+
     // ========================================================================
     // Prompt & Response Operations
     // ========================================================================
@@ -377,13 +562,19 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         let start = StdInstant::now();
         let req = request.into_inner();
 
-        let session_id = parse_session_id(&req.session_id)?;
+        let session_id = parse_session_id(&req.session_id).map_err(error_to_status)?;
         let metadata = req.metadata.map(proto_to_prompt_metadata);
 
         let prompt_id = self.graph
             .add_prompt(session_id, req.content, metadata)
             .await
             .map_err(error_to_status)?;
+        for attachment in req.attachments {
+            self.graph
+                .add_attachment(prompt_id, proto_to_attachment(attachment))
+                .await
+                .map_err(error_to_status)?;
+        }
 
         // Retrieve the created prompt
         let node = self.graph
@@ -409,16 +600,22 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         let start = StdInstant::now();
         let req = request.into_inner();
 
-        let prompt_id = parse_node_id(&req.prompt_id)?;
+        let prompt_id = parse_node_id(&req.prompt_id).map_err(error_to_status)?;
         let token_usage = req.token_usage
             .ok_or_else(|| Status::invalid_argument("Missing token_usage"))?;
-        let token_usage = proto_to_token_usage(token_usage);
+        let token_usage = proto_to_token_usage(token_usage).map_err(error_to_status)?;
         let metadata = req.metadata.map(proto_to_response_metadata);
 
         let response_id = self.graph
             .add_response(prompt_id, req.content, token_usage, metadata)
             .await
             .map_err(error_to_status)?;
+        for attachment in req.attachments {
+            self.graph
+                .add_attachment(response_id, proto_to_attachment(attachment))
+                .await
+                .map_err(error_to_status)?;
+        }
 
         // Retrieve the created response
         let node = self.graph
@@ -521,7 +718,8 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         Ok(Response::new(HealthResponse {
             status: health_response::ServingStatus::Serving as i32,
             version: env!("CARGO_PKG_VERSION").to_string(),
-            uptime_seconds: self.config.start_time.elapsed().as_secs() as i64,
+            uptime_seconds: i64::try_from(self.config.start_time.elapsed().as_secs())
+                .unwrap_or(i64::MAX),
         }))
     }
 
@@ -532,22 +730,18 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
     ) -> Result<Response<MetricsResponse>, Status> {
         let stats = self.graph.stats().await.map_err(error_to_status)?;
 
-        // Get Prometheus metrics if available
-        let (active_sessions, avg_write_latency_ms, avg_read_latency_ms) =
-            if let Some(graph_metrics) = self.graph.get_metrics() {
-                (
-                    graph_metrics.sessions_created as i64,
-                    graph_metrics.avg_write_latency_ms,
-                    graph_metrics.avg_read_latency_ms,
-                )
-            } else {
-                (0, 0.0, 0.0)
-            };
+        // Sessions are never closed, so every stored session is active
+        let active_sessions = i64::try_from(stats.session_count).unwrap_or(i64::MAX);
+        let (avg_write_latency_ms, avg_read_latency_ms) = self
+            .graph
+            .get_metrics()
+            .map(|metrics| (metrics.avg_write_latency_ms, metrics.avg_read_latency_ms))
+            .unwrap_or((0.0, 0.0));
 
         Ok(Response::new(MetricsResponse {
-            total_nodes: stats.node_count as i64,
-            total_edges: stats.edge_count as i64,
-            total_sessions: stats.session_count as i64,
+            total_nodes: i64::try_from(stats.node_count).unwrap_or(i64::MAX),
+            total_edges: i64::try_from(stats.edge_count).unwrap_or(i64::MAX),
+            total_sessions: i64::try_from(stats.session_count).unwrap_or(i64::MAX),
             active_sessions,
             avg_write_latency_ms,
             avg_read_latency_ms,
@@ -555,3 +749,459 @@ impl MemoryGraphService for MemoryGraphServiceImpl {
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::custom::{EdgeKind, EndpointKind, NodeKind};
+    use crate::grpc::proto;
+    use crate::grpc::proto::memory_graph_service_client::MemoryGraphServiceClient;
+    use crate::grpc::proto::memory_graph_service_server::MemoryGraphServiceServer;
+    use crate::Config;
+    use std::collections::HashMap;
+    use tempfile::{tempdir, TempDir};
+    use tonic::transport::{Channel, Server};
+
+    /// Serve a fresh graph on an ephemeral port and connect a client to it
+    async fn serve() -> (
+        MemoryGraphServiceClient<Channel>,
+        Arc<AsyncMemoryGraph>,
+        TempDir,
+    ) {
+        let dir = tempdir().unwrap();
+        let graph = Arc::new(
+            AsyncMemoryGraph::open(Config::new(dir.path()))
+                .await
+                .unwrap(),
+        );
+        let service =
+            MemoryGraphServiceImpl::new(Arc::clone(&graph), None, ServiceConfig::default());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = futures::stream::unfold(listener, |listener| async move {
+            let accepted = listener.accept().await.map(|(stream, _)| stream);
+            Some((accepted, listener))
+        });
+        tokio::spawn(
+            Server::builder()
+                .add_service(MemoryGraphServiceServer::new(service))
+                .serve_with_incoming(incoming),
+        );
+
+        let client = MemoryGraphServiceClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap();
+        (client, graph, dir)
+    }
+
+    /// Add a prompt recorded against `model` and its response over the wire
+    async fn add_exchange(
+        client: &mut MemoryGraphServiceClient<Channel>,
+        session_id: &str,
+        content: &str,
+        model: &str,
+    ) -> (PromptNode, ResponseNode) {
+        let prompt = client
+            .add_prompt(AddPromptRequest {
+                session_id: session_id.to_string(),
+                content: content.to_string(),
+                metadata: Some(PromptMetadata {
+                    model: model.to_string(),
+                    ..PromptMetadata::default()
+                }),
+                attachments: Vec::new(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let response = client
+            .add_response(AddResponseRequest {
+                prompt_id: prompt.id.clone(),
+                content: format!("Answer to {content}"),
+                token_usage: Some(TokenUsage {
+                    prompt_tokens: 10,
+                    completion_tokens: 20,
+                    total_tokens: 30,
+                }),
+                metadata: Some(ResponseMetadata {
+                    model: model.to_string(),
+                    ..ResponseMetadata::default()
+                }),
+                attachments: Vec::new(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        (prompt, response)
+    }
+
+    async fn create_session(client: &mut MemoryGraphServiceClient<Channel>) -> Session {
+        client
+            .create_session(CreateSessionRequest::default())
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_round_trip() {
+        let (mut client, _graph, _dir) = serve().await;
+        let session = create_session(&mut client).await;
+        add_exchange(&mut client, &session.id, "What is Rust?", "gpt-4").await;
+
+        let result = client
+            .execute_query(ExpressionQueryRequest {
+                expression: "MATCH (r:Response)-[:RESPONDS_TO]->(p:Prompt) \
+                             RETURN p.content, r.usage.total_tokens AS tokens"
+                    .to_string(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(result.columns, vec!["p.content", "tokens"]);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].values, vec!["\"What is Rust?\"", "30"]);
+
+        let status = client
+            .execute_query(ExpressionQueryRequest {
+                expression: "MATCH (p:Nope) RETURN p".to_string(),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_search_round_trip() {
+        let (mut client, _graph, _dir) = serve().await;
+        let session = create_session(&mut client).await;
+        let (prompt, _) =
+            add_exchange(&mut client, &session.id, "Explain borrow checking", "gpt-4").await;
+        add_exchange(&mut client, &session.id, "Bake some bread", "gpt-4").await;
+
+        let hits = client
+            .search(SearchRequest {
+                query: "borrow".to_string(),
+                node_types: vec![proto::NodeType::Prompt as i32],
+                ..SearchRequest::default()
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .hits;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].node_id, prompt.id);
+        assert_eq!(hits[0].session_id.as_deref(), Some(session.id.as_str()));
+        assert!(!hits[0].highlights.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_and_list_sessions_page_tokens() {
+        let (mut client, _graph, _dir) = serve().await;
+        let session = create_session(&mut client).await;
+        create_session(&mut client).await;
+        for i in 0..3 {
+            add_exchange(&mut client, &session.id, &format!("Prompt {i}"), "gpt-4").await;
+        }
+
+        let mut request = QueryRequest {
+            session_id: Some(session.id.clone()),
+            node_type: Some(proto::NodeType::Prompt as i32),
+            limit: 2,
+            ..QueryRequest::default()
+        };
+        let first = client.query(request.clone()).await.unwrap().into_inner();
+        assert_eq!(first.nodes.len(), 2);
+        assert_eq!(first.total_count, 3);
+        assert!(!first.next_page_token.is_empty());
+
+        request.page_token = first.next_page_token;
+        let second = client.query(request).await.unwrap().into_inner();
+        assert_eq!(second.nodes.len(), 1);
+        assert!(second.next_page_token.is_empty());
+        assert!(first.nodes.iter().all(|n| n.id != second.nodes[0].id));
+
+        let sessions = client
+            .list_sessions(ListSessionsRequest {
+                limit: 1,
+                ..ListSessionsRequest::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(sessions.sessions.len(), 1);
+        assert_eq!(sessions.total_count, 2);
+        let rest = client
+            .list_sessions(ListSessionsRequest {
+                limit: 1,
+                page_token: sessions.next_page_token,
+                ..ListSessionsRequest::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(rest.sessions.len(), 1);
+        assert_ne!(rest.sessions[0].id, sessions.sessions[0].id);
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_round_trip() {
+        let (mut client, _graph, _dir) = serve().await;
+        let session = create_session(&mut client).await;
+        add_exchange(&mut client, &session.id, "One", "gpt-4").await;
+        add_exchange(&mut client, &session.id, "Two", "gpt-4").await;
+        add_exchange(&mut client, &session.id, "Three", "claude").await;
+
+        let response = client
+            .aggregate(AggregateRequest {
+                query: Some(QueryRequest {
+                    node_type: Some(proto::NodeType::Response as i32),
+                    ..QueryRequest::default()
+                }),
+                group_by: vec!["model".to_string()],
+                aggregates: vec!["count".to_string(), "sum(total_tokens)".to_string()],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.columns, vec!["count", "sum(total_tokens)"]);
+
+        let rows: HashMap<String, (u64, Option<f64>)> = response
+            .rows
+            .into_iter()
+            .map(|row| (row.key[0].clone(), (row.count, row.values[1].value)))
+            .collect();
+        assert_eq!(rows["gpt-4"], (2, Some(60.0)));
+        assert_eq!(rows["claude"], (1, Some(30.0)));
+
+        let status = client
+            .aggregate(AggregateRequest {
+                group_by: vec!["colour".to_string()],
+                ..AggregateRequest::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_lookup_round_trip() {
+        let (mut client, _graph, _dir) = serve().await;
+        let session = create_session(&mut client).await;
+        let (first, _) = add_exchange(&mut client, &session.id, "One", "gpt-4").await;
+        let (second, _) = add_exchange(&mut client, &session.id, "Two", "gpt-4").await;
+        add_exchange(&mut client, &session.id, "Three", "claude").await;
+
+        let page = client
+            .lookup(LookupRequest {
+                key: Some(lookup_request::Key::Model("gpt-4".to_string())),
+                limit: 3,
+                page_token: String::new(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(page.nodes.len(), 3);
+        let next = client
+            .lookup(LookupRequest {
+                key: Some(lookup_request::Key::Model("gpt-4".to_string())),
+                limit: 3,
+                page_token: page.next_page_token,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(next.nodes.len(), 1);
+        assert!(next.next_page_token.is_empty());
+
+        let ids: Vec<String> = page
+            .nodes
+            .into_iter()
+            .chain(next.nodes)
+            .map(|n| n.id)
+            .collect();
+        assert!(ids.contains(&first.id) && ids.contains(&second.id));
+
+        let status = client.lookup(LookupRequest::default()).await.unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_custom_node_round_trip() {
+        let (mut client, graph, _dir) = serve().await;
+        graph
+            .register_node_kind(NodeKind::new("ticket").schema(serde_json::json!({
+                "type": "object",
+                "required": ["title"]
+            })))
+            .await
+            .unwrap();
+
+        let custom = CustomNode {
+            kind: "ticket".to_string(),
+            payload: r#"{"title":"Broken login"}"#.to_string(),
+            metadata: HashMap::from([("team".to_string(), "auth".to_string())]),
+            ..CustomNode::default()
+        };
+        let created = client
+            .create_node(CreateNodeRequest {
+                node: Some(Node {
+                    node_data: Some(node::NodeData::Custom(custom.clone())),
+                    ..Node::default()
+                }),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(created.r#type, proto::NodeType::Custom as i32);
+
+        let fetched = client
+            .get_node(GetNodeRequest {
+                node_id: created.id.clone(),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let Some(node::NodeData::Custom(stored)) = fetched.node_data else {
+            panic!("expected a custom node");
+        };
+        assert_eq!(stored.kind, "ticket");
+        assert_eq!(stored.metadata["team"], "auth");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&stored.payload).unwrap(),
+            serde_json::json!({"title": "Broken login"})
+        );
+
+        let invalid = CustomNode {
+            payload: "{}".to_string(),
+            ..custom
+        };
+        let status = client
+            .create_node(CreateNodeRequest {
+                node: Some(Node {
+                    node_data: Some(node::NodeData::Custom(invalid)),
+                    ..Node::default()
+                }),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_custom_edge_round_trip() {
+        let (mut client, graph, _dir) = serve().await;
+        graph
+            .register_edge_kind(
+                EdgeKind::new("cites")
+                    .source(EndpointKind::Type(crate::NodeType::Response))
+                    .target(EndpointKind::Type(crate::NodeType::Prompt)),
+            )
+            .await
+            .unwrap();
+        let session = create_session(&mut client).await;
+        let (prompt, response) = add_exchange(&mut client, &session.id, "Cite me", "gpt-4").await;
+
+        let created = client
+            .create_edge(CreateEdgeRequest {
+                edge: Some(Edge {
+                    from_node_id: response.id.clone(),
+                    to_node_id: prompt.id.clone(),
+                    r#type: proto::EdgeType::Custom as i32,
+                    custom_type: "cites".to_string(),
+                    ..Edge::default()
+                }),
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert!(!created.id.is_empty());
+
+        let edges = client
+            .get_edges(GetEdgesRequest {
+                node_id: response.id.clone(),
+                direction: Some(EdgeDirection::Outgoing as i32),
+                r#type: Some(proto::EdgeType::Custom as i32),
+                custom_type: Some("cites".to_string()),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .edges;
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].id, created.id);
+        assert_eq!(edges[0].custom_type, "cites");
+        assert_eq!(edges[0].to_node_id, prompt.id);
+
+        // Reversed endpoints violate the registered kind
+        let status = client
+            .create_edge(CreateEdgeRequest {
+                edge: Some(Edge {
+                    from_node_id: prompt.id,
+                    to_node_id: response.id,
+                    r#type: proto::EdgeType::Custom as i32,
+                    custom_type: "cites".to_string(),
+                    ..Edge::default()
+                }),
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_round_trip() {
+        let (mut client, _graph, _dir) = serve().await;
+        let session = create_session(&mut client).await;
+        let image = vec![1u8, 2, 3, 4];
+        let attachment = Attachment {
+            media_type: "image/png".to_string(),
+            size: image.len() as u64,
+            sha256: crate::attachments::sha256_hex(&image),
+            body: Some(attachment::Body::InlineData(image.clone())),
+            source_uri: None,
+            name: Some("diagram.png".to_string()),
+        };
+
+        let prompt = client
+            .add_prompt(AddPromptRequest {
+                session_id: session.id,
+                content: "Describe this".to_string(),
+                metadata: None,
+                attachments: vec![attachment.clone()],
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(prompt.attachments, vec![attachment.clone()]);
+
+        let response = client
+            .add_response(AddResponseRequest {
+                prompt_id: prompt.id.clone(),
+                content: "A diagram".to_string(),
+                token_usage: Some(TokenUsage::default()),
+                metadata: None,
+                attachments: vec![Attachment {
+                    sha256: "0".repeat(64),
+                    ..attachment
+                }],
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(response.code(), tonic::Code::InvalidArgument);
+
+        let fetched = client
+            .get_node(GetNodeRequest { node_id: prompt.id })
+            .await
+            .unwrap()
+            .into_inner();
+        let Some(node::NodeData::Prompt(stored)) = fetched.node_data else {
+            panic!("expected a prompt");
+        };
+        assert_eq!(
+            stored.attachments[0].body,
+            Some(attachment::Body::InlineData(image))
+        );
+    }
+}
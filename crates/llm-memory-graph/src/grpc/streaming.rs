@@ -3,6 +3,9 @@
 //! This module implements streaming response handlers for large result sets
 //! and real-time event subscriptions.
 
+// The stub handlers stay async so callers are unaffected once they are implemented
+#![allow(clippy::unused_async)]
+
 use crate::grpc::proto;
 use futures::Stream;
 use std::pin::Pin;
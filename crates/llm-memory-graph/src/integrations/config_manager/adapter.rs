@@ -1,7 +1,7 @@
 //! Configuration adapter for transforming between Config Manager schemas and local Config
 
 use super::types::MemoryGraphConfig;
-use crate::Config;
+use crate::{Config, ConstraintMode};
 use std::path::PathBuf;
 use tracing::{debug, info, warn};
 
@@ -27,6 +27,12 @@ impl ConfigAdapter {
             enable_wal: remote_config.storage.enable_wal,
             compression_level: remote_config.storage.compression_level,
             flush_interval_ms: remote_config.storage.flush_interval_ms,
+            query_cache_size: if remote_config.performance.enable_query_cache {
+                remote_config.performance.query_cache_size
+            } else {
+                0
+            },
+            edge_constraints: ConstraintMode::default(),
         };
 
         // Apply local environment variable overrides (highest priority)
@@ -119,6 +125,14 @@ impl ConfigAdapter {
             merged.flush_interval_ms = local_config.flush_interval_ms;
         }
 
+        // If a local query cache is configured, use its size
+        if local_config.query_cache_size != 0 {
+            merged.query_cache_size = local_config.query_cache_size;
+        }
+
+        // Edge constraints are a local concern
+        merged.edge_constraints = local_config.edge_constraints;
+
         info!("Merged configuration with local overrides");
         merged
     }
@@ -282,6 +282,7 @@ impl<B: AsyncStorageBackend> VaultStorageAdapter<B> {
             Node::ToolInvocation(t) => t.session_id,
             Node::Agent(_) => None,
             Node::Template(_) => None,
+            Node::Custom(c) => c.session_id,
         };
 
         if let Some(sid) = session_id {
@@ -397,6 +398,7 @@ impl<B: AsyncStorageBackend> AsyncStorageBackend for VaultStorageAdapter<B> {
             Node::ToolInvocation(t) => t.session_id,
             Node::Agent(_) => None,
             Node::Template(_) => None,
+            Node::Custom(c) => c.session_id,
         };
 
         if let Some(sid) = session_id {
@@ -53,15 +53,27 @@
 #![allow(clippy::too_many_lines)]
 #![allow(clippy::explicit_iter_loop)]
 
+pub mod attachments;
 pub mod benchmarks;
+pub mod branch;
+pub mod constraints;
+pub mod context;
+pub mod custom;
+pub mod diff;
 pub mod engine;
-// pub mod grpc; // TODO: Complete gRPC implementation
-pub mod integrations;
+pub mod export;
+pub mod grpc;
+// pub mod integrations;
 pub mod migration;
 pub mod observatory;
 pub mod plugin;
 pub mod query;
+pub mod search;
 pub mod storage;
+pub mod streaming;
+pub mod transcript;
+pub mod vector;
+pub mod views;
 
 // Re-export main types
 pub use engine::{AsyncMemoryGraph, MemoryGraph};
@@ -96,10 +96,10 @@ pub mod config;
 pub mod consumer;
 pub mod emitter;
 pub mod events;
-pub mod ingest;
+// pub mod ingest;
 pub mod kafka;
-pub mod lineage;
-pub mod mapping;
+// pub mod lineage;
+// pub mod mapping;
 pub mod metrics;
 pub mod prometheus;
 pub mod publisher;
@@ -128,9 +128,9 @@ pub use consumer::{
     ConsumptionStats, InMemoryConsumer, LogLevel, MetricType, NoOpConsumer, SpanStatus,
     TelemetryConsumer, TelemetryData, TraceContext,
 };
-pub use ingest::{IngestionConfig, IngestionPipeline, IngestionStats, ProcessingResult};
-pub use lineage::{LineageBuilder, LineageChain, LineageEdge, LineageEdgeType, LineageNode};
-pub use mapping::{MappedEdge, MappedEntity, MappingConfig, MappingResult, SpanMapper};
+// pub use ingest::{IngestionConfig, IngestionPipeline, IngestionStats, ProcessingResult};
+// pub use lineage::{LineageBuilder, LineageChain, LineageEdge, LineageEdgeType, LineageNode};
+// pub use mapping::{MappedEdge, MappedEntity, MappingConfig, MappingResult, SpanMapper};
 pub use temporal::{
     Correlation, DataPoint, TemporalGraph, TemporalGraphBuilder, TemporalNode, TimeSeries,
 };
@@ -70,6 +70,9 @@ use prometheus::{
     Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry,
 };
 
+/// Plan label for query durations recorded without a plan shape
+pub const UNKNOWN_PLAN: &str = "unknown";
+
 /// Prometheus metrics for MemoryGraph monitoring
 ///
 /// Provides comprehensive production-grade metrics across multiple categories:
@@ -104,6 +107,8 @@ pub struct PrometheusMetrics {
     pub read_latency: Histogram,
     /// Query execution duration distribution (seconds)
     pub query_duration: Histogram,
+    /// Query execution duration distribution (seconds) by plan shape
+    pub query_duration_by_plan: HistogramVec,
     /// Tool execution duration distribution (seconds)
     pub tool_duration: Histogram,
     /// Batch operation size distribution
@@ -240,6 +245,16 @@ impl PrometheusMetrics {
         )?;
         registry.register(Box::new(query_duration.clone()))?;
 
+        let query_duration_by_plan = HistogramVec::new(
+            HistogramOpts::new(
+                "memory_graph_query_plan_duration_seconds",
+                "Query execution duration in seconds by plan shape",
+            )
+            .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["plan"],
+        )?;
+        registry.register(Box::new(query_duration_by_plan.clone()))?;
+
         let tool_duration = Histogram::with_opts(
             HistogramOpts::new(
                 "memory_graph_tool_duration_seconds",
@@ -386,6 +401,7 @@ impl PrometheusMetrics {
             write_latency,
             read_latency,
             query_duration,
+            query_duration_by_plan,
             tool_duration,
             batch_size,
             active_sessions,
@@ -477,8 +493,23 @@ impl PrometheusMetrics {
     }
 
     /// Record query execution duration in seconds
+    ///
+    /// The per-plan series records it under the plan `unknown`.
     pub fn record_query_duration(&self, duration_secs: f64) {
+        self.record_query_duration_for_plan(UNKNOWN_PLAN, duration_secs);
+    }
+
+    /// Record query execution duration in seconds for a plan shape
+    ///
+    /// `plan` is the plan shape reported in
+    /// [`QueryStats::shape`](crate::query::QueryStats::shape), such as
+    /// `session_index+node_type`. The duration also counts towards the
+    /// unlabelled query duration series.
+    pub fn record_query_duration_for_plan(&self, plan: &str, duration_secs: f64) {
         self.query_duration.observe(duration_secs);
+        self.query_duration_by_plan
+            .with_label_values(&[plan])
+            .observe(duration_secs);
     }
 
     /// Record tool execution duration in seconds
@@ -806,6 +837,26 @@ mod tests {
         assert!(true);
     }
 
+    #[test]
+    fn test_query_duration_by_plan() {
+        let registry = Registry::new();
+        let metrics = PrometheusMetrics::new(&registry).unwrap();
+
+        metrics.record_query_duration(0.5);
+        metrics.record_query_duration_for_plan("session_index", 0.5);
+
+        // Every query counts towards the unlabelled series
+        assert_eq!(metrics.query_duration.get_sample_count(), 2);
+        let by_plan = |plan: &str| {
+            metrics
+                .query_duration_by_plan
+                .with_label_values(&[plan])
+                .get_sample_count()
+        };
+        assert_eq!(by_plan(UNKNOWN_PLAN), 1);
+        assert_eq!(by_plan("session_index"), 1);
+    }
+
     #[test]
     fn test_gauge_updates() {
         let registry = Registry::new();
@@ -0,0 +1,734 @@
+//! Grouped aggregates over query results
+//!
+//! An [`Aggregation`] groups the nodes matched by an
+//! [`AsyncQueryBuilder`](super::AsyncQueryBuilder) by one or more dimensions
+//! and computes count, sum, average, min, max and percentile aggregates over
+//! token counts and latencies, returning one typed [`AggregateRow`] per group.
+//!
+//! Responses and tool invocations do not store their session, template or
+//! agent; they are attributed through the prompt they answer, which is loaded
+//! from storage when it is not part of the result. Nodes that have no value
+//! for a grouping dimension (a prompt without a template when grouping by
+//! template) are left out of the result.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::query::aggregate::{Aggregation, GroupBy, Metric, TimeBucket};
+//! use llm_memory_graph::query::AsyncQueryBuilder;
+//! use llm_memory_graph::NodeType;
+//!
+//! # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+//! let daily_cost = Aggregation::new()
+//!     .group_by(GroupBy::Model)
+//!     .group_by(GroupBy::Time(TimeBucket::Day))
+//!     .sum(Metric::TotalTokens)
+//!     .percentile(Metric::LatencyMs, 95.0);
+//!
+//! for row in builder.node_type(NodeType::Response).aggregate(&daily_cost).await? {
+//!     println!("{:?}: {} responses, {:?}", row.key, row.count, row.values);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::predicate::{Field, FieldValue};
+use crate::search::node_timestamp;
+use crate::storage::AsyncStorageBackend;
+use crate::{AgentId, EdgeType, Node, NodeId, SessionId, TemplateId};
+use crate::{Error, Result};
+use chrono::{DateTime, Datelike, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+/// Width of a time bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TimeBucket {
+    /// Calendar hour
+    Hour,
+    /// Calendar day (UTC)
+    Day,
+    /// ISO week, starting on Monday (UTC)
+    Week,
+}
+
+impl TimeBucket {
+    /// Start of the bucket containing `time`
+    #[must_use]
+    pub fn start(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let day = time.duration_trunc(Duration::days(1)).unwrap_or(time);
+        match self {
+            TimeBucket::Hour => time.duration_trunc(Duration::hours(1)).unwrap_or(time),
+            TimeBucket::Day => day,
+            TimeBucket::Week => {
+                day - Duration::days(i64::from(day.weekday().num_days_from_monday()))
+            }
+        }
+    }
+}
+
+/// A dimension to group nodes by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupBy {
+    /// Prompt or response model
+    Model,
+    /// Session the node belongs to
+    Session,
+    /// Agent that handled the prompt
+    Agent,
+    /// Template the prompt was instantiated from
+    Template,
+    /// Tool invocation name
+    ToolName,
+    /// Creation time, truncated to a bucket
+    Time(TimeBucket),
+}
+
+impl GroupBy {
+    /// Whether grouping needs the prompt a response or tool call belongs to
+    fn needs_prompt(self) -> bool {
+        matches!(self, GroupBy::Session | GroupBy::Agent | GroupBy::Template)
+    }
+}
+
+impl fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GroupBy::Model => "model",
+            GroupBy::Session => "session",
+            GroupBy::Agent => "agent",
+            GroupBy::Template => "template",
+            GroupBy::ToolName => "tool",
+            GroupBy::Time(TimeBucket::Hour) => "hour",
+            GroupBy::Time(TimeBucket::Day) => "day",
+            GroupBy::Time(TimeBucket::Week) => "week",
+        })
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "model" => Ok(GroupBy::Model),
+            "session" => Ok(GroupBy::Session),
+            "agent" => Ok(GroupBy::Agent),
+            "template" => Ok(GroupBy::Template),
+            "tool" | "tool_name" => Ok(GroupBy::ToolName),
+            "hour" => Ok(GroupBy::Time(TimeBucket::Hour)),
+            "day" => Ok(GroupBy::Time(TimeBucket::Day)),
+            "week" => Ok(GroupBy::Time(TimeBucket::Week)),
+            other => Err(Error::ValidationError(format!(
+                "Unknown grouping '{other}'. Use: model, session, agent, template, tool, hour, day, week"
+            ))),
+        }
+    }
+}
+
+/// A numeric node field that can be aggregated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Metric {
+    /// Response `usage.prompt_tokens`
+    PromptTokens,
+    /// Response `usage.completion_tokens`
+    CompletionTokens,
+    /// Response `usage.total_tokens`
+    TotalTokens,
+    /// Response `metadata.latency_ms`
+    LatencyMs,
+    /// Tool invocation `duration_ms`
+    DurationMs,
+}
+
+impl Metric {
+    /// The predicate field holding this metric
+    #[must_use]
+    pub const fn field(self) -> Field {
+        match self {
+            Metric::PromptTokens => Field::PromptTokens,
+            Metric::CompletionTokens => Field::CompletionTokens,
+            Metric::TotalTokens => Field::TotalTokens,
+            Metric::LatencyMs => Field::LatencyMs,
+            Metric::DurationMs => Field::ToolDurationMs,
+        }
+    }
+
+    /// Read the metric from a node, if the node has it
+    #[must_use]
+    pub fn value(self, node: &Node) -> Option<f64> {
+        match self.field().value(node)? {
+            FieldValue::Integer(i) => Some(i as f64),
+            FieldValue::Float(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Metric::PromptTokens => "prompt_tokens",
+            Metric::CompletionTokens => "completion_tokens",
+            Metric::TotalTokens => "total_tokens",
+            Metric::LatencyMs => "latency_ms",
+            Metric::DurationMs => "duration_ms",
+        })
+    }
+}
+
+impl FromStr for Metric {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "prompt_tokens" => Ok(Metric::PromptTokens),
+            "completion_tokens" => Ok(Metric::CompletionTokens),
+            "total_tokens" | "tokens" => Ok(Metric::TotalTokens),
+            "latency_ms" | "latency" => Ok(Metric::LatencyMs),
+            "duration_ms" | "duration" => Ok(Metric::DurationMs),
+            other => Err(Error::ValidationError(format!(
+                "Unknown metric '{other}'. Use: prompt_tokens, completion_tokens, total_tokens, latency_ms, duration_ms"
+            ))),
+        }
+    }
+}
+
+/// An aggregate computed for each group
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Aggregate {
+    /// Number of nodes in the group
+    Count,
+    /// Sum of a metric
+    Sum(Metric),
+    /// Mean of a metric
+    Avg(Metric),
+    /// Smallest value of a metric
+    Min(Metric),
+    /// Largest value of a metric
+    Max(Metric),
+    /// Percentile (0–100) of a metric, linearly interpolated
+    Percentile(Metric, f64),
+}
+
+impl Aggregate {
+    /// The metric this aggregate reads, `None` for [`Aggregate::Count`]
+    #[must_use]
+    pub const fn metric(self) -> Option<Metric> {
+        match self {
+            Aggregate::Count => None,
+            Aggregate::Sum(m)
+            | Aggregate::Avg(m)
+            | Aggregate::Min(m)
+            | Aggregate::Max(m)
+            | Aggregate::Percentile(m, _) => Some(m),
+        }
+    }
+
+    fn compute(self, nodes: &[&Node]) -> Option<f64> {
+        let mut values: Vec<f64> = match self.metric() {
+            Some(metric) => nodes.iter().filter_map(|n| metric.value(n)).collect(),
+            None => Vec::new(),
+        };
+        values.sort_by(f64::total_cmp);
+        self.evaluate(nodes.len(), &values)
+    }
+
+    /// Compute the aggregate for a group of `count` nodes from the group's
+    /// values of [`Aggregate::metric`], sorted ascending
+    pub(crate) fn evaluate(self, count: usize, values: &[f64]) -> Option<f64> {
+        if self == Aggregate::Count {
+            return Some(count as f64);
+        }
+        if values.is_empty() {
+            return None;
+        }
+
+        let sum = || values.iter().sum::<f64>();
+        match self {
+            Aggregate::Count => None,
+            Aggregate::Sum(_) => Some(sum()),
+            Aggregate::Avg(_) => Some(sum() / values.len() as f64),
+            Aggregate::Min(_) => values.first().copied(),
+            Aggregate::Max(_) => values.last().copied(),
+            Aggregate::Percentile(_, p) => {
+                let rank = p / 100.0 * (values.len() - 1) as f64;
+                let low = rank.floor() as usize;
+                let high = rank.ceil() as usize;
+                Some(values[low] + (values[high] - values[low]) * (rank - low as f64))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Aggregate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Aggregate::Count => f.write_str("count"),
+            Aggregate::Sum(m) => write!(f, "sum({m})"),
+            Aggregate::Avg(m) => write!(f, "avg({m})"),
+            Aggregate::Min(m) => write!(f, "min({m})"),
+            Aggregate::Max(m) => write!(f, "max({m})"),
+            Aggregate::Percentile(m, p) => write!(f, "p{p}({m})"),
+        }
+    }
+}
+
+impl FromStr for Aggregate {
+    type Err = Error;
+
+    /// Parse `count`, `sum(metric)`, `avg(..)`, `min(..)`, `max(..)` or a
+    /// percentile such as `p95(latency_ms)`
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_lowercase();
+        if s == "count" || s == "count()" {
+            return Ok(Aggregate::Count);
+        }
+
+        let invalid = || {
+            Error::ValidationError(format!(
+                "Invalid aggregate '{s}'. Use count, sum(metric), avg(metric), min(metric), max(metric) or p95(metric)"
+            ))
+        };
+        let (function, rest) = s.split_once('(').ok_or_else(invalid)?;
+        let metric: Metric = rest.strip_suffix(')').ok_or_else(invalid)?.parse()?;
+        match function.trim() {
+            "sum" => Ok(Aggregate::Sum(metric)),
+            "avg" | "mean" => Ok(Aggregate::Avg(metric)),
+            "min" => Ok(Aggregate::Min(metric)),
+            "max" => Ok(Aggregate::Max(metric)),
+            "median" => Ok(Aggregate::Percentile(metric, 50.0)),
+            function => {
+                let p: f64 = function
+                    .strip_prefix('p')
+                    .and_then(|p| p.parse().ok())
+                    .ok_or_else(invalid)?;
+                Ok(Aggregate::Percentile(metric, p))
+            }
+        }
+    }
+}
+
+/// Grouping dimensions and aggregates for
+/// [`AsyncQueryBuilder::aggregate`](super::AsyncQueryBuilder::aggregate)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Aggregation {
+    /// Grouping dimensions, outermost first
+    pub group_by: Vec<GroupBy>,
+    /// Aggregates computed for every group
+    pub aggregates: Vec<Aggregate>,
+}
+
+impl Aggregation {
+    /// An aggregation with a single group and no aggregates
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a grouping dimension
+    #[must_use]
+    pub fn group_by(mut self, group_by: GroupBy) -> Self {
+        self.group_by.push(group_by);
+        self
+    }
+
+    /// Add an aggregate
+    #[must_use]
+    pub fn aggregate(mut self, aggregate: Aggregate) -> Self {
+        self.aggregates.push(aggregate);
+        self
+    }
+
+    /// Count the nodes in each group
+    #[must_use]
+    pub fn count(self) -> Self {
+        self.aggregate(Aggregate::Count)
+    }
+
+    /// Sum a metric
+    #[must_use]
+    pub fn sum(self, metric: Metric) -> Self {
+        self.aggregate(Aggregate::Sum(metric))
+    }
+
+    /// Average a metric
+    #[must_use]
+    pub fn avg(self, metric: Metric) -> Self {
+        self.aggregate(Aggregate::Avg(metric))
+    }
+
+    /// Minimum of a metric
+    #[must_use]
+    pub fn min(self, metric: Metric) -> Self {
+        self.aggregate(Aggregate::Min(metric))
+    }
+
+    /// Maximum of a metric
+    #[must_use]
+    pub fn max(self, metric: Metric) -> Self {
+        self.aggregate(Aggregate::Max(metric))
+    }
+
+    /// Percentile (0–100) of a metric
+    #[must_use]
+    pub fn percentile(self, metric: Metric, percentile: f64) -> Self {
+        self.aggregate(Aggregate::Percentile(metric, percentile))
+    }
+
+    /// Check that percentiles are within 0–100
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error naming the first invalid aggregate.
+    pub fn validate(&self) -> Result<()> {
+        for aggregate in &self.aggregates {
+            if let Aggregate::Percentile(_, p) = aggregate {
+                if !(0.0..=100.0).contains(p) {
+                    return Err(Error::ValidationError(format!(
+                        "Percentile must be between 0 and 100, got {p}"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The value of one grouping dimension
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GroupValue {
+    /// Model name
+    Model(String),
+    /// Session
+    Session(SessionId),
+    /// Agent
+    Agent(AgentId),
+    /// Template
+    Template(TemplateId),
+    /// Tool name
+    ToolName(String),
+    /// Start of a time bucket
+    Time(DateTime<Utc>),
+}
+
+impl GroupValue {
+    fn order(&self, other: &GroupValue) -> Ordering {
+        match (self, other) {
+            (GroupValue::Time(a), GroupValue::Time(b)) => a.cmp(b),
+            (a, b) => a.to_string().cmp(&b.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for GroupValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GroupValue::Model(model) | GroupValue::ToolName(model) => f.write_str(model),
+            GroupValue::Session(id) => write!(f, "{id}"),
+            GroupValue::Agent(id) => write!(f, "{id}"),
+            GroupValue::Template(id) => write!(f, "{id}"),
+            GroupValue::Time(time) => write!(f, "{}", time.to_rfc3339()),
+        }
+    }
+}
+
+/// Aggregates for one group
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateRow {
+    /// One value per grouping dimension, in [`Aggregation::group_by`] order
+    pub key: Vec<GroupValue>,
+    /// Number of nodes in the group
+    pub count: usize,
+    /// One value per aggregate, in [`Aggregation::aggregates`] order; `None`
+    /// when no node in the group has the metric
+    pub values: Vec<Option<f64>>,
+}
+
+/// The prompt a node belongs to
+#[derive(Debug, Clone, Copy)]
+struct Origin {
+    prompt: NodeId,
+    session: SessionId,
+    template: Option<TemplateId>,
+}
+
+/// Prompts and agents that responses and tool calls are attributed to
+#[derive(Debug, Default)]
+pub(crate) struct Attribution {
+    /// Node -> the prompt it belongs to
+    origins: HashMap<NodeId, Origin>,
+    /// Prompt -> the agent that handled it
+    agents: HashMap<NodeId, AgentId>,
+}
+
+impl Attribution {
+    /// Load what `aggregation` needs to attribute `nodes`
+    pub(crate) async fn resolve(
+        storage: &dyn AsyncStorageBackend,
+        nodes: &[Node],
+        aggregation: &Aggregation,
+    ) -> Result<Self> {
+        let mut attribution = Self::default();
+        if !aggregation.group_by.iter().any(|g| g.needs_prompt()) {
+            return Ok(attribution);
+        }
+
+        let mut lookup = Lookup {
+            storage,
+            loaded: nodes.iter().map(|n| (n.id(), n)).collect(),
+            fetched: HashMap::new(),
+        };
+
+        for node in nodes {
+            // Tool call -> response -> prompt
+            let prompt_id = match node {
+                Node::Prompt(p) => Some(p.id),
+                Node::Response(r) => Some(r.prompt_id),
+                Node::ToolInvocation(t) => match lookup.get(t.response_id).await? {
+                    Some(Node::Response(r)) => Some(r.prompt_id),
+                    _ => None,
+                },
+                _ => None,
+            };
+            let Some(prompt_id) = prompt_id else {
+                continue;
+            };
+            if let Some(Node::Prompt(prompt)) = lookup.get(prompt_id).await? {
+                attribution.origins.insert(
+                    node.id(),
+                    Origin {
+                        prompt: prompt_id,
+                        session: prompt.session_id,
+                        template: prompt.template_id,
+                    },
+                );
+            }
+        }
+
+        if aggregation.group_by.contains(&GroupBy::Agent) {
+            let prompt_ids: HashSet<NodeId> =
+                attribution.origins.values().map(|o| o.prompt).collect();
+            for prompt_id in prompt_ids {
+                for edge in storage.get_outgoing_edges(&prompt_id).await? {
+                    if edge.edge_type != EdgeType::HandledBy {
+                        continue;
+                    }
+                    if let Some(Node::Agent(agent)) = lookup.get(edge.to).await? {
+                        attribution.agents.insert(prompt_id, agent.id);
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(attribution)
+    }
+
+    /// The value of `dimension` for `node`, if it has one
+    fn value(&self, dimension: GroupBy, node: &Node) -> Option<GroupValue> {
+        let origin = self.origins.get(&node.id());
+        match (dimension, node) {
+            (GroupBy::Model, node) => match Field::Model.value(node)? {
+                FieldValue::String(model) => Some(GroupValue::Model(model)),
+                _ => None,
+            },
+            (GroupBy::ToolName, Node::ToolInvocation(t)) => {
+                Some(GroupValue::ToolName(t.tool_name.clone()))
+            }
+            (GroupBy::Time(bucket), node) => {
+                Some(GroupValue::Time(bucket.start(node_timestamp(node))))
+            }
+            (GroupBy::Session, Node::Session(s)) => Some(GroupValue::Session(s.id)),
+            (GroupBy::Session, _) => origin.map(|o| GroupValue::Session(o.session)),
+            (GroupBy::Template, Node::Template(t)) => Some(GroupValue::Template(t.id)),
+            (GroupBy::Template, _) => origin?.template.map(GroupValue::Template),
+            (GroupBy::Agent, Node::Agent(a)) => Some(GroupValue::Agent(a.id)),
+            (GroupBy::Agent, _) => self
+                .agents
+                .get(&origin?.prompt)
+                .map(|id| GroupValue::Agent(*id)),
+            (GroupBy::ToolName, _) => None,
+        }
+    }
+}
+
+/// Node lookups that prefer the query result over storage
+struct Lookup<'a> {
+    storage: &'a dyn AsyncStorageBackend,
+    loaded: HashMap<NodeId, &'a Node>,
+    fetched: HashMap<NodeId, Option<Node>>,
+}
+
+impl Lookup<'_> {
+    async fn get(&mut self, id: NodeId) -> Result<Option<Node>> {
+        if let Some(node) = self.loaded.get(&id) {
+            return Ok(Some((*node).clone()));
+        }
+        if let Some(node) = self.fetched.get(&id) {
+            return Ok(node.clone());
+        }
+        let node = self.storage.get_node(&id).await?;
+        self.fetched.insert(id, node.clone());
+        Ok(node)
+    }
+}
+
+/// Group `nodes` and compute the aggregates of each group
+///
+/// Rows are ordered by key; time buckets sort chronologically. Without
+/// grouping dimensions there is exactly one row, even for no nodes.
+pub(crate) fn aggregate_nodes(
+    nodes: &[Node],
+    aggregation: &Aggregation,
+    attribution: &Attribution,
+) -> Vec<AggregateRow> {
+    let mut groups: HashMap<Vec<GroupValue>, Vec<&Node>> = HashMap::new();
+    if aggregation.group_by.is_empty() {
+        groups.insert(Vec::new(), Vec::new());
+    }
+
+    for node in nodes {
+        if let Some(key) = group_key(node, aggregation, attribution) {
+            groups.entry(key).or_default().push(node);
+        }
+    }
+
+    let mut rows: Vec<AggregateRow> = groups
+        .into_iter()
+        .map(|(key, members)| AggregateRow {
+            key,
+            count: members.len(),
+            values: aggregation
+                .aggregates
+                .iter()
+                .map(|aggregate| aggregate.compute(&members))
+                .collect(),
+        })
+        .collect();
+    sort_rows(&mut rows);
+    rows
+}
+
+/// The group `node` falls into, or `None` when it has no value for one of
+/// the grouping dimensions
+pub(crate) fn group_key(
+    node: &Node,
+    aggregation: &Aggregation,
+    attribution: &Attribution,
+) -> Option<Vec<GroupValue>> {
+    aggregation
+        .group_by
+        .iter()
+        .map(|dimension| attribution.value(*dimension, node))
+        .collect()
+}
+
+/// Order rows by key, with time buckets sorting chronologically
+pub(crate) fn sort_rows(rows: &mut [AggregateRow]) {
+    rows.sort_by(|a, b| {
+        a.key
+            .iter()
+            .zip(&b.key)
+            .map(|(x, y)| x.order(y))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ResponseMetadata, ResponseNode, TokenUsage};
+    use chrono::TimeZone;
+
+    fn response(model: &str, tokens: u32, latency_ms: u64, hour: u32) -> Node {
+        let mut response = ResponseNode::with_metadata(
+            NodeId::new(),
+            "r".to_string(),
+            TokenUsage::new(tokens / 2, tokens - tokens / 2),
+            ResponseMetadata {
+                model: model.to_string(),
+                latency_ms,
+                ..ResponseMetadata::default()
+            },
+        );
+        response.timestamp = Utc.with_ymd_and_hms(2024, 3, 6, hour, 30, 0).unwrap();
+        Node::Response(response)
+    }
+
+    #[test]
+    fn test_parse_aggregates() {
+        assert_eq!("count".parse::<Aggregate>().unwrap(), Aggregate::Count);
+        assert_eq!(
+            "SUM(total_tokens)".parse::<Aggregate>().unwrap(),
+            Aggregate::Sum(Metric::TotalTokens)
+        );
+        assert_eq!(
+            "p95(latency_ms)".parse::<Aggregate>().unwrap(),
+            Aggregate::Percentile(Metric::LatencyMs, 95.0)
+        );
+        assert!("sum(content)".parse::<Aggregate>().is_err());
+        assert!("p95 latency".parse::<Aggregate>().is_err());
+        assert_eq!(
+            "week".parse::<GroupBy>().unwrap(),
+            GroupBy::Time(TimeBucket::Week)
+        );
+
+        let invalid = Aggregation::new().percentile(Metric::LatencyMs, 120.0);
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_aggregate_by_model_and_hour() {
+        let nodes = vec![
+            response("gpt-4", 100, 200, 9),
+            response("gpt-4", 300, 400, 9),
+            response("gpt-4", 50, 100, 10),
+            response("claude", 10, 1_000, 9),
+        ];
+        let aggregation = Aggregation::new()
+            .group_by(GroupBy::Model)
+            .group_by(GroupBy::Time(TimeBucket::Hour))
+            .count()
+            .sum(Metric::TotalTokens)
+            .avg(Metric::LatencyMs)
+            .percentile(Metric::LatencyMs, 50.0)
+            .max(Metric::DurationMs);
+
+        let rows = aggregate_nodes(&nodes, &aggregation, &Attribution::default());
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].key[0], GroupValue::Model("claude".to_string()));
+
+        let nine = Utc.with_ymd_and_hms(2024, 3, 6, 9, 0, 0).unwrap();
+        let gpt4 = &rows[1];
+        assert_eq!(
+            gpt4.key,
+            vec![
+                GroupValue::Model("gpt-4".to_string()),
+                GroupValue::Time(nine)
+            ]
+        );
+        assert_eq!(gpt4.count, 2);
+        assert_eq!(
+            gpt4.values,
+            vec![Some(2.0), Some(400.0), Some(300.0), Some(300.0), None]
+        );
+
+        // Without dimensions everything lands in one row
+        let total = aggregate_nodes(
+            &nodes,
+            &Aggregation::new().sum(Metric::TotalTokens),
+            &Attribution::default(),
+        );
+        assert_eq!(total.len(), 1);
+        assert_eq!(total[0].values, vec![Some(460.0)]);
+        assert_eq!(
+            TimeBucket::Week.start(nine),
+            Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap()
+        );
+    }
+}
@@ -3,7 +3,15 @@
 //! This module provides a fluent API for building and executing async queries
 //! over the graph data with support for streaming large result sets.
 
-use crate::Result;
+use super::aggregate::{self, AggregateRow, Aggregation};
+use super::cache::{cache_key, QueryCache};
+use super::cursor::{Cursor, Page};
+use super::explain::{
+    plan_shape, sort_and_paginate, AccessPath, Explain, NodeFilters, QueryStats, StatsRecorder,
+};
+use super::predicate::Predicate;
+use crate::observatory::PrometheusMetrics;
+use crate::{Error, Result};
 use crate::storage::AsyncStorageBackend;
 use crate::{Node, NodeType, SessionId};
 use chrono::{DateTime, Utc};
@@ -47,8 +55,12 @@ pub struct AsyncQueryBuilder {
     session_filter: Option<SessionId>,
     node_type_filter: Option<NodeType>,
     time_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    predicate: Option<Predicate>,
+    cursor: Option<Cursor>,
     limit: Option<usize>,
     offset: usize,
+    cache: Option<Arc<QueryCache>>,
+    metrics: Option<Arc<PrometheusMetrics>>,
 }
 
 impl AsyncQueryBuilder {
@@ -73,8 +85,12 @@ impl AsyncQueryBuilder {
             session_filter: None,
             node_type_filter: None,
             time_range: None,
+            predicate: None,
+            cursor: None,
             limit: None,
             offset: 0,
+            cache: None,
+            metrics: None,
         }
     }
 
@@ -141,6 +157,34 @@ impl AsyncQueryBuilder {
         self
     }
 
+    /// Keep only nodes matching `predicate`
+    ///
+    /// May be called more than once; the predicates are combined with AND.
+    /// Without a session filter, the session index is used when the predicate
+    /// confines results to known sessions, and every node is scanned otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::query::AsyncQueryBuilder;
+    /// # use llm_memory_graph::query::{Field, Predicate};
+    /// # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+    /// let expensive = builder
+    ///     .filter(Field::TotalTokens.gt(4_000))
+    ///     .filter(Predicate::session_tag("production"))
+    ///     .execute()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.predicate = Some(match self.predicate.take() {
+            Some(existing) => existing.and(predicate),
+            None => predicate,
+        });
+        self
+    }
+
     /// Limit the number of results
     ///
     /// # Examples
@@ -181,6 +225,60 @@ impl AsyncQueryBuilder {
         self
     }
 
+    /// Resume after the last row of a previous page
+    ///
+    /// Use this in place of [`offset`](Self::offset): the page starts strictly
+    /// after the cursor position, so nodes added since the previous page do
+    /// not shift or repeat rows.
+    ///
+    /// A query answered by a reverse lookup (see [`explain`](Self::explain))
+    /// seeks to the cursor in the lookup index and never reads earlier rows.
+    /// Session and full-scan queries have no timestamp-ordered index to seek
+    /// in, so every page loads and sorts the whole result before cutting it at
+    /// the cursor, and costs about the same as the first.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::query::{AsyncQueryBuilder, Cursor};
+    /// # async fn example(builder: AsyncQueryBuilder, token: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// let page = builder
+    ///     .cursor(Cursor::decode(token)?)
+    ///     .limit(10)
+    ///     .execute_page()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn cursor(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    /// Serve repeated queries from a shared result cache
+    ///
+    /// Pages returned by [`execute_page`](Self::execute_page) and the methods
+    /// built on it are stored in `cache` and reused until a write invalidates
+    /// them. The storage this builder reads from must be wrapped with
+    /// [`QueryCache::watch`] so that writes reach the cache.
+    /// [`AsyncMemoryGraph::query`](crate::engine::AsyncMemoryGraph::query) sets
+    /// this up when the graph has a query cache.
+    pub fn cache(mut self, cache: Arc<QueryCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Record the duration of every executed query in `metrics`
+    ///
+    /// Durations go to the query duration histogram labelled with the plan
+    /// shape, including pages served from the result cache.
+    /// [`AsyncMemoryGraph::query`](crate::engine::AsyncMemoryGraph::query) sets
+    /// this up when the graph has query metrics attached.
+    pub fn metrics(mut self, metrics: Arc<PrometheusMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
     /// Execute the query and return all matching nodes
     ///
     /// This loads all results into memory. For large result sets, consider using
@@ -197,66 +295,249 @@ impl AsyncQueryBuilder {
     /// # }
     /// ```
     pub async fn execute(&self) -> Result<Vec<Node>> {
-        // Get base nodes from session or all nodes
-        let mut nodes = if let Some(session_id) = &self.session_filter {
-            self.storage.get_session_nodes(session_id).await?
-        } else {
-            // For now, we'll need to iterate through sessions
-            // In production, you'd want a more efficient approach
-            vec![]
-        };
+        Ok(self.execute_page().await?.items)
+    }
 
-        // Apply node type filter
-        if let Some(node_type) = &self.node_type_filter {
-            nodes.retain(|node| node.node_type() == *node_type);
+    /// Execute the query and return one page with a cursor for the next
+    ///
+    /// `next_cursor` is set when a [`limit`](Self::limit) cut the result short.
+    /// Pass it to [`cursor`](Self::cursor) on an otherwise identical query to
+    /// fetch the following page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage retrieval fails, or if both a cursor and an
+    /// offset were given.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::query::AsyncQueryBuilder;
+    /// # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+    /// let page = builder.limit(50).execute_page().await?;
+    /// let token = page.next_cursor.map(|cursor| cursor.encode());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_page(&self) -> Result<Page> {
+        if self.cursor.is_some() && self.offset > 0 {
+            return Err(Error::ValidationError(
+                "Query cannot combine a cursor with an offset".to_string(),
+            ));
         }
 
-        // Apply time range filter
-        if let Some((start, end)) = &self.time_range {
-            nodes.retain(|node| {
-                let timestamp = match node {
-                    Node::Prompt(p) => p.timestamp,
-                    Node::Response(r) => r.timestamp,
-                    Node::Session(s) => s.created_at,
-                    Node::ToolInvocation(t) => t.timestamp,
-                    Node::Agent(a) => a.created_at,
-                    Node::Template(t) => t.created_at,
-                };
-                timestamp >= *start && timestamp <= *end
-            });
+        Ok(self.execute_page_with_stats().await?.0)
+    }
+
+    /// Describe how the query would run without running it
+    ///
+    /// Reports the access path, the filters applied before and after nodes
+    /// are fetched, and row estimates based on storage statistics.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage statistics cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::query::{AsyncQueryBuilder, Field};
+    /// # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+    /// let plan = builder.filter(Field::TotalTokens.gt(4_000)).explain().await?;
+    /// println!("{plan}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn explain(&self) -> Result<Explain> {
+        let stats = self.storage.stats().await?;
+        Ok(self
+            .filters()
+            .explain(&stats, self.cursor.is_some(), self.offset, self.limit))
+    }
+
+    /// Execute the query and report what it did
+    ///
+    /// Returns the same nodes as [`execute`](Self::execute) along with the
+    /// nodes scanned and returned and the time spent in each stage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// [`execute_page`](Self::execute_page).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::query::AsyncQueryBuilder;
+    /// # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+    /// let (nodes, stats) = builder.limit(10).execute_with_stats().await?;
+    /// println!("{}: scanned {} in {:?}", stats.shape, stats.nodes_scanned, stats.elapsed);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_with_stats(&self) -> Result<(Vec<Node>, QueryStats)> {
+        let (page, stats) = self.execute_page_with_stats().await?;
+        Ok((page.items, stats))
+    }
+
+    /// Execute the query for one page and report what it did
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// [`execute_page`](Self::execute_page).
+    pub async fn execute_page_with_stats(&self) -> Result<(Page, QueryStats)> {
+        if self.cursor.is_some() && self.offset > 0 {
+            return Err(Error::ValidationError(
+                "Query cannot combine a cursor with an offset".to_string(),
+            ));
         }
 
-        // Sort by timestamp (newest first)
-        nodes.sort_by(|a, b| {
-            let ts_a = match a {
-                Node::Prompt(p) => p.timestamp,
-                Node::Response(r) => r.timestamp,
-                Node::Session(s) => s.created_at,
-                Node::ToolInvocation(t) => t.timestamp,
-                Node::Agent(a) => a.created_at,
-                Node::Template(t) => t.created_at,
-            };
-            let ts_b = match b {
-                Node::Prompt(p) => p.timestamp,
-                Node::Response(r) => r.timestamp,
-                Node::Session(s) => s.created_at,
-                Node::ToolInvocation(t) => t.timestamp,
-                Node::Agent(a) => a.created_at,
-                Node::Template(t) => t.created_at,
-            };
-            ts_b.cmp(&ts_a)
+        let filters = self.filters();
+        let cached = self.cache.as_ref().map(|cache| {
+            let key = cache_key(&filters, self.cursor.as_ref(), self.offset, self.limit);
+            (cache, cache.get(&key), key)
         });
+        let pending = match cached {
+            Some((_, Ok(hit), _)) => {
+                let mut recorder = StatsRecorder::new(hit.shape);
+                let rows = hit.page.items.len();
+                recorder.cache_hits(rows);
+                recorder.stage("cache", 0, rows);
+                let stats = recorder.finish(rows);
+                self.record_duration(&stats);
+                return Ok((hit.page, stats));
+            }
+            Some((cache, Err(generation), key)) => Some((cache, generation, key)),
+            None => None,
+        };
 
-        // Apply offset
-        let nodes: Vec<_> = nodes.into_iter().skip(self.offset).collect();
+        let (access_path, _, post_fetch) = filters.plan(false);
+        let shape = plan_shape(&access_path, &post_fetch);
+        let mut recorder = StatsRecorder::new(shape.clone());
+
+        let nodes = self
+            .fetch(&access_path, self.cursor.as_ref(), &mut recorder)
+            .await?;
+        let nodes = filters.apply(nodes, &mut recorder);
+        let page = sort_and_paginate(
+            nodes,
+            self.cursor.as_ref(),
+            self.offset,
+            self.limit,
+            &mut recorder,
+        );
 
-        // Apply limit
-        let nodes = if let Some(limit) = self.limit {
-            nodes.into_iter().take(limit).collect()
-        } else {
-            nodes
-        };
+        if let Some((cache, generation, key)) = pending {
+            cache.insert(key, &filters, &access_path, &page, shape, generation);
+        }
+
+        let stats = recorder.finish(page.items.len());
+        self.record_duration(&stats);
+        Ok((page, stats))
+    }
 
+    /// Group the matching nodes and compute aggregates for each group
+    ///
+    /// Unlike [`execute`](Self::execute), a query without a session filter or
+    /// predicate aggregates over the whole graph. Limits, offsets and cursors
+    /// page through node results and are ignored here.
+    ///
+    /// See [`aggregate`](super::aggregate) for how responses and tool calls are
+    /// attributed to sessions, templates and agents.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage retrieval fails or a percentile is outside
+    /// 0–100.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::query::aggregate::{Aggregation, GroupBy, Metric};
+    /// # use llm_memory_graph::query::AsyncQueryBuilder;
+    /// # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+    /// let per_tool = Aggregation::new()
+    ///     .group_by(GroupBy::ToolName)
+    ///     .count()
+    ///     .avg(Metric::DurationMs);
+    /// let rows = builder.aggregate(&per_tool).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn aggregate(&self, aggregation: &Aggregation) -> Result<Vec<AggregateRow>> {
+        aggregation.validate()?;
+
+        let nodes = self.matching(true).await?;
+        let attribution =
+            aggregate::Attribution::resolve(self.storage.as_ref(), &nodes, aggregation).await?;
+
+        Ok(aggregate::aggregate_nodes(
+            &nodes,
+            aggregation,
+            &attribution,
+        ))
+    }
+
+    fn record_duration(&self, stats: &QueryStats) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_query_duration_for_plan(&stats.shape, stats.elapsed.as_secs_f64());
+        }
+    }
+
+    fn filters(&self) -> NodeFilters<'_> {
+        NodeFilters {
+            session: self.session_filter,
+            predicate: self.predicate.as_ref(),
+            node_type: self.node_type_filter.as_ref(),
+            start: self.time_range.map(|(start, _)| start),
+            end: self.time_range.map(|(_, end)| end),
+        }
+    }
+
+    /// Load the nodes matching every filter, unordered
+    ///
+    /// With `scan_all`, a query without session filter or predicate matches
+    /// the whole graph instead of nothing.
+    async fn matching(&self, scan_all: bool) -> Result<Vec<Node>> {
+        let filters = self.filters();
+        let (access_path, _, post_fetch) = filters.plan(scan_all);
+        let mut recorder = StatsRecorder::new(plan_shape(&access_path, &post_fetch));
+
+        let nodes = self.fetch(&access_path, None, &mut recorder).await?;
+        Ok(filters.apply(nodes, &mut recorder))
+    }
+
+    /// Load the candidate nodes for an access path
+    ///
+    /// Lookups start after `after`; other paths return every candidate.
+    async fn fetch(
+        &self,
+        access_path: &AccessPath,
+        after: Option<&Cursor>,
+        recorder: &mut StatsRecorder,
+    ) -> Result<Vec<Node>> {
+        let nodes = match access_path {
+            AccessPath::SessionIndex { sessions } => {
+                let mut nodes = Vec::new();
+                for session_id in sessions {
+                    nodes.extend(self.storage.get_session_nodes(session_id).await?);
+                }
+                nodes
+            }
+            AccessPath::Lookup(lookup) => {
+                self.storage
+                    .lookup_nodes(lookup, after, usize::MAX)
+                    .await?
+                    .items
+            }
+            AccessPath::FullScan => self.storage.scan_nodes().await?,
+            // Without a session or predicate there is nothing to narrow a full
+            // scan, so return nothing rather than the whole graph
+            AccessPath::Empty => Vec::new(),
+        };
+        recorder.fetched(nodes.len());
+        recorder.stage("fetch", 0, nodes.len());
         Ok(nodes)
     }
 
@@ -266,6 +547,11 @@ impl AsyncQueryBuilder {
     /// one at a time without loading everything into memory. The stream uses
     /// storage-level streaming to avoid loading all nodes at once.
     ///
+    /// Predicates can depend on other nodes (a response's session comes from
+    /// its prompt), so queries with a [`filter`](Self::filter) resolve the
+    /// full result first and then stream it. The same applies to queries with
+    /// a [`cursor`](Self::cursor), which need the result in page order.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -292,6 +578,19 @@ impl AsyncQueryBuilder {
     pub fn execute_stream(&self) -> Pin<Box<dyn Stream<Item = Result<Node>> + Send + '_>> {
         use futures::StreamExt;
 
+        if self.predicate.is_some() || self.cursor.is_some() {
+            return Box::pin(async_stream::stream! {
+                match self.execute().await {
+                    Ok(nodes) => {
+                        for node in nodes {
+                            yield Ok(node);
+                        }
+                    }
+                    Err(e) => yield Err(e),
+                }
+            });
+        }
+
         let session_filter = self.session_filter;
         let node_type_filter = self.node_type_filter.clone();
         let time_range = self.time_range;
@@ -336,6 +635,7 @@ impl AsyncQueryBuilder {
                         Node::ToolInvocation(t) => t.timestamp,
                         Node::Agent(a) => a.created_at,
                         Node::Template(t) => t.created_at,
+                        Node::Custom(c) => c.created_at,
                     };
 
                     if timestamp < start || timestamp > end {
@@ -389,6 +689,8 @@ impl AsyncQueryBuilder {
         if self.session_filter.is_some()
             && self.node_type_filter.is_none()
             && self.time_range.is_none()
+            && self.predicate.is_none()
+            && self.cursor.is_none()
             && self.offset == 0
             && self.limit.is_none()
         {
@@ -620,4 +922,341 @@ mod tests {
 
         assert_eq!(count, 5);
     }
+
+    #[tokio::test]
+    async fn test_predicate_filters() {
+        use crate::query::{Field, Predicate};
+        use crate::{ResponseMetadata, ResponseNode, TokenUsage};
+
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap())
+            as Arc<dyn crate::storage::AsyncStorageBackend>;
+
+        let mut sessions = Vec::new();
+        for (i, reason) in ["stop", "length", "length"].iter().enumerate() {
+            let session = ConversationSession::new();
+            backend
+                .store_node(&Node::Session(session.clone()))
+                .await
+                .unwrap();
+            let prompt = PromptNode::new(session.id, format!("Prompt {i}"));
+            backend
+                .store_node(&Node::Prompt(prompt.clone()))
+                .await
+                .unwrap();
+            let response = ResponseNode::with_metadata(
+                prompt.id,
+                "Response".to_string(),
+                TokenUsage::new(10, 100 * (i as u32 + 1)),
+                ResponseMetadata {
+                    finish_reason: (*reason).to_string(),
+                    ..ResponseMetadata::default()
+                },
+            );
+            backend.store_node(&Node::Response(response)).await.unwrap();
+            sessions.push(session.id);
+        }
+
+        // No session filter: falls back to a full scan
+        let truncated = AsyncQueryBuilder::new(Arc::clone(&backend))
+            .filter(Field::FinishReason.eq("length"))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(truncated.len(), 2);
+
+        // Session alternatives are read through the session index
+        let scoped = AsyncQueryBuilder::new(Arc::clone(&backend))
+            .filter(Predicate::session(sessions[0]).or(Predicate::session(sessions[1])))
+            .filter(Field::CompletionTokens.ge(200))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(scoped.len(), 1);
+        assert_eq!(scoped[0].node_type(), NodeType::Response);
+
+        let count = AsyncQueryBuilder::new(Arc::clone(&backend))
+            .filter(!Predicate::node_type(NodeType::Session))
+            .filter(Predicate::session(sessions[2]))
+            .count()
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_pagination() {
+        use futures::StreamExt;
+
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap())
+            as Arc<dyn crate::storage::AsyncStorageBackend>;
+
+        let session = ConversationSession::new();
+        for i in 0..7 {
+            let prompt = PromptNode::new(session.id, format!("Prompt {i}"));
+            backend.store_node(&Node::Prompt(prompt)).await.unwrap();
+        }
+
+        let query = || {
+            AsyncQueryBuilder::new(Arc::clone(&backend))
+                .session(session.id)
+                .limit(3)
+        };
+
+        let first = query().execute_page().await.unwrap();
+        assert_eq!(first.items.len(), 3);
+        let cursor = first.next_cursor.unwrap();
+
+        // A prompt added between pages lands before the cursor
+        let late = PromptNode::new(session.id, "Late".to_string());
+        backend.store_node(&Node::Prompt(late)).await.unwrap();
+
+        let token = cursor.encode();
+        let second = query()
+            .cursor(token.parse().unwrap())
+            .execute_page()
+            .await
+            .unwrap();
+        let third = query()
+            .cursor(second.next_cursor.unwrap())
+            .execute_page()
+            .await
+            .unwrap();
+        assert_eq!(third.items.len(), 1);
+        assert!(third.next_cursor.is_none());
+
+        let ids: std::collections::HashSet<_> = first
+            .items
+            .iter()
+            .chain(&second.items)
+            .chain(&third.items)
+            .map(Node::id)
+            .collect();
+        assert_eq!(ids.len(), 7);
+
+        // Streams resume from the same position
+        let streamed: Vec<_> = query()
+            .cursor(cursor)
+            .execute_stream()
+            .map(|result| result.unwrap().id())
+            .collect()
+            .await;
+        let paged: Vec<_> = second.items.iter().map(Node::id).collect();
+        assert_eq!(streamed, paged);
+
+        assert!(query().cursor(cursor).offset(1).execute().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_aggregate_attributes_through_prompts() {
+        use crate::query::aggregate::{Aggregation, GroupBy, GroupValue, Metric};
+        use crate::{
+            AgentNode, Edge, EdgeType, ResponseNode, TemplateId, TokenUsage, ToolInvocation,
+        };
+
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap())
+            as Arc<dyn crate::storage::AsyncStorageBackend>;
+
+        let session = ConversationSession::new();
+        let agent = AgentNode::new("coder".to_string(), "dev".to_string(), vec![]);
+        backend
+            .store_node(&Node::Agent(agent.clone()))
+            .await
+            .unwrap();
+
+        let template_id = TemplateId::new();
+        let mut templated = PromptNode::new(session.id, "From template".to_string());
+        templated.template_id = Some(template_id);
+        let plain = PromptNode::new(session.id, "Plain".to_string());
+        for (prompt, tokens) in [(&templated, 100), (&plain, 40)] {
+            backend
+                .store_node(&Node::Prompt(prompt.clone()))
+                .await
+                .unwrap();
+            let response = ResponseNode::new(
+                prompt.id,
+                "Response".to_string(),
+                TokenUsage::new(tokens / 2, tokens / 2),
+            );
+            backend
+                .store_node(&Node::Response(response.clone()))
+                .await
+                .unwrap();
+            let tool =
+                ToolInvocation::new(response.id, "search".to_string(), serde_json::json!({}));
+            backend
+                .store_node(&Node::ToolInvocation(tool))
+                .await
+                .unwrap();
+        }
+        backend
+            .store_edge(&Edge::new(templated.id, agent.node_id, EdgeType::HandledBy))
+            .await
+            .unwrap();
+
+        // Only responses are matched; their prompts come from storage
+        let by_template = AsyncQueryBuilder::new(Arc::clone(&backend))
+            .session(session.id)
+            .node_type(NodeType::Response)
+            .aggregate(
+                &Aggregation::new()
+                    .group_by(GroupBy::Template)
+                    .sum(Metric::TotalTokens),
+            )
+            .await
+            .unwrap();
+        assert_eq!(by_template.len(), 1);
+        assert_eq!(by_template[0].key, vec![GroupValue::Template(template_id)]);
+        assert_eq!(by_template[0].values, vec![Some(100.0)]);
+
+        // Without a session filter the whole graph is aggregated
+        let by_agent = AsyncQueryBuilder::new(Arc::clone(&backend))
+            .node_type(NodeType::ToolInvocation)
+            .aggregate(&Aggregation::new().group_by(GroupBy::Agent).count())
+            .await
+            .unwrap();
+        assert_eq!(by_agent.len(), 1);
+        assert_eq!(by_agent[0].key, vec![GroupValue::Agent(agent.id)]);
+        assert_eq!(by_agent[0].count, 1);
+
+        let by_session = AsyncQueryBuilder::new(backend)
+            .aggregate(&Aggregation::new().group_by(GroupBy::Session).count())
+            .await
+            .unwrap();
+        assert_eq!(by_session.len(), 1);
+        assert_eq!(by_session[0].count, 6);
+    }
+
+    #[tokio::test]
+    async fn test_explain_and_execute_with_stats() {
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap())
+            as Arc<dyn crate::storage::AsyncStorageBackend>;
+
+        let session = ConversationSession::new();
+        let other = ConversationSession::new();
+        for (s, prompts) in [(&session, 4), (&other, 2)] {
+            backend.store_node(&Node::Session(s.clone())).await.unwrap();
+            for i in 0..prompts {
+                let prompt = PromptNode::new(s.id, format!("prompt {i}"));
+                backend.store_node(&Node::Prompt(prompt)).await.unwrap();
+            }
+        }
+
+        let query = AsyncQueryBuilder::new(Arc::clone(&backend))
+            .session(session.id)
+            .node_type(NodeType::Prompt)
+            .limit(2);
+
+        let plan = query.explain().await.unwrap();
+        assert_eq!(
+            plan.access_path,
+            AccessPath::SessionIndex {
+                sessions: vec![session.id]
+            }
+        );
+        assert_eq!(plan.shape(), "session_index+node_type");
+        assert_eq!(plan.estimated_scanned, 4);
+        assert_eq!(plan.estimated_rows, 2);
+
+        let (nodes, stats) = query.execute_with_stats().await.unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(stats.shape, plan.shape());
+        assert_eq!(stats.nodes_scanned, 5);
+        assert_eq!(stats.nodes_deserialized, 5);
+        assert_eq!(stats.nodes_returned, 2);
+        assert_eq!(stats.cache_hits, 0);
+        let names: Vec<&str> = stats.stages.iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["fetch", "node_type", "sort", "paginate"]);
+        assert_eq!(stats.stage("node_type").unwrap().rows_out, 4);
+
+        // Without any narrowing filter nothing is read
+        let (_, stats) = AsyncQueryBuilder::new(backend)
+            .execute_with_stats()
+            .await
+            .unwrap();
+        assert_eq!(stats.shape, "empty");
+        assert_eq!(stats.nodes_scanned, 0);
+    }
+
+    #[tokio::test]
+    async fn test_indexed_equality_reads_lookup() {
+        use crate::query::Field;
+        use crate::storage::Lookup;
+        use crate::ToolInvocation;
+
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap())
+            as Arc<dyn crate::storage::AsyncStorageBackend>;
+
+        let response = crate::NodeId::new();
+        for name in ["search", "search", "fetch"] {
+            let tool = ToolInvocation::new(response, name.to_string(), serde_json::json!({}));
+            backend
+                .store_node(&Node::ToolInvocation(tool))
+                .await
+                .unwrap();
+        }
+
+        let query =
+            AsyncQueryBuilder::new(Arc::clone(&backend)).filter(Field::ToolName.eq("search"));
+        let plan = query.explain().await.unwrap();
+        assert_eq!(
+            plan.access_path,
+            AccessPath::Lookup(Lookup::ToolName("search".to_string()))
+        );
+        assert!(plan.to_string().starts_with("Lookup(ToolName(\"search\"))"));
+
+        let (nodes, stats) = query.execute_with_stats().await.unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(stats.shape, "lookup+predicate");
+        assert_eq!(stats.nodes_scanned, 2);
+
+        // A later page seeks past the cursor instead of rereading the first
+        let search =
+            || AsyncQueryBuilder::new(Arc::clone(&backend)).filter(Field::ToolName.eq("search"));
+        let first = search().limit(1).execute_page().await.unwrap();
+        let (second, stats) = search()
+            .cursor(first.next_cursor.unwrap())
+            .limit(1)
+            .execute_page_with_stats()
+            .await
+            .unwrap();
+        assert_eq!(stats.nodes_scanned, 1);
+        assert_ne!(second.items[0].id(), first.items[0].id());
+        assert!(second.next_cursor.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_execute_records_query_duration() {
+        let dir = tempdir().unwrap();
+        let backend = Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap())
+            as Arc<dyn crate::storage::AsyncStorageBackend>;
+        let metrics = Arc::new(PrometheusMetrics::new(&prometheus::Registry::new()).unwrap());
+
+        let session = ConversationSession::new();
+        backend
+            .store_node(&Node::Session(session.clone()))
+            .await
+            .unwrap();
+
+        let query = AsyncQueryBuilder::new(backend)
+            .session(session.id)
+            .node_type(NodeType::Prompt)
+            .metrics(Arc::clone(&metrics));
+        query.execute().await.unwrap();
+        query.execute_with_stats().await.unwrap();
+
+        let recorded = |plan: &str| {
+            metrics
+                .query_duration_by_plan
+                .with_label_values(&[plan])
+                .get_sample_count()
+        };
+        assert_eq!(recorded("session_index+node_type"), 2);
+        assert_eq!(recorded("empty"), 0);
+        assert_eq!(metrics.query_duration.get_sample_count(), 2);
+    }
 }
@@ -0,0 +1,591 @@
+//! Async graph traversals over an async storage backend
+//!
+//! [`AsyncGraphTraversal`] offers the same bounded traversals, path searches
+//! and conversation-thread reconstruction as
+//! [`GraphTraversal`](super::GraphTraversal), awaiting storage instead of
+//! blocking on it.
+//!
+//! Breadth-first traversals fetch the neighbours of a whole level
+//! concurrently and can be consumed as a stream with
+//! [`AsyncGraphTraversal::bfs_stream`]. Streams do their work lazily and spawn
+//! no tasks, so dropping one (or wrapping it in a timeout, `take_until`, a
+//! `select!` branch and so on) cancels any lookups still in flight.
+
+use super::traversal::{self, Direction, Path, Subgraph, TraversalOptions, Visit};
+use crate::search::node_timestamp;
+use crate::storage::AsyncStorageBackend;
+use crate::{Edge, Node, NodeId, SessionId};
+use crate::{Error, Result};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Number of storage lookups a traversal keeps in flight by default
+const DEFAULT_CONCURRENCY: usize = 16;
+
+/// Async graph traversal helper
+///
+/// # Examples
+///
+/// ```no_run
+/// use llm_memory_graph::engine::AsyncMemoryGraph;
+/// use llm_memory_graph::query::{Direction, TraversalOptions};
+/// use llm_memory_graph::{Config, EdgeType};
+/// use futures::StreamExt;
+///
+/// # async fn example(prompt_id: llm_memory_graph::NodeId) -> Result<(), Box<dyn std::error::Error>> {
+/// let graph = AsyncMemoryGraph::open(Config::default()).await?;
+/// let traversal = graph.traversal().concurrency(32);
+///
+/// let options = TraversalOptions::new()
+///     .direction(Direction::Incoming)
+///     .edge_type(EdgeType::RespondsTo)
+///     .max_depth(1);
+/// let visits = traversal.bfs(prompt_id, &options).await?;
+///
+/// // Or handle nodes as they are discovered
+/// let mut stream = traversal.bfs_stream(prompt_id, &options);
+/// while let Some(visit) = stream.next().await {
+///     println!("{}", visit?.node.id());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AsyncGraphTraversal {
+    storage: Arc<dyn AsyncStorageBackend>,
+    concurrency: usize,
+}
+
+impl AsyncGraphTraversal {
+    /// Create a traversal helper over the given backend
+    pub fn new(storage: Arc<dyn AsyncStorageBackend>) -> Self {
+        Self {
+            storage,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Set how many storage lookups may be in flight at once
+    ///
+    /// Values below 1 are treated as 1.
+    #[must_use]
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Breadth-first traversal from `start` within the given limits
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` does not exist or storage fails.
+    pub async fn bfs(&self, start: NodeId, options: &TraversalOptions) -> Result<Vec<Visit>> {
+        self.bfs_stream(start, options).try_collect().await
+    }
+
+    /// Breadth-first traversal from `start`, yielding nodes as they are found
+    ///
+    /// Visits arrive in the same order [`bfs`](Self::bfs) returns them. The
+    /// neighbours of each level are fetched concurrently. The stream ends
+    /// after the first error, which is `NodeNotFound` if `start` does not
+    /// exist.
+    pub fn bfs_stream<'a>(
+        &'a self,
+        start: NodeId,
+        options: &'a TraversalOptions,
+    ) -> Pin<Box<dyn Stream<Item = Result<Visit>> + Send + 'a>> {
+        Box::pin(async_stream::stream! {
+            let start_node = match self.storage.get_node(&start).await {
+                Ok(Some(node)) => node,
+                Ok(None) => {
+                    yield Err(Error::NodeNotFound(start.to_string()));
+                    return;
+                }
+                Err(e) => {
+                    yield Err(e);
+                    return;
+                }
+            };
+
+            yield Ok(Visit {
+                node: start_node,
+                depth: 0,
+                edge: None,
+            });
+
+            let mut seen = HashSet::from([start]);
+            let mut frontier = vec![start];
+            let mut visited = 1;
+            let mut depth = 0;
+
+            while !frontier.is_empty()
+                && !options.visits_exhausted(visited)
+                && options.max_depth.is_none_or(|max| depth < max)
+            {
+                depth += 1;
+
+                // Edges of every node on this level, kept in frontier order
+                let mut candidates = Vec::new();
+                let mut edges = stream::iter(std::mem::take(&mut frontier))
+                    .map(|id| self.neighbors(id, options))
+                    .buffered(self.concurrency);
+                while let Some(result) = edges.next().await {
+                    match result {
+                        Ok(neighbors) => candidates.extend(
+                            neighbors
+                                .into_iter()
+                                .filter(|(_, neighbor)| seen.insert(*neighbor)),
+                        ),
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        }
+                    }
+                }
+                drop(edges);
+
+                let mut nodes = stream::iter(candidates)
+                    .map(|(edge, id)| async move {
+                        self.storage.get_node(&id).await.map(|node| (edge, node))
+                    })
+                    .buffered(self.concurrency);
+                while let Some(result) = nodes.next().await {
+                    match result {
+                        Ok((edge, Some(node))) if options.enters(&node) => {
+                            frontier.push(node.id());
+                            yield Ok(Visit {
+                                node,
+                                depth,
+                                edge: Some(edge),
+                            });
+                            visited += 1;
+                            if options.visits_exhausted(visited) {
+                                return;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            yield Err(e);
+                            return;
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    /// Depth-first traversal from `start` within the given limits
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` does not exist or storage fails.
+    pub async fn dfs(&self, start: NodeId, options: &TraversalOptions) -> Result<Vec<Visit>> {
+        traversal::dfs(&self.storage, start, options).await
+    }
+
+    /// Fewest-hop path from `from` to `to`, or `None` if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either endpoint does not exist or storage fails.
+    pub async fn shortest_path(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        options: &TraversalOptions,
+    ) -> Result<Option<Path>> {
+        traversal::shortest_path(&self.storage, from, to, options).await
+    }
+
+    /// Every simple path from `from` to `to` with at most `max_length` edges
+    ///
+    /// Paths are returned shortest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either endpoint does not exist or storage fails.
+    pub async fn simple_paths(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        max_length: usize,
+        options: &TraversalOptions,
+    ) -> Result<Vec<Path>> {
+        traversal::simple_paths(&self.storage, from, to, max_length, options).await
+    }
+
+    /// Nodes within `hops` of `start` together with the edges between them
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` does not exist or storage fails.
+    pub async fn neighborhood(
+        &self,
+        start: NodeId,
+        hops: usize,
+        options: &TraversalOptions,
+    ) -> Result<Subgraph> {
+        traversal::neighborhood(&self.storage, start, hops, options).await
+    }
+
+    /// Get the conversation thread containing a node
+    ///
+    /// Returns the prompts and responses of the node's session in
+    /// chronological order, like
+    /// [`GraphTraversal::get_conversation_thread`](super::GraphTraversal::get_conversation_thread).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the node does not exist, belongs to no session
+    /// (agents and templates), or storage fails.
+    pub async fn get_conversation_thread(&self, start: NodeId) -> Result<Vec<Node>> {
+        let node = self.require(start).await?;
+        let session_id = self.session_of(&node).await?;
+
+        let mut nodes = self.storage.get_session_nodes(&session_id).await?;
+        nodes.retain(|n| matches!(n, Node::Prompt(_) | Node::Response(_)));
+        nodes.sort_by_key(node_timestamp);
+
+        Ok(nodes)
+    }
+
+    /// Every node of a session together with the edges between them
+    ///
+    /// Besides the session node, prompts and responses, this includes the
+    /// tool invocations, agents and templates they link to, so handoffs and
+    /// template use show up. Prompts and responses of other sessions are left
+    /// out even when referenced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn session_subgraph(&self, session_id: &SessionId) -> Result<Subgraph> {
+        let members = self.storage.get_session_nodes(session_id).await?;
+        let mut ids: HashSet<NodeId> = members.iter().map(Node::id).collect();
+        let options = TraversalOptions::new();
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut edge_ids = HashSet::new();
+        let mut extra = Vec::new();
+        let mut all_edges = stream::iter(members.iter().map(Node::id))
+            .map(|id| self.neighbors(id, &options))
+            .buffered(self.concurrency);
+        while let Some(neighbors) = all_edges.next().await {
+            for (edge, neighbor) in neighbors? {
+                if !ids.contains(&neighbor) {
+                    extra.push(neighbor);
+                }
+                if edge_ids.insert(edge.id) {
+                    edges.push(edge);
+                }
+            }
+        }
+        drop(all_edges);
+        nodes.extend(members);
+
+        // Pull in tools, agents and templates hanging off the conversation
+        for id in extra {
+            if ids.contains(&id) {
+                continue;
+            }
+            if let Some(node) = self.storage.get_node(&id).await? {
+                if !matches!(node, Node::Prompt(_) | Node::Response(_) | Node::Session(_)) {
+                    ids.insert(id);
+                    nodes.push(node);
+                }
+            }
+        }
+
+        edges.retain(|edge| ids.contains(&edge.from) && ids.contains(&edge.to));
+        Ok(Subgraph { nodes, edges })
+    }
+
+    async fn require(&self, id: NodeId) -> Result<Node> {
+        self.storage
+            .get_node(&id)
+            .await?
+            .ok_or_else(|| Error::NodeNotFound(id.to_string()))
+    }
+
+    /// Session a node belongs to, found by walking up to its prompt
+    async fn session_of(&self, node: &Node) -> Result<SessionId> {
+        let prompt_id = match node {
+            Node::Session(s) => return Ok(s.id),
+            Node::Prompt(p) => return Ok(p.session_id),
+            Node::Response(r) => r.prompt_id,
+            Node::ToolInvocation(t) => match self.require(t.response_id).await? {
+                Node::Response(r) => r.prompt_id,
+                _ => {
+                    return Err(Error::TraversalError(
+                        "ToolInvocation does not point to a response".to_string(),
+                    ))
+                }
+            },
+            Node::Agent(_) => {
+                return Err(Error::TraversalError(
+                    "Cannot get conversation thread for agent nodes".to_string(),
+                ))
+            }
+            Node::Template(_) => {
+                return Err(Error::TraversalError(
+                    "Cannot get conversation thread for template nodes".to_string(),
+                ))
+            }
+            Node::Custom(c) => {
+                return c.session_id.ok_or_else(|| {
+                    Error::TraversalError(format!(
+                        "Custom '{}' node does not belong to a session",
+                        c.kind
+                    ))
+                })
+            }
+        };
+
+        match self.require(prompt_id).await? {
+            Node::Prompt(p) => Ok(p.session_id),
+            _ => Err(Error::TraversalError(
+                "Response does not point to a prompt".to_string(),
+            )),
+        }
+    }
+
+    /// Followable edges of `id`, each with the node at its other end
+    async fn neighbors(
+        &self,
+        id: NodeId,
+        options: &TraversalOptions,
+    ) -> Result<Vec<(Edge, NodeId)>> {
+        let outgoing = async {
+            if options.direction == Direction::Incoming {
+                Ok(Vec::new())
+            } else {
+                self.storage.get_outgoing_edges(&id).await
+            }
+        };
+        let incoming = async {
+            if options.direction == Direction::Outgoing {
+                Ok(Vec::new())
+            } else {
+                self.storage.get_incoming_edges(&id).await
+            }
+        };
+        let (outgoing, incoming) = futures::try_join!(outgoing, incoming)?;
+
+        let outgoing = outgoing.into_iter().map(|edge| {
+            let to = edge.to;
+            (edge, to)
+        });
+        let incoming = incoming.into_iter().map(|edge| {
+            let from = edge.from;
+            (edge, from)
+        });
+        Ok(outgoing
+            .chain(incoming)
+            .filter(|(edge, _)| options.follows(edge))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::AsyncMemoryGraph;
+    use crate::{AgentNode, Config, EdgeType, TokenUsage, ToolInvocation};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_async_traversal() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let first = graph
+            .add_prompt(session.id, "One".to_string(), None)
+            .await
+            .unwrap();
+        let second = graph
+            .add_prompt(session.id, "Two".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(second, "Answer".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+
+        let traversal = graph.traversal();
+        let responses = TraversalOptions::new()
+            .direction(Direction::Incoming)
+            .edge_type(EdgeType::RespondsTo);
+        let visits = traversal.bfs(second, &responses).await.unwrap();
+        assert_eq!(visits.len(), 2);
+        assert_eq!(visits[1].node.id(), response);
+
+        // Prompts are only linked through the session node
+        let path = traversal
+            .shortest_path(response, first, &TraversalOptions::new())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.nodes[1], second);
+        assert_eq!(path.edges[2].edge_type, EdgeType::PartOf);
+
+        assert!(traversal
+            .bfs(NodeId::new(), &TraversalOptions::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bfs_stream() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let mut prompts = Vec::new();
+        for i in 0..5 {
+            let prompt = graph
+                .add_prompt(session.id, format!("Prompt {i}"), None)
+                .await
+                .unwrap();
+            graph
+                .add_response(prompt, format!("Response {i}"), TokenUsage::new(1, 1), None)
+                .await
+                .unwrap();
+            prompts.push(prompt);
+        }
+
+        // The concurrent stream yields exactly what the sequential walk finds
+        let traversal = graph.traversal().concurrency(2);
+        let options = TraversalOptions::new();
+        let streamed: Vec<(NodeId, usize)> = traversal
+            .bfs_stream(prompts[0], &options)
+            .map_ok(|v| (v.node.id(), v.depth))
+            .try_collect()
+            .await
+            .unwrap();
+        let sequential: Vec<(NodeId, usize)> =
+            traversal::bfs(&traversal.storage, prompts[0], &options)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|v| (v.node.id(), v.depth))
+                .collect();
+        assert_eq!(streamed, sequential);
+        // Session node, five prompts and five responses
+        assert_eq!(streamed.len(), 11);
+
+        // Consumers can stop early by dropping the stream
+        let mut stream = traversal.bfs_stream(prompts[0], &options);
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(first.node.id(), prompts[0]);
+        drop(stream);
+
+        let limited = traversal
+            .bfs(prompts[0], &options.clone().max_visits(4))
+            .await
+            .unwrap();
+        assert_eq!(limited.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_thread() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Weather?".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(prompt, "Sunny".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        let tool = ToolInvocation::new(response, "weather".to_string(), serde_json::json!({}));
+        let tool_id = graph.add_tool_invocation(tool).await.unwrap();
+
+        let thread = graph
+            .traversal()
+            .get_conversation_thread(tool_id)
+            .await
+            .unwrap();
+        let ids: Vec<NodeId> = thread.iter().map(Node::id).collect();
+        assert_eq!(ids, vec![prompt, response]);
+
+        let agent = AgentNode::new("a".to_string(), "r".to_string(), vec![]);
+        let agent_node = agent.node_id;
+        graph.add_agent(agent).await.unwrap();
+        assert!(matches!(
+            graph.traversal().get_conversation_thread(agent_node).await,
+            Err(Error::TraversalError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_session_subgraph() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Book a flight".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(
+                prompt,
+                "Handing off".to_string(),
+                TokenUsage::new(1, 1),
+                None,
+            )
+            .await
+            .unwrap();
+        let tool = ToolInvocation::new(response, "search".to_string(), serde_json::json!({}));
+        let tool_id = graph.add_tool_invocation(tool).await.unwrap();
+        let agent = AgentNode::new("booker".to_string(), "travel".to_string(), vec![]);
+        let agent_node = agent.node_id;
+        graph.add_agent(agent).await.unwrap();
+        graph.transfer_to_agent(response, agent_node).await.unwrap();
+
+        // A prompt in another session is not pulled in
+        let other = graph.create_session().await.unwrap();
+        let elsewhere = graph
+            .add_prompt(other.id, "Unrelated".to_string(), None)
+            .await
+            .unwrap();
+        graph
+            .add_edge(prompt, elsewhere, EdgeType::References)
+            .await
+            .unwrap();
+
+        let subgraph = graph
+            .traversal()
+            .session_subgraph(&session.id)
+            .await
+            .unwrap();
+        let ids: HashSet<NodeId> = subgraph.nodes.iter().map(Node::id).collect();
+        assert_eq!(
+            ids,
+            HashSet::from([session.node_id, prompt, response, tool_id, agent_node])
+        );
+        let mut types: Vec<EdgeType> = subgraph.edges.iter().map(|e| e.edge_type.clone()).collect();
+        types.sort_by_key(|t| format!("{t:?}"));
+        assert_eq!(
+            types,
+            vec![
+                EdgeType::Invokes,
+                EdgeType::PartOf,
+                EdgeType::RespondsTo,
+                EdgeType::TransfersTo
+            ]
+        );
+    }
+}
@@ -0,0 +1,620 @@
+//! Result cache for [`AsyncQueryBuilder`](super::AsyncQueryBuilder) queries
+//!
+//! Dashboards tend to re-run the same queries against sessions that rarely
+//! change. A [`QueryCache`] keeps the pages those queries returned, keyed by
+//! the normalized query, and hands them back until a write could change them.
+//!
+//! Invalidation is driven by the storage backend returned from
+//! [`QueryCache::watch`]. After each node write or delete it drops exactly the
+//! entries that could see the node:
+//!
+//! - queries read through the session index depend on their sessions only, so
+//!   writes to other sessions, and to nodes outside any session (agents,
+//!   templates, tool invocations), leave them alone
+//! - full-scan queries depend on every session
+//! - a query with a node type filter and no predicate ignores nodes of other
+//!   types; predicates can look at other nodes (a session's tags, a
+//!   response's prompt), so queries with one depend on every type
+//!
+//! Edge and embedding writes never change node query results and are ignored.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::Config;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default().with_query_cache(1_000)).await?;
+//! let session = graph.create_session().await?;
+//!
+//! let first = graph.query().session(session.id).execute().await?;
+//! let again = graph.query().session(session.id).execute().await?; // served from cache
+//!
+//! let stats = graph.cache_stats().await;
+//! println!("query cache hit rate: {:.2}", stats.query_hit_rate());
+//! # Ok(())
+//! # }
+//! ```
+
+use super::cursor::{Cursor, Page};
+use super::explain::{AccessPath, NodeFilters};
+use super::predicate::Predicate;
+use crate::search::{SearchHit, TextQuery};
+use crate::storage::{AsyncStorageBackend, Lookup, StorageStats};
+use crate::vector::{SimilarNode, VectorFilter};
+use crate::Result;
+use crate::{Edge, EdgeId, Node, NodeId, NodeType, SessionId};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// What a cached result depends on
+#[derive(Debug, Clone)]
+struct Dependencies {
+    /// Sessions read through the index; `None` for a full scan
+    sessions: Option<Vec<SessionId>>,
+    /// The only node type that can change the result, if any
+    node_type: Option<NodeType>,
+}
+
+impl Dependencies {
+    fn affected_by(&self, session: Option<SessionId>, node_type: &NodeType) -> bool {
+        let session_matches = match (&self.sessions, session) {
+            (None, _) => true,
+            (Some(sessions), Some(session)) => sessions.contains(&session),
+            // Nodes outside every session never appear in the session index
+            (Some(_), None) => false,
+        };
+        session_matches && self.node_type.as_ref().is_none_or(|t| t == node_type)
+    }
+}
+
+struct Entry {
+    page: Page,
+    shape: String,
+    dependencies: Dependencies,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<String, Entry>,
+    /// Bumped by every invalidation, so results computed across a write are
+    /// not stored
+    generation: u64,
+    /// Logical clock for least-recently-used eviction
+    tick: u64,
+}
+
+/// A page served from the cache
+#[derive(Debug)]
+pub(crate) struct CachedPage {
+    pub(crate) page: Page,
+    pub(crate) shape: String,
+}
+
+/// Shared cache of query result pages
+///
+/// Hold it in an [`Arc`] and pass the same instance to
+/// [`AsyncQueryBuilder::cache`](super::AsyncQueryBuilder::cache) and
+/// [`QueryCache::watch`]. [`AsyncMemoryGraph`](crate::engine::AsyncMemoryGraph)
+/// does both when [`Config::query_cache_size`](crate::Config::query_cache_size)
+/// is non-zero.
+pub struct QueryCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl QueryCache {
+    /// Create a cache holding at most `capacity` result pages
+    ///
+    /// The least recently used page is evicted when the cache is full.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Wrap a backend so that its writes invalidate this cache
+    ///
+    /// Queries using the cache must read through the returned backend, or
+    /// through another handle whose writes all go through it.
+    pub fn watch(
+        self: &Arc<Self>,
+        backend: Arc<dyn AsyncStorageBackend>,
+    ) -> Arc<dyn AsyncStorageBackend> {
+        Arc::new(InvalidatingBackend {
+            inner: backend,
+            cache: Arc::clone(self),
+        })
+    }
+
+    /// Number of cached pages
+    pub fn len(&self) -> usize {
+        self.lock().map.len()
+    }
+
+    /// Whether the cache holds no pages
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Lookups that had to run the query
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Drop every cached page
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.generation += 1;
+        entries.map.clear();
+    }
+
+    /// Drop the pages that could include a node of `node_type` in `session`
+    pub fn invalidate(&self, session: Option<SessionId>, node_type: &NodeType) {
+        let mut entries = self.lock();
+        entries.generation += 1;
+        entries
+            .map
+            .retain(|_, entry| !entry.dependencies.affected_by(session, node_type));
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Entries> {
+        // A panic while holding the lock cannot leave the map inconsistent
+        self.entries
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Look up a page, counting the hit or miss
+    ///
+    /// On a miss, returns the generation to pass to [`insert`](Self::insert).
+    pub(crate) fn get(&self, key: &str) -> std::result::Result<CachedPage, u64> {
+        let mut entries = self.lock();
+        entries.tick += 1;
+        let tick = entries.tick;
+        let generation = entries.generation;
+        if let Some(entry) = entries.map.get_mut(key) {
+            entry.last_used = tick;
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            Ok(CachedPage {
+                page: entry.page.clone(),
+                shape: entry.shape.clone(),
+            })
+        } else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            Err(generation)
+        }
+    }
+
+    /// Store a page computed since `generation`, unless a write happened since
+    pub(crate) fn insert(
+        &self,
+        key: String,
+        filters: &NodeFilters<'_>,
+        access_path: &AccessPath,
+        page: &Page,
+        shape: String,
+        generation: u64,
+    ) {
+        let mut entries = self.lock();
+        if entries.generation != generation {
+            return;
+        }
+
+        let sessions = match access_path {
+            AccessPath::SessionIndex { sessions } => Some(sessions.clone()),
+            AccessPath::Lookup(_) | AccessPath::FullScan => None,
+            AccessPath::Empty => Some(Vec::new()),
+        };
+        // Predicates can depend on nodes of any type
+        let node_type = if filters.predicate.is_some() {
+            None
+        } else {
+            filters.node_type.cloned()
+        };
+
+        if entries.map.len() >= self.capacity && !entries.map.contains_key(&key) {
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.map.remove(&oldest);
+            }
+        }
+
+        entries.tick += 1;
+        let last_used = entries.tick;
+        entries.map.insert(
+            key,
+            Entry {
+                page: page.clone(),
+                shape,
+                dependencies: Dependencies {
+                    sessions,
+                    node_type,
+                },
+                last_used,
+            },
+        );
+    }
+}
+
+impl std::fmt::Debug for QueryCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryCache")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Cache key for a query: its filters and page position in a fixed order
+///
+/// Top-level AND terms are sorted, so adding the same filters in a different
+/// order hits the same entry.
+pub(crate) fn cache_key(
+    filters: &NodeFilters<'_>,
+    cursor: Option<&Cursor>,
+    offset: usize,
+    limit: Option<usize>,
+) -> String {
+    let predicate = filters.predicate.map(|p| {
+        let mut terms = match p {
+            Predicate::And(terms) => terms.iter().map(ToString::to_string).collect(),
+            other => vec![other.to_string()],
+        };
+        terms.sort();
+        terms.join(" AND ")
+    });
+    let show = |value: Option<String>| value.unwrap_or_default();
+
+    format!(
+        "session={}|predicate={}|type={}|start={}|end={}|cursor={}|offset={offset}|limit={}",
+        show(filters.session.map(|s| s.to_string())),
+        show(predicate),
+        show(filters.node_type.map(|t| format!("{t:?}"))),
+        show(filters.start.map(|t| t.to_rfc3339())),
+        show(filters.end.map(|t| t.to_rfc3339())),
+        show(cursor.map(Cursor::encode)),
+        show(limit.map(|l| l.to_string())),
+    )
+}
+
+/// Backend wrapper that invalidates a [`QueryCache`] after node writes
+struct InvalidatingBackend {
+    inner: Arc<dyn AsyncStorageBackend>,
+    cache: Arc<QueryCache>,
+}
+
+impl InvalidatingBackend {
+    /// The session a node is indexed under, looking up a response's prompt
+    ///
+    /// `batch` holds nodes written together with this one, whose prompts may
+    /// not be visible through `get_node` yet.
+    async fn session_of(&self, node: &Node, batch: &[Node]) -> Result<Option<SessionId>> {
+        Ok(match node {
+            Node::Session(session) => Some(session.id),
+            Node::Prompt(prompt) => Some(prompt.session_id),
+            Node::Response(response) => {
+                let in_batch = batch.iter().find(|n| n.id() == response.prompt_id);
+                match in_batch {
+                    Some(Node::Prompt(prompt)) => Some(prompt.session_id),
+                    _ => match self.inner.get_node(&response.prompt_id).await? {
+                        Some(Node::Prompt(prompt)) => Some(prompt.session_id),
+                        _ => None,
+                    },
+                }
+            }
+            Node::Custom(custom) => custom.session_id,
+            Node::ToolInvocation(_) | Node::Agent(_) | Node::Template(_) => None,
+        })
+    }
+
+    async fn invalidate(&self, node: &Node, batch: &[Node]) -> Result<()> {
+        let session = self.session_of(node, batch).await?;
+        self.cache.invalidate(session, &node.node_type());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncStorageBackend for InvalidatingBackend {
+    async fn store_node(&self, node: &Node) -> Result<()> {
+        self.inner.store_node(node).await?;
+        self.invalidate(node, &[]).await
+    }
+
+    async fn get_node(&self, id: &NodeId) -> Result<Option<Node>> {
+        self.inner.get_node(id).await
+    }
+
+    async fn delete_node(&self, id: &NodeId) -> Result<()> {
+        // Resolve the session while the node and its prompt still exist
+        let existing = match self.inner.get_node(id).await? {
+            Some(node) => {
+                let session = self.session_of(&node, &[]).await?;
+                Some((session, node.node_type()))
+            }
+            None => None,
+        };
+        self.inner.delete_node(id).await?;
+        if let Some((session, node_type)) = existing {
+            self.cache.invalidate(session, &node_type);
+        }
+        Ok(())
+    }
+
+    async fn store_edge(&self, edge: &Edge) -> Result<()> {
+        self.inner.store_edge(edge).await
+    }
+
+    async fn get_edge(&self, id: &EdgeId) -> Result<Option<Edge>> {
+        self.inner.get_edge(id).await
+    }
+
+    async fn delete_edge(&self, id: &EdgeId) -> Result<()> {
+        self.inner.delete_edge(id).await
+    }
+
+    async fn get_session_nodes(&self, session_id: &SessionId) -> Result<Vec<Node>> {
+        self.inner.get_session_nodes(session_id).await
+    }
+
+    async fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_outgoing_edges(node_id).await
+    }
+
+    async fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_incoming_edges(node_id).await
+    }
+
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        self.inner.scan_nodes().await
+    }
+
+    async fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        self.inner.search_text(query).await
+    }
+
+    async fn store_embedding(&self, node_id: &NodeId, vector: &[f32]) -> Result<()> {
+        self.inner.store_embedding(node_id, vector).await
+    }
+
+    async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.inner.get_embedding(node_id).await
+    }
+
+    async fn delete_embedding(&self, node_id: &NodeId) -> Result<()> {
+        self.inner.delete_embedding(node_id).await
+    }
+
+    async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        self.inner.similar_nodes(vector, k, filter).await
+    }
+
+    async fn write_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.inner.write_catalog(puts, deletes).await
+    }
+
+    async fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.inner.stage_catalog(puts, deletes).await
+    }
+
+    async fn insert_catalog_entry(&self, key: &[u8], value: &[u8]) -> Result<bool> {
+        self.inner.insert_catalog_entry(key, value).await
+    }
+
+    async fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.get_catalog_entry(key).await
+    }
+
+    async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner.scan_catalog(prefix).await
+    }
+
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.inner.lookup_nodes(lookup, after, limit).await
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn stats(&self) -> Result<StorageStats> {
+        self.inner.stats().await
+    }
+
+    async fn store_nodes_batch(&self, nodes: &[Node]) -> Result<Vec<NodeId>> {
+        let ids = self.inner.store_nodes_batch(nodes).await?;
+        for node in nodes {
+            self.invalidate(node, nodes).await?;
+        }
+        Ok(ids)
+    }
+
+    async fn store_edges_batch(&self, edges: &[Edge]) -> Result<Vec<EdgeId>> {
+        self.inner.store_edges_batch(edges).await
+    }
+
+    fn get_session_nodes_stream(
+        &self,
+        session_id: &SessionId,
+    ) -> std::pin::Pin<Box<dyn futures::stream::Stream<Item = Result<Node>> + Send + '_>> {
+        self.inner.get_session_nodes_stream(session_id)
+    }
+
+    async fn count_session_nodes(&self, session_id: &SessionId) -> Result<usize> {
+        self.inner.count_session_nodes(session_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::AsyncQueryBuilder;
+    use crate::storage::AsyncSledBackend;
+    use crate::{AgentNode, ConversationSession, PromptNode, ResponseNode, TokenUsage};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_cache_hits_and_precise_invalidation() {
+        let dir = tempdir().unwrap();
+        let cache = Arc::new(QueryCache::new(16));
+        let backend = cache.watch(Arc::new(AsyncSledBackend::open(dir.path()).await.unwrap()));
+
+        let a = ConversationSession::new();
+        let b = ConversationSession::new();
+        let prompt = PromptNode::new(a.id, "hello".to_string());
+        backend
+            .store_nodes_batch(&[
+                Node::Session(a.clone()),
+                Node::Session(b.clone()),
+                Node::Prompt(prompt.clone()),
+            ])
+            .await
+            .unwrap();
+
+        let query = |session: SessionId| {
+            AsyncQueryBuilder::new(Arc::clone(&backend))
+                .cache(Arc::clone(&cache))
+                .session(session)
+                .node_type(NodeType::Prompt)
+        };
+
+        assert_eq!(query(a.id).execute().await.unwrap().len(), 1);
+        let (nodes, stats) = query(a.id).execute_with_stats().await.unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.nodes_scanned, 0);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        query(b.id).execute().await.unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // Responses are of another type, agents are in no session
+        let response = ResponseNode::new(prompt.id, "hi".to_string(), TokenUsage::new(1, 1));
+        backend.store_node(&Node::Response(response)).await.unwrap();
+        backend
+            .store_node(&Node::Agent(AgentNode::new(
+                "a".to_string(),
+                "r".to_string(),
+                vec![],
+            )))
+            .await
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+
+        // A prompt in session B only drops B's entry
+        let other = PromptNode::new(b.id, "other".to_string());
+        backend
+            .store_node(&Node::Prompt(other.clone()))
+            .await
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+        assert_eq!(query(b.id).execute().await.unwrap().len(), 1);
+
+        backend.delete_node(&other.id).await.unwrap();
+        assert!(query(b.id).execute().await.unwrap().is_empty());
+        assert_eq!(cache.misses(), 4);
+    }
+
+    #[test]
+    fn test_cache_key_normalizes_predicates() {
+        use crate::query::Field;
+
+        let first = Field::TotalTokens
+            .gt(10)
+            .and(Predicate::session_tag("prod"));
+        let second = Predicate::session_tag("prod").and(Field::TotalTokens.gt(10));
+        let key = |predicate| {
+            let filters = NodeFilters {
+                session: None,
+                predicate: Some(predicate),
+                node_type: None,
+                start: None,
+                end: None,
+            };
+            cache_key(&filters, None, 0, Some(5))
+        };
+        assert_eq!(key(&first), key(&second));
+        assert_ne!(key(&first), key(&Field::TotalTokens.gt(11)));
+    }
+
+    #[test]
+    fn test_eviction_and_stale_generation() {
+        let cache = QueryCache::new(1);
+        let filters = NodeFilters {
+            session: None,
+            predicate: None,
+            node_type: None,
+            start: None,
+            end: None,
+        };
+        let page = Page {
+            items: Vec::new(),
+            next_cursor: None,
+        };
+        let path = AccessPath::FullScan;
+
+        let generation = cache.get("a").unwrap_err();
+        cache.insert(
+            "a".to_string(),
+            &filters,
+            &path,
+            &page,
+            String::new(),
+            generation,
+        );
+        cache.insert(
+            "b".to_string(),
+            &filters,
+            &path,
+            &page,
+            String::new(),
+            generation,
+        );
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("b").is_ok());
+
+        // A write between lookup and insert keeps the result out of the cache
+        let generation = cache.get("c").unwrap_err();
+        cache.invalidate(None, &NodeType::Agent);
+        cache.insert(
+            "c".to_string(),
+            &filters,
+            &path,
+            &page,
+            String::new(),
+            generation,
+        );
+        assert!(cache.is_empty());
+    }
+}
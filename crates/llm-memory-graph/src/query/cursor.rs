@@ -0,0 +1,262 @@
+//! Opaque cursors for stable pagination
+//!
+//! Query results are ordered newest first by timestamp, with ties broken by
+//! node ID. A [`Cursor`] records the position of the last row of a page, so
+//! the next page starts strictly after it no matter how many nodes were added
+//! in between. Unlike an offset, new rows at the head of the result never
+//! shift later pages, and resuming does not walk over the skipped rows again.
+
+use crate::search::node_timestamp;
+use crate::{Error, Result};
+use crate::{Node, NodeId};
+use chrono::{DateTime, Utc};
+use std::cmp::Ordering;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Leading byte of an encoded cursor, bumped if the layout changes
+const CURSOR_VERSION: u8 = 1;
+
+/// Encoded length: version, seconds, nanoseconds and node ID
+const CURSOR_LEN: usize = 1 + 8 + 4 + 16;
+
+/// Position after the last row of a page
+///
+/// Cursors are exchanged as opaque strings; use [`Cursor::encode`] (or
+/// `to_string()`) and [`Cursor::decode`] (or `parse()`).
+///
+/// # Examples
+///
+/// ```no_run
+/// # use llm_memory_graph::engine::AsyncMemoryGraph;
+/// # use llm_memory_graph::SessionId;
+/// # async fn example(graph: AsyncMemoryGraph, session_id: SessionId) -> Result<(), Box<dyn std::error::Error>> {
+/// let first = graph.query().session(session_id).limit(50).execute_page().await?;
+/// if let Some(cursor) = first.next_cursor {
+///     let token = cursor.encode();
+///     // ... later, possibly in another process
+///     let second = graph
+///         .query()
+///         .session(session_id)
+///         .cursor(token.parse()?)
+///         .limit(50)
+///         .execute_page()
+///         .await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    timestamp: DateTime<Utc>,
+    node_id: NodeId,
+}
+
+impl Cursor {
+    /// Create a cursor positioned at the given row
+    #[must_use]
+    pub const fn new(timestamp: DateTime<Utc>, node_id: NodeId) -> Self {
+        Self { timestamp, node_id }
+    }
+
+    /// Create a cursor positioned at `node`
+    #[must_use]
+    pub fn at(node: &Node) -> Self {
+        Self::new(node_timestamp(node), node.id())
+    }
+
+    /// Timestamp of the row the cursor points at
+    #[must_use]
+    pub const fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    /// ID of the node the cursor points at
+    #[must_use]
+    pub const fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Encode the cursor as an opaque, URL-safe token
+    #[must_use]
+    pub fn encode(&self) -> String {
+        let mut bytes = Vec::with_capacity(CURSOR_LEN);
+        bytes.push(CURSOR_VERSION);
+        bytes.extend_from_slice(&self.timestamp.timestamp().to_be_bytes());
+        bytes.extend_from_slice(&self.timestamp.timestamp_subsec_nanos().to_be_bytes());
+        bytes.extend_from_slice(&self.node_id.to_bytes());
+        bytes
+            .iter()
+            .fold(String::with_capacity(CURSOR_LEN * 2), |mut token, b| {
+                let _ = write!(token, "{b:02x}");
+                token
+            })
+    }
+
+    /// Decode a token produced by [`encode`](Self::encode)
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the token is malformed.
+    pub fn decode(token: &str) -> Result<Self> {
+        let invalid = || Error::ValidationError(format!("Invalid page cursor: {token:?}"));
+
+        if token.len() != CURSOR_LEN * 2 || !token.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..CURSOR_LEN)
+            .map(|i| u8::from_str_radix(&token[2 * i..2 * i + 2], 16))
+            .collect::<std::result::Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        if bytes[0] != CURSOR_VERSION {
+            return Err(invalid());
+        }
+
+        let secs = i64::from_be_bytes(bytes[1..9].try_into().map_err(|_| invalid())?);
+        let nanos = u32::from_be_bytes(bytes[9..13].try_into().map_err(|_| invalid())?);
+        let node_id = NodeId::from_bytes(bytes[13..].try_into().map_err(|_| invalid())?);
+        let timestamp = DateTime::from_timestamp(secs, nanos).ok_or_else(invalid)?;
+
+        Ok(Self::new(timestamp, node_id))
+    }
+
+    /// Whether `node` comes after the cursor in result order
+    pub(crate) fn precedes(&self, node: &Node) -> bool {
+        compare_positions(
+            (self.timestamp, self.node_id),
+            (node_timestamp(node), node.id()),
+        ) == Ordering::Less
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::decode(s)
+    }
+}
+
+/// One page of query results
+#[derive(Debug, Clone)]
+pub struct Page<T = Node> {
+    /// Rows on this page
+    pub items: Vec<T>,
+    /// Cursor for the next page, or `None` if this is the last page
+    pub next_cursor: Option<Cursor>,
+}
+
+impl Page {
+    /// Keep the nodes `f` maps to a value, in order
+    ///
+    /// The next cursor is unchanged, so paging continues after the last row
+    /// the backend returned.
+    pub(crate) fn filter_map<T>(self, f: impl FnMut(Node) -> Option<T>) -> Page<T> {
+        Page {
+            items: self.items.into_iter().filter_map(f).collect(),
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+/// Order two rows newest first, breaking timestamp ties by node ID
+fn compare_positions(a: (DateTime<Utc>, NodeId), b: (DateTime<Utc>, NodeId)) -> Ordering {
+    b.0.cmp(&a.0)
+        .then_with(|| b.1.to_bytes().cmp(&a.1.to_bytes()))
+}
+
+/// Sort nodes into result order
+pub(crate) fn sort_newest_first(nodes: &mut [Node]) {
+    nodes.sort_by_cached_key(|node| {
+        (
+            std::cmp::Reverse(node_timestamp(node)),
+            std::cmp::Reverse(node.id().to_bytes()),
+        )
+    });
+}
+
+/// Cut one page out of sorted nodes
+///
+/// Rows at or before `cursor` are dropped, then `offset` rows are skipped and
+/// at most `limit` returned. A next cursor is only produced when rows remain.
+pub(crate) fn paginate(
+    nodes: Vec<Node>,
+    cursor: Option<&Cursor>,
+    offset: usize,
+    limit: Option<usize>,
+) -> Page {
+    let start = match cursor {
+        Some(cursor) => nodes.partition_point(|node| !cursor.precedes(node)),
+        None => 0,
+    };
+    let mut items: Vec<Node> = nodes.into_iter().skip(start).skip(offset).collect();
+
+    let next_cursor = match limit {
+        Some(limit) if items.len() > limit => {
+            items.truncate(limit);
+            items.last().map(Cursor::at)
+        }
+        _ => None,
+    };
+
+    Page { items, next_cursor }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PromptNode, SessionId};
+    use chrono::Duration;
+
+    fn prompt_at(timestamp: DateTime<Utc>) -> Node {
+        let mut prompt = PromptNode::new(SessionId::new(), "p".to_string());
+        prompt.timestamp = timestamp;
+        Node::Prompt(prompt)
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = Cursor::new(Utc::now(), NodeId::new());
+        let token = cursor.encode();
+        assert_eq!(token.parse::<Cursor>().unwrap(), cursor);
+
+        assert!(Cursor::decode("").is_err());
+        assert!(Cursor::decode(&token[1..]).is_err());
+        assert!(Cursor::decode(&format!("02{}", &token[2..])).is_err());
+        assert!(Cursor::decode(&"zz".repeat(CURSOR_LEN)).is_err());
+    }
+
+    #[test]
+    fn test_paginate_is_stable_under_inserts() {
+        let base = Utc::now();
+        let mut nodes: Vec<Node> = (0..5)
+            .map(|i| prompt_at(base - Duration::seconds(i)))
+            .collect();
+        // Two rows share a timestamp, so the tie-break decides their order
+        nodes.push(prompt_at(base - Duration::seconds(2)));
+        sort_newest_first(&mut nodes);
+
+        let first = paginate(nodes.clone(), None, 0, Some(3));
+        assert_eq!(first.items.len(), 3);
+        let cursor = first.next_cursor.unwrap();
+
+        // A newer row arriving between pages must not shift the next page
+        nodes.push(prompt_at(base + Duration::seconds(10)));
+        sort_newest_first(&mut nodes);
+
+        let second = paginate(nodes, Some(&cursor), 0, Some(3));
+        assert_eq!(second.items.len(), 3);
+        assert!(second.next_cursor.is_none());
+
+        let mut seen: Vec<NodeId> = first.items.iter().map(Node::id).collect();
+        seen.extend(second.items.iter().map(Node::id));
+        let unique: std::collections::HashSet<_> = seen.iter().collect();
+        assert_eq!(unique.len(), 6);
+    }
+}
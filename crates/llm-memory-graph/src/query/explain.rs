@@ -0,0 +1,606 @@
+//! Query explain plans and execution statistics
+//!
+//! [`QueryBuilder::explain`](super::QueryBuilder::explain) and
+//! [`AsyncQueryBuilder::explain`](super::AsyncQueryBuilder::explain) describe
+//! how a query would run without running it: the [`AccessPath`] used to fetch
+//! candidate nodes, the filters that path answers, the filters applied to the
+//! fetched nodes afterwards, and estimated row counts.
+//!
+//! The `execute_with_stats` methods run the query and report what actually
+//! happened in a [`QueryStats`]: nodes read from storage, rows returned and the
+//! time spent in each stage. [`QueryStats::shape`] names the plan with a small,
+//! fixed vocabulary such as `session_index+node_type`, which keeps it usable as
+//! a metric label.
+
+use super::cursor;
+use super::predicate::{self, Comparison, Field, FieldValue, Predicate};
+use crate::search::node_timestamp;
+use crate::storage::{Lookup, StorageStats};
+use crate::{Node, NodeType, SessionId, TemplateId};
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// How a query fetches its candidate nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessPath {
+    /// Read the nodes of these sessions through the session index
+    SessionIndex {
+        /// Sessions whose nodes are read
+        sessions: Vec<SessionId>,
+    },
+    /// Read the nodes listed under a reverse lookup
+    Lookup(Lookup),
+    /// Read and decode every stored node
+    FullScan,
+    /// Nothing narrows the query, so no nodes are read
+    Empty,
+}
+
+impl AccessPath {
+    /// Short name used in plan shapes
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccessPath::SessionIndex { .. } => "session_index",
+            AccessPath::Lookup(_) => "lookup",
+            AccessPath::FullScan => "full_scan",
+            AccessPath::Empty => "empty",
+        }
+    }
+
+    /// Rough number of nodes this path reads, from storage statistics
+    ///
+    /// The session index estimate assumes nodes are spread evenly over sessions.
+    /// The lookup index keeps no per-key counts, so its estimate is the upper
+    /// bound of every node.
+    pub fn estimate_scanned(&self, stats: &StorageStats) -> u64 {
+        match self {
+            AccessPath::SessionIndex { sessions } => {
+                let per_session = stats.node_count.div_ceil(stats.session_count.max(1));
+                (per_session * sessions.len() as u64).min(stats.node_count)
+            }
+            AccessPath::Lookup(_) | AccessPath::FullScan => stats.node_count,
+            AccessPath::Empty => 0,
+        }
+    }
+}
+
+impl fmt::Display for AccessPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccessPath::SessionIndex { sessions } => {
+                let ids = sessions
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "SessionIndex({ids})")
+            }
+            AccessPath::Lookup(lookup) => write!(f, "Lookup({lookup:?})"),
+            AccessPath::FullScan => f.write_str("FullScan"),
+            AccessPath::Empty => f.write_str("Empty"),
+        }
+    }
+}
+
+/// A filter in an explain plan
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanFilter {
+    /// Node belongs to this session
+    Session(SessionId),
+    /// Node matches a predicate
+    Predicate(Predicate),
+    /// Node has this type
+    NodeType(NodeType),
+    /// Node timestamp falls within the bounds (inclusive)
+    TimeRange {
+        /// Earliest timestamp
+        start: Option<DateTime<Utc>>,
+        /// Latest timestamp
+        end: Option<DateTime<Utc>>,
+    },
+}
+
+impl PlanFilter {
+    /// Short name used in plan shapes and stage statistics
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlanFilter::Session(_) => "session",
+            PlanFilter::Predicate(_) => "predicate",
+            PlanFilter::NodeType(_) => "node_type",
+            PlanFilter::TimeRange { .. } => "time_range",
+        }
+    }
+}
+
+impl fmt::Display for PlanFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlanFilter::Session(id) => write!(f, "session = {id}"),
+            PlanFilter::Predicate(predicate) => write!(f, "{predicate}"),
+            PlanFilter::NodeType(node_type) => write!(f, "node_type = {node_type:?}"),
+            PlanFilter::TimeRange { start, end } => {
+                let bound = |b: &Option<DateTime<Utc>>| {
+                    b.map_or_else(|| "..".to_string(), |t| t.to_rfc3339())
+                };
+                write!(f, "timestamp in [{}, {}]", bound(start), bound(end))
+            }
+        }
+    }
+}
+
+/// How a query would run, as returned by `explain()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explain {
+    /// How candidate nodes are fetched
+    pub access_path: AccessPath,
+    /// Filters answered by the access path before nodes are decoded
+    pub pre_fetch_filters: Vec<PlanFilter>,
+    /// Filters applied to fetched nodes, in order
+    pub post_fetch_filters: Vec<PlanFilter>,
+    /// Estimated nodes read from storage
+    pub estimated_scanned: u64,
+    /// Upper bound on returned rows: the estimated scan, capped by the limit
+    pub estimated_rows: u64,
+    /// Whether the page resumes after a cursor
+    pub cursor: bool,
+    /// Rows skipped after sorting
+    pub offset: usize,
+    /// Maximum rows returned
+    pub limit: Option<usize>,
+}
+
+impl Explain {
+    /// Bounded-cardinality name for the plan, e.g. `full_scan+predicate`
+    pub fn shape(&self) -> String {
+        plan_shape(&self.access_path, &self.post_fetch_filters)
+    }
+}
+
+impl fmt::Display for Explain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} (estimated {} scanned)",
+            self.access_path, self.estimated_scanned
+        )?;
+        for filter in &self.pre_fetch_filters {
+            writeln!(f, "  IndexFilter({filter})")?;
+        }
+        for filter in &self.post_fetch_filters {
+            writeln!(f, "Filter({filter})")?;
+        }
+        f.write_str("Sort(newest first)")?;
+        if self.cursor {
+            f.write_str("\nAfterCursor")?;
+        }
+        if self.offset > 0 {
+            write!(f, "\nSkip({})", self.offset)?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, "\nLimit({limit})")?;
+        }
+        write!(f, "\nestimated rows: {}", self.estimated_rows)
+    }
+}
+
+/// Timing and row counts for one stage of an executed query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageStats {
+    /// Stage name: `fetch`, a filter name, `sort`, `paginate` or `cache`
+    pub name: &'static str,
+    /// Rows entering the stage
+    pub rows_in: usize,
+    /// Rows leaving the stage
+    pub rows_out: usize,
+    /// Time spent in the stage
+    pub elapsed: Duration,
+}
+
+/// What happened while executing a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryStats {
+    /// Bounded-cardinality name for the executed plan
+    pub shape: String,
+    /// Node records read from storage
+    pub nodes_scanned: usize,
+    /// Nodes decoded from those records
+    pub nodes_deserialized: usize,
+    /// Rows returned to the caller
+    pub nodes_returned: usize,
+    /// Nodes served from a cache instead of storage
+    pub cache_hits: usize,
+    /// Per-stage breakdown, in execution order
+    pub stages: Vec<StageStats>,
+    /// Total execution time
+    pub elapsed: Duration,
+}
+
+impl QueryStats {
+    /// Statistics for the named stage, if it ran
+    pub fn stage(&self, name: &str) -> Option<&StageStats> {
+        self.stages.iter().find(|s| s.name == name)
+    }
+}
+
+/// Name a plan by its access path and post-fetch filter kinds
+pub(crate) fn plan_shape(access_path: &AccessPath, post_fetch: &[PlanFilter]) -> String {
+    std::iter::once(access_path.name())
+        .chain(post_fetch.iter().map(PlanFilter::name))
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Collects [`QueryStats`] while a query runs
+pub(crate) struct StatsRecorder {
+    started: Instant,
+    mark: Instant,
+    stats: QueryStats,
+}
+
+impl StatsRecorder {
+    pub(crate) fn new(shape: String) -> Self {
+        let now = Instant::now();
+        Self {
+            started: now,
+            mark: now,
+            stats: QueryStats {
+                shape,
+                nodes_scanned: 0,
+                nodes_deserialized: 0,
+                nodes_returned: 0,
+                cache_hits: 0,
+                stages: Vec::new(),
+                elapsed: Duration::ZERO,
+            },
+        }
+    }
+
+    /// Count nodes read and decoded from storage
+    pub(crate) fn fetched(&mut self, count: usize) {
+        self.stats.nodes_scanned += count;
+        self.stats.nodes_deserialized += count;
+    }
+
+    /// Count nodes served from a cache
+    pub(crate) fn cache_hit(&mut self) {
+        self.cache_hits(1);
+    }
+
+    /// Count several nodes served from a cache
+    pub(crate) fn cache_hits(&mut self, count: usize) {
+        self.stats.cache_hits += count;
+    }
+
+    /// Close the current stage, timing it from the end of the previous one
+    pub(crate) fn stage(&mut self, name: &'static str, rows_in: usize, rows_out: usize) {
+        let now = Instant::now();
+        self.stats.stages.push(StageStats {
+            name,
+            rows_in,
+            rows_out,
+            elapsed: now - self.mark,
+        });
+        self.mark = now;
+    }
+
+    pub(crate) fn finish(mut self, returned: usize) -> QueryStats {
+        self.stats.nodes_returned = returned;
+        self.stats.elapsed = self.started.elapsed();
+        self.stats
+    }
+}
+
+/// The filters shared by [`QueryBuilder`](super::QueryBuilder) and
+/// [`AsyncQueryBuilder`](super::AsyncQueryBuilder), planned the same way for both
+pub(crate) struct NodeFilters<'q> {
+    pub(crate) session: Option<SessionId>,
+    pub(crate) predicate: Option<&'q Predicate>,
+    pub(crate) node_type: Option<&'q NodeType>,
+    pub(crate) start: Option<DateTime<Utc>>,
+    pub(crate) end: Option<DateTime<Utc>>,
+}
+
+impl NodeFilters<'_> {
+    /// Choose the access path and split the filters around the fetch
+    ///
+    /// The session index is used for a session filter, or for a predicate that
+    /// confines results to known sessions. A predicate without such a scope
+    /// reads a reverse lookup when it requires an indexed field to equal a
+    /// value (see [`index_lookup`]), and needs a full scan otherwise. With no
+    /// session or predicate nothing is read, unless `scan_all` asks for the
+    /// whole graph.
+    pub(crate) fn plan(&self, scan_all: bool) -> (AccessPath, Vec<PlanFilter>, Vec<PlanFilter>) {
+        let mut pre_fetch = Vec::new();
+        let mut post_fetch = Vec::new();
+
+        let access_path = if let Some(session) = self.session {
+            pre_fetch.push(PlanFilter::Session(session));
+            AccessPath::SessionIndex {
+                sessions: vec![session],
+            }
+        } else if let Some(predicate) = self.predicate {
+            match predicate.session_scope() {
+                Some(scope) => {
+                    // Sorted so plans for the same query compare and print alike
+                    let mut sessions: Vec<SessionId> = scope.into_iter().collect();
+                    sessions.sort_by_key(ToString::to_string);
+                    pre_fetch.extend(sessions.iter().copied().map(PlanFilter::Session));
+                    AccessPath::SessionIndex { sessions }
+                }
+                None => match index_lookup(predicate, self.node_type) {
+                    Some((lookup, term)) => {
+                        pre_fetch.push(PlanFilter::Predicate(term.clone()));
+                        AccessPath::Lookup(lookup)
+                    }
+                    None => AccessPath::FullScan,
+                },
+            }
+        } else if scan_all {
+            AccessPath::FullScan
+        } else {
+            AccessPath::Empty
+        };
+
+        if let Some(predicate) = self.predicate {
+            post_fetch.push(PlanFilter::Predicate(predicate.clone()));
+        }
+        if let Some(node_type) = self.node_type {
+            post_fetch.push(PlanFilter::NodeType(node_type.clone()));
+        }
+        if self.start.is_some() || self.end.is_some() {
+            post_fetch.push(PlanFilter::TimeRange {
+                start: self.start,
+                end: self.end,
+            });
+        }
+
+        (access_path, pre_fetch, post_fetch)
+    }
+
+    /// Build an explain plan, estimating rows from storage statistics
+    pub(crate) fn explain(
+        &self,
+        stats: &StorageStats,
+        cursor: bool,
+        offset: usize,
+        limit: Option<usize>,
+    ) -> Explain {
+        let (access_path, pre_fetch_filters, post_fetch_filters) = self.plan(false);
+        let estimated_scanned = access_path.estimate_scanned(stats);
+        let estimated_rows = estimated_scanned.saturating_sub(offset as u64);
+        let estimated_rows = limit.map_or(estimated_rows, |l| estimated_rows.min(l as u64));
+
+        Explain {
+            access_path,
+            pre_fetch_filters,
+            post_fetch_filters,
+            estimated_scanned,
+            estimated_rows,
+            cursor,
+            offset,
+            limit,
+        }
+    }
+
+    /// Apply the post-fetch filters, recording a stage for each
+    pub(crate) fn apply(&self, mut nodes: Vec<Node>, recorder: &mut StatsRecorder) -> Vec<Node> {
+        if let Some(predicate) = self.predicate {
+            let rows_in = nodes.len();
+            predicate::retain_matching(&mut nodes, predicate);
+            recorder.stage("predicate", rows_in, nodes.len());
+        }
+
+        if let Some(node_type) = self.node_type {
+            let rows_in = nodes.len();
+            nodes.retain(|node| node.node_type() == *node_type);
+            recorder.stage("node_type", rows_in, nodes.len());
+        }
+
+        if self.start.is_some() || self.end.is_some() {
+            let rows_in = nodes.len();
+            nodes.retain(|node| {
+                let timestamp = node_timestamp(node);
+                self.start.is_none_or(|start| timestamp >= start)
+                    && self.end.is_none_or(|end| timestamp <= end)
+            });
+            recorder.stage("time_range", rows_in, nodes.len());
+        }
+
+        nodes
+    }
+}
+
+/// Find a conjunct of `predicate` that a reverse lookup answers
+///
+/// Equality on [`Field::ToolName`], [`Field::Kind`] or [`Field::TemplateId`]
+/// maps directly to a lookup. [`Field::Model`] is also an agent field, which
+/// the model lookup does not cover, so it is only used when the node type
+/// filter or another conjunct limits results to prompts or responses. Agent
+/// lookups follow `HandledBy` edges and have no predicate to plan from.
+fn index_lookup<'p>(
+    predicate: &'p Predicate,
+    node_type: Option<&NodeType>,
+) -> Option<(Lookup, &'p Predicate)> {
+    let terms = match predicate {
+        Predicate::And(terms) => terms.as_slice(),
+        term => std::slice::from_ref(term),
+    };
+    let excludes_agents = |t: &NodeType| matches!(t, NodeType::Prompt | NodeType::Response);
+    let model_indexed = node_type.is_some_and(excludes_agents)
+        || terms
+            .iter()
+            .any(|term| matches!(term, Predicate::NodeType(t) if excludes_agents(t)));
+
+    terms.iter().find_map(|term| {
+        let Predicate::Compare {
+            field,
+            op: Comparison::Eq,
+            value: FieldValue::String(value),
+        } = term
+        else {
+            return None;
+        };
+        let lookup = match field {
+            Field::ToolName => Lookup::ToolName(value.clone()),
+            Field::Kind => Lookup::Kind(value.clone()),
+            Field::Model if model_indexed => Lookup::Model(value.clone()),
+            Field::TemplateId => Lookup::Template(TemplateId::from_uuid(value.parse().ok()?)),
+            _ => return None,
+        };
+        Some((lookup, term))
+    })
+}
+
+/// Sort filtered nodes and cut out the requested page
+pub(crate) fn sort_and_paginate(
+    mut nodes: Vec<Node>,
+    after: Option<&cursor::Cursor>,
+    offset: usize,
+    limit: Option<usize>,
+    recorder: &mut StatsRecorder,
+) -> cursor::Page {
+    let rows = nodes.len();
+    cursor::sort_newest_first(&mut nodes);
+    recorder.stage("sort", rows, rows);
+
+    let page = cursor::paginate(nodes, after, offset, limit);
+    recorder.stage("paginate", rows, page.items.len());
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::Field;
+
+    fn stats(node_count: u64, session_count: u64) -> StorageStats {
+        StorageStats {
+            node_count,
+            edge_count: 0,
+            storage_bytes: 0,
+            session_count,
+        }
+    }
+
+    #[test]
+    fn test_plan_chooses_access_path() {
+        let session = SessionId::new();
+        let scoped = Predicate::Session(session).and(Field::TotalTokens.gt(10));
+        let unscoped = Field::TotalTokens.gt(10);
+        let filters = |session, predicate| NodeFilters {
+            session,
+            predicate,
+            node_type: Some(&NodeType::Response),
+            start: None,
+            end: None,
+        };
+
+        let (path, pre, post) = filters(Some(session), None).plan(false);
+        assert_eq!(
+            path,
+            AccessPath::SessionIndex {
+                sessions: vec![session]
+            }
+        );
+        assert_eq!(pre, vec![PlanFilter::Session(session)]);
+        assert_eq!(plan_shape(&path, &post), "session_index+node_type");
+
+        let (path, pre, post) = filters(None, Some(&scoped)).plan(false);
+        assert_eq!(path.name(), "session_index");
+        assert_eq!(pre.len(), 1);
+        assert_eq!(
+            plan_shape(&path, &post),
+            "session_index+predicate+node_type"
+        );
+
+        let (path, pre, _) = filters(None, Some(&unscoped)).plan(false);
+        assert_eq!(path, AccessPath::FullScan);
+        assert!(pre.is_empty());
+
+        assert_eq!(filters(None, None).plan(false).0, AccessPath::Empty);
+        assert_eq!(filters(None, None).plan(true).0, AccessPath::FullScan);
+    }
+
+    #[test]
+    fn test_plan_chooses_lookup_for_indexed_equality() {
+        let filters = |predicate, node_type| NodeFilters {
+            session: None,
+            predicate: Some(predicate),
+            node_type,
+            start: None,
+            end: None,
+        };
+
+        let tool = Field::ToolName
+            .eq("search")
+            .and(Field::ToolSuccess.eq(true));
+        let (path, pre, post) = filters(&tool, None).plan(false);
+        assert_eq!(
+            path,
+            AccessPath::Lookup(Lookup::ToolName("search".to_string()))
+        );
+        assert_eq!(
+            pre,
+            vec![PlanFilter::Predicate(Field::ToolName.eq("search"))]
+        );
+        assert_eq!(plan_shape(&path, &post), "lookup+predicate");
+
+        let explain = filters(&tool, None).explain(&stats(100, 4), false, 0, None);
+        let text = explain.to_string();
+        assert!(text.starts_with("Lookup(ToolName(\"search\")) (estimated 100 scanned)"));
+        assert!(text.contains("IndexFilter(ToolName = 'search')"));
+
+        let template = TemplateId::new();
+        let by_template = Field::TemplateId.eq(template.to_string());
+        assert_eq!(
+            filters(&by_template, None).plan(false).0,
+            AccessPath::Lookup(Lookup::Template(template))
+        );
+
+        // Agents carry a model too, so the model lookup needs a type that excludes them
+        let model = Field::Model.eq("gpt-4");
+        assert_eq!(filters(&model, None).plan(false).0, AccessPath::FullScan);
+        assert_eq!(
+            filters(&model, Some(&NodeType::Response)).plan(false).0,
+            AccessPath::Lookup(Lookup::Model("gpt-4".to_string()))
+        );
+        let typed = Predicate::node_type(NodeType::Prompt).and(model.clone());
+        assert_eq!(
+            filters(&typed, None).plan(false).0,
+            AccessPath::Lookup(Lookup::Model("gpt-4".to_string()))
+        );
+
+        // Only equality under a conjunction narrows the candidates
+        let either = Field::ToolName.eq("search").or(Field::ToolName.eq("fetch"));
+        assert_eq!(filters(&either, None).plan(false).0, AccessPath::FullScan);
+        let prefix = Field::ToolName.starts_with("sea");
+        assert_eq!(filters(&prefix, None).plan(false).0, AccessPath::FullScan);
+    }
+
+    #[test]
+    fn test_explain_estimates() {
+        let session = SessionId::new();
+        let filters = NodeFilters {
+            session: Some(session),
+            predicate: None,
+            node_type: None,
+            start: Some(Utc::now()),
+            end: None,
+        };
+
+        let explain = filters.explain(&stats(100, 4), false, 0, None);
+        assert_eq!(explain.estimated_scanned, 25);
+        assert_eq!(explain.estimated_rows, 25);
+
+        let explain = filters.explain(&stats(100, 4), true, 0, Some(10));
+        assert_eq!(explain.estimated_rows, 10);
+
+        let text = explain.to_string();
+        assert!(text.starts_with(&format!("SessionIndex({session})")));
+        assert!(text.contains("IndexFilter(session = "));
+        assert!(text.contains("Filter(timestamp in ["));
+        assert!(text.contains("AfterCursor"));
+        assert!(text.ends_with("estimated rows: 10"));
+    }
+}
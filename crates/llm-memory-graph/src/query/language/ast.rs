@@ -0,0 +1,257 @@
+//! Abstract syntax tree for the graph query language
+
+use crate::{EdgeType, NodeType};
+use serde_json::Value;
+use std::fmt;
+
+/// A parsed `MATCH ... WHERE ... RETURN ...` query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Comma-separated patterns from the MATCH clause
+    pub patterns: Vec<Pattern>,
+    /// Optional WHERE predicate
+    pub where_clause: Option<Expr>,
+    /// Whether RETURN DISTINCT was requested
+    pub distinct: bool,
+    /// Projections from the RETURN clause
+    pub returns: Vec<Projection>,
+    /// ORDER BY items, applied in order
+    pub order_by: Vec<OrderItem>,
+    /// Number of rows to skip
+    pub skip: Option<usize>,
+    /// Maximum number of rows to return
+    pub limit: Option<usize>,
+}
+
+/// A chain of node patterns connected by relationship patterns
+///
+/// `(a)-[r]->(b)<-[s]-(c)` has `start = a` and two steps.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    /// First node in the chain
+    pub start: NodePattern,
+    /// Subsequent relationship/node pairs
+    pub steps: Vec<(RelPattern, NodePattern)>,
+}
+
+impl Pattern {
+    /// All node patterns in chain order
+    pub fn nodes(&self) -> Vec<&NodePattern> {
+        std::iter::once(&self.start)
+            .chain(self.steps.iter().map(|(_, node)| node))
+            .collect()
+    }
+}
+
+/// A node pattern such as `(p:Prompt {id: '...'})`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodePattern {
+    /// Variable name; anonymous patterns receive a generated name
+    pub variable: String,
+    /// Optional node type label
+    pub label: Option<NodeType>,
+    /// Inline property equality constraints
+    pub properties: Vec<(String, Value)>,
+}
+
+/// Direction of a relationship pattern relative to how it is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// `(a)-[]->(b)`
+    Outgoing,
+    /// `(a)<-[]-(b)`
+    Incoming,
+    /// `(a)-[]-(b)`
+    Both,
+}
+
+impl Direction {
+    /// The same relationship traversed from the other end
+    #[must_use]
+    pub fn reversed(self) -> Self {
+        match self {
+            Direction::Outgoing => Direction::Incoming,
+            Direction::Incoming => Direction::Outgoing,
+            Direction::Both => Direction::Both,
+        }
+    }
+}
+
+/// A relationship pattern such as `-[e:INVOKES|HANDLED_BY]->`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelPattern {
+    /// Variable name; anonymous patterns receive a generated name
+    pub variable: String,
+    /// Allowed edge types; empty means any type
+    pub edge_types: Vec<EdgeType>,
+    /// Direction as written
+    pub direction: Direction,
+}
+
+/// Comparison operators usable in predicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `=`
+    Eq,
+    /// `<>` or `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `CONTAINS` (substring or list membership)
+    Contains,
+    /// `STARTS WITH`
+    StartsWith,
+    /// `ENDS WITH`
+    EndsWith,
+    /// `IN` (membership in a list literal)
+    In,
+    /// `=~` (regular expression match)
+    Matches,
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "<>",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+            CompareOp::Contains => "CONTAINS",
+            CompareOp::StartsWith => "STARTS WITH",
+            CompareOp::EndsWith => "ENDS WITH",
+            CompareOp::In => "IN",
+            CompareOp::Matches => "=~",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Expressions used in WHERE, RETURN and ORDER BY
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// A literal value
+    Literal(Value),
+    /// A bound node or edge
+    Variable(String),
+    /// A (possibly nested) field of a bound node or edge, e.g. `p.metadata.model`
+    Property {
+        /// Variable the property belongs to
+        variable: String,
+        /// Field path below the variable
+        path: Vec<String>,
+    },
+    /// Logical negation
+    Not(Box<Expr>),
+    /// Logical conjunction
+    And(Box<Expr>, Box<Expr>),
+    /// Logical disjunction
+    Or(Box<Expr>, Box<Expr>),
+    /// Binary comparison
+    Compare {
+        /// Left operand
+        left: Box<Expr>,
+        /// Operator
+        op: CompareOp,
+        /// Right operand
+        right: Box<Expr>,
+    },
+    /// `IS NULL` / `IS NOT NULL`
+    IsNull {
+        /// Tested expression
+        expr: Box<Expr>,
+        /// True for `IS NOT NULL`
+        negated: bool,
+    },
+}
+
+impl Expr {
+    /// Variables referenced anywhere in this expression
+    pub fn variables(&self) -> Vec<&str> {
+        let mut vars = Vec::new();
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables<'a>(&'a self, vars: &mut Vec<&'a str>) {
+        match self {
+            Expr::Literal(_) => {}
+            Expr::Variable(v) | Expr::Property { variable: v, .. } => {
+                if !vars.contains(&v.as_str()) {
+                    vars.push(v);
+                }
+            }
+            Expr::Not(e) | Expr::IsNull { expr: e, .. } => e.collect_variables(vars),
+            Expr::And(l, r)
+            | Expr::Or(l, r)
+            | Expr::Compare {
+                left: l, right: r, ..
+            } => {
+                l.collect_variables(vars);
+                r.collect_variables(vars);
+            }
+        }
+    }
+
+    /// Split a predicate into its top-level AND terms
+    pub fn into_conjuncts(self) -> Vec<Expr> {
+        match self {
+            Expr::And(l, r) => {
+                let mut terms = l.into_conjuncts();
+                terms.extend(r.into_conjuncts());
+                terms
+            }
+            other => vec![other],
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Literal(Value::String(s)) => write!(f, "'{}'", s.replace('\'', "\\'")),
+            Expr::Literal(v) => write!(f, "{v}"),
+            Expr::Variable(v) => f.write_str(v),
+            Expr::Property { variable, path } => write!(f, "{variable}.{}", path.join(".")),
+            Expr::Not(e) => write!(f, "NOT {e}"),
+            Expr::And(l, r) => write!(f, "({l} AND {r})"),
+            Expr::Or(l, r) => write!(f, "({l} OR {r})"),
+            Expr::Compare { left, op, right } => write!(f, "{left} {op} {right}"),
+            Expr::IsNull { expr, negated } => {
+                write!(f, "{expr} IS {}NULL", if *negated { "NOT " } else { "" })
+            }
+        }
+    }
+}
+
+/// A RETURN item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    /// Projected expression
+    pub expr: Expr,
+    /// Optional `AS` alias
+    pub alias: Option<String>,
+}
+
+impl Projection {
+    /// Column name used in results
+    pub fn column_name(&self) -> String {
+        self.alias.clone().unwrap_or_else(|| self.expr.to_string())
+    }
+}
+
+/// An ORDER BY item
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderItem {
+    /// Sort key
+    pub expr: Expr,
+    /// Sort descending instead of ascending
+    pub descending: bool,
+}
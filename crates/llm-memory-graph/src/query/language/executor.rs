@@ -0,0 +1,790 @@
+//! Executor for graph query plans over an [`AsyncStorageBackend`]
+//!
+//! Rows are built step by step: scans and lookups bind the anchor of each
+//! pattern, expansions follow edges to bind the rest, and filters drop rows as
+//! soon as their variables are available. Nodes and edges are exposed to
+//! expressions as their JSON form, so `p.content` and `p.metadata.model` address
+//! the same fields that appear in exports.
+
+use super::ast::{CompareOp, Direction, Expr, Projection};
+use super::planner::{PlanStep, QueryPlan};
+use crate::observatory::PrometheusMetrics;
+use crate::query::explain::{QueryStats, StatsRecorder};
+use crate::storage::AsyncStorageBackend;
+use crate::{Edge, Node, NodeId, NodeType};
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Pseudo-property holding a node's type name, used for label checks
+pub(crate) const LABEL_FIELD: &str = "_label";
+
+/// Default cap on intermediate rows, guarding against runaway cross products
+pub const DEFAULT_MAX_ROWS: usize = 1_000_000;
+
+/// Tabular result of a graph query
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueryResult {
+    /// Column names, taken from RETURN aliases or expression text
+    pub columns: Vec<String>,
+    /// Result rows, one value per column
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl QueryResult {
+    /// Number of result rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Whether the query matched nothing
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+/// A value bound to a pattern variable
+#[derive(Debug, Clone)]
+enum Bound {
+    Node {
+        id: NodeId,
+        label: NodeType,
+        value: Arc<Value>,
+    },
+    Edge {
+        value: Arc<Value>,
+    },
+}
+
+impl Bound {
+    fn value(&self) -> &Value {
+        match self {
+            Bound::Node { value, .. } | Bound::Edge { value } => value,
+        }
+    }
+}
+
+type Row = HashMap<String, Bound>;
+
+fn node_binding(node: &Node) -> Result<Bound> {
+    // Nodes serialize as `{"Prompt": {...}}`; expressions address the inner struct
+    let value = match serde_json::to_value(node)? {
+        Value::Object(map) => map.into_iter().next().map_or(Value::Null, |(_, v)| v),
+        other => other,
+    };
+    Ok(Bound::Node {
+        id: node.id(),
+        label: node.node_type(),
+        value: Arc::new(value),
+    })
+}
+
+fn edge_binding(edge: &Edge) -> Result<Bound> {
+    Ok(Bound::Edge {
+        value: Arc::new(serde_json::to_value(edge)?),
+    })
+}
+
+/// Executes [`QueryPlan`]s against a storage backend
+///
+/// # Examples
+///
+/// ```no_run
+/// use llm_memory_graph::query::language::{parse_query, plan_query, QueryExecutor};
+/// use llm_memory_graph::storage::AsyncSledBackend;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let backend = AsyncSledBackend::open("./data/graph.db").await?;
+/// let plan = plan_query(&parse_query("MATCH (p:Prompt) RETURN p.content LIMIT 5")?)?;
+/// let result = QueryExecutor::new(Arc::new(backend)).execute(&plan).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct QueryExecutor {
+    storage: Arc<dyn AsyncStorageBackend>,
+    max_rows: usize,
+    metrics: Option<Arc<PrometheusMetrics>>,
+}
+
+impl QueryExecutor {
+    /// Create an executor over the given backend
+    pub fn new(storage: Arc<dyn AsyncStorageBackend>) -> Self {
+        Self {
+            storage,
+            max_rows: DEFAULT_MAX_ROWS,
+            metrics: None,
+        }
+    }
+
+    /// Set the maximum number of intermediate rows before execution is aborted
+    #[must_use]
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Record the duration of every executed plan in `metrics`, labelled with
+    /// the plan shape
+    #[must_use]
+    pub fn with_metrics(mut self, metrics: Arc<PrometheusMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Execute a plan and collect its results
+    pub async fn execute(&self, plan: &QueryPlan) -> Result<QueryResult> {
+        Ok(self.execute_with_stats(plan).await?.0)
+    }
+
+    /// Execute a plan and report rows and timings for each step
+    ///
+    /// Stages are named after the plan steps (`scan_nodes`, `expand`,
+    /// `filter`, ...) followed by `project`. Nodes reached again by a later
+    /// expansion are served from a per-query cache and counted as cache hits.
+    pub async fn execute_with_stats(&self, plan: &QueryPlan) -> Result<(QueryResult, QueryStats)> {
+        let regexes = compile_regexes(plan)?;
+        let mut nodes: HashMap<NodeId, Option<Bound>> = HashMap::new();
+        let mut rows: Vec<Row> = vec![Row::new()];
+        let mut recorder = StatsRecorder::new(plan.shape());
+
+        for step in &plan.steps {
+            let rows_in = rows.len();
+            rows = match step {
+                PlanStep::ScanNodes { variable, label } => {
+                    let candidates = self.storage.scan_nodes().await?;
+                    recorder.fetched(candidates.len());
+                    self.bind_candidates(rows, variable, label.as_ref(), &candidates)?
+                }
+                PlanStep::ScanSession {
+                    variable,
+                    label,
+                    session_id,
+                } => {
+                    let candidates = self.storage.get_session_nodes(session_id).await?;
+                    recorder.fetched(candidates.len());
+                    self.bind_candidates(rows, variable, label.as_ref(), &candidates)?
+                }
+                PlanStep::LookupNode {
+                    variable,
+                    label,
+                    node_id,
+                } => {
+                    let candidates: Vec<Node> =
+                        self.storage.get_node(node_id).await?.into_iter().collect();
+                    recorder.fetched(candidates.len());
+                    self.bind_candidates(rows, variable, label.as_ref(), &candidates)?
+                }
+                PlanStep::Expand {
+                    from,
+                    edge_variable,
+                    edge_types,
+                    direction,
+                    to,
+                    to_label,
+                } => {
+                    let mut expanded = Vec::new();
+                    for row in rows {
+                        let Some(Bound::Node { id: from_id, .. }) = row.get(from) else {
+                            return Err(Error::QueryError(format!(
+                                "Variable '{from}' is not bound to a node"
+                            )));
+                        };
+                        let from_id = *from_id;
+
+                        let mut neighbours = Vec::new();
+                        if *direction != Direction::Incoming {
+                            for edge in self.storage.get_outgoing_edges(&from_id).await? {
+                                neighbours.push((edge.to, edge));
+                            }
+                        }
+                        if *direction != Direction::Outgoing {
+                            for edge in self.storage.get_incoming_edges(&from_id).await? {
+                                // A self-loop was already found going out
+                                if *direction == Direction::Both && edge.from == edge.to {
+                                    continue;
+                                }
+                                neighbours.push((edge.from, edge));
+                            }
+                        }
+
+                        for (neighbour, edge) in neighbours {
+                            if !edge_types.is_empty() && !edge_types.contains(&edge.edge_type) {
+                                continue;
+                            }
+                            let target = match row.get(to) {
+                                Some(Bound::Node { id, .. }) => {
+                                    if *id != neighbour {
+                                        continue;
+                                    }
+                                    None
+                                }
+                                Some(Bound::Edge { .. }) => {
+                                    return Err(Error::QueryError(format!(
+                                        "Variable '{to}' is not bound to a node"
+                                    )))
+                                }
+                                None => {
+                                    let Some(bound) = self
+                                        .load_node(&mut nodes, neighbour, &mut recorder)
+                                        .await?
+                                    else {
+                                        continue;
+                                    };
+                                    if let (Some(want), Bound::Node { label, .. }) =
+                                        (to_label, &bound)
+                                    {
+                                        if want != label {
+                                            continue;
+                                        }
+                                    }
+                                    Some(bound)
+                                }
+                            };
+
+                            let mut next = row.clone();
+                            next.insert(edge_variable.clone(), edge_binding(&edge)?);
+                            if let Some(bound) = target {
+                                next.insert(to.clone(), bound);
+                            }
+                            expanded.push(next);
+                            self.check_rows(expanded.len())?;
+                        }
+                    }
+                    expanded
+                }
+                PlanStep::Filter(expr) => rows
+                    .into_iter()
+                    .filter(|row| truthy(&evaluate(expr, row, &regexes)))
+                    .collect(),
+            };
+            recorder.stage(step.name(), rows_in, rows.len());
+            if rows.is_empty() {
+                break;
+            }
+        }
+
+        let rows_in = rows.len();
+        let result = self.project(plan, &rows, &regexes)?;
+        recorder.stage("project", rows_in, result.len());
+
+        let stats = recorder.finish(result.len());
+        if let Some(metrics) = &self.metrics {
+            metrics.record_query_duration_for_plan(&stats.shape, stats.elapsed.as_secs_f64());
+        }
+        Ok((result, stats))
+    }
+
+    fn check_rows(&self, count: usize) -> Result<()> {
+        if count > self.max_rows {
+            return Err(Error::QueryError(format!(
+                "Query produced more than {} intermediate rows; add more selective patterns or predicates",
+                self.max_rows
+            )));
+        }
+        Ok(())
+    }
+
+    async fn load_node(
+        &self,
+        cache: &mut HashMap<NodeId, Option<Bound>>,
+        id: NodeId,
+        recorder: &mut StatsRecorder,
+    ) -> Result<Option<Bound>> {
+        if let Some(bound) = cache.get(&id) {
+            recorder.cache_hit();
+            return Ok(bound.clone());
+        }
+        let bound = match self.storage.get_node(&id).await? {
+            Some(node) => {
+                recorder.fetched(1);
+                Some(node_binding(&node)?)
+            }
+            None => None,
+        };
+        cache.insert(id, bound.clone());
+        Ok(bound)
+    }
+
+    /// Cross every row with the candidate nodes that satisfy the label
+    fn bind_candidates(
+        &self,
+        rows: Vec<Row>,
+        variable: &str,
+        label: Option<&NodeType>,
+        candidates: &[Node],
+    ) -> Result<Vec<Row>> {
+        let bindings = candidates
+            .iter()
+            .filter(|node| label.is_none_or(|l| node.node_type() == *l))
+            .map(node_binding)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            for bound in &bindings {
+                let mut next = row.clone();
+                next.insert(variable.to_string(), bound.clone());
+                out.push(next);
+                self.check_rows(out.len())?;
+            }
+        }
+        Ok(out)
+    }
+
+    fn project(
+        &self,
+        plan: &QueryPlan,
+        rows: &[Row],
+        regexes: &HashMap<String, Regex>,
+    ) -> Result<QueryResult> {
+        let columns: Vec<String> = plan
+            .projections
+            .iter()
+            .map(Projection::column_name)
+            .collect();
+
+        let mut projected: Vec<(Vec<Value>, Vec<Value>)> = Vec::with_capacity(rows.len());
+        let mut seen = HashSet::new();
+        for row in rows {
+            let values: Vec<Value> = plan
+                .projections
+                .iter()
+                .map(|p| evaluate(&p.expr, row, regexes))
+                .collect();
+            if plan.distinct && !seen.insert(serde_json::to_string(&values)?) {
+                continue;
+            }
+            let keys = plan
+                .order_by
+                .iter()
+                .map(|item| {
+                    // ORDER BY may name a RETURN alias
+                    if let Expr::Variable(name) = &item.expr {
+                        if let Some(idx) = plan
+                            .projections
+                            .iter()
+                            .position(|p| p.alias.as_deref() == Some(name))
+                        {
+                            return values[idx].clone();
+                        }
+                    }
+                    evaluate(&item.expr, row, regexes)
+                })
+                .collect();
+            projected.push((values, keys));
+        }
+
+        if !plan.order_by.is_empty() {
+            projected.sort_by(|(_, a), (_, b)| {
+                for (idx, item) in plan.order_by.iter().enumerate() {
+                    let ordering = sort_order(&a[idx], &b[idx]);
+                    let ordering = if item.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    };
+                    if ordering != Ordering::Equal {
+                        return ordering;
+                    }
+                }
+                Ordering::Equal
+            });
+        }
+
+        let rows = projected
+            .into_iter()
+            .map(|(values, _)| values)
+            .skip(plan.skip.unwrap_or(0))
+            .take(plan.limit.unwrap_or(usize::MAX))
+            .collect();
+
+        Ok(QueryResult { columns, rows })
+    }
+}
+
+/// Compile every literal `=~` pattern up front so invalid regexes fail early
+fn compile_regexes(plan: &QueryPlan) -> Result<HashMap<String, Regex>> {
+    fn visit(expr: &Expr, out: &mut HashMap<String, Regex>) -> Result<()> {
+        match expr {
+            Expr::Compare { left, op, right } => {
+                if let (CompareOp::Matches, Expr::Literal(Value::String(pattern))) =
+                    (op, right.as_ref())
+                {
+                    if !out.contains_key(pattern) {
+                        let regex = Regex::new(pattern).map_err(|e| {
+                            Error::QueryError(format!(
+                                "Invalid regular expression '{pattern}': {e}"
+                            ))
+                        })?;
+                        out.insert(pattern.clone(), regex);
+                    }
+                }
+                visit(left, out)?;
+                visit(right, out)
+            }
+            Expr::And(l, r) | Expr::Or(l, r) => {
+                visit(l, out)?;
+                visit(r, out)
+            }
+            Expr::Not(e) | Expr::IsNull { expr: e, .. } => visit(e, out),
+            Expr::Literal(_) | Expr::Variable(_) | Expr::Property { .. } => Ok(()),
+        }
+    }
+
+    let mut regexes = HashMap::new();
+    for step in &plan.steps {
+        if let PlanStep::Filter(expr) = step {
+            visit(expr, &mut regexes)?;
+        }
+    }
+    for projection in &plan.projections {
+        visit(&projection.expr, &mut regexes)?;
+    }
+    Ok(regexes)
+}
+
+/// Evaluate an expression against a row; missing data evaluates to null
+fn evaluate(expr: &Expr, row: &Row, regexes: &HashMap<String, Regex>) -> Value {
+    match expr {
+        Expr::Literal(value) => value.clone(),
+        Expr::Variable(name) => row.get(name).map_or(Value::Null, |b| b.value().clone()),
+        Expr::Property { variable, path } => {
+            let Some(bound) = row.get(variable) else {
+                return Value::Null;
+            };
+            if let (Bound::Node { label, .. }, [field]) = (bound, path.as_slice()) {
+                if field == LABEL_FIELD {
+                    return Value::String(format!("{label:?}"));
+                }
+            }
+            let mut current = bound.value();
+            for key in path {
+                match current.get(key) {
+                    Some(next) => current = next,
+                    None => return Value::Null,
+                }
+            }
+            current.clone()
+        }
+        Expr::Not(inner) => match evaluate(inner, row, regexes) {
+            Value::Null => Value::Null,
+            value => Value::Bool(!truthy(&value)),
+        },
+        Expr::And(l, r) => {
+            Value::Bool(truthy(&evaluate(l, row, regexes)) && truthy(&evaluate(r, row, regexes)))
+        }
+        Expr::Or(l, r) => {
+            Value::Bool(truthy(&evaluate(l, row, regexes)) || truthy(&evaluate(r, row, regexes)))
+        }
+        Expr::IsNull { expr, negated } => {
+            Value::Bool(evaluate(expr, row, regexes).is_null() != *negated)
+        }
+        Expr::Compare { left, op, right } => {
+            let l = evaluate(left, row, regexes);
+            let r = evaluate(right, row, regexes);
+            compare(&l, *op, &r, regexes).map_or(Value::Null, Value::Bool)
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Bool(b) => *b,
+        Value::Null => false,
+        Value::Number(n) => n.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+/// Apply a comparison; `None` means the result is unknown (a null operand)
+fn compare(
+    left: &Value,
+    op: CompareOp,
+    right: &Value,
+    regexes: &HashMap<String, Regex>,
+) -> Option<bool> {
+    if left.is_null() || right.is_null() {
+        return None;
+    }
+    match op {
+        CompareOp::Eq => Some(values_equal(left, right)),
+        CompareOp::Ne => Some(!values_equal(left, right)),
+        CompareOp::Lt => order(left, right).map(Ordering::is_lt),
+        CompareOp::Le => order(left, right).map(Ordering::is_le),
+        CompareOp::Gt => order(left, right).map(Ordering::is_gt),
+        CompareOp::Ge => order(left, right).map(Ordering::is_ge),
+        CompareOp::Contains => match (left, right) {
+            (Value::String(l), Value::String(r)) => Some(l.contains(r.as_str())),
+            (Value::Array(items), needle) => Some(items.iter().any(|i| values_equal(i, needle))),
+            _ => Some(false),
+        },
+        CompareOp::StartsWith => match (left, right) {
+            (Value::String(l), Value::String(r)) => Some(l.starts_with(r.as_str())),
+            _ => Some(false),
+        },
+        CompareOp::EndsWith => match (left, right) {
+            (Value::String(l), Value::String(r)) => Some(l.ends_with(r.as_str())),
+            _ => Some(false),
+        },
+        CompareOp::In => match right {
+            Value::Array(items) => Some(items.iter().any(|i| values_equal(left, i))),
+            _ => Some(false),
+        },
+        CompareOp::Matches => {
+            let (Value::String(text), Value::String(pattern)) = (left, right) else {
+                return Some(false);
+            };
+            match regexes.get(pattern) {
+                Some(regex) => Some(regex.is_match(text)),
+                // Patterns computed at runtime are compiled on demand
+                None => Regex::new(pattern).ok().map(|r| r.is_match(text)),
+            }
+        }
+    }
+}
+
+fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(_), Value::Number(_)) | (Value::String(_), Value::String(_)) => {
+            order(left, right) == Some(Ordering::Equal)
+        }
+        _ => left == right,
+    }
+}
+
+/// Natural ordering of two comparable values
+///
+/// Numbers compare numerically and strings that are both RFC 3339 timestamps
+/// compare chronologically, so `p.timestamp > '2024-01-01T00:00:00Z'` works
+/// regardless of the stored precision.
+fn order(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => l.as_f64()?.partial_cmp(&r.as_f64()?),
+        (Value::String(l), Value::String(r)) => match (parse_datetime(l), parse_datetime(r)) {
+            (Some(l), Some(r)) => Some(l.cmp(&r)),
+            _ => Some(l.cmp(r)),
+        },
+        (Value::Bool(l), Value::Bool(r)) => Some(l.cmp(r)),
+        _ => None,
+    }
+}
+
+/// Total ordering used by ORDER BY: comparable values first, nulls last
+fn sort_order(left: &Value, right: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Bool(_) => 0,
+            Value::Number(_) => 1,
+            Value::String(_) => 2,
+            Value::Array(_) => 3,
+            Value::Object(_) => 4,
+            Value::Null => 5,
+        }
+    }
+    order(left, right).unwrap_or_else(|| rank(left).cmp(&rank(right)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::language::{parse_query, plan_query};
+    use crate::storage::AsyncSledBackend;
+    use crate::{AgentNode, ConversationSession, EdgeType, PromptNode, ResponseNode, TokenUsage};
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    struct Fixture {
+        executor: QueryExecutor,
+        session: ConversationSession,
+        _dir: tempfile::TempDir,
+    }
+
+    async fn fixture() -> Fixture {
+        let dir = tempdir().unwrap();
+        let backend = AsyncSledBackend::open(dir.path()).await.unwrap();
+
+        let session = ConversationSession::new();
+        backend
+            .store_node(&Node::Session(session.clone()))
+            .await
+            .unwrap();
+
+        let agent = AgentNode::new(
+            "Coder".to_string(),
+            "coder".to_string(),
+            vec!["rust".to_string()],
+        );
+        backend
+            .store_node(&Node::Agent(agent.clone()))
+            .await
+            .unwrap();
+
+        for (i, text) in [
+            "Explain Rust lifetimes",
+            "Write a Python script",
+            "Rust traits?",
+        ]
+        .iter()
+        .enumerate()
+        {
+            let prompt = PromptNode::new(session.id, (*text).to_string());
+            let response = ResponseNode::new(
+                prompt.id,
+                format!("Answer {i}"),
+                TokenUsage::new(10, 10 * (i as u32 + 1)),
+            );
+            backend
+                .store_node(&Node::Prompt(prompt.clone()))
+                .await
+                .unwrap();
+            backend
+                .store_node(&Node::Response(response.clone()))
+                .await
+                .unwrap();
+            backend
+                .store_edge(&Edge::new(response.id, prompt.id, EdgeType::RespondsTo))
+                .await
+                .unwrap();
+            if text.contains("Rust") {
+                backend
+                    .store_edge(&Edge::new(prompt.id, agent.node_id, EdgeType::HandledBy))
+                    .await
+                    .unwrap();
+            }
+        }
+
+        Fixture {
+            executor: QueryExecutor::new(Arc::new(backend)),
+            session,
+            _dir: dir,
+        }
+    }
+
+    async fn run(fixture: &Fixture, query: &str) -> Result<QueryResult> {
+        let plan = plan_query(&parse_query(query)?)?;
+        fixture.executor.execute(&plan).await
+    }
+
+    #[tokio::test]
+    async fn test_execute_match_and_filter() {
+        let f = fixture().await;
+        let result = run(
+            &f,
+            "MATCH (p:Prompt) WHERE p.content CONTAINS 'Rust' RETURN p.content ORDER BY p.content",
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.columns, vec!["p.content"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![json!("Explain Rust lifetimes")],
+                vec![json!("Rust traits?")]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_traversal() {
+        let f = fixture().await;
+        let result = run(
+            &f,
+            "MATCH (r:Response)-[:RESPONDS_TO]->(p:Prompt)-[:HANDLED_BY]->(a:Agent {role: 'coder'}) \
+             RETURN r.content AS answer, a.name ORDER BY answer DESC",
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.columns, vec!["answer", "a.name"]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![json!("Answer 2"), json!("Coder")],
+                vec![json!("Answer 0"), json!("Coder")]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_session_scan_with_nested_fields() {
+        let f = fixture().await;
+        let result = run(
+            &f,
+            &format!(
+                "MATCH (p:Prompt {{session_id: '{}'}})<-[:RESPONDS_TO]-(r) \
+                 WHERE r.usage.completion_tokens >= 20 \
+                 RETURN r.usage.completion_tokens AS tokens ORDER BY tokens SKIP 1 LIMIT 1",
+                f.session.id
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows, vec![vec![json!(30)]]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_distinct_regex_and_nulls() {
+        let f = fixture().await;
+        let result = run(
+            &f,
+            "MATCH (p:Prompt)-[:HANDLED_BY]->(a:Agent) RETURN DISTINCT a.name",
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows, vec![vec![json!("Coder")]]);
+
+        let result = run(
+            &f,
+            "MATCH (p:Prompt) WHERE p.content =~ '(?i)^write' AND p.missing IS NULL RETURN p.content",
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.rows, vec![vec![json!("Write a Python script")]]);
+
+        assert!(run(&f, "MATCH (p:Prompt) WHERE p.content =~ '(' RETURN p")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_with_stats() {
+        let f = fixture().await;
+        let plan = plan_query(
+            &parse_query("MATCH (p:Prompt)-[:HANDLED_BY]->(a:Agent) RETURN p.content").unwrap(),
+        )
+        .unwrap();
+        let (result, stats) = f.executor.execute_with_stats(&plan).await.unwrap();
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(stats.shape, "scan_nodes+expand");
+        // Every node is scanned once, then the agent is loaded by the first
+        // expansion and reused by the second
+        assert_eq!(stats.nodes_scanned, 9);
+        assert_eq!(stats.cache_hits, 1);
+        assert_eq!(stats.nodes_returned, 2);
+        assert_eq!(stats.stage("scan_nodes").unwrap().rows_out, 3);
+        assert_eq!(stats.stage("expand").unwrap().rows_out, 2);
+        assert_eq!(stats.stage("project").unwrap().rows_out, 2);
+    }
+
+    #[tokio::test]
+    async fn test_execute_row_limit() {
+        let f = fixture().await;
+        let plan = plan_query(&parse_query("MATCH (a), (b) RETURN a, b").unwrap()).unwrap();
+        let executor = QueryExecutor::new(Arc::clone(&f.executor.storage)).with_max_rows(10);
+        assert!(executor.execute(&plan).await.is_err());
+    }
+}
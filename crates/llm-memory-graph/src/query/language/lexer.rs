@@ -0,0 +1,255 @@
+//! Tokenizer for the graph query language
+
+use crate::{Error, Result};
+
+/// Kinds of tokens produced by the lexer
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    /// Identifier or keyword (keywords are matched case-insensitively by the parser)
+    Ident(String),
+    /// Quoted string literal
+    Str(String),
+    /// Integer literal
+    Int(i64),
+    /// Floating point literal
+    Float(f64),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+    Dot,
+    Dash,
+    Pipe,
+    Star,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    RegexMatch,
+    Eof,
+}
+
+/// A token with its byte offset in the source
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub pos: usize,
+}
+
+/// Split a query string into tokens
+pub(crate) fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<(usize, char)> = input.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (pos, c) = chars[i];
+        let next = chars.get(i + 1).map(|(_, c)| *c);
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        // Line comments
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i].1 != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+
+        let (kind, width) = match c {
+            '(' => (TokenKind::LParen, 1),
+            ')' => (TokenKind::RParen, 1),
+            '[' => (TokenKind::LBracket, 1),
+            ']' => (TokenKind::RBracket, 1),
+            '{' => (TokenKind::LBrace, 1),
+            '}' => (TokenKind::RBrace, 1),
+            ':' => (TokenKind::Colon, 1),
+            ',' => (TokenKind::Comma, 1),
+            '.' => (TokenKind::Dot, 1),
+            '-' => (TokenKind::Dash, 1),
+            '|' => (TokenKind::Pipe, 1),
+            '*' => (TokenKind::Star, 1),
+            '=' if next == Some('~') => (TokenKind::RegexMatch, 2),
+            '=' => (TokenKind::Eq, 1),
+            '!' if next == Some('=') => (TokenKind::Ne, 2),
+            '<' if next == Some('>') => (TokenKind::Ne, 2),
+            '<' if next == Some('=') => (TokenKind::Le, 2),
+            '<' => (TokenKind::Lt, 1),
+            '>' if next == Some('=') => (TokenKind::Ge, 2),
+            '>' => (TokenKind::Gt, 1),
+            '\'' | '"' => {
+                let (value, consumed) = lex_string(&chars[i..], c, pos)?;
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    pos,
+                });
+                i += consumed;
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                let start = i;
+                let mut is_float = false;
+                while i < chars.len()
+                    && (chars[i].1.is_ascii_digit()
+                        || (chars[i].1 == '.'
+                            && !is_float
+                            && chars.get(i + 1).is_some_and(|(_, c)| c.is_ascii_digit())))
+                {
+                    if chars[i].1 == '.' {
+                        is_float = true;
+                    }
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().map(|(_, c)| c).collect();
+                let kind = if is_float {
+                    TokenKind::Float(text.parse().map_err(|_| {
+                        Error::QueryError(format!("Invalid number '{text}' at position {pos}"))
+                    })?)
+                } else {
+                    TokenKind::Int(text.parse().map_err(|_| {
+                        Error::QueryError(format!("Invalid number '{text}' at position {pos}"))
+                    })?)
+                };
+                tokens.push(Token { kind, pos });
+                continue;
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().map(|(_, c)| c).collect();
+                tokens.push(Token {
+                    kind: TokenKind::Ident(text),
+                    pos,
+                });
+                continue;
+            }
+            '`' => {
+                // Backtick-quoted identifier
+                let (value, consumed) = lex_string(&chars[i..], '`', pos)?;
+                tokens.push(Token {
+                    kind: TokenKind::Ident(value),
+                    pos,
+                });
+                i += consumed;
+                continue;
+            }
+            other => {
+                return Err(Error::QueryError(format!(
+                    "Unexpected character '{other}' at position {pos}"
+                )))
+            }
+        };
+
+        tokens.push(Token { kind, pos });
+        i += width;
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        pos: input.len(),
+    });
+    Ok(tokens)
+}
+
+/// Lex a quoted literal starting at `chars[0]`, returning its value and the
+/// number of characters consumed including both quotes
+fn lex_string(chars: &[(usize, char)], quote: char, pos: usize) -> Result<(String, usize)> {
+    let mut value = String::new();
+    let mut i = 1;
+    while i < chars.len() {
+        match chars[i].1 {
+            '\\' => {
+                let escaped = chars.get(i + 1).map(|(_, c)| *c).ok_or_else(|| {
+                    Error::QueryError(format!("Unterminated string at position {pos}"))
+                })?;
+                value.push(match escaped {
+                    'n' => '\n',
+                    't' => '\t',
+                    other => other,
+                });
+                i += 2;
+            }
+            c if c == quote => return Ok((value, i + 1)),
+            c => {
+                value.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err(Error::QueryError(format!(
+        "Unterminated string at position {pos}"
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_pattern() {
+        assert_eq!(
+            kinds("(p:Prompt)-[:INVOKES]->(t)"),
+            vec![
+                TokenKind::LParen,
+                TokenKind::Ident("p".into()),
+                TokenKind::Colon,
+                TokenKind::Ident("Prompt".into()),
+                TokenKind::RParen,
+                TokenKind::Dash,
+                TokenKind::LBracket,
+                TokenKind::Colon,
+                TokenKind::Ident("INVOKES".into()),
+                TokenKind::RBracket,
+                TokenKind::Dash,
+                TokenKind::Gt,
+                TokenKind::LParen,
+                TokenKind::Ident("t".into()),
+                TokenKind::RParen,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_literals_and_operators() {
+        assert_eq!(
+            kinds("'it\\'s' 42 0.5 <> != <= >= =~"),
+            vec![
+                TokenKind::Str("it's".into()),
+                TokenKind::Int(42),
+                TokenKind::Float(0.5),
+                TokenKind::Ne,
+                TokenKind::Ne,
+                TokenKind::Le,
+                TokenKind::Ge,
+                TokenKind::RegexMatch,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert!(tokenize("'unterminated").is_err());
+        assert!(tokenize("a # b").is_err());
+    }
+}
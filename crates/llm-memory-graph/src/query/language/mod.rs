@@ -0,0 +1,35 @@
+//! Declarative graph query language
+//!
+//! A small Cypher-like language for matching patterns of nodes and edges:
+//!
+//! ```text
+//! MATCH (r:Response)-[:RESPONDS_TO]->(p:Prompt)-[:HANDLED_BY]->(a:Agent {role: 'coder'})
+//! WHERE p.metadata.model = 'gpt-4' AND r.usage.total_tokens > 1000
+//! RETURN p.content, r.usage.total_tokens AS tokens
+//! ORDER BY tokens DESC
+//! LIMIT 10
+//! ```
+//!
+//! Labels are [`NodeType`](crate::NodeType) names and relationship types are
+//! [`EdgeType`](crate::EdgeType) names in either `RESPONDS_TO` or `RespondsTo`
+//! form. Properties address the JSON form of nodes and edges, so nested fields
+//! such as `metadata.model` or an edge's `properties.latency_ms` work directly.
+//!
+//! Queries go through three stages: [`parse_query`] builds a [`Query`],
+//! [`plan_query`] chooses access paths and filter placement, and
+//! [`QueryExecutor`] runs the plan against an
+//! [`AsyncStorageBackend`](crate::storage::AsyncStorageBackend).
+
+mod ast;
+mod executor;
+mod lexer;
+mod parser;
+mod planner;
+
+pub use ast::{
+    CompareOp, Direction, Expr, NodePattern, OrderItem, Pattern, Projection, Query, RelPattern,
+};
+pub use executor::{QueryExecutor, QueryResult, DEFAULT_MAX_ROWS};
+pub(crate) use parser::parse_node_type;
+pub use parser::parse_query;
+pub use planner::{plan_query, PlanStep, QueryPlan};
@@ -0,0 +1,592 @@
+//! Recursive-descent parser for the graph query language
+//!
+//! Grammar (keywords are case-insensitive):
+//!
+//! ```text
+//! query      := MATCH pattern (',' pattern)* [WHERE expr]
+//!               RETURN [DISTINCT] projection (',' projection)*
+//!               [ORDER BY order (',' order)*] [SKIP int] [LIMIT int]
+//! pattern    := node (rel node)*
+//! node       := '(' [ident] [':' Label] ['{' ident ':' literal (',' ...)* '}'] ')'
+//! rel        := '-' ['[' [ident] [':' TYPE ('|' TYPE)*] ']'] '-' ['>']
+//!             | '<' '-' ['[' ... ']'] '-'
+//! expr       := and (OR and)*
+//! and        := not (AND not)*
+//! not        := NOT not | comparison
+//! comparison := operand [op operand | IS [NOT] NULL]
+//! operand    := literal | list | ident ['.' ident]* | '(' expr ')'
+//! projection := expr [AS ident]
+//! order      := expr [ASC | DESC]
+//! ```
+
+use super::ast::{
+    CompareOp, Direction, Expr, NodePattern, OrderItem, Pattern, Projection, Query, RelPattern,
+};
+use super::lexer::{tokenize, Token, TokenKind};
+use crate::{EdgeType, NodeType};
+use crate::{Error, Result};
+use serde_json::Value;
+
+/// Parse a query string into a [`Query`]
+///
+/// # Examples
+///
+/// ```no_run
+/// use llm_memory_graph::query::language::parse_query;
+///
+/// let query = parse_query(
+///     "MATCH (p:Prompt)-[:INVOKES]->(t:ToolInvocation) WHERE t.success = false RETURN p, t",
+/// ).unwrap();
+/// assert_eq!(query.returns.len(), 2);
+/// ```
+pub fn parse_query(input: &str) -> Result<Query> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        anonymous: 0,
+    };
+    let query = parser.query()?;
+    parser.expect(&TokenKind::Eof, "end of query")?;
+    Ok(query)
+}
+
+/// Parse a node label into a [`NodeType`]
+pub(crate) fn parse_node_type(name: &str) -> Option<NodeType> {
+    match normalize(name).as_str() {
+        "prompt" => Some(NodeType::Prompt),
+        "response" => Some(NodeType::Response),
+        "session" => Some(NodeType::Session),
+        "tool" | "toolinvocation" => Some(NodeType::ToolInvocation),
+        "agent" => Some(NodeType::Agent),
+        "template" => Some(NodeType::Template),
+        "custom" => Some(NodeType::Custom),
+        _ => None,
+    }
+}
+
+/// Parse a relationship type into an [`EdgeType`]
+pub(crate) fn parse_edge_type(name: &str) -> Option<EdgeType> {
+    match normalize(name).as_str() {
+        "follows" => Some(EdgeType::Follows),
+        "respondsto" => Some(EdgeType::RespondsTo),
+        "handledby" => Some(EdgeType::HandledBy),
+        "partof" => Some(EdgeType::PartOf),
+        "invokes" => Some(EdgeType::Invokes),
+        "transfersto" => Some(EdgeType::TransfersTo),
+        "instantiates" => Some(EdgeType::Instantiates),
+        "inherits" => Some(EdgeType::Inherits),
+        "references" => Some(EdgeType::References),
+        "branchesfrom" => Some(EdgeType::BranchesFrom),
+        _ => None,
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    anonymous: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    fn peek_at(&self, offset: usize) -> &TokenKind {
+        let idx = (self.pos + offset).min(self.tokens.len() - 1);
+        &self.tokens[idx].kind
+    }
+
+    fn position(&self) -> usize {
+        self.tokens[self.pos].pos
+    }
+
+    fn advance(&mut self) -> TokenKind {
+        let kind = self.tokens[self.pos].kind.clone();
+        if self.pos < self.tokens.len() - 1 {
+            self.pos += 1;
+        }
+        kind
+    }
+
+    fn error(&self, expected: &str) -> Error {
+        let found = match self.peek() {
+            TokenKind::Eof => "end of query".to_string(),
+            TokenKind::Ident(s) => format!("'{s}'"),
+            TokenKind::Str(s) => format!("string '{s}'"),
+            other => format!("{other:?}"),
+        };
+        Error::QueryError(format!(
+            "Expected {expected} but found {found} at position {}",
+            self.position()
+        ))
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        self.peek() == kind
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.check(kind) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind, what: &str) -> Result<()> {
+        if self.eat(kind) {
+            Ok(())
+        } else {
+            Err(self.error(what))
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), TokenKind::Ident(s) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_keyword_at(&self, offset: usize, keyword: &str) -> bool {
+        matches!(self.peek_at(offset), TokenKind::Ident(s) if s.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if self.is_keyword(keyword) {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(keyword))
+        }
+    }
+
+    fn ident(&mut self, what: &str) -> Result<String> {
+        match self.peek() {
+            TokenKind::Ident(s) => {
+                let s = s.clone();
+                self.advance();
+                Ok(s)
+            }
+            _ => Err(self.error(what)),
+        }
+    }
+
+    fn anonymous_name(&mut self) -> String {
+        let name = format!("_anon{}", self.anonymous);
+        self.anonymous += 1;
+        name
+    }
+
+    fn usize_literal(&mut self, what: &str) -> Result<usize> {
+        match self.peek() {
+            TokenKind::Int(n) if *n >= 0 => {
+                let n = *n as usize;
+                self.advance();
+                Ok(n)
+            }
+            _ => Err(self.error(what)),
+        }
+    }
+
+    // ===== Clauses =====
+
+    fn query(&mut self) -> Result<Query> {
+        self.expect_keyword("MATCH")?;
+        let mut patterns = vec![self.pattern()?];
+        while self.eat(&TokenKind::Comma) {
+            patterns.push(self.pattern()?);
+        }
+
+        let where_clause = if self.eat_keyword("WHERE") {
+            Some(self.expr()?)
+        } else {
+            None
+        };
+
+        self.expect_keyword("RETURN")?;
+        let distinct = self.eat_keyword("DISTINCT");
+        let mut returns = vec![self.projection()?];
+        while self.eat(&TokenKind::Comma) {
+            returns.push(self.projection()?);
+        }
+
+        let mut order_by = Vec::new();
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.expr()?;
+                let descending = self.eat_keyword("DESC") || self.eat_keyword("DESCENDING");
+                if !descending && !self.eat_keyword("ASC") {
+                    self.eat_keyword("ASCENDING");
+                }
+                order_by.push(OrderItem { expr, descending });
+                if !self.eat(&TokenKind::Comma) {
+                    break;
+                }
+            }
+        }
+
+        let skip = if self.eat_keyword("SKIP") {
+            Some(self.usize_literal("a non-negative integer after SKIP")?)
+        } else {
+            None
+        };
+        let limit = if self.eat_keyword("LIMIT") {
+            Some(self.usize_literal("a non-negative integer after LIMIT")?)
+        } else {
+            None
+        };
+
+        Ok(Query {
+            patterns,
+            where_clause,
+            distinct,
+            returns,
+            order_by,
+            skip,
+            limit,
+        })
+    }
+
+    fn projection(&mut self) -> Result<Projection> {
+        let expr = self.expr()?;
+        let alias = if self.eat_keyword("AS") {
+            Some(self.ident("an alias after AS")?)
+        } else {
+            None
+        };
+        Ok(Projection { expr, alias })
+    }
+
+    // ===== Patterns =====
+
+    fn pattern(&mut self) -> Result<Pattern> {
+        let start = self.node_pattern()?;
+        let mut steps = Vec::new();
+        while self.check(&TokenKind::Dash) || self.check(&TokenKind::Lt) {
+            let rel = self.rel_pattern()?;
+            let node = self.node_pattern()?;
+            steps.push((rel, node));
+        }
+        Ok(Pattern { start, steps })
+    }
+
+    fn node_pattern(&mut self) -> Result<NodePattern> {
+        self.expect(&TokenKind::LParen, "'(' to start a node pattern")?;
+
+        let variable = match self.peek() {
+            TokenKind::Ident(_) => self.ident("a variable")?,
+            _ => self.anonymous_name(),
+        };
+
+        let label = if self.eat(&TokenKind::Colon) {
+            let pos = self.position();
+            let name = self.ident("a node label")?;
+            Some(parse_node_type(&name).ok_or_else(|| {
+                Error::QueryError(format!("Unknown node label '{name}' at position {pos}"))
+            })?)
+        } else {
+            None
+        };
+
+        let mut properties = Vec::new();
+        if self.eat(&TokenKind::LBrace) {
+            if !self.check(&TokenKind::RBrace) {
+                loop {
+                    let key = self.ident("a property name")?;
+                    self.expect(&TokenKind::Colon, "':' after property name")?;
+                    let value = self.literal()?;
+                    properties.push((key, value));
+                    if !self.eat(&TokenKind::Comma) {
+                        break;
+                    }
+                }
+            }
+            self.expect(&TokenKind::RBrace, "'}' to close properties")?;
+        }
+
+        self.expect(&TokenKind::RParen, "')' to close a node pattern")?;
+        Ok(NodePattern {
+            variable,
+            label,
+            properties,
+        })
+    }
+
+    fn rel_pattern(&mut self) -> Result<RelPattern> {
+        let incoming = self.eat(&TokenKind::Lt);
+        self.expect(&TokenKind::Dash, "'-' in a relationship pattern")?;
+
+        let mut variable = None;
+        let mut edge_types = Vec::new();
+        if self.eat(&TokenKind::LBracket) {
+            if let TokenKind::Ident(_) = self.peek() {
+                variable = Some(self.ident("a variable")?);
+            }
+            if self.eat(&TokenKind::Colon) {
+                loop {
+                    let pos = self.position();
+                    let name = self.ident("a relationship type")?;
+                    edge_types.push(parse_edge_type(&name).ok_or_else(|| {
+                        Error::QueryError(format!(
+                            "Unknown relationship type '{name}' at position {pos}"
+                        ))
+                    })?);
+                    if !self.eat(&TokenKind::Pipe) {
+                        break;
+                    }
+                    // Cypher allows both `:A|B` and `:A|:B`
+                    self.eat(&TokenKind::Colon);
+                }
+            }
+            self.expect(&TokenKind::RBracket, "']' to close a relationship pattern")?;
+        }
+
+        self.expect(&TokenKind::Dash, "'-' in a relationship pattern")?;
+        let outgoing = self.eat(&TokenKind::Gt);
+
+        let direction = match (incoming, outgoing) {
+            (true, true) => {
+                return Err(Error::QueryError(format!(
+                    "Relationship cannot point both ways at position {}",
+                    self.position()
+                )))
+            }
+            (true, false) => Direction::Incoming,
+            (false, true) => Direction::Outgoing,
+            (false, false) => Direction::Both,
+        };
+
+        let variable = variable.unwrap_or_else(|| self.anonymous_name());
+        Ok(RelPattern {
+            variable,
+            edge_types,
+            direction,
+        })
+    }
+
+    // ===== Expressions =====
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut left = self.and_expr()?;
+        while self.eat_keyword("OR") {
+            let right = self.and_expr()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut left = self.not_expr()?;
+        while self.eat_keyword("AND") {
+            let right = self.not_expr()?;
+            left = Expr::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.eat_keyword("NOT") {
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        let left = self.operand()?;
+
+        if self.eat_keyword("IS") {
+            let negated = self.eat_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(Expr::IsNull {
+                expr: Box::new(left),
+                negated,
+            });
+        }
+
+        let op = match self.peek() {
+            TokenKind::Eq => Some(CompareOp::Eq),
+            TokenKind::Ne => Some(CompareOp::Ne),
+            TokenKind::Lt => Some(CompareOp::Lt),
+            TokenKind::Le => Some(CompareOp::Le),
+            TokenKind::Gt => Some(CompareOp::Gt),
+            TokenKind::Ge => Some(CompareOp::Ge),
+            TokenKind::RegexMatch => Some(CompareOp::Matches),
+            _ if self.is_keyword("CONTAINS") => Some(CompareOp::Contains),
+            _ if self.is_keyword("IN") => Some(CompareOp::In),
+            _ if self.is_keyword("STARTS") && self.is_keyword_at(1, "WITH") => {
+                Some(CompareOp::StartsWith)
+            }
+            _ if self.is_keyword("ENDS") && self.is_keyword_at(1, "WITH") => {
+                Some(CompareOp::EndsWith)
+            }
+            _ => None,
+        };
+
+        let Some(op) = op else {
+            return Ok(left);
+        };
+        self.advance();
+        if matches!(op, CompareOp::StartsWith | CompareOp::EndsWith) {
+            self.advance();
+        }
+
+        let right = self.operand()?;
+        Ok(Expr::Compare {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        })
+    }
+
+    fn operand(&mut self) -> Result<Expr> {
+        match self.peek() {
+            TokenKind::LParen => {
+                self.advance();
+                let expr = self.expr()?;
+                self.expect(&TokenKind::RParen, "')'")?;
+                Ok(expr)
+            }
+            TokenKind::Ident(name)
+                if !["true", "false", "null"]
+                    .iter()
+                    .any(|k| name.eq_ignore_ascii_case(k)) =>
+            {
+                let variable = self.ident("a variable")?;
+                let mut path = Vec::new();
+                while self.eat(&TokenKind::Dot) {
+                    path.push(self.ident("a property name after '.'")?);
+                }
+                if path.is_empty() {
+                    Ok(Expr::Variable(variable))
+                } else {
+                    Ok(Expr::Property { variable, path })
+                }
+            }
+            _ => Ok(Expr::Literal(self.literal()?)),
+        }
+    }
+
+    fn literal(&mut self) -> Result<Value> {
+        let negative = self.eat(&TokenKind::Dash);
+        let value = match self.peek().clone() {
+            TokenKind::Int(n) => Value::from(if negative { -n } else { n }),
+            TokenKind::Float(f) => Value::from(if negative { -f } else { f }),
+            _ if negative => return Err(self.error("a number after '-'")),
+            TokenKind::Str(s) => Value::String(s),
+            TokenKind::Ident(s) if s.eq_ignore_ascii_case("true") => Value::Bool(true),
+            TokenKind::Ident(s) if s.eq_ignore_ascii_case("false") => Value::Bool(false),
+            TokenKind::Ident(s) if s.eq_ignore_ascii_case("null") => Value::Null,
+            TokenKind::LBracket => {
+                self.advance();
+                let mut items = Vec::new();
+                if !self.check(&TokenKind::RBracket) {
+                    loop {
+                        items.push(self.literal()?);
+                        if !self.eat(&TokenKind::Comma) {
+                            break;
+                        }
+                    }
+                }
+                self.expect(&TokenKind::RBracket, "']' to close a list")?;
+                return Ok(Value::Array(items));
+            }
+            _ => return Err(self.error("a literal value")),
+        };
+        self.advance();
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_full_query() {
+        let query = parse_query(
+            "MATCH (p:Prompt)<-[:RESPONDS_TO]-(r:Response)-[:INVOKES]->(t:Tool) \
+             WHERE t.success = false AND p.metadata.model STARTS WITH 'gpt' \
+             RETURN p.content AS prompt, t.tool_name \
+             ORDER BY p.timestamp DESC SKIP 5 LIMIT 10",
+        )
+        .unwrap();
+
+        assert_eq!(query.patterns.len(), 1);
+        let pattern = &query.patterns[0];
+        assert_eq!(pattern.start.label, Some(NodeType::Prompt));
+        assert_eq!(pattern.steps.len(), 2);
+        assert_eq!(pattern.steps[0].0.direction, Direction::Incoming);
+        assert_eq!(pattern.steps[0].0.edge_types, vec![EdgeType::RespondsTo]);
+        assert_eq!(pattern.steps[1].0.direction, Direction::Outgoing);
+        assert_eq!(pattern.steps[1].1.label, Some(NodeType::ToolInvocation));
+
+        assert_eq!(query.returns.len(), 2);
+        assert_eq!(query.returns[0].column_name(), "prompt");
+        assert_eq!(query.returns[1].column_name(), "t.tool_name");
+        assert!(query.order_by[0].descending);
+        assert_eq!(query.skip, Some(5));
+        assert_eq!(query.limit, Some(10));
+
+        let conjuncts = query.where_clause.unwrap().into_conjuncts();
+        assert_eq!(conjuncts.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_inline_properties_and_lists() {
+        let query = parse_query(
+            "match (a:Agent {role: 'coder'}), (p)-[h:HANDLED_BY|TRANSFERS_TO]-(a) \
+             where p.metadata.temperature >= 0.5 or a.status in ['Active', 'Idle'] \
+             return distinct a",
+        )
+        .unwrap();
+
+        assert_eq!(query.patterns.len(), 2);
+        assert_eq!(
+            query.patterns[0].start.properties,
+            vec![("role".to_string(), Value::from("coder"))]
+        );
+        let rel = &query.patterns[1].steps[0].0;
+        assert_eq!(rel.variable, "h");
+        assert_eq!(rel.direction, Direction::Both);
+        assert_eq!(rel.edge_types.len(), 2);
+        assert!(query.distinct);
+        assert!(matches!(query.where_clause, Some(Expr::Or(_, _))));
+    }
+
+    #[test]
+    fn test_anonymous_variables_are_unique() {
+        let query = parse_query("MATCH ()-->()<--() RETURN count").unwrap();
+        let nodes = query.patterns[0].nodes();
+        assert_ne!(nodes[0].variable, nodes[1].variable);
+        assert_ne!(nodes[1].variable, nodes[2].variable);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_query("RETURN p").is_err());
+        assert!(parse_query("MATCH (p:Unknown) RETURN p").is_err());
+        assert!(parse_query("MATCH (p)-[:NOPE]->(q) RETURN p").is_err());
+        assert!(parse_query("MATCH (p) RETURN p LIMIT -1").is_err());
+        assert!(parse_query("MATCH (p)<-[]->(q) RETURN p").is_err());
+
+        let err = parse_query("MATCH (p:Prompt RETURN p").unwrap_err();
+        assert!(err.to_string().contains("position"));
+    }
+}
@@ -0,0 +1,543 @@
+//! Query planner turning a parsed [`Query`] into an executable [`QueryPlan`]
+//!
+//! For every MATCH pattern the planner picks an anchor node, the one with the
+//! cheapest access path, and expands the rest of the chain outward from it along
+//! edges. WHERE conjuncts are pushed down to run right after the step that binds
+//! the last variable they reference, so rows are discarded as early as possible.
+//!
+//! Access paths, from cheapest to most expensive:
+//!
+//! 1. a variable already bound by an earlier pattern
+//! 2. a node ID equality (`p.id = '...'`) answered with a point lookup
+//! 3. a session equality (`p.session_id = '...'`, `s.id = '...'` on sessions)
+//!    answered from the session index
+//! 4. a full node scan, filtered by label
+
+use super::ast::{CompareOp, Direction, Expr, OrderItem, Projection, Query};
+use crate::{EdgeType, NodeId, NodeType, SessionId};
+use crate::{Error, Result};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fmt;
+
+/// A single operator in a query plan
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlanStep {
+    /// Bind `variable` to every stored node, optionally restricted to a label
+    ScanNodes {
+        /// Variable to bind
+        variable: String,
+        /// Required node type
+        label: Option<NodeType>,
+    },
+    /// Bind `variable` to the nodes of one session
+    ScanSession {
+        /// Variable to bind
+        variable: String,
+        /// Required node type
+        label: Option<NodeType>,
+        /// Session whose nodes are scanned
+        session_id: SessionId,
+    },
+    /// Bind `variable` to a single node fetched by ID
+    LookupNode {
+        /// Variable to bind
+        variable: String,
+        /// Required node type
+        label: Option<NodeType>,
+        /// Node to fetch
+        node_id: NodeId,
+    },
+    /// Follow edges from an already bound node
+    Expand {
+        /// Bound source variable
+        from: String,
+        /// Variable to bind each traversed edge to
+        edge_variable: String,
+        /// Allowed edge types; empty means any
+        edge_types: Vec<EdgeType>,
+        /// Direction relative to `from`
+        direction: Direction,
+        /// Variable to bind (or check, if already bound) at the far end
+        to: String,
+        /// Required node type at the far end
+        to_label: Option<NodeType>,
+    },
+    /// Drop rows for which the predicate is not true
+    Filter(Expr),
+}
+
+impl PlanStep {
+    /// Short name of the operator, used for stage statistics and plan shapes
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlanStep::ScanNodes { .. } => "scan_nodes",
+            PlanStep::ScanSession { .. } => "scan_session",
+            PlanStep::LookupNode { .. } => "lookup_node",
+            PlanStep::Expand { .. } => "expand",
+            PlanStep::Filter(_) => "filter",
+        }
+    }
+}
+
+impl fmt::Display for PlanStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label =
+            |l: &Option<NodeType>| l.as_ref().map(|l| format!(":{l:?}")).unwrap_or_default();
+        match self {
+            PlanStep::ScanNodes { variable, label: l } => {
+                write!(f, "ScanNodes({variable}{})", label(l))
+            }
+            PlanStep::ScanSession {
+                variable,
+                label: l,
+                session_id,
+            } => write!(
+                f,
+                "ScanSession({variable}{}, session={session_id})",
+                label(l)
+            ),
+            PlanStep::LookupNode {
+                variable,
+                label: l,
+                node_id,
+            } => write!(f, "LookupNode({variable}{}, id={node_id})", label(l)),
+            PlanStep::Expand {
+                from,
+                edge_variable,
+                edge_types,
+                direction,
+                to,
+                to_label,
+            } => {
+                let types = edge_types
+                    .iter()
+                    .map(|t| format!("{t:?}"))
+                    .collect::<Vec<_>>()
+                    .join("|");
+                let (left, right) = match direction {
+                    Direction::Outgoing => ("-", "->"),
+                    Direction::Incoming => ("<-", "-"),
+                    Direction::Both => ("-", "-"),
+                };
+                write!(
+                    f,
+                    "Expand(({from}){left}[{edge_variable}:{types}]{right}({to}{}))",
+                    label(to_label)
+                )
+            }
+            PlanStep::Filter(expr) => write!(f, "Filter({expr})"),
+        }
+    }
+}
+
+/// An executable query plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPlan {
+    /// Operators producing the matched rows, in execution order
+    pub steps: Vec<PlanStep>,
+    /// Whether duplicate result rows are removed
+    pub distinct: bool,
+    /// Result projections
+    pub projections: Vec<Projection>,
+    /// Sort keys
+    pub order_by: Vec<OrderItem>,
+    /// Rows to skip after sorting
+    pub skip: Option<usize>,
+    /// Maximum rows to return
+    pub limit: Option<usize>,
+}
+
+impl QueryPlan {
+    /// Bounded-cardinality name for the plan, e.g. `scan_session+filter+expand`
+    ///
+    /// Lists each kind of step once, in order of first use, so it can serve as
+    /// a metric label. The plan itself, as printed by `Display`, is the explain
+    /// output for the query language.
+    pub fn shape(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for step in &self.steps {
+            if !names.contains(&step.name()) {
+                names.push(step.name());
+            }
+        }
+        names.join("+")
+    }
+}
+
+impl fmt::Display for QueryPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for step in &self.steps {
+            writeln!(f, "{step}")?;
+        }
+        let columns = self
+            .projections
+            .iter()
+            .map(Projection::column_name)
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(
+            f,
+            "Project({}{columns})",
+            if self.distinct { "DISTINCT " } else { "" }
+        )?;
+        if !self.order_by.is_empty() {
+            let keys = self
+                .order_by
+                .iter()
+                .map(|o| format!("{}{}", o.expr, if o.descending { " DESC" } else { "" }))
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "\nSort({keys})")?;
+        }
+        if let Some(skip) = self.skip {
+            write!(f, "\nSkip({skip})")?;
+        }
+        if let Some(limit) = self.limit {
+            write!(f, "\nLimit({limit})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Name of the field holding a node's graph ID for a given label
+fn node_id_field(label: Option<&NodeType>) -> Option<&'static str> {
+    match label? {
+        NodeType::Prompt | NodeType::Response | NodeType::ToolInvocation | NodeType::Custom => {
+            Some("id")
+        }
+        NodeType::Session | NodeType::Agent | NodeType::Template => Some("node_id"),
+    }
+}
+
+/// Name of the field holding a node's session ID for a given label
+fn session_field(label: Option<&NodeType>) -> Option<&'static str> {
+    match label? {
+        NodeType::Prompt => Some("session_id"),
+        NodeType::Session => Some("id"),
+        _ => None,
+    }
+}
+
+/// If `expr` is `variable.field = 'string'` (either way round), return the string
+fn string_equality<'a>(expr: &'a Expr, variable: &str, field: &str) -> Option<&'a str> {
+    let Expr::Compare {
+        left,
+        op: CompareOp::Eq,
+        right,
+    } = expr
+    else {
+        return None;
+    };
+    let matches_field = |e: &Expr| matches!(e, Expr::Property { variable: v, path } if v == variable && path.len() == 1 && path[0] == field);
+    match (left.as_ref(), right.as_ref()) {
+        (l, Expr::Literal(Value::String(s))) if matches_field(l) => Some(s),
+        (Expr::Literal(Value::String(s)), r) if matches_field(r) => Some(s),
+        _ => None,
+    }
+}
+
+/// Build the access step for an anchor node, or `None` if it needs a full scan
+fn index_access(variable: &str, label: Option<&NodeType>, conjuncts: &[Expr]) -> Option<PlanStep> {
+    if let Some(field) = node_id_field(label) {
+        for term in conjuncts {
+            if let Some(id) = string_equality(term, variable, field) {
+                if let Ok(uuid) = uuid::Uuid::parse_str(id) {
+                    return Some(PlanStep::LookupNode {
+                        variable: variable.to_string(),
+                        label: label.cloned(),
+                        node_id: NodeId::from(uuid),
+                    });
+                }
+            }
+        }
+    }
+    if let Some(field) = session_field(label) {
+        for term in conjuncts {
+            if let Some(id) = string_equality(term, variable, field) {
+                if let Ok(uuid) = uuid::Uuid::parse_str(id) {
+                    return Some(PlanStep::ScanSession {
+                        variable: variable.to_string(),
+                        label: label.cloned(),
+                        session_id: SessionId::from(uuid),
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Plan a parsed query
+///
+/// Fails if the query references variables that no pattern binds, or binds the
+/// same variable to both a node and a relationship.
+pub fn plan_query(query: &Query) -> Result<QueryPlan> {
+    // Inline `{key: value}` properties are just more equality conjuncts
+    let mut conjuncts: Vec<Expr> = query
+        .where_clause
+        .clone()
+        .map(Expr::into_conjuncts)
+        .unwrap_or_default();
+    let mut node_vars = HashSet::new();
+    let mut edge_vars = HashSet::new();
+    for pattern in &query.patterns {
+        for node in pattern.nodes() {
+            node_vars.insert(node.variable.clone());
+            for (key, value) in &node.properties {
+                conjuncts.push(Expr::Compare {
+                    left: Box::new(Expr::Property {
+                        variable: node.variable.clone(),
+                        path: vec![key.clone()],
+                    }),
+                    op: CompareOp::Eq,
+                    right: Box::new(Expr::Literal(value.clone())),
+                });
+            }
+        }
+        for (rel, _) in &pattern.steps {
+            if !edge_vars.insert(rel.variable.clone()) {
+                return Err(Error::QueryError(format!(
+                    "Relationship variable '{}' is bound more than once",
+                    rel.variable
+                )));
+            }
+        }
+    }
+    if let Some(var) = node_vars.intersection(&edge_vars).next() {
+        return Err(Error::QueryError(format!(
+            "Variable '{var}' is used for both a node and a relationship"
+        )));
+    }
+
+    // Every referenced variable must be bound by some pattern
+    let known: HashSet<&str> = node_vars
+        .iter()
+        .chain(edge_vars.iter())
+        .map(String::as_str)
+        .collect();
+    let aliases: HashSet<String> = query
+        .returns
+        .iter()
+        .filter_map(|p| p.alias.clone())
+        .collect();
+    let check = |expr: &Expr, allow_aliases: bool| -> Result<()> {
+        for var in expr.variables() {
+            if !(known.contains(var) || allow_aliases && aliases.contains(var)) {
+                return Err(Error::QueryError(format!("Unknown variable '{var}'")));
+            }
+        }
+        Ok(())
+    };
+    for term in &conjuncts {
+        check(term, false)?;
+    }
+    for projection in &query.returns {
+        check(&projection.expr, false)?;
+    }
+    for item in &query.order_by {
+        check(&item.expr, true)?;
+    }
+
+    let mut steps = Vec::new();
+    let mut bound: HashSet<String> = HashSet::new();
+    let mut pending = conjuncts;
+
+    for pattern in &query.patterns {
+        let nodes = pattern.nodes();
+
+        // Pick the anchor with the cheapest access path
+        let mut best: Option<(u8, usize, Option<PlanStep>)> = None;
+        for (idx, node) in nodes.iter().enumerate() {
+            let (cost, access) = if bound.contains(&node.variable) {
+                (0, None)
+            } else if let Some(step) = index_access(&node.variable, node.label.as_ref(), &pending) {
+                let cost = if matches!(step, PlanStep::LookupNode { .. }) {
+                    1
+                } else {
+                    2
+                };
+                (cost, Some(step))
+            } else if node.label.is_some() {
+                (3, None)
+            } else {
+                (4, None)
+            };
+            if best.as_ref().is_none_or(|(c, _, _)| cost < *c) {
+                best = Some((cost, idx, access));
+            }
+        }
+        let (cost, anchor, access) = best.expect("patterns always contain a node");
+
+        if cost > 0 {
+            let node = nodes[anchor];
+            steps.push(access.unwrap_or_else(|| PlanStep::ScanNodes {
+                variable: node.variable.clone(),
+                label: node.label.clone(),
+            }));
+            bound.insert(node.variable.clone());
+            push_ready_filters(&mut steps, &mut pending, &bound);
+        } else if let Some(label) = &nodes[anchor].label {
+            // Re-using a bound variable with a label still constrains its type
+            steps.push(label_filter(&nodes[anchor].variable, label));
+        }
+
+        // Expand to the right of the anchor, then to the left
+        for (from, (rel, to)) in nodes.iter().zip(&pattern.steps).skip(anchor) {
+            steps.push(PlanStep::Expand {
+                from: from.variable.clone(),
+                edge_variable: rel.variable.clone(),
+                edge_types: rel.edge_types.clone(),
+                direction: rel.direction,
+                to: to.variable.clone(),
+                to_label: to.label.clone(),
+            });
+            bound.insert(rel.variable.clone());
+            bound.insert(to.variable.clone());
+            push_ready_filters(&mut steps, &mut pending, &bound);
+        }
+        for i in (0..anchor).rev() {
+            let (rel, _) = &pattern.steps[i];
+            steps.push(PlanStep::Expand {
+                from: nodes[i + 1].variable.clone(),
+                edge_variable: rel.variable.clone(),
+                edge_types: rel.edge_types.clone(),
+                direction: rel.direction.reversed(),
+                to: nodes[i].variable.clone(),
+                to_label: nodes[i].label.clone(),
+            });
+            bound.insert(rel.variable.clone());
+            bound.insert(nodes[i].variable.clone());
+            push_ready_filters(&mut steps, &mut pending, &bound);
+        }
+    }
+
+    // Predicates without variables (e.g. `WHERE 1 = 1`) run once at the end
+    steps.extend(pending.into_iter().map(PlanStep::Filter));
+
+    Ok(QueryPlan {
+        steps,
+        distinct: query.distinct,
+        projections: query.returns.clone(),
+        order_by: query.order_by.clone(),
+        skip: query.skip,
+        limit: query.limit,
+    })
+}
+
+/// Move every pending conjunct whose variables are all bound into the plan
+fn push_ready_filters(steps: &mut Vec<PlanStep>, pending: &mut Vec<Expr>, bound: &HashSet<String>) {
+    let (ready, waiting): (Vec<_>, Vec<_>) = pending.drain(..).partition(|term| {
+        let vars = term.variables();
+        !vars.is_empty() && vars.iter().all(|v| bound.contains(*v))
+    });
+    *pending = waiting;
+    steps.extend(ready.into_iter().map(PlanStep::Filter));
+}
+
+/// Filter requiring `variable` to be a node of the given type
+fn label_filter(variable: &str, label: &NodeType) -> PlanStep {
+    PlanStep::Filter(Expr::Compare {
+        left: Box::new(Expr::Property {
+            variable: variable.to_string(),
+            path: vec![super::executor::LABEL_FIELD.to_string()],
+        }),
+        op: CompareOp::Eq,
+        right: Box::new(Expr::Literal(Value::String(format!("{label:?}")))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::language::parse_query;
+
+    fn plan(input: &str) -> QueryPlan {
+        plan_query(&parse_query(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_plan_uses_node_lookup() {
+        let id = NodeId::new();
+        let plan = plan(&format!(
+            "MATCH (r:Response)-[:RESPONDS_TO]->(p:Prompt) WHERE p.id = '{id}' RETURN r"
+        ));
+
+        // The prompt is the anchor, so the response is reached by reversing the edge
+        assert_eq!(
+            plan.steps[0],
+            PlanStep::LookupNode {
+                variable: "p".to_string(),
+                label: Some(NodeType::Prompt),
+                node_id: id,
+            }
+        );
+        // The equality used for the lookup is still checked
+        assert!(matches!(plan.steps[1], PlanStep::Filter(_)));
+        assert!(matches!(
+            &plan.steps[2],
+            PlanStep::Expand { from, direction: Direction::Incoming, to, .. } if from == "p" && to == "r"
+        ));
+    }
+
+    #[test]
+    fn test_plan_uses_session_index() {
+        let session = SessionId::new();
+        let plan = plan(&format!(
+            "MATCH (p:Prompt {{session_id: '{session}'}}) RETURN p.content"
+        ));
+        assert!(matches!(
+            &plan.steps[0],
+            PlanStep::ScanSession { session_id, .. } if *session_id == session
+        ));
+    }
+
+    #[test]
+    fn test_plan_prefers_labelled_scan_and_pushes_filters() {
+        let plan = plan(
+            "MATCH (x)-[:HANDLED_BY]->(a:Agent) WHERE a.role = 'coder' AND x.content CONTAINS 'rust' RETURN x",
+        );
+        assert!(matches!(
+            &plan.steps[0],
+            PlanStep::ScanNodes { variable, label: Some(NodeType::Agent) } if variable == "a"
+        ));
+        // a.role is checked right after the scan, before expanding
+        assert!(matches!(&plan.steps[1], PlanStep::Filter(e) if e.variables() == vec!["a"]));
+        assert!(matches!(&plan.steps[2], PlanStep::Expand { .. }));
+        assert!(matches!(&plan.steps[3], PlanStep::Filter(e) if e.variables() == vec!["x"]));
+    }
+
+    #[test]
+    fn test_plan_joins_on_shared_variables() {
+        let plan = plan("MATCH (a:Agent), (p:Prompt)-[:HANDLED_BY]->(a) RETURN p");
+        let scans = plan
+            .steps
+            .iter()
+            .filter(|s| matches!(s, PlanStep::ScanNodes { .. }))
+            .count();
+        assert_eq!(scans, 1);
+    }
+
+    #[test]
+    fn test_plan_rejects_unknown_variables() {
+        let query = parse_query("MATCH (p:Prompt) WHERE q.content = 'x' RETURN p").unwrap();
+        assert!(plan_query(&query).is_err());
+
+        let query = parse_query("MATCH (p)-[p]->(q) RETURN p").unwrap();
+        assert!(plan_query(&query).is_err());
+
+        // ORDER BY may refer to RETURN aliases
+        let query = parse_query("MATCH (p:Prompt) RETURN p.content AS text ORDER BY text").unwrap();
+        assert!(plan_query(&query).is_ok());
+    }
+
+    #[test]
+    fn test_plan_display() {
+        let plan = plan("MATCH (p:Prompt) WHERE p.content CONTAINS 'x' RETURN p LIMIT 3");
+        let text = plan.to_string();
+        assert!(text.contains("ScanNodes(p:Prompt)"));
+        assert!(text.contains("Filter(p.content CONTAINS 'x')"));
+        assert!(text.contains("Limit(3)"));
+        assert_eq!(plan.shape(), "scan_nodes+filter");
+    }
+}
@@ -1,12 +1,32 @@
 //! Query interface for graph traversal and filtering
 
+pub mod aggregate;
 pub mod async_query;
-
+pub mod async_traversal;
+pub mod cache;
+pub mod cursor;
+pub mod explain;
+pub mod language;
+pub mod pattern;
+pub mod predicate;
+pub mod traversal;
+
+pub use aggregate::{AggregateRow, Aggregation};
 pub use async_query::AsyncQueryBuilder;
+pub use async_traversal::AsyncGraphTraversal;
+pub use cache::QueryCache;
+pub use cursor::{Cursor, Page};
+pub use explain::{AccessPath, Explain, PlanFilter, QueryStats, StageStats};
+pub use language::{QueryExecutor, QueryResult};
+pub use pattern::{GraphPattern, PatternMatch, PatternScope};
+pub use predicate::{Field, Predicate};
+pub use traversal::{Direction, Path, Subgraph, TraversalOptions, Visit};
 
 use crate::{Error, Result};
 use crate::{EdgeType, Node, NodeId, NodeType, SessionId};
+use explain::{plan_shape, sort_and_paginate, NodeFilters, StatsRecorder};
 use chrono::{DateTime, Utc};
+use futures::FutureExt;
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::visit::{Bfs, Dfs};
 use std::collections::HashMap;
@@ -37,6 +57,8 @@ pub struct QueryBuilder<'a> {
     node_type_filter: Option<NodeType>,
     start_time: Option<DateTime<Utc>>,
     end_time: Option<DateTime<Utc>>,
+    predicate: Option<Predicate>,
+    cursor: Option<Cursor>,
     limit: Option<usize>,
     offset: usize,
 }
@@ -62,6 +84,8 @@ impl<'a> QueryBuilder<'a> {
             node_type_filter: None,
             start_time: None,
             end_time: None,
+            predicate: None,
+            cursor: None,
             limit: None,
             offset: 0,
         }
@@ -142,6 +166,34 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
+    /// Keep only nodes matching `predicate`
+    ///
+    /// May be called more than once; the predicates are combined with AND. A
+    /// query with a predicate but no session filter scans the whole graph
+    /// unless the predicate confines it to known sessions.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::{MemoryGraph, Config, query::QueryBuilder};
+    /// # use llm_memory_graph::query::{Field, Predicate};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = MemoryGraph::open(Config::default())?;
+    /// let failed_tools = QueryBuilder::new(&graph)
+    ///     .filter(Field::ToolSuccess.eq(false).and(Field::ToolName.eq("web_search")))
+    ///     .execute()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.predicate = Some(match self.predicate.take() {
+            Some(existing) => existing.and(predicate),
+            None => predicate,
+        });
+        self
+    }
+
     /// Limit the number of results
     ///
     /// # Examples
@@ -178,6 +230,41 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
+    /// Resume after the last row of a previous page
+    ///
+    /// Use this in place of [`offset`](Self::offset): the page starts strictly
+    /// after the cursor position, so nodes added since the previous page do
+    /// not shift or repeat rows.
+    ///
+    /// A query answered by a reverse lookup seeks to the cursor in the lookup
+    /// index and never reads earlier rows. Session and full-scan queries have
+    /// no timestamp-ordered index to seek in, so every page loads and sorts
+    /// the whole result before cutting it at the cursor.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::{MemoryGraph, Config, query::QueryBuilder};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = MemoryGraph::open(Config::default())?;
+    /// # let session = graph.create_session()?;
+    /// let page = QueryBuilder::new(&graph).session(session.id).limit(10).execute_page()?;
+    /// if let Some(cursor) = page.next_cursor {
+    ///     let next = QueryBuilder::new(&graph)
+    ///         .session(session.id)
+    ///         .cursor(cursor)
+    ///         .limit(10)
+    ///         .execute_page()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub const fn cursor(mut self, cursor: Cursor) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
     /// Execute the query and return matching nodes
     ///
     /// # Errors
@@ -200,83 +287,156 @@ impl<'a> QueryBuilder<'a> {
     /// # }
     /// ```
     pub fn execute(&self) -> Result<Vec<Node>> {
-        let mut nodes = if let Some(session_id) = self.session_filter {
-            self.graph.get_session_nodes(session_id)?
-        } else {
-            // If no session filter, we'd need to scan all nodes
-            // For now, require a session filter for efficiency
-            return Err(Error::ValidationError(
-                "Query must specify a session filter".to_string(),
-            ));
-        };
+        Ok(self.execute_page()?.items)
+    }
 
-        // Apply node type filter
-        if let Some(ref node_type) = self.node_type_filter {
-            nodes.retain(|n| n.node_type() == *node_type);
-        }
+    /// Execute the query and return one page with a cursor for the next
+    ///
+    /// `next_cursor` is set when a [`limit`](Self::limit) cut the result short.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage retrieval fails, or if both a cursor and an
+    /// offset were given.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::{MemoryGraph, Config, query::QueryBuilder};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = MemoryGraph::open(Config::default())?;
+    /// # let session = graph.create_session()?;
+    /// let page = QueryBuilder::new(&graph)
+    ///     .session(session.id)
+    ///     .limit(20)
+    ///     .execute_page()?;
+    /// let token = page.next_cursor.map(|cursor| cursor.encode());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_page(&self) -> Result<Page> {
+        Ok(self.execute_page_with_stats()?.0)
+    }
 
-        // Apply time filters
-        if let Some(start_time) = self.start_time {
-            nodes.retain(|n| {
-                let timestamp = match n {
-                    Node::Prompt(p) => p.timestamp,
-                    Node::Response(r) => r.timestamp,
-                    Node::Session(s) => s.created_at,
-                    Node::ToolInvocation(t) => t.timestamp,
-                    Node::Agent(a) => a.created_at,
-                    Node::Template(t) => t.created_at,
-                };
-                timestamp >= start_time
-            });
-        }
+    /// Describe how the query would run without running it
+    ///
+    /// Reports the access path, the filters applied before and after nodes
+    /// are fetched, and row estimates based on storage statistics.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage statistics cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::{MemoryGraph, Config, query::QueryBuilder};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = MemoryGraph::open(Config::default())?;
+    /// # let session = graph.create_session()?;
+    /// let plan = QueryBuilder::new(&graph).session(session.id).explain()?;
+    /// println!("{plan}");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain(&self) -> Result<Explain> {
+        let stats = self.graph.stats()?;
+        Ok(self
+            .filters()
+            .explain(&stats, self.cursor.is_some(), self.offset, self.limit))
+    }
+
+    /// Execute the query and report what it did
+    ///
+    /// Returns the same nodes as [`execute`](Self::execute) along with the
+    /// nodes scanned and returned and the time spent in each stage.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// [`execute_page`](Self::execute_page).
+    pub fn execute_with_stats(&self) -> Result<(Vec<Node>, QueryStats)> {
+        let (page, stats) = self.execute_page_with_stats()?;
+        Ok((page.items, stats))
+    }
 
-        if let Some(end_time) = self.end_time {
-            nodes.retain(|n| {
-                let timestamp = match n {
-                    Node::Prompt(p) => p.timestamp,
-                    Node::Response(r) => r.timestamp,
-                    Node::Session(s) => s.created_at,
-                    Node::ToolInvocation(t) => t.timestamp,
-                    Node::Agent(a) => a.created_at,
-                    Node::Template(t) => t.created_at,
-                };
-                timestamp <= end_time
-            });
+    /// Execute the query for one page and report what it did
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as
+    /// [`execute_page`](Self::execute_page).
+    pub fn execute_page_with_stats(&self) -> Result<(Page, QueryStats)> {
+        if self.cursor.is_some() && self.offset > 0 {
+            return Err(Error::ValidationError(
+                "Query cannot combine a cursor with an offset".to_string(),
+            ));
         }
 
-        // Sort by timestamp (newest first)
-        nodes.sort_by(|a, b| {
-            let time_a = match a {
-                Node::Prompt(p) => p.timestamp,
-                Node::Response(r) => r.timestamp,
-                Node::Session(s) => s.created_at,
-                Node::ToolInvocation(t) => t.timestamp,
-                Node::Agent(a) => a.created_at,
-                Node::Template(t) => t.created_at,
-            };
-            let time_b = match b {
-                Node::Prompt(p) => p.timestamp,
-                Node::Response(r) => r.timestamp,
-                Node::Session(s) => s.created_at,
-                Node::ToolInvocation(t) => t.timestamp,
-                Node::Agent(a) => a.created_at,
-                Node::Template(t) => t.created_at,
-            };
-            time_b.cmp(&time_a)
-        });
+        let filters = self.filters();
+        let (access_path, _, post_fetch) = filters.plan(false);
+        let mut recorder = StatsRecorder::new(plan_shape(&access_path, &post_fetch));
 
-        // Apply offset and limit
-        let start = self.offset;
-        let end = if let Some(limit) = self.limit {
-            (start + limit).min(nodes.len())
-        } else {
-            nodes.len()
+        let nodes = match &access_path {
+            AccessPath::SessionIndex { sessions } => {
+                let mut nodes = Vec::new();
+                for session_id in sessions {
+                    nodes.extend(self.graph.get_session_nodes(*session_id)?);
+                }
+                nodes
+            }
+            AccessPath::Lookup(lookup) => {
+                self.graph
+                    .lookup_nodes(lookup, self.cursor.as_ref(), usize::MAX)?
+                    .items
+            }
+            AccessPath::FullScan => self.graph.scan_nodes()?,
+            AccessPath::Empty => {
+                // Without a session or predicate this would return the whole graph
+                return Err(Error::ValidationError(
+                    "Query must specify a session filter or predicate".to_string(),
+                ));
+            }
         };
+        recorder.fetched(nodes.len());
+        recorder.stage("fetch", 0, nodes.len());
+
+        let nodes = filters.apply(nodes, &mut recorder);
+        let page = sort_and_paginate(
+            nodes,
+            self.cursor.as_ref(),
+            self.offset,
+            self.limit,
+            &mut recorder,
+        );
+
+        let stats = recorder.finish(page.items.len());
+        Ok((page, stats))
+    }
 
-        Ok(nodes[start..end].to_vec())
+    fn filters(&self) -> NodeFilters<'_> {
+        NodeFilters {
+            session: self.session_filter,
+            predicate: self.predicate.as_ref(),
+            node_type: self.node_type_filter.as_ref(),
+            start: self.start_time,
+            end: self.end_time,
+        }
     }
 }
 
+/// Run a traversal against the synchronous graph
+///
+/// [`MemoryGraph`](crate::engine::MemoryGraph) answers every lookup of the
+/// shared traversal code without suspending, so the traversal finishes on its
+/// first poll. No executor is involved, which keeps the [`GraphTraversal`]
+/// methods safe to call from inside an async runtime.
+fn run_sync<T>(traversal: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+    traversal
+        .now_or_never()
+        .unwrap_or_else(|| unreachable!("synchronous graph lookups never suspend"))
+}
+
 /// Graph traversal utilities
 pub struct GraphTraversal<'a> {
     graph: &'a crate::engine::MemoryGraph,
@@ -501,6 +661,12 @@ impl<'a> GraphTraversal<'a> {
                     "Cannot get conversation thread for template nodes".to_string(),
                 ));
             }
+            Node::Custom(c) => c.session_id.ok_or_else(|| {
+                Error::TraversalError(format!(
+                    "Custom '{}' node does not belong to a session",
+                    c.kind
+                ))
+            })?,
         };
 
         // Get all nodes in the session
@@ -518,6 +684,7 @@ impl<'a> GraphTraversal<'a> {
                 Node::ToolInvocation(t) => t.timestamp,
                 Node::Agent(ag) => ag.created_at,
                 Node::Template(t) => t.created_at,
+                Node::Custom(c) => c.created_at,
             };
             let time_b = match b {
                 Node::Prompt(p) => p.timestamp,
@@ -526,6 +693,7 @@ impl<'a> GraphTraversal<'a> {
                 Node::ToolInvocation(t) => t.timestamp,
                 Node::Agent(ag) => ag.created_at,
                 Node::Template(t) => t.created_at,
+                Node::Custom(c) => c.created_at,
             };
             time_a.cmp(&time_b)
         });
@@ -568,6 +736,94 @@ impl<'a> GraphTraversal<'a> {
 
         Ok(responses)
     }
+
+    /// Breadth-first traversal from `start` within the given limits
+    ///
+    /// Unlike [`bfs`](Self::bfs), this only follows the allowed edges and
+    /// returns each node with its depth and the edge it was reached through.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` does not exist or storage fails.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use llm_memory_graph::{MemoryGraph, Config, EdgeType, query::GraphTraversal};
+    /// # use llm_memory_graph::query::traversal::{Direction, TraversalOptions};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let graph = MemoryGraph::open(Config::default())?;
+    /// # let session = graph.create_session()?;
+    /// # let prompt_id = graph.add_prompt(session.id, "Test".to_string(), None)?;
+    /// let options = TraversalOptions::new()
+    ///     .direction(Direction::Outgoing)
+    ///     .edge_type(EdgeType::Follows)
+    ///     .max_depth(5);
+    /// for visit in GraphTraversal::new(&graph).bfs_with(prompt_id, &options)? {
+    ///     println!("{} at depth {}", visit.node.id(), visit.depth);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn bfs_with(&self, start: NodeId, options: &TraversalOptions) -> Result<Vec<Visit>> {
+        run_sync(traversal::bfs(self.graph, start, options))
+    }
+
+    /// Depth-first traversal from `start` within the given limits
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` does not exist or storage fails.
+    pub fn dfs_with(&self, start: NodeId, options: &TraversalOptions) -> Result<Vec<Visit>> {
+        run_sync(traversal::dfs(self.graph, start, options))
+    }
+
+    /// Fewest-hop path from `from` to `to`, or `None` if there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either endpoint does not exist or storage fails.
+    pub fn shortest_path(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        options: &TraversalOptions,
+    ) -> Result<Option<Path>> {
+        run_sync(traversal::shortest_path(self.graph, from, to, options))
+    }
+
+    /// Every simple path from `from` to `to` with at most `max_length` edges
+    ///
+    /// Paths are returned shortest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either endpoint does not exist or storage fails.
+    pub fn simple_paths(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        max_length: usize,
+        options: &TraversalOptions,
+    ) -> Result<Vec<Path>> {
+        run_sync(traversal::simple_paths(
+            self.graph, from, to, max_length, options,
+        ))
+    }
+
+    /// Nodes within `hops` of `start` together with the edges between them
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `start` does not exist or storage fails.
+    pub fn neighborhood(
+        &self,
+        start: NodeId,
+        hops: usize,
+        options: &TraversalOptions,
+    ) -> Result<Subgraph> {
+        run_sync(traversal::neighborhood(self.graph, start, hops, options))
+    }
 }
 
 #[cfg(test)]
@@ -624,6 +880,39 @@ mod tests {
         assert_eq!(nodes.len(), 2);
     }
 
+    #[test]
+    fn test_query_predicate_without_session() {
+        use crate::{AgentNode, AgentStatus};
+
+        let dir = tempdir().unwrap();
+        let graph = MemoryGraph::open(Config::new(dir.path())).unwrap();
+
+        let mut reviewer =
+            AgentNode::new("reviewer".to_string(), "code-review".to_string(), vec![]);
+        reviewer.status = AgentStatus::Active;
+        graph.add_agent(reviewer).unwrap();
+        graph
+            .add_agent(AgentNode::new(
+                "planner".to_string(),
+                "planning".to_string(),
+                vec![],
+            ))
+            .unwrap();
+
+        let nodes = QueryBuilder::new(&graph)
+            .filter(Field::AgentStatus.eq(AgentStatus::Active))
+            .execute()
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+
+        let nodes = QueryBuilder::new(&graph)
+            .filter(Field::AgentRole.one_of(["planning", "code-review"]))
+            .filter(!Field::AgentName.starts_with("plan"))
+            .execute()
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+    }
+
     #[test]
     fn test_bfs_traversal() {
         let dir = tempdir().unwrap();
@@ -642,6 +931,53 @@ mod tests {
         assert_eq!(nodes[0], prompt_id);
     }
 
+    #[tokio::test]
+    async fn test_traversals_run_inside_runtime() {
+        let dir = tempdir().unwrap();
+        let graph = MemoryGraph::open(Config::new(dir.path())).unwrap();
+
+        let session = graph.create_session().unwrap();
+        let prompt_id = graph
+            .add_prompt(session.id, "Test".to_string(), None)
+            .unwrap();
+        let response_id = graph
+            .add_response(
+                prompt_id,
+                "Response".to_string(),
+                TokenUsage::new(1, 1),
+                None,
+            )
+            .unwrap();
+
+        let traversal = GraphTraversal::new(&graph);
+        let options = TraversalOptions::new();
+        assert_eq!(
+            traversal.bfs_with(prompt_id, &options).unwrap()[0]
+                .node
+                .id(),
+            prompt_id
+        );
+        assert_eq!(
+            traversal.dfs_with(prompt_id, &options).unwrap()[0]
+                .node
+                .id(),
+            prompt_id
+        );
+        let path = traversal
+            .shortest_path(response_id, prompt_id, &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.len(), 1);
+        assert_eq!(
+            traversal
+                .simple_paths(response_id, prompt_id, 1, &options)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(traversal.neighborhood(prompt_id, 1, &options).is_ok());
+    }
+
     #[test]
     fn test_conversation_thread() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,1069 @@
+//! Structural pattern matching over conversation subgraphs
+//!
+//! A [`GraphPattern`] is a small graph of typed pattern nodes, each with an
+//! optional [`Predicate`], connected by directed pattern edges that name the
+//! [`EdgeType`]s they accept. Constraints relate the nodes of a match to each
+//! other, such as requiring several tool calls to share a tool name or to
+//! happen in order. [`AsyncMemoryGraph::match_pattern`](crate::engine::AsyncMemoryGraph::match_pattern)
+//! finds every subgraph isomorphic to the pattern: pattern nodes bind to
+//! distinct graph nodes and pattern edges to distinct graph edges.
+//!
+//! A [`PatternScope`] restricts the search to sessions and a time window, or
+//! requires every match to stay inside one session. Scopes apply to the
+//! conversation nodes of a match (sessions, prompts, responses, tool
+//! invocations and custom nodes recorded in a session); agents, templates and
+//! session-less custom nodes are shared between sessions and match wherever
+//! they are.
+//!
+//! Symmetric patterns match once per symmetry: three interchangeable tool
+//! calls produce six bindings of the same three nodes unless a constraint
+//! such as [`GraphPattern::before`] orders them.
+//!
+//! # Examples
+//!
+//! A response invoking the same tool three times in a row, each retried:
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::query::pattern::{GraphPattern, PatternScope};
+//! use llm_memory_graph::query::predicate::Field;
+//! use llm_memory_graph::{Config, EdgeType, NodeType};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//!
+//! let mut pattern = GraphPattern::new().node("r", NodeType::Response);
+//! for call in ["a", "b", "c"] {
+//!     pattern = pattern
+//!         .node(call, NodeType::ToolInvocation)
+//!         .filter(call, Field::ToolRetryCount.gt(0))
+//!         .edge("r", EdgeType::Invokes, call);
+//! }
+//! let pattern = pattern
+//!     .same_value(Field::ToolName, ["a", "b", "c"])
+//!     .before("a", "b")
+//!     .before("b", "c");
+//!
+//! for found in graph.match_pattern(&pattern, &PatternScope::new()).await? {
+//!     println!("{:?} retried {:?}", found.node("r").map(|n| n.id()), found.node("a"));
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use super::predicate::{Field, Predicate, PredicateContext};
+use crate::search::node_timestamp;
+use crate::storage::AsyncStorageBackend;
+use crate::{Edge, EdgeId, EdgeType, Node, NodeId, NodeType, SessionId};
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+
+/// A node in a [`GraphPattern`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternNode {
+    /// Name the matched node is reported under
+    pub variable: String,
+    /// Required node type; any type when `None`
+    pub node_type: Option<NodeType>,
+    /// Condition the matched node must satisfy
+    pub predicate: Option<Predicate>,
+}
+
+/// A directed edge in a [`GraphPattern`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternEdge {
+    /// Variable of the source node
+    pub from: String,
+    /// Variable of the target node
+    pub to: String,
+    /// Accepted edge types; any type when empty
+    pub edge_types: Vec<EdgeType>,
+    /// Properties the matched edge must carry with exactly these values
+    pub properties: Vec<(String, String)>,
+}
+
+impl PatternEdge {
+    /// An edge of any type from `from` to `to`
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+            edge_types: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    /// Accept edges of `edge_type`, in addition to any already accepted
+    #[must_use]
+    pub fn edge_type(mut self, edge_type: EdgeType) -> Self {
+        self.edge_types.push(edge_type);
+        self
+    }
+
+    /// Require the edge property `key` to equal `value`
+    #[must_use]
+    pub fn property(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.properties.push((key.into(), value.into()));
+        self
+    }
+
+    fn accepts(&self, edge: &Edge) -> bool {
+        (self.edge_types.is_empty() || self.edge_types.contains(&edge.edge_type))
+            && self
+                .properties
+                .iter()
+                .all(|(key, value)| edge.properties.get(key) == Some(value))
+    }
+}
+
+/// A condition between several nodes of a match
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// The nodes all have `field`, with equal values
+    SameValue {
+        /// Field to compare
+        field: Field,
+        /// Variables of the nodes
+        variables: Vec<String>,
+    },
+    /// `first` was created strictly before `second`
+    Before {
+        /// Variable of the earlier node
+        first: String,
+        /// Variable of the later node
+        second: String,
+    },
+}
+
+impl Constraint {
+    fn variables(&self) -> Vec<&str> {
+        match self {
+            Constraint::SameValue { variables, .. } => {
+                variables.iter().map(String::as_str).collect()
+            }
+            Constraint::Before { first, second } => vec![first, second],
+        }
+    }
+
+    fn holds(&self, node: impl Fn(&str) -> Option<Node>) -> bool {
+        match self {
+            Constraint::SameValue { field, variables } => {
+                let mut values = variables
+                    .iter()
+                    .map(|v| node(v).and_then(|n| field.value(&n)));
+                let Some(Some(first)) = values.next() else {
+                    return false;
+                };
+                values.all(|value| value.as_ref() == Some(&first))
+            }
+            Constraint::Before { first, second } => match (node(first), node(second)) {
+                (Some(a), Some(b)) => node_timestamp(&a) < node_timestamp(&b),
+                _ => false,
+            },
+        }
+    }
+}
+
+/// A subgraph shape to search for
+///
+/// Variables are declared with [`node`](Self::node) or
+/// [`any_node`](Self::any_node) before edges, filters and constraints refer
+/// to them; [`validate`](Self::validate) reports undeclared variables.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphPattern {
+    /// Pattern nodes, in declaration order
+    pub nodes: Vec<PatternNode>,
+    /// Pattern edges, in declaration order
+    pub edges: Vec<PatternEdge>,
+    /// Conditions between nodes
+    pub constraints: Vec<Constraint>,
+}
+
+impl GraphPattern {
+    /// An empty pattern
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a node of `node_type`
+    #[must_use]
+    pub fn node(mut self, variable: impl Into<String>, node_type: NodeType) -> Self {
+        self.nodes.push(PatternNode {
+            variable: variable.into(),
+            node_type: Some(node_type),
+            predicate: None,
+        });
+        self
+    }
+
+    /// Declare a node of any type
+    #[must_use]
+    pub fn any_node(mut self, variable: impl Into<String>) -> Self {
+        self.nodes.push(PatternNode {
+            variable: variable.into(),
+            node_type: None,
+            predicate: None,
+        });
+        self
+    }
+
+    /// Require the node bound to `variable` to match `predicate`, in addition
+    /// to any earlier filter on it
+    ///
+    /// Filters on undeclared variables are ignored; use
+    /// [`validate`](Self::validate) to catch them.
+    #[must_use]
+    pub fn filter(mut self, variable: &str, predicate: Predicate) -> Self {
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.variable == variable) {
+            node.predicate = Some(match node.predicate.take() {
+                Some(existing) => existing.and(predicate),
+                None => predicate,
+            });
+        }
+        self
+    }
+
+    /// Require an edge of `edge_type` from `from` to `to`
+    #[must_use]
+    pub fn edge(self, from: impl Into<String>, edge_type: EdgeType, to: impl Into<String>) -> Self {
+        self.edge_matching(PatternEdge::new(from, to).edge_type(edge_type))
+    }
+
+    /// Require an edge described by `edge`
+    #[must_use]
+    pub fn edge_matching(mut self, edge: PatternEdge) -> Self {
+        self.edges.push(edge);
+        self
+    }
+
+    /// Require the nodes bound to `variables` to share a value of `field`
+    #[must_use]
+    pub fn same_value<V: Into<String>>(
+        mut self,
+        field: Field,
+        variables: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.constraints.push(Constraint::SameValue {
+            field,
+            variables: variables.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Require the node bound to `first` to be created before the one bound
+    /// to `second`
+    #[must_use]
+    pub fn before(mut self, first: impl Into<String>, second: impl Into<String>) -> Self {
+        self.constraints.push(Constraint::Before {
+            first: first.into(),
+            second: second.into(),
+        });
+        self
+    }
+
+    /// Check that the pattern has nodes, unique variables, and edges and
+    /// constraints that only refer to declared variables
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error describing the first problem found.
+    pub fn validate(&self) -> Result<()> {
+        if self.nodes.is_empty() {
+            return Err(Error::ValidationError(
+                "A pattern needs at least one node".to_string(),
+            ));
+        }
+        let mut declared = HashSet::new();
+        for node in &self.nodes {
+            if !declared.insert(node.variable.as_str()) {
+                return Err(Error::ValidationError(format!(
+                    "Pattern variable '{}' is declared twice",
+                    node.variable
+                )));
+            }
+        }
+
+        let referenced = self
+            .edges
+            .iter()
+            .flat_map(|e| [e.from.as_str(), e.to.as_str()])
+            .chain(self.constraints.iter().flat_map(Constraint::variables));
+        for variable in referenced {
+            if !declared.contains(variable) {
+                return Err(Error::ValidationError(format!(
+                    "Pattern variable '{variable}' is not declared"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    fn index_of(&self, variable: &str) -> usize {
+        self.nodes
+            .iter()
+            .position(|n| n.variable == variable)
+            .expect("pattern was validated")
+    }
+}
+
+/// Where a pattern may match
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatternScope {
+    /// Only match conversation nodes from these sessions; all sessions when empty
+    pub sessions: Vec<SessionId>,
+    /// Only match prompts, responses and tool invocations created in this
+    /// inclusive range
+    pub time_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    /// Require the conversation nodes of each match to share one session
+    pub same_session: bool,
+    /// Stop after this many matches
+    pub limit: Option<usize>,
+}
+
+impl PatternScope {
+    /// Search the whole graph
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Search `session_id`, in addition to any sessions already in scope
+    #[must_use]
+    pub fn session(mut self, session_id: SessionId) -> Self {
+        self.sessions.push(session_id);
+        self
+    }
+
+    /// Only match prompts, responses and tool invocations created between
+    /// `start` and `end`, inclusive
+    #[must_use]
+    pub fn time_range(mut self, start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        self.time_range = Some((start, end));
+        self
+    }
+
+    /// Require each match to stay within one session
+    #[must_use]
+    pub fn same_session(mut self) -> Self {
+        self.same_session = true;
+        self
+    }
+
+    /// Stop after `limit` matches
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// One binding of a pattern to the graph
+#[derive(Debug, Clone)]
+pub struct PatternMatch {
+    /// Matched node for each pattern variable
+    pub nodes: HashMap<String, Node>,
+    /// Matched edge for each pattern edge, in [`GraphPattern::edges`] order
+    pub edges: Vec<Edge>,
+}
+
+impl PatternMatch {
+    /// The node bound to `variable`
+    pub fn node(&self, variable: &str) -> Option<&Node> {
+        self.nodes.get(variable)
+    }
+}
+
+/// Whether a node is part of a conversation, and so subject to the scope
+fn in_conversation(node: &Node) -> bool {
+    match node {
+        Node::Agent(_) | Node::Template(_) => false,
+        Node::Custom(custom) => custom.session_id.is_some(),
+        _ => true,
+    }
+}
+
+/// A pattern node in search order, with the edge used to reach it
+struct Step {
+    variable: usize,
+    /// Pattern edge to an earlier step, and whether this node is its target
+    anchor: Option<(usize, bool)>,
+}
+
+/// Order pattern nodes so each one after the first of its connected
+/// component is reached through an edge from an earlier one
+///
+/// Components start from a typed node, preferring conversation types when the
+/// scope names sessions, since those can be listed from the session index.
+fn search_order(pattern: &GraphPattern, scoped: bool) -> Vec<Step> {
+    let rank = |node: &PatternNode| match &node.node_type {
+        Some(NodeType::Agent | NodeType::Template) if scoped => 2,
+        Some(_) => 0,
+        None => 1,
+    };
+
+    let mut placed = vec![false; pattern.nodes.len()];
+    let mut order: Vec<Step> = Vec::with_capacity(pattern.nodes.len());
+    while order.len() < pattern.nodes.len() {
+        let seed = (0..pattern.nodes.len())
+            .filter(|i| !placed[*i])
+            .min_by_key(|i| rank(&pattern.nodes[*i]))
+            .expect("an unplaced node remains");
+        placed[seed] = true;
+        let component_start = order.len();
+        order.push(Step {
+            variable: seed,
+            anchor: None,
+        });
+
+        let mut next = component_start;
+        while next < order.len() {
+            let current = order[next].variable;
+            for (index, edge) in pattern.edges.iter().enumerate() {
+                let from = pattern.index_of(&edge.from);
+                let to = pattern.index_of(&edge.to);
+                let (other, is_target) = if from == current {
+                    (to, true)
+                } else if to == current {
+                    (from, false)
+                } else {
+                    continue;
+                };
+                if !placed[other] {
+                    placed[other] = true;
+                    order.push(Step {
+                        variable: other,
+                        anchor: Some((index, is_target)),
+                    });
+                }
+            }
+            next += 1;
+        }
+    }
+    order
+}
+
+/// Backtracking subgraph matcher with memoized storage lookups
+struct Matcher<'a> {
+    storage: &'a dyn AsyncStorageBackend,
+    pattern: &'a GraphPattern,
+    scope: &'a PatternScope,
+    order: Vec<Step>,
+    nodes: HashMap<NodeId, Option<Node>>,
+    outgoing: HashMap<NodeId, Vec<Edge>>,
+    incoming: HashMap<NodeId, Vec<Edge>>,
+    sessions: HashMap<NodeId, Option<SessionId>>,
+    session_nodes: HashMap<SessionId, Node>,
+    /// Nodes a component may start from, loaded on first use
+    seeds: Option<Vec<NodeId>>,
+    /// Every node in the graph, loaded when a seed is outside the session pool
+    all: Option<Vec<NodeId>>,
+    matches: Vec<PatternMatch>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(
+        storage: &'a dyn AsyncStorageBackend,
+        pattern: &'a GraphPattern,
+        scope: &'a PatternScope,
+    ) -> Self {
+        Self {
+            storage,
+            pattern,
+            scope,
+            order: search_order(pattern, !scope.sessions.is_empty()),
+            nodes: HashMap::new(),
+            outgoing: HashMap::new(),
+            incoming: HashMap::new(),
+            sessions: HashMap::new(),
+            session_nodes: HashMap::new(),
+            seeds: None,
+            all: None,
+            matches: Vec::new(),
+        }
+    }
+
+    fn remember(&mut self, node: Node) -> NodeId {
+        let id = node.id();
+        if let Node::Session(session) = &node {
+            self.session_nodes.insert(session.id, node.clone());
+        }
+        self.nodes.insert(id, Some(node));
+        id
+    }
+
+    async fn node(&mut self, id: NodeId) -> Result<Option<Node>> {
+        if let Some(node) = self.nodes.get(&id) {
+            return Ok(node.clone());
+        }
+        let node = self.storage.get_node(&id).await?;
+        match &node {
+            Some(node) => {
+                self.remember(node.clone());
+            }
+            None => {
+                self.nodes.insert(id, None);
+            }
+        }
+        Ok(node)
+    }
+
+    async fn edges(&mut self, id: NodeId, outgoing: bool) -> Result<Vec<Edge>> {
+        let memo = if outgoing {
+            &self.outgoing
+        } else {
+            &self.incoming
+        };
+        if let Some(edges) = memo.get(&id) {
+            return Ok(edges.clone());
+        }
+        let edges = if outgoing {
+            self.storage.get_outgoing_edges(&id).await?
+        } else {
+            self.storage.get_incoming_edges(&id).await?
+        };
+        let memo = if outgoing {
+            &mut self.outgoing
+        } else {
+            &mut self.incoming
+        };
+        memo.insert(id, edges.clone());
+        Ok(edges)
+    }
+
+    /// Graph edges that can stand for pattern edge `index` between bound nodes
+    async fn edges_between(&mut self, index: usize, from: NodeId, to: NodeId) -> Result<Vec<Edge>> {
+        let pattern_edge = &self.pattern.edges[index];
+        Ok(self
+            .edges(from, true)
+            .await?
+            .into_iter()
+            .filter(|edge| edge.to == to && pattern_edge.accepts(edge))
+            .collect())
+    }
+
+    /// Session of a conversation node, following responses and tool calls
+    /// up to their prompt
+    async fn session_of(&mut self, id: NodeId) -> Result<Option<SessionId>> {
+        if let Some(session) = self.sessions.get(&id) {
+            return Ok(*session);
+        }
+        let mut current = id;
+        let session = loop {
+            match self.node(current).await? {
+                Some(Node::Prompt(prompt)) => break Some(prompt.session_id),
+                Some(Node::Session(session)) => break Some(session.id),
+                Some(Node::Response(response)) => current = response.prompt_id,
+                Some(Node::ToolInvocation(tool)) => current = tool.response_id,
+                Some(Node::Custom(custom)) => break custom.session_id,
+                _ => break None,
+            }
+        };
+        self.sessions.insert(id, session);
+        Ok(session)
+    }
+
+    /// Context for evaluating predicates on `node` on its own
+    async fn context(&mut self, node: &Node) -> Result<PredicateContext> {
+        let mut related = vec![node.clone()];
+        let mut parent = match node {
+            Node::ToolInvocation(tool) => Some(tool.response_id),
+            Node::Response(response) => Some(response.prompt_id),
+            _ => None,
+        };
+        while let Some(id) = parent.take() {
+            if let Some(next) = self.node(id).await? {
+                if let Node::Response(response) = &next {
+                    parent = Some(response.prompt_id);
+                }
+                related.push(next);
+            }
+        }
+        if let Some(session) = self.session_of(node.id()).await? {
+            if !self.session_nodes.contains_key(&session) {
+                // Session nodes are only reachable through the session index
+                for node in self.storage.get_session_nodes(&session).await? {
+                    self.remember(node);
+                }
+            }
+            related.extend(self.session_nodes.get(&session).cloned());
+        }
+        Ok(PredicateContext::from_nodes(&related))
+    }
+
+    /// Candidate start nodes: the scoped sessions' nodes, or the whole graph
+    async fn seeds(&mut self, variable: usize) -> Result<Vec<NodeId>> {
+        let pattern_node = &self.pattern.nodes[variable];
+        let conversational = !matches!(
+            pattern_node.node_type,
+            Some(NodeType::Agent | NodeType::Template)
+        );
+        if self.scope.sessions.is_empty() || !conversational {
+            return self.all_nodes().await;
+        }
+
+        if let Some(seeds) = &self.seeds {
+            return Ok(seeds.clone());
+        }
+        let mut seeds = Vec::new();
+        for session_id in &self.scope.sessions {
+            for node in self.storage.get_session_nodes(session_id).await? {
+                let is_response = matches!(node, Node::Response(_));
+                let id = self.remember(node);
+                seeds.push(id);
+                // Tool invocations are reached through their response
+                if is_response {
+                    for edge in self.edges(id, true).await? {
+                        if edge.edge_type == EdgeType::Invokes {
+                            seeds.push(edge.to);
+                        }
+                    }
+                }
+            }
+        }
+        self.seeds = Some(seeds.clone());
+        Ok(seeds)
+    }
+
+    async fn all_nodes(&mut self) -> Result<Vec<NodeId>> {
+        if let Some(all) = &self.all {
+            return Ok(all.clone());
+        }
+        let all: Vec<NodeId> = self
+            .storage
+            .scan_nodes()
+            .await?
+            .into_iter()
+            .map(|node| self.remember(node))
+            .collect();
+        self.all = Some(all.clone());
+        Ok(all)
+    }
+
+    /// Graph nodes that step `depth` may bind to
+    async fn candidates(
+        &mut self,
+        depth: usize,
+        binding: &[Option<NodeId>],
+    ) -> Result<Vec<NodeId>> {
+        let step = &self.order[depth];
+        let variable = step.variable;
+        let Some((index, is_target)) = step.anchor else {
+            return self.seeds(variable).await;
+        };
+
+        let pattern_edge = &self.pattern.edges[index];
+        let other = if is_target {
+            &pattern_edge.from
+        } else {
+            &pattern_edge.to
+        };
+        let anchor = binding[self.pattern.index_of(other)].expect("anchor is bound earlier");
+        let mut seen = HashSet::new();
+        Ok(self
+            .edges(anchor, is_target)
+            .await?
+            .into_iter()
+            .filter(|edge| self.pattern.edges[index].accepts(edge))
+            .map(|edge| if is_target { edge.to } else { edge.from })
+            .filter(|id| seen.insert(*id))
+            .collect())
+    }
+
+    /// Whether `id` can be bound at step `depth` given the earlier bindings
+    async fn admits(
+        &mut self,
+        depth: usize,
+        id: NodeId,
+        binding: &[Option<NodeId>],
+    ) -> Result<bool> {
+        let variable = self.order[depth].variable;
+        if binding.contains(&Some(id)) {
+            return Ok(false);
+        }
+        let Some(node) = self.node(id).await? else {
+            return Ok(false);
+        };
+
+        let pattern_node = &self.pattern.nodes[variable];
+        if pattern_node
+            .node_type
+            .as_ref()
+            .is_some_and(|t| *t != node.node_type())
+        {
+            return Ok(false);
+        }
+        if let Some(predicate) = &pattern_node.predicate {
+            let context = self.context(&node).await?;
+            if !predicate.evaluate(&node, &context) {
+                return Ok(false);
+            }
+        }
+        if !self.in_scope(&node, binding).await? {
+            return Ok(false);
+        }
+
+        let mut bound = binding.to_vec();
+        bound[variable] = Some(id);
+        for index in 0..self.pattern.edges.len() {
+            let edge = &self.pattern.edges[index];
+            let from = self.pattern.index_of(&edge.from);
+            let to = self.pattern.index_of(&edge.to);
+            if from != variable && to != variable {
+                continue;
+            }
+            if let (Some(from), Some(to)) = (bound[from], bound[to]) {
+                if self.edges_between(index, from, to).await?.is_empty() {
+                    return Ok(false);
+                }
+            }
+        }
+
+        for constraint in &self.pattern.constraints {
+            let variables: Vec<usize> = constraint
+                .variables()
+                .into_iter()
+                .map(|v| self.pattern.index_of(v))
+                .collect();
+            if !variables.contains(&variable) || variables.iter().any(|v| bound[*v].is_none()) {
+                continue;
+            }
+            let holds = constraint.holds(|name| {
+                bound[self.pattern.index_of(name)]
+                    .and_then(|id| self.nodes.get(&id).cloned().flatten())
+            });
+            if !holds {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn in_scope(&mut self, node: &Node, binding: &[Option<NodeId>]) -> Result<bool> {
+        if !in_conversation(node) {
+            return Ok(true);
+        }
+        if let (Some((start, end)), Node::Prompt(_) | Node::Response(_) | Node::ToolInvocation(_)) =
+            (self.scope.time_range, node)
+        {
+            let created = node_timestamp(node);
+            if created < start || created > end {
+                return Ok(false);
+            }
+        }
+        if self.scope.sessions.is_empty() && !self.scope.same_session {
+            return Ok(true);
+        }
+
+        let Some(session) = self.session_of(node.id()).await? else {
+            return Ok(false);
+        };
+        if !self.scope.sessions.is_empty() && !self.scope.sessions.contains(&session) {
+            return Ok(false);
+        }
+        if self.scope.same_session {
+            for other in binding.iter().flatten() {
+                let is_conversation = self
+                    .nodes
+                    .get(other)
+                    .and_then(Option::as_ref)
+                    .is_some_and(in_conversation);
+                if is_conversation && self.session_of(*other).await? != Some(session) {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Record every way of choosing distinct edges for a complete node binding
+    async fn emit(&mut self, binding: &[Option<NodeId>]) -> Result<()> {
+        let ids: Vec<NodeId> = binding
+            .iter()
+            .map(|id| id.expect("binding is complete"))
+            .collect();
+        let mut options = Vec::with_capacity(self.pattern.edges.len());
+        for index in 0..self.pattern.edges.len() {
+            let edge = &self.pattern.edges[index];
+            let from = ids[self.pattern.index_of(&edge.from)];
+            let to = ids[self.pattern.index_of(&edge.to)];
+            options.push(self.edges_between(index, from, to).await?);
+        }
+
+        let nodes: HashMap<String, Node> = self
+            .pattern
+            .nodes
+            .iter()
+            .zip(&ids)
+            .filter_map(|(pattern_node, id)| {
+                let node = self.nodes.get(id).cloned().flatten()?;
+                Some((pattern_node.variable.clone(), node))
+            })
+            .collect();
+
+        let mut chosen: Vec<usize> = vec![0; options.len()];
+        let mut depth = 0;
+        // Odometer over edge choices, skipping reuse of an edge
+        loop {
+            if depth == options.len() {
+                self.matches.push(PatternMatch {
+                    nodes: nodes.clone(),
+                    edges: chosen
+                        .iter()
+                        .zip(&options)
+                        .map(|(i, edges)| edges[*i].clone())
+                        .collect(),
+                });
+                if self.is_full() || depth == 0 {
+                    return Ok(());
+                }
+                depth -= 1;
+                chosen[depth] += 1;
+                continue;
+            }
+            if chosen[depth] >= options[depth].len() {
+                if depth == 0 {
+                    return Ok(());
+                }
+                chosen[depth] = 0;
+                depth -= 1;
+                chosen[depth] += 1;
+                continue;
+            }
+            let edge_id: EdgeId = options[depth][chosen[depth]].id;
+            let reused = (0..depth).any(|d| options[d][chosen[d]].id == edge_id);
+            if reused {
+                chosen[depth] += 1;
+            } else {
+                depth += 1;
+            }
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.scope
+            .limit
+            .is_some_and(|limit| self.matches.len() >= limit)
+    }
+
+    async fn run(mut self) -> Result<Vec<PatternMatch>> {
+        if self.scope.limit == Some(0) {
+            return Ok(Vec::new());
+        }
+
+        let mut binding: Vec<Option<NodeId>> = vec![None; self.pattern.nodes.len()];
+        let first = self.candidates(0, &binding).await?;
+        let mut stack: Vec<(Vec<NodeId>, usize)> = vec![(first, 0)];
+
+        while !stack.is_empty() {
+            let depth = stack.len() - 1;
+            let variable = self.order[depth].variable;
+            binding[variable] = None;
+
+            let (candidates, next) = &mut stack[depth];
+            let Some(id) = candidates.get(*next).copied() else {
+                stack.pop();
+                continue;
+            };
+            *next += 1;
+
+            if !self.admits(depth, id, &binding).await? {
+                continue;
+            }
+            binding[variable] = Some(id);
+
+            if depth + 1 == self.order.len() {
+                self.emit(&binding).await?;
+                if self.is_full() {
+                    break;
+                }
+            } else {
+                let candidates = self.candidates(depth + 1, &binding).await?;
+                stack.push((candidates, 0));
+            }
+        }
+
+        Ok(self.matches)
+    }
+}
+
+/// Find every binding of `pattern` within `scope`
+pub(crate) async fn match_pattern(
+    storage: &dyn AsyncStorageBackend,
+    pattern: &GraphPattern,
+    scope: &PatternScope,
+) -> Result<Vec<PatternMatch>> {
+    pattern.validate()?;
+    Matcher::new(storage, pattern, scope).run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::AsyncMemoryGraph;
+    use crate::{AgentNode, Config, TokenUsage, ToolInvocation};
+    use chrono::Duration;
+    use tempfile::tempdir;
+
+    async fn turn(graph: &AsyncMemoryGraph, session: SessionId) -> (NodeId, NodeId) {
+        let prompt = graph
+            .add_prompt(session, "question".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(prompt, "answer".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        (prompt, response)
+    }
+
+    #[tokio::test]
+    async fn test_repeated_retried_tool_calls() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let (_, response) = turn(&graph, session.id).await;
+
+        let base = Utc::now();
+        let calls = [
+            ("search", 1),
+            ("search", 2),
+            ("calculator", 1),
+            ("search", 1),
+            ("search", 0),
+        ];
+        let mut ids = Vec::new();
+        for (second, (name, retries)) in (0..).zip(calls) {
+            let mut tool = ToolInvocation::new(response, name.to_string(), serde_json::json!({}));
+            tool.retry_count = retries;
+            tool.timestamp = base + Duration::seconds(second);
+            ids.push(graph.add_tool_invocation(tool).await.unwrap());
+        }
+
+        let mut pattern = GraphPattern::new().node("r", NodeType::Response);
+        for call in ["a", "b", "c"] {
+            pattern = pattern
+                .node(call, NodeType::ToolInvocation)
+                .filter(call, Field::ToolRetryCount.gt(0))
+                .edge("r", EdgeType::Invokes, call);
+        }
+        let unordered = pattern.clone().same_value(Field::ToolName, ["a", "b", "c"]);
+        let ordered = unordered.clone().before("a", "b").before("b", "c");
+
+        let found = graph
+            .match_pattern(&ordered, &PatternScope::new())
+            .await
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        let bound: Vec<NodeId> = ["a", "b", "c"]
+            .iter()
+            .map(|v| found[0].node(v).unwrap().id())
+            .collect();
+        assert_eq!(bound, vec![ids[0], ids[1], ids[3]]);
+        assert_eq!(found[0].edges.len(), 3);
+        assert!(found[0].edges.iter().all(|e| e.from == response));
+
+        // Without an order, every permutation of the three calls is a match
+        let all = graph
+            .match_pattern(&unordered, &PatternScope::new())
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 6);
+        let limited = PatternScope::new().limit(2);
+        assert_eq!(
+            graph
+                .match_pattern(&unordered, &limited)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let window =
+            PatternScope::new().time_range(base + Duration::seconds(1), base + Duration::hours(1));
+        assert!(graph
+            .match_pattern(&ordered, &window)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let undeclared = GraphPattern::new()
+            .node("r", NodeType::Response)
+            .before("r", "x");
+        assert!(graph
+            .match_pattern(&undeclared, &PatternScope::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_transfer_and_back_within_a_session() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let mut agents = Vec::new();
+        for name in ["planner", "coder"] {
+            let agent = AgentNode::new(name.to_string(), "assistant".to_string(), vec![]);
+            agents.push(agent.node_id);
+            graph.add_agent(agent).await.unwrap();
+        }
+
+        // One session transfers there and back, another spreads it over two sessions
+        let here = graph.create_session().await.unwrap();
+        let other = graph.create_session().await.unwrap();
+        for (first, second) in [(here.id, here.id), (here.id, other.id)] {
+            let (p1, r1) = turn(&graph, first).await;
+            let (p2, r2) = turn(&graph, second).await;
+            graph.assign_agent_to_prompt(p1, agents[0]).await.unwrap();
+            graph.transfer_to_agent(r1, agents[1]).await.unwrap();
+            graph.assign_agent_to_prompt(p2, agents[1]).await.unwrap();
+            graph.transfer_to_agent(r2, agents[0]).await.unwrap();
+        }
+
+        let pattern = GraphPattern::new()
+            .node("a", NodeType::Agent)
+            .node("b", NodeType::Agent)
+            .node("p1", NodeType::Prompt)
+            .node("r1", NodeType::Response)
+            .node("p2", NodeType::Prompt)
+            .node("r2", NodeType::Response)
+            .edge("p1", EdgeType::HandledBy, "a")
+            .edge("r1", EdgeType::RespondsTo, "p1")
+            .edge("r1", EdgeType::TransfersTo, "b")
+            .edge("p2", EdgeType::HandledBy, "b")
+            .edge("r2", EdgeType::RespondsTo, "p2")
+            .edge("r2", EdgeType::TransfersTo, "a")
+            .before("r1", "r2");
+
+        let anywhere = graph
+            .match_pattern(&pattern, &PatternScope::new())
+            .await
+            .unwrap();
+        // Either pair, a turn of one pair followed by a turn of the other,
+        // and the coder handing back to the planner across pairs
+        assert_eq!(anywhere.len(), 4);
+
+        let same = graph
+            .match_pattern(&pattern, &PatternScope::new().same_session())
+            .await
+            .unwrap();
+        // Only the turns that all happened in `here` remain
+        assert_eq!(same.len(), 2);
+        assert!(same.iter().all(|m| {
+            ["p1", "p2"].iter().all(|v| match m.node(v) {
+                Some(Node::Prompt(p)) => p.session_id == here.id,
+                _ => false,
+            })
+        }));
+
+        let elsewhere = graph
+            .match_pattern(&pattern, &PatternScope::new().session(other.id))
+            .await
+            .unwrap();
+        assert!(elsewhere.is_empty());
+    }
+}
@@ -0,0 +1,839 @@
+//! Composable node predicates for the query builders
+//!
+//! A [`Predicate`] is a boolean expression over typed node fields, metadata
+//! maps and tags. Predicates compose with [`Predicate::and`], [`Predicate::or`]
+//! and `!` (or `.not()` from [`std::ops::Not`]), and are passed to
+//! [`QueryBuilder::filter`](super::QueryBuilder::filter) or
+//! [`AsyncQueryBuilder::filter`](super::AsyncQueryBuilder::filter).
+//!
+//! Comparisons against a field the node does not have (for example
+//! [`Field::Temperature`] on a response) are false, whatever the operator.
+//!
+//! Session restrictions are pushed down to the session index: a predicate that
+//! only admits nodes from known sessions loads just those sessions instead of
+//! scanning the whole graph.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::query::predicate::{Field, Predicate};
+//! use llm_memory_graph::query::AsyncQueryBuilder;
+//!
+//! # async fn example(builder: AsyncQueryBuilder) -> Result<(), Box<dyn std::error::Error>> {
+//! let slow_or_truncated = Field::LatencyMs
+//!     .gt(2_000)
+//!     .or(Field::FinishReason.eq("length"));
+//!
+//! let nodes = builder
+//!     .filter(Field::Model.eq("gpt-4").and(slow_or_truncated))
+//!     .filter(!Predicate::metadata("team", "billing"))
+//!     .execute()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::search::node_timestamp;
+use crate::{AgentStatus, Node, NodeId, NodeType, SessionId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// A typed node field that predicates can compare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Field {
+    /// Creation time of any node
+    Timestamp,
+    /// Prompt or response `metadata.model`, or an agent's model
+    Model,
+    /// Prompt `metadata.temperature`
+    Temperature,
+    /// Prompt `metadata.max_tokens`
+    MaxTokens,
+    /// Prompt or response content
+    Content,
+    /// Response `metadata.finish_reason`
+    FinishReason,
+    /// Response `metadata.latency_ms`
+    LatencyMs,
+    /// Response `usage.prompt_tokens`
+    PromptTokens,
+    /// Response `usage.completion_tokens`
+    CompletionTokens,
+    /// Response `usage.total_tokens`
+    TotalTokens,
+    /// Tool invocation name
+    ToolName,
+    /// Whether a tool invocation succeeded
+    ToolSuccess,
+    /// Tool invocation duration in milliseconds
+    ToolDurationMs,
+    /// Tool invocation retry count
+    ToolRetryCount,
+    /// Agent name
+    AgentName,
+    /// Agent role
+    AgentRole,
+    /// Agent status, compared as a lowercase string such as `"active"`
+    AgentStatus,
+    /// Template name
+    TemplateName,
+    /// Template author
+    TemplateAuthor,
+    /// ID of the template a prompt was instantiated from, as a string
+    TemplateId,
+    /// Custom node kind
+    Kind,
+}
+
+impl Field {
+    /// Read this field from a node, if the node has it
+    pub fn value(self, node: &Node) -> Option<FieldValue> {
+        let value = match (self, node) {
+            (Field::Timestamp, node) => FieldValue::Timestamp(node_timestamp(node)),
+            (Field::Model, Node::Prompt(p)) => p.metadata.model.clone().into(),
+            (Field::Model, Node::Response(r)) => r.metadata.model.clone().into(),
+            (Field::Model, Node::Agent(a)) => a.model.clone().into(),
+            (Field::Temperature, Node::Prompt(p)) => f64::from(p.metadata.temperature).into(),
+            (Field::MaxTokens, Node::Prompt(p)) => {
+                FieldValue::Integer(i64::try_from(p.metadata.max_tokens?).unwrap_or(i64::MAX))
+            }
+            (Field::Content, Node::Prompt(p)) => p.content.clone().into(),
+            (Field::Content, Node::Response(r)) => r.content.clone().into(),
+            (Field::FinishReason, Node::Response(r)) => r.metadata.finish_reason.clone().into(),
+            (Field::LatencyMs, Node::Response(r)) => r.metadata.latency_ms.into(),
+            (Field::PromptTokens, Node::Response(r)) => r.usage.prompt_tokens.into(),
+            (Field::CompletionTokens, Node::Response(r)) => r.usage.completion_tokens.into(),
+            (Field::TotalTokens, Node::Response(r)) => r.usage.total_tokens.into(),
+            (Field::ToolName, Node::ToolInvocation(t)) => t.tool_name.clone().into(),
+            (Field::ToolSuccess, Node::ToolInvocation(t)) => t.success.into(),
+            (Field::ToolDurationMs, Node::ToolInvocation(t)) => t.duration_ms.into(),
+            (Field::ToolRetryCount, Node::ToolInvocation(t)) => t.retry_count.into(),
+            (Field::AgentName, Node::Agent(a)) => a.name.clone().into(),
+            (Field::AgentRole, Node::Agent(a)) => a.role.clone().into(),
+            (Field::AgentStatus, Node::Agent(a)) => a.status.clone().into(),
+            (Field::TemplateName, Node::Template(t)) => t.name.clone().into(),
+            (Field::TemplateAuthor, Node::Template(t)) => t.author.clone().into(),
+            (Field::TemplateId, Node::Prompt(p)) => p.template_id?.to_string().into(),
+            (Field::Kind, Node::Custom(c)) => c.kind.clone().into(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// `field = value`
+    pub fn eq(self, value: impl Into<FieldValue>) -> Predicate {
+        self.compare(Comparison::Eq, value)
+    }
+
+    /// `field <> value`
+    pub fn ne(self, value: impl Into<FieldValue>) -> Predicate {
+        self.compare(Comparison::Ne, value)
+    }
+
+    /// `field < value`
+    pub fn lt(self, value: impl Into<FieldValue>) -> Predicate {
+        self.compare(Comparison::Lt, value)
+    }
+
+    /// `field <= value`
+    pub fn le(self, value: impl Into<FieldValue>) -> Predicate {
+        self.compare(Comparison::Le, value)
+    }
+
+    /// `field > value`
+    pub fn gt(self, value: impl Into<FieldValue>) -> Predicate {
+        self.compare(Comparison::Gt, value)
+    }
+
+    /// `field >= value`
+    pub fn ge(self, value: impl Into<FieldValue>) -> Predicate {
+        self.compare(Comparison::Ge, value)
+    }
+
+    /// `low <= field <= high`
+    pub fn between(self, low: impl Into<FieldValue>, high: impl Into<FieldValue>) -> Predicate {
+        self.ge(low).and(self.le(high))
+    }
+
+    /// Field is a string containing `value`
+    pub fn contains(self, value: impl Into<String>) -> Predicate {
+        self.compare(Comparison::Contains, value.into())
+    }
+
+    /// Field is a string starting with `value`
+    pub fn starts_with(self, value: impl Into<String>) -> Predicate {
+        self.compare(Comparison::StartsWith, value.into())
+    }
+
+    /// Field equals any of `values`
+    pub fn one_of<V: Into<FieldValue>>(self, values: impl IntoIterator<Item = V>) -> Predicate {
+        Predicate::Or(values.into_iter().map(|v| self.eq(v)).collect())
+    }
+
+    fn compare(self, op: Comparison, value: impl Into<FieldValue>) -> Predicate {
+        Predicate::Compare {
+            field: self,
+            op,
+            value: value.into(),
+        }
+    }
+}
+
+/// A value a field is compared against
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FieldValue {
+    /// Text
+    String(String),
+    /// Whole number
+    Integer(i64),
+    /// Floating-point number
+    Float(f64),
+    /// Boolean
+    Bool(bool),
+    /// Point in time
+    Timestamp(DateTime<Utc>),
+}
+
+impl FieldValue {
+    /// Order two values of compatible types; integers and floats compare numerically
+    fn partial_order(&self, other: &FieldValue) -> Option<Ordering> {
+        match (self, other) {
+            (FieldValue::String(a), FieldValue::String(b)) => Some(a.cmp(b)),
+            (FieldValue::Integer(a), FieldValue::Integer(b)) => Some(a.cmp(b)),
+            (FieldValue::Integer(a), FieldValue::Float(b)) => (*a as f64).partial_cmp(b),
+            (FieldValue::Float(a), FieldValue::Integer(b)) => a.partial_cmp(&(*b as f64)),
+            (FieldValue::Float(a), FieldValue::Float(b)) => a.partial_cmp(b),
+            (FieldValue::Bool(a), FieldValue::Bool(b)) => Some(a.cmp(b)),
+            (FieldValue::Timestamp(a), FieldValue::Timestamp(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::String(s) => write!(f, "'{s}'"),
+            FieldValue::Integer(i) => write!(f, "{i}"),
+            FieldValue::Float(x) => write!(f, "{x}"),
+            FieldValue::Bool(b) => write!(f, "{b}"),
+            FieldValue::Timestamp(t) => write!(f, "{}", t.to_rfc3339()),
+        }
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::String(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::String(value.to_string())
+    }
+}
+
+impl From<i64> for FieldValue {
+    fn from(value: i64) -> Self {
+        FieldValue::Integer(value)
+    }
+}
+
+impl From<i32> for FieldValue {
+    fn from(value: i32) -> Self {
+        FieldValue::Integer(i64::from(value))
+    }
+}
+
+impl From<u32> for FieldValue {
+    fn from(value: u32) -> Self {
+        FieldValue::Integer(i64::from(value))
+    }
+}
+
+impl From<u64> for FieldValue {
+    fn from(value: u64) -> Self {
+        FieldValue::Integer(i64::try_from(value).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<f32> for FieldValue {
+    fn from(value: f32) -> Self {
+        FieldValue::Float(f64::from(value))
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::Float(value)
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Bool(value)
+    }
+}
+
+impl From<DateTime<Utc>> for FieldValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        FieldValue::Timestamp(value)
+    }
+}
+
+impl From<AgentStatus> for FieldValue {
+    fn from(value: AgentStatus) -> Self {
+        FieldValue::String(format!("{value:?}").to_lowercase())
+    }
+}
+
+/// Comparison operators for [`Predicate::Compare`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Comparison {
+    /// `=`
+    Eq,
+    /// `<>`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// Substring match on strings
+    Contains,
+    /// Prefix match on strings
+    StartsWith,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Comparison::Eq => "=",
+            Comparison::Ne => "<>",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+            Comparison::Contains => "CONTAINS",
+            Comparison::StartsWith => "STARTS WITH",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A boolean condition on a node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Predicate {
+    /// Node belongs to this session
+    Session(SessionId),
+    /// Node has this type
+    NodeType(NodeType),
+    /// Typed field comparison
+    Compare {
+        /// Field to read
+        field: Field,
+        /// Operator
+        op: Comparison,
+        /// Right-hand side
+        value: FieldValue,
+    },
+    /// Node's metadata map has `key`, optionally with exactly `value`
+    ///
+    /// Checks prompt and response `metadata.custom`, and the `metadata` map of
+    /// sessions, tool invocations and templates.
+    Metadata {
+        /// Metadata key
+        key: String,
+        /// Required value; `None` only requires the key to be present
+        value: Option<String>,
+    },
+    /// Custom node payload value at a dot-separated `path` compares to `value`
+    ///
+    /// Only string, number and boolean payload values can match.
+    Payload {
+        /// Path into the payload, such as `"author.name"` or `"labels.0"`
+        path: String,
+        /// Operator
+        op: Comparison,
+        /// Right-hand side
+        value: FieldValue,
+    },
+    /// Node itself carries this tag (sessions, agents and templates)
+    Tag(String),
+    /// The session the node belongs to carries this tag
+    SessionTag(String),
+    /// All of the predicates hold (true when empty)
+    And(Vec<Predicate>),
+    /// Any of the predicates holds (false when empty)
+    Or(Vec<Predicate>),
+    /// The predicate does not hold
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    /// Node belongs to `session_id`
+    pub fn session(session_id: SessionId) -> Self {
+        Predicate::Session(session_id)
+    }
+
+    /// Node has type `node_type`
+    pub fn node_type(node_type: NodeType) -> Self {
+        Predicate::NodeType(node_type)
+    }
+
+    /// Metadata `key` equals `value`
+    pub fn metadata(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Predicate::Metadata {
+            key: key.into(),
+            value: Some(value.into()),
+        }
+    }
+
+    /// Metadata has `key`, with any value
+    pub fn has_metadata(key: impl Into<String>) -> Self {
+        Predicate::Metadata {
+            key: key.into(),
+            value: None,
+        }
+    }
+
+    /// Custom node payload value at `path` compares to `value` with `op`
+    pub fn payload(path: impl Into<String>, op: Comparison, value: impl Into<FieldValue>) -> Self {
+        Predicate::Payload {
+            path: path.into(),
+            op,
+            value: value.into(),
+        }
+    }
+
+    /// Custom node has kind `kind`
+    pub fn kind(kind: impl Into<String>) -> Self {
+        Field::Kind.eq(kind.into())
+    }
+
+    /// Node carries `tag`
+    pub fn tag(tag: impl Into<String>) -> Self {
+        Predicate::Tag(tag.into())
+    }
+
+    /// Node's session carries `tag`
+    pub fn session_tag(tag: impl Into<String>) -> Self {
+        Predicate::SessionTag(tag.into())
+    }
+
+    /// Both this and `other` hold
+    #[must_use]
+    pub fn and(self, other: Predicate) -> Self {
+        match self {
+            Predicate::And(mut terms) => {
+                terms.push(other);
+                Predicate::And(terms)
+            }
+            this => Predicate::And(vec![this, other]),
+        }
+    }
+
+    /// Either this or `other` holds
+    #[must_use]
+    pub fn or(self, other: Predicate) -> Self {
+        match self {
+            Predicate::Or(mut terms) => {
+                terms.push(other);
+                Predicate::Or(terms)
+            }
+            this => Predicate::Or(vec![this, other]),
+        }
+    }
+
+    /// Evaluate against a single node
+    ///
+    /// Without the surrounding query, only prompts, sessions and custom nodes
+    /// know their session, and [`Predicate::SessionTag`] is always false; the query
+    /// builders supply that context.
+    pub fn matches(&self, node: &Node) -> bool {
+        self.evaluate(node, &PredicateContext::default())
+    }
+
+    pub(crate) fn evaluate(&self, node: &Node, context: &PredicateContext) -> bool {
+        match self {
+            Predicate::Session(id) => context.session_of(node) == Some(*id),
+            Predicate::NodeType(node_type) => node.node_type() == *node_type,
+            Predicate::Compare { field, op, value } => field
+                .value(node)
+                .is_some_and(|actual| compare(&actual, *op, value)),
+            Predicate::Metadata { key, value } => metadata_of(node)
+                .and_then(|map| map.get(key))
+                .is_some_and(|actual| value.as_ref().is_none_or(|v| v == actual)),
+            Predicate::Payload { path, op, value } => {
+                payload_value(node, path).is_some_and(|actual| compare(&actual, *op, value))
+            }
+            Predicate::Tag(tag) => tags_of(node).is_some_and(|tags| tags.contains(tag)),
+            Predicate::SessionTag(tag) => context
+                .session_of(node)
+                .and_then(|id| context.session_tags.get(&id))
+                .is_some_and(|tags| tags.contains(tag)),
+            Predicate::And(terms) => terms.iter().all(|t| t.evaluate(node, context)),
+            Predicate::Or(terms) => terms.iter().any(|t| t.evaluate(node, context)),
+            Predicate::Not(inner) => !inner.evaluate(node, context),
+        }
+    }
+
+    /// Sessions this predicate is confined to, if it can only match nodes
+    /// from a known set of sessions
+    ///
+    /// Used to read from the session index instead of scanning every node.
+    pub(crate) fn session_scope(&self) -> Option<HashSet<SessionId>> {
+        match self {
+            Predicate::Session(id) => Some(HashSet::from([*id])),
+            Predicate::And(terms) => terms
+                .iter()
+                .filter_map(Predicate::session_scope)
+                .reduce(|a, b| a.intersection(&b).copied().collect()),
+            Predicate::Or(terms) if !terms.is_empty() => {
+                terms.iter().try_fold(HashSet::new(), |mut all, term| {
+                    all.extend(term.session_scope()?);
+                    Some(all)
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::ops::Not for Predicate {
+    type Output = Predicate;
+
+    fn not(self) -> Predicate {
+        match self {
+            Predicate::Not(inner) => *inner,
+            other => Predicate::Not(Box::new(other)),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn join(f: &mut fmt::Formatter<'_>, terms: &[Predicate], sep: &str) -> fmt::Result {
+            f.write_str("(")?;
+            for (i, term) in terms.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(sep)?;
+                }
+                write!(f, "{term}")?;
+            }
+            f.write_str(")")
+        }
+
+        match self {
+            Predicate::Session(id) => write!(f, "session = {id}"),
+            Predicate::NodeType(t) => write!(f, "type = {t:?}"),
+            Predicate::Compare { field, op, value } => write!(f, "{field:?} {op} {value}"),
+            Predicate::Metadata {
+                key,
+                value: Some(value),
+            } => write!(f, "metadata['{key}'] = '{value}'"),
+            Predicate::Metadata { key, value: None } => write!(f, "metadata has '{key}'"),
+            Predicate::Payload { path, op, value } => write!(f, "payload.{path} {op} {value}"),
+            Predicate::Tag(tag) => write!(f, "tag '{tag}'"),
+            Predicate::SessionTag(tag) => write!(f, "session tag '{tag}'"),
+            Predicate::And(terms) => join(f, terms, " AND "),
+            Predicate::Or(terms) => join(f, terms, " OR "),
+            Predicate::Not(inner) => write!(f, "NOT {inner}"),
+        }
+    }
+}
+
+/// Facts about the candidate set that single nodes cannot answer
+#[derive(Debug, Default)]
+pub(crate) struct PredicateContext {
+    node_sessions: HashMap<NodeId, SessionId>,
+    session_tags: HashMap<SessionId, Vec<String>>,
+}
+
+impl PredicateContext {
+    /// Resolve sessions and session tags from the loaded nodes
+    ///
+    /// Responses find their session through their prompt, and tool invocations
+    /// through their response, when those are part of `nodes`.
+    pub(crate) fn from_nodes(nodes: &[Node]) -> Self {
+        let mut context = Self::default();
+        for node in nodes {
+            match node {
+                Node::Prompt(p) => {
+                    context.node_sessions.insert(p.id, p.session_id);
+                }
+                Node::Session(s) => {
+                    context.node_sessions.insert(s.node_id, s.id);
+                    context.session_tags.insert(s.id, s.tags.clone());
+                }
+                _ => {}
+            }
+        }
+        for node in nodes {
+            if let Node::Response(r) = node {
+                if let Some(session) = context.node_sessions.get(&r.prompt_id).copied() {
+                    context.node_sessions.insert(r.id, session);
+                }
+            }
+        }
+        for node in nodes {
+            if let Node::ToolInvocation(t) = node {
+                if let Some(session) = context.node_sessions.get(&t.response_id).copied() {
+                    context.node_sessions.insert(t.id, session);
+                }
+            }
+        }
+        context
+    }
+
+    fn session_of(&self, node: &Node) -> Option<SessionId> {
+        match node {
+            Node::Prompt(p) => Some(p.session_id),
+            Node::Session(s) => Some(s.id),
+            Node::Custom(c) => c.session_id,
+            other => self.node_sessions.get(&other.id()).copied(),
+        }
+    }
+}
+
+/// Keep only the nodes matching `predicate`
+pub(crate) fn retain_matching(nodes: &mut Vec<Node>, predicate: &Predicate) {
+    let context = PredicateContext::from_nodes(nodes);
+    nodes.retain(|node| predicate.evaluate(node, &context));
+}
+
+fn compare(actual: &FieldValue, op: Comparison, expected: &FieldValue) -> bool {
+    match op {
+        Comparison::Contains | Comparison::StartsWith => match (actual, expected) {
+            (FieldValue::String(a), FieldValue::String(e)) => {
+                if op == Comparison::Contains {
+                    a.contains(e.as_str())
+                } else {
+                    a.starts_with(e.as_str())
+                }
+            }
+            _ => false,
+        },
+        _ => actual
+            .partial_order(expected)
+            .is_some_and(|ordering| match op {
+                Comparison::Eq => ordering == Ordering::Equal,
+                Comparison::Ne => ordering != Ordering::Equal,
+                Comparison::Lt => ordering == Ordering::Less,
+                Comparison::Le => ordering != Ordering::Greater,
+                Comparison::Gt => ordering == Ordering::Greater,
+                Comparison::Ge => ordering != Ordering::Less,
+                Comparison::Contains | Comparison::StartsWith => unreachable!(),
+            }),
+    }
+}
+
+fn metadata_of(node: &Node) -> Option<&HashMap<String, String>> {
+    match node {
+        Node::Prompt(p) => Some(&p.metadata.custom),
+        Node::Response(r) => Some(&r.metadata.custom),
+        Node::Session(s) => Some(&s.metadata),
+        Node::ToolInvocation(t) => Some(&t.metadata),
+        Node::Template(t) => Some(&t.metadata),
+        Node::Custom(c) => Some(&c.metadata),
+        Node::Agent(_) => None,
+    }
+}
+
+/// Scalar payload value of a custom node, as a comparable field value
+fn payload_value(node: &Node, path: &str) -> Option<FieldValue> {
+    let Node::Custom(custom) = node else {
+        return None;
+    };
+    match custom.field(path)? {
+        serde_json::Value::String(s) => Some(FieldValue::String(s.clone())),
+        serde_json::Value::Bool(b) => Some(FieldValue::Bool(*b)),
+        serde_json::Value::Number(n) => n
+            .as_i64()
+            .map(FieldValue::Integer)
+            .or_else(|| n.as_f64().map(FieldValue::Float)),
+        _ => None,
+    }
+}
+
+fn tags_of(node: &Node) -> Option<&Vec<String>> {
+    match node {
+        Node::Session(s) => Some(&s.tags),
+        Node::Agent(a) => Some(&a.tags),
+        Node::Template(t) => Some(&t.tags),
+        Node::Prompt(_) | Node::Response(_) | Node::ToolInvocation(_) | Node::Custom(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ConversationSession, PromptMetadata, PromptNode, ResponseMetadata, ResponseNode, TokenUsage,
+    };
+
+    fn fixture() -> Vec<Node> {
+        let mut session = ConversationSession::new();
+        session.tags.push("prod".to_string());
+
+        let metadata = PromptMetadata {
+            model: "gpt-4".to_string(),
+            temperature: 0.2,
+            custom: HashMap::from([("team".to_string(), "billing".to_string())]),
+            ..PromptMetadata::default()
+        };
+        let prompt = PromptNode::with_metadata(session.id, "Hi".to_string(), metadata);
+
+        let response = ResponseNode::with_metadata(
+            prompt.id,
+            "Hello".to_string(),
+            TokenUsage::new(10, 300),
+            ResponseMetadata {
+                model: "gpt-4".to_string(),
+                finish_reason: "length".to_string(),
+                latency_ms: 2_500,
+                custom: HashMap::new(),
+            },
+        );
+
+        vec![
+            Node::Session(session),
+            Node::Prompt(prompt),
+            Node::Response(response),
+        ]
+    }
+
+    fn matching(nodes: &[Node], predicate: &Predicate) -> Vec<NodeType> {
+        let mut nodes = nodes.to_vec();
+        retain_matching(&mut nodes, predicate);
+        nodes.iter().map(Node::node_type).collect()
+    }
+
+    #[test]
+    fn test_typed_comparisons() {
+        let nodes = fixture();
+
+        assert_eq!(
+            matching(&nodes, &Field::Model.eq("gpt-4")),
+            vec![NodeType::Prompt, NodeType::Response]
+        );
+        assert_eq!(
+            matching(&nodes, &Field::Temperature.lt(0.5)),
+            vec![NodeType::Prompt]
+        );
+        assert_eq!(
+            matching(&nodes, &Field::TotalTokens.between(100, 500)),
+            vec![NodeType::Response]
+        );
+        // Missing fields never match, even for <>
+        assert!(matching(&nodes, &Field::ToolName.ne("search")).is_empty());
+    }
+
+    #[test]
+    fn test_boolean_composition_and_context() {
+        let nodes = fixture();
+        let session_id = match &nodes[0] {
+            Node::Session(s) => s.id,
+            _ => unreachable!(),
+        };
+
+        let predicate = Field::LatencyMs
+            .gt(2_000)
+            .or(Predicate::metadata("team", "billing"));
+        assert_eq!(
+            matching(&nodes, &predicate),
+            vec![NodeType::Prompt, NodeType::Response]
+        );
+        let neither = (!Predicate::node_type(NodeType::Session)).and(!predicate);
+        assert!(matching(&nodes, &neither).is_empty());
+
+        // The response resolves its session tag through its prompt
+        assert_eq!(
+            matching(
+                &nodes,
+                &Predicate::session_tag("prod").and(Field::FinishReason.eq("length"))
+            ),
+            vec![NodeType::Response]
+        );
+        assert_eq!(matching(&nodes, &Predicate::session(session_id)).len(), 3);
+    }
+
+    #[test]
+    fn test_custom_kind_and_payload() {
+        let session_id = SessionId::new();
+        let ticket = Node::Custom(crate::CustomNode::in_session(
+            session_id,
+            "ticket".to_string(),
+            serde_json::json!({"priority": 2, "owner": {"team": "identity"}, "open": true}),
+        ));
+        let document = Node::Custom(crate::CustomNode::new(
+            "document".to_string(),
+            serde_json::json!({"priority": 2.5, "title": "Runbook"}),
+        ));
+        let nodes = vec![ticket, document];
+
+        let custom = |p: &Predicate| {
+            let context = PredicateContext::from_nodes(&nodes);
+            nodes
+                .iter()
+                .filter(|n| p.evaluate(n, &context))
+                .map(|n| match n {
+                    Node::Custom(c) => c.kind.clone(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(custom(&Predicate::kind("ticket")), vec!["ticket"]);
+        assert_eq!(
+            custom(&Predicate::payload("priority", Comparison::Ge, 2)),
+            vec!["ticket", "document"]
+        );
+        assert_eq!(
+            custom(&Predicate::payload("priority", Comparison::Gt, 2)),
+            vec!["document"]
+        );
+        assert_eq!(
+            custom(&Predicate::payload(
+                "owner.team",
+                Comparison::Eq,
+                "identity"
+            )),
+            vec!["ticket"]
+        );
+        // Objects are not scalar values and never compare
+        assert!(custom(&Predicate::payload("owner", Comparison::Ne, "x")).is_empty());
+        assert_eq!(custom(&Predicate::session(session_id)), vec!["ticket"]);
+        assert_eq!(
+            Predicate::payload("open", Comparison::Eq, true).to_string(),
+            "payload.open = true"
+        );
+    }
+
+    #[test]
+    fn test_session_scope() {
+        let a = SessionId::new();
+        let b = SessionId::new();
+
+        let scoped = Predicate::session(a).and(Field::Model.eq("x"));
+        assert_eq!(scoped.session_scope(), Some(HashSet::from([a])));
+
+        let either = Predicate::session(a).or(Predicate::session(b));
+        assert_eq!(either.session_scope(), Some(HashSet::from([a, b])));
+
+        assert_eq!(
+            Predicate::session(a)
+                .or(Field::Model.eq("x"))
+                .session_scope(),
+            None
+        );
+        assert_eq!((!Predicate::session(a)).session_scope(), None);
+    }
+}
@@ -0,0 +1,666 @@
+//! Bounded, typed graph traversals and path finding
+//!
+//! [`TraversalOptions`] restrict which edges a traversal follows (by type and
+//! direction), which nodes it may enter (by type), how deep it goes and how
+//! many nodes it visits. The algorithms here back both
+//! [`GraphTraversal`](super::GraphTraversal) on the sync engine and
+//! [`AsyncGraphTraversal`](super::AsyncGraphTraversal).
+//!
+//! The start node of a traversal is always visited, and the endpoints of a
+//! path search are always allowed, whatever the node type filter says. Edges
+//! whose other end no longer exists are skipped.
+
+use crate::storage::AsyncStorageBackend;
+use crate::{Edge, EdgeType, Node, NodeId, NodeType};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+/// Which edges of a node a traversal follows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Follow edges from the node to their targets
+    Outgoing,
+    /// Follow edges into the node back to their sources
+    Incoming,
+    /// Follow edges either way
+    #[default]
+    Both,
+}
+
+/// Limits and filters for a traversal
+///
+/// # Examples
+///
+/// ```
+/// use llm_memory_graph::query::traversal::{Direction, TraversalOptions};
+/// use llm_memory_graph::{EdgeType, NodeType};
+///
+/// let options = TraversalOptions::new()
+///     .direction(Direction::Incoming)
+///     .edge_type(EdgeType::RespondsTo)
+///     .edge_type(EdgeType::Invokes)
+///     .node_type(NodeType::Response)
+///     .node_type(NodeType::ToolInvocation)
+///     .max_depth(2)
+///     .max_visits(500);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TraversalOptions {
+    /// Edge direction to follow
+    pub direction: Direction,
+    /// Edge types to follow; all types when empty
+    pub edge_types: Vec<EdgeType>,
+    /// Node types that may be entered; all types when empty
+    pub node_types: Vec<NodeType>,
+    /// Maximum number of hops from the start node
+    pub max_depth: Option<usize>,
+    /// Maximum number of nodes to visit
+    pub max_visits: Option<usize>,
+}
+
+impl TraversalOptions {
+    /// Follow every edge in both directions, without limits
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the edge direction to follow
+    #[must_use]
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    /// Also follow edges of `edge_type`
+    #[must_use]
+    pub fn edge_type(mut self, edge_type: EdgeType) -> Self {
+        self.edge_types.push(edge_type);
+        self
+    }
+
+    /// Also allow entering nodes of `node_type`
+    #[must_use]
+    pub fn node_type(mut self, node_type: NodeType) -> Self {
+        self.node_types.push(node_type);
+        self
+    }
+
+    /// Stop `max_depth` hops from the start node
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Stop after visiting `max_visits` nodes
+    #[must_use]
+    pub fn max_visits(mut self, max_visits: usize) -> Self {
+        self.max_visits = Some(max_visits);
+        self
+    }
+
+    pub(crate) fn follows(&self, edge: &Edge) -> bool {
+        self.edge_types.is_empty() || self.edge_types.contains(&edge.edge_type)
+    }
+
+    pub(crate) fn enters(&self, node: &Node) -> bool {
+        self.node_types.is_empty() || self.node_types.contains(&node.node_type())
+    }
+
+    pub(crate) fn visits_exhausted(&self, visited: usize) -> bool {
+        self.max_visits.is_some_and(|max| visited >= max)
+    }
+}
+
+/// A node reached by a traversal
+#[derive(Debug, Clone)]
+pub struct Visit {
+    /// The node
+    pub node: Node,
+    /// Number of hops from the start node
+    pub depth: usize,
+    /// Edge the node was reached through; `None` for the start node
+    pub edge: Option<Edge>,
+}
+
+/// A path between two nodes
+#[derive(Debug, Clone)]
+pub struct Path {
+    /// Nodes along the path, starting with the source
+    pub nodes: Vec<NodeId>,
+    /// Edges along the path; `edges[i]` connects `nodes[i]` and `nodes[i + 1]`
+    pub edges: Vec<Edge>,
+}
+
+impl Path {
+    /// Number of edges on the path
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Whether the path is a single node without edges
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+}
+
+/// Nodes around a start node together with the edges between them
+#[derive(Debug, Clone, Default)]
+pub struct Subgraph {
+    /// Nodes, in breadth-first order from the start node
+    pub nodes: Vec<Node>,
+    /// Followable edges whose endpoints are both in `nodes`
+    pub edges: Vec<Edge>,
+}
+
+impl From<Vec<Visit>> for Subgraph {
+    /// The visited nodes and the edges they were reached through
+    fn from(visits: Vec<Visit>) -> Self {
+        let mut subgraph = Self::default();
+        for visit in visits {
+            subgraph.edges.extend(visit.edge);
+            subgraph.nodes.push(visit.node);
+        }
+        subgraph
+    }
+}
+
+/// Node and edge lookups the traversal algorithms run against
+#[async_trait]
+pub(crate) trait GraphSource: Send + Sync {
+    async fn node(&self, id: &NodeId) -> Result<Option<Node>>;
+    async fn outgoing(&self, id: &NodeId) -> Result<Vec<Edge>>;
+    async fn incoming(&self, id: &NodeId) -> Result<Vec<Edge>>;
+}
+
+#[async_trait]
+impl GraphSource for crate::engine::MemoryGraph {
+    async fn node(&self, id: &NodeId) -> Result<Option<Node>> {
+        match self.get_node(*id) {
+            Ok(node) => Ok(Some(node)),
+            Err(Error::NodeNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn outgoing(&self, id: &NodeId) -> Result<Vec<Edge>> {
+        self.get_outgoing_edges(*id)
+    }
+
+    async fn incoming(&self, id: &NodeId) -> Result<Vec<Edge>> {
+        self.get_incoming_edges(*id)
+    }
+}
+
+#[async_trait]
+impl GraphSource for Arc<dyn AsyncStorageBackend> {
+    async fn node(&self, id: &NodeId) -> Result<Option<Node>> {
+        self.get_node(id).await
+    }
+
+    async fn outgoing(&self, id: &NodeId) -> Result<Vec<Edge>> {
+        self.get_outgoing_edges(id).await
+    }
+
+    async fn incoming(&self, id: &NodeId) -> Result<Vec<Edge>> {
+        self.get_incoming_edges(id).await
+    }
+}
+
+/// Memoizing view of a source, filtered by the traversal options
+struct Walker<'a, S: ?Sized> {
+    source: &'a S,
+    options: &'a TraversalOptions,
+    nodes: HashMap<NodeId, Option<Node>>,
+    neighbors: HashMap<NodeId, Vec<(Edge, NodeId)>>,
+}
+
+impl<'a, S: GraphSource + ?Sized> Walker<'a, S> {
+    fn new(source: &'a S, options: &'a TraversalOptions) -> Self {
+        Self {
+            source,
+            options,
+            nodes: HashMap::new(),
+            neighbors: HashMap::new(),
+        }
+    }
+
+    async fn node(&mut self, id: NodeId) -> Result<Option<Node>> {
+        if let Some(node) = self.nodes.get(&id) {
+            return Ok(node.clone());
+        }
+        let node = self.source.node(&id).await?;
+        self.nodes.insert(id, node.clone());
+        Ok(node)
+    }
+
+    async fn require(&mut self, id: NodeId) -> Result<Node> {
+        self.node(id)
+            .await?
+            .ok_or_else(|| Error::NodeNotFound(id.to_string()))
+    }
+
+    /// Followable edges of `id`, each with the node at its other end
+    async fn neighbors(&mut self, id: NodeId) -> Result<Vec<(Edge, NodeId)>> {
+        if let Some(neighbors) = self.neighbors.get(&id) {
+            return Ok(neighbors.clone());
+        }
+
+        let mut neighbors = Vec::new();
+        if self.options.direction != Direction::Incoming {
+            for edge in self.source.outgoing(&id).await? {
+                if self.options.follows(&edge) {
+                    let to = edge.to;
+                    neighbors.push((edge, to));
+                }
+            }
+        }
+        if self.options.direction != Direction::Outgoing {
+            for edge in self.source.incoming(&id).await? {
+                if self.options.follows(&edge) {
+                    let from = edge.from;
+                    neighbors.push((edge, from));
+                }
+            }
+        }
+
+        self.neighbors.insert(id, neighbors.clone());
+        Ok(neighbors)
+    }
+
+    /// The node `id` if it exists and the options allow entering it
+    async fn enter(&mut self, id: NodeId) -> Result<Option<Node>> {
+        Ok(self
+            .node(id)
+            .await?
+            .filter(|node| self.options.enters(node)))
+    }
+}
+
+/// Breadth-first traversal from `start`
+pub(crate) async fn bfs<S: GraphSource + ?Sized>(
+    source: &S,
+    start: NodeId,
+    options: &TraversalOptions,
+) -> Result<Vec<Visit>> {
+    let mut walker = Walker::new(source, options);
+    let start_node = walker.require(start).await?;
+
+    let mut visits = Vec::new();
+    let mut seen = HashSet::from([start]);
+    let mut queue = VecDeque::from([Visit {
+        node: start_node,
+        depth: 0,
+        edge: None,
+    }]);
+
+    while let Some(visit) = queue.pop_front() {
+        let (id, depth) = (visit.node.id(), visit.depth);
+        visits.push(visit);
+        if options.visits_exhausted(visits.len()) {
+            break;
+        }
+        if options.max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+
+        for (edge, neighbor) in walker.neighbors(id).await? {
+            if !seen.insert(neighbor) {
+                continue;
+            }
+            if let Some(node) = walker.enter(neighbor).await? {
+                queue.push_back(Visit {
+                    node,
+                    depth: depth + 1,
+                    edge: Some(edge),
+                });
+            }
+        }
+    }
+
+    Ok(visits)
+}
+
+/// Depth-first (pre-order) traversal from `start`
+pub(crate) async fn dfs<S: GraphSource + ?Sized>(
+    source: &S,
+    start: NodeId,
+    options: &TraversalOptions,
+) -> Result<Vec<Visit>> {
+    let mut walker = Walker::new(source, options);
+    let start_node = walker.require(start).await?;
+
+    let mut visits = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack = vec![(start, 0, None)];
+
+    while let Some((id, depth, edge)) = stack.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        let node = if id == start {
+            start_node.clone()
+        } else {
+            match walker.enter(id).await? {
+                Some(node) => node,
+                None => continue,
+            }
+        };
+        visits.push(Visit { node, depth, edge });
+        if options.visits_exhausted(visits.len()) {
+            break;
+        }
+        if options.max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+
+        // Push in reverse so the first neighbor is explored first
+        for (edge, neighbor) in walker.neighbors(id).await?.into_iter().rev() {
+            if !seen.contains(&neighbor) {
+                stack.push((neighbor, depth + 1, Some(edge)));
+            }
+        }
+    }
+
+    Ok(visits)
+}
+
+/// Fewest-hop path from `from` to `to`
+///
+/// `max_depth` bounds the path length and `max_visits` the number of nodes
+/// expanded before giving up.
+pub(crate) async fn shortest_path<S: GraphSource + ?Sized>(
+    source: &S,
+    from: NodeId,
+    to: NodeId,
+    options: &TraversalOptions,
+) -> Result<Option<Path>> {
+    let mut walker = Walker::new(source, options);
+    walker.require(from).await?;
+    walker.require(to).await?;
+
+    let mut parents: HashMap<NodeId, (NodeId, Edge)> = HashMap::new();
+    let mut seen = HashSet::from([from]);
+    let mut queue = VecDeque::from([(from, 0)]);
+    let mut expanded = 0;
+
+    while let Some((id, depth)) = queue.pop_front() {
+        if id == to {
+            return Ok(Some(trace_back(&parents, from, to)));
+        }
+        expanded += 1;
+        if options.visits_exhausted(expanded) && !queue.is_empty() {
+            break;
+        }
+        if options.max_depth.is_some_and(|max| depth >= max) {
+            continue;
+        }
+
+        for (edge, neighbor) in walker.neighbors(id).await? {
+            if !seen.insert(neighbor) {
+                continue;
+            }
+            if neighbor != to && walker.enter(neighbor).await?.is_none() {
+                continue;
+            }
+            parents.insert(neighbor, (id, edge));
+            queue.push_back((neighbor, depth + 1));
+        }
+    }
+
+    Ok(None)
+}
+
+fn trace_back(parents: &HashMap<NodeId, (NodeId, Edge)>, from: NodeId, to: NodeId) -> Path {
+    let mut nodes = vec![to];
+    let mut edges = Vec::new();
+    let mut current = to;
+    while current != from {
+        let (parent, edge) = &parents[&current];
+        edges.push(edge.clone());
+        nodes.push(*parent);
+        current = *parent;
+    }
+    nodes.reverse();
+    edges.reverse();
+    Path { nodes, edges }
+}
+
+/// Every simple path from `from` to `to` with at most `max_length` edges
+///
+/// Paths are returned shortest first. `max_visits` caps the number of paths.
+pub(crate) async fn simple_paths<S: GraphSource + ?Sized>(
+    source: &S,
+    from: NodeId,
+    to: NodeId,
+    max_length: usize,
+    options: &TraversalOptions,
+) -> Result<Vec<Path>> {
+    let mut walker = Walker::new(source, options);
+    walker.require(from).await?;
+    walker.require(to).await?;
+
+    let mut paths = Vec::new();
+    if from == to {
+        paths.push(Path {
+            nodes: vec![from],
+            edges: Vec::new(),
+        });
+        return Ok(paths);
+    }
+
+    // Each frame holds the node's remaining neighbors to try
+    let mut nodes = vec![from];
+    let mut edges: Vec<Edge> = Vec::new();
+    let mut on_path = HashSet::from([from]);
+    let mut frames = vec![walker.neighbors(from).await?.into_iter()];
+
+    while let Some(frame) = frames.last_mut() {
+        if options.visits_exhausted(paths.len()) {
+            break;
+        }
+        let Some((edge, neighbor)) = frame.next() else {
+            frames.pop();
+            if let Some(id) = nodes.pop() {
+                on_path.remove(&id);
+            }
+            edges.pop();
+            continue;
+        };
+        if on_path.contains(&neighbor) {
+            continue;
+        }
+
+        if neighbor == to {
+            let mut path_nodes = nodes.clone();
+            path_nodes.push(to);
+            let mut path_edges = edges.clone();
+            path_edges.push(edge);
+            paths.push(Path {
+                nodes: path_nodes,
+                edges: path_edges,
+            });
+            continue;
+        }
+        // Intermediate nodes need room for at least one more hop to `to`
+        if edges.len() + 2 > max_length || walker.enter(neighbor).await?.is_none() {
+            continue;
+        }
+
+        nodes.push(neighbor);
+        edges.push(edge);
+        on_path.insert(neighbor);
+        frames.push(walker.neighbors(neighbor).await?.into_iter());
+    }
+
+    paths.sort_by_key(Path::len);
+    Ok(paths)
+}
+
+/// Nodes within `hops` of `start`, with the edges among them
+pub(crate) async fn neighborhood<S: GraphSource + ?Sized>(
+    source: &S,
+    start: NodeId,
+    hops: usize,
+    options: &TraversalOptions,
+) -> Result<Subgraph> {
+    let bounded = TraversalOptions {
+        max_depth: Some(hops),
+        ..options.clone()
+    };
+    let visits = bfs(source, start, &bounded).await?;
+    let members: HashSet<NodeId> = visits.iter().map(|v| v.node.id()).collect();
+
+    let mut walker = Walker::new(source, options);
+    let mut edge_ids = HashSet::new();
+    let mut edges = Vec::new();
+    for visit in &visits {
+        for (edge, neighbor) in walker.neighbors(visit.node.id()).await? {
+            if members.contains(&neighbor) && edge_ids.insert(edge.id) {
+                edges.push(edge);
+            }
+        }
+    }
+
+    Ok(Subgraph {
+        nodes: visits.into_iter().map(|v| v.node).collect(),
+        edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, MemoryGraph, TokenUsage};
+    use futures::executor::block_on;
+    use tempfile::tempdir;
+
+    /// Three prompts, each answered by one response
+    ///
+    /// Every prompt has a Follows edge to the one before it and a PartOf edge
+    /// to the session node.
+    fn conversation(graph: &MemoryGraph) -> (Vec<NodeId>, Vec<NodeId>) {
+        let session = graph.create_session().unwrap();
+        let mut prompts = Vec::new();
+        let mut responses = Vec::new();
+        for i in 0..3 {
+            let prompt = graph
+                .add_prompt(session.id, format!("Prompt {i}"), None)
+                .unwrap();
+            let response = graph
+                .add_response(prompt, format!("Response {i}"), TokenUsage::new(1, 1), None)
+                .unwrap();
+            prompts.push(prompt);
+            responses.push(response);
+        }
+        (prompts, responses)
+    }
+
+    #[test]
+    fn test_bounded_traversal() {
+        let dir = tempdir().unwrap();
+        let graph = MemoryGraph::open(Config::new(dir.path())).unwrap();
+        let (prompts, responses) = conversation(&graph);
+
+        let follows = TraversalOptions::new()
+            .direction(Direction::Outgoing)
+            .edge_type(EdgeType::Follows);
+        let all: Vec<NodeId> = block_on(bfs(&graph, prompts[2], &follows))
+            .unwrap()
+            .iter()
+            .map(|v| v.node.id())
+            .collect();
+        assert_eq!(all, vec![prompts[2], prompts[1], prompts[0]]);
+
+        let shallow = block_on(bfs(&graph, prompts[2], &follows.clone().max_depth(1))).unwrap();
+        assert_eq!(shallow.len(), 2);
+        assert_eq!(shallow[1].depth, 1);
+        assert_eq!(
+            shallow[1].edge.as_ref().unwrap().edge_type,
+            EdgeType::Follows
+        );
+
+        let limited = block_on(dfs(
+            &graph,
+            prompts[0],
+            &TraversalOptions::new().max_visits(3),
+        ))
+        .unwrap();
+        assert_eq!(limited.len(), 3);
+
+        // Responses only: the start prompt plus its own response
+        let only_responses = TraversalOptions::new().node_type(NodeType::Response);
+        let visits = block_on(bfs(&graph, prompts[0], &only_responses)).unwrap();
+        assert_eq!(visits.len(), 2);
+        assert_eq!(visits[1].node.id(), responses[0]);
+
+        assert!(matches!(
+            block_on(bfs(&graph, NodeId::new(), &follows)),
+            Err(Error::NodeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_paths_and_neighborhood() {
+        let dir = tempdir().unwrap();
+        let graph = MemoryGraph::open(Config::new(dir.path())).unwrap();
+        let (prompts, responses) = conversation(&graph);
+
+        // Leave out PartOf so paths cannot shortcut through the session node
+        let thread = TraversalOptions::new()
+            .edge_type(EdgeType::Follows)
+            .edge_type(EdgeType::RespondsTo);
+
+        let path = block_on(shortest_path(&graph, responses[0], prompts[2], &thread))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            path.nodes,
+            vec![responses[0], prompts[0], prompts[1], prompts[2]]
+        );
+        assert_eq!(path.len(), 3);
+
+        // Responses point at prompts, never the other way
+        let outgoing = thread.clone().direction(Direction::Outgoing);
+        assert!(
+            block_on(shortest_path(&graph, prompts[2], responses[0], &outgoing))
+                .unwrap()
+                .is_none()
+        );
+        assert!(block_on(shortest_path(
+            &graph,
+            responses[0],
+            prompts[2],
+            &thread.clone().max_depth(2)
+        ))
+        .unwrap()
+        .is_none());
+
+        // An extra edge gives a second route from the last prompt to the first
+        graph
+            .add_edge(prompts[2], prompts[0], EdgeType::References)
+            .unwrap();
+        let linked = outgoing.edge_type(EdgeType::References);
+        let paths = block_on(simple_paths(&graph, prompts[2], prompts[0], 2, &linked)).unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0].len(), 1);
+        assert_eq!(paths[1].nodes, vec![prompts[2], prompts[1], prompts[0]]);
+        let direct = block_on(simple_paths(&graph, prompts[2], prompts[0], 1, &linked)).unwrap();
+        assert_eq!(direct.len(), 1);
+
+        let linked = linked.direction(Direction::Both);
+        let around = block_on(neighborhood(&graph, prompts[1], 1, &linked)).unwrap();
+        let ids: HashSet<NodeId> = around.nodes.iter().map(Node::id).collect();
+        assert_eq!(
+            ids,
+            HashSet::from([prompts[0], prompts[1], prompts[2], responses[1]])
+        );
+        // Includes the reference between the two outer prompts
+        assert_eq!(around.edges.len(), 4);
+    }
+}
@@ -0,0 +1,281 @@
+//! Full-text search over node content
+//!
+//! Prompt and response content, template names and text, and tool names are
+//! tokenized into an inverted index that the storage backend maintains on every
+//! write. Searches are ranked with BM25 and return a highlighted snippet for
+//! each hit.
+//!
+//! Query syntax:
+//!
+//! - `invoice reconciliation` matches nodes containing both words
+//! - `"invoice reconciliation"` matches the exact phrase
+//! - `reconcil*` matches any word starting with `reconcil`
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::search::TextQuery;
+//! use llm_memory_graph::{Config, NodeType};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let hits = graph
+//!     .search_text(&TextQuery::new("\"invoice reconciliation\"").node_type(NodeType::Prompt))
+//!     .await?;
+//! for hit in hits {
+//!     println!("{:.2} {} {}", hit.score, hit.node_id, hit.snippet.text);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod query;
+mod snippet;
+mod tokenizer;
+
+pub(crate) use query::{parse_search_query, Clause};
+pub(crate) use snippet::build_snippet;
+pub(crate) use tokenizer::tokenize;
+
+use crate::{Node, NodeId, NodeType, SessionId};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::ops::Range;
+
+/// Default number of hits returned by a search
+pub const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+/// A full-text search request
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextQuery {
+    /// Query text (terms, `"phrases"` and `prefix*` terms)
+    pub query: String,
+    /// Only return nodes belonging to this session
+    pub session_id: Option<SessionId>,
+    /// Only return nodes of these types; empty means any indexed type
+    pub node_types: Vec<NodeType>,
+    /// Only return nodes created at or after this time
+    pub after: Option<DateTime<Utc>>,
+    /// Only return nodes created at or before this time
+    pub before: Option<DateTime<Utc>>,
+    /// Maximum number of hits
+    pub limit: usize,
+    /// Number of top-ranked hits to skip
+    pub offset: usize,
+}
+
+impl TextQuery {
+    /// Create a query with no filters
+    pub fn new(query: impl Into<String>) -> Self {
+        Self {
+            query: query.into(),
+            session_id: None,
+            node_types: Vec::new(),
+            after: None,
+            before: None,
+            limit: DEFAULT_SEARCH_LIMIT,
+            offset: 0,
+        }
+    }
+
+    /// Restrict results to a session
+    #[must_use]
+    pub fn session(mut self, session_id: SessionId) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Restrict results to a node type; may be called more than once
+    #[must_use]
+    pub fn node_type(mut self, node_type: NodeType) -> Self {
+        self.node_types.push(node_type);
+        self
+    }
+
+    /// Only match nodes created at or after `after`
+    #[must_use]
+    pub fn after(mut self, after: DateTime<Utc>) -> Self {
+        self.after = Some(after);
+        self
+    }
+
+    /// Only match nodes created at or before `before`
+    #[must_use]
+    pub fn before(mut self, before: DateTime<Utc>) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    /// Set the maximum number of hits
+    #[must_use]
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Skip the first `offset` hits
+    #[must_use]
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Whether a document with these attributes passes the filters
+    pub(crate) fn accepts(
+        &self,
+        node_type: &NodeType,
+        session_id: Option<SessionId>,
+        timestamp: DateTime<Utc>,
+    ) -> bool {
+        (self.node_types.is_empty() || self.node_types.contains(node_type))
+            && self.session_id.is_none_or(|s| session_id == Some(s))
+            && self.after.is_none_or(|after| timestamp >= after)
+            && self.before.is_none_or(|before| timestamp <= before)
+    }
+}
+
+/// Excerpt of matched text with the matching words marked
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Snippet {
+    /// Excerpt text, with `...` where it was cut
+    pub text: String,
+    /// Byte ranges of `text` that matched the query
+    pub highlights: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    /// Render the snippet with each highlight wrapped in `pre` and `post`
+    pub fn render(&self, pre: &str, post: &str) -> String {
+        let mut out = String::with_capacity(self.text.len());
+        let mut last = 0;
+        for range in &self.highlights {
+            out.push_str(&self.text[last..range.start]);
+            out.push_str(pre);
+            out.push_str(&self.text[range.clone()]);
+            out.push_str(post);
+            last = range.end;
+        }
+        out.push_str(&self.text[last..]);
+        out
+    }
+}
+
+/// A ranked search result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchHit {
+    /// Matching node
+    pub node_id: NodeId,
+    /// Type of the matching node
+    pub node_type: NodeType,
+    /// Session the node belongs to, where known
+    pub session_id: Option<SessionId>,
+    /// When the node was created
+    pub timestamp: DateTime<Utc>,
+    /// BM25 relevance score; higher is better
+    pub score: f64,
+    /// Highlighted excerpt of the matching text
+    pub snippet: Snippet,
+}
+
+/// Text fields of a node that are indexed, in a fixed order
+pub(crate) fn indexed_fields(node: &Node) -> Vec<&str> {
+    match node {
+        Node::Prompt(p) => vec![p.content.as_str()],
+        Node::Response(r) => vec![r.content.as_str()],
+        Node::Template(t) => vec![t.name.as_str(), t.template.as_str()],
+        Node::ToolInvocation(t) => vec![t.tool_name.as_str()],
+        Node::Custom(c) => {
+            let mut fields = Vec::new();
+            payload_strings(&c.payload, &mut fields);
+            fields
+        }
+        Node::Session(_) | Node::Agent(_) => Vec::new(),
+    }
+}
+
+/// String values anywhere in a custom node payload, in document order
+fn payload_strings<'a>(value: &'a serde_json::Value, out: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::String(s) => out.push(s),
+        serde_json::Value::Array(items) => {
+            for item in items {
+                payload_strings(item, out);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values() {
+                payload_strings(item, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Creation time used for time filters
+pub(crate) fn node_timestamp(node: &Node) -> DateTime<Utc> {
+    match node {
+        Node::Prompt(p) => p.timestamp,
+        Node::Response(r) => r.timestamp,
+        Node::Session(s) => s.created_at,
+        Node::ToolInvocation(t) => t.timestamp,
+        Node::Agent(a) => a.created_at,
+        Node::Template(t) => t.created_at,
+        Node::Custom(c) => c.created_at,
+    }
+}
+
+/// Merge per-partition results into one ranked page
+///
+/// Each partition must have been searched with `offset = 0` and
+/// `limit = query.offset + query.limit`.
+pub(crate) fn merge_hits(parts: Vec<Vec<SearchHit>>, query: &TextQuery) -> Vec<SearchHit> {
+    let mut hits: Vec<SearchHit> = parts.into_iter().flatten().collect();
+    sort_hits(&mut hits);
+    hits.into_iter()
+        .skip(query.offset)
+        .take(query.limit)
+        .collect()
+}
+
+/// Order hits by descending score, newest first on ties
+pub(crate) fn sort_hits(hits: &mut [SearchHit]) {
+    hits.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| b.timestamp.cmp(&a.timestamp))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_render() {
+        let snippet = Snippet {
+            text: "reconcile the invoice now".to_string(),
+            highlights: vec![0..9, 14..21],
+        };
+        assert_eq!(snippet.render("[", "]"), "[reconcile] the [invoice] now");
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let session = SessionId::new();
+        let now = Utc::now();
+        let query = TextQuery::new("x")
+            .session(session)
+            .node_type(NodeType::Prompt)
+            .after(now - chrono::Duration::hours(1));
+
+        assert!(query.accepts(&NodeType::Prompt, Some(session), now));
+        assert!(!query.accepts(&NodeType::Response, Some(session), now));
+        assert!(!query.accepts(&NodeType::Prompt, None, now));
+        assert!(!query.accepts(
+            &NodeType::Prompt,
+            Some(session),
+            now - chrono::Duration::hours(2)
+        ));
+    }
+}
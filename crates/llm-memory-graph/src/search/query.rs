@@ -0,0 +1,101 @@
+//! Parser for full-text search queries
+
+use super::tokenizer::tokenize;
+use crate::{Error, Result};
+
+/// One required part of a search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Clause {
+    /// A single word
+    Term(String),
+    /// Any word starting with this prefix
+    Prefix(String),
+    /// Words that must appear consecutively
+    Phrase(Vec<String>),
+}
+
+impl Clause {
+    /// Whether a single indexed word satisfies (part of) this clause
+    pub(crate) fn matches_word(&self, word: &str) -> bool {
+        match self {
+            Clause::Term(term) => term == word,
+            Clause::Prefix(prefix) => word.starts_with(prefix.as_str()),
+            Clause::Phrase(terms) => terms.iter().any(|t| t == word),
+        }
+    }
+}
+
+/// Parse a query into clauses, all of which must match
+///
+/// Double quotes delimit phrases and a trailing `*` makes a word a prefix.
+/// Punctuation inside words splits them the same way indexing does.
+pub(crate) fn parse_search_query(input: &str) -> Result<Vec<Clause>> {
+    let mut clauses = Vec::new();
+
+    for (i, part) in input.split('"').enumerate() {
+        if i % 2 == 1 {
+            // Inside quotes
+            let terms: Vec<String> = tokenize(part).into_iter().map(|t| t.term).collect();
+            match terms.len() {
+                0 => {}
+                1 => clauses.push(Clause::Term(terms.into_iter().next().unwrap_or_default())),
+                _ => clauses.push(Clause::Phrase(terms)),
+            }
+            continue;
+        }
+
+        for word in part.split_whitespace() {
+            let (word, is_prefix) = match word.strip_suffix('*') {
+                Some(stem) => (stem, true),
+                None => (word, false),
+            };
+            let mut terms: Vec<String> = tokenize(word).into_iter().map(|t| t.term).collect();
+            // `e-mail*` is the term `e` followed by the prefix `mail`
+            let last = if is_prefix { terms.pop() } else { None };
+            clauses.extend(terms.into_iter().map(Clause::Term));
+            if let Some(prefix) = last {
+                clauses.push(Clause::Prefix(prefix));
+            }
+        }
+    }
+
+    if input.matches('"').count() % 2 == 1 {
+        return Err(Error::ValidationError(
+            "Unterminated phrase in search query".to_string(),
+        ));
+    }
+    if clauses.is_empty() {
+        return Err(Error::ValidationError(
+            "Search query contains no searchable words".to_string(),
+        ));
+    }
+
+    clauses.dedup();
+    Ok(clauses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_terms_phrases_and_prefixes() {
+        let clauses = parse_search_query("Invoice \"bank statement\" reconcil* e-mail").unwrap();
+        assert_eq!(
+            clauses,
+            vec![
+                Clause::Term("invoice".to_string()),
+                Clause::Phrase(vec!["bank".to_string(), "statement".to_string()]),
+                Clause::Prefix("reconcil".to_string()),
+                Clause::Term("e".to_string()),
+                Clause::Term("mail".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_empty_and_unbalanced() {
+        assert!(parse_search_query("  ?! ").is_err());
+        assert!(parse_search_query("\"open phrase").is_err());
+    }
+}
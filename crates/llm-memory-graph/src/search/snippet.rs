@@ -0,0 +1,110 @@
+//! Highlighted excerpts for search results
+
+use super::query::Clause;
+use super::tokenizer::tokenize;
+use super::Snippet;
+
+/// Approximate snippet length in bytes
+const SNIPPET_LEN: usize = 160;
+
+/// Context kept before the first match
+const LEADING_CONTEXT: usize = 40;
+
+const ELLIPSIS: &str = "...";
+
+/// Build a snippet from the first field that contains a match
+pub(crate) fn build_snippet(fields: &[&str], clauses: &[Clause]) -> Snippet {
+    let found = fields.iter().find_map(|text| {
+        let matches: Vec<_> = tokenize(text)
+            .into_iter()
+            .filter(|t| clauses.iter().any(|c| c.matches_word(&t.term)))
+            .collect();
+        (!matches.is_empty()).then_some((*text, matches))
+    });
+
+    let Some((text, matches)) = found else {
+        // Nothing to highlight; show the start of the first field
+        let text = fields.first().copied().unwrap_or_default();
+        let end = floor_char_boundary(text, SNIPPET_LEN.min(text.len()));
+        return Snippet {
+            text: excerpt(text, 0, end),
+            highlights: Vec::new(),
+        };
+    };
+
+    // Start a little before the first match, at a word boundary when possible
+    let first = matches[0].start;
+    let mut start = floor_char_boundary(text, first.saturating_sub(LEADING_CONTEXT));
+    if start > 0 {
+        if let Some(space) = text[start..first].find(char::is_whitespace) {
+            start += space + 1;
+        }
+    }
+    let mut end = floor_char_boundary(text, (start + SNIPPET_LEN).min(text.len()));
+    if end < text.len() {
+        if let Some(space) = text[first..end].rfind(char::is_whitespace) {
+            end = first + space;
+        }
+    }
+
+    let offset = if start > 0 { ELLIPSIS.len() } else { 0 };
+    let highlights = matches
+        .iter()
+        .filter(|t| t.start >= start && t.end <= end)
+        .map(|t| (t.start - start + offset)..(t.end - start + offset))
+        .collect();
+
+    Snippet {
+        text: excerpt(text, start, end),
+        highlights,
+    }
+}
+
+fn excerpt(text: &str, start: usize, end: usize) -> String {
+    let mut out = String::new();
+    if start > 0 {
+        out.push_str(ELLIPSIS);
+    }
+    out.push_str(&text[start..end]);
+    if end < text.len() {
+        out.push_str(ELLIPSIS);
+    }
+    out
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_highlights_matches() {
+        let clauses = vec![
+            Clause::Term("invoice".to_string()),
+            Clause::Prefix("reconcil".to_string()),
+        ];
+        let snippet = build_snippet(&["Please reconcile this Invoice today"], &clauses);
+        assert_eq!(snippet.text, "Please reconcile this Invoice today");
+        assert_eq!(
+            snippet.render("<", ">"),
+            "Please <reconcile> this <Invoice> today"
+        );
+    }
+
+    #[test]
+    fn test_snippet_trims_long_text() {
+        let text = format!("{} needle {}", "word ".repeat(100), "tail ".repeat(100));
+        let snippet = build_snippet(&["unrelated", &text], &[Clause::Term("needle".to_string())]);
+        assert!(snippet.text.starts_with(ELLIPSIS));
+        assert!(snippet.text.ends_with(ELLIPSIS));
+        assert!(snippet.text.len() <= SNIPPET_LEN + 2 * ELLIPSIS.len());
+        assert_eq!(snippet.highlights.len(), 1);
+        assert_eq!(&snippet.text[snippet.highlights[0].clone()], "needle");
+    }
+}
@@ -0,0 +1,61 @@
+//! Word tokenizer shared by indexing, querying and snippet highlighting
+
+/// Longest token that is indexed; longer runs (hashes, base64) are skipped
+const MAX_TOKEN_LEN: usize = 64;
+
+/// A normalized word and where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+    /// Lowercased word
+    pub term: String,
+    /// Byte range of the word in the source text
+    pub start: usize,
+    /// End of the byte range (exclusive)
+    pub end: usize,
+}
+
+/// Split text into lowercased alphanumeric words
+pub(crate) fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+
+    for (pos, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        if c.is_alphanumeric() {
+            start.get_or_insert(pos);
+        } else if let Some(begin) = start.take() {
+            if pos - begin <= MAX_TOKEN_LEN {
+                tokens.push(Token {
+                    term: text[begin..pos].to_lowercase(),
+                    start: begin,
+                    end: pos,
+                });
+            }
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let tokens = tokenize("Reconcile the Q3 invoices, please!");
+        let terms: Vec<&str> = tokens.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(terms, vec!["reconcile", "the", "q3", "invoices", "please"]);
+        assert_eq!((tokens[3].start, tokens[3].end), (17, 25));
+    }
+
+    #[test]
+    fn test_tokenize_unicode_and_long_tokens() {
+        let long = "a".repeat(MAX_TOKEN_LEN + 1);
+        let tokens = tokenize(&format!("Über {long} café"));
+        let terms: Vec<&str> = tokens.iter().map(|t| t.term.as_str()).collect();
+        assert_eq!(terms, vec!["über", "café"]);
+    }
+}
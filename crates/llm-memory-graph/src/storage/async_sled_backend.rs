@@ -4,7 +4,12 @@
 //! using `tokio::task::spawn_blocking` to run blocking operations on a dedicated
 //! thread pool without blocking the async runtime.
 
-use super::{AsyncStorageBackend, SerializationFormat, SledBackend, StorageBackend, StorageStats};
+use super::{
+    AsyncStorageBackend, Lookup, SerializationFormat, SledBackend, StorageBackend, StorageStats,
+};
+use crate::query::{Cursor, Page};
+use crate::search::{SearchHit, TextQuery};
+use crate::vector::{SimilarNode, VectorFilter};
 use crate::Result;
 use crate::{Edge, EdgeId, Node, NodeId, SessionId};
 use async_trait::async_trait;
@@ -48,6 +53,13 @@ impl AsyncSledBackend {
         })
     }
 
+    /// Wrap an already opened synchronous backend
+    pub(crate) fn from_sync(inner: SledBackend) -> Self {
+        Self {
+            inner: Arc::new(inner),
+        }
+    }
+
     /// Open with a custom serialization format
     ///
     /// # Examples
@@ -164,6 +176,129 @@ impl AsyncStorageBackend for AsyncSledBackend {
             .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
     }
 
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        let inner = Arc::clone(&self.inner);
+
+        tokio::task::spawn_blocking(move || inner.scan_nodes())
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        let inner = Arc::clone(&self.inner);
+        let query = query.clone();
+
+        tokio::task::spawn_blocking(move || inner.search_text(&query))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn store_embedding(&self, node_id: &NodeId, vector: &[f32]) -> Result<()> {
+        let inner = Arc::clone(&self.inner);
+        let node_id = *node_id;
+        let vector = vector.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.store_embedding(&node_id, &vector))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        let inner = Arc::clone(&self.inner);
+        let node_id = *node_id;
+
+        tokio::task::spawn_blocking(move || inner.get_embedding(&node_id))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn delete_embedding(&self, node_id: &NodeId) -> Result<()> {
+        let inner = Arc::clone(&self.inner);
+        let node_id = *node_id;
+
+        tokio::task::spawn_blocking(move || inner.delete_embedding(&node_id))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        let inner = Arc::clone(&self.inner);
+        let vector = vector.to_vec();
+        let filter = filter.clone();
+
+        tokio::task::spawn_blocking(move || inner.similar_nodes(&vector, k, &filter))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn write_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let inner = Arc::clone(&self.inner);
+        let puts = puts.to_vec();
+        let deletes = deletes.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.write_catalog(&puts, &deletes))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let inner = Arc::clone(&self.inner);
+        let puts = puts.to_vec();
+        let deletes = deletes.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.stage_catalog(&puts, &deletes))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn insert_catalog_entry(&self, key: &[u8], value: &[u8]) -> Result<bool> {
+        let inner = Arc::clone(&self.inner);
+        let key = key.to_vec();
+        let value = value.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.insert_catalog_entry(&key, &value))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let inner = Arc::clone(&self.inner);
+        let key = key.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.get_catalog_entry(&key))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let inner = Arc::clone(&self.inner);
+        let prefix = prefix.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.scan_catalog(&prefix))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        let inner = Arc::clone(&self.inner);
+        let lookup = lookup.clone();
+        let after = after.copied();
+
+        tokio::task::spawn_blocking(move || inner.lookup_nodes(&lookup, after.as_ref(), limit))
+            .await
+            .map_err(|e| crate::Error::RuntimeError(e.to_string()))?
+    }
+
     async fn flush(&self) -> Result<()> {
         let inner = Arc::clone(&self.inner);
 
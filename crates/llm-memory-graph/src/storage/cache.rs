@@ -112,6 +112,9 @@ impl StorageCache {
             node_cache_misses: 0,
             edge_cache_hits: 0,
             edge_cache_misses: 0,
+            query_cache_size: 0,
+            query_cache_hits: 0,
+            query_cache_misses: 0,
         }
     }
 
@@ -143,6 +146,12 @@ pub struct CacheStats {
     pub edge_cache_hits: u64,
     /// Edge cache misses
     pub edge_cache_misses: u64,
+    /// Number of query result pages in cache
+    pub query_cache_size: u64,
+    /// Queries answered from the query result cache
+    pub query_cache_hits: u64,
+    /// Queries that missed the query result cache
+    pub query_cache_misses: u64,
 }
 
 impl CacheStats {
@@ -165,6 +174,16 @@ impl CacheStats {
             self.edge_cache_hits as f64 / total as f64
         }
     }
+
+    /// Calculate query result cache hit rate
+    pub fn query_hit_rate(&self) -> f64 {
+        let total = self.query_cache_hits + self.query_cache_misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.query_cache_hits as f64 / total as f64
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,297 @@
+//! Maintained reverse lookups from node attributes to nodes
+//!
+//! The index lives in three sled trees next to the graph data:
+//!
+//! - `lookup_entries`: `kind key_len key position` -> empty
+//! - `lookup_docs`: `node_id` -> the entry keys written for that node
+//! - `lookup_meta`: index version marker
+//!
+//! `position` stores the node's timestamp and ID bitwise inverted, so a
+//! forward scan over one lookup yields nodes newest first with ties broken by
+//! node ID descending, the order [`Cursor`] pages use. Resuming after a cursor
+//! is a range scan starting just past the cursor's own key.
+
+use crate::query::cursor::{paginate, sort_newest_first};
+use crate::query::{Cursor, Page};
+use crate::{Error, Result};
+use crate::{Node, NodeId, TemplateId};
+use chrono::{DateTime, Utc};
+use sled::{Db, Tree};
+use std::ops::Bound;
+
+const VERSION_KEY: &[u8] = b"version";
+const INDEX_VERSION: u64 = 1;
+
+/// Encoded length of a position: seconds, nanoseconds and node ID
+const POSITION_LEN: usize = 8 + 4 + 16;
+
+/// An attribute value to find nodes by
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Lookup {
+    /// Prompts instantiated from a template (their `template_id`)
+    Template(TemplateId),
+    /// Nodes with a `HandledBy` edge to the agent node with this ID
+    Agent(NodeId),
+    /// Tool invocations of the named tool
+    ToolName(String),
+    /// Prompts and responses recorded against the named model
+    Model(String),
+    /// Custom nodes of the named kind
+    Kind(String),
+}
+
+impl Lookup {
+    /// Lookups a node satisfies through its own fields
+    ///
+    /// Agent lookups come from `HandledBy` edges and are added by the backend.
+    pub(crate) fn of_node(node: &Node) -> Vec<Self> {
+        match node {
+            Node::Prompt(p) => {
+                let mut lookups = vec![Self::Model(p.metadata.model.clone())];
+                lookups.extend(p.template_id.map(Self::Template));
+                lookups
+            }
+            Node::Response(r) => vec![Self::Model(r.metadata.model.clone())],
+            Node::ToolInvocation(t) => vec![Self::ToolName(t.tool_name.clone())],
+            Node::Custom(c) => vec![Self::Kind(c.kind.clone())],
+            Node::Session(_) | Node::Agent(_) | Node::Template(_) => Vec::new(),
+        }
+    }
+
+    /// Whether `node` satisfies this lookup through its own fields
+    pub(crate) fn matches(&self, node: &Node) -> bool {
+        Self::of_node(node).contains(self)
+    }
+
+    /// Key prefix shared by every entry of this lookup
+    fn prefix(&self) -> Vec<u8> {
+        let (kind, key): (u8, Vec<u8>) = match self {
+            Self::Template(id) => (1, id.as_uuid().as_bytes().to_vec()),
+            Self::Agent(id) => (2, id.to_bytes().to_vec()),
+            Self::ToolName(name) => (3, name.as_bytes().to_vec()),
+            Self::Model(model) => (4, model.as_bytes().to_vec()),
+            Self::Kind(kind) => (5, kind.as_bytes().to_vec()),
+        };
+        let mut prefix = Vec::with_capacity(1 + 4 + key.len() + POSITION_LEN);
+        prefix.push(kind);
+        // Keys are length-prefixed so one value can never be a prefix of another
+        #[allow(clippy::cast_possible_truncation)]
+        prefix.extend_from_slice(&(key.len() as u32).to_be_bytes());
+        prefix.extend_from_slice(&key);
+        prefix
+    }
+}
+
+/// Reverse lookup index stored in the graph database
+pub(crate) struct LookupIndex {
+    entries: Tree,
+    docs: Tree,
+    meta: Tree,
+}
+
+impl LookupIndex {
+    /// Open (or create) the index trees in `db`
+    pub(crate) fn open(db: &Db) -> Result<Self> {
+        Ok(Self {
+            entries: db.open_tree(b"lookup_entries")?,
+            docs: db.open_tree(b"lookup_docs")?,
+            meta: db.open_tree(b"lookup_meta")?,
+        })
+    }
+
+    /// Whether the index has been populated for this database
+    pub(crate) fn is_built(&self) -> Result<bool> {
+        Ok(self.meta.get(VERSION_KEY)?.is_some())
+    }
+
+    /// Record that the index covers every stored node
+    pub(crate) fn mark_built(&self) -> Result<()> {
+        self.meta
+            .insert(VERSION_KEY, &INDEX_VERSION.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Replace the lookups a node is listed under
+    pub(crate) fn index(
+        &self,
+        node_id: NodeId,
+        timestamp: DateTime<Utc>,
+        lookups: &[Lookup],
+    ) -> Result<()> {
+        self.remove(&node_id)?;
+        if lookups.is_empty() {
+            return Ok(());
+        }
+
+        let position = encode_position(timestamp, node_id);
+        let mut keys: Vec<Vec<u8>> = lookups
+            .iter()
+            .map(|lookup| {
+                let mut key = lookup.prefix();
+                key.extend_from_slice(&position);
+                key
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        for key in &keys {
+            self.entries.insert(key.as_slice(), &[])?;
+        }
+        self.docs
+            .insert(node_id.to_bytes(), rmp_serde::to_vec(&keys)?)?;
+        Ok(())
+    }
+
+    /// Remove a node from every lookup, if present
+    pub(crate) fn remove(&self, node_id: &NodeId) -> Result<()> {
+        let Some(bytes) = self.docs.remove(node_id.to_bytes())? else {
+            return Ok(());
+        };
+        let keys: Vec<Vec<u8>> = rmp_serde::from_slice(&bytes)?;
+        for key in keys {
+            self.entries.remove(key)?;
+        }
+        Ok(())
+    }
+
+    /// Node IDs listed under `lookup` in result order, starting after `after`
+    pub(crate) fn scan<'a>(
+        &'a self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+    ) -> impl Iterator<Item = Result<NodeId>> + 'a {
+        let prefix = lookup.prefix();
+        let start = match after {
+            Some(cursor) => {
+                let mut key = prefix.clone();
+                key.extend_from_slice(&encode_position(cursor.timestamp(), cursor.node_id()));
+                Bound::Excluded(key)
+            }
+            None => Bound::Included(prefix.clone()),
+        };
+        self.entries
+            .range((start, Bound::Unbounded))
+            .take_while(move |entry| {
+                entry
+                    .as_ref()
+                    .map_or(true, |(key, _)| key.starts_with(&prefix))
+            })
+            .map(|entry| {
+                let (key, _) = entry?;
+                decode_node_id(&key)
+            })
+    }
+}
+
+/// Sort matching nodes and cut the page after `after`
+///
+/// Used by backends without a maintained index.
+pub(crate) fn page_of(mut nodes: Vec<Node>, after: Option<&Cursor>, limit: usize) -> Page {
+    sort_newest_first(&mut nodes);
+    nodes.dedup_by_key(|node| node.id());
+    paginate(nodes, after, 0, Some(limit))
+}
+
+/// Encode a row position so byte order matches result order
+fn encode_position(timestamp: DateTime<Utc>, node_id: NodeId) -> [u8; POSITION_LEN] {
+    // Flipping the sign bit makes signed seconds sort as unsigned bytes
+    #[allow(clippy::cast_sign_loss)]
+    let secs = (timestamp.timestamp() as u64) ^ (1 << 63);
+    let mut position = [0u8; POSITION_LEN];
+    position[..8].copy_from_slice(&(!secs).to_be_bytes());
+    position[8..12].copy_from_slice(&(!timestamp.timestamp_subsec_nanos()).to_be_bytes());
+    for (slot, byte) in position[12..].iter_mut().zip(node_id.to_bytes()) {
+        *slot = !byte;
+    }
+    position
+}
+
+/// Recover the node ID from the tail of an entry key
+fn decode_node_id(key: &[u8]) -> Result<NodeId> {
+    if key.len() < POSITION_LEN {
+        return Err(Error::Storage("Invalid lookup index entry".to_string()));
+    }
+    let mut bytes = [0u8; 16];
+    for (slot, byte) in bytes.iter_mut().zip(&key[key.len() - 16..]) {
+        *slot = !byte;
+    }
+    Ok(NodeId::from_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::tempdir;
+
+    fn ids(index: &LookupIndex, lookup: &Lookup, after: Option<&Cursor>) -> Vec<NodeId> {
+        index.scan(lookup, after).map(Result::unwrap).collect()
+    }
+
+    #[test]
+    fn test_scan_order_and_resume() {
+        let dir = tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let index = LookupIndex::open(&db).unwrap();
+
+        let base = Utc::now();
+        let tool = Lookup::ToolName("search".to_string());
+        let rows: Vec<(NodeId, DateTime<Utc>)> = (0..4)
+            .map(|i| (NodeId::new(), base - Duration::seconds(i)))
+            .collect();
+        for (id, timestamp) in &rows {
+            index
+                .index(*id, *timestamp, std::slice::from_ref(&tool))
+                .unwrap();
+        }
+        // A longer tool name sharing the prefix must not leak into the scan
+        index
+            .index(
+                NodeId::new(),
+                base,
+                &[Lookup::ToolName("search_web".to_string())],
+            )
+            .unwrap();
+
+        let all = ids(&index, &tool, None);
+        assert_eq!(all, rows.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+
+        let cursor = Cursor::new(rows[1].1, rows[1].0);
+        assert_eq!(ids(&index, &tool, Some(&cursor)), all[2..]);
+
+        // Re-indexing moves a node; removing drops it
+        index
+            .index(
+                rows[3].0,
+                base + Duration::seconds(5),
+                std::slice::from_ref(&tool),
+            )
+            .unwrap();
+        assert_eq!(ids(&index, &tool, None)[0], rows[3].0);
+        index.remove(&rows[3].0).unwrap();
+        assert_eq!(ids(&index, &tool, None).len(), 3);
+    }
+
+    #[test]
+    fn test_negative_timestamps_sort_oldest_last() {
+        let dir = tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let index = LookupIndex::open(&db).unwrap();
+
+        let model = Lookup::Model("gpt-4".to_string());
+        let old = NodeId::new();
+        let new = NodeId::new();
+        index
+            .index(
+                old,
+                DateTime::from_timestamp(-100, 0).unwrap(),
+                std::slice::from_ref(&model),
+            )
+            .unwrap();
+        index
+            .index(new, Utc::now(), std::slice::from_ref(&model))
+            .unwrap();
+        assert_eq!(ids(&index, &model, None), vec![new, old]);
+    }
+}
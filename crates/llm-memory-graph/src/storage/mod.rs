@@ -2,20 +2,38 @@
 
 mod async_sled_backend;
 mod cache;
+mod lookup_index;
 mod pooled_backend;
+mod read_only;
 mod serialization;
+mod sharded_backend;
 mod sled_backend;
+mod text_index;
+mod vector_index;
 
 pub use async_sled_backend::AsyncSledBackend;
 pub use cache::{CacheStats, StorageCache};
+pub use lookup_index::Lookup;
 pub use pooled_backend::{PoolConfig, PoolMetrics, PoolMetricsSnapshot, PooledAsyncBackend};
+pub use read_only::{AsyncReadOnlyBackend, ReadOnlyBackend};
 pub use serialization::{SerializationFormat, Serializer};
+pub use sharded_backend::{ShardManifest, ShardedBackend};
 pub use sled_backend::SledBackend;
 
-use crate::Result;
-use crate::{Edge, EdgeId, Node, NodeId, SessionId};
+use crate::query::{Cursor, Page};
+use crate::search::{SearchHit, TextQuery};
+use crate::vector::{SimilarNode, VectorFilter};
+use crate::{Edge, EdgeId, EdgeType, Node, NodeId, SessionId};
+use crate::{Error, Result};
 use async_trait::async_trait;
 
+/// Error returned by the default implementations of optional backend methods
+fn unsupported(operation: &str) -> Error {
+    Error::Unsupported(format!(
+        "{operation} is not supported by this storage backend"
+    ))
+}
+
 /// Trait defining storage backend operations
 pub trait StorageBackend: Send + Sync {
     /// Store a node in the backend
@@ -45,6 +63,112 @@ pub trait StorageBackend: Send + Sync {
     /// Get all edges to a node
     fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>>;
 
+    /// Load every node in the store
+    ///
+    /// This is a full scan; prefer session or edge lookups where possible.
+    /// The default implementation returns [`Error::Unsupported`].
+    fn scan_nodes(&self) -> Result<Vec<Node>> {
+        Err(unsupported("scan_nodes"))
+    }
+
+    /// Run a ranked full-text search over indexed node content
+    ///
+    /// The default implementation returns [`Error::Unsupported`].
+    fn search_text(&self, _query: &TextQuery) -> Result<Vec<SearchHit>> {
+        Err(unsupported("search_text"))
+    }
+
+    /// Attach (or replace) the embedding of a prompt, response or template node
+    ///
+    /// The default implementation returns [`Error::Unsupported`], as do the
+    /// other embedding methods.
+    fn store_embedding(&self, _node_id: &NodeId, _vector: &[f32]) -> Result<()> {
+        Err(unsupported("store_embedding"))
+    }
+
+    /// Retrieve a node's embedding, if it has one
+    fn get_embedding(&self, _node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        Err(unsupported("get_embedding"))
+    }
+
+    /// Remove a node's embedding, if it has one
+    fn delete_embedding(&self, _node_id: &NodeId) -> Result<()> {
+        Err(unsupported("delete_embedding"))
+    }
+
+    /// Find the `k` nodes whose embeddings are most similar to `vector`
+    fn similar_nodes(
+        &self,
+        _vector: &[f32],
+        _k: usize,
+        _filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        Err(unsupported("similar_nodes"))
+    }
+
+    /// Atomically delete the `deletes` keys, then store the `puts` entries,
+    /// flushing the write to disk before returning
+    ///
+    /// The catalog holds small named records that are not part of the graph
+    /// itself, such as saved query and view definitions. The default
+    /// implementation returns [`Error::Unsupported`], as do the other
+    /// catalog methods.
+    fn write_catalog(&self, _puts: &[(Vec<u8>, Vec<u8>)], _deletes: &[Vec<u8>]) -> Result<()> {
+        Err(unsupported("write_catalog"))
+    }
+
+    /// Like [`Self::write_catalog`], but leave the write for the next
+    /// [`Self::flush`] instead of flushing it to disk before returning
+    ///
+    /// The entries are readable at once. The default implementation calls
+    /// [`Self::write_catalog`].
+    fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.write_catalog(puts, deletes)
+    }
+
+    /// Store a catalog entry only if `key` is not taken, flushing the write
+    /// to disk before returning
+    ///
+    /// The check and the write are one atomic step. Returns whether the entry
+    /// was stored.
+    fn insert_catalog_entry(&self, _key: &[u8], _value: &[u8]) -> Result<bool> {
+        Err(unsupported("insert_catalog_entry"))
+    }
+
+    /// Retrieve a catalog entry
+    fn get_catalog_entry(&self, _key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Err(unsupported("get_catalog_entry"))
+    }
+
+    /// Load every catalog entry whose key starts with `prefix`, in key order
+    fn scan_catalog(&self, _prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Err(unsupported("scan_catalog"))
+    }
+
+    /// Page through the nodes listed under a reverse lookup, newest first
+    ///
+    /// The default implementation scans; backends with a maintained index
+    /// should override it.
+    fn lookup_nodes(&self, lookup: &Lookup, after: Option<&Cursor>, limit: usize) -> Result<Page> {
+        let nodes = match lookup {
+            Lookup::Agent(agent) => {
+                let mut nodes = Vec::new();
+                for edge in self.get_incoming_edges(agent)? {
+                    if edge.edge_type == EdgeType::HandledBy {
+                        nodes.extend(self.get_node(&edge.from)?);
+                    }
+                }
+                nodes
+            }
+            _ => self
+                .scan_nodes()?
+                .into_iter()
+                .filter(|node| lookup.matches(node))
+                .collect(),
+        };
+        Ok(lookup_index::page_of(nodes, after, limit))
+    }
+
     /// Flush any pending writes
     fn flush(&self) -> Result<()>;
 
@@ -98,6 +222,119 @@ pub trait AsyncStorageBackend: Send + Sync {
     /// Get all edges to a node asynchronously
     async fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>>;
 
+    /// Load every node in the store asynchronously
+    ///
+    /// This is a full scan; prefer session or edge lookups where possible.
+    /// The default implementation returns [`Error::Unsupported`].
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        Err(unsupported("scan_nodes"))
+    }
+
+    /// Run a ranked full-text search over indexed node content asynchronously
+    ///
+    /// The default implementation returns [`Error::Unsupported`].
+    async fn search_text(&self, _query: &TextQuery) -> Result<Vec<SearchHit>> {
+        Err(unsupported("search_text"))
+    }
+
+    /// Attach (or replace) the embedding of a prompt, response or template node asynchronously
+    ///
+    /// The default implementation returns [`Error::Unsupported`], as do the
+    /// other embedding methods.
+    async fn store_embedding(&self, _node_id: &NodeId, _vector: &[f32]) -> Result<()> {
+        Err(unsupported("store_embedding"))
+    }
+
+    /// Retrieve a node's embedding asynchronously
+    async fn get_embedding(&self, _node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        Err(unsupported("get_embedding"))
+    }
+
+    /// Remove a node's embedding asynchronously
+    async fn delete_embedding(&self, _node_id: &NodeId) -> Result<()> {
+        Err(unsupported("delete_embedding"))
+    }
+
+    /// Find the `k` nodes whose embeddings are most similar to `vector` asynchronously
+    async fn similar_nodes(
+        &self,
+        _vector: &[f32],
+        _k: usize,
+        _filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        Err(unsupported("similar_nodes"))
+    }
+
+    /// Atomically delete the `deletes` keys, then store the `puts` entries,
+    /// flushing the write to disk before returning
+    ///
+    /// The default implementation returns [`Error::Unsupported`], as do the
+    /// other catalog methods.
+    async fn write_catalog(
+        &self,
+        _puts: &[(Vec<u8>, Vec<u8>)],
+        _deletes: &[Vec<u8>],
+    ) -> Result<()> {
+        Err(unsupported("write_catalog"))
+    }
+
+    /// Like [`Self::write_catalog`], but leave the write for the next
+    /// [`Self::flush`] instead of flushing it to disk before returning
+    ///
+    /// The entries are readable at once. The default implementation calls
+    /// [`Self::write_catalog`].
+    async fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.write_catalog(puts, deletes).await
+    }
+
+    /// Store a catalog entry only if `key` is not taken, asynchronously
+    ///
+    /// The check and the write are one atomic step. Returns whether the entry
+    /// was stored.
+    async fn insert_catalog_entry(&self, _key: &[u8], _value: &[u8]) -> Result<bool> {
+        Err(unsupported("insert_catalog_entry"))
+    }
+
+    /// Retrieve a catalog entry asynchronously
+    async fn get_catalog_entry(&self, _key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Err(unsupported("get_catalog_entry"))
+    }
+
+    /// Load every catalog entry whose key starts with `prefix` asynchronously
+    async fn scan_catalog(&self, _prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        Err(unsupported("scan_catalog"))
+    }
+
+    /// Page through the nodes listed under a reverse lookup asynchronously
+    ///
+    /// The default implementation scans; backends with a maintained index
+    /// should override it.
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        let nodes = match lookup {
+            Lookup::Agent(agent) => {
+                let mut nodes = Vec::new();
+                for edge in self.get_incoming_edges(agent).await? {
+                    if edge.edge_type == EdgeType::HandledBy {
+                        nodes.extend(self.get_node(&edge.from).await?);
+                    }
+                }
+                nodes
+            }
+            _ => self
+                .scan_nodes()
+                .await?
+                .into_iter()
+                .filter(|node| lookup.matches(node))
+                .collect(),
+        };
+        Ok(lookup_index::page_of(nodes, after, limit))
+    }
+
     /// Flush any pending writes asynchronously
     async fn flush(&self) -> Result<()>;
 
@@ -31,7 +31,10 @@
 //! ```
 
 use crate::{Error, Result};
-use crate::storage::{AsyncSledBackend, AsyncStorageBackend, StorageStats};
+use crate::query::{Cursor, Page};
+use crate::search::{SearchHit, TextQuery};
+use crate::storage::{AsyncSledBackend, AsyncStorageBackend, Lookup, StorageStats};
+use crate::vector::{SimilarNode, VectorFilter};
 use crate::{Edge, EdgeId, Node, NodeId, SessionId};
 use async_trait::async_trait;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
@@ -394,6 +397,71 @@ impl AsyncStorageBackend for PooledAsyncBackend {
             .await
     }
 
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        self.with_permit(self.backend.scan_nodes()).await
+    }
+
+    async fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        self.with_permit(self.backend.search_text(query)).await
+    }
+
+    async fn store_embedding(&self, node_id: &NodeId, vector: &[f32]) -> Result<()> {
+        self.with_permit(self.backend.store_embedding(node_id, vector))
+            .await
+    }
+
+    async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.with_permit(self.backend.get_embedding(node_id)).await
+    }
+
+    async fn delete_embedding(&self, node_id: &NodeId) -> Result<()> {
+        self.with_permit(self.backend.delete_embedding(node_id))
+            .await
+    }
+
+    async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        self.with_permit(self.backend.similar_nodes(vector, k, filter))
+            .await
+    }
+
+    async fn write_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.with_permit(self.backend.write_catalog(puts, deletes))
+            .await
+    }
+
+    async fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.with_permit(self.backend.stage_catalog(puts, deletes))
+            .await
+    }
+
+    async fn insert_catalog_entry(&self, key: &[u8], value: &[u8]) -> Result<bool> {
+        self.with_permit(self.backend.insert_catalog_entry(key, value))
+            .await
+    }
+
+    async fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.with_permit(self.backend.get_catalog_entry(key)).await
+    }
+
+    async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.with_permit(self.backend.scan_catalog(prefix)).await
+    }
+
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.with_permit(self.backend.lookup_nodes(lookup, after, limit))
+            .await
+    }
+
     async fn flush(&self) -> Result<()> {
         self.with_permit(self.backend.flush()).await
     }
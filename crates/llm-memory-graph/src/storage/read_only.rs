@@ -0,0 +1,469 @@
+//! Read-only storage backends over snapshot copies
+//!
+//! Sled holds an exclusive lock on its database directory, so a second process
+//! cannot open a graph that a server is already using. The backends in this module
+//! copy the database directory (either the live database or a checkpoint taken
+//! earlier) and open that copy instead. Every mutating operation is rejected with
+//! [`Error::ReadOnly`].
+//!
+//! The copy is taken file by file without coordinating with the process holding
+//! the lock, so a snapshot is stale as soon as it is taken. If that process writes
+//! while the copy is in progress, the copied files can come from different
+//! instants: sled recovers them like a crash image, which may drop the latest
+//! writes or, in the worst case, refuse to open the copy. Snapshot a checkpoint or
+//! an idle database when an exact view matters.
+//!
+//! Each reader gets its own copy in a fresh temporary directory, which is removed
+//! when the backend is dropped. Sled opens the copy for writing, so copies are
+//! never shared or reused: nothing a reader does can leak into another reader's
+//! view.
+
+use super::{
+    AsyncSledBackend, AsyncStorageBackend, Lookup, SledBackend, StorageBackend, StorageStats,
+};
+use crate::query::{Cursor, Page};
+use crate::search::{SearchHit, TextQuery};
+use crate::vector::{SimilarNode, VectorFilter};
+use crate::{Edge, EdgeId, Node, NodeId, SessionId};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Private copy of a database directory, removed on drop
+#[derive(Debug)]
+pub(crate) struct SnapshotDir {
+    dir: TempDir,
+}
+
+impl SnapshotDir {
+    /// Copy `source` into a fresh temporary directory owned by this reader
+    pub(crate) fn create(source: &Path) -> Result<Self> {
+        if !source.is_dir() {
+            return Err(Error::StorageError(format!(
+                "Cannot snapshot {}: not a database directory",
+                source.display()
+            )));
+        }
+
+        let dir = tempfile::Builder::new()
+            .prefix("llm-memory-graph-snapshot-")
+            .tempdir()?;
+        copy_dir(source, dir.path())?;
+        Ok(Self { dir })
+    }
+
+    /// Location of the copied database
+    pub(crate) fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Open a private snapshot of `source`
+    fn open(source: &Path) -> Result<(SledBackend, Self)> {
+        let snapshot = Self::create(source)?;
+        let backend = SledBackend::open(snapshot.path())?;
+        Ok((backend, snapshot))
+    }
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_only(operation: &str) -> Error {
+    Error::ReadOnly(format!("{operation} is not permitted on a read-only graph"))
+}
+
+/// Synchronous read-only backend backed by a snapshot copy
+pub struct ReadOnlyBackend {
+    // Declared before the snapshot so the database is closed before its files are removed
+    inner: SledBackend,
+    _snapshot: SnapshotDir,
+}
+
+impl ReadOnlyBackend {
+    /// Snapshot the database at `path` and open the copy
+    ///
+    /// Works whether or not another process currently holds the database open.
+    /// See the [module documentation](self) for what the snapshot guarantees.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let (inner, snapshot) = SnapshotDir::open(path.as_ref())?;
+        Ok(Self {
+            inner,
+            _snapshot: snapshot,
+        })
+    }
+}
+
+impl StorageBackend for ReadOnlyBackend {
+    fn store_node(&self, _node: &Node) -> Result<()> {
+        Err(read_only("store_node"))
+    }
+
+    fn get_node(&self, id: &NodeId) -> Result<Option<Node>> {
+        self.inner.get_node(id)
+    }
+
+    fn delete_node(&self, _id: &NodeId) -> Result<()> {
+        Err(read_only("delete_node"))
+    }
+
+    fn store_edge(&self, _edge: &Edge) -> Result<()> {
+        Err(read_only("store_edge"))
+    }
+
+    fn get_edge(&self, id: &EdgeId) -> Result<Option<Edge>> {
+        self.inner.get_edge(id)
+    }
+
+    fn delete_edge(&self, _id: &EdgeId) -> Result<()> {
+        Err(read_only("delete_edge"))
+    }
+
+    fn get_session_nodes(&self, session_id: &SessionId) -> Result<Vec<Node>> {
+        self.inner.get_session_nodes(session_id)
+    }
+
+    fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_outgoing_edges(node_id)
+    }
+
+    fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_incoming_edges(node_id)
+    }
+
+    fn scan_nodes(&self) -> Result<Vec<Node>> {
+        self.inner.scan_nodes()
+    }
+
+    fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        self.inner.search_text(query)
+    }
+
+    fn store_embedding(&self, _node_id: &NodeId, _vector: &[f32]) -> Result<()> {
+        Err(read_only("store_embedding"))
+    }
+
+    fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.inner.get_embedding(node_id)
+    }
+
+    fn delete_embedding(&self, _node_id: &NodeId) -> Result<()> {
+        Err(read_only("delete_embedding"))
+    }
+
+    fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        self.inner.similar_nodes(vector, k, filter)
+    }
+
+    fn write_catalog(&self, _puts: &[(Vec<u8>, Vec<u8>)], _deletes: &[Vec<u8>]) -> Result<()> {
+        Err(read_only("write_catalog"))
+    }
+
+    fn stage_catalog(&self, _puts: &[(Vec<u8>, Vec<u8>)], _deletes: &[Vec<u8>]) -> Result<()> {
+        Err(read_only("stage_catalog"))
+    }
+
+    fn insert_catalog_entry(&self, _key: &[u8], _value: &[u8]) -> Result<bool> {
+        Err(read_only("insert_catalog_entry"))
+    }
+
+    fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.get_catalog_entry(key)
+    }
+
+    fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner.scan_catalog(prefix)
+    }
+
+    fn lookup_nodes(&self, lookup: &Lookup, after: Option<&Cursor>, limit: usize) -> Result<Page> {
+        self.inner.lookup_nodes(lookup, after, limit)
+    }
+
+    fn flush(&self) -> Result<()> {
+        // Nothing is ever written, so there is nothing to flush
+        Ok(())
+    }
+
+    fn stats(&self) -> Result<StorageStats> {
+        self.inner.stats()
+    }
+}
+
+/// Async read-only backend backed by a snapshot copy
+#[derive(Clone)]
+pub struct AsyncReadOnlyBackend {
+    inner: AsyncSledBackend,
+    _snapshot: Arc<SnapshotDir>,
+}
+
+impl AsyncReadOnlyBackend {
+    /// Snapshot the database at `path` and open the copy
+    ///
+    /// Works whether or not another process currently holds the database open.
+    /// See the [module documentation](self) for what the snapshot guarantees.
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let source = path.as_ref().to_path_buf();
+        let (inner, snapshot) = tokio::task::spawn_blocking(move || SnapshotDir::open(&source))
+            .await
+            .map_err(|e| Error::RuntimeError(e.to_string()))??;
+        Ok(Self {
+            inner: AsyncSledBackend::from_sync(inner),
+            _snapshot: Arc::new(snapshot),
+        })
+    }
+}
+
+#[async_trait]
+impl AsyncStorageBackend for AsyncReadOnlyBackend {
+    async fn store_node(&self, _node: &Node) -> Result<()> {
+        Err(read_only("store_node"))
+    }
+
+    async fn get_node(&self, id: &NodeId) -> Result<Option<Node>> {
+        self.inner.get_node(id).await
+    }
+
+    async fn delete_node(&self, _id: &NodeId) -> Result<()> {
+        Err(read_only("delete_node"))
+    }
+
+    async fn store_edge(&self, _edge: &Edge) -> Result<()> {
+        Err(read_only("store_edge"))
+    }
+
+    async fn get_edge(&self, id: &EdgeId) -> Result<Option<Edge>> {
+        self.inner.get_edge(id).await
+    }
+
+    async fn delete_edge(&self, _id: &EdgeId) -> Result<()> {
+        Err(read_only("delete_edge"))
+    }
+
+    async fn get_session_nodes(&self, session_id: &SessionId) -> Result<Vec<Node>> {
+        self.inner.get_session_nodes(session_id).await
+    }
+
+    async fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_outgoing_edges(node_id).await
+    }
+
+    async fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_incoming_edges(node_id).await
+    }
+
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        self.inner.scan_nodes().await
+    }
+
+    async fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        self.inner.search_text(query).await
+    }
+
+    async fn store_embedding(&self, _node_id: &NodeId, _vector: &[f32]) -> Result<()> {
+        Err(read_only("store_embedding"))
+    }
+
+    async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.inner.get_embedding(node_id).await
+    }
+
+    async fn delete_embedding(&self, _node_id: &NodeId) -> Result<()> {
+        Err(read_only("delete_embedding"))
+    }
+
+    async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        self.inner.similar_nodes(vector, k, filter).await
+    }
+
+    async fn write_catalog(
+        &self,
+        _puts: &[(Vec<u8>, Vec<u8>)],
+        _deletes: &[Vec<u8>],
+    ) -> Result<()> {
+        Err(read_only("write_catalog"))
+    }
+
+    async fn stage_catalog(
+        &self,
+        _puts: &[(Vec<u8>, Vec<u8>)],
+        _deletes: &[Vec<u8>],
+    ) -> Result<()> {
+        Err(read_only("stage_catalog"))
+    }
+
+    async fn insert_catalog_entry(&self, _key: &[u8], _value: &[u8]) -> Result<bool> {
+        Err(read_only("insert_catalog_entry"))
+    }
+
+    async fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.get_catalog_entry(key).await
+    }
+
+    async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner.scan_catalog(prefix).await
+    }
+
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.inner.lookup_nodes(lookup, after, limit).await
+    }
+
+    async fn flush(&self) -> Result<()> {
+        // Nothing is ever written, so there is nothing to flush
+        Ok(())
+    }
+
+    async fn stats(&self) -> Result<StorageStats> {
+        self.inner.stats().await
+    }
+
+    async fn store_nodes_batch(&self, _nodes: &[Node]) -> Result<Vec<NodeId>> {
+        Err(read_only("store_nodes_batch"))
+    }
+
+    async fn store_edges_batch(&self, _edges: &[Edge]) -> Result<Vec<EdgeId>> {
+        Err(read_only("store_edges_batch"))
+    }
+
+    fn get_session_nodes_stream(
+        &self,
+        session_id: &SessionId,
+    ) -> std::pin::Pin<Box<dyn futures::stream::Stream<Item = Result<Node>> + Send + '_>> {
+        self.inner.get_session_nodes_stream(session_id)
+    }
+
+    async fn count_session_nodes(&self, session_id: &SessionId) -> Result<usize> {
+        self.inner.count_session_nodes(session_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConversationSession, PromptNode};
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_snapshot_of_locked_database() {
+        let dir = tempdir().unwrap();
+        let live = SledBackend::open(dir.path()).unwrap();
+
+        let session = ConversationSession::new();
+        live.store_node(&Node::Session(session.clone())).unwrap();
+
+        // The live handle still holds the lock
+        assert!(matches!(
+            SledBackend::open(dir.path()),
+            Err(Error::DatabaseLocked(_))
+        ));
+
+        let backend = ReadOnlyBackend::open(dir.path()).unwrap();
+        assert!(backend.get_node(&session.node_id).unwrap().is_some());
+        assert_eq!(backend.stats().unwrap().node_count, 1);
+
+        let prompt = PromptNode::new(session.id, "Hello".to_string());
+        assert!(matches!(
+            backend.store_node(&Node::Prompt(prompt)),
+            Err(Error::ReadOnly(_))
+        ));
+    }
+
+    #[test]
+    fn test_snapshot_removed_on_drop() {
+        let dir = tempdir().unwrap();
+        drop(SledBackend::open(dir.path()).unwrap());
+
+        let snapshot = SnapshotDir::create(dir.path()).unwrap();
+        let path = snapshot.path().to_path_buf();
+        assert!(path.exists());
+        drop(snapshot);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_snapshots_are_private() {
+        let dir = tempdir().unwrap();
+        let live = SledBackend::open(dir.path()).unwrap();
+        live.store_node(&Node::Session(ConversationSession::new()))
+            .unwrap();
+        live.flush().unwrap();
+
+        let (first, first_dir) = SnapshotDir::open(dir.path()).unwrap();
+        let (second, second_dir) = SnapshotDir::open(dir.path()).unwrap();
+        assert_ne!(first_dir.path(), second_dir.path());
+
+        // A write through one copy is not visible through the other
+        first
+            .store_node(&Node::Session(ConversationSession::new()))
+            .unwrap();
+        assert_eq!(first.stats().unwrap().node_count, 2);
+        assert_eq!(second.stats().unwrap().node_count, 1);
+
+        drop(first);
+        let path = first_dir.path().to_path_buf();
+        drop(first_dir);
+        assert!(!path.exists());
+
+        live.store_node(&Node::Session(ConversationSession::new()))
+            .unwrap();
+        live.flush().unwrap();
+        let backend = ReadOnlyBackend::open(dir.path()).unwrap();
+        assert_eq!(backend.stats().unwrap().node_count, 2);
+    }
+
+    #[test]
+    fn test_snapshot_requires_directory() {
+        let dir = tempdir().unwrap();
+        assert!(ReadOnlyBackend::open(dir.path().join("missing")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_async_read_only_rejects_writes() {
+        let dir = tempdir().unwrap();
+        let live = AsyncSledBackend::open(dir.path()).await.unwrap();
+        let session = ConversationSession::new();
+        live.store_node(&Node::Session(session.clone()))
+            .await
+            .unwrap();
+
+        let backend = AsyncReadOnlyBackend::open(dir.path()).await.unwrap();
+        assert_eq!(
+            backend.get_session_nodes(&session.id).await.unwrap().len(),
+            1
+        );
+        assert!(matches!(
+            backend.delete_node(&session.node_id).await,
+            Err(Error::ReadOnly(_))
+        ));
+        assert!(matches!(
+            backend.store_nodes_batch(&[Node::Session(session)]).await,
+            Err(Error::ReadOnly(_))
+        ));
+    }
+}
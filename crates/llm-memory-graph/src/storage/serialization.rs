@@ -1,7 +1,12 @@
 //! Serialization utilities for storage
+//!
+//! Fields added to stored types carry `#[serde(default)]`, which lets JSON and
+//! MessagePack records written before the field existed decode unchanged.
+//! Bincode is not self-describing and cannot skip a missing field, so its
+//! older layouts are upgraded on read; see [`Serializer::deserialize_node`].
 
 use crate::{Error, Result};
-use crate::{Edge, Node};
+use crate::{Attachment, Edge, Node};
 
 /// Serialization format options
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -42,6 +47,10 @@ impl Serializer {
     }
 
     /// Deserialize a node from bytes
+    ///
+    /// Bincode prompts and responses written before nodes had attachments are
+    /// read with an empty attachment list. They are stored in the current
+    /// layout the next time they are written.
     pub fn deserialize_node(&self, bytes: &[u8]) -> Result<Node> {
         match self.format {
             SerializationFormat::Json => {
@@ -50,9 +59,16 @@ impl Serializer {
             SerializationFormat::MessagePack => {
                 rmp_serde::from_slice(bytes).map_err(|e| Error::SerializationError(e.to_string()))
             }
-            SerializationFormat::Bincode => {
-                bincode::deserialize(bytes).map_err(|e| Error::SerializationError(e.to_string()))
-            }
+            SerializationFormat::Bincode => bincode::deserialize(bytes)
+                .or_else(|e| match *e {
+                    bincode::ErrorKind::Io(ref io)
+                        if io.kind() == std::io::ErrorKind::UnexpectedEof =>
+                    {
+                        deserialize_bincode_without_attachments(bytes).ok_or(e)
+                    }
+                    _ => Err(e),
+                })
+                .map_err(|e| Error::SerializationError(e.to_string())),
         }
     }
 
@@ -93,10 +109,89 @@ impl Default for Serializer {
     }
 }
 
+/// Decode a Bincode prompt or response from before nodes had attachments
+///
+/// The attachment list is the last field of both node types, so an old record
+/// is a current one that stops where the list would start. Appending an empty
+/// list gives the current layout.
+fn deserialize_bincode_without_attachments(bytes: &[u8]) -> Option<Node> {
+    let mut upgraded = bytes.to_vec();
+    upgraded.extend(bincode::serialize(&Vec::<Attachment>::new()).ok()?);
+    match bincode::deserialize(&upgraded).ok()? {
+        node @ (Node::Prompt(_) | Node::Response(_)) => Some(node),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{NodeId, PromptNode, SessionId};
+    use crate::{
+        NodeId, PromptMetadata, PromptNode, ResponseMetadata, SessionId, TemplateId, TokenUsage,
+    };
+    use chrono::{DateTime, Utc};
+    use serde::Serialize;
+    use std::collections::HashMap;
+
+    /// Prompt and response layouts from before nodes had attachments
+    #[derive(Serialize)]
+    enum LegacyNode {
+        Prompt {
+            id: NodeId,
+            session_id: SessionId,
+            timestamp: DateTime<Utc>,
+            template_id: Option<TemplateId>,
+            content: String,
+            variables: HashMap<String, String>,
+            metadata: PromptMetadata,
+        },
+        Response {
+            id: NodeId,
+            prompt_id: NodeId,
+            timestamp: DateTime<Utc>,
+            content: String,
+            usage: TokenUsage,
+            metadata: ResponseMetadata,
+        },
+    }
+
+    fn legacy_nodes() -> Vec<LegacyNode> {
+        vec![
+            LegacyNode::Prompt {
+                id: NodeId::new(),
+                session_id: SessionId::new(),
+                timestamp: Utc::now(),
+                template_id: None,
+                content: "question".to_string(),
+                variables: HashMap::new(),
+                metadata: PromptMetadata::default(),
+            },
+            LegacyNode::Response {
+                id: NodeId::new(),
+                prompt_id: NodeId::new(),
+                timestamp: Utc::now(),
+                content: "answer".to_string(),
+                usage: TokenUsage::new(10, 20),
+                metadata: ResponseMetadata::default(),
+            },
+        ]
+    }
+
+    fn assert_upgraded(legacy: &LegacyNode, node: &Node) {
+        match (legacy, node) {
+            (LegacyNode::Prompt { id, content, .. }, Node::Prompt(prompt)) => {
+                assert_eq!(prompt.id, *id);
+                assert_eq!(&prompt.content, content);
+                assert!(prompt.attachments.is_empty());
+            }
+            (LegacyNode::Response { id, usage, .. }, Node::Response(response)) => {
+                assert_eq!(response.id, *id);
+                assert_eq!(response.usage.total_tokens, usage.total_tokens);
+                assert!(response.attachments.is_empty());
+            }
+            _ => panic!("decoded the wrong node type: {node:?}"),
+        }
+    }
 
     #[test]
     fn test_node_json_serialization() {
@@ -124,6 +219,32 @@ mod tests {
         assert_eq!(node.id(), deserialized.id());
     }
 
+    #[test]
+    fn test_messagepack_reads_nodes_without_attachments() {
+        let serializer = Serializer::new(SerializationFormat::MessagePack);
+        for legacy in legacy_nodes() {
+            let bytes = rmp_serde::to_vec(&legacy).unwrap();
+            assert_upgraded(&legacy, &serializer.deserialize_node(&bytes).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_bincode_reads_nodes_without_attachments() {
+        let serializer = Serializer::new(SerializationFormat::Bincode);
+        for legacy in legacy_nodes() {
+            let bytes = bincode::serialize(&legacy).unwrap();
+            assert_upgraded(&legacy, &serializer.deserialize_node(&bytes).unwrap());
+        }
+
+        // Current records round-trip, and truncated ones still fail
+        let node = Node::Prompt(PromptNode::new(SessionId::new(), "Test".to_string()));
+        let bytes = serializer.serialize_node(&node).unwrap();
+        assert_eq!(serializer.deserialize_node(&bytes).unwrap().id(), node.id());
+        assert!(serializer
+            .deserialize_node(&bytes[..bytes.len() / 2])
+            .is_err());
+    }
+
     #[test]
     fn test_edge_serialization() {
         use crate::{Edge, EdgeType};
@@ -0,0 +1,1174 @@
+//! Sharded storage backend spreading data across multiple sled databases
+//!
+//! A single sled instance serializes all writes through one log, which caps
+//! write throughput. `ShardedBackend` partitions the graph across N independent
+//! [`AsyncSledBackend`]s, routing each session (and everything that belongs to it)
+//! to a shard chosen by hashing its `SessionId`.
+//!
+//! Layout on disk:
+//!
+//! ```text
+//! <root>/manifest.json   shard count and format version, written once at creation
+//! <root>/directory/      cross-shard edges, nodes waiting for a parent, catalog
+//! <root>/shard-000/      one sled database per shard
+//! <root>/shard-001/
+//! ...
+//! ```
+//!
+//! Agents and templates are shared between sessions, so they are placed by hashing
+//! their own node ID. Edges live on the shard of their source node.
+//!
+//! Lookups by ID start from the item's home shard, chosen by hashing the node or
+//! edge ID. An item stored on another shard, such as a prompt on its session's
+//! shard, leaves a pointer in its home shard's catalog, so a lookup by ID reads
+//! at most two shards. Routing a write reads the shards, and the directory only
+//! while some node waits for its parent. The directory is otherwise only
+//! written for edges whose target lives on a different shard, recorded there so
+//! incoming-edge lookups can find them without scanning every shard.
+//!
+//! A response or tool invocation stored before its parent cannot know its
+//! session yet. It is placed on the home shard of its parent and recorded in the
+//! directory as waiting for it; when the parent is stored, the node, its
+//! embedding and its outgoing edges move to the parent's shard, followed by
+//! anything waiting on the node. The move copies the data and flushes it before
+//! changing any pointer, and flushes again before deleting the old copy. The
+//! node stays recorded as waiting until it is done, so storing the parent again
+//! finishes an interrupted move.
+//!
+//! Apart from moves, writes do not flush: like a single sled database, the
+//! shards and the directory reach the disk in the background and on
+//! [`AsyncStorageBackend::flush`]. Pointers and directory entries are written
+//! before the data they describe, so a reader never finds data it cannot
+//! reach, only a pointer to data that is not written yet, which reads as
+//! missing. Each database recovers independently from a crash, though, so a
+//! crash before the next flush can keep a node while losing its pointer,
+//! leaving it reachable through its session but not by ID.
+//!
+//! Full-text search asks every shard and merges their hits by score. Each
+//! shard scores BM25 against its own document frequencies, so the ranking is
+//! approximate: a term that is rare on one shard but common overall scores
+//! higher there than global statistics would give it. The difference shrinks
+//! as shards grow and sessions spread evenly across them.
+
+use super::lookup_index;
+use super::{AsyncSledBackend, AsyncStorageBackend, Lookup, StorageStats};
+use crate::query::{Cursor, Page};
+use crate::search::{self, SearchHit, TextQuery};
+use crate::vector::{self, SimilarNode, VectorFilter};
+use crate::{Edge, EdgeId, Node, NodeId, SessionId};
+use crate::{Error, Result};
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Current version of the on-disk manifest format
+const MANIFEST_VERSION: u32 = 1;
+
+/// File name of the shard manifest inside the root directory
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Manifest describing a sharded store
+///
+/// The shard count is fixed when the store is created; reopening with a different
+/// count would silently misroute every session, so it is rejected instead.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardManifest {
+    /// Manifest format version
+    pub version: u32,
+    /// Number of shards the store was created with
+    pub shard_count: u32,
+    /// When the store was created
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ShardManifest {
+    fn path(root: &Path) -> PathBuf {
+        root.join(MANIFEST_FILE)
+    }
+
+    /// Load the manifest from a sharded store root, if one exists
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = Self::path(root);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = std::fs::read(path)?;
+        let manifest: Self = serde_json::from_slice(&bytes)?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(Error::ConfigError(format!(
+                "Unsupported shard manifest version {} (expected {})",
+                manifest.version, MANIFEST_VERSION
+            )));
+        }
+        Ok(Some(manifest))
+    }
+
+    fn store(&self, root: &Path) -> Result<()> {
+        let bytes = serde_json::to_vec_pretty(self)?;
+        std::fs::write(Self::path(root), bytes)?;
+        Ok(())
+    }
+}
+
+/// Catalog prefix of the pointers a home shard keeps to nodes stored elsewhere
+const NODE_POINTER_PREFIX: &[u8] = b"sharded/node/";
+
+/// Catalog prefix of the pointers a home shard keeps to edges stored elsewhere
+const EDGE_POINTER_PREFIX: &[u8] = b"sharded/edge/";
+
+/// Directory of what hashing alone cannot place
+struct ShardDirectory {
+    db: sled::Db,
+    /// (target node ID, edge ID) -> shard index, only for edges stored away from their target
+    remote_incoming: sled::Tree,
+    /// Node ID -> (parent ID, shard index), for responses and tools stored
+    /// before their parent, with the shard they were placed on
+    waiting_for: sled::Tree,
+    /// (parent ID, node ID) -> (), the reverse of `waiting_for`
+    waiting_children: sled::Tree,
+    /// Number of entries in `waiting_for`, so writes skip the directory when
+    /// nothing is waiting
+    waiting: AtomicUsize,
+    /// Catalog entries, kept once for the whole graph
+    catalog: sled::Tree,
+}
+
+impl ShardDirectory {
+    fn open(path: &Path) -> Result<Self> {
+        let db = sled::open(path)?;
+        let remote_incoming = db.open_tree(b"remote_incoming")?;
+        let waiting_for = db.open_tree(b"waiting_for")?;
+        let waiting_children = db.open_tree(b"waiting_children")?;
+        let catalog = db.open_tree(b"catalog")?;
+        let waiting = AtomicUsize::new(waiting_for.len());
+
+        Ok(Self {
+            db,
+            remote_incoming,
+            waiting_for,
+            waiting_children,
+            waiting,
+            catalog,
+        })
+    }
+
+    fn decode_shard(bytes: &[u8]) -> Option<usize> {
+        let raw: [u8; 4] = bytes.try_into().ok()?;
+        Some(u32::from_be_bytes(raw) as usize)
+    }
+
+    fn encode_shard(shard: usize) -> [u8; 4] {
+        #[allow(clippy::cast_possible_truncation)]
+        (shard as u32).to_be_bytes()
+    }
+
+    fn remote_key(to: &NodeId, edge: &EdgeId) -> Vec<u8> {
+        Self::pair_key(to.to_bytes(), edge.to_bytes())
+    }
+
+    fn waiting_key(parent: &NodeId, child: &NodeId) -> Vec<u8> {
+        Self::pair_key(parent.to_bytes(), child.to_bytes())
+    }
+
+    fn pair_key(first: [u8; 16], second: [u8; 16]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(32);
+        key.extend_from_slice(&first);
+        key.extend_from_slice(&second);
+        key
+    }
+
+    /// The second ID of a key built by `pair_key`
+    fn second_id(key: &[u8]) -> Option<[u8; 16]> {
+        key.get(16..32).and_then(|b| <[u8; 16]>::try_from(b).ok())
+    }
+
+    /// Whether any node is waiting for its parent
+    fn has_waiting(&self) -> bool {
+        self.waiting.load(Ordering::Acquire) > 0
+    }
+
+    /// Whether a node is waiting for its parent to be stored
+    fn is_waiting(&self, id: &NodeId) -> Result<bool> {
+        Ok(self.has_waiting() && self.waiting_for.contains_key(id.to_bytes())?)
+    }
+
+    /// Record that `child` was placed on `shard` before `parent` was stored
+    fn add_waiting(&self, parent: &NodeId, child: &NodeId, shard: usize) -> Result<()> {
+        let mut value = parent.to_bytes().to_vec();
+        value.extend_from_slice(&Self::encode_shard(shard));
+        self.waiting_children
+            .insert(Self::waiting_key(parent, child), &[])?;
+        if self.waiting_for.insert(child.to_bytes(), value)?.is_none() {
+            self.waiting.fetch_add(1, Ordering::AcqRel);
+        }
+        Ok(())
+    }
+
+    /// Forget that `child` is waiting, if it is
+    fn remove_waiting(&self, child: &NodeId) -> Result<()> {
+        if !self.has_waiting() {
+            return Ok(());
+        }
+        if let Some(value) = self.waiting_for.remove(child.to_bytes())? {
+            self.waiting.fetch_sub(1, Ordering::AcqRel);
+            let mut key = value[..16].to_vec();
+            key.extend_from_slice(&child.to_bytes());
+            self.waiting_children.remove(key)?;
+        }
+        Ok(())
+    }
+
+    /// The shard a waiting node was placed on
+    fn waiting_shard(&self, child: &NodeId) -> Result<Option<usize>> {
+        Ok(self
+            .waiting_for
+            .get(child.to_bytes())?
+            .and_then(|value| value.get(16..).and_then(Self::decode_shard)))
+    }
+
+    /// Nodes waiting for `parent`
+    fn children_of(&self, parent: &NodeId) -> Result<Vec<NodeId>> {
+        if !self.has_waiting() {
+            return Ok(Vec::new());
+        }
+        self.waiting_children
+            .scan_prefix(parent.to_bytes())
+            .keys()
+            .filter_map(|key| match key {
+                Ok(key) => Self::second_id(&key).map(|id| Ok(NodeId::from_bytes(id))),
+                Err(e) => Some(Err(e.into())),
+            })
+            .collect()
+    }
+}
+
+/// Storage backend that partitions the graph across multiple sled databases
+///
+/// Session-scoped nodes (sessions, prompts, responses, tool invocations) are
+/// co-located on the shard selected by their session, so per-session queries hit
+/// a single database. Global operations such as [`AsyncStorageBackend::stats`]
+/// scatter to every shard concurrently and gather the results.
+///
+/// # Examples
+///
+/// ```no_run
+/// use llm_memory_graph::storage::{AsyncStorageBackend, ShardedBackend};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let backend = ShardedBackend::open("./data/sharded", 8).await?;
+///     let stats = backend.stats().await?;
+///     println!("{} nodes across {} shards", stats.node_count, backend.shard_count());
+///     Ok(())
+/// }
+/// ```
+pub struct ShardedBackend {
+    shards: Vec<AsyncSledBackend>,
+    directory: ShardDirectory,
+    manifest: ShardManifest,
+}
+
+impl ShardedBackend {
+    /// Open or create a sharded store with the given number of shards
+    ///
+    /// When the store already exists, `shard_count` must match the count recorded
+    /// in its manifest; otherwise a [`Error::ConfigError`] is returned.
+    pub async fn open<P: AsRef<Path>>(path: P, shard_count: usize) -> Result<Self> {
+        if shard_count == 0 {
+            return Err(Error::ConfigError(
+                "Shard count must be at least 1".to_string(),
+            ));
+        }
+        let shard_count = u32::try_from(shard_count)
+            .map_err(|_| Error::ConfigError(format!("Too many shards: {shard_count}")))?;
+
+        let root = path.as_ref().to_path_buf();
+        let manifest = {
+            let root = root.clone();
+            tokio::task::spawn_blocking(move || -> Result<ShardManifest> {
+                std::fs::create_dir_all(&root)?;
+                match ShardManifest::load(&root)? {
+                    Some(existing) if existing.shard_count != shard_count => {
+                        Err(Error::ConfigError(format!(
+                            "Store at {} was created with {} shards, cannot open with {}",
+                            root.display(),
+                            existing.shard_count,
+                            shard_count
+                        )))
+                    }
+                    Some(existing) => Ok(existing),
+                    None => {
+                        let manifest = ShardManifest {
+                            version: MANIFEST_VERSION,
+                            shard_count,
+                            created_at: chrono::Utc::now(),
+                        };
+                        manifest.store(&root)?;
+                        Ok(manifest)
+                    }
+                }
+            })
+            .await
+            .map_err(|e| Error::RuntimeError(e.to_string()))??
+        };
+
+        Self::open_with_manifest(root, manifest).await
+    }
+
+    /// Open an existing sharded store, taking the shard count from its manifest
+    pub async fn open_existing<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let root = path.as_ref().to_path_buf();
+        let manifest = {
+            let root = root.clone();
+            tokio::task::spawn_blocking(move || ShardManifest::load(&root))
+                .await
+                .map_err(|e| Error::RuntimeError(e.to_string()))??
+        }
+        .ok_or_else(|| {
+            Error::ConfigError(format!("No shard manifest found at {}", root.display()))
+        })?;
+
+        Self::open_with_manifest(root, manifest).await
+    }
+
+    async fn open_with_manifest(root: PathBuf, manifest: ShardManifest) -> Result<Self> {
+        let shards = try_join_all(
+            (0..manifest.shard_count)
+                .map(|i| AsyncSledBackend::open(root.join(format!("shard-{i:03}")))),
+        )
+        .await?;
+
+        let directory_path = root.join("directory");
+        let directory = tokio::task::spawn_blocking(move || ShardDirectory::open(&directory_path))
+            .await
+            .map_err(|e| Error::RuntimeError(e.to_string()))??;
+
+        Ok(Self {
+            shards,
+            directory,
+            manifest,
+        })
+    }
+
+    /// Number of shards in this store
+    #[must_use]
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The manifest this store was opened with
+    #[must_use]
+    pub fn manifest(&self) -> &ShardManifest {
+        &self.manifest
+    }
+
+    /// Shard index that owns the given session
+    #[must_use]
+    pub fn shard_for_session(&self, session_id: &SessionId) -> usize {
+        self.bucket(session_id.as_uuid().as_bytes())
+    }
+
+    /// Map raw ID bytes to a shard using FNV-1a
+    ///
+    /// The hash must be stable across processes and compiler versions since the
+    /// resulting placement is persisted, which rules out `DefaultHasher`.
+    fn bucket(&self, bytes: &[u8]) -> usize {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in bytes {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        let index = (hash % self.shards.len() as u64) as usize;
+        index
+    }
+
+    /// Catalog key of the pointer to an item stored away from its home shard
+    fn pointer_key(prefix: &[u8], id: [u8; 16]) -> Vec<u8> {
+        let mut key = prefix.to_vec();
+        key.extend_from_slice(&id);
+        key
+    }
+
+    /// The shard holding a node or edge: the one named by the pointer on its
+    /// home shard, or the home shard itself
+    ///
+    /// Items that were never stored resolve to their home shard.
+    async fn resolve(&self, prefix: &[u8], id: [u8; 16]) -> Result<usize> {
+        let home = self.bucket(&id);
+        let pointer = self.shards[home]
+            .get_catalog_entry(&Self::pointer_key(prefix, id))
+            .await?;
+        Ok(pointer
+            .and_then(|value| ShardDirectory::decode_shard(&value))
+            .unwrap_or(home))
+    }
+
+    /// Point a node or edge's home shard at `shard`, or drop the pointer if
+    /// `shard` is the home shard
+    async fn set_pointer(&self, prefix: &[u8], id: [u8; 16], shard: usize) -> Result<()> {
+        let home = self.bucket(&id);
+        if shard == home {
+            return self.drop_pointer(prefix, id).await;
+        }
+        let value = ShardDirectory::encode_shard(shard).to_vec();
+        self.shards[home]
+            .stage_catalog(&[(Self::pointer_key(prefix, id), value)], &[])
+            .await
+    }
+
+    /// Drop the pointer a node or edge's home shard keeps, if any
+    async fn drop_pointer(&self, prefix: &[u8], id: [u8; 16]) -> Result<()> {
+        self.shards[self.bucket(&id)]
+            .stage_catalog(&[], &[Self::pointer_key(prefix, id)])
+            .await
+    }
+
+    async fn node_shard(&self, id: &NodeId) -> Result<usize> {
+        self.resolve(NODE_POINTER_PREFIX, id.to_bytes()).await
+    }
+
+    async fn edge_shard(&self, id: &EdgeId) -> Result<usize> {
+        self.resolve(EDGE_POINTER_PREFIX, id.to_bytes()).await
+    }
+
+    /// The shard holding a node, if it is stored
+    async fn locate_node(&self, id: &NodeId) -> Result<Option<usize>> {
+        let shard = self.node_shard(id).await?;
+        Ok(self.shards[shard]
+            .get_node(id)
+            .await?
+            .is_some()
+            .then_some(shard))
+    }
+
+    /// Decide which shard a new node belongs to
+    ///
+    /// Also returns the parent the node has to wait for: a response or tool
+    /// invocation whose parent is not stored yet, or is itself waiting.
+    async fn route_node(&self, node: &Node) -> Result<(usize, Option<NodeId>)> {
+        let shard = match node {
+            Node::Session(s) => self.shard_for_session(&s.id),
+            Node::Prompt(p) => self.shard_for_session(&p.session_id),
+            // Responses and tool invocations follow their parent so the session
+            // index on that shard can resolve them.
+            Node::Response(r) => return self.route_child(&r.prompt_id).await,
+            Node::ToolInvocation(t) => return self.route_child(&t.response_id).await,
+            Node::Custom(c) => match c.session_id {
+                Some(session_id) => self.shard_for_session(&session_id),
+                None => self.bucket(&node.id().to_bytes()),
+            },
+            Node::Agent(_) | Node::Template(_) => self.bucket(&node.id().to_bytes()),
+        };
+        Ok((shard, None))
+    }
+
+    /// Place a node next to its parent, waiting for it if it is not settled
+    async fn route_child(&self, parent: &NodeId) -> Result<(usize, Option<NodeId>)> {
+        match self.locate_node(parent).await? {
+            Some(shard) if !self.directory.is_waiting(parent)? => Ok((shard, None)),
+            Some(shard) => Ok((shard, Some(*parent))),
+            None => Ok((self.bucket(&parent.to_bytes()), Some(*parent))),
+        }
+    }
+
+    /// Move the nodes waiting for `parent` to its shard, and theirs after them
+    async fn adopt_waiting(&self, parent: &NodeId, shard: usize) -> Result<()> {
+        let mut parents = vec![*parent];
+        while let Some(parent) = parents.pop() {
+            for child in self.directory.children_of(&parent)? {
+                // A waiting node's placement never changes, so where it was
+                // placed is where its data is, even if a crash interrupted a move
+                if let Some(from) = self.directory.waiting_shard(&child)? {
+                    self.move_node(&child, from, shard).await?;
+                }
+                self.directory.remove_waiting(&child)?;
+                parents.push(child);
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a node, its embedding and its outgoing edges to another shard
+    ///
+    /// The data is copied before the pointers change and deleted after, with a
+    /// flush in between each step, so the move can be repeated if it is
+    /// interrupted.
+    async fn move_node(&self, id: &NodeId, from: usize, to: usize) -> Result<()> {
+        let Some(node) = self.shards[from].get_node(id).await? else {
+            // Moved already, or deleted
+            if self.shards[to].get_node(id).await?.is_some() {
+                self.set_pointer(NODE_POINTER_PREFIX, id.to_bytes(), to)
+                    .await?;
+            }
+            return Ok(());
+        };
+        if from == to {
+            // Store it again so the shard's session index finds its parent
+            return self.shards[to].store_node(&node).await;
+        }
+        let embedding = self.shards[from].get_embedding(id).await?;
+        let outgoing = self.shards[from].get_outgoing_edges(id).await?;
+        let incoming = self.shards[from].get_incoming_edges(id).await?;
+
+        self.shards[to].store_node(&node).await?;
+        if let Some(vector) = embedding {
+            self.shards[to].store_embedding(id, &vector).await?;
+        }
+        for edge in &outgoing {
+            self.shards[to].store_edge(edge).await?;
+        }
+        self.shards[to].flush().await?;
+
+        self.set_pointer(NODE_POINTER_PREFIX, id.to_bytes(), to)
+            .await?;
+        let encoded = ShardDirectory::encode_shard(to);
+        for edge in &outgoing {
+            self.set_pointer(EDGE_POINTER_PREFIX, edge.id.to_bytes(), to)
+                .await?;
+            let key = ShardDirectory::remote_key(&edge.to, &edge.id);
+            if self.locate_node(&edge.to).await? == Some(to) {
+                self.directory.remote_incoming.remove(key)?;
+            } else {
+                self.directory.remote_incoming.insert(key, &encoded)?;
+            }
+        }
+        // Edges into the node stay with their sources, which are now remote
+        for edge in incoming.iter().filter(|edge| edge.from != *id) {
+            self.directory.remote_incoming.insert(
+                ShardDirectory::remote_key(id, &edge.id),
+                &ShardDirectory::encode_shard(from),
+            )?;
+        }
+        self.flush().await?;
+
+        for edge in &outgoing {
+            self.shards[from].delete_edge(&edge.id).await?;
+        }
+        self.shards[from].delete_node(id).await
+    }
+}
+
+#[async_trait]
+impl AsyncStorageBackend for ShardedBackend {
+    async fn store_node(&self, node: &Node) -> Result<()> {
+        let id = node.id();
+        let home = self.bucket(&id.to_bytes());
+        // A stored node keeps its shard; only its parent arriving moves it
+        let (shard, waiting_for) = match self.node_shard(&id).await? {
+            shard if shard != home => (shard, None),
+            _ => self.route_node(node).await?,
+        };
+        if shard != home {
+            self.set_pointer(NODE_POINTER_PREFIX, id.to_bytes(), shard)
+                .await?;
+        }
+        if let Some(parent) = waiting_for {
+            self.directory.add_waiting(&parent, &id, shard)?;
+        }
+
+        self.shards[shard].store_node(node).await?;
+        if matches!(node, Node::Prompt(_) | Node::Response(_)) && !self.directory.is_waiting(&id)? {
+            self.adopt_waiting(&id, shard).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_node(&self, id: &NodeId) -> Result<Option<Node>> {
+        let shard = self.node_shard(id).await?;
+        self.shards[shard].get_node(id).await
+    }
+
+    async fn delete_node(&self, id: &NodeId) -> Result<()> {
+        let shard = self.node_shard(id).await?;
+        self.shards[shard].delete_node(id).await?;
+        if shard != self.bucket(&id.to_bytes()) {
+            self.drop_pointer(NODE_POINTER_PREFIX, id.to_bytes())
+                .await?;
+        }
+        for key in self
+            .directory
+            .remote_incoming
+            .scan_prefix(id.to_bytes())
+            .keys()
+        {
+            self.directory.remote_incoming.remove(key?)?;
+        }
+        self.directory.remove_waiting(id)?;
+        Ok(())
+    }
+
+    async fn store_edge(&self, edge: &Edge) -> Result<()> {
+        let shard = self.node_shard(&edge.from).await?;
+        if shard != self.bucket(&edge.id.to_bytes()) {
+            self.set_pointer(EDGE_POINTER_PREFIX, edge.id.to_bytes(), shard)
+                .await?;
+        }
+        if self.locate_node(&edge.to).await? != Some(shard) {
+            self.directory.remote_incoming.insert(
+                ShardDirectory::remote_key(&edge.to, &edge.id),
+                &ShardDirectory::encode_shard(shard),
+            )?;
+        }
+
+        self.shards[shard].store_edge(edge).await
+    }
+
+    async fn get_edge(&self, id: &EdgeId) -> Result<Option<Edge>> {
+        let shard = self.edge_shard(id).await?;
+        self.shards[shard].get_edge(id).await
+    }
+
+    async fn delete_edge(&self, id: &EdgeId) -> Result<()> {
+        let shard = self.edge_shard(id).await?;
+        if let Some(edge) = self.shards[shard].get_edge(id).await? {
+            self.shards[shard].delete_edge(id).await?;
+            self.directory
+                .remote_incoming
+                .remove(ShardDirectory::remote_key(&edge.to, id))?;
+        }
+        if shard != self.bucket(&id.to_bytes()) {
+            self.drop_pointer(EDGE_POINTER_PREFIX, id.to_bytes())
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn get_session_nodes(&self, session_id: &SessionId) -> Result<Vec<Node>> {
+        self.shards[self.shard_for_session(session_id)]
+            .get_session_nodes(session_id)
+            .await
+    }
+
+    async fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        // Edges are stored next to their source; unknown sources resolve to
+        // the same home shard `store_edge` would have picked.
+        let shard = self.node_shard(node_id).await?;
+        self.shards[shard].get_outgoing_edges(node_id).await
+    }
+
+    async fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        // Edges whose source is co-located with the target
+        let shard = self.node_shard(node_id).await?;
+        let mut edges = self.shards[shard].get_incoming_edges(node_id).await?;
+
+        // Edges stored on the source's shard, found through the directory
+        for entry in self
+            .directory
+            .remote_incoming
+            .scan_prefix(node_id.to_bytes())
+        {
+            let (key, value) = entry?;
+            let Some(shard) = ShardDirectory::decode_shard(&value) else {
+                continue;
+            };
+            let Some(edge_bytes) = ShardDirectory::second_id(&key) else {
+                continue;
+            };
+            let edge_id = EdgeId::from_bytes(edge_bytes);
+            if let Some(edge) = self.shards[shard].get_edge(&edge_id).await? {
+                if !edges.iter().any(|e| e.id == edge.id) {
+                    edges.push(edge);
+                }
+            }
+        }
+
+        Ok(edges)
+    }
+
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        let per_shard =
+            try_join_all(self.shards.iter().map(AsyncStorageBackend::scan_nodes)).await?;
+        Ok(per_shard.into_iter().flatten().collect())
+    }
+
+    async fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        // Scores use per-shard document frequencies; see the module docs
+        let per_shard_query = TextQuery {
+            limit: query.offset.saturating_add(query.limit),
+            offset: 0,
+            ..query.clone()
+        };
+        let per_shard = try_join_all(
+            self.shards
+                .iter()
+                .map(|shard| shard.search_text(&per_shard_query)),
+        )
+        .await?;
+        Ok(search::merge_hits(per_shard, query))
+    }
+
+    async fn store_embedding(&self, node_id: &NodeId, vector: &[f32]) -> Result<()> {
+        // Embeddings live on the node's shard so deleting the node drops them
+        match self.locate_node(node_id).await? {
+            Some(shard) => self.shards[shard].store_embedding(node_id, vector).await,
+            None => Err(Error::NodeNotFound(node_id.to_string())),
+        }
+    }
+
+    async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        let shard = self.node_shard(node_id).await?;
+        self.shards[shard].get_embedding(node_id).await
+    }
+
+    async fn delete_embedding(&self, node_id: &NodeId) -> Result<()> {
+        let shard = self.node_shard(node_id).await?;
+        self.shards[shard].delete_embedding(node_id).await
+    }
+
+    async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        let per_shard = try_join_all(
+            self.shards
+                .iter()
+                .map(|shard| shard.similar_nodes(vector, k, filter)),
+        )
+        .await?;
+        Ok(vector::merge_similar(per_shard, k))
+    }
+
+    async fn write_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.stage_catalog(puts, deletes).await?;
+        self.directory.db.flush_async().await?;
+        Ok(())
+    }
+
+    async fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for key in deletes {
+            batch.remove(key.as_slice());
+        }
+        for (key, value) in puts {
+            batch.insert(key.as_slice(), value.as_slice());
+        }
+        self.directory.catalog.apply_batch(batch)?;
+        Ok(())
+    }
+
+    async fn insert_catalog_entry(&self, key: &[u8], value: &[u8]) -> Result<bool> {
+        let inserted = self
+            .directory
+            .catalog
+            .compare_and_swap(key, None as Option<&[u8]>, Some(value))?
+            .is_ok();
+        if inserted {
+            self.directory.db.flush_async().await?;
+        }
+        Ok(inserted)
+    }
+
+    async fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.directory.catalog.get(key)?.map(|value| value.to_vec()))
+    }
+
+    async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.directory
+            .catalog
+            .scan_prefix(prefix)
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((key.to_vec(), value.to_vec()))
+            })
+            .collect()
+    }
+
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        // Every shard pages in the same order, so the first `limit + 1` rows
+        // overall are among the first `limit + 1` rows of some shard
+        let per_shard = try_join_all(
+            self.shards
+                .iter()
+                .map(|shard| shard.lookup_nodes(lookup, after, limit.saturating_add(1))),
+        )
+        .await?;
+        let nodes = per_shard.into_iter().flat_map(|page| page.items).collect();
+        Ok(lookup_index::page_of(nodes, after, limit))
+    }
+
+    async fn flush(&self) -> Result<()> {
+        try_join_all(self.shards.iter().map(AsyncStorageBackend::flush)).await?;
+        self.directory.db.flush_async().await?;
+        Ok(())
+    }
+
+    async fn stats(&self) -> Result<StorageStats> {
+        let per_shard = try_join_all(self.shards.iter().map(AsyncStorageBackend::stats)).await?;
+
+        // Sessions never span shards, so per-shard counts can simply be summed
+        let mut total = StorageStats {
+            node_count: 0,
+            edge_count: 0,
+            storage_bytes: self.directory.db.size_on_disk()?,
+            session_count: 0,
+        };
+        for stats in per_shard {
+            total.node_count += stats.node_count;
+            total.edge_count += stats.edge_count;
+            total.storage_bytes += stats.storage_bytes;
+            total.session_count += stats.session_count;
+        }
+        Ok(total)
+    }
+
+    async fn count_session_nodes(&self, session_id: &SessionId) -> Result<usize> {
+        self.shards[self.shard_for_session(session_id)]
+            .count_session_nodes(session_id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AgentNode, ConversationSession, EdgeType, PromptNode, ResponseNode, TokenUsage,
+        ToolInvocation,
+    };
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_manifest_created_and_enforced() {
+        let dir = tempdir().unwrap();
+        {
+            let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+            assert_eq!(backend.shard_count(), 4);
+            assert_eq!(backend.manifest().shard_count, 4);
+        }
+
+        let manifest = ShardManifest::load(dir.path()).unwrap().unwrap();
+        assert_eq!(manifest.shard_count, 4);
+
+        assert!(matches!(
+            ShardedBackend::open(dir.path(), 2).await,
+            Err(Error::ConfigError(_))
+        ));
+
+        let reopened = ShardedBackend::open_existing(dir.path()).await.unwrap();
+        assert_eq!(reopened.shard_count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_open_existing_requires_manifest() {
+        let dir = tempdir().unwrap();
+        assert!(matches!(
+            ShardedBackend::open_existing(dir.path()).await,
+            Err(Error::ConfigError(_))
+        ));
+        assert!(ShardedBackend::open(dir.path(), 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_session_nodes_are_colocated() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        let session = ConversationSession::new();
+        backend
+            .store_node(&Node::Session(session.clone()))
+            .await
+            .unwrap();
+
+        let prompt = PromptNode::new(session.id, "Hello".to_string());
+        backend
+            .store_node(&Node::Prompt(prompt.clone()))
+            .await
+            .unwrap();
+
+        let response = ResponseNode::new(prompt.id, "Hi".to_string(), TokenUsage::new(1, 1));
+        backend
+            .store_node(&Node::Response(response.clone()))
+            .await
+            .unwrap();
+
+        let expected = backend.shard_for_session(&session.id);
+        assert_eq!(backend.node_shard(&prompt.id).await.unwrap(), expected);
+        assert_eq!(backend.node_shard(&response.id).await.unwrap(), expected);
+
+        let nodes = backend.get_session_nodes(&session.id).await.unwrap();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(backend.count_session_nodes(&session.id).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_node_writes_skip_directory() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        let mut prompts = Vec::new();
+        for _ in 0..8 {
+            let session = ConversationSession::new();
+            backend
+                .store_node(&Node::Session(session.clone()))
+                .await
+                .unwrap();
+            let prompt = PromptNode::new(session.id, "Hello".to_string());
+            backend
+                .store_node(&Node::Prompt(prompt.clone()))
+                .await
+                .unwrap();
+            let response = ResponseNode::new(prompt.id, "Hi".to_string(), TokenUsage::new(1, 1));
+            backend
+                .store_node(&Node::Response(response.clone()))
+                .await
+                .unwrap();
+            let edge = Edge::new(response.id, prompt.id, EdgeType::RespondsTo);
+            backend.store_edge(&edge).await.unwrap();
+            prompts.push((prompt.id, response.id, edge.id));
+        }
+
+        // Nodes and co-located edges are found by hashing alone
+        assert!(backend.directory.remote_incoming.is_empty());
+        assert!(backend.directory.waiting_for.is_empty());
+        for (prompt, response, edge) in prompts {
+            assert!(backend.get_node(&prompt).await.unwrap().is_some());
+            assert!(backend.get_node(&response).await.unwrap().is_some());
+            assert!(backend.get_edge(&edge).await.unwrap().is_some());
+            assert_eq!(backend.get_incoming_edges(&prompt).await.unwrap().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_text_across_shards() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        for i in 0..8 {
+            let session = ConversationSession::new();
+            let prompt = PromptNode::new(session.id, format!("invoice number {i}"));
+            backend.store_node(&Node::Prompt(prompt)).await.unwrap();
+        }
+
+        let query = TextQuery::new("invoice").limit(5);
+        assert_eq!(backend.search_text(&query).await.unwrap().len(), 5);
+        assert_eq!(
+            backend.search_text(&query.offset(5)).await.unwrap().len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn test_similar_nodes_across_shards() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        let mut prompts = Vec::new();
+        for i in 0..8 {
+            let session = ConversationSession::new();
+            let prompt = PromptNode::new(session.id, format!("prompt {i}"));
+            backend
+                .store_node(&Node::Prompt(prompt.clone()))
+                .await
+                .unwrap();
+            backend
+                .store_embedding(&prompt.id, &[1.0, i as f32])
+                .await
+                .unwrap();
+            prompts.push(prompt.id);
+        }
+
+        let hits = backend
+            .similar_nodes(&[0.0, 1.0], 3, &VectorFilter::new())
+            .await
+            .unwrap();
+        let ids: Vec<NodeId> = hits.iter().map(|h| h.node_id).collect();
+        assert_eq!(ids, vec![prompts[7], prompts[6], prompts[5]]);
+    }
+
+    #[tokio::test]
+    async fn test_cross_shard_edges_and_stats() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        let mut prompts = Vec::new();
+        for _ in 0..16 {
+            let session = ConversationSession::new();
+            backend
+                .store_node(&Node::Session(session.clone()))
+                .await
+                .unwrap();
+            let prompt = PromptNode::new(session.id, "Question".to_string());
+            backend
+                .store_node(&Node::Prompt(prompt.clone()))
+                .await
+                .unwrap();
+            prompts.push(prompt);
+        }
+
+        let agent = AgentNode::new(
+            "Researcher".to_string(),
+            "research".to_string(),
+            vec!["search".to_string()],
+        );
+        backend
+            .store_node(&Node::Agent(agent.clone()))
+            .await
+            .unwrap();
+
+        for prompt in &prompts {
+            let edge = Edge::new(prompt.id, agent.node_id, EdgeType::HandledBy);
+            backend.store_edge(&edge).await.unwrap();
+        }
+
+        // Every prompt's edge is visible from the shared agent, wherever it lives
+        let incoming = backend.get_incoming_edges(&agent.node_id).await.unwrap();
+        assert_eq!(incoming.len(), prompts.len());
+
+        let outgoing = backend.get_outgoing_edges(&prompts[0].id).await.unwrap();
+        assert_eq!(outgoing.len(), 1);
+
+        let stats = backend.stats().await.unwrap();
+        assert_eq!(stats.node_count, 33);
+        assert_eq!(stats.edge_count, 16);
+        assert_eq!(stats.session_count, 16);
+
+        // Deleting an edge removes it from the directory as well
+        backend.delete_edge(&incoming[0].id).await.unwrap();
+        assert!(backend.get_edge(&incoming[0].id).await.unwrap().is_none());
+        let incoming = backend.get_incoming_edges(&agent.node_id).await.unwrap();
+        assert_eq!(incoming.len(), prompts.len() - 1);
+    }
+
+    #[tokio::test]
+    async fn test_delete_node_clears_directory() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        let agent = AgentNode::new(
+            "Researcher".to_string(),
+            "research".to_string(),
+            vec!["search".to_string()],
+        );
+        backend
+            .store_node(&Node::Agent(agent.clone()))
+            .await
+            .unwrap();
+        let agent_shard = backend.node_shard(&agent.node_id).await.unwrap();
+        // A prompt on another shard, so its edge to the agent is remote
+        let prompt = loop {
+            let prompt = PromptNode::new(SessionId::new(), "Question".to_string());
+            if backend.shard_for_session(&prompt.session_id) != agent_shard {
+                break prompt;
+            }
+        };
+        backend
+            .store_node(&Node::Prompt(prompt.clone()))
+            .await
+            .unwrap();
+        let edge = Edge::new(prompt.id, agent.node_id, EdgeType::HandledBy);
+        backend.store_edge(&edge).await.unwrap();
+        let remote = |id: &NodeId| backend.directory.remote_incoming.scan_prefix(id.to_bytes());
+        assert_eq!(remote(&agent.node_id).count(), 1);
+
+        backend.delete_node(&agent.node_id).await.unwrap();
+        assert!(backend.get_node(&agent.node_id).await.unwrap().is_none());
+        assert_eq!(remote(&agent.node_id).count(), 0);
+        assert!(backend
+            .get_incoming_edges(&agent.node_id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(backend.locate_node(&agent.node_id).await.unwrap(), None);
+
+        // Deleting a node stored away from its home shard drops its pointer
+        let key = ShardedBackend::pointer_key(NODE_POINTER_PREFIX, prompt.id.to_bytes());
+        let home = backend.bucket(&prompt.id.to_bytes());
+        let pointer = || backend.shards[home].get_catalog_entry(&key);
+        if home != backend.shard_for_session(&prompt.session_id) {
+            assert!(pointer().await.unwrap().is_some());
+        }
+        backend.delete_node(&prompt.id).await.unwrap();
+        assert!(pointer().await.unwrap().is_none());
+        assert!(backend.get_node(&prompt.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_nodes_stored_before_parent_join_its_session() {
+        let dir = tempdir().unwrap();
+        let backend = ShardedBackend::open(dir.path(), 4).await.unwrap();
+
+        let session = ConversationSession::new();
+        let session_shard = backend.shard_for_session(&session.id);
+        // A prompt whose children are first placed away from the session
+        let prompt = loop {
+            let prompt = PromptNode::new(session.id, "Hello".to_string());
+            if backend.bucket(&prompt.id.to_bytes()) != session_shard {
+                break prompt;
+            }
+        };
+
+        let response = ResponseNode::new(prompt.id, "Hi".to_string(), TokenUsage::new(1, 1));
+        backend
+            .store_node(&Node::Response(response.clone()))
+            .await
+            .unwrap();
+        backend
+            .store_embedding(&response.id, &[1.0, 0.0])
+            .await
+            .unwrap();
+        let responds = Edge::new(response.id, prompt.id, EdgeType::RespondsTo);
+        backend.store_edge(&responds).await.unwrap();
+        let tool = ToolInvocation::new(response.id, "search".to_string(), serde_json::json!({}));
+        backend
+            .store_node(&Node::ToolInvocation(tool.clone()))
+            .await
+            .unwrap();
+        assert_ne!(
+            backend.node_shard(&response.id).await.unwrap(),
+            session_shard
+        );
+
+        backend
+            .store_node(&Node::Session(session.clone()))
+            .await
+            .unwrap();
+        backend
+            .store_node(&Node::Prompt(prompt.clone()))
+            .await
+            .unwrap();
+
+        // Both followed the prompt, with the response's embedding and edge
+        for id in [response.id, tool.id] {
+            assert_eq!(backend.node_shard(&id).await.unwrap(), session_shard);
+            assert!(!backend.directory.is_waiting(&id).unwrap());
+        }
+        assert!(!backend.directory.has_waiting());
+        let nodes = backend.get_session_nodes(&session.id).await.unwrap();
+        assert!(nodes.iter().any(|n| n.id() == response.id));
+        assert_eq!(nodes.len(), 3);
+        assert!(backend.get_node(&tool.id).await.unwrap().is_some());
+        assert!(backend.get_embedding(&response.id).await.unwrap().is_some());
+        let outgoing = backend.get_outgoing_edges(&response.id).await.unwrap();
+        assert_eq!(outgoing.len(), 1);
+        let incoming = backend.get_incoming_edges(&prompt.id).await.unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(backend.stats().await.unwrap().node_count, 4);
+    }
+
+    #[tokio::test]
+    async fn test_data_survives_reopen() {
+        let dir = tempdir().unwrap();
+        let session = ConversationSession::new();
+        {
+            let backend = ShardedBackend::open(dir.path(), 3).await.unwrap();
+            backend
+                .store_node(&Node::Session(session.clone()))
+                .await
+                .unwrap();
+            backend.flush().await.unwrap();
+        }
+
+        let backend = ShardedBackend::open_existing(dir.path()).await.unwrap();
+        assert!(backend.get_node(&session.node_id).await.unwrap().is_some());
+    }
+}
@@ -1,8 +1,14 @@
 //! Sled-based storage backend implementation
 
+use super::lookup_index::{Lookup, LookupIndex};
+use super::text_index::TextIndex;
+use super::vector_index::VectorIndex;
 use super::{SerializationFormat, Serializer, StorageBackend, StorageStats};
+use crate::query::{Cursor, Page};
+use crate::search::{self, SearchHit, TextQuery};
+use crate::vector::{self, SimilarNode, VectorFilter};
 use crate::{Error, Result};
-use crate::{Edge, EdgeId, Node, NodeId, SessionId};
+use crate::{Edge, EdgeId, EdgeType, Node, NodeId, SessionId};
 use sled::{Db, Tree};
 use std::path::Path;
 
@@ -14,29 +20,59 @@ pub struct SledBackend {
     session_index: Tree,
     outgoing_edges_index: Tree,
     incoming_edges_index: Tree,
+    catalog: Tree,
+    text_index: TextIndex,
+    vector_index: VectorIndex,
+    lookup_index: LookupIndex,
     serializer: Serializer,
 }
 
 impl SledBackend {
     /// Open or create a new Sled backend at the specified path
+    ///
+    /// Returns [`Error::DatabaseLocked`] if another process already holds the
+    /// database open.
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let db = sled::open(path)?;
+        let db = sled::open(path).map_err(|e| match e {
+            // sled reports lock contention as a generic I/O error
+            sled::Error::Io(ref io) if io.to_string().contains("could not acquire lock") => {
+                Error::DatabaseLocked(io.to_string())
+            }
+            other => Error::from(other),
+        })?;
 
         let nodes = db.open_tree(b"nodes")?;
         let edges = db.open_tree(b"edges")?;
         let session_index = db.open_tree(b"session_index")?;
         let outgoing_edges_index = db.open_tree(b"outgoing_edges")?;
         let incoming_edges_index = db.open_tree(b"incoming_edges")?;
+        let catalog = db.open_tree(b"catalog")?;
+        let text_index = TextIndex::open(&db)?;
+        let vector_index = VectorIndex::open(&db)?;
+        let lookup_index = LookupIndex::open(&db)?;
 
-        Ok(Self {
+        let backend = Self {
             db,
             nodes,
             edges,
             session_index,
             outgoing_edges_index,
             incoming_edges_index,
+            catalog,
+            text_index,
+            vector_index,
+            lookup_index,
             serializer: Serializer::new(SerializationFormat::MessagePack),
-        })
+        };
+
+        if !backend.text_index.is_built()? {
+            backend.rebuild_text_index()?;
+        }
+        if !backend.lookup_index.is_built()? {
+            backend.rebuild_lookup_index()?;
+        }
+
+        Ok(backend)
     }
 
     /// Open with a custom serialization format
@@ -46,6 +82,84 @@ impl SledBackend {
         Ok(backend)
     }
 
+    /// Index every stored node for full-text search
+    ///
+    /// Runs once for databases created before the text index existed.
+    fn rebuild_text_index(&self) -> Result<()> {
+        for result in self.nodes.iter() {
+            let (_, bytes) = result?;
+            let node = self.serializer.deserialize_node(&bytes)?;
+            self.index_text(&node)?;
+        }
+        self.text_index.mark_built()
+    }
+
+    /// Add a node's text fields to the full-text index
+    fn index_text(&self, node: &Node) -> Result<()> {
+        let fields = search::indexed_fields(node);
+        if fields.is_empty() {
+            return Ok(());
+        }
+        self.text_index.index(
+            node.id(),
+            &fields,
+            node.node_type(),
+            self.owning_session(node)?,
+            search::node_timestamp(node),
+        )
+    }
+
+    /// List every stored node under its reverse lookups
+    ///
+    /// Runs once for databases created before the lookup index existed.
+    fn rebuild_lookup_index(&self) -> Result<()> {
+        for result in self.nodes.iter() {
+            let (_, bytes) = result?;
+            let node = self.serializer.deserialize_node(&bytes)?;
+            self.index_lookups(&node)?;
+        }
+        self.lookup_index.mark_built()
+    }
+
+    /// List a node under its field lookups and the agents handling it
+    fn index_lookups(&self, node: &Node) -> Result<()> {
+        let mut lookups = Lookup::of_node(node);
+        for edge in self.get_outgoing_edges(&node.id())? {
+            if edge.edge_type == EdgeType::HandledBy {
+                lookups.push(Lookup::Agent(edge.to));
+            }
+        }
+        self.lookup_index
+            .index(node.id(), search::node_timestamp(node), &lookups)
+    }
+
+    /// Re-list the source of a `HandledBy` edge after the edge changed
+    fn reindex_handled(&self, edge: &Edge) -> Result<()> {
+        if edge.edge_type != EdgeType::HandledBy {
+            return Ok(());
+        }
+        match self.get_node(&edge.from)? {
+            Some(node) => self.index_lookups(&node),
+            None => Ok(()),
+        }
+    }
+
+    /// Session a node belongs to, following parents for responses and tools
+    fn owning_session(&self, node: &Node) -> Result<Option<SessionId>> {
+        let parent = match node {
+            Node::Prompt(p) => return Ok(Some(p.session_id)),
+            Node::Session(s) => return Ok(Some(s.id)),
+            Node::Response(r) => r.prompt_id,
+            Node::ToolInvocation(t) => t.response_id,
+            Node::Custom(c) => return Ok(c.session_id),
+            Node::Agent(_) | Node::Template(_) => return Ok(None),
+        };
+        match self.get_node(&parent)? {
+            Some(parent) => self.owning_session(&parent),
+            None => Ok(None),
+        }
+    }
+
     /// Build a composite key for indexing
     fn build_index_key(prefix: &[u8], id: &[u8]) -> Vec<u8> {
         let mut key = Vec::with_capacity(prefix.len() + id.len());
@@ -94,8 +208,17 @@ impl StorageBackend for SledBackend {
                 // Templates are global entities, not tied to specific sessions
                 // They're accessed via template ID or Instantiates/Inherits edges
             }
+            Node::Custom(c) => {
+                if let Some(session_id) = c.session_id {
+                    let key = Self::build_index_key(&session_id.to_bytes(), &id.to_bytes());
+                    self.session_index.insert(key, &[])?;
+                }
+            }
         }
 
+        self.index_text(node)?;
+        self.index_lookups(node)?;
+
         self.db.flush()?;
         Ok(())
     }
@@ -112,6 +235,9 @@ impl StorageBackend for SledBackend {
 
     fn delete_node(&self, id: &NodeId) -> Result<()> {
         self.nodes.remove(id.to_bytes())?;
+        self.text_index.remove(id)?;
+        self.vector_index.remove(id)?;
+        self.lookup_index.remove(id)?;
         self.db.flush()?;
         Ok(())
     }
@@ -130,6 +256,8 @@ impl StorageBackend for SledBackend {
         let incoming_key = Self::build_index_key(&edge.to.to_bytes(), &edge.id.to_bytes());
         self.incoming_edges_index.insert(incoming_key, &[])?;
 
+        self.reindex_handled(edge)?;
+
         self.db.flush()?;
         Ok(())
     }
@@ -145,7 +273,10 @@ impl StorageBackend for SledBackend {
     }
 
     fn delete_edge(&self, id: &EdgeId) -> Result<()> {
-        self.edges.remove(id.to_bytes())?;
+        if let Some(bytes) = self.edges.remove(id.to_bytes())? {
+            let edge = self.serializer.deserialize_edge(&bytes)?;
+            self.reindex_handled(&edge)?;
+        }
         self.db.flush()?;
         Ok(())
     }
@@ -216,6 +347,141 @@ impl StorageBackend for SledBackend {
         Ok(edges)
     }
 
+    fn scan_nodes(&self) -> Result<Vec<Node>> {
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        for result in self.nodes.iter() {
+            let (_, bytes) = result?;
+            nodes.push(self.serializer.deserialize_node(&bytes)?);
+        }
+        Ok(nodes)
+    }
+
+    fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        let clauses = search::parse_search_query(&query.query)?;
+        let mut hits = Vec::new();
+        for doc in self
+            .text_index
+            .search(&clauses, query)?
+            .into_iter()
+            .skip(query.offset)
+        {
+            let Some(node) = self.get_node(&doc.node_id)? else {
+                continue;
+            };
+            hits.push(SearchHit {
+                node_id: doc.node_id,
+                node_type: doc.entry.node_type,
+                session_id: doc.entry.session_id,
+                timestamp: doc.entry.timestamp,
+                score: doc.score,
+                snippet: search::build_snippet(&search::indexed_fields(&node), &clauses),
+            });
+        }
+        Ok(hits)
+    }
+
+    fn store_embedding(&self, node_id: &NodeId, vector: &[f32]) -> Result<()> {
+        let node = self
+            .get_node(node_id)?
+            .ok_or_else(|| Error::NodeNotFound(node_id.to_string()))?;
+        let node_type = node.node_type();
+        if !vector::supports_embedding(&node_type) {
+            return Err(Error::ValidationError(format!(
+                "{:?} nodes cannot carry embeddings",
+                node_type
+            )));
+        }
+
+        self.vector_index
+            .insert(*node_id, vector, node_type, self.owning_session(&node)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.vector_index.get(node_id)
+    }
+
+    fn delete_embedding(&self, node_id: &NodeId) -> Result<()> {
+        self.vector_index.remove(node_id)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        self.vector_index.search(vector, k, filter)
+    }
+
+    fn write_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.stage_catalog(puts, deletes)?;
+        self.db.flush()?;
+        Ok(())
+    }
+
+    fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for key in deletes {
+            batch.remove(key.as_slice());
+        }
+        for (key, value) in puts {
+            batch.insert(key.as_slice(), value.as_slice());
+        }
+        self.catalog.apply_batch(batch)?;
+        Ok(())
+    }
+
+    fn insert_catalog_entry(&self, key: &[u8], value: &[u8]) -> Result<bool> {
+        let inserted = self
+            .catalog
+            .compare_and_swap(key, None as Option<&[u8]>, Some(value))?
+            .is_ok();
+        if inserted {
+            self.db.flush()?;
+        }
+        Ok(inserted)
+    }
+
+    fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.catalog.get(key)?.map(|value| value.to_vec()))
+    }
+
+    fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.catalog
+            .scan_prefix(prefix)
+            .map(|entry| {
+                let (key, value) = entry?;
+                Ok((key.to_vec(), value.to_vec()))
+            })
+            .collect()
+    }
+
+    fn lookup_nodes(&self, lookup: &Lookup, after: Option<&Cursor>, limit: usize) -> Result<Page> {
+        let mut items = Vec::new();
+        let mut has_more = false;
+        for node_id in self.lookup_index.scan(lookup, after) {
+            let Some(node) = self.get_node(&node_id?)? else {
+                continue;
+            };
+            if items.len() == limit {
+                has_more = true;
+                break;
+            }
+            items.push(node);
+        }
+
+        let next_cursor = if has_more {
+            items.last().map(Cursor::at)
+        } else {
+            None
+        };
+        Ok(Page { items, next_cursor })
+    }
+
     fn flush(&self) -> Result<()> {
         self.db.flush()?;
         Ok(())
@@ -321,6 +587,228 @@ mod tests {
         assert_eq!(incoming.len(), 1);
     }
 
+    #[test]
+    fn test_scan_nodes() {
+        let dir = tempdir().unwrap();
+        let backend = SledBackend::open(dir.path()).unwrap();
+
+        let session = ConversationSession::new();
+        backend.store_node(&Node::Session(session.clone())).unwrap();
+        let prompt = PromptNode::new(session.id, "Test".to_string());
+        backend.store_node(&Node::Prompt(prompt)).unwrap();
+
+        let nodes = backend.scan_nodes().unwrap();
+        assert_eq!(nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_search_text_follows_writes() {
+        let dir = tempdir().unwrap();
+        let backend = SledBackend::open(dir.path()).unwrap();
+
+        let session = ConversationSession::new();
+        let mut prompt = PromptNode::new(session.id, "reconcile the ledger".to_string());
+        backend.store_node(&Node::Prompt(prompt.clone())).unwrap();
+
+        let query = TextQuery::new("ledger");
+        assert_eq!(backend.search_text(&query).unwrap().len(), 1);
+
+        // Overwriting a node replaces its indexed text
+        prompt.content = "summarize the meeting".to_string();
+        backend.store_node(&Node::Prompt(prompt.clone())).unwrap();
+        assert!(backend.search_text(&query).unwrap().is_empty());
+        assert_eq!(
+            backend.search_text(&TextQuery::new("meeting")).unwrap()[0].session_id,
+            Some(session.id)
+        );
+
+        backend.delete_node(&prompt.id).unwrap();
+        assert!(backend
+            .search_text(&TextQuery::new("meeting"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_text_index_rebuilt_for_existing_data() {
+        let dir = tempdir().unwrap();
+        let session = ConversationSession::new();
+        {
+            let backend = SledBackend::open(dir.path()).unwrap();
+            let prompt = PromptNode::new(session.id, "legacy prompt".to_string());
+            backend.store_node(&Node::Prompt(prompt)).unwrap();
+            // Simulate a database written before the text index existed
+            for tree in ["text_postings", "text_docs", "text_meta"] {
+                backend.db.drop_tree(tree).unwrap();
+            }
+        }
+
+        let backend = SledBackend::open(dir.path()).unwrap();
+        assert_eq!(
+            backend
+                .search_text(&TextQuery::new("legacy"))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_embeddings_follow_node_lifecycle() {
+        let dir = tempdir().unwrap();
+        let backend = SledBackend::open(dir.path()).unwrap();
+
+        let session = ConversationSession::new();
+        let prompt = PromptNode::new(session.id, "hello".to_string());
+        backend.store_node(&Node::Session(session.clone())).unwrap();
+        backend.store_node(&Node::Prompt(prompt.clone())).unwrap();
+
+        assert!(matches!(
+            backend.store_embedding(&NodeId::new(), &[1.0]),
+            Err(Error::NodeNotFound(_))
+        ));
+        assert!(matches!(
+            backend.store_embedding(&session.node_id, &[1.0]),
+            Err(Error::ValidationError(_))
+        ));
+
+        backend.store_embedding(&prompt.id, &[0.5, 0.5]).unwrap();
+        let hits = backend
+            .similar_nodes(&[1.0, 1.0], 3, &VectorFilter::new())
+            .unwrap();
+        assert_eq!(hits[0].node_id, prompt.id);
+        assert_eq!(hits[0].session_id, Some(session.id));
+
+        // Deleting the node drops its embedding
+        backend.delete_node(&prompt.id).unwrap();
+        assert!(backend.get_embedding(&prompt.id).unwrap().is_none());
+        assert!(backend
+            .similar_nodes(&[1.0, 1.0], 3, &VectorFilter::new())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_lookups_follow_writes() {
+        let dir = tempdir().unwrap();
+        let backend = SledBackend::open(dir.path()).unwrap();
+
+        let session = ConversationSession::new();
+        let template_id = crate::TemplateId::new();
+        let prompts: Vec<PromptNode> = (0..3)
+            .map(|i| {
+                PromptNode::from_template(
+                    session.id,
+                    template_id,
+                    format!("prompt {i}"),
+                    std::collections::HashMap::new(),
+                )
+            })
+            .collect();
+        for prompt in &prompts {
+            backend.store_node(&Node::Prompt(prompt.clone())).unwrap();
+        }
+
+        let lookup = Lookup::Template(template_id);
+        let first = backend.lookup_nodes(&lookup, None, 2).unwrap();
+        assert_eq!(first.items.len(), 2);
+        let rest = backend
+            .lookup_nodes(&lookup, first.next_cursor.as_ref(), 2)
+            .unwrap();
+        assert_eq!(rest.items.len(), 1);
+        assert!(rest.next_cursor.is_none());
+
+        // Agent lookups follow HandledBy edges in both directions of their life
+        let agent = NodeId::new();
+        let edge = Edge::new(prompts[0].id, agent, EdgeType::HandledBy);
+        backend.store_edge(&edge).unwrap();
+        let handled = backend
+            .lookup_nodes(&Lookup::Agent(agent), None, 10)
+            .unwrap();
+        assert_eq!(handled.items[0].id(), prompts[0].id);
+        backend.delete_edge(&edge.id).unwrap();
+        assert!(backend
+            .lookup_nodes(&Lookup::Agent(agent), None, 10)
+            .unwrap()
+            .items
+            .is_empty());
+
+        backend.delete_node(&prompts[1].id).unwrap();
+        assert_eq!(
+            backend.lookup_nodes(&lookup, None, 10).unwrap().items.len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_lookup_index_rebuilt_for_existing_data() {
+        let dir = tempdir().unwrap();
+        let response_id = NodeId::new();
+        {
+            let backend = SledBackend::open(dir.path()).unwrap();
+            let tool = crate::ToolInvocation::new(
+                response_id,
+                "calculator".to_string(),
+                serde_json::json!({}),
+            );
+            backend.store_node(&Node::ToolInvocation(tool)).unwrap();
+            // Simulate a database written before the lookup index existed
+            for tree in ["lookup_entries", "lookup_docs", "lookup_meta"] {
+                backend.db.drop_tree(tree).unwrap();
+            }
+        }
+
+        let backend = SledBackend::open(dir.path()).unwrap();
+        let page = backend
+            .lookup_nodes(&Lookup::ToolName("calculator".to_string()), None, 10)
+            .unwrap();
+        assert_eq!(page.items.len(), 1);
+    }
+
+    #[test]
+    fn test_catalog_entries() {
+        let dir = tempdir().unwrap();
+        let backend = SledBackend::open(dir.path()).unwrap();
+
+        let entry = |key: &str, value: &str| (key.as_bytes().to_vec(), value.as_bytes().to_vec());
+        backend
+            .write_catalog(
+                &[
+                    entry("view/a", "1"),
+                    entry("view/b", "2"),
+                    entry("query/a", "3"),
+                ],
+                &[],
+            )
+            .unwrap();
+        // Staged writes are readable before they are flushed
+        backend
+            .stage_catalog(&[entry("view/c", "4")], &[b"view/a".to_vec()])
+            .unwrap();
+
+        let views = backend.scan_catalog(b"view/").unwrap();
+        assert_eq!(views, vec![entry("view/b", "2"), entry("view/c", "4")]);
+        assert_eq!(
+            backend.get_catalog_entry(b"query/a").unwrap(),
+            Some(b"3".to_vec())
+        );
+        assert_eq!(backend.get_catalog_entry(b"view/a").unwrap(), None);
+
+        // Inserting never replaces an existing entry
+        assert!(!backend.insert_catalog_entry(b"query/a", b"5").unwrap());
+        assert!(backend.insert_catalog_entry(b"query/b", b"6").unwrap());
+        assert_eq!(
+            backend.get_catalog_entry(b"query/a").unwrap(),
+            Some(b"3".to_vec())
+        );
+        assert_eq!(
+            backend.get_catalog_entry(b"query/b").unwrap(),
+            Some(b"6".to_vec())
+        );
+        // The catalog is not part of the graph
+        assert_eq!(backend.stats().unwrap().node_count, 0);
+    }
+
     #[test]
     fn test_stats() {
         let dir = tempdir().unwrap();
@@ -0,0 +1,395 @@
+//! Persistent inverted index backing full-text search
+//!
+//! The index lives in three sled trees next to the graph data:
+//!
+//! - `text_postings`: `term 0x00 node_id` -> word positions of `term` in the node
+//! - `text_docs`: `node_id` -> document attributes used for filtering and scoring
+//! - `text_meta`: corpus statistics (document count and total length)
+//!
+//! Terms never contain a zero byte, so a prefix scan over `term 0x00` yields
+//! exactly the postings of one term, and a scan over a bare prefix yields the
+//! postings of every term starting with it.
+
+use crate::search::{tokenize, Clause, TextQuery};
+use crate::{Error, Result};
+use crate::{NodeId, NodeType, SessionId};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sled::{Db, Tree};
+use std::collections::{BTreeMap, HashMap};
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 length normalization
+const B: f64 = 0.75;
+/// Position gap between fields so phrases cannot span two fields
+const FIELD_GAP: u32 = 16;
+/// Upper bound on how many distinct terms a prefix may expand to
+const MAX_PREFIX_EXPANSION: usize = 256;
+
+const VERSION_KEY: &[u8] = b"version";
+const DOC_COUNT_KEY: &[u8] = b"doc_count";
+const TOTAL_LENGTH_KEY: &[u8] = b"total_length";
+const INDEX_VERSION: u64 = 1;
+
+/// Attributes of an indexed node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DocEntry {
+    pub node_type: NodeType,
+    pub session_id: Option<SessionId>,
+    pub timestamp: DateTime<Utc>,
+    /// Number of indexed words
+    pub length: u32,
+    /// Distinct terms, so postings can be removed without the original text
+    pub terms: Vec<String>,
+}
+
+/// A node that matched every clause, with its BM25 score
+#[derive(Debug, Clone)]
+pub(crate) struct ScoredDoc {
+    pub node_id: NodeId,
+    pub score: f64,
+    pub entry: DocEntry,
+}
+
+/// Inverted index stored in the graph database
+pub(crate) struct TextIndex {
+    postings: Tree,
+    docs: Tree,
+    meta: Tree,
+}
+
+/// Per-clause matches: node -> (idf, term frequency) contributions
+type ClauseMatches = HashMap<NodeId, Vec<(f64, u32)>>;
+
+impl TextIndex {
+    /// Open (or create) the index trees in `db`
+    pub(crate) fn open(db: &Db) -> Result<Self> {
+        Ok(Self {
+            postings: db.open_tree(b"text_postings")?,
+            docs: db.open_tree(b"text_docs")?,
+            meta: db.open_tree(b"text_meta")?,
+        })
+    }
+
+    /// Whether the index has been populated for this database
+    ///
+    /// Databases created before full-text search existed have nodes but no
+    /// index; the backend rebuilds it once on open.
+    pub(crate) fn is_built(&self) -> Result<bool> {
+        Ok(self.meta.get(VERSION_KEY)?.is_some())
+    }
+
+    /// Record that the index covers every stored node
+    pub(crate) fn mark_built(&self) -> Result<()> {
+        self.meta
+            .insert(VERSION_KEY, &INDEX_VERSION.to_be_bytes())?;
+        Ok(())
+    }
+
+    /// Index (or re-index) the text fields of a node
+    pub(crate) fn index(
+        &self,
+        node_id: NodeId,
+        fields: &[&str],
+        node_type: NodeType,
+        session_id: Option<SessionId>,
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        self.remove(&node_id)?;
+
+        let mut positions: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        let mut position = 0u32;
+        for field in fields {
+            for token in tokenize(field) {
+                positions.entry(token.term).or_default().push(position);
+                position += 1;
+            }
+            position += FIELD_GAP;
+        }
+        let length: u32 = positions.values().map(|p| p.len() as u32).sum();
+        if length == 0 {
+            return Ok(());
+        }
+
+        for (term, term_positions) in &positions {
+            self.postings.insert(
+                posting_key(term, &node_id),
+                rmp_serde::to_vec(term_positions)?,
+            )?;
+        }
+
+        let entry = DocEntry {
+            node_type,
+            session_id,
+            timestamp,
+            length,
+            terms: positions.into_keys().collect(),
+        };
+        self.docs
+            .insert(node_id.to_bytes(), rmp_serde::to_vec(&entry)?)?;
+        self.adjust(DOC_COUNT_KEY, 1)?;
+        self.adjust(TOTAL_LENGTH_KEY, i64::from(length))?;
+        Ok(())
+    }
+
+    /// Remove a node from the index, if present
+    pub(crate) fn remove(&self, node_id: &NodeId) -> Result<()> {
+        let Some(bytes) = self.docs.remove(node_id.to_bytes())? else {
+            return Ok(());
+        };
+        let entry: DocEntry = rmp_serde::from_slice(&bytes)?;
+        for term in &entry.terms {
+            self.postings.remove(posting_key(term, node_id))?;
+        }
+        self.adjust(DOC_COUNT_KEY, -1)?;
+        self.adjust(TOTAL_LENGTH_KEY, -i64::from(entry.length))?;
+        Ok(())
+    }
+
+    /// Find nodes matching every clause and the query's filters, best first
+    ///
+    /// Returns at most `query.offset + query.limit` documents so callers can
+    /// page or merge results.
+    pub(crate) fn search(&self, clauses: &[Clause], query: &TextQuery) -> Result<Vec<ScoredDoc>> {
+        let doc_count = self.counter(DOC_COUNT_KEY)?.max(1);
+        let avg_length = self.counter(TOTAL_LENGTH_KEY)? as f64 / doc_count as f64;
+
+        let mut matched: Option<ClauseMatches> = None;
+        for clause in clauses {
+            let clause_matches = self.match_clause(clause, doc_count)?;
+            matched = Some(match matched {
+                None => clause_matches,
+                Some(mut acc) => {
+                    acc.retain(|id, _| clause_matches.contains_key(id));
+                    for (id, contributions) in &mut acc {
+                        contributions.extend_from_slice(&clause_matches[id]);
+                    }
+                    acc
+                }
+            });
+            if matched.as_ref().is_some_and(HashMap::is_empty) {
+                break;
+            }
+        }
+
+        let mut scored = Vec::new();
+        for (node_id, contributions) in matched.unwrap_or_default() {
+            let Some(bytes) = self.docs.get(node_id.to_bytes())? else {
+                continue;
+            };
+            let entry: DocEntry = rmp_serde::from_slice(&bytes)?;
+            if !query.accepts(&entry.node_type, entry.session_id, entry.timestamp) {
+                continue;
+            }
+            let norm = K1 * (1.0 - B + B * f64::from(entry.length) / avg_length.max(1.0));
+            let score = contributions
+                .iter()
+                .map(|(idf, tf)| {
+                    let tf = f64::from(*tf);
+                    idf * tf * (K1 + 1.0) / (tf + norm)
+                })
+                .sum();
+            scored.push(ScoredDoc {
+                node_id,
+                score,
+                entry,
+            });
+        }
+
+        scored.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.entry.timestamp.cmp(&a.entry.timestamp))
+        });
+        scored.truncate(query.offset.saturating_add(query.limit));
+        Ok(scored)
+    }
+
+    fn match_clause(&self, clause: &Clause, doc_count: u64) -> Result<ClauseMatches> {
+        let mut matches: ClauseMatches = HashMap::new();
+        match clause {
+            Clause::Term(term) => {
+                let postings = self.term_postings(term)?;
+                let idf = idf(doc_count, postings.len());
+                for (id, positions) in postings {
+                    matches.insert(id, vec![(idf, positions.len() as u32)]);
+                }
+            }
+            Clause::Prefix(prefix) => {
+                let mut by_term: BTreeMap<String, Vec<(NodeId, u32)>> = BTreeMap::new();
+                for item in self.postings.scan_prefix(prefix.as_bytes()) {
+                    let (key, value) = item?;
+                    let (term, id) = split_posting_key(&key)?;
+                    if !by_term.contains_key(term) && by_term.len() >= MAX_PREFIX_EXPANSION {
+                        break;
+                    }
+                    let positions: Vec<u32> = rmp_serde::from_slice(&value)?;
+                    by_term
+                        .entry(term.to_string())
+                        .or_default()
+                        .push((id, positions.len() as u32));
+                }
+                for postings in by_term.into_values() {
+                    let idf = idf(doc_count, postings.len());
+                    for (id, tf) in postings {
+                        matches.entry(id).or_default().push((idf, tf));
+                    }
+                }
+            }
+            Clause::Phrase(terms) => {
+                let lists = terms
+                    .iter()
+                    .map(|term| self.term_postings(term))
+                    .collect::<Result<Vec<_>>>()?;
+                let idfs: Vec<f64> = lists.iter().map(|l| idf(doc_count, l.len())).collect();
+
+                let Some((first, rest)) = lists.split_first() else {
+                    return Ok(matches);
+                };
+                for (id, starts) in first {
+                    let Some(others) = rest
+                        .iter()
+                        .map(|list| list.get(id))
+                        .collect::<Option<Vec<_>>>()
+                    else {
+                        continue;
+                    };
+                    let occurrences = starts
+                        .iter()
+                        .filter(|&&start| {
+                            others.iter().enumerate().all(|(i, positions)| {
+                                positions.binary_search(&(start + i as u32 + 1)).is_ok()
+                            })
+                        })
+                        .count() as u32;
+                    if occurrences > 0 {
+                        matches.insert(*id, idfs.iter().map(|idf| (*idf, occurrences)).collect());
+                    }
+                }
+            }
+        }
+        Ok(matches)
+    }
+
+    fn term_postings(&self, term: &str) -> Result<HashMap<NodeId, Vec<u32>>> {
+        let mut prefix = term.as_bytes().to_vec();
+        prefix.push(0);
+        let mut postings = HashMap::new();
+        for item in self.postings.scan_prefix(prefix) {
+            let (key, value) = item?;
+            let (_, id) = split_posting_key(&key)?;
+            postings.insert(id, rmp_serde::from_slice(&value)?);
+        }
+        Ok(postings)
+    }
+
+    fn counter(&self, key: &[u8]) -> Result<u64> {
+        Ok(self
+            .meta
+            .get(key)?
+            .and_then(|v| v.as_ref().try_into().ok())
+            .map_or(0, u64::from_be_bytes))
+    }
+
+    fn adjust(&self, key: &[u8], delta: i64) -> Result<()> {
+        self.meta.update_and_fetch(key, |old| {
+            let current = old
+                .and_then(|v| v.try_into().ok())
+                .map_or(0, u64::from_be_bytes);
+            Some(current.saturating_add_signed(delta).to_be_bytes().to_vec())
+        })?;
+        Ok(())
+    }
+}
+
+/// Inverse document frequency, never negative
+fn idf(doc_count: u64, doc_freq: usize) -> f64 {
+    let n = doc_count as f64;
+    let df = doc_freq as f64;
+    (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+}
+
+fn posting_key(term: &str, node_id: &NodeId) -> Vec<u8> {
+    let mut key = Vec::with_capacity(term.len() + 17);
+    key.extend_from_slice(term.as_bytes());
+    key.push(0);
+    key.extend_from_slice(&node_id.to_bytes());
+    key
+}
+
+fn split_posting_key(key: &[u8]) -> Result<(&str, NodeId)> {
+    let invalid = || Error::Storage("Invalid key in text index".to_string());
+    let split = key.len().checked_sub(17).ok_or_else(invalid)?;
+    if key[split] != 0 {
+        return Err(invalid());
+    }
+    let term = std::str::from_utf8(&key[..split]).map_err(|_| invalid())?;
+    let id: [u8; 16] = key[split + 1..].try_into().map_err(|_| invalid())?;
+    Ok((term, NodeId::from_bytes(id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::parse_search_query;
+    use tempfile::tempdir;
+
+    fn add(index: &TextIndex, text: &str) -> NodeId {
+        let id = NodeId::new();
+        index
+            .index(id, &[text], NodeType::Prompt, None, Utc::now())
+            .unwrap();
+        id
+    }
+
+    fn search(index: &TextIndex, query: &str) -> Vec<NodeId> {
+        let clauses = parse_search_query(query).unwrap();
+        index
+            .search(&clauses, &TextQuery::new(query))
+            .unwrap()
+            .into_iter()
+            .map(|d| d.node_id)
+            .collect()
+    }
+
+    #[test]
+    fn test_bm25_ranking_and_phrases() {
+        let dir = tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let index = TextIndex::open(&db).unwrap();
+
+        let focused = add(&index, "invoice reconciliation for invoice batch");
+        let passing = add(
+            &index,
+            "the reconciliation of an invoice was mentioned once among many other words here",
+        );
+        let unrelated = add(&index, "weather report");
+
+        assert_eq!(search(&index, "invoice"), vec![focused, passing]);
+        assert_eq!(search(&index, "\"invoice reconciliation\""), vec![focused]);
+        assert_eq!(search(&index, "reconcil*").len(), 2);
+        assert_eq!(search(&index, "weather invoice"), Vec::<NodeId>::new());
+        assert_eq!(search(&index, "report"), vec![unrelated]);
+    }
+
+    #[test]
+    fn test_reindex_and_remove() {
+        let dir = tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let index = TextIndex::open(&db).unwrap();
+
+        let id = add(&index, "first draft");
+        index
+            .index(id, &["second draft"], NodeType::Prompt, None, Utc::now())
+            .unwrap();
+        assert!(search(&index, "first").is_empty());
+        assert_eq!(search(&index, "second"), vec![id]);
+        assert_eq!(index.counter(DOC_COUNT_KEY).unwrap(), 1);
+
+        index.remove(&id).unwrap();
+        assert!(search(&index, "draft").is_empty());
+        assert_eq!(index.counter(DOC_COUNT_KEY).unwrap(), 0);
+        assert_eq!(index.counter(TOTAL_LENGTH_KEY).unwrap(), 0);
+    }
+}
@@ -0,0 +1,375 @@
+//! Persistent nearest-neighbour index over node embeddings
+//!
+//! The index lives in three sled trees next to the graph data:
+//!
+//! - `embeddings`: `node_id` -> the vector and the node attributes used for filtering
+//! - `vector_graph`: `node_id` -> the node's HNSW links on every layer
+//! - `vector_meta`: index version and entry point
+//!
+//! The graph is held in memory and every link change is written through, so
+//! opening a database only reloads the stored links. The graph is rebuilt from
+//! `embeddings` only when the two trees disagree, e.g. after a crash between
+//! writes.
+
+use crate::vector::{self, Hnsw, SimilarNode, SimilarityMetric, VectorFilter};
+use crate::{Error, Result};
+use crate::{NodeId, NodeType, SessionId};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use sled::{Db, Tree};
+use std::collections::HashMap;
+
+/// Minimum candidate list size when searching
+const EF_SEARCH: usize = 64;
+
+const VERSION_KEY: &[u8] = b"version";
+const ENTRY_KEY: &[u8] = b"entry";
+const INDEX_VERSION: u64 = 1;
+
+/// A stored embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EmbeddingEntry {
+    vector: Vec<f32>,
+    node_type: NodeType,
+    session_id: Option<SessionId>,
+}
+
+/// Attributes kept in memory for filtering
+#[derive(Debug, Clone)]
+struct Attributes {
+    node_type: NodeType,
+    session_id: Option<SessionId>,
+}
+
+/// In-memory graph plus filter attributes
+#[derive(Default)]
+struct State {
+    graph: Hnsw,
+    attributes: HashMap<NodeId, Attributes>,
+}
+
+/// Embedding store and HNSW index in the graph database
+pub(crate) struct VectorIndex {
+    embeddings: Tree,
+    links: Tree,
+    meta: Tree,
+    state: RwLock<State>,
+}
+
+impl VectorIndex {
+    /// Open (or create) the index trees in `db` and load the graph
+    pub(crate) fn open(db: &Db) -> Result<Self> {
+        let index = Self {
+            embeddings: db.open_tree(b"embeddings")?,
+            links: db.open_tree(b"vector_graph")?,
+            meta: db.open_tree(b"vector_meta")?,
+            state: RwLock::new(State::default()),
+        };
+        if !index.load()? {
+            index.rebuild()?;
+        }
+        Ok(index)
+    }
+
+    /// Attach (or replace) a node's embedding
+    pub(crate) fn insert(
+        &self,
+        node_id: NodeId,
+        vector: &[f32],
+        node_type: NodeType,
+        session_id: Option<SessionId>,
+    ) -> Result<()> {
+        vector::validate_vector(vector)?;
+
+        let mut state = self.state.write();
+        if let Some(dimension) = state.graph.dimension() {
+            let replacing_only = state.graph.len() == 1 && state.graph.node(&node_id).is_some();
+            if dimension != vector.len() && !replacing_only {
+                return Err(dimension_mismatch(dimension, vector.len()));
+            }
+        }
+
+        let entry = EmbeddingEntry {
+            vector: vector.to_vec(),
+            node_type: node_type.clone(),
+            session_id,
+        };
+        self.embeddings
+            .insert(node_id.to_bytes(), rmp_serde::to_vec(&entry)?)?;
+
+        let changed = state.graph.insert(node_id, vector);
+        self.persist(&state.graph, &changed)?;
+        state.attributes.insert(
+            node_id,
+            Attributes {
+                node_type,
+                session_id,
+            },
+        );
+        Ok(())
+    }
+
+    /// A node's embedding, if it has one
+    pub(crate) fn get(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        match self.embeddings.get(node_id.to_bytes())? {
+            Some(bytes) => {
+                let entry: EmbeddingEntry = rmp_serde::from_slice(&bytes)?;
+                Ok(Some(entry.vector))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Remove a node's embedding, if present
+    pub(crate) fn remove(&self, node_id: &NodeId) -> Result<()> {
+        if self.embeddings.remove(node_id.to_bytes())?.is_none() {
+            return Ok(());
+        }
+
+        let mut state = self.state.write();
+        let changed = state.graph.remove(node_id);
+        self.links.remove(node_id.to_bytes())?;
+        self.persist(&state.graph, &changed)?;
+        state.attributes.remove(node_id);
+        Ok(())
+    }
+
+    /// The `k` nodes most similar to `vector` that pass `filter`, best first
+    pub(crate) fn search(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        vector::validate_vector(vector)?;
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+
+        let state = self.state.read();
+        match state.graph.dimension() {
+            Some(dimension) if dimension != vector.len() => {
+                return Err(dimension_mismatch(dimension, vector.len()));
+            }
+            None => return Ok(Vec::new()),
+            Some(_) => {}
+        }
+
+        let query_norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+        // Dot-product results are re-ranked from the cosine neighbourhood, so
+        // start from a wider candidate list
+        let mut ef = match filter.metric {
+            SimilarityMetric::Cosine => EF_SEARCH.max(k),
+            SimilarityMetric::DotProduct => EF_SEARCH.max(4 * k),
+        };
+
+        // Widen the search until enough candidates survive the filters
+        loop {
+            let mut hits: Vec<SimilarNode> = state
+                .graph
+                .search(vector, ef)
+                .into_iter()
+                .filter_map(|(node_id, cosine)| {
+                    let attributes = state.attributes.get(&node_id)?;
+                    if !filter.accepts(&attributes.node_type, attributes.session_id) {
+                        return None;
+                    }
+                    let score = match filter.metric {
+                        SimilarityMetric::Cosine => cosine,
+                        SimilarityMetric::DotProduct => {
+                            cosine * query_norm * state.graph.node(&node_id)?.norm
+                        }
+                    };
+                    if filter.min_score.is_some_and(|min| score < min) {
+                        return None;
+                    }
+                    Some(SimilarNode {
+                        node_id,
+                        node_type: attributes.node_type.clone(),
+                        session_id: attributes.session_id,
+                        score,
+                    })
+                })
+                .collect();
+
+            if hits.len() >= k || ef >= state.graph.len() {
+                hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+                hits.truncate(k);
+                return Ok(hits);
+            }
+            ef = ef.saturating_mul(4);
+        }
+    }
+
+    /// Load the stored graph, returning `false` if it needs rebuilding
+    fn load(&self) -> Result<bool> {
+        if self.meta.get(VERSION_KEY)?.is_none() || self.links.len() != self.embeddings.len() {
+            return Ok(false);
+        }
+
+        let mut state = State::default();
+        for result in self.embeddings.iter() {
+            let (key, bytes) = result?;
+            let node_id = decode_node_id(&key)?;
+            let Some(link_bytes) = self.links.get(&key)? else {
+                return Ok(false);
+            };
+            let entry: EmbeddingEntry = rmp_serde::from_slice(&bytes)?;
+            let links = decode_links(&rmp_serde::from_slice::<Vec<Vec<[u8; 16]>>>(&link_bytes)?);
+            state.graph.restore(node_id, &entry.vector, links);
+            state.attributes.insert(
+                node_id,
+                Attributes {
+                    node_type: entry.node_type,
+                    session_id: entry.session_id,
+                },
+            );
+        }
+
+        let entry = self
+            .meta
+            .get(ENTRY_KEY)?
+            .map(|bytes| decode_node_id(&bytes))
+            .transpose()?;
+        state.graph.set_entry(entry);
+
+        *self.state.write() = state;
+        Ok(true)
+    }
+
+    /// Rebuild the graph from the stored embeddings
+    fn rebuild(&self) -> Result<()> {
+        self.links.clear()?;
+
+        let mut state = State::default();
+        for result in self.embeddings.iter() {
+            let (key, bytes) = result?;
+            let node_id = decode_node_id(&key)?;
+            let entry: EmbeddingEntry = rmp_serde::from_slice(&bytes)?;
+            state.graph.insert(node_id, &entry.vector);
+            state.attributes.insert(
+                node_id,
+                Attributes {
+                    node_type: entry.node_type,
+                    session_id: entry.session_id,
+                },
+            );
+        }
+
+        let all: Vec<NodeId> = state.attributes.keys().copied().collect();
+        self.persist(&state.graph, &all)?;
+        self.meta
+            .insert(VERSION_KEY, &INDEX_VERSION.to_be_bytes())?;
+
+        *self.state.write() = state;
+        Ok(())
+    }
+
+    /// Write the links of `changed` nodes and the entry point
+    fn persist(&self, graph: &Hnsw, changed: &[NodeId]) -> Result<()> {
+        for node_id in changed {
+            if let Some(node) = graph.node(node_id) {
+                let links: Vec<Vec<[u8; 16]>> = node
+                    .links
+                    .iter()
+                    .map(|layer| layer.iter().map(NodeId::to_bytes).collect())
+                    .collect();
+                self.links
+                    .insert(node_id.to_bytes(), rmp_serde::to_vec(&links)?)?;
+            }
+        }
+        match graph.entry() {
+            Some(entry) => self.meta.insert(ENTRY_KEY, &entry.to_bytes())?,
+            None => self.meta.remove(ENTRY_KEY)?,
+        };
+        Ok(())
+    }
+}
+
+fn decode_node_id(bytes: &[u8]) -> Result<NodeId> {
+    let bytes: [u8; 16] = bytes
+        .try_into()
+        .map_err(|_| Error::Storage("Invalid node ID in vector index".to_string()))?;
+    Ok(NodeId::from_bytes(bytes))
+}
+
+fn decode_links(layers: &[Vec<[u8; 16]>]) -> Vec<Vec<NodeId>> {
+    layers
+        .iter()
+        .map(|layer| layer.iter().copied().map(NodeId::from_bytes).collect())
+        .collect()
+}
+
+fn dimension_mismatch(expected: usize, actual: usize) -> Error {
+    Error::ValidationError(format!(
+        "Embedding has dimension {actual}, but this graph stores {expected}-dimensional vectors"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_index_persists_across_open() {
+        let dir = tempdir().unwrap();
+        let a = NodeId::new();
+        let b = NodeId::new();
+        {
+            let db = sled::open(dir.path()).unwrap();
+            let index = VectorIndex::open(&db).unwrap();
+            index
+                .insert(a, &[1.0, 0.0], NodeType::Prompt, None)
+                .unwrap();
+            index
+                .insert(b, &[0.0, 1.0], NodeType::Response, None)
+                .unwrap();
+            db.flush().unwrap();
+        }
+
+        let db = sled::open(dir.path()).unwrap();
+        let index = VectorIndex::open(&db).unwrap();
+        assert_eq!(index.get(&a).unwrap(), Some(vec![1.0, 0.0]));
+
+        let hits = index.search(&[0.1, 0.9], 1, &VectorFilter::new()).unwrap();
+        assert_eq!(hits[0].node_id, b);
+
+        let filter = VectorFilter::new().node_type(NodeType::Prompt);
+        let hits = index.search(&[0.1, 0.9], 5, &filter).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].node_id, a);
+    }
+
+    #[test]
+    fn test_dimension_and_metrics() {
+        let dir = tempdir().unwrap();
+        let db = sled::open(dir.path()).unwrap();
+        let index = VectorIndex::open(&db).unwrap();
+
+        let short = NodeId::new();
+        let long = NodeId::new();
+        index
+            .insert(short, &[1.0, 0.0], NodeType::Prompt, None)
+            .unwrap();
+        index
+            .insert(long, &[3.0, 3.0], NodeType::Prompt, None)
+            .unwrap();
+        assert!(matches!(
+            index.insert(NodeId::new(), &[1.0, 0.0, 0.0], NodeType::Prompt, None),
+            Err(Error::ValidationError(_))
+        ));
+
+        let cosine = index.search(&[1.0, 0.1], 1, &VectorFilter::new()).unwrap();
+        assert_eq!(cosine[0].node_id, short);
+
+        let filter = VectorFilter::new().metric(SimilarityMetric::DotProduct);
+        let dot = index.search(&[1.0, 0.1], 1, &filter).unwrap();
+        assert_eq!(dot[0].node_id, long);
+        assert!((dot[0].score - 3.3).abs() < 1e-4);
+
+        index.remove(&long).unwrap();
+        assert!(index.get(&long).unwrap().is_none());
+        assert_eq!(index.search(&[1.0, 0.1], 5, &filter).unwrap().len(), 1);
+    }
+}
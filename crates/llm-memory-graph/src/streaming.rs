@@ -0,0 +1,714 @@
+//! Incremental capture of streamed responses
+//!
+//! [`AsyncMemoryGraph::add_response`] records a response once it is
+//! complete. When a model streams its output, [`AsyncMemoryGraph::begin_response`]
+//! instead stores the [`ResponseNode`] up front, with empty content and the
+//! finish reason [`STREAMING`], and returns a [`ResponseStream`]. Each
+//! [`ResponseStream::append_chunk`] writes the chunk, its token usage and
+//! its arrival time, and [`ResponseStream::finish`] fills in the node's
+//! content, usage, latency and finish reason.
+//!
+//! A stream that is never finished, because the process crashed or the
+//! handle was dropped, is recovered the next time the graph is opened: its
+//! chunks become the response content and the finish reason is set to
+//! [`INTERRUPTED`].
+//!
+//! # Durability
+//!
+//! The start of a stream is flushed to disk before
+//! [`AsyncMemoryGraph::begin_response`] returns, and its end before
+//! [`ResponseStream::finish`] or [`ResponseStream::interrupt`] returns.
+//! By default each chunk is also flushed before
+//! [`ResponseStream::append_chunk`] returns, so a crash loses no appended
+//! chunk. Streams that produce many small chunks can opt into batching with
+//! [`ResponseStream::flush_interval`]: chunks are then written without waiting
+//! for the disk, and a crash loses the chunks appended since the last flush,
+//! with the response recovered from the chunks before them.
+//!
+//! Chunks are kept after the stream ends, so time-to-first-token and
+//! inter-chunk timings remain available through
+//! [`AsyncMemoryGraph::response_chunks`]. Finished responses also record the
+//! time to first token, in milliseconds, as the `time_to_first_token_ms`
+//! custom metadata entry.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::{Config, TokenUsage};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let session = graph.create_session().await?;
+//! let prompt = graph.add_prompt(session.id, "Tell me a story".to_string(), None).await?;
+//!
+//! let mut stream = graph.begin_response(prompt).await?;
+//! for token in ["Once", " upon", " a time"] {
+//!     stream.append_chunk(token, TokenUsage::new(0, 1)).await?;
+//! }
+//! let response_id = stream.finish("stop", TokenUsage::new(12, 3)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::engine::AsyncMemoryGraph;
+use crate::storage::AsyncStorageBackend;
+use crate::{
+    Edge, EdgeType, Error, Node, NodeId, ResponseMetadata, ResponseNode, Result, TokenUsage,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// Finish reason of a response whose stream is still open
+pub const STREAMING: &str = "streaming";
+
+/// Finish reason of a response whose stream ended without being finished
+pub const INTERRUPTED: &str = "interrupted";
+
+/// Custom metadata key holding the time to first token, in milliseconds
+pub const TIME_TO_FIRST_TOKEN_KEY: &str = "time_to_first_token_ms";
+
+const OPEN_PREFIX: &str = "stream_open/";
+const CHUNK_PREFIX: &str = "stream_chunk/";
+
+fn open_key(response_id: &NodeId) -> Vec<u8> {
+    format!("{OPEN_PREFIX}{response_id}").into_bytes()
+}
+
+fn chunk_prefix(response_id: &NodeId) -> Vec<u8> {
+    format!("{CHUNK_PREFIX}{response_id}/").into_bytes()
+}
+
+fn chunk_key(response_id: &NodeId, index: u32) -> Vec<u8> {
+    format!("{CHUNK_PREFIX}{response_id}/{index:010}").into_bytes()
+}
+
+/// One persisted piece of a streamed response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamChunk {
+    /// Position of the chunk in the stream, from 0
+    pub index: u32,
+    /// Text of the chunk
+    pub text: String,
+    /// Tokens the chunk accounts for
+    pub usage_delta: TokenUsage,
+    /// When the chunk was appended
+    pub received_at: DateTime<Utc>,
+}
+
+/// Time from the start of a stream to its first chunk
+#[must_use]
+pub fn time_to_first_token(
+    started_at: DateTime<Utc>,
+    chunks: &[StreamChunk],
+) -> Option<chrono::Duration> {
+    chunks.first().map(|chunk| chunk.received_at - started_at)
+}
+
+fn add_usage(total: TokenUsage, delta: TokenUsage) -> TokenUsage {
+    let prompt_tokens = total.prompt_tokens.saturating_add(delta.prompt_tokens);
+    let completion_tokens = total
+        .completion_tokens
+        .saturating_add(delta.completion_tokens);
+    TokenUsage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens.saturating_add(completion_tokens),
+    }
+}
+
+/// How often [`ResponseStream::append_chunk`] flushes chunks to disk
+///
+/// Chunks are flushed once `chunks` of them are waiting, or when a chunk is
+/// appended `elapsed` or more after the last flush, whichever comes first.
+/// The timer is only checked on append, so a stalled stream holds its
+/// unflushed chunks until the next chunk or the end of the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushInterval {
+    /// Unflushed chunks that trigger a flush; 0 and 1 flush every chunk
+    pub chunks: u32,
+    /// Time since the last flush after which the next chunk is flushed
+    pub elapsed: Duration,
+}
+
+impl FlushInterval {
+    /// Flush every chunk before [`ResponseStream::append_chunk`] returns
+    pub const EVERY_CHUNK: Self = Self::new(1, Duration::ZERO);
+
+    /// Flush after `chunks` chunks or `elapsed`, whichever comes first
+    #[must_use]
+    pub const fn new(chunks: u32, elapsed: Duration) -> Self {
+        Self { chunks, elapsed }
+    }
+
+    /// Whether `pending` chunks, the oldest flushed at `since`, should be flushed
+    fn due(&self, pending: u32, since: Instant) -> bool {
+        pending >= self.chunks || since.elapsed() >= self.elapsed
+    }
+}
+
+impl Default for FlushInterval {
+    /// [`Self::EVERY_CHUNK`]
+    fn default() -> Self {
+        Self::EVERY_CHUNK
+    }
+}
+
+/// A response being streamed, returned by [`AsyncMemoryGraph::begin_response`]
+///
+/// Dropping the handle without calling [`finish`](Self::finish) or
+/// [`interrupt`](Self::interrupt) leaves the stream open until the graph is
+/// next opened, when it is recovered as interrupted.
+pub struct ResponseStream<'a> {
+    graph: &'a AsyncMemoryGraph,
+    response: ResponseNode,
+    next_index: u32,
+    first_chunk_at: Option<DateTime<Utc>>,
+    writer: ChunkWriter,
+}
+
+/// Writes chunks to the catalog and flushes them as often as the interval says
+struct ChunkWriter {
+    flush_interval: FlushInterval,
+    /// Chunks written since the last flush
+    unflushed: u32,
+    last_flush: Instant,
+}
+
+impl ChunkWriter {
+    fn new(flush_interval: FlushInterval) -> Self {
+        Self {
+            flush_interval,
+            unflushed: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    async fn write(
+        &mut self,
+        storage: &dyn AsyncStorageBackend,
+        response_id: &NodeId,
+        chunk: &StreamChunk,
+    ) -> Result<()> {
+        let value =
+            serde_json::to_vec(chunk).map_err(|e| Error::SerializationError(e.to_string()))?;
+        storage
+            .stage_catalog(&[(chunk_key(response_id, chunk.index), value)], &[])
+            .await?;
+        self.unflushed += 1;
+        if self.flush_interval.due(self.unflushed, self.last_flush) {
+            storage.flush().await?;
+            self.unflushed = 0;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+impl ResponseStream<'_> {
+    /// ID of the response node
+    #[must_use]
+    pub fn response_id(&self) -> NodeId {
+        self.response.id
+    }
+
+    /// Text received so far
+    #[must_use]
+    pub fn content(&self) -> &str {
+        &self.response.content
+    }
+
+    /// Sum of the usage deltas received so far
+    #[must_use]
+    pub fn usage(&self) -> TokenUsage {
+        self.response.usage
+    }
+
+    /// Flush appended chunks to disk as often as `interval` says
+    ///
+    /// Defaults to [`FlushInterval::EVERY_CHUNK`]. A longer interval saves
+    /// flushes, but a crash loses the chunks appended since the last one.
+    #[must_use]
+    pub fn flush_interval(mut self, interval: FlushInterval) -> Self {
+        self.writer.flush_interval = interval;
+        self
+    }
+
+    /// Persist the next chunk of the response
+    ///
+    /// The chunk is readable through [`AsyncMemoryGraph::response_chunks`]
+    /// at once, and flushed to disk according to the stream's
+    /// [`FlushInterval`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn append_chunk(&mut self, text: &str, usage_delta: TokenUsage) -> Result<()> {
+        let chunk = StreamChunk {
+            index: self.next_index,
+            text: text.to_string(),
+            usage_delta,
+            received_at: Utc::now(),
+        };
+        self.writer
+            .write(self.graph.storage(), &self.response.id, &chunk)
+            .await?;
+
+        self.next_index += 1;
+        self.first_chunk_at.get_or_insert(chunk.received_at);
+        self.response.content.push_str(text);
+        self.response.usage = add_usage(self.response.usage, usage_delta);
+        Ok(())
+    }
+
+    /// Complete the response with the reason generation stopped and the
+    /// final token usage, which replaces the sum of the deltas
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn finish(
+        self,
+        finish_reason: impl Into<String>,
+        usage: TokenUsage,
+    ) -> Result<NodeId> {
+        let mut response = self.response;
+        response.usage = usage;
+        complete(
+            self.graph,
+            response,
+            finish_reason.into(),
+            self.first_chunk_at,
+            Utc::now(),
+        )
+        .await
+    }
+
+    /// End the stream early, marking the response as [`INTERRUPTED`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn interrupt(self) -> Result<NodeId> {
+        complete(
+            self.graph,
+            self.response,
+            INTERRUPTED.to_string(),
+            self.first_chunk_at,
+            Utc::now(),
+        )
+        .await
+    }
+}
+
+/// Store the final state of a streamed response and close its stream
+async fn complete(
+    graph: &AsyncMemoryGraph,
+    mut response: ResponseNode,
+    finish_reason: String,
+    first_chunk_at: Option<DateTime<Utc>>,
+    ended_at: DateTime<Utc>,
+) -> Result<NodeId> {
+    let started_at = response.timestamp;
+    response.metadata.finish_reason = finish_reason;
+    response.metadata.latency_ms = millis(ended_at - started_at);
+    if let Some(first) = first_chunk_at {
+        response.metadata.custom.insert(
+            TIME_TO_FIRST_TOKEN_KEY.to_string(),
+            millis(first - started_at).to_string(),
+        );
+    }
+
+    let storage = graph.storage();
+    storage
+        .store_node(&Node::Response(response.clone()))
+        .await?;
+    storage
+        .write_catalog(&[], &[open_key(&response.id)])
+        .await?;
+    storage.flush().await?;
+    graph.response_stored(&response).await;
+    Ok(response.id)
+}
+
+fn millis(duration: chrono::Duration) -> u64 {
+    u64::try_from(duration.num_milliseconds()).unwrap_or(0)
+}
+
+impl AsyncMemoryGraph {
+    /// Start recording a streamed response to a prompt
+    ///
+    /// The response node is stored immediately, linked to the prompt, with
+    /// empty content and the finish reason [`STREAMING`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prompt does not exist, the edge to it is
+    /// rejected, or storage fails.
+    pub async fn begin_response(&self, prompt_id: NodeId) -> Result<ResponseStream<'_>> {
+        self.begin_response_with_metadata(prompt_id, ResponseMetadata::default())
+            .await
+    }
+
+    /// Start recording a streamed response with the given metadata
+    ///
+    /// The finish reason and latency in `metadata` are overwritten when the
+    /// stream ends. The `RespondsTo` edge is checked like one added with
+    /// [`Self::add_edge`]; if it is rejected, nothing is kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the prompt does not exist, the edge to it is
+    /// rejected, or storage fails.
+    pub async fn begin_response_with_metadata(
+        &self,
+        prompt_id: NodeId,
+        mut metadata: ResponseMetadata,
+    ) -> Result<ResponseStream<'_>> {
+        match self.get_node(&prompt_id).await? {
+            Some(Node::Prompt(_)) => {}
+            Some(_) => {
+                return Err(Error::ValidationError(format!(
+                    "Node {prompt_id} is not a prompt"
+                )))
+            }
+            None => return Err(Error::NodeNotFound(prompt_id.to_string())),
+        }
+
+        metadata.finish_reason = STREAMING.to_string();
+        let response =
+            ResponseNode::with_metadata(prompt_id, String::new(), TokenUsage::new(0, 0), metadata);
+
+        // The open marker goes first, so a crash part way leaves nothing
+        // that recovery cannot find
+        let storage = self.storage();
+        storage
+            .write_catalog(&[(open_key(&response.id), Vec::new())], &[])
+            .await?;
+        storage
+            .store_node(&Node::Response(response.clone()))
+            .await?;
+        // The edge rules need both ends stored
+        let edge = Edge::new(response.id, prompt_id, EdgeType::RespondsTo);
+        if let Err(e) = self.check_edges(std::slice::from_ref(&edge)).await {
+            storage.delete_node(&response.id).await?;
+            storage
+                .write_catalog(&[], &[open_key(&response.id)])
+                .await?;
+            return Err(e);
+        }
+        storage.store_edge(&edge).await?;
+        storage.flush().await?;
+
+        Ok(ResponseStream {
+            graph: self,
+            response,
+            next_index: 0,
+            first_chunk_at: None,
+            writer: ChunkWriter::new(FlushInterval::default()),
+        })
+    }
+
+    /// The persisted chunks of a streamed response, in order
+    ///
+    /// Responses recorded with [`Self::add_response`] have none.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails or a chunk cannot be decoded.
+    pub async fn response_chunks(&self, response_id: &NodeId) -> Result<Vec<StreamChunk>> {
+        self.storage()
+            .scan_catalog(&chunk_prefix(response_id))
+            .await?
+            .into_iter()
+            .map(|(_, value)| {
+                serde_json::from_slice(&value).map_err(|e| Error::SerializationError(e.to_string()))
+            })
+            .collect()
+    }
+
+    /// Close every stream left open, marking its response as [`INTERRUPTED`]
+    ///
+    /// Each response gets the content and summed usage of the chunks that
+    /// were persisted. This runs when the graph is opened, before any stream
+    /// can be started; returns the IDs of the recovered responses.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub(crate) async fn recover_interrupted_responses(&self) -> Result<Vec<NodeId>> {
+        let storage = self.storage();
+        let mut recovered = Vec::new();
+        for (key, _) in storage.scan_catalog(OPEN_PREFIX.as_bytes()).await? {
+            let id = std::str::from_utf8(&key[OPEN_PREFIX.len()..])
+                .ok()
+                .and_then(|id| uuid::Uuid::parse_str(id).ok())
+                .map(NodeId::from);
+            let node = match id {
+                Some(id) => storage.get_node(&id).await?,
+                None => None,
+            };
+            let Some(Node::Response(mut response)) = node else {
+                // The stream never got its node; nothing to recover
+                storage.write_catalog(&[], &[key]).await?;
+                continue;
+            };
+
+            let chunks = self.response_chunks(&response.id).await?;
+            response.content = chunks.iter().map(|c| c.text.as_str()).collect();
+            response.usage = chunks.iter().fold(TokenUsage::new(0, 0), |total, c| {
+                add_usage(total, c.usage_delta)
+            });
+            let ended_at = chunks
+                .last()
+                .map_or(response.timestamp, |chunk| chunk.received_at);
+            let first_chunk_at = chunks.first().map(|chunk| chunk.received_at);
+
+            tracing::warn!("Recovering interrupted response stream {}", response.id);
+            recovered.push(
+                complete(
+                    self,
+                    response,
+                    INTERRUPTED.to_string(),
+                    first_chunk_at,
+                    ended_at,
+                )
+                .await?,
+            );
+        }
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{AsyncSledBackend, StorageStats};
+    use crate::{Config, EdgeId, SessionId};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::tempdir;
+
+    /// Counts flushes and flushing catalog writes on the way to sled
+    struct CountingBackend {
+        inner: AsyncSledBackend,
+        flushes: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AsyncStorageBackend for CountingBackend {
+        async fn store_node(&self, node: &Node) -> Result<()> {
+            self.inner.store_node(node).await
+        }
+
+        async fn get_node(&self, id: &NodeId) -> Result<Option<Node>> {
+            self.inner.get_node(id).await
+        }
+
+        async fn delete_node(&self, id: &NodeId) -> Result<()> {
+            self.inner.delete_node(id).await
+        }
+
+        async fn store_edge(&self, edge: &Edge) -> Result<()> {
+            self.inner.store_edge(edge).await
+        }
+
+        async fn get_edge(&self, id: &EdgeId) -> Result<Option<Edge>> {
+            self.inner.get_edge(id).await
+        }
+
+        async fn delete_edge(&self, id: &EdgeId) -> Result<()> {
+            self.inner.delete_edge(id).await
+        }
+
+        async fn get_session_nodes(&self, session_id: &SessionId) -> Result<Vec<Node>> {
+            self.inner.get_session_nodes(session_id).await
+        }
+
+        async fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+            self.inner.get_outgoing_edges(node_id).await
+        }
+
+        async fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+            self.inner.get_incoming_edges(node_id).await
+        }
+
+        async fn write_catalog(
+            &self,
+            puts: &[(Vec<u8>, Vec<u8>)],
+            deletes: &[Vec<u8>],
+        ) -> Result<()> {
+            self.flushes.fetch_add(1, Ordering::SeqCst);
+            self.inner.write_catalog(puts, deletes).await
+        }
+
+        async fn stage_catalog(
+            &self,
+            puts: &[(Vec<u8>, Vec<u8>)],
+            deletes: &[Vec<u8>],
+        ) -> Result<()> {
+            self.inner.stage_catalog(puts, deletes).await
+        }
+
+        async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+            self.inner.scan_catalog(prefix).await
+        }
+
+        async fn flush(&self) -> Result<()> {
+            self.flushes.fetch_add(1, Ordering::SeqCst);
+            self.inner.flush().await
+        }
+
+        async fn stats(&self) -> Result<StorageStats> {
+            self.inner.stats().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_finish() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "Count to two".to_string(), None)
+            .await
+            .unwrap();
+
+        let mut stream = graph.begin_response(prompt).await.unwrap();
+        let response_id = stream.response_id();
+        let Some(Node::Response(open)) = graph.get_node(&response_id).await.unwrap() else {
+            panic!("response not stored");
+        };
+        assert_eq!(open.metadata.finish_reason, STREAMING);
+
+        stream
+            .append_chunk("One,", TokenUsage::new(0, 2))
+            .await
+            .unwrap();
+        stream
+            .append_chunk(" two", TokenUsage::new(0, 1))
+            .await
+            .unwrap();
+        assert_eq!(stream.content(), "One, two");
+        assert_eq!(stream.usage().completion_tokens, 3);
+        stream.finish("stop", TokenUsage::new(4, 3)).await.unwrap();
+
+        let Some(Node::Response(done)) = graph.get_node(&response_id).await.unwrap() else {
+            panic!("response missing");
+        };
+        assert_eq!(done.content, "One, two");
+        assert_eq!(done.usage.total_tokens, 7);
+        assert_eq!(done.metadata.finish_reason, "stop");
+        assert!(done.metadata.custom.contains_key(TIME_TO_FIRST_TOKEN_KEY));
+
+        let chunks = graph.response_chunks(&response_id).await.unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks[0].received_at <= chunks[1].received_at);
+        assert!(time_to_first_token(done.timestamp, &chunks).is_some());
+
+        let responses = graph.get_incoming_edges(&prompt).await.unwrap();
+        assert_eq!(responses.len(), 1);
+        assert!(graph
+            .recover_interrupted_responses()
+            .await
+            .unwrap()
+            .is_empty());
+
+        let err = graph.begin_response(response_id).await;
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_flush_interval_and_usage_sum() {
+        let interval = FlushInterval::new(3, Duration::from_secs(30));
+        let now = Instant::now();
+        assert!(!interval.due(2, now));
+        assert!(interval.due(3, now));
+        assert!(FlushInterval::new(100, Duration::ZERO).due(1, now));
+        assert!(FlushInterval::EVERY_CHUNK.due(1, now));
+        assert_eq!(FlushInterval::default(), FlushInterval::EVERY_CHUNK);
+
+        let total = add_usage(TokenUsage::new(u32::MAX - 10, 5), TokenUsage::new(20, 1));
+        assert_eq!(total.prompt_tokens, u32::MAX);
+        assert_eq!(total.completion_tokens, 6);
+        assert_eq!(total.total_tokens, u32::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_unfinished_stream_recovered_on_open() {
+        let dir = tempdir().unwrap();
+        let response_id = {
+            let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+                .await
+                .unwrap();
+            let session = graph.create_session().await.unwrap();
+            let prompt = graph
+                .add_prompt(session.id, "Write a poem".to_string(), None)
+                .await
+                .unwrap();
+            let mut stream = graph.begin_response(prompt).await.unwrap();
+            stream
+                .append_chunk("Roses are", TokenUsage::new(5, 2))
+                .await
+                .unwrap();
+            stream.response_id()
+        };
+
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let Some(Node::Response(response)) = graph.get_node(&response_id).await.unwrap() else {
+            panic!("response missing");
+        };
+        assert_eq!(response.content, "Roses are");
+        assert_eq!(response.usage.total_tokens, 7);
+        assert_eq!(response.metadata.finish_reason, INTERRUPTED);
+        assert!(graph
+            .recover_interrupted_responses()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_chunks_flushed_per_interval() {
+        let dir = tempdir().unwrap();
+        let storage = CountingBackend {
+            inner: AsyncSledBackend::open(dir.path()).await.unwrap(),
+            flushes: AtomicUsize::new(0),
+        };
+        let response_id = NodeId::new();
+        let mut writer = ChunkWriter::new(FlushInterval::new(3, Duration::from_secs(30)));
+        for index in 0..7 {
+            let chunk = StreamChunk {
+                index,
+                text: format!("chunk {index}"),
+                usage_delta: TokenUsage::new(0, 1),
+                received_at: Utc::now(),
+            };
+            writer.write(&storage, &response_id, &chunk).await.unwrap();
+        }
+
+        // Every chunk is readable, but only each third one was flushed
+        let chunks = storage
+            .scan_catalog(&chunk_prefix(&response_id))
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 7);
+        assert_eq!(storage.flushes.load(Ordering::SeqCst), 2);
+        assert_eq!(writer.unflushed, 1);
+
+        writer.flush_interval = FlushInterval::EVERY_CHUNK;
+        let chunk = StreamChunk {
+            index: 7,
+            text: "last".to_string(),
+            usage_delta: TokenUsage::new(0, 1),
+            received_at: Utc::now(),
+        };
+        writer.write(&storage, &response_id, &chunk).await.unwrap();
+        assert_eq!(storage.flushes.load(Ordering::SeqCst), 3);
+    }
+}
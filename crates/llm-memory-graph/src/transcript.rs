@@ -0,0 +1,874 @@
+//! Chat transcripts rebuilt from stored sessions
+//!
+//! A [`Transcript`] turns the prompts, responses and tool invocations of a
+//! session back into the message list an LLM API expects, for replay or
+//! fine-tuning. Prompts become user turns and responses assistant turns, each
+//! prompt followed by its responses. Tool invocations recorded against a
+//! response become tool calls on that assistant turn, followed by their
+//! results.
+//!
+//! Transcripts render as OpenAI chat-completions messages, Anthropic messages
+//! or a neutral role/content form (see [`TranscriptFormat`]). Only the neutral
+//! form carries template provenance and node IDs; provider formats keep to
+//! the fields their APIs accept, so agent names appear as the OpenAI `name`
+//! field and are dropped for Anthropic, which has no equivalent.
+//!
+//! Attachments become [`ContentPart`]s on their message. Provider formats
+//! render the parts of user turns in the block types their APIs take for
+//! input (images, documents and, for OpenAI, audio) and drop the rest,
+//! including everything attached to assistant turns; the neutral form keeps
+//! every part.
+
+use crate::engine::AsyncMemoryGraph;
+use crate::search::node_timestamp;
+use crate::{Attachment, Modality, ResponseNode, ToolInvocation};
+use crate::{EdgeType, Error, Node, NodeId, PromptNode, Result, SessionId, TemplateId};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Message format of a rendered transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TranscriptFormat {
+    /// OpenAI chat-completions messages
+    OpenAi,
+    /// Anthropic messages
+    Anthropic,
+    /// Provider-neutral role/content messages with provenance
+    #[default]
+    Neutral,
+}
+
+impl fmt::Display for TranscriptFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+            Self::Neutral => "neutral",
+        })
+    }
+}
+
+impl FromStr for TranscriptFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "openai" => Ok(Self::OpenAi),
+            "anthropic" => Ok(Self::Anthropic),
+            "neutral" => Ok(Self::Neutral),
+            _ => Err(Error::ValidationError(format!(
+                "Unknown transcript format '{s}'. Use openai, anthropic or neutral"
+            ))),
+        }
+    }
+}
+
+/// What to include when building a transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TranscriptOptions {
+    /// Include tool calls and their results
+    pub include_tools: bool,
+    /// Attach the template each prompt was instantiated from
+    pub include_templates: bool,
+    /// Name assistant turns after the agent that handled the prompt
+    pub include_agents: bool,
+    /// Carry attachments as content parts, with their content
+    pub include_attachments: bool,
+    /// System prompt to put ahead of the conversation
+    pub system_prompt: Option<String>,
+}
+
+impl Default for TranscriptOptions {
+    fn default() -> Self {
+        Self {
+            include_tools: true,
+            include_templates: false,
+            include_agents: false,
+            include_attachments: true,
+            system_prompt: None,
+        }
+    }
+}
+
+impl TranscriptOptions {
+    /// Tool calls and attachments included, provenance left out
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include or leave out tool calls and results
+    #[must_use]
+    pub const fn tools(mut self, include: bool) -> Self {
+        self.include_tools = include;
+        self
+    }
+
+    /// Include or leave out template provenance
+    #[must_use]
+    pub const fn templates(mut self, include: bool) -> Self {
+        self.include_templates = include;
+        self
+    }
+
+    /// Include or leave out agent names
+    #[must_use]
+    pub const fn agents(mut self, include: bool) -> Self {
+        self.include_agents = include;
+        self
+    }
+
+    /// Include or leave out attachments
+    #[must_use]
+    pub const fn attachments(mut self, include: bool) -> Self {
+        self.include_attachments = include;
+        self
+    }
+
+    /// Start the transcript with a system prompt
+    #[must_use]
+    pub fn system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+}
+
+/// Author of a transcript message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// System instructions
+    System,
+    /// A prompt
+    User,
+    /// A response
+    Assistant,
+    /// The result of a tool call
+    Tool,
+}
+
+/// Template a prompt was instantiated from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateProvenance {
+    /// Template ID
+    pub template_id: TemplateId,
+    /// Node ID of the template
+    pub node_id: NodeId,
+    /// Template name
+    pub name: String,
+    /// Template version
+    pub version: String,
+    /// Variable bindings used, if recorded on the link
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, String>,
+}
+
+/// A tool call made by an assistant turn
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolCall {
+    /// Call ID linking the call to its result message
+    pub id: String,
+    /// Tool name
+    pub name: String,
+    /// Arguments passed to the tool
+    pub arguments: Value,
+    /// Node ID of the tool invocation
+    pub node_id: NodeId,
+}
+
+/// An attachment carried by a transcript message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ContentPart {
+    /// Category of the media type
+    pub modality: Modality,
+    /// Media type, such as `image/png`
+    pub media_type: String,
+    /// Size of the content in bytes
+    pub size: u64,
+    /// Hex SHA-256 of the content
+    pub sha256: String,
+    /// File name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Where the content came from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_uri: Option<String>,
+    /// Base64 content, if the graph holds it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+impl ContentPart {
+    /// The content as a `data:` URL, else the source URI
+    fn url(&self) -> Option<String> {
+        match (&self.data, &self.source_uri) {
+            (Some(data), _) => Some(format!("data:{};base64,{data}", self.media_type)),
+            (None, uri) => uri.clone(),
+        }
+    }
+
+    /// Anthropic `source` object for an image or document
+    fn anthropic_source(&self) -> Option<Value> {
+        match (&self.data, &self.source_uri) {
+            (Some(data), _) => Some(json!({
+                "type": "base64",
+                "media_type": self.media_type,
+                "data": data,
+            })),
+            (None, Some(uri)) => Some(json!({ "type": "url", "url": uri })),
+            (None, None) => None,
+        }
+    }
+
+    /// OpenAI content block for a user turn, if OpenAI takes this part
+    fn to_openai(&self) -> Option<Value> {
+        match self.modality {
+            Modality::Image => Some(json!({
+                "type": "image_url",
+                "image_url": { "url": self.url()? },
+            })),
+            Modality::Audio => {
+                let format = self.media_type.split('/').nth(1).unwrap_or_default();
+                let format = if format == "mpeg" { "mp3" } else { format };
+                Some(json!({
+                    "type": "input_audio",
+                    "input_audio": { "data": self.data.as_ref()?, "format": format },
+                }))
+            }
+            Modality::Document => Some(json!({
+                "type": "file",
+                "file": {
+                    "filename": self.name.clone().unwrap_or_else(|| self.sha256.clone()),
+                    "file_data": self.url().filter(|_| self.data.is_some())?,
+                },
+            })),
+            Modality::Video | Modality::Text | Modality::Other => None,
+        }
+    }
+
+    /// Anthropic content block for a user turn, if Anthropic takes this part
+    fn to_anthropic(&self) -> Option<Value> {
+        let block_type = match self.modality {
+            Modality::Image => "image",
+            Modality::Document if self.media_type == "application/pdf" => "document",
+            _ => return None,
+        };
+        Some(json!({ "type": block_type, "source": self.anthropic_source()? }))
+    }
+}
+
+/// One message of a transcript
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptMessage {
+    /// Author of the message
+    pub role: Role,
+    /// Message text
+    pub content: String,
+    /// Node the message was built from; `None` for the system prompt
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<NodeId>,
+    /// When the node was recorded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// Agent that handled the turn
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent: Option<String>,
+    /// Template the prompt was instantiated from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub template: Option<TemplateProvenance>,
+    /// Tool calls made by an assistant turn
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// Call a tool message answers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Whether a tool message reports a failure
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_error: bool,
+    /// Attachments of the prompt or response
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+}
+
+impl TranscriptMessage {
+    fn new(role: Role, content: String) -> Self {
+        Self {
+            role,
+            content,
+            node_id: None,
+            timestamp: None,
+            agent: None,
+            template: None,
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+            is_error: false,
+            parts: Vec::new(),
+        }
+    }
+
+    fn from_node(role: Role, content: String, node: &Node) -> Self {
+        Self {
+            node_id: Some(node.id()),
+            timestamp: Some(node_timestamp(node)),
+            ..Self::new(role, content)
+        }
+    }
+}
+
+/// A session rebuilt as a list of chat messages
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    /// Session the transcript was built from
+    pub session_id: SessionId,
+    /// Messages in conversation order
+    pub messages: Vec<TranscriptMessage>,
+}
+
+impl Transcript {
+    /// Build the transcript of a session
+    ///
+    /// A forked session includes the history it shares with its parent, see
+    /// [`AsyncMemoryGraph::branch_thread`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the session does not exist or storage fails.
+    pub async fn load(
+        graph: &AsyncMemoryGraph,
+        session_id: &SessionId,
+        options: &TranscriptOptions,
+    ) -> Result<Self> {
+        let mut prompts: Vec<PromptNode> = Vec::new();
+        let mut responses: HashMap<NodeId, Vec<ResponseNode>> = HashMap::new();
+        for node in graph.branch_thread(*session_id).await? {
+            match node {
+                Node::Prompt(prompt) => prompts.push(prompt),
+                Node::Response(response) => {
+                    responses
+                        .entry(response.prompt_id)
+                        .or_default()
+                        .push(response);
+                }
+                _ => {}
+            }
+        }
+        prompts.sort_by_key(|p| p.timestamp);
+
+        let mut messages = Vec::new();
+        if let Some(system) = &options.system_prompt {
+            messages.push(TranscriptMessage::new(Role::System, system.clone()));
+        }
+
+        for prompt in prompts {
+            let (template, agent) = provenance(graph, &prompt, options).await?;
+            let prompt_id = prompt.id;
+            let content = prompt.content.clone();
+            let parts = content_parts(graph, &prompt.attachments, options).await?;
+            let mut message =
+                TranscriptMessage::from_node(Role::User, content, &Node::Prompt(prompt));
+            message.template = template;
+            message.parts = parts;
+            messages.push(message);
+
+            let mut replies = responses.remove(&prompt_id).unwrap_or_default();
+            replies.sort_by_key(|r| r.timestamp);
+            for response in replies {
+                let tools = if options.include_tools {
+                    tool_invocations(graph, &response.id).await?
+                } else {
+                    Vec::new()
+                };
+
+                let content = response.content.clone();
+                let parts = content_parts(graph, &response.attachments, options).await?;
+                let mut message = TranscriptMessage::from_node(
+                    Role::Assistant,
+                    content,
+                    &Node::Response(response),
+                );
+                message.agent.clone_from(&agent);
+                message.parts = parts;
+                message.tool_calls = tools
+                    .iter()
+                    .map(|tool| ToolCall {
+                        id: call_id(&tool.id),
+                        name: tool.tool_name.clone(),
+                        arguments: tool.parameters.clone(),
+                        node_id: tool.id,
+                    })
+                    .collect();
+                messages.push(message);
+
+                for tool in tools {
+                    let id = call_id(&tool.id);
+                    let is_error = tool.error.is_some();
+                    let content = tool_output(&tool);
+                    let mut result = TranscriptMessage::from_node(
+                        Role::Tool,
+                        content,
+                        &Node::ToolInvocation(tool),
+                    );
+                    result.tool_call_id = Some(id);
+                    result.is_error = is_error;
+                    messages.push(result);
+                }
+            }
+        }
+
+        Ok(Self {
+            session_id: *session_id,
+            messages,
+        })
+    }
+
+    /// Render the transcript in the given format
+    #[must_use]
+    pub fn render(&self, format: TranscriptFormat) -> Value {
+        match format {
+            TranscriptFormat::OpenAi => self.to_openai(),
+            TranscriptFormat::Anthropic => self.to_anthropic(),
+            TranscriptFormat::Neutral => serde_json::to_value(self).unwrap_or(Value::Null),
+        }
+    }
+
+    /// Render as an OpenAI chat-completions body: `{"messages": [...]}`
+    #[must_use]
+    pub fn to_openai(&self) -> Value {
+        let messages: Vec<Value> = self
+            .messages
+            .iter()
+            .map(|message| match message.role {
+                Role::System => json!({ "role": "system", "content": message.content }),
+                Role::User => {
+                    let parts: Vec<Value> = message
+                        .parts
+                        .iter()
+                        .filter_map(ContentPart::to_openai)
+                        .collect();
+                    if parts.is_empty() {
+                        json!({ "role": "user", "content": message.content })
+                    } else {
+                        let mut blocks = Vec::with_capacity(parts.len() + 1);
+                        if !message.content.is_empty() {
+                            blocks.push(json!({ "type": "text", "text": message.content }));
+                        }
+                        blocks.extend(parts);
+                        json!({ "role": "user", "content": blocks })
+                    }
+                }
+                Role::Assistant => {
+                    let mut value = json!({ "role": "assistant", "content": message.content });
+                    if let Some(agent) = &message.agent {
+                        value["name"] = Value::String(openai_name(agent));
+                    }
+                    if !message.tool_calls.is_empty() {
+                        let calls: Vec<Value> = message
+                            .tool_calls
+                            .iter()
+                            .map(|call| {
+                                json!({
+                                    "id": call.id,
+                                    "type": "function",
+                                    "function": {
+                                        "name": call.name,
+                                        "arguments": call.arguments.to_string(),
+                                    },
+                                })
+                            })
+                            .collect();
+                        value["tool_calls"] = Value::Array(calls);
+                    }
+                    value
+                }
+                Role::Tool => json!({
+                    "role": "tool",
+                    "tool_call_id": message.tool_call_id,
+                    "content": message.content,
+                }),
+            })
+            .collect();
+
+        json!({ "messages": messages })
+    }
+
+    /// Render as an Anthropic messages body: `{"system": ..., "messages": [...]}`
+    ///
+    /// Tool results travel in user turns, and consecutive turns from the same
+    /// side are merged so roles alternate as the API requires.
+    #[must_use]
+    pub fn to_anthropic(&self) -> Value {
+        let mut system = Vec::new();
+        let mut turns: Vec<(&str, Vec<Value>)> = Vec::new();
+
+        for message in &self.messages {
+            let (role, blocks) = match message.role {
+                Role::System => {
+                    system.push(message.content.as_str());
+                    continue;
+                }
+                Role::User => {
+                    let mut blocks: Vec<Value> = message
+                        .parts
+                        .iter()
+                        .filter_map(ContentPart::to_anthropic)
+                        .collect();
+                    if !message.content.is_empty() || blocks.is_empty() {
+                        blocks.push(json!({ "type": "text", "text": message.content }));
+                    }
+                    ("user", blocks)
+                }
+                Role::Assistant => {
+                    let mut blocks = Vec::new();
+                    if !message.content.is_empty() {
+                        blocks.push(json!({ "type": "text", "text": message.content }));
+                    }
+                    blocks.extend(message.tool_calls.iter().map(|call| {
+                        json!({
+                            "type": "tool_use",
+                            "id": call.id,
+                            "name": call.name,
+                            "input": call.arguments,
+                        })
+                    }));
+                    ("assistant", blocks)
+                }
+                Role::Tool => (
+                    "user",
+                    vec![json!({
+                        "type": "tool_result",
+                        "tool_use_id": message.tool_call_id,
+                        "content": message.content,
+                        "is_error": message.is_error,
+                    })],
+                ),
+            };
+
+            match turns.last_mut() {
+                Some((last, existing)) if *last == role => existing.extend(blocks),
+                _ => turns.push((role, blocks)),
+            }
+        }
+
+        let messages: Vec<Value> = turns
+            .into_iter()
+            .map(|(role, content)| json!({ "role": role, "content": content }))
+            .collect();
+        let mut body = json!({ "messages": messages });
+        if !system.is_empty() {
+            body["system"] = Value::String(system.join("\n\n"));
+        }
+        body
+    }
+}
+
+/// Template and agent recorded for a prompt, as far as the options ask
+async fn provenance(
+    graph: &AsyncMemoryGraph,
+    prompt: &PromptNode,
+    options: &TranscriptOptions,
+) -> Result<(Option<TemplateProvenance>, Option<String>)> {
+    if !options.include_templates && !options.include_agents {
+        return Ok((None, None));
+    }
+
+    let mut template = None;
+    let mut agent = None;
+    for edge in graph.get_outgoing_edges(&prompt.id).await? {
+        match edge.edge_type {
+            EdgeType::Instantiates if options.include_templates && template.is_none() => {
+                if let Some(Node::Template(t)) = graph.get_node(&edge.to).await? {
+                    let variables = edge
+                        .properties
+                        .get("variable_bindings")
+                        .and_then(|v| serde_json::from_str(v).ok())
+                        .unwrap_or_default();
+                    template = Some(TemplateProvenance {
+                        template_id: t.id,
+                        node_id: t.node_id,
+                        name: t.name,
+                        version: edge
+                            .properties
+                            .get("template_version")
+                            .cloned()
+                            .unwrap_or_else(|| t.version.to_string()),
+                        variables,
+                    });
+                }
+            }
+            EdgeType::HandledBy if options.include_agents && agent.is_none() => {
+                if let Some(Node::Agent(a)) = graph.get_node(&edge.to).await? {
+                    agent = Some(a.name);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok((template, agent))
+}
+
+/// Content parts for `attachments`, as far as the options ask
+async fn content_parts(
+    graph: &AsyncMemoryGraph,
+    attachments: &[Attachment],
+    options: &TranscriptOptions,
+) -> Result<Vec<ContentPart>> {
+    if !options.include_attachments {
+        return Ok(Vec::new());
+    }
+    let mut parts = Vec::with_capacity(attachments.len());
+    for attachment in attachments {
+        let data = graph.attachment_data(attachment).await?;
+        parts.push(ContentPart {
+            modality: attachment.modality(),
+            media_type: attachment.media_type.clone(),
+            size: attachment.size,
+            sha256: attachment.sha256.clone(),
+            name: attachment.name.clone(),
+            source_uri: attachment.source_uri.clone(),
+            data: data.map(|bytes| BASE64.encode(bytes)),
+        });
+    }
+    Ok(parts)
+}
+
+/// Tool invocations of a response, oldest first
+async fn tool_invocations(
+    graph: &AsyncMemoryGraph,
+    response_id: &NodeId,
+) -> Result<Vec<ToolInvocation>> {
+    let mut tools = Vec::new();
+    for edge in graph.get_outgoing_edges(response_id).await? {
+        if edge.edge_type == EdgeType::Invokes {
+            if let Some(Node::ToolInvocation(tool)) = graph.get_node(&edge.to).await? {
+                tools.push(tool);
+            }
+        }
+    }
+    tools.sort_by_key(|t| t.timestamp);
+    Ok(tools)
+}
+
+/// Call ID for a tool invocation, valid for both providers
+fn call_id(node_id: &NodeId) -> String {
+    format!("call_{}", node_id.to_string().replace('-', ""))
+}
+
+/// Text a tool message carries: the result, else the error
+fn tool_output(tool: &ToolInvocation) -> String {
+    match (&tool.result, &tool.error) {
+        (Some(Value::String(text)), _) => text.clone(),
+        (Some(result), _) => result.to_string(),
+        (None, Some(error)) => error.clone(),
+        (None, None) => String::new(),
+    }
+}
+
+/// Agent name limited to the characters OpenAI accepts in `name`
+fn openai_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attachments;
+    use crate::{AgentNode, Config, PromptTemplate, TokenUsage, VariableSpec};
+    use tempfile::tempdir;
+
+    #[tokio::test]
+    async fn test_session_transcript() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+
+        let prompt = graph
+            .add_prompt(session.id, "Weather in Paris?".to_string(), None)
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(prompt, "Checking.".to_string(), TokenUsage::new(5, 2), None)
+            .await
+            .unwrap();
+        let mut tool = ToolInvocation::new(
+            response,
+            "get_weather".to_string(),
+            json!({ "city": "Paris" }),
+        );
+        tool.result = Some(json!({ "temp_c": 21 }));
+        graph.add_tool_invocation(tool).await.unwrap();
+
+        let agent = AgentNode::new("weather bot".to_string(), "helper".to_string(), vec![]);
+        let agent_node = agent.node_id;
+        graph.add_agent(agent).await.unwrap();
+        graph
+            .add_edge(prompt, agent_node, EdgeType::HandledBy)
+            .await
+            .unwrap();
+
+        let template = PromptTemplate::new(
+            "weather".to_string(),
+            "Weather in {{city}}?".to_string(),
+            vec![VariableSpec::new(
+                "city".to_string(),
+                "string".to_string(),
+                true,
+                "City".to_string(),
+            )],
+        );
+        let template_node = template.node_id;
+        graph.create_template(template).await.unwrap();
+        graph
+            .link_prompt_to_template(prompt, template_node)
+            .await
+            .unwrap();
+
+        let follow_up = graph
+            .add_prompt(session.id, "Thanks".to_string(), None)
+            .await
+            .unwrap();
+
+        let options = TranscriptOptions::new()
+            .agents(true)
+            .templates(true)
+            .system_prompt("Be brief.");
+        let transcript = Transcript::load(&graph, &session.id, &options)
+            .await
+            .unwrap();
+        let roles: Vec<Role> = transcript.messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            vec![
+                Role::System,
+                Role::User,
+                Role::Assistant,
+                Role::Tool,
+                Role::User
+            ]
+        );
+        assert_eq!(
+            transcript.messages[1].template.as_ref().unwrap().name,
+            "weather"
+        );
+        assert_eq!(transcript.messages[4].node_id, Some(follow_up));
+
+        let openai = transcript.to_openai();
+        let messages = openai["messages"].as_array().unwrap();
+        assert_eq!(messages[2]["name"], "weather_bot");
+        let call = &messages[2]["tool_calls"][0];
+        assert_eq!(call["function"]["name"], "get_weather");
+        assert_eq!(call["function"]["arguments"], r#"{"city":"Paris"}"#);
+        assert_eq!(messages[3]["tool_call_id"], call["id"]);
+        assert_eq!(messages[3]["content"], r#"{"temp_c":21}"#);
+
+        // Tool result and the next prompt merge into one user turn
+        let anthropic = transcript.to_anthropic();
+        assert_eq!(anthropic["system"], "Be brief.");
+        let messages = anthropic["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][1]["type"], "tool_use");
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert_eq!(messages[2]["content"][1]["text"], "Thanks");
+
+        let neutral = graph
+            .session_transcript(
+                &session.id,
+                TranscriptFormat::Neutral,
+                &TranscriptOptions::new(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(neutral["messages"].as_array().unwrap().len(), 4);
+        assert!(neutral["messages"][0].get("template").is_none());
+
+        assert!(graph
+            .session_transcript(&SessionId::new(), TranscriptFormat::OpenAi, &options)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_attachments_become_content_parts() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let prompt = graph
+            .add_prompt(session.id, "What is this?".to_string(), None)
+            .await
+            .unwrap();
+        graph
+            .add_attachment(prompt, attachments::from_bytes("image/png", vec![1, 2, 3]))
+            .await
+            .unwrap();
+        graph
+            .add_attachment(
+                prompt,
+                Attachment::external("application/pdf", 10, attachments::sha256_hex(b"x"))
+                    .with_source_uri("https://example.com/spec.pdf"),
+            )
+            .await
+            .unwrap();
+        let response = graph
+            .add_response(prompt, "A chart".to_string(), TokenUsage::new(1, 1), None)
+            .await
+            .unwrap();
+        graph
+            .add_attachment(response, attachments::from_bytes("image/svg+xml", vec![4]))
+            .await
+            .unwrap();
+
+        let transcript = Transcript::load(&graph, &session.id, &TranscriptOptions::new())
+            .await
+            .unwrap();
+        let parts = &transcript.messages[0].parts;
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].data.as_deref(), Some("AQID"));
+        assert_eq!(parts[1].modality, Modality::Document);
+        assert!(parts[1].data.is_none());
+        assert_eq!(transcript.messages[1].parts.len(), 1);
+
+        let openai = transcript.to_openai();
+        let user = &openai["messages"][0]["content"];
+        assert_eq!(user[0]["text"], "What is this?");
+        assert_eq!(user[1]["image_url"]["url"], "data:image/png;base64,AQID");
+        // OpenAI files need their content, and assistant turns stay text
+        assert_eq!(user.as_array().unwrap().len(), 2);
+        assert_eq!(openai["messages"][1]["content"], "A chart");
+
+        let anthropic = transcript.to_anthropic();
+        let user = &anthropic["messages"][0]["content"];
+        assert_eq!(user[0]["source"]["data"], "AQID");
+        assert_eq!(user[1]["type"], "document");
+        assert_eq!(user[1]["source"]["url"], "https://example.com/spec.pdf");
+        assert_eq!(user[2]["type"], "text");
+
+        let without = Transcript::load(
+            &graph,
+            &session.id,
+            &TranscriptOptions::new().attachments(false),
+        )
+        .await
+        .unwrap();
+        assert!(without.messages.iter().all(|m| m.parts.is_empty()));
+    }
+}
@@ -0,0 +1,451 @@
+//! Hierarchical navigable small world graph over unit vectors
+//!
+//! Vectors are normalized on insert so the graph is navigated by cosine
+//! similarity; the original norm is kept so dot-product scores can be
+//! recovered when re-ranking. The graph holds no storage handles: the caller
+//! persists the links of every node an update reports as changed.
+
+use crate::NodeId;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Links per node on upper layers
+const M: usize = 16;
+/// Links per node on the bottom layer
+const M0: usize = 2 * M;
+/// Candidate list size while inserting
+const EF_CONSTRUCTION: usize = 100;
+/// Highest layer a node can be assigned to
+const MAX_LEVEL: usize = 16;
+
+/// A vector in the graph and its links on each layer it belongs to
+#[derive(Debug, Clone)]
+pub(crate) struct HnswNode {
+    /// Unit-length copy of the vector
+    pub unit: Vec<f32>,
+    /// Length of the original vector
+    pub norm: f32,
+    /// Neighbours on layers `0..=level`
+    pub links: Vec<Vec<NodeId>>,
+}
+
+impl HnswNode {
+    fn level(&self) -> usize {
+        self.links.len() - 1
+    }
+}
+
+/// In-memory HNSW graph
+#[derive(Debug, Default)]
+pub(crate) struct Hnsw {
+    nodes: HashMap<NodeId, HnswNode>,
+    entry: Option<NodeId>,
+}
+
+/// Similarity paired with a node, ordered by similarity alone
+#[derive(Debug, Clone, Copy)]
+struct Scored(f32, NodeId);
+
+impl PartialEq for Scored {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scored {}
+
+impl PartialOrd for Scored {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scored {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hnsw {
+    /// Number of vectors in the graph
+    pub(crate) fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Dimension of the stored vectors, if any are stored
+    pub(crate) fn dimension(&self) -> Option<usize> {
+        self.nodes.values().next().map(|n| n.unit.len())
+    }
+
+    /// Node searches start from
+    pub(crate) fn entry(&self) -> Option<NodeId> {
+        self.entry
+    }
+
+    /// A node's vector and links
+    pub(crate) fn node(&self, id: &NodeId) -> Option<&HnswNode> {
+        self.nodes.get(id)
+    }
+
+    /// Re-add a node with links loaded from storage
+    pub(crate) fn restore(&mut self, id: NodeId, vector: &[f32], links: Vec<Vec<NodeId>>) {
+        let (unit, norm) = normalize(vector);
+        self.nodes.insert(id, HnswNode { unit, norm, links });
+    }
+
+    /// Set the entry point after restoring nodes
+    ///
+    /// Falls back to the highest node when the stored entry point is missing.
+    pub(crate) fn set_entry(&mut self, entry: Option<NodeId>) {
+        self.entry = entry
+            .filter(|id| self.nodes.contains_key(id))
+            .or_else(|| self.highest_node());
+    }
+
+    /// Insert or replace a vector, returning every node whose links changed
+    pub(crate) fn insert(&mut self, id: NodeId, vector: &[f32]) -> Vec<NodeId> {
+        let mut changed: HashSet<NodeId> = self.remove(&id).into_iter().collect();
+        changed.insert(id);
+
+        let (unit, norm) = normalize(vector);
+        let level = level_for(&id);
+
+        let Some(entry) = self.entry else {
+            self.nodes.insert(
+                id,
+                HnswNode {
+                    unit,
+                    norm,
+                    links: vec![Vec::new(); level + 1],
+                },
+            );
+            self.entry = Some(id);
+            return changed.into_iter().collect();
+        };
+
+        // Pick neighbours before adding the node so it never finds itself
+        let top = self.nodes[&entry].level();
+        let mut entry_points = vec![entry];
+        for layer in (level + 1..=top).rev() {
+            entry_points = self
+                .search_layer(&unit, &entry_points, 1, layer)
+                .into_iter()
+                .map(|s| s.1)
+                .collect();
+        }
+        let mut links = vec![Vec::new(); level + 1];
+        for layer in (0..=level.min(top)).rev() {
+            let candidates = self.search_layer(&unit, &entry_points, EF_CONSTRUCTION, layer);
+            links[layer] = candidates
+                .iter()
+                .take(max_links(layer))
+                .map(|s| s.1)
+                .collect();
+            entry_points = candidates.into_iter().map(|s| s.1).collect();
+        }
+
+        for (layer, neighbours) in links.iter().enumerate() {
+            for neighbour in neighbours {
+                self.link(*neighbour, id, &unit, layer);
+                changed.insert(*neighbour);
+            }
+        }
+        self.nodes.insert(id, HnswNode { unit, norm, links });
+        if level > top {
+            self.entry = Some(id);
+        }
+
+        changed.into_iter().collect()
+    }
+
+    /// Remove a vector, returning every remaining node whose links changed
+    ///
+    /// Former neighbours are reconnected among themselves so the graph stays
+    /// navigable. Links from nodes the removed one did not link back to are
+    /// left dangling and skipped during search.
+    pub(crate) fn remove(&mut self, id: &NodeId) -> Vec<NodeId> {
+        let Some(removed) = self.nodes.remove(id) else {
+            return Vec::new();
+        };
+
+        let mut changed = HashSet::new();
+        for (layer, neighbours) in removed.links.iter().enumerate() {
+            for neighbour in neighbours {
+                let Some(node) = self.nodes.get(neighbour) else {
+                    continue;
+                };
+                if node.level() < layer {
+                    continue;
+                }
+
+                let unit = node.unit.clone();
+                let mut pool: Vec<NodeId> = node.links[layer]
+                    .iter()
+                    .chain(neighbours)
+                    .filter(|n| *n != id && *n != neighbour)
+                    .copied()
+                    .collect();
+                pool.sort_by_key(NodeId::to_bytes);
+                pool.dedup();
+                let links = self.closest(&unit, pool, layer);
+                if let Some(node) = self.nodes.get_mut(neighbour) {
+                    node.links[layer] = links;
+                }
+                changed.insert(*neighbour);
+            }
+        }
+
+        if self.entry == Some(*id) {
+            self.entry = self.highest_node();
+        }
+
+        changed.into_iter().collect()
+    }
+
+    /// Approximate nearest neighbours of `query` by cosine similarity
+    ///
+    /// Returns up to `ef` nodes, most similar first. Larger `ef` trades speed
+    /// for recall.
+    pub(crate) fn search(&self, query: &[f32], ef: usize) -> Vec<(NodeId, f32)> {
+        let Some(entry) = self.entry else {
+            return Vec::new();
+        };
+        let (unit, _) = normalize(query);
+
+        let mut entry_points = vec![entry];
+        for layer in (1..=self.nodes[&entry].level()).rev() {
+            entry_points = self
+                .search_layer(&unit, &entry_points, 1, layer)
+                .into_iter()
+                .map(|s| s.1)
+                .collect();
+        }
+        self.search_layer(&unit, &entry_points, ef.max(1), 0)
+            .into_iter()
+            .map(|Scored(score, id)| (id, score))
+            .collect()
+    }
+
+    /// Best-first search of one layer, returning up to `ef` nodes best first
+    fn search_layer(
+        &self,
+        unit: &[f32],
+        entry_points: &[NodeId],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Scored> {
+        let mut visited: HashSet<NodeId> = HashSet::new();
+        let mut candidates = BinaryHeap::new();
+        let mut best = BinaryHeap::new();
+
+        for id in entry_points {
+            if let Some(node) = self.nodes.get(id) {
+                visited.insert(*id);
+                let scored = Scored(dot(unit, &node.unit), *id);
+                candidates.push(scored);
+                best.push(Reverse(scored));
+            }
+        }
+        while best.len() > ef {
+            best.pop();
+        }
+
+        while let Some(Scored(score, id)) = candidates.pop() {
+            if best.len() >= ef && best.peek().is_some_and(|worst| score < worst.0 .0) {
+                break;
+            }
+            let Some(links) = self.nodes[&id].links.get(layer) else {
+                continue;
+            };
+            for neighbour in links {
+                if !visited.insert(*neighbour) {
+                    continue;
+                }
+                // Links to removed nodes may linger until the neighbour is relinked
+                let Some(node) = self.nodes.get(neighbour) else {
+                    continue;
+                };
+                let scored = Scored(dot(unit, &node.unit), *neighbour);
+                if best.len() < ef || best.peek().is_some_and(|worst| scored > worst.0) {
+                    candidates.push(scored);
+                    best.push(Reverse(scored));
+                    if best.len() > ef {
+                        best.pop();
+                    }
+                }
+            }
+        }
+
+        let mut result: Vec<Scored> = best.into_iter().map(|r| r.0).collect();
+        result.sort_by(|a, b| b.cmp(a));
+        result
+    }
+
+    /// Add `new` to the links of `node`, keeping only the closest when full
+    fn link(&mut self, node: NodeId, new: NodeId, new_unit: &[f32], layer: usize) {
+        let Some(existing) = self.nodes.get(&node) else {
+            return;
+        };
+        let mut pool = existing.links[layer].clone();
+        pool.push(new);
+        if pool.len() <= max_links(layer) {
+            if let Some(existing) = self.nodes.get_mut(&node) {
+                existing.links[layer] = pool;
+            }
+            return;
+        }
+
+        // `new` is not in the map yet, so score it separately
+        let unit = existing.unit.clone();
+        let new_score = dot(&unit, new_unit);
+        let mut scored: Vec<Scored> = pool
+            .into_iter()
+            .filter_map(|id| {
+                if id == new {
+                    Some(Scored(new_score, id))
+                } else {
+                    self.nodes.get(&id).map(|n| Scored(dot(&unit, &n.unit), id))
+                }
+            })
+            .collect();
+        scored.sort_by(|a, b| b.cmp(a));
+        scored.truncate(max_links(layer));
+        if let Some(existing) = self.nodes.get_mut(&node) {
+            existing.links[layer] = scored.into_iter().map(|s| s.1).collect();
+        }
+    }
+
+    /// The closest members of `pool` to `unit` that exist on `layer`
+    fn closest(&self, unit: &[f32], pool: Vec<NodeId>, layer: usize) -> Vec<NodeId> {
+        let mut scored: Vec<Scored> = pool
+            .into_iter()
+            .filter_map(|id| {
+                self.nodes
+                    .get(&id)
+                    .filter(|n| n.level() >= layer)
+                    .map(|n| Scored(dot(unit, &n.unit), id))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.cmp(a));
+        scored.truncate(max_links(layer));
+        scored.into_iter().map(|s| s.1).collect()
+    }
+
+    fn highest_node(&self) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .max_by_key(|(_, node)| node.level())
+            .map(|(id, _)| *id)
+    }
+}
+
+fn max_links(layer: usize) -> usize {
+    if layer == 0 {
+        M0
+    } else {
+        M
+    }
+}
+
+/// Assign a layer from the node ID
+///
+/// Layers follow the usual geometric distribution, but are derived from a
+/// stable hash of the ID instead of a random draw so that rebuilding an index
+/// reproduces the same structure.
+fn level_for(id: &NodeId) -> usize {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in id.to_bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    // Uniform in (0, 1]
+    let uniform = ((hash >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    let level = (-uniform.ln() / (M as f64).ln()).floor() as usize;
+    level.min(MAX_LEVEL)
+}
+
+fn normalize(vector: &[f32]) -> (Vec<f32>, f32) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return (vector.to_vec(), 0.0);
+    }
+    (vector.iter().map(|v| v / norm).collect(), norm)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random vectors
+    fn vectors(count: usize, dimension: usize) -> Vec<(NodeId, Vec<f32>)> {
+        let mut state: u32 = 7;
+        (0..count)
+            .map(|_| {
+                let vector = (0..dimension)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 17;
+                        state ^= state << 5;
+                        (state % 2000) as f32 / 1000.0 - 1.0
+                    })
+                    .collect();
+                (NodeId::new(), vector)
+            })
+            .collect()
+    }
+
+    fn exact(data: &[(NodeId, Vec<f32>)], query: &[f32], k: usize) -> Vec<NodeId> {
+        let (unit, _) = normalize(query);
+        let mut scored: Vec<(NodeId, f32)> = data
+            .iter()
+            .map(|(id, v)| (*id, dot(&unit, &normalize(v).0)))
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored.into_iter().take(k).map(|(id, _)| id).collect()
+    }
+
+    #[test]
+    fn test_search_recall() {
+        let data = vectors(500, 16);
+        let mut graph = Hnsw::default();
+        for (id, vector) in &data {
+            graph.insert(*id, vector);
+        }
+        assert_eq!(graph.len(), 500);
+
+        let mut found = 0;
+        for (_, query) in data.iter().take(20) {
+            let expected = exact(&data, query, 10);
+            let actual: Vec<NodeId> = graph.search(query, 50).into_iter().map(|r| r.0).collect();
+            found += expected.iter().filter(|id| actual.contains(id)).count();
+        }
+        // Recall@10 over 20 queries
+        assert!(found >= 180, "recall too low: {found}/200");
+    }
+
+    #[test]
+    fn test_remove_keeps_graph_navigable() {
+        let data = vectors(200, 8);
+        let mut graph = Hnsw::default();
+        for (id, vector) in &data {
+            graph.insert(*id, vector);
+        }
+
+        for (id, _) in data.iter().take(100) {
+            graph.remove(id);
+        }
+        assert_eq!(graph.len(), 100);
+        assert!(graph.entry().is_some_and(|e| graph.node(&e).is_some()));
+
+        let (target, query) = &data[150];
+        let results = graph.search(query, 20);
+        assert_eq!(results[0].0, *target);
+        assert!(results
+            .iter()
+            .all(|(id, _)| !data[..100].iter().any(|(removed, _)| removed == id)));
+    }
+}
@@ -0,0 +1,179 @@
+//! Embedding vectors and nearest-neighbour search
+//!
+//! Prompt, response, template and custom nodes can carry an embedding vector.
+//! Vectors are stored in their own tree, separate from the node records, and
+//! indexed in a hierarchical navigable small world (HNSW) graph that the
+//! storage backend persists alongside the data, so reopening a database does
+//! not rebuild it.
+//!
+//! All vectors in one database must have the same dimension; the first vector
+//! stored fixes it.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::vector::{SimilarityMetric, VectorFilter};
+//! use llm_memory_graph::{Config, NodeType};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let session = graph.create_session().await?;
+//! let prompt = graph.add_prompt(session.id, "Summarize the Q3 report".to_string(), None).await?;
+//! graph.set_embedding(&prompt, vec![0.12, 0.80, 0.31]).await?;
+//!
+//! let filter = VectorFilter::new()
+//!     .node_type(NodeType::Prompt)
+//!     .metric(SimilarityMetric::Cosine);
+//! for hit in graph.similar_nodes(&[0.10, 0.82, 0.29], 5, &filter).await? {
+//!     println!("{:.3} {}", hit.score, hit.node_id);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+mod hnsw;
+
+pub(crate) use hnsw::Hnsw;
+
+use crate::{Error, NodeId, NodeType, Result, SessionId};
+use serde::{Deserialize, Serialize};
+
+/// How similarity between two vectors is scored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimilarityMetric {
+    /// Cosine of the angle between the vectors, in `[-1, 1]`
+    #[default]
+    Cosine,
+    /// Raw dot product, which also rewards longer vectors
+    DotProduct,
+}
+
+/// Filters and scoring options for a nearest-neighbour search
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VectorFilter {
+    /// Only return nodes belonging to this session
+    pub session_id: Option<SessionId>,
+    /// Only return nodes of these types; empty means any type with embeddings
+    pub node_types: Vec<NodeType>,
+    /// Drop results scoring below this value
+    pub min_score: Option<f32>,
+    /// Scoring metric
+    pub metric: SimilarityMetric,
+}
+
+impl VectorFilter {
+    /// Create a filter that accepts every node, scored by cosine similarity
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restrict results to a session
+    #[must_use]
+    pub fn session(mut self, session_id: SessionId) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Restrict results to a node type; may be called more than once
+    #[must_use]
+    pub fn node_type(mut self, node_type: NodeType) -> Self {
+        self.node_types.push(node_type);
+        self
+    }
+
+    /// Drop results scoring below `min_score`
+    #[must_use]
+    pub fn min_score(mut self, min_score: f32) -> Self {
+        self.min_score = Some(min_score);
+        self
+    }
+
+    /// Set the scoring metric
+    #[must_use]
+    pub fn metric(mut self, metric: SimilarityMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Whether a node with these attributes passes the filters
+    pub(crate) fn accepts(&self, node_type: &NodeType, session_id: Option<SessionId>) -> bool {
+        (self.node_types.is_empty() || self.node_types.contains(node_type))
+            && self.session_id.is_none_or(|s| session_id == Some(s))
+    }
+}
+
+/// A nearest-neighbour result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimilarNode {
+    /// Matching node
+    pub node_id: NodeId,
+    /// Type of the matching node
+    pub node_type: NodeType,
+    /// Session the node belongs to, where known
+    pub session_id: Option<SessionId>,
+    /// Similarity under the requested metric; higher is better
+    pub score: f32,
+}
+
+/// Whether nodes of this type may carry an embedding
+pub(crate) fn supports_embedding(node_type: &NodeType) -> bool {
+    matches!(
+        node_type,
+        NodeType::Prompt | NodeType::Response | NodeType::Template | NodeType::Custom
+    )
+}
+
+/// Reject vectors that cannot be indexed
+pub(crate) fn validate_vector(vector: &[f32]) -> Result<()> {
+    if vector.is_empty() {
+        return Err(Error::ValidationError(
+            "Embedding vector must not be empty".to_string(),
+        ));
+    }
+    if vector.iter().any(|v| !v.is_finite()) {
+        return Err(Error::ValidationError(
+            "Embedding vector must only contain finite values".to_string(),
+        ));
+    }
+    if vector.iter().all(|v| *v == 0.0) {
+        return Err(Error::ValidationError(
+            "Embedding vector must not be all zeros".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Merge per-partition results into the overall top `k`
+pub(crate) fn merge_similar(parts: Vec<Vec<SimilarNode>>, k: usize) -> Vec<SimilarNode> {
+    let mut hits: Vec<SimilarNode> = parts.into_iter().flatten().collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(k);
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_vector() {
+        assert!(validate_vector(&[0.5, 1.0]).is_ok());
+        assert!(validate_vector(&[]).is_err());
+        assert!(validate_vector(&[0.0, 0.0]).is_err());
+        assert!(validate_vector(&[1.0, f32::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_filter_accepts() {
+        let session = SessionId::new();
+        let filter = VectorFilter::new()
+            .session(session)
+            .node_type(NodeType::Response);
+
+        assert!(filter.accepts(&NodeType::Response, Some(session)));
+        assert!(!filter.accepts(&NodeType::Prompt, Some(session)));
+        assert!(!filter.accepts(&NodeType::Response, None));
+        assert!(VectorFilter::new().accepts(&NodeType::Template, None));
+    }
+}
@@ -0,0 +1,1455 @@
+//! Saved queries and materialized views
+//!
+//! Both are stored by name in the graph's catalog, so they survive restarts
+//! and are shared by every process opening the database.
+//!
+//! A [`SavedQuery`] is a graph query expression (see
+//! [`crate::query::language`]) that can be run again by name.
+//!
+//! A materialized view is a named [`Aggregation`] over the nodes matching a
+//! [`ViewDefinition`]. Its groups are kept in the catalog and updated on every
+//! write through [`AsyncMemoryGraph`]: the backend wrapper installed by the
+//! engine records what each node contributed to the view, and on a write
+//! takes the old contribution out of its group and adds the new one.
+//! Groups keep running counts, sums, minimums and maximums, so a write costs
+//! the same however large its group is. [`AsyncMemoryGraph::read_view`]
+//! reads the stored groups; percentiles, and a minimum or maximum whose value
+//! has left the group, are computed exactly on read from the view's stored
+//! contributions.
+//!
+//! Writes to different nodes maintain views concurrently. A write only locks
+//! the views whose contributions it changes, and only while it updates their
+//! groups.
+//!
+//! Responses and tool invocations are attributed through the prompt they
+//! answer, as in [`AsyncQueryBuilder::aggregate`](crate::query::AsyncQueryBuilder::aggregate).
+//! Writing a prompt or response therefore also updates the responses and tool
+//! calls below it, and `HandledBy` edges update the prompt they start from
+//! when grouping by agent. View filters see the node together with that
+//! prompt and response, so session predicates work for every node type;
+//! session tag predicates are not supported.
+//!
+//! Writes that bypass the engine, such as another tool writing to the sled
+//! files directly, are not seen; [`AsyncMemoryGraph::refresh_view`] rebuilds
+//! a view from scratch.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use llm_memory_graph::engine::AsyncMemoryGraph;
+//! use llm_memory_graph::query::aggregate::{Aggregation, GroupBy, Metric};
+//! use llm_memory_graph::views::ViewDefinition;
+//! use llm_memory_graph::{Config, NodeType, TokenUsage};
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let graph = AsyncMemoryGraph::open(Config::default()).await?;
+//! let tokens_by_model = ViewDefinition::new(
+//!     "tokens_by_model",
+//!     Aggregation::new()
+//!         .group_by(GroupBy::Model)
+//!         .count()
+//!         .sum(Metric::TotalTokens),
+//! )
+//! .node_type(NodeType::Response);
+//! graph.create_view(tokens_by_model).await?;
+//!
+//! let session = graph.create_session().await?;
+//! let prompt = graph.add_prompt(session.id, "Hi".to_string(), None).await?;
+//! graph
+//!     .add_response(prompt, "Hello".to_string(), TokenUsage::new(2, 3), None)
+//!     .await?;
+//!
+//! for row in graph.read_view("tokens_by_model").await? {
+//!     println!("{:?}: {} responses, {:?}", row.key, row.count, row.values);
+//! }
+//!
+//! graph
+//!     .save_query("long_answers", "MATCH (r:Response) WHERE r.usage.total_tokens > 1000 RETURN r")
+//!     .await?;
+//! let result = graph.run_saved_query("long_answers").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::engine::AsyncMemoryGraph;
+use crate::query::aggregate::{
+    self, Aggregate, AggregateRow, Aggregation, Attribution, GroupValue, Metric,
+};
+use crate::query::predicate::{Predicate, PredicateContext};
+use crate::query::{Cursor, Page, QueryResult};
+use crate::search::{SearchHit, TextQuery};
+use crate::storage::{AsyncStorageBackend, Lookup, StorageStats};
+use crate::vector::{SimilarNode, VectorFilter};
+use crate::{Edge, EdgeId, EdgeType, Error, Node, NodeId, NodeType, Result, SessionId};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLock};
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard, RwLock as AsyncRwLock, RwLockReadGuard};
+
+/// Catalog key prefix of view definitions: `view/<name>`
+const VIEW_PREFIX: &str = "view/";
+/// Catalog key prefix of view groups: `view_group/<name>/<group key>`
+const GROUP_PREFIX: &str = "view_group/";
+/// Catalog key prefix of node contributions: `view_node/<name>/<node id>`
+const CONTRIBUTION_PREFIX: &str = "view_node/";
+/// Catalog key prefix of saved queries: `query/<name>`
+const QUERY_PREFIX: &str = "query/";
+
+/// A graph query stored under a name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedQuery {
+    /// Name the query is saved under
+    pub name: String,
+    /// Query expression, as accepted by [`AsyncMemoryGraph::execute_query`]
+    pub expression: String,
+    /// When the query was saved
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a materialized view aggregates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ViewDefinition {
+    /// Name the view is stored under
+    pub name: String,
+    /// Only aggregate nodes of this type
+    pub node_type: Option<NodeType>,
+    /// Only aggregate nodes matching this predicate
+    pub filter: Option<Predicate>,
+    /// Grouping dimensions and aggregates
+    pub aggregation: Aggregation,
+    /// When the view was defined
+    pub created_at: DateTime<Utc>,
+}
+
+impl ViewDefinition {
+    /// A view over every node
+    pub fn new(name: impl Into<String>, aggregation: Aggregation) -> Self {
+        Self {
+            name: name.into(),
+            node_type: None,
+            filter: None,
+            aggregation,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Only aggregate nodes of `node_type`
+    #[must_use]
+    pub fn node_type(mut self, node_type: NodeType) -> Self {
+        self.node_type = Some(node_type);
+        self
+    }
+
+    /// Only aggregate nodes matching `predicate`, in addition to any earlier filter
+    #[must_use]
+    pub fn filter(mut self, predicate: Predicate) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(existing) => existing.and(predicate),
+            None => predicate,
+        });
+        self
+    }
+
+    /// Check the name, aggregates and filter
+    ///
+    /// # Errors
+    ///
+    /// Returns a validation error if the name is not made of letters, digits,
+    /// `_`, `-` and `.`, an aggregate is invalid, or the filter uses session
+    /// tags.
+    pub fn validate(&self) -> Result<()> {
+        validate_name(&self.name)?;
+        self.aggregation.validate()?;
+        if self.filter.as_ref().is_some_and(uses_session_tags) {
+            return Err(Error::ValidationError(
+                "View filters cannot use session tags".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Metrics the aggregates read, each once
+    fn metrics(&self) -> Vec<Metric> {
+        let mut metrics = Vec::new();
+        for metric in self
+            .aggregation
+            .aggregates
+            .iter()
+            .filter_map(|a| a.metric())
+        {
+            if !metrics.contains(&metric) {
+                metrics.push(metric);
+            }
+        }
+        metrics
+    }
+
+    /// What `node` adds to this view, given the prompt and response it hangs off
+    async fn contribution(
+        &self,
+        storage: &dyn AsyncStorageBackend,
+        node: &Node,
+        lineage: &[Node],
+    ) -> Result<Option<Contribution>> {
+        if self
+            .node_type
+            .as_ref()
+            .is_some_and(|t| *t != node.node_type())
+        {
+            return Ok(None);
+        }
+        if let Some(filter) = &self.filter {
+            if !filter.evaluate(node, &PredicateContext::from_nodes(lineage)) {
+                return Ok(None);
+            }
+        }
+
+        let attribution = Attribution::resolve(storage, lineage, &self.aggregation).await?;
+        let Some(key) = aggregate::group_key(node, &self.aggregation, &attribution) else {
+            return Ok(None);
+        };
+        Ok(Some(Contribution {
+            key,
+            values: self.metrics().iter().map(|m| m.value(node)).collect(),
+        }))
+    }
+}
+
+/// What one node adds to a view: its group and its metric values
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Contribution {
+    key: Vec<GroupValue>,
+    /// One value per [`ViewDefinition::metrics`] entry
+    values: Vec<Option<f64>>,
+}
+
+/// Running totals of one metric within a view group
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MetricState {
+    /// Nodes in the group that have a value for the metric
+    present: usize,
+    sum: f64,
+    min: Option<f64>,
+    max: Option<f64>,
+    /// A removed value was the minimum or maximum, so `min` and `max` are
+    /// only bounds until the group is read from its contributions
+    stale_bounds: bool,
+}
+
+impl MetricState {
+    fn add(&mut self, value: f64) {
+        self.present += 1;
+        self.sum += value;
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+    }
+
+    fn remove(&mut self, value: f64) {
+        self.present = self.present.saturating_sub(1);
+        if self.present == 0 {
+            *self = Self::default();
+            return;
+        }
+        self.sum -= value;
+        if self.min == Some(value) || self.max == Some(value) {
+            self.stale_bounds = true;
+        }
+    }
+}
+
+/// Stored state of one view group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GroupState {
+    key: Vec<GroupValue>,
+    count: usize,
+    /// One entry per [`ViewDefinition::metrics`] entry
+    metrics: Vec<MetricState>,
+}
+
+impl GroupState {
+    fn new(key: Vec<GroupValue>, metrics: usize) -> Self {
+        Self {
+            key,
+            count: 0,
+            metrics: vec![MetricState::default(); metrics],
+        }
+    }
+
+    fn add(&mut self, contribution: &Contribution) {
+        self.count += 1;
+        for (state, value) in self.metrics.iter_mut().zip(&contribution.values) {
+            if let Some(value) = value {
+                state.add(*value);
+            }
+        }
+    }
+
+    fn remove(&mut self, contribution: &Contribution) {
+        self.count = self.count.saturating_sub(1);
+        for (state, value) in self.metrics.iter_mut().zip(&contribution.values) {
+            if let Some(value) = value {
+                state.remove(*value);
+            }
+        }
+    }
+
+    /// Whether `aggregate` needs the group's individual values
+    fn needs_values(&self, aggregate: Aggregate, metrics: &[Metric]) -> bool {
+        match aggregate {
+            Aggregate::Percentile(..) => true,
+            Aggregate::Min(m) | Aggregate::Max(m) => metrics
+                .iter()
+                .position(|x| *x == m)
+                .is_some_and(|i| self.metrics[i].stale_bounds),
+            Aggregate::Count | Aggregate::Sum(_) | Aggregate::Avg(_) => false,
+        }
+    }
+
+    /// The group's row; `values` holds its sorted metric values when
+    /// [`needs_values`](Self::needs_values) asked for them
+    fn row(
+        &self,
+        definition: &ViewDefinition,
+        metrics: &[Metric],
+        values: Option<&Vec<Vec<f64>>>,
+    ) -> AggregateRow {
+        AggregateRow {
+            key: self.key.clone(),
+            count: self.count,
+            values: definition
+                .aggregation
+                .aggregates
+                .iter()
+                .map(|aggregate| {
+                    let Some(i) = aggregate
+                        .metric()
+                        .and_then(|m| metrics.iter().position(|x| *x == m))
+                    else {
+                        return aggregate.evaluate(self.count, &[]);
+                    };
+                    if let Some(values) = values.filter(|_| self.needs_values(*aggregate, metrics))
+                    {
+                        return aggregate.evaluate(self.count, &values[i]);
+                    }
+                    let state = &self.metrics[i];
+                    if state.present == 0 {
+                        return None;
+                    }
+                    #[allow(clippy::cast_precision_loss)]
+                    match aggregate {
+                        Aggregate::Sum(_) => Some(state.sum),
+                        Aggregate::Avg(_) => Some(state.sum / state.present as f64),
+                        Aggregate::Min(_) => state.min,
+                        Aggregate::Max(_) => state.max,
+                        Aggregate::Count | Aggregate::Percentile(..) => None,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Catalog writes collected for one view
+#[derive(Default)]
+struct Staged {
+    groups: HashMap<Vec<u8>, GroupState>,
+    contributions: HashMap<Vec<u8>, Option<Contribution>>,
+}
+
+impl Staged {
+    fn into_writes(
+        self,
+        puts: &mut Vec<(Vec<u8>, Vec<u8>)>,
+        deletes: &mut Vec<Vec<u8>>,
+    ) -> Result<()> {
+        for (key, group) in self.groups {
+            if group.count == 0 {
+                deletes.push(key);
+            } else {
+                puts.push((key, rmp_serde::to_vec(&group)?));
+            }
+        }
+        for (key, contribution) in self.contributions {
+            match contribution {
+                Some(contribution) => puts.push((key, rmp_serde::to_vec(&contribution)?)),
+                None => deletes.push(key),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Number of locks that writes to the same node serialize on
+const NODE_STRIPES: usize = 64;
+
+/// A view definition and the lock guarding updates to its groups
+struct MaterializedView {
+    definition: ViewDefinition,
+    groups: AsyncMutex<()>,
+}
+
+/// Views defined on a graph, and the locks ordering writes against view updates
+pub(crate) struct ViewCatalog {
+    /// The backend below the maintaining wrapper
+    storage: Arc<dyn AsyncStorageBackend>,
+    views: RwLock<Vec<Arc<MaterializedView>>>,
+    /// Held shared by writes, exclusively while views are created, refreshed
+    /// or dropped
+    maintenance: AsyncRwLock<()>,
+    /// Held while a node is written and its view contributions updated, so
+    /// two writes to one node cannot apply their view updates out of order
+    nodes: Vec<AsyncMutex<()>>,
+}
+
+/// Guard held across a write and the view updates that follow it
+struct WriteGuard<'a> {
+    _shared: RwLockReadGuard<'a, ()>,
+    _nodes: Vec<MutexGuard<'a, ()>>,
+}
+
+impl MaterializedView {
+    fn new(definition: ViewDefinition) -> Arc<Self> {
+        Arc::new(Self {
+            definition,
+            groups: AsyncMutex::new(()),
+        })
+    }
+}
+
+impl ViewCatalog {
+    /// Load the stored views and wrap `backend` so engine writes keep them current
+    pub(crate) async fn attach(
+        backend: Arc<dyn AsyncStorageBackend>,
+    ) -> Result<(Arc<dyn AsyncStorageBackend>, Arc<Self>)> {
+        let mut views = Vec::new();
+        for (_, bytes) in backend.scan_catalog(VIEW_PREFIX.as_bytes()).await? {
+            views.push(MaterializedView::new(rmp_serde::from_slice(&bytes)?));
+        }
+
+        let catalog = Arc::new(Self {
+            storage: Arc::clone(&backend),
+            views: RwLock::new(views),
+            maintenance: AsyncRwLock::new(()),
+            nodes: (0..NODE_STRIPES).map(|_| AsyncMutex::new(())).collect(),
+        });
+        let wrapped = Arc::new(MaintainingBackend {
+            inner: backend,
+            catalog: Arc::clone(&catalog),
+        });
+        Ok((wrapped, catalog))
+    }
+
+    fn views(&self) -> Vec<Arc<MaterializedView>> {
+        self.views
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn definitions(&self) -> Vec<ViewDefinition> {
+        self.views()
+            .iter()
+            .map(|view| view.definition.clone())
+            .collect()
+    }
+
+    fn definition(&self, name: &str) -> Result<ViewDefinition> {
+        self.definitions()
+            .into_iter()
+            .find(|view| view.name == name)
+            .ok_or_else(|| Error::QueryError(format!("No view named '{name}'")))
+    }
+
+    /// Lock out view changes for a write, and writes to the nodes it affects
+    ///
+    /// `affected` lists the nodes whose view contributions the write changes.
+    /// It is only called while a view exists; `None` means no view does and
+    /// the write needs no maintenance.
+    async fn write_guard<F, Fut>(
+        &self,
+        affected: F,
+    ) -> Result<Option<(WriteGuard<'_>, Vec<NodeId>)>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<NodeId>>>,
+    {
+        let shared = self.maintenance.read().await;
+        if self.views().is_empty() {
+            return Ok(None);
+        }
+        let ids = affected().await?;
+
+        let mut stripes: Vec<usize> = ids.iter().map(node_stripe).collect();
+        stripes.sort_unstable();
+        stripes.dedup();
+        let mut nodes = Vec::with_capacity(stripes.len());
+        for stripe in stripes {
+            nodes.push(self.nodes[stripe].lock().await);
+        }
+        let guard = WriteGuard {
+            _shared: shared,
+            _nodes: nodes,
+        };
+        Ok(Some((guard, ids)))
+    }
+
+    async fn create(&self, definition: ViewDefinition) -> Result<()> {
+        definition.validate()?;
+        let _guard = self.maintenance.write().await;
+        if self.definition(&definition.name).is_ok() {
+            return Err(Error::ValidationError(format!(
+                "A view named '{}' already exists",
+                definition.name
+            )));
+        }
+
+        let mut puts = vec![(view_key(&definition.name), rmp_serde::to_vec(&definition)?)];
+        puts.extend(self.build(&definition).await?);
+        self.storage.write_catalog(&puts, &[]).await?;
+
+        self.views
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(MaterializedView::new(definition));
+        Ok(())
+    }
+
+    async fn refresh(&self, name: &str) -> Result<()> {
+        let _guard = self.maintenance.write().await;
+        let definition = self.definition(name)?;
+        let deletes = self.state_keys(name).await?;
+        let puts = self.build(&definition).await?;
+        self.storage.write_catalog(&puts, &deletes).await
+    }
+
+    async fn drop_view(&self, name: &str) -> Result<()> {
+        let _guard = self.maintenance.write().await;
+        self.definition(name)?;
+        let mut deletes = self.state_keys(name).await?;
+        deletes.push(view_key(name));
+        self.storage.write_catalog(&[], &deletes).await?;
+
+        self.views
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|view| view.definition.name != name);
+        Ok(())
+    }
+
+    async fn read(&self, name: &str) -> Result<Vec<AggregateRow>> {
+        let definition = self.definition(name)?;
+        let metrics = definition.metrics();
+
+        let mut groups = Vec::new();
+        for (_, bytes) in self.storage.scan_catalog(&group_prefix(name)).await? {
+            groups.push(rmp_serde::from_slice::<GroupState>(&bytes)?);
+        }
+        let needs_values = groups.iter().any(|group| {
+            definition
+                .aggregation
+                .aggregates
+                .iter()
+                .any(|aggregate| group.needs_values(*aggregate, &metrics))
+        });
+        let values = if needs_values {
+            self.group_values(&definition, metrics.len()).await?
+        } else {
+            HashMap::new()
+        };
+
+        let mut rows = Vec::with_capacity(groups.len());
+        for group in &groups {
+            let group_values = values.get(&group_key(name, &group.key)?);
+            rows.push(group.row(&definition, &metrics, group_values));
+        }
+        // Like a fresh aggregate, an ungrouped view always has its one row
+        if rows.is_empty() && definition.aggregation.group_by.is_empty() {
+            rows.push(GroupState::new(Vec::new(), metrics.len()).row(&definition, &metrics, None));
+        }
+        aggregate::sort_rows(&mut rows);
+        Ok(rows)
+    }
+
+    /// Sorted metric values of every group, from the view's stored contributions
+    async fn group_values(
+        &self,
+        definition: &ViewDefinition,
+        metrics: usize,
+    ) -> Result<HashMap<Vec<u8>, Vec<Vec<f64>>>> {
+        let mut groups: HashMap<Vec<u8>, Vec<Vec<f64>>> = HashMap::new();
+        let prefix = contribution_prefix(&definition.name);
+        for (_, bytes) in self.storage.scan_catalog(&prefix).await? {
+            let contribution: Contribution = rmp_serde::from_slice(&bytes)?;
+            let values = groups
+                .entry(group_key(&definition.name, &contribution.key)?)
+                .or_insert_with(|| vec![Vec::new(); metrics]);
+            for (values, value) in values.iter_mut().zip(&contribution.values) {
+                values.extend(value);
+            }
+        }
+        for values in groups.values_mut().flatten() {
+            values.sort_by(f64::total_cmp);
+        }
+        Ok(groups)
+    }
+
+    /// Keys of every stored group and contribution of a view
+    async fn state_keys(&self, name: &str) -> Result<Vec<Vec<u8>>> {
+        let mut keys = Vec::new();
+        for prefix in [group_prefix(name), contribution_prefix(name)] {
+            keys.extend(
+                self.storage
+                    .scan_catalog(&prefix)
+                    .await?
+                    .into_iter()
+                    .map(|(key, _)| key),
+            );
+        }
+        Ok(keys)
+    }
+
+    /// Compute a view's groups and contributions from every stored node
+    async fn build(&self, definition: &ViewDefinition) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let nodes = self.storage.scan_nodes().await?;
+        let by_id: HashMap<NodeId, &Node> = nodes.iter().map(|n| (n.id(), n)).collect();
+        let metrics = definition.metrics().len();
+
+        let mut staged = Staged::default();
+        for node in &nodes {
+            if definition
+                .node_type
+                .as_ref()
+                .is_some_and(|t| *t != node.node_type())
+            {
+                continue;
+            }
+            let lineage = lineage(self.storage.as_ref(), node, &by_id).await?;
+            let Some(contribution) = definition
+                .contribution(self.storage.as_ref(), node, &lineage)
+                .await?
+            else {
+                continue;
+            };
+            staged
+                .groups
+                .entry(group_key(&definition.name, &contribution.key)?)
+                .or_insert_with(|| GroupState::new(contribution.key.clone(), metrics))
+                .add(&contribution);
+            staged.contributions.insert(
+                contribution_key(&definition.name, &node.id()),
+                Some(contribution),
+            );
+        }
+
+        let mut puts = Vec::new();
+        staged.into_writes(&mut puts, &mut Vec::new())?;
+        Ok(puts)
+    }
+
+    /// Bring every view up to date for the nodes in `ids`
+    ///
+    /// Called with the nodes locked through [`write_guard`](Self::write_guard),
+    /// so their stored contributions can be read without locking the view.
+    async fn maintain(&self, ids: &[NodeId]) -> Result<()> {
+        let views = self.views();
+        if views.is_empty() || ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut current = Vec::with_capacity(ids.len());
+        for id in ids {
+            let node = self.storage.get_node(id).await?;
+            let lineage = match &node {
+                Some(node) => lineage(self.storage.as_ref(), node, &HashMap::new()).await?,
+                None => Vec::new(),
+            };
+            current.push((*id, node, lineage));
+        }
+
+        for view in &views {
+            let definition = &view.definition;
+            let mut changes = Vec::new();
+            for (id, node, lineage) in &current {
+                let new = match node {
+                    // A node never changes type, so it cannot have contributed before either
+                    Some(node)
+                        if definition
+                            .node_type
+                            .as_ref()
+                            .is_some_and(|t| *t != node.node_type()) =>
+                    {
+                        continue;
+                    }
+                    Some(node) => {
+                        definition
+                            .contribution(self.storage.as_ref(), node, lineage)
+                            .await?
+                    }
+                    None => None,
+                };
+                let key = contribution_key(&definition.name, id);
+                let old = match self.storage.get_catalog_entry(&key).await? {
+                    Some(bytes) => Some(rmp_serde::from_slice::<Contribution>(&bytes)?),
+                    None => None,
+                };
+                if old != new {
+                    changes.push((key, old, new));
+                }
+            }
+            if changes.is_empty() {
+                continue;
+            }
+
+            let _groups = view.groups.lock().await;
+            let mut staged = Staged::default();
+            for (key, old, new) in changes {
+                self.stage(definition, key, old.as_ref(), new, &mut staged)
+                    .await?;
+            }
+            let mut puts = Vec::new();
+            let mut deletes = Vec::new();
+            staged.into_writes(&mut puts, &mut deletes)?;
+            self.storage.write_catalog(&puts, &deletes).await?;
+        }
+        Ok(())
+    }
+
+    /// Move a node's contribution from its old group to its new one
+    async fn stage(
+        &self,
+        view: &ViewDefinition,
+        key: Vec<u8>,
+        old: Option<&Contribution>,
+        new: Option<Contribution>,
+        staged: &mut Staged,
+    ) -> Result<()> {
+        if let Some(old) = old {
+            self.staged_group(view, &old.key, staged).await?.remove(old);
+        }
+        if let Some(new) = &new {
+            self.staged_group(view, &new.key, staged).await?.add(new);
+        }
+        staged.contributions.insert(key, new);
+        Ok(())
+    }
+
+    async fn staged_group<'s>(
+        &self,
+        view: &ViewDefinition,
+        key: &[GroupValue],
+        staged: &'s mut Staged,
+    ) -> Result<&'s mut GroupState> {
+        let catalog_key = group_key(&view.name, key)?;
+        if !staged.groups.contains_key(&catalog_key) {
+            let group = match self.storage.get_catalog_entry(&catalog_key).await? {
+                Some(bytes) => rmp_serde::from_slice(&bytes)?,
+                None => GroupState::new(key.to_vec(), view.metrics().len()),
+            };
+            staged.groups.insert(catalog_key.clone(), group);
+        }
+        Ok(staged
+            .groups
+            .get_mut(&catalog_key)
+            .expect("group was staged above"))
+    }
+
+    /// Nodes whose view contributions depend on `node`: the responses to a
+    /// prompt and the tool calls made by those responses
+    async fn dependents(&self, node: &Node) -> Result<Vec<NodeId>> {
+        let responses = match node {
+            Node::Prompt(prompt) => self
+                .storage
+                .get_incoming_edges(&prompt.id)
+                .await?
+                .into_iter()
+                .filter(|edge| edge.edge_type == EdgeType::RespondsTo)
+                .map(|edge| edge.from)
+                .collect(),
+            Node::Response(response) => vec![response.id],
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut dependents = Vec::new();
+        for response in responses {
+            if response != node.id() {
+                dependents.push(response);
+            }
+            dependents.extend(
+                self.storage
+                    .get_outgoing_edges(&response)
+                    .await?
+                    .into_iter()
+                    .filter(|edge| edge.edge_type == EdgeType::Invokes)
+                    .map(|edge| edge.to),
+            );
+        }
+        Ok(dependents)
+    }
+
+    /// `nodes` and their dependents, each once
+    async fn affected(&self, nodes: &[Node]) -> Result<Vec<NodeId>> {
+        let mut seen = HashSet::new();
+        let mut ids = Vec::new();
+        for node in nodes {
+            let dependents = self.dependents(node).await?;
+            for id in std::iter::once(node.id()).chain(dependents) {
+                if seen.insert(id) {
+                    ids.push(id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Nodes affected by adding or removing `edges`
+    ///
+    /// Only `HandledBy` edges change a node's attribution.
+    async fn affected_by_edges(&self, edges: &[Edge]) -> Result<Vec<NodeId>> {
+        let mut prompts = Vec::new();
+        for edge in edges.iter().filter(|e| e.edge_type == EdgeType::HandledBy) {
+            if let Some(node) = self.storage.get_node(&edge.from).await? {
+                prompts.push(node);
+            }
+        }
+        self.affected(&prompts).await
+    }
+}
+
+/// `node` followed by the response and prompt it hangs off, preferring `known`
+async fn lineage(
+    storage: &dyn AsyncStorageBackend,
+    node: &Node,
+    known: &HashMap<NodeId, &Node>,
+) -> Result<Vec<Node>> {
+    let fetch = |id: NodeId| async move {
+        match known.get(&id) {
+            Some(node) => Ok(Some((*node).clone())),
+            None => storage.get_node(&id).await,
+        }
+    };
+
+    let mut lineage = vec![node.clone()];
+    let mut parent = match node {
+        Node::ToolInvocation(tool) => Some(tool.response_id),
+        Node::Response(response) => Some(response.prompt_id),
+        _ => None,
+    };
+    while let Some(id) = parent.take() {
+        let Some(next) = fetch(id).await? else {
+            break;
+        };
+        if let Node::Response(response) = &next {
+            parent = Some(response.prompt_id);
+        }
+        lineage.push(next);
+    }
+    Ok(lineage)
+}
+
+fn uses_session_tags(predicate: &Predicate) -> bool {
+    match predicate {
+        Predicate::SessionTag(_) => true,
+        Predicate::And(terms) | Predicate::Or(terms) => terms.iter().any(uses_session_tags),
+        Predicate::Not(inner) => uses_session_tags(inner),
+        _ => false,
+    }
+}
+
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::ValidationError(format!(
+            "Invalid name '{name}'; use letters, digits, '_', '-' and '.'"
+        )))
+    }
+}
+
+/// Lock stripe a node's writes serialize on
+fn node_stripe(id: &NodeId) -> usize {
+    usize::from(id.to_bytes()[15]) % NODE_STRIPES
+}
+
+fn view_key(name: &str) -> Vec<u8> {
+    format!("{VIEW_PREFIX}{name}").into_bytes()
+}
+
+fn group_prefix(name: &str) -> Vec<u8> {
+    format!("{GROUP_PREFIX}{name}/").into_bytes()
+}
+
+fn group_key(name: &str, key: &[GroupValue]) -> Result<Vec<u8>> {
+    let mut catalog_key = group_prefix(name);
+    catalog_key.extend(rmp_serde::to_vec(key)?);
+    Ok(catalog_key)
+}
+
+fn contribution_prefix(name: &str) -> Vec<u8> {
+    format!("{CONTRIBUTION_PREFIX}{name}/").into_bytes()
+}
+
+fn contribution_key(name: &str, id: &NodeId) -> Vec<u8> {
+    let mut key = contribution_prefix(name);
+    key.extend_from_slice(&id.to_bytes());
+    key
+}
+
+fn query_key(name: &str) -> Vec<u8> {
+    format!("{QUERY_PREFIX}{name}").into_bytes()
+}
+
+/// Backend wrapper that updates materialized views after each write
+struct MaintainingBackend {
+    inner: Arc<dyn AsyncStorageBackend>,
+    catalog: Arc<ViewCatalog>,
+}
+
+#[async_trait]
+impl AsyncStorageBackend for MaintainingBackend {
+    async fn store_node(&self, node: &Node) -> Result<()> {
+        let nodes = std::slice::from_ref(node);
+        let guard = self.catalog.write_guard(|| self.catalog.affected(nodes));
+        let Some((_guard, ids)) = guard.await? else {
+            return self.inner.store_node(node).await;
+        };
+        self.inner.store_node(node).await?;
+        self.catalog.maintain(&ids).await
+    }
+
+    async fn get_node(&self, id: &NodeId) -> Result<Option<Node>> {
+        self.inner.get_node(id).await
+    }
+
+    async fn delete_node(&self, id: &NodeId) -> Result<()> {
+        // Edges outlive the node, but its children are only found through it
+        let guard = self.catalog.write_guard(|| async {
+            match self.inner.get_node(id).await? {
+                Some(node) => self.catalog.affected(&[node]).await,
+                None => Ok(vec![*id]),
+            }
+        });
+        let Some((_guard, ids)) = guard.await? else {
+            return self.inner.delete_node(id).await;
+        };
+        self.inner.delete_node(id).await?;
+        self.catalog.maintain(&ids).await
+    }
+
+    async fn store_edge(&self, edge: &Edge) -> Result<()> {
+        let edges = std::slice::from_ref(edge);
+        let guard = self
+            .catalog
+            .write_guard(|| self.catalog.affected_by_edges(edges));
+        let Some((_guard, ids)) = guard.await? else {
+            return self.inner.store_edge(edge).await;
+        };
+        self.inner.store_edge(edge).await?;
+        self.catalog.maintain(&ids).await
+    }
+
+    async fn get_edge(&self, id: &EdgeId) -> Result<Option<Edge>> {
+        self.inner.get_edge(id).await
+    }
+
+    async fn delete_edge(&self, id: &EdgeId) -> Result<()> {
+        let guard = self.catalog.write_guard(|| async {
+            let removed = self.inner.get_edge(id).await?;
+            self.catalog.affected_by_edges(removed.as_slice()).await
+        });
+        let Some((_guard, ids)) = guard.await? else {
+            return self.inner.delete_edge(id).await;
+        };
+        self.inner.delete_edge(id).await?;
+        self.catalog.maintain(&ids).await
+    }
+
+    async fn get_session_nodes(&self, session_id: &SessionId) -> Result<Vec<Node>> {
+        self.inner.get_session_nodes(session_id).await
+    }
+
+    async fn get_outgoing_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_outgoing_edges(node_id).await
+    }
+
+    async fn get_incoming_edges(&self, node_id: &NodeId) -> Result<Vec<Edge>> {
+        self.inner.get_incoming_edges(node_id).await
+    }
+
+    async fn scan_nodes(&self) -> Result<Vec<Node>> {
+        self.inner.scan_nodes().await
+    }
+
+    async fn search_text(&self, query: &TextQuery) -> Result<Vec<SearchHit>> {
+        self.inner.search_text(query).await
+    }
+
+    async fn store_embedding(&self, node_id: &NodeId, vector: &[f32]) -> Result<()> {
+        self.inner.store_embedding(node_id, vector).await
+    }
+
+    async fn get_embedding(&self, node_id: &NodeId) -> Result<Option<Vec<f32>>> {
+        self.inner.get_embedding(node_id).await
+    }
+
+    async fn delete_embedding(&self, node_id: &NodeId) -> Result<()> {
+        self.inner.delete_embedding(node_id).await
+    }
+
+    async fn similar_nodes(
+        &self,
+        vector: &[f32],
+        k: usize,
+        filter: &VectorFilter,
+    ) -> Result<Vec<SimilarNode>> {
+        self.inner.similar_nodes(vector, k, filter).await
+    }
+
+    async fn write_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.inner.write_catalog(puts, deletes).await
+    }
+
+    async fn stage_catalog(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        self.inner.stage_catalog(puts, deletes).await
+    }
+
+    async fn insert_catalog_entry(&self, key: &[u8], value: &[u8]) -> Result<bool> {
+        self.inner.insert_catalog_entry(key, value).await
+    }
+
+    async fn get_catalog_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        self.inner.get_catalog_entry(key).await
+    }
+
+    async fn scan_catalog(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.inner.scan_catalog(prefix).await
+    }
+
+    async fn lookup_nodes(
+        &self,
+        lookup: &Lookup,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page> {
+        self.inner.lookup_nodes(lookup, after, limit).await
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.inner.flush().await
+    }
+
+    async fn stats(&self) -> Result<StorageStats> {
+        self.inner.stats().await
+    }
+
+    async fn store_nodes_batch(&self, nodes: &[Node]) -> Result<Vec<NodeId>> {
+        let guard = self.catalog.write_guard(|| self.catalog.affected(nodes));
+        let Some((_guard, affected)) = guard.await? else {
+            return self.inner.store_nodes_batch(nodes).await;
+        };
+        let ids = self.inner.store_nodes_batch(nodes).await?;
+        self.catalog.maintain(&affected).await?;
+        Ok(ids)
+    }
+
+    async fn store_edges_batch(&self, edges: &[Edge]) -> Result<Vec<EdgeId>> {
+        let guard = self
+            .catalog
+            .write_guard(|| self.catalog.affected_by_edges(edges));
+        let Some((_guard, affected)) = guard.await? else {
+            return self.inner.store_edges_batch(edges).await;
+        };
+        let ids = self.inner.store_edges_batch(edges).await?;
+        self.catalog.maintain(&affected).await?;
+        Ok(ids)
+    }
+
+    fn get_session_nodes_stream(
+        &self,
+        session_id: &SessionId,
+    ) -> std::pin::Pin<Box<dyn futures::stream::Stream<Item = Result<Node>> + Send + '_>> {
+        self.inner.get_session_nodes_stream(session_id)
+    }
+
+    async fn count_session_nodes(&self, session_id: &SessionId) -> Result<usize> {
+        self.inner.count_session_nodes(session_id).await
+    }
+}
+
+impl AsyncMemoryGraph {
+    // ===== Materialized Views =====
+
+    /// Define a materialized view and build it from the current graph
+    ///
+    /// From then on, every write through this graph updates the view.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the definition is invalid, a view with the same
+    /// name exists, the graph is read-only, or storage fails.
+    pub async fn create_view(&self, definition: ViewDefinition) -> Result<()> {
+        self.view_catalog().create(definition).await
+    }
+
+    /// The current rows of a view, ordered by group key
+    ///
+    /// Reads only the stored groups; nothing is recomputed. A view without
+    /// grouping dimensions always has exactly one row.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::QueryError`] if there is no such view, or an error if
+    /// storage fails.
+    pub async fn read_view(&self, name: &str) -> Result<Vec<AggregateRow>> {
+        self.view_catalog().read(name).await
+    }
+
+    /// Every view defined on this graph, by name
+    pub fn list_views(&self) -> Vec<ViewDefinition> {
+        let mut views = self.view_catalog().definitions();
+        views.sort_by(|a, b| a.name.cmp(&b.name));
+        views
+    }
+
+    /// Rebuild a view from the current graph
+    ///
+    /// Only needed after writes that bypassed this graph.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::QueryError`] if there is no such view, or an error if
+    /// the graph is read-only or storage fails.
+    pub async fn refresh_view(&self, name: &str) -> Result<()> {
+        self.view_catalog().refresh(name).await
+    }
+
+    /// Delete a view and its stored groups
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::QueryError`] if there is no such view, or an error if
+    /// the graph is read-only or storage fails.
+    pub async fn drop_view(&self, name: &str) -> Result<()> {
+        self.view_catalog().drop_view(name).await
+    }
+
+    // ===== Saved Queries =====
+
+    /// Save a graph query under `name`, replacing any query saved under it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the name is invalid, the query cannot be parsed or
+    /// planned, the graph is read-only, or storage fails.
+    pub async fn save_query(&self, name: &str, expression: &str) -> Result<SavedQuery> {
+        validate_name(name)?;
+        self.explain_query(expression)?;
+
+        let saved = SavedQuery {
+            name: name.to_string(),
+            expression: expression.to_string(),
+            created_at: Utc::now(),
+        };
+        self.view_catalog()
+            .storage
+            .write_catalog(&[(query_key(name), rmp_serde::to_vec(&saved)?)], &[])
+            .await?;
+        Ok(saved)
+    }
+
+    /// The query saved under `name`, if any
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn saved_query(&self, name: &str) -> Result<Option<SavedQuery>> {
+        match self
+            .view_catalog()
+            .storage
+            .get_catalog_entry(&query_key(name))
+            .await?
+        {
+            Some(bytes) => Ok(Some(rmp_serde::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Every saved query, by name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if storage fails.
+    pub async fn list_saved_queries(&self) -> Result<Vec<SavedQuery>> {
+        self.view_catalog()
+            .storage
+            .scan_catalog(QUERY_PREFIX.as_bytes())
+            .await?
+            .into_iter()
+            .map(|(_, bytes)| Ok(rmp_serde::from_slice(&bytes)?))
+            .collect()
+    }
+
+    /// Run the query saved under `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::QueryError`] if there is no such query, or an error if
+    /// it fails to run.
+    pub async fn run_saved_query(&self, name: &str) -> Result<QueryResult> {
+        let saved = self
+            .saved_query(name)
+            .await?
+            .ok_or_else(|| Error::QueryError(format!("No saved query named '{name}'")))?;
+        self.execute_query(&saved.expression).await
+    }
+
+    /// Delete the query saved under `name`, returning whether it existed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the graph is read-only or storage fails.
+    pub async fn drop_saved_query(&self, name: &str) -> Result<bool> {
+        let existed = self.saved_query(name).await?.is_some();
+        if existed {
+            self.view_catalog()
+                .storage
+                .write_catalog(&[], &[query_key(name)])
+                .await?;
+        }
+        Ok(existed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query::aggregate::{GroupBy, Metric};
+    use crate::query::predicate::Field;
+    use crate::{AgentNode, Config, ResponseMetadata, TokenUsage, ToolInvocation};
+    use tempfile::tempdir;
+
+    fn by_model() -> Aggregation {
+        Aggregation::new()
+            .group_by(GroupBy::Model)
+            .count()
+            .sum(Metric::TotalTokens)
+            .max(Metric::TotalTokens)
+            .percentile(Metric::TotalTokens, 50.0)
+    }
+
+    async fn respond(
+        graph: &AsyncMemoryGraph,
+        session: SessionId,
+        model: &str,
+        tokens: u32,
+    ) -> NodeId {
+        let prompt = graph
+            .add_prompt(session, "question".to_string(), None)
+            .await
+            .unwrap();
+        let metadata = ResponseMetadata {
+            model: model.to_string(),
+            ..ResponseMetadata::default()
+        };
+        graph
+            .add_response(
+                prompt,
+                "answer".to_string(),
+                TokenUsage::new(tokens, 0),
+                Some(metadata),
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn fresh(graph: &AsyncMemoryGraph, aggregation: &Aggregation) -> Vec<AggregateRow> {
+        graph
+            .query()
+            .node_type(NodeType::Response)
+            .aggregate(aggregation)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_view_follows_writes() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        respond(&graph, session.id, "gpt-4", 10).await;
+
+        let view = ViewDefinition::new("by_model", by_model()).node_type(NodeType::Response);
+        graph.create_view(view.clone()).await.unwrap();
+        assert!(graph.create_view(view).await.is_err());
+        assert_eq!(
+            graph.read_view("by_model").await.unwrap(),
+            fresh(&graph, &by_model()).await
+        );
+
+        let doomed = respond(&graph, session.id, "gpt-4", 30).await;
+        respond(&graph, session.id, "claude", 5).await;
+        respond(&graph, session.id, "gpt-4", 20).await;
+        let rows = graph.read_view("by_model").await.unwrap();
+        assert_eq!(rows, fresh(&graph, &by_model()).await);
+        assert_eq!(rows[1].count, 3);
+        assert_eq!(
+            rows[1].values,
+            vec![Some(3.0), Some(60.0), Some(30.0), Some(20.0)]
+        );
+
+        graph.delete_nodes_batch(vec![doomed]).await.unwrap();
+        let rows = graph.read_view("by_model").await.unwrap();
+        assert_eq!(rows, fresh(&graph, &by_model()).await);
+        assert_eq!(
+            rows[1].values,
+            vec![Some(2.0), Some(30.0), Some(20.0), Some(15.0)]
+        );
+
+        graph.refresh_view("by_model").await.unwrap();
+        assert_eq!(graph.read_view("by_model").await.unwrap(), rows);
+
+        graph.drop_view("by_model").await.unwrap();
+        assert!(graph.read_view("by_model").await.is_err());
+        assert!(graph.list_views().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_writes_keep_running_totals() {
+        let dir = tempdir().unwrap();
+        let graph = Arc::new(
+            AsyncMemoryGraph::open(Config::new(dir.path()))
+                .await
+                .unwrap(),
+        );
+        let session = graph.create_session().await.unwrap();
+        let bounds = Aggregation::new()
+            .group_by(GroupBy::Model)
+            .count()
+            .avg(Metric::TotalTokens)
+            .min(Metric::TotalTokens)
+            .max(Metric::TotalTokens);
+        let view = ViewDefinition::new("bounds", bounds.clone()).node_type(NodeType::Response);
+        graph.create_view(view).await.unwrap();
+
+        let writers: Vec<_> = (1..=20)
+            .map(|tokens| {
+                let graph = Arc::clone(&graph);
+                tokio::spawn(async move {
+                    let model = if tokens % 2 == 0 { "even" } else { "odd" };
+                    respond(&graph, session.id, model, tokens).await
+                })
+            })
+            .collect();
+        let mut ids = Vec::new();
+        for writer in writers {
+            ids.push(writer.await.unwrap());
+        }
+        let rows = graph.read_view("bounds").await.unwrap();
+        assert_eq!(rows, fresh(&graph, &bounds).await);
+        assert_eq!(rows[0].count, 10);
+
+        // Removing the smallest and largest values leaves exact bounds
+        graph
+            .delete_nodes_batch(vec![ids[0], ids[19]])
+            .await
+            .unwrap();
+        let rows = graph.read_view("bounds").await.unwrap();
+        assert_eq!(rows, fresh(&graph, &bounds).await);
+        assert_eq!(
+            rows.iter()
+                .map(|row| row.values.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                vec![Some(9.0), Some(10.0), Some(2.0), Some(18.0)],
+                vec![Some(9.0), Some(11.0), Some(3.0), Some(19.0)],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_filtered_view_groups_by_agent() {
+        let dir = tempdir().unwrap();
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        let agent = AgentNode::new("coder".to_string(), "dev".to_string(), vec![]);
+        let agent_node = agent.node_id;
+        let agent_id = graph.add_agent(agent).await.unwrap();
+
+        let failed_calls = ViewDefinition::new(
+            "failed_calls",
+            Aggregation::new().group_by(GroupBy::Agent).count(),
+        )
+        .node_type(NodeType::ToolInvocation)
+        .filter(Field::ToolSuccess.eq(false));
+        graph.create_view(failed_calls).await.unwrap();
+        assert!(graph.read_view("failed_calls").await.unwrap().is_empty());
+
+        let response = respond(&graph, session.id, "gpt-4", 10).await;
+        let mut tool = ToolInvocation::new(response, "search".to_string(), serde_json::json!({}));
+        tool.success = false;
+        graph.add_tool_invocation(tool).await.unwrap();
+        // Not attributed to an agent yet
+        assert!(graph.read_view("failed_calls").await.unwrap().is_empty());
+
+        let Some(Node::Response(r)) = graph.get_node(&response).await.unwrap() else {
+            panic!("response not stored");
+        };
+        graph
+            .assign_agent_to_prompt(r.prompt_id, agent_node)
+            .await
+            .unwrap();
+        let rows = graph.read_view("failed_calls").await.unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].key, vec![GroupValue::Agent(agent_id)]);
+        assert_eq!(rows[0].count, 1);
+
+        let view = ViewDefinition::new("bad", Aggregation::new().count())
+            .filter(Predicate::session_tag("prod"));
+        assert!(graph.create_view(view).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_views_and_saved_queries_persist() {
+        let dir = tempdir().unwrap();
+        let expression = "MATCH (r:Response) RETURN r";
+        {
+            let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+                .await
+                .unwrap();
+            let count = ViewDefinition::new("responses", Aggregation::new().count())
+                .node_type(NodeType::Response);
+            graph.create_view(count).await.unwrap();
+            assert_eq!(graph.read_view("responses").await.unwrap()[0].count, 0);
+
+            graph.save_query("all_responses", expression).await.unwrap();
+            assert!(graph.save_query("broken", "MATCH (").await.is_err());
+            assert!(graph.save_query("bad name", expression).await.is_err());
+        }
+
+        let graph = AsyncMemoryGraph::open(Config::new(dir.path()))
+            .await
+            .unwrap();
+        let session = graph.create_session().await.unwrap();
+        respond(&graph, session.id, "gpt-4", 10).await;
+        assert_eq!(graph.read_view("responses").await.unwrap()[0].count, 1);
+
+        let saved = graph.list_saved_queries().await.unwrap();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(saved[0].expression, expression);
+        assert_eq!(
+            graph.run_saved_query("all_responses").await.unwrap().len(),
+            1
+        );
+        assert!(graph.drop_saved_query("all_responses").await.unwrap());
+        assert!(graph.run_saved_query("all_responses").await.is_err());
+    }
+}
@@ -0,0 +1,45 @@
+#!/bin/bash
+# Check that the vendored copies of the gRPC proto match the workspace proto
+#
+# proto/memory_graph.proto is the source of truth. Each package that is
+# published on its own keeps a copy inside its package root, because cargo
+# and npm only package files below it. Pass --fix to overwrite the copies.
+
+set -e
+
+cd "$(dirname "$0")/.."
+
+CANONICAL="proto/memory_graph.proto"
+COPIES=(
+    "crates/llm-memory-graph/proto/memory_graph.proto"
+    "crates/llm-memory-graph-client/proto/memory_graph.proto"
+    "clients/typescript/proto/memory_graph.proto"
+)
+
+# Colors for output
+RED='\033[0;31m'
+GREEN='\033[0;32m'
+NC='\033[0m' # No Color
+
+status=0
+for copy in "${COPIES[@]}"; do
+    if [ "$1" == "--fix" ]; then
+        rm -f "$copy"
+        cp "$CANONICAL" "$copy"
+        echo -e "${GREEN}✓${NC} $copy updated"
+    elif [ -L "$copy" ]; then
+        echo -e "${RED}✗${NC} $copy is a symlink; it must be a regular file"
+        status=1
+    elif cmp -s "$CANONICAL" "$copy"; then
+        echo -e "${GREEN}✓${NC} $copy matches $CANONICAL"
+    else
+        echo -e "${RED}✗${NC} $copy differs from $CANONICAL"
+        status=1
+    fi
+done
+
+if [ $status -ne 0 ]; then
+    echo ""
+    echo "Run scripts/check_proto_sync.sh --fix to copy $CANONICAL over them."
+fi
+exit $status